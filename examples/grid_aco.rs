@@ -0,0 +1,83 @@
+//! Generates a grid, runs Aco on it with a closure as heuristic and draws the best route.
+//!
+//! ```text
+//! $ cargo run --example grid_aco
+//! ```
+//!
+//! The svg is written to grid_aco.svg, run it from the root of the repository, where the
+//! svg template is found.
+
+use dop_with_aco::graph::layout;
+use dop_with_aco::prelude::*;
+
+use decorum::R64;
+use num_traits::Zero;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io;
+
+const SIZE: (usize, usize) = (8, 8);
+
+fn main() -> io::Result<()> {
+    // every third node has a reward, edges take between 1 and 3 time units
+    let mut rng = Rng::new(RngKind::default(), 7);
+    let mut node = 0;
+    let mut nw_generator = || {
+        node += 1;
+        if node % 3 == 0 {
+            R64::from_inner(10.0 + 10.0 * rng.rand_float())
+        } else {
+            R64::zero()
+        }
+    };
+    let mut ew_rng = Rng::new(RngKind::default(), 8);
+    let mut ew_generator = || R64::from_inner(1.0 + 2.0 * ew_rng.rand_float());
+    let graph = Grid::new(SIZE, &mut nw_generator, &mut ew_generator).generate();
+
+    let goal_point = 0;
+    let inv_shortest_paths = graph.inv_shortest_paths(goal_point);
+    let graph = RefCell::new(graph);
+    let problem = ProblemInstance::new(&graph, goal_point, R64::from_inner(30.0));
+
+    // collected value per time unit, like the heuristic h2 of the experiments
+    let heuristic = |value: R64, weight: R64, _distance: R64, _elapsed: R64| value / weight;
+    let params = aco::Params::new(
+        &heuristic,
+        1.0,
+        3.0,
+        0.1,
+        0.5,
+        Some(1),
+        10,
+        inv_shortest_paths,
+    );
+    let supervisor = aco::Supervisor::new(10, io::sink());
+    let mut algo = Aco::new(problem, params, supervisor);
+    for _ in 0..100 {
+        algo.single_iteration();
+    }
+
+    let (route, score, length) = algo.current_solution();
+    println!(
+        "Best route {} with score {} and length {}",
+        route, score, length
+    );
+
+    // the route is drawn with the highest level on top of the remaining grid edges
+    let points = layout::grid(SIZE).into_values().collect();
+    let route_edges = route.edges();
+    let levels = graph
+        .borrow()
+        .edge_ids()
+        .into_iter()
+        .map(|(from, to)| {
+            let on_route = route_edges.contains(&(from, to));
+            (from, to, if on_route { 1.0 } else { 0.0 })
+        })
+        .collect();
+    Svg::new(600, 600).with_title("Best Aco route").heatmap(
+        points,
+        levels,
+        File::create("grid_aco.svg")?,
+    )
+}
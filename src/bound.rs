@@ -0,0 +1,115 @@
+use crate::graph::GenericWeightedGraph;
+
+use decorum::R64;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+
+/// Returns an upper bound on the node weight, which vehicle_count routes from and back to
+/// goal_point can collect without exceeding max_time each.
+///
+/// Every visit of a node uses one incoming and one outgoing edge, so a route is at least as long
+/// as the sum of half its cheapest incoming and outgoing edge over the distinct nodes it visits.
+/// With these as costs, choosing the nodes to visit is a knapsack problem, whose fractional
+/// relaxation is solved greedily by weight per cost. Nodes without a round trip from goal_point
+/// within max_time are left out.
+pub fn reward_bound<IndexType>(
+    graph: &dyn GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = R64,
+        EdgeWeightType = R64,
+    >,
+    goal_point: IndexType,
+    max_time: R64,
+    vehicle_count: usize,
+) -> f64
+where
+    IndexType: Copy + Ord + Debug + Display,
+{
+    let mut min_in: BTreeMap<IndexType, R64> = BTreeMap::new();
+    let mut min_out: BTreeMap<IndexType, R64> = BTreeMap::new();
+    for ((from, to), &weight) in graph.iter_edges() {
+        let out = min_out.entry(from).or_insert(weight);
+        *out = (*out).min(weight);
+        let inc = min_in.entry(to).or_insert(weight);
+        *inc = (*inc).min(weight);
+    }
+
+    let paths_from = graph.shortest_paths(goal_point);
+    let paths_to = graph.inv_shortest_paths(goal_point);
+    let round_trip = |node| match (paths_from.get(&node), paths_to.get(&node)) {
+        (Some(Some((_, there))), Some(Some((_, back)))) => Some(*there + *back),
+        _ => None,
+    };
+
+    // (weight, cost) of all nodes worth visiting
+    let mut items: Vec<(f64, f64)> = graph
+        .iter_nodes()
+        .filter(|&(node, weight)| node != goal_point && *weight > R64::from_inner(0.0))
+        .filter(|&(node, _)| round_trip(node).is_some_and(|length| length <= max_time))
+        .map(|(node, weight)| {
+            let cost = (min_in[&node] + min_out[&node]) / R64::from_inner(2.0);
+            (weight.into_inner(), cost.into_inner())
+        })
+        .collect();
+    // free nodes first, then by weight per cost
+    items.sort_by(|(w1, c1), (w2, c2)| (w2 * c1).total_cmp(&(w1 * c2)));
+
+    let mut bound = graph
+        .node_weight(goal_point)
+        .map_or(0.0, |weight| weight.into_inner().max(0.0));
+    let mut capacity = max_time.into_inner() * vehicle_count as f64;
+    for (weight, cost) in items {
+        if cost <= capacity {
+            bound += weight;
+            capacity -= cost;
+        } else {
+            bound += weight * capacity / cost;
+            break;
+        }
+    }
+
+    bound
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    fn graph() -> MatrixGraph<usize, R64, R64> {
+        let r = R64::from_inner;
+        // a star around node 0, node 3 is far away
+        MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(4.0), r(6.0), r(100.0), r(3.0)],
+            vec![
+                (0, 1, r(1.0)),
+                (1, 0, r(1.0)),
+                (0, 2, r(2.0)),
+                (2, 0, r(2.0)),
+                (0, 3, r(50.0)),
+                (3, 0, r(50.0)),
+                (0, 4, r(2.0)),
+                (4, 0, r(2.0)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn nodes_are_packed_fractionally() {
+        let graph = graph();
+        let r = R64::from_inner;
+
+        // nodes 1 and 2 fit, the remaining capacity is enough for half of node 4
+        assert_eq!(reward_bound(&graph, 0, r(4.0), 1), 11.5);
+        assert_eq!(reward_bound(&graph, 0, r(4.0), 2), 13.0);
+        assert_eq!(reward_bound(&graph, 0, r(100.0), 1), 113.0);
+    }
+
+    #[test]
+    fn unreachable_nodes_are_left_out() {
+        let graph = graph();
+
+        assert_eq!(reward_bound(&graph, 0, R64::from_inner(1.0), 3), 0.0);
+    }
+}
@@ -0,0 +1,685 @@
+//! The commands of the binary, which runs, replays, exports and evaluates the experiments
+//! configured in yaml files. Errors are printed and end the command, so the remaining configs
+//! of a folder still run.
+use crate::dynamic_graph_experiment::{DynamicGraphExperiment, RunSummary};
+use crate::experiment_config::algo_config::ALGORITHMS;
+use crate::experiment_config::sweep_config::replace_params;
+use crate::experiment_config::{
+    AlgoConfig, ExperimentConfig, ExperimentConfigError, GeneralExperimentConfig,
+    GraphCreationConfig, GraphDynamicsConfig, SweepConfig, TuningConfig,
+};
+use crate::metaheuristic::{heuristics, SyncHeuristic};
+use crate::plot;
+use crate::report;
+use crate::tuning::{Candidate, Tuner};
+
+use csv::Writer;
+use decorum::R64;
+use std::fs::{create_dir, read_to_string, write};
+use std::path::Path;
+
+/// Restricts the algorithms and heuristics, which are run, to the names given with
+/// `--only-algo` and `--only-heuristic`. Each option can be repeated or take a comma separated
+/// list of names, an empty list allows all of them.
+#[derive(Debug, Default)]
+pub struct Filter {
+    algorithms: Vec<String>,
+    heuristics: Vec<String>,
+}
+
+impl Filter {
+    /// Removes the filter options from args and returns the filter they describe.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut filter = Filter::default();
+        let mut i = 0;
+        while i < args.len() {
+            let names = match args[i].as_str() {
+                "--only-algo" => &mut filter.algorithms,
+                "--only-heuristic" => &mut filter.heuristics,
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            let option = args.remove(i);
+            if i == args.len() {
+                return Err(format!("{} needs a name", option));
+            }
+            names.extend(args.remove(i).split(',').map(String::from));
+        }
+        if let Some(name) = filter
+            .algorithms
+            .iter()
+            .find(|name| !ALGORITHMS.contains(&name.as_str()))
+        {
+            return Err(format!(
+                "Unknown algorithm {}, available are {}.",
+                name,
+                ALGORITHMS.join(", ")
+            ));
+        }
+        Ok(filter)
+    }
+
+    pub fn algorithm(&self, name: &str) -> bool {
+        self.algorithms.is_empty() || self.algorithms.iter().any(|allowed| allowed == name)
+    }
+
+    pub fn heuristic(&self, name: &str) -> bool {
+        self.heuristics.is_empty() || self.heuristics.iter().any(|allowed| allowed == name)
+    }
+}
+
+/// Reads the config at path, resolves its seeds and updates all of its sections to their full
+/// versions. Returns None after printing the error, if the config is invalid.
+fn resolve_cfg(path: &Path) -> Option<ExperimentConfig> {
+    let entry = path;
+    let yaml = read_to_string(entry).unwrap();
+    // missing seeds are derived from the master seed, the rewritten config keeps them
+    let mut experiment = match ExperimentConfig::from_yaml(&yaml) {
+        Ok(val) => val.with_resolved_seeds(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+
+    // update all cfg entries to their full versions
+    let general_cfg = GeneralExperimentConfig::Full(experiment.experiment.cfg());
+    let algo_cfg = if let Ok(two) = experiment.algorithm.two_swap() {
+        AlgoConfig::TwoSwap(two)
+    } else if let Ok(mmaco) = experiment.algorithm.mm_aco() {
+        AlgoConfig::MMAco(mmaco)
+    } else if let Ok(acs) = experiment.algorithm.acs() {
+        AlgoConfig::Acs(acs)
+    } else if let Ok(aco) = experiment.algorithm.aco() {
+        AlgoConfig::Aco(aco)
+    } else if let Ok(random) = experiment.algorithm.random() {
+        AlgoConfig::Random(random)
+    } else if let Ok(exact) = experiment.algorithm.exact() {
+        AlgoConfig::Exact(exact)
+    } else {
+        eprintln!("Invalid Algorithm config for {}", entry.to_str().unwrap());
+        return None;
+    };
+    let graph_creation_cfg = if let Ok(f) = experiment.graph_creation.file() {
+        GraphCreationConfig::File(f)
+    } else if let Ok(g) = experiment.graph_creation.grid() {
+        GraphCreationConfig::Grid(g)
+    } else if let Ok(e) = experiment.graph_creation.erdos_renyi() {
+        GraphCreationConfig::ErdosRenyi(e)
+    } else if let Ok(p) = experiment.graph_creation.planted_route() {
+        GraphCreationConfig::PlantedRoute(p)
+    } else if let Ok(i) = experiment.graph_creation.instance() {
+        GraphCreationConfig::Instance(i)
+    } else {
+        eprintln!(
+            "Invalid Graph Creation config for {}",
+            entry.to_str().unwrap()
+        );
+        return None;
+    };
+    let graph_dynamics_cfg = experiment
+        .graph_dynamics
+        .as_ref()
+        .map(|dynamics| GraphDynamicsConfig::Full(dynamics.cfg()));
+
+    // write full version to cfg for later usage
+    experiment.experiment = general_cfg;
+    experiment.algorithm = algo_cfg;
+    experiment.graph_creation = graph_creation_cfg;
+    experiment.graph_dynamics = graph_dynamics_cfg;
+    Some(experiment)
+}
+
+/// Returns true if filter doesn't allow the algorithm of experiment and reports the skipped
+/// config.
+fn filtered_out(stem: &str, experiment: &ExperimentConfig, filter: &Filter) -> bool {
+    let algorithm = experiment.algorithm.name();
+    if !filter.algorithm(algorithm) {
+        println!(
+            "Skipping config {}, algorithm {} is filtered out",
+            stem, algorithm
+        );
+    }
+    !filter.algorithm(algorithm)
+}
+
+/// Runs the config at path with every heuristic filter allows, logging to a folder named like
+/// the config in experiment_location. The resolved config is written back to path.
+pub fn run_cfg(path: &Path, experiment_location: &str, filter: &Filter) {
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+
+    println!("\n---------------------------------------------------");
+    println!("Running config {}: ", stem);
+    let experiment = match resolve_cfg(path) {
+        Some(experiment) => experiment,
+        None => return,
+    };
+    if filtered_out(stem, &experiment, filter) {
+        return;
+    }
+    let par_string = serde_yaml::to_string(&experiment).unwrap();
+    println!("{}", par_string);
+    let res = write(path, par_string.as_bytes());
+    if let Err(e) = res {
+        eprintln!("{}", e);
+    }
+
+    // create directory for log storage
+    let log_folder = Path::new(experiment_location).join(stem);
+    let _res = create_dir(&log_folder);
+
+    if let Some(sweep) = &experiment.sweep {
+        run_sweep(&experiment, sweep, &log_folder, filter);
+    } else if let Some(tuning) = &experiment.tuning {
+        run_tuning(&experiment, tuning, &log_folder);
+    } else {
+        run_heuristics(&experiment, &log_folder, filter);
+    }
+}
+
+/// Prints what running the config at path would do, without running or changing anything:
+/// the resolved config, the runs of its algorithm with every heuristic, their iterations and
+/// logs. Reports graph files and instances, which don't exist.
+pub fn dry_run_cfg(path: &Path, experiment_location: &str, filter: &Filter) {
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+
+    println!("\n---------------------------------------------------");
+    println!("Config {}: ", stem);
+    let experiment = match resolve_cfg(path) {
+        Some(experiment) => experiment,
+        None => return,
+    };
+    if filtered_out(stem, &experiment, filter) {
+        return;
+    }
+    println!("{}", serde_yaml::to_string(&experiment).unwrap());
+
+    let source = if let Ok(file) = experiment.graph_creation.file() {
+        Some(file.filename)
+    } else {
+        experiment
+            .graph_creation
+            .instance()
+            .ok()
+            .map(|instance| instance.instance)
+    };
+    if let Some(source) = source.filter(|source| !Path::new(source).exists()) {
+        eprintln!("Graph source {} of config {} doesn't exist", source, stem);
+    }
+    let experiment_cfg = experiment.experiment.cfg();
+    if experiment_cfg.finished {
+        println!("Already finished, nothing would run");
+        return;
+    }
+
+    let log_folder = Path::new(experiment_location).join(stem);
+    let algorithm = experiment.algorithm.name();
+    let (heuristics, folders) = if let Some(tuning) = &experiment.tuning {
+        // every candidate runs at most once on each instance
+        println!(
+            "Tuning {} with heuristic {}: at most {} runs of {} candidates in {} rounds",
+            algorithm,
+            tuning.heuristic,
+            tuning.candidates * tuning.rounds * tuning.instances,
+            tuning.candidates,
+            tuning.rounds
+        );
+        println!("Logs: {}", log_folder.join("tuning_c*_i*").display());
+        return;
+    } else if let Some(sweep) = &experiment.sweep {
+        let points = match sweep.expand(&experiment) {
+            Ok(points) => points,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        (
+            heuristics_for(&points[0].config),
+            (0..points.len())
+                .map(|i| log_folder.join(format!("sweep_{}", i)))
+                .collect(),
+        )
+    } else {
+        (heuristics_for(&experiment), vec![log_folder])
+    };
+    let names: Vec<String> = match heuristics {
+        Ok(heuristics) => heuristics
+            .into_iter()
+            .map(|(_, name)| name)
+            .filter(|name| filter.heuristic(name))
+            .collect(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let starts = experiment_cfg.start_count.unwrap_or(1);
+    let params = experiment.algorithm.params_string();
+    println!(
+        "Runs: {} x {} heuristics ({}) x {} starts x {} parameter sets = {}",
+        if params.is_empty() {
+            algorithm.to_string()
+        } else {
+            format!("{} ({})", algorithm, params)
+        },
+        names.len(),
+        names.join(", "),
+        starts,
+        folders.len(),
+        names.len() * starts * folders.len()
+    );
+    let recovery = experiment
+        .graph_dynamics
+        .as_ref()
+        .map(|dynamics| dynamics.cfg())
+        .filter(|dynamics| dynamics.recovery_time_ms.is_none())
+        .map_or(0, |dynamics| {
+            dynamics.changes * dynamics.change_after_i as usize
+        });
+    let iterations = match experiment.algorithm.iterations() {
+        Some(iterations) => (iterations + recovery).to_string(),
+        None => "until no improvement is found".to_string(),
+    };
+    match experiment_cfg.max_evaluations {
+        Some(evaluations) => println!(
+            "Iterations per run: {}, at most {} evaluations",
+            iterations, evaluations
+        ),
+        None => println!("Iterations per run: {}", iterations),
+    }
+    println!("Logs:");
+    for folder in folders.iter() {
+        for name in names.iter() {
+            let log = folder.join(name);
+            if starts > 1 {
+                println!("  {}_s0 to _s{}", log.display(), starts - 1);
+            } else {
+                println!("  {}", log.display());
+            }
+        }
+    }
+}
+
+/// A heuristic together with the name its runs are logged under.
+type NamedHeuristic = (Box<SyncHeuristic<R64, R64>>, String);
+
+/// Returns the heuristics configured for experiment, or the default heuristics of its algorithm
+/// and graph type.
+fn heuristics_for(
+    experiment: &ExperimentConfig,
+) -> Result<Vec<NamedHeuristic>, ExperimentConfigError> {
+    if let Some(names) = experiment.experiment.cfg().heuristics {
+        return names
+            .iter()
+            .map(|name| match heuristics::by_name(name) {
+                Some((name, heuristic)) => Ok((
+                    Box::new(heuristic) as Box<SyncHeuristic<R64, R64>>,
+                    name.to_string(),
+                )),
+                None if name.contains('=') => match heuristics::parse(name) {
+                    Ok((name, heuristic)) => Ok((heuristic, name)),
+                    Err(e) => Err(ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                        "Invalid heuristic {}: {}.",
+                        name, e
+                    ))),
+                },
+                None => Err(ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                    "Unknown heuristic {}, available are {} or compositions of them like \
+                     `mixed = 0.5 * value + value_per_cost`.",
+                    name,
+                    heuristics::HEURISTICS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))),
+            })
+            .collect();
+    }
+    let geo = experiment.graph_creation.file().is_ok();
+    let defaults: Vec<(&SyncHeuristic<R64, R64>, &str)> = if experiment.algorithm.two_swap().is_ok()
+    {
+        vec![
+            (&heuristics::value, "h1"),
+            (&heuristics::value_per_cost, "h2"),
+        ]
+    } else if experiment.algorithm.aco().is_ok()
+        || experiment.algorithm.acs().is_ok()
+        || experiment.algorithm.mm_aco().is_ok()
+        || experiment.algorithm.exact().is_ok()
+    {
+        if geo {
+            vec![
+                (&heuristics::saturated_value, "h1"),
+                (&heuristics::value_per_cost, "h2"),
+                (&heuristics::distance_decay, "h3"),
+            ]
+        } else {
+            vec![
+                (&heuristics::saturated_value, "h1"),
+                (&heuristics::value_per_cost, "h2"),
+            ]
+        }
+    } else if experiment.algorithm.random().is_ok() {
+        vec![(&heuristics::value_per_cost, "h2")]
+    } else {
+        Vec::new()
+    };
+    Ok(defaults
+        .into_iter()
+        .map(|(heuristic, name)| (Box::new(heuristic) as Box<_>, name.to_string()))
+        .collect())
+}
+
+/// Runs the experiment with all heuristics fitting its algorithm, which filter allows, logging
+/// to log_folder. The config is stored next to the logs, so the runs can be replayed later on.
+/// Returns the summaries of all runs, which were not already finished.
+fn run_heuristics(
+    experiment: &ExperimentConfig,
+    log_folder: &Path,
+    filter: &Filter,
+) -> Vec<(String, RunSummary)> {
+    let res = write(
+        log_folder.join("config.yaml"),
+        serde_yaml::to_string(experiment).unwrap().as_bytes(),
+    );
+    if let Err(e) = res {
+        eprintln!("{}", e);
+    }
+
+    let mut heuristics = match heuristics_for(experiment) {
+        Ok(heuristics) => heuristics,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Vec::new();
+        }
+    };
+    heuristics.retain(|(_, name)| filter.heuristic(name));
+    if heuristics.is_empty() {
+        println!("Skipping the experiment, all of its heuristics are filtered out");
+        return Vec::new();
+    }
+    let runs: Vec<(&SyncHeuristic<R64, R64>, &str)> = heuristics
+        .iter()
+        .map(|(heuristic, name)| (&**heuristic, name.as_str()))
+        .collect();
+    let names: Vec<String> = heuristics.iter().map(|(_, name)| name.clone()).collect();
+    println!("Running heuristics {}", names.join(", "));
+    // the graph is created once and shared by the runs of all heuristics
+    match DynamicGraphExperiment::run_heuristics(experiment, &runs, log_folder) {
+        Ok(summaries) => names
+            .into_iter()
+            .zip(summaries)
+            .filter_map(|(name, summary)| summary.map(|summary| (name, summary)))
+            .collect(),
+        Err(e) => {
+            eprintln!("{}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Runs every parameter set of sweep in its own subfolder of log_folder and writes a
+/// leaderboard of all runs. Scores of different heuristics are not comparable, so the runs
+/// are ranked by their average score per heuristic.
+fn run_sweep(
+    experiment: &ExperimentConfig,
+    sweep: &SweepConfig,
+    log_folder: &Path,
+    filter: &Filter,
+) {
+    let points = match sweep.expand(experiment) {
+        Ok(points) => points,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let mut results = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        println!(
+            "Running sweep point {}/{}: {:?}",
+            i + 1,
+            points.len(),
+            point.params
+        );
+        let point_folder = log_folder.join(format!("sweep_{}", i));
+        let _res = create_dir(&point_folder);
+        for (name, summary) in run_heuristics(&point.config, &point_folder, filter) {
+            results.push((i, name, summary));
+        }
+    }
+    results.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.score.total_cmp(&a.2.score)));
+
+    let mut writer = match Writer::from_path(log_folder.join("leaderboard.csv")) {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let mut header = vec![
+        "rank".to_string(),
+        "point".to_string(),
+        "heuristic".to_string(),
+    ];
+    header.extend(sweep.params.keys().cloned());
+    header.extend(vec!["score".to_string(), "length".to_string()]);
+    let _res = writer.write_record(&header);
+    let mut rank = 0;
+    for (j, (i, name, summary)) in results.iter().enumerate() {
+        rank = if j > 0 && results[j - 1].1 == *name {
+            rank + 1
+        } else {
+            1
+        };
+        let mut record = vec![rank.to_string(), i.to_string(), name.to_string()];
+        record.extend(points[*i].params.values().map(|value| value.to_string()));
+        record.extend(vec![summary.score.to_string(), summary.length.to_string()]);
+        let _res = writer.write_record(&record);
+    }
+    let _res = writer.flush();
+}
+
+/// Tunes the algorithm parameters of experiment for a single heuristic. Every evaluation runs
+/// in its own subfolder of log_folder, the final candidates are written to tuning.csv.
+fn run_tuning(experiment: &ExperimentConfig, tuning: &TuningConfig, log_folder: &Path) {
+    let heuristic = match heuristics_for(experiment)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(_, name)| *name == tuning.heuristic)
+    {
+        Some((heuristic, _)) => heuristic,
+        None => {
+            eprintln!(
+                "Heuristic {} is not available for this experiment",
+                tuning.heuristic
+            );
+            return;
+        }
+    };
+
+    let mut evaluate = |candidate: &Candidate, instance: usize| {
+        let seed = (tuning.seed + instance as u64) as f64;
+        let params = candidate
+            .params
+            .iter()
+            .map(|(name, &value)| ("algorithm", name.as_str(), value))
+            .chain(
+                ["experiment", "algorithm", "graph_creation"]
+                    .iter()
+                    .map(|&section| (section, "seed", seed)),
+            );
+        let config = match replace_params(experiment, params) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return None;
+            }
+        };
+
+        println!(
+            "Evaluating candidate {} on instance {}: {:?}",
+            candidate.id, instance, candidate.params
+        );
+        let file = log_folder.join(format!("tuning_c{}_i{}", candidate.id, instance));
+        match DynamicGraphExperiment::run_config(&config, &*heuristic, &tuning.heuristic, &file) {
+            Ok(summary) => summary.map(|summary| summary.score),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }
+    };
+    let elites = Tuner::new(tuning).run(&mut evaluate);
+
+    let mut writer = match Writer::from_path(log_folder.join("tuning.csv")) {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let mut header = vec![
+        "rank".to_string(),
+        "candidate".to_string(),
+        "round".to_string(),
+    ];
+    header.extend(tuning.params.keys().cloned());
+    header.extend(vec!["score".to_string(), "instances".to_string()]);
+    let _res = writer.write_record(&header);
+    for (rank, elite) in elites.iter().enumerate() {
+        let mut record = vec![
+            (rank + 1).to_string(),
+            elite.id.to_string(),
+            elite.round.to_string(),
+        ];
+        record.extend(elite.params.values().map(|value| value.to_string()));
+        record.extend(vec![
+            elite.mean_score(tuning.instances).to_string(),
+            elite.scores.len().to_string(),
+        ]);
+        let _res = writer.write_record(&record);
+    }
+    let _res = writer.flush();
+
+    match elites.first() {
+        Some(best) => println!("Best parameters: {:?}", best.params),
+        None => eprintln!("No candidate finished all instances"),
+    }
+}
+
+/// Reconstructs all runs in run_dir from their replay logs and the config stored next to them.
+pub fn replay(run_dir: &Path) {
+    let experiment = read_to_string(run_dir.join("config.yaml"))
+        .map_err(|e| e.to_string())
+        .and_then(|yaml| ExperimentConfig::from_yaml(&yaml).map_err(|e| e.to_string()));
+    let experiment = match experiment {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let heuristics = match heuristics_for(&experiment) {
+        Ok(heuristics) => heuristics,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    for (heuristic, name) in heuristics {
+        let file = run_dir.join(&name);
+        if !file.exists() {
+            continue;
+        }
+        println!("Replaying heuristic {}", name);
+        match DynamicGraphExperiment::replay_config(&experiment, &*heuristic, &name, &file) {
+            Ok(Some(summary)) => println!(
+                "Final score {} with length {}",
+                summary.score, summary.length
+            ),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+/// Exports the instances of the experiment configured at path to out_dir, so they can be
+/// shipped with its results and run again without the original graph source.
+pub fn export(path: &Path, out_dir: &str) {
+    let experiment = read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|yaml| ExperimentConfig::from_yaml(&yaml).map_err(|e| e.to_string()));
+    let experiment = match experiment {
+        Ok(val) => val.with_resolved_seeds(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let result = if experiment.graph_creation.file().is_ok() {
+        DynamicGraphExperiment::export_geopoint_config(&experiment, out_dir)
+    } else {
+        DynamicGraphExperiment::export_usize_config(&experiment, out_dir)
+    };
+    match result {
+        Ok(()) => println!("Exported instance to {}", out_dir),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Draws the convergence of all runs in the config folders of experiment_dir next to them.
+pub fn plot(experiment_dir: &Path) {
+    let result = plot::collect_runs(experiment_dir).and_then(|runs| {
+        println!("Plotting {} runs", runs.len());
+        plot::plot_convergence(&runs, experiment_dir)
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+/// Compares the final routes of all runs in the config folders of experiment_dir pairwise and
+/// writes their similarity to `route_similarity` in experiment_dir.
+pub fn compare(experiment_dir: &Path) {
+    let result = report::collect_routes(experiment_dir).and_then(|runs| {
+        println!("Comparing the routes of {} runs", runs.len());
+        let mut writer = Writer::from_path(experiment_dir.join("route_similarity"))?;
+        for comparison in report::compare_routes(&runs) {
+            writer.serialize(comparison)?;
+        }
+        writer.flush()
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_options_are_taken_from_the_args() {
+        let mut args: Vec<String> = vec!["bin", "--only-algo", "aco,acs", "cfg.yaml"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let filter = Filter::from_args(&mut args).unwrap();
+        assert_eq!(args, vec!["bin", "cfg.yaml"]);
+        assert!(filter.algorithm("acs") && !filter.algorithm("exact"));
+        assert!(filter.heuristic("h1"));
+
+        let mut args = vec!["--only-algo".to_string(), "unknown".to_string()];
+        assert!(Filter::from_args(&mut args).is_err());
+        let mut args = vec!["--only-heuristic".to_string()];
+        assert!(Filter::from_args(&mut args).is_err());
+    }
+}
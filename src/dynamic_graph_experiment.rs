@@ -1,67 +1,484 @@
 use csv::Writer;
 use decorum::R64;
-use indicatif::ProgressIterator;
+use indicatif::ProgressBar;
 use num_traits::Zero;
-use oorandom::Rand64;
+use serde::Serialize;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::fs::File;
 use std::hash::Hash;
-use std::time::Instant;
+use std::io;
+use std::io::BufWriter;
+use std::iter;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::experiment_config::{ExperimentConfig, ExperimentConfigError, GraphDynamicsConfig};
-use crate::geo::GeoPoint;
-use crate::graph::generate::{ErdosRenyi, Generate, Grid};
+use crate::bound::reward_bound;
+use crate::dynamics::{DynamicsEngine, Neighborhood};
+use crate::experiment_config::general_experiment_config::{
+    BudgetUnit, FullConfig, MandatoryNodesConfig, ObjectiveConfig, WarmStartRoute,
+};
+use crate::experiment_config::graph_creation_config::InstanceLoad;
+use crate::experiment_config::graph_dynamics_config::FullConfig as DynamicsConfig;
+use crate::experiment_config::sweep_config::replace_params;
+use crate::experiment_config::{
+    ExperimentConfig, ExperimentConfigError, GeneralExperimentConfig, GraphCreationConfig,
+};
+use crate::geo::{GeoPoint, SpatialIndex};
+use crate::graph::export::{Locate, Svg};
+use crate::graph::generate::{ErdosRenyi, Generate, Grid, PlantedRoute};
 use crate::graph::import::{import_pbf, ImportError};
-use crate::graph::{Edge, GenericWeightedGraph, MatrixGraph};
+use crate::graph::layout::{self, Layout};
+use crate::graph::{
+    normalize_edge_weights, normalize_node_weights, random_variances, Coarsening, Fingerprint,
+    GenericWeightedGraph, MatrixGraph, Normalization, StochasticEdges,
+};
+use crate::instance::{anonymize, load_instance, write_instance, InstanceConfig};
+use crate::metaheuristic::aco::{export_heatmap, ConstructionLimits, Pheromones};
+use crate::metaheuristic::service_time::{random_service_times, ServiceTimes};
+use crate::metaheuristic::similarity::Similarity;
+use crate::metaheuristic::supervisor::{RunInfo, Summary};
+use crate::metaheuristic::time_window::random_time_windows;
 use crate::metaheuristic::{
-    aco, acs, mm_aco, random_search, two_swap, Aco, Acs, Heuristic, MMAco, Metaheuristic,
-    ProblemInstance, RandomSearch, TwoSwap,
+    aco, acs, exact, mm_aco, random_search, shorten_route, solution_length_with_service_times,
+    solution_score_with_penalties, solution_score_with_service_times, two_swap, Aco, Acs, Exact,
+    MMAco, Metaheuristic, Objective, ProblemInstance, RandomSearch, Reoptimize, ReturnCosts,
+    RevisitPenalty, Solution, SyncHeuristic, TimeWindows, TwoSwap,
 };
-use crate::rng::rng64;
+use crate::replay::{read_records, reconstruct, DynamicsReplay, ReplayLog};
+use crate::report::{read_periods, report, PeriodRecord, PeriodRun};
+use crate::rng::{gen_float_range, gen_range, rng64, Rng};
+use crate::run_context::RunContext;
 use crate::util::{Distance, SmallVal};
 
+/// Iterations of the force directed layout, which places the nodes of Erdős–Rényi graphs on
+/// their pheromone heatmaps.
+const LAYOUT_ITERATIONS: usize = 100;
+
 pub struct DynamicGraphExperiment {}
 
+/// Result of one experiment, averaged over all of its starts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunSummary {
+    pub score: f64,
+    pub length: f64,
+    /// Node weight of the nodes the best solution visits, in the graph at the end of the run.
+    pub collected: f64,
+}
+
+/// Decides when a run ends, either after its iterations or, with a budget, after the iteration
+/// which uses up the budget of heuristic evaluations. Shows the progress towards whichever
+/// limit applies.
+struct Termination {
+    iterations: Option<usize>,
+    max_evaluations: Option<usize>,
+    evaluations: usize,
+    bar: ProgressBar,
+}
+
+impl Termination {
+    fn new(iterations: Option<usize>, max_evaluations: Option<usize>) -> Self {
+        let bar = match max_evaluations.or(iterations) {
+            Some(len) => ProgressBar::new(len as u64),
+            None => ProgressBar::hidden(),
+        };
+        Termination {
+            iterations,
+            max_evaluations,
+            evaluations: 0,
+            bar,
+        }
+    }
+
+    /// Returns true if another iteration should be run after iteration iterations, which
+    /// evaluated the heuristic evaluations times in total.
+    fn proceed(&mut self, iteration: usize, evaluations: usize) -> bool {
+        let proceed = match self.max_evaluations {
+            // iterations without any evaluations would never use up the budget
+            Some(max_evaluations) => {
+                evaluations < max_evaluations && (iteration == 0 || evaluations > self.evaluations)
+            }
+            None => self
+                .iterations
+                .is_none_or(|iterations| iteration < iterations),
+        };
+        self.evaluations = evaluations;
+        self.bar.set_position(match self.max_evaluations {
+            Some(_) => evaluations as u64,
+            None => iteration as u64,
+        });
+        if !proceed {
+            self.bar.finish();
+        }
+        proceed
+    }
+}
+
+/// Reborrows an optional generator, so it can be passed on more than once.
+fn reborrow<'g>(
+    generator: &'g mut Option<&mut dyn FnMut() -> R64>,
+) -> Option<&'g mut dyn FnMut() -> R64> {
+    match generator {
+        Some(generator) => Some(&mut **generator),
+        None => None,
+    }
+}
+
+/// Scores around one change of the graph, the score of the best solution right before the
+/// change, of the same solution in the changed graph and at the end of the recovery.
+/// The mean score over all recovery iterations is the offline performance of the recovery.
+#[derive(Serialize)]
+struct RecoveryRecord {
+    algorithm: String,
+    heuristic: String,
+    run_id: String,
+    change: usize,
+    pre_change_score: f64,
+    post_change_score: f64,
+    recovered_score: f64,
+    mean_score: f64,
+    recovery_iterations: usize,
+    /// Time from the change until the recovered score was first reached.
+    recovery_time_us: u128,
+    /// Similarity of the best routes before the change and at the end of the recovery.
+    route_edge_overlap: f64,
+    route_jaccard: f64,
+    route_levenshtein: usize,
+}
+
+/// Summary of the best solution found from one start of a multi-start experiment.
+#[derive(Serialize)]
+struct StartRecord {
+    start: usize,
+    start_node: String,
+    score: f64,
+    length: f64,
+    visited_nodes: usize,
+}
+
+/// Planted route of a generated graph next to the best solution found on it. The gap is the
+/// part of the optimal collected node weight, which the run missed. Graph dynamics change the
+/// node weights, so the planted route is only optimal in the initial graph.
+#[derive(Serialize)]
+struct PlantedRecord {
+    planted_route: String,
+    planted_score: f64,
+    planted_length: f64,
+    collected: f64,
+    length: f64,
+    gap: f64,
+    recovered: bool,
+}
+
+/// Best solution of one iteration, reconstructed from a replay log.
+#[derive(Serialize)]
+struct ReplayedRecord {
+    iteration: usize,
+    improved: bool,
+    score: f64,
+    logged_length: f64,
+    length: Option<f64>,
+    collected: f64,
+    consistent: bool,
+    solution: String,
+}
+
+/// Whether an experiment is run, reconstructed from the replay logs of an earlier run or
+/// only its instances are exported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    Replay,
+    Export,
+}
+
+/// A heuristic together with the file, which its run logs to.
+struct HeuristicRun<'h> {
+    heuristic: &'h SyncHeuristic<R64, R64>,
+    name: &'h str,
+    filename: String,
+}
+
+impl<'h> HeuristicRun<'h> {
+    fn new(heuristic: &'h SyncHeuristic<R64, R64>, name: &'h str, filename: &str) -> Self {
+        HeuristicRun {
+            heuristic,
+            name,
+            filename: filename.to_string(),
+        }
+    }
+}
+
+/// Returns the summary of the only run of a config.
+fn single(summaries: Vec<Option<RunSummary>>) -> Option<RunSummary> {
+    summaries.into_iter().next().flatten()
+}
+
+/// Returns a generator of weights, which draws them from range with the rng in rc.
+fn weight_gen(rc: &RefCell<Rng>, range: (f64, f64)) -> impl FnMut() -> R64 + '_ {
+    move || R64::from_inner(gen_float_range(&mut rc.borrow_mut(), range.0..range.1))
+}
+
+/// Shortest paths from all nodes to a goal point, as returned by inv_shortest_paths.
+type InvShortestPaths<IndexType> = BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>;
+
+/// Shortest paths to the goal points in the original graph of a config. They are computed once
+/// and shared by the runs of all heuristics, which all start on the same graph.
+struct PathCache<IndexType> {
+    paths: RefCell<HashMap<IndexType, InvShortestPaths<IndexType>>>,
+}
+
+impl<IndexType: Hash + Copy + Eq + Display + Debug + Ord> PathCache<IndexType> {
+    fn new() -> Self {
+        PathCache {
+            paths: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the shortest paths from all nodes to goal_point, graph has to be the original
+    /// graph of the config, so this is only called before graph dynamics change it.
+    fn inv_shortest_paths(
+        &self,
+        graph: &MatrixGraph<IndexType, R64, R64>,
+        goal_point: IndexType,
+    ) -> InvShortestPaths<IndexType> {
+        self.paths
+            .borrow_mut()
+            .entry(goal_point)
+            .or_insert_with(|| graph.inv_shortest_paths(goal_point))
+            .clone()
+    }
+}
+
 impl DynamicGraphExperiment {
+    /// Runs the experiment with heuristic and logs to file and the files next to it, whose names
+    /// start with it. Pbf imports are indexed by GeoPoints, all other graphs by usize.
+    pub fn run_config(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        file: &Path,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let file = file.to_str().unwrap();
+        if config.graph_creation.file().is_ok() {
+            Self::run_geopoint_config(config, heuristic, heuristic_name, file)
+        } else {
+            Self::run_usize_config(config, heuristic, heuristic_name, file)
+        }
+    }
+
+    /// Runs the experiment with every heuristic, each logging to the file in log_folder named
+    /// after it. The graph and the shortest paths in it are only created once, every run starts
+    /// on its own copy of them, so the runs are the same as with run_config.
+    /// Returns the summaries in the order of heuristics, which are None for finished experiments.
+    pub fn run_heuristics(
+        config: &ExperimentConfig,
+        heuristics: &[(&SyncHeuristic<R64, R64>, &str)],
+        log_folder: &Path,
+    ) -> Result<Vec<Option<RunSummary>>, ExperimentConfigError> {
+        let runs: Vec<HeuristicRun> = heuristics
+            .iter()
+            .map(|&(heuristic, name)| {
+                HeuristicRun::new(heuristic, name, log_folder.join(name).to_str().unwrap())
+            })
+            .collect();
+        if config.graph_creation.file().is_ok() {
+            Self::geopoint_config(config, &runs, Mode::Run)
+        } else {
+            Self::usize_config(config, &runs, Mode::Run)
+        }
+    }
+
+    /// Reconstructs the run logged to file from its replay logs, see replay_experiment.
+    pub fn replay_config(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        file: &Path,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let file = file.to_str().unwrap();
+        if config.graph_creation.file().is_ok() {
+            Self::replay_geopoint_config(config, heuristic, heuristic_name, file)
+        } else {
+            Self::replay_usize_config(config, heuristic, heuristic_name, file)
+        }
+    }
+
     pub fn run_geopoint_config(
         config: &ExperimentConfig,
-        heuristic: &Heuristic<R64, R64>,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        filename: &str,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let run = HeuristicRun::new(heuristic, heuristic_name, filename);
+        Self::geopoint_config(config, &[run], Mode::Run).map(single)
+    }
+
+    /// Reconstructs the run logged to filename from its replay logs, see replay_experiment.
+    pub fn replay_geopoint_config(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        filename: &str,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let run = HeuristicRun::new(heuristic, heuristic_name, filename);
+        Self::geopoint_config(config, &[run], Mode::Replay).map(single)
+    }
+
+    pub fn run_usize_config(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
         filename: &str,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let run = HeuristicRun::new(heuristic, heuristic_name, filename);
+        Self::usize_config(config, &[run], Mode::Run).map(single)
+    }
+
+    /// Reconstructs the run logged to filename from its replay logs, see replay_experiment.
+    pub fn replay_usize_config(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        filename: &str,
+    ) -> Result<Option<RunSummary>, ExperimentConfigError> {
+        let run = HeuristicRun::new(heuristic, heuristic_name, filename);
+        Self::usize_config(config, &[run], Mode::Replay).map(single)
+    }
+
+    /// Refuses to export imported graphs: instances only keep the position of every node,
+    /// so the coordinates geo heuristics and congestion radii rely on would be lost.
+    pub fn export_geopoint_config(
+        _config: &ExperimentConfig,
+        _dir: &str,
+    ) -> Result<(), ExperimentConfigError> {
+        Err(ExperimentConfigError::InvalidGraphConfig(
+            "Imported graphs can not be exported, as instances drop their coordinates.".to_string(),
+        ))
+    }
+
+    /// Exports the instance of every start of the experiment, see export_instance.
+    /// With a single start it is written to dir, otherwise to dir_s<start>.
+    pub fn export_usize_config(
+        config: &ExperimentConfig,
+        dir: &str,
     ) -> Result<(), ExperimentConfigError> {
-        if config.experiment.cfg().finished {
-            return Ok(());
+        let run = HeuristicRun::new(&|_, _, _, _| R64::zero(), "", dir);
+        Self::usize_config(config, &[run], Mode::Export).map(|_| ())
+    }
+
+    fn geopoint_config(
+        config: &ExperimentConfig,
+        runs: &[HeuristicRun],
+        mode: Mode,
+    ) -> Result<Vec<Option<RunSummary>>, ExperimentConfigError> {
+        if mode == Mode::Run && config.experiment.cfg().finished {
+            return Ok(runs.iter().map(|_| None).collect());
         }
 
         if let Ok(f) = config.graph_creation.file() {
             let is_two_swap = config.algorithm.two_swap().is_ok();
-            let mut rng = rng64(f.seed as u128);
-            let nw_delta = f.nw_range.1 - f.nw_range.0;
+            let rc = RefCell::new(rng64(f.seed as u128));
             let mut nw_gen = || {
+                let mut rng = rc.borrow_mut();
                 if rng.rand_float() < f.node_weight_probability && !is_two_swap {
-                    R64::from_inner(rng.rand_float() * nw_delta + f.nw_range.0)
+                    R64::from_inner(gen_float_range(&mut rng, f.nw_range.0..f.nw_range.1))
                 } else if rng.rand_float() < f.node_weight_probability && is_two_swap {
-                    R64::from_inner(rng.rand_float() * nw_delta + f.nw_range.0) + R64::small()
+                    R64::from_inner(gen_float_range(&mut rng, f.nw_range.0..f.nw_range.1))
+                        + R64::small()
                 } else if is_two_swap {
                     R64::small()
                 } else {
                     R64::zero()
                 }
             };
-            let pbf = import_pbf(f.filename.as_str(), &mut nw_gen);
+            let pbf = import_pbf(
+                f.filename.as_str(),
+                &mut nw_gen,
+                f.region.as_ref(),
+                &f.cleanup.unwrap_or_default(),
+            );
             match pbf {
                 Err(ImportError::MissingFile(msg)) => Err(
                     ExperimentConfigError::InvalidGraphConfig(format!("File not found: {}", msg)),
                 ),
-                Ok(graph) => Self::run_experiment::<GeoPoint>(
-                    config,
-                    heuristic,
-                    graph,
-                    filename,
-                    &mut nw_gen,
-                    None,
-                ),
+                Ok(graph) => {
+                    let experiment_cfg = config.experiment.cfg();
+                    if let Some(BudgetUnit::Km { km_per_hour }) = experiment_cfg.max_time_unit {
+                        if km_per_hour <= 0.0 {
+                            return Err(ExperimentConfigError::InvalidGraphConfig(format!(
+                                "km_per_hour has to be positive, but is {}.",
+                                km_per_hour
+                            )));
+                        }
+                    }
+                    let index = SpatialIndex::from_graph(&graph);
+                    let start_nodes: Vec<GeoPoint> = experiment_cfg
+                        .goal_point
+                        .iter()
+                        .chain(experiment_cfg.goal_points.iter().flatten())
+                        .filter_map(|&(lat, lon)| {
+                            index.nearest_node(&GeoPoint::from_degrees(lat, lon))
+                        })
+                        .map(|(node, _)| node)
+                        .collect();
+                    let mandatory_nodes = match experiment_cfg.mandatory_nodes {
+                        Some(MandatoryNodesConfig::Ids { .. }) => {
+                            return Err(ExperimentConfigError::InvalidGraphConfig(
+                                "mandatory node ids can only be used with usize indexed graphs."
+                                    .to_string(),
+                            ))
+                        }
+                        Some(MandatoryNodesConfig::Points { points }) => points
+                            .iter()
+                            .filter_map(|&(lat, lon)| {
+                                index.nearest_node(&GeoPoint::from_degrees(lat, lon))
+                            })
+                            .map(|(node, _)| node)
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    let warm_start_route = match experiment_cfg
+                        .warm_start
+                        .and_then(|warm_start| warm_start.route)
+                    {
+                        Some(WarmStartRoute::Ids { .. }) => {
+                            return Err(ExperimentConfigError::InvalidGraphConfig(
+                                "warm start ids can only be used with usize indexed graphs."
+                                    .to_string(),
+                            ))
+                        }
+                        Some(WarmStartRoute::Points { points }) => points
+                            .iter()
+                            .filter_map(|&(lat, lon)| {
+                                index.nearest_node(&GeoPoint::from_degrees(lat, lon))
+                            })
+                            .map(|(node, _)| node)
+                            .collect(),
+                        None => Vec::new(),
+                    };
+                    let generated = *rc.borrow();
+                    Self::run_each(runs, graph, |run, graph, paths| {
+                        // every run draws the same node weights for graph dynamics
+                        *rc.borrow_mut() = generated;
+                        Self::run_experiment::<GeoPoint>(
+                            config,
+                            run.heuristic,
+                            run.name,
+                            graph,
+                            &run.filename,
+                            paths,
+                            None,
+                            &mut nw_gen,
+                            None,
+                            start_nodes.clone(),
+                            mandatory_nodes.clone(),
+                            warm_start_route.clone(),
+                            mode,
+                        )
+                    })
+                }
                 _ => panic!("pbf import threw an undefined error"),
             }
         } else {
@@ -71,79 +488,254 @@ impl DynamicGraphExperiment {
         }
     }
 
-    pub fn run_usize_config(
+    fn usize_config(
         config: &ExperimentConfig,
-        heuristic: &Heuristic<R64, R64>,
-        filename: &str,
-    ) -> Result<(), ExperimentConfigError> {
-        if config.experiment.cfg().finished {
-            return Ok(());
+        runs: &[HeuristicRun],
+        mode: Mode,
+    ) -> Result<Vec<Option<RunSummary>>, ExperimentConfigError> {
+        if mode == Mode::Run && config.experiment.cfg().finished {
+            return Ok(runs.iter().map(|_| None).collect());
+        }
+        let experiment_cfg = config.experiment.cfg();
+        if experiment_cfg.goal_point.is_some() || experiment_cfg.goal_points.is_some() {
+            return Err(ExperimentConfigError::InvalidGraphConfig(
+                "goal points can only be used with GeoPoint indexed graphs.".to_string(),
+            ));
+        }
+        if experiment_cfg.max_time_unit.is_some() {
+            return Err(ExperimentConfigError::InvalidGraphConfig(
+                "max_time_unit can only be used with pbf imports.".to_string(),
+            ));
         }
+        let mandatory_nodes = match experiment_cfg.mandatory_nodes {
+            Some(MandatoryNodesConfig::Ids { ids }) => ids,
+            Some(MandatoryNodesConfig::Points { .. }) => {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "mandatory node points can only be used with GeoPoint indexed graphs."
+                        .to_string(),
+                ))
+            }
+            _ => Vec::new(),
+        };
+        let warm_start_route = match experiment_cfg
+            .warm_start
+            .and_then(|warm_start| warm_start.route)
+        {
+            Some(WarmStartRoute::Ids { ids }) => ids,
+            Some(WarmStartRoute::Points { .. }) => {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "warm start points can only be used with GeoPoint indexed graphs.".to_string(),
+                ))
+            }
+            None => Vec::new(),
+        };
 
         if let Ok(grid) = config.graph_creation.grid() {
             let is_two_swap = config.algorithm.two_swap().is_ok();
             let rc = RefCell::new(rng64(grid.seed as u128));
-            let nw_delta = grid.nw_range.1 - grid.nw_range.0;
             let mut nw_gen = || {
                 let mut rng = rc.borrow_mut();
                 if rng.rand_float() < grid.node_weight_probability && !is_two_swap {
-                    R64::from_inner(rng.rand_float() * nw_delta + grid.nw_range.0)
+                    R64::from_inner(gen_float_range(&mut rng, grid.nw_range.0..grid.nw_range.1))
                 } else if rng.rand_float() < grid.node_weight_probability && is_two_swap {
-                    R64::from_inner(rng.rand_float() * nw_delta + grid.nw_range.0) + R64::small()
+                    R64::from_inner(gen_float_range(&mut rng, grid.nw_range.0..grid.nw_range.1))
+                        + R64::small()
                 } else if is_two_swap {
                     R64::small()
                 } else {
                     R64::zero()
                 }
             };
-            let ew_delta = grid.ew_range.1 - grid.ew_range.0;
-            let mut ew_gen =
-                || R64::from_inner(rc.borrow_mut().rand_float() * ew_delta + grid.ew_range.0);
+            let mut ew_gen = weight_gen(&rc, grid.ew_range);
+            let mut horizontal_gen =
+                weight_gen(&rc, grid.horizontal_ew_range.unwrap_or(grid.ew_range));
+            let mut vertical_gen = weight_gen(&rc, grid.vertical_ew_range.unwrap_or(grid.ew_range));
             let mut grid_gen = Grid::new(
                 (grid.size.0 as usize, grid.size.1 as usize),
                 &mut nw_gen,
                 &mut ew_gen,
-            );
+            )
+            .with_horizontal_weights(&mut horizontal_gen)
+            .with_vertical_weights(&mut vertical_gen);
+            if let Some(factor) = grid.asymmetry {
+                grid_gen = grid_gen.with_asymmetry(R64::from_inner(factor));
+            }
             let graph = grid_gen.generate();
-            graph.shortest_paths(0);
+            // heatmaps draw the nodes at their place in the grid
+            let layout = config
+                .pheromone_heatmap
+                .map(|_| layout::grid((grid.size.0 as usize, grid.size.1 as usize)));
 
             //nw_gen is reinitialized here, because we only want it to always create a value now
             let mut nw_gen = || {
                 R64::from_inner(
-                    rc.borrow_mut().rand_float() * nw_delta + grid.nw_range.0 + f64::small(),
+                    gen_float_range(&mut rc.borrow_mut(), grid.nw_range.0..grid.nw_range.1)
+                        + f64::small(),
                 )
             };
-            Self::run_experiment(
-                config,
-                heuristic,
-                graph,
-                filename,
-                &mut nw_gen,
-                Some(&mut ew_gen),
-            )
+            let generated = *rc.borrow();
+            Self::run_each(runs, graph, |run, graph, paths| {
+                // every run draws the same weights for graph dynamics
+                *rc.borrow_mut() = generated;
+                Self::run_experiment(
+                    config,
+                    run.heuristic,
+                    run.name,
+                    graph,
+                    &run.filename,
+                    paths,
+                    layout.as_ref(),
+                    &mut nw_gen,
+                    Some(&mut ew_gen),
+                    Vec::new(),
+                    mandatory_nodes.clone(),
+                    warm_start_route.clone(),
+                    mode,
+                )
+            })
         } else if let Ok(er) = config.graph_creation.erdos_renyi() {
             let rc = RefCell::new(rng64(er.seed as u128));
-            let nw_delta = er.nw_range.1 - er.nw_range.0;
-            let mut nw_gen =
-                || R64::from_inner(rc.borrow_mut().rand_float() * nw_delta + er.nw_range.0);
-            let ew_delta = er.ew_range.1 - er.ew_range.0;
-            let mut ew_gen =
-                || R64::from_inner(rc.borrow_mut().rand_float() * ew_delta + er.ew_range.0);
+            let mut nw_gen = weight_gen(&rc, er.nw_range);
+            let mut ew_gen = weight_gen(&rc, er.ew_range);
             let mut er_gen = ErdosRenyi::new(
                 er.size as usize,
                 er.connection_probability,
                 &mut nw_gen,
                 &mut ew_gen,
-            );
+            )
+            .with_min_out_degree(er.min_out_degree.unwrap_or(0));
+            if er.repair_connectivity.unwrap_or(false) {
+                er_gen = er_gen.with_connectivity_repair(0);
+            }
             let graph = er_gen.generate();
-            Self::run_experiment(
-                config,
-                heuristic,
-                graph,
-                filename,
+            if er_gen.degree_edges() > 0 || er_gen.repaired_edges() > 0 {
+                println!(
+                    "Added {} edges for the minimum out degree and {} edges to repair the connectivity",
+                    er_gen.degree_edges(),
+                    er_gen.repaired_edges()
+                );
+            }
+            let layout = config
+                .pheromone_heatmap
+                .map(|_| layout::force_directed(&graph, LAYOUT_ITERATIONS, er.seed as u128));
+            let generated = *rc.borrow();
+            Self::run_each(runs, graph, |run, graph, paths| {
+                // every run draws the same weights for graph dynamics
+                *rc.borrow_mut() = generated;
+                Self::run_experiment(
+                    config,
+                    run.heuristic,
+                    run.name,
+                    graph,
+                    &run.filename,
+                    paths,
+                    layout.as_ref(),
+                    &mut nw_gen,
+                    Some(&mut ew_gen),
+                    Vec::new(),
+                    mandatory_nodes.clone(),
+                    warm_start_route.clone(),
+                    mode,
+                )
+            })
+        } else if let Ok(planted) = config.graph_creation.planted_route() {
+            if planted.size == 0 {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "planted route graphs need at least one node.".to_string(),
+                ));
+            }
+            if planted.ew_range.0 <= 0.0 {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "planted route graphs need positive edge weights.".to_string(),
+                ));
+            }
+            let rc = RefCell::new(rng64(planted.seed as u128));
+            let mut route_rng = rc.borrow().stream(1);
+            let mut nw_gen = weight_gen(&rc, planted.nw_range);
+            let mut ew_gen = weight_gen(&rc, planted.ew_range);
+            let mut planted_gen = PlantedRoute::new(
+                planted.size as usize,
+                planted.route_size as usize,
+                R64::from_inner(config.experiment.cfg().budget()),
                 &mut nw_gen,
-                Some(&mut ew_gen),
+                &mut ew_gen,
+                &mut route_rng,
             )
+            .with_noise(planted.noise_probability);
+            let graph = planted_gen.generate();
+            let (route, planted_score, planted_length) = (
+                planted_gen.route().clone(),
+                planted_gen.score().into_inner(),
+                planted_gen.length().into_inner(),
+            );
+            let generated = *rc.borrow();
+            Self::run_each(runs, graph, |run, graph, paths| {
+                // every run draws the same weights for graph dynamics
+                *rc.borrow_mut() = generated;
+                let summary = Self::run_experiment(
+                    config,
+                    run.heuristic,
+                    run.name,
+                    graph,
+                    &run.filename,
+                    paths,
+                    None,
+                    &mut nw_gen,
+                    Some(&mut ew_gen),
+                    vec![0],
+                    mandatory_nodes.clone(),
+                    warm_start_route.clone(),
+                    mode,
+                )?;
+                if mode == Mode::Run {
+                    let gap = planted_score - summary.collected;
+                    let mut writer = Writer::from_path(format!("{}_planted", run.filename))?;
+                    writer.serialize(PlantedRecord {
+                        planted_route: route
+                            .iter_nodes()
+                            .map(|node| node.to_string())
+                            .collect::<Vec<String>>()
+                            .join(" "),
+                        planted_score,
+                        planted_length,
+                        collected: summary.collected,
+                        length: summary.length,
+                        gap,
+                        recovered: gap <= 1e-9,
+                    })?;
+                    writer.flush()?;
+                }
+                Ok(summary)
+            })
+        } else if let Ok(instance_cfg) = config.graph_creation.instance() {
+            let (graph, instance) = load_instance(Path::new(&instance_cfg.instance))
+                .map_err(|e| ExperimentConfigError::InvalidGraphConfig(e.to_string()))?;
+            let rc = RefCell::new(rng64(instance.seed as u128));
+            let mut nw_gen = weight_gen(&rc, instance.nw_range);
+            let ew_range = instance.ew_range.unwrap_or_default();
+            let mut ew_gen = weight_gen(&rc, ew_range);
+            let generated = *rc.borrow();
+            Self::run_each(runs, graph, |run, graph, paths| {
+                // every run draws the same weights for graph dynamics
+                *rc.borrow_mut() = generated;
+                Self::run_experiment(
+                    config,
+                    run.heuristic,
+                    run.name,
+                    graph,
+                    &run.filename,
+                    paths,
+                    None,
+                    &mut nw_gen,
+                    instance
+                        .ew_range
+                        .map(|_| &mut ew_gen as &mut dyn FnMut() -> R64),
+                    vec![instance.goal_point],
+                    mandatory_nodes.clone(),
+                    warm_start_route.clone(),
+                    mode,
+                )
+            })
         } else {
             Err(ExperimentConfigError::InvalidGraphConfig(
                 "usize indexed Graphs are not implemented yet".to_string(),
@@ -151,70 +743,915 @@ impl DynamicGraphExperiment {
         }
     }
 
+    /// Calls run for every heuristic run with the graph and the shortest paths in it.
+    /// Every run gets its own copy of graph, which graph dynamics can change, only the last run
+    /// takes graph itself.
+    fn run_each<IndexType: Clone + Hash + Copy + Eq + Display + Debug + Ord>(
+        runs: &[HeuristicRun],
+        graph: MatrixGraph<IndexType, R64, R64>,
+        mut run: impl FnMut(
+            &HeuristicRun,
+            MatrixGraph<IndexType, R64, R64>,
+            &PathCache<IndexType>,
+        ) -> Result<RunSummary, ExperimentConfigError>,
+    ) -> Result<Vec<Option<RunSummary>>, ExperimentConfigError> {
+        let paths = PathCache::new();
+        let mut graph = Some(graph);
+        let mut summaries = Vec::with_capacity(runs.len());
+        for (i, heuristic_run) in runs.iter().enumerate() {
+            let graph = if i + 1 == runs.len() {
+                graph.take().unwrap()
+            } else {
+                graph.as_ref().unwrap().clone()
+            };
+            summaries.push(Some(run(heuristic_run, graph, &paths)?));
+        }
+        Ok(summaries)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn run_experiment<
-        IndexType: 'static + Distance<IndexType> + Clone + Hash + Copy + Eq + Debug + Display + Ord,
+        IndexType: 'static
+            + Distance<IndexType>
+            + Locate
+            + Neighborhood
+            + Clone
+            + Hash
+            + Copy
+            + Eq
+            + Debug
+            + Display
+            + Ord
+            + Sync,
     >(
         config: &ExperimentConfig,
-        heuristic: &Heuristic<R64, R64>,
-        graph: MatrixGraph<IndexType, R64, R64>,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        mut graph: MatrixGraph<IndexType, R64, R64>,
         filename: &str,
+        paths: &PathCache<IndexType>,
+        layout: Option<&Layout>,
         nw_generator: &mut dyn FnMut() -> R64,
-        ew_generator: Option<&mut dyn FnMut() -> R64>,
-    ) -> Result<(), ExperimentConfigError> {
+        mut ew_generator: Option<&mut dyn FnMut() -> R64>,
+        start_nodes: Vec<IndexType>,
+        mandatory_nodes: Vec<IndexType>,
+        warm_start_route: Vec<IndexType>,
+        mode: Mode,
+    ) -> Result<RunSummary, ExperimentConfigError> {
         let experiment_cfg = config.experiment.cfg();
-        let g_nodes = graph.node_ids();
-        let mut start_rng = rng64(experiment_cfg.seed as u128);
-        let start_node = g_nodes[(start_rng.rand_float() * g_nodes.len() as f64) as usize];
+        if let Some(&node) = mandatory_nodes.iter().find(|&&node| !graph.has_node(node)) {
+            return Err(ExperimentConfigError::InvalidGraphConfig(format!(
+                "mandatory node {} is not part of the graph.",
+                node
+            )));
+        }
+        if let Some(&node) = warm_start_route.iter().find(|&&node| !graph.has_node(node)) {
+            return Err(ExperimentConfigError::InvalidGraphConfig(format!(
+                "warm start node {} is not part of the graph.",
+                node
+            )));
+        }
+        // drawn in the order of the node ids, so every run of the config gets the same ones, and
+        // before the normalization, which can change the nodes with a reward
+        let mut service_times = match experiment_cfg.service_times {
+            Some(st_cfg) => random_service_times(
+                graph
+                    .iter_nodes()
+                    .filter(|(_, weight)| **weight > R64::zero())
+                    .map(|(id, _)| id),
+                &mut rng64(experiment_cfg.seed as u128).stream(1),
+                st_cfg.probability.unwrap_or(1.0),
+                st_cfg.range,
+            ),
+            None => ServiceTimes::new(),
+        };
+        let (edge_scaling, node_scaling) = match experiment_cfg.normalization {
+            Some(_) if config.graph_dynamics.is_some() => {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "normalization is not supported with graph_dynamics, which draw weights \
+                     on the original scale."
+                        .to_string(),
+                ));
+            }
+            Some(n_cfg) if n_cfg.edges == Some(Normalization::ZScore) => {
+                return Err(ExperimentConfigError::InvalidGraphConfig(
+                    "z_score can't normalize edge weights, which have to stay positive for \
+                     the shortest paths."
+                        .to_string(),
+                ));
+            }
+            Some(n_cfg) => (
+                n_cfg
+                    .edges
+                    .map(|method| normalize_edge_weights(&mut graph, method)),
+                n_cfg
+                    .nodes
+                    .map(|method| normalize_node_weights(&mut graph, method)),
+            ),
+            None => (None, None),
+        };
+        if let Some(scaling) = node_scaling {
+            println!("Normalized rewards to {}", scaling);
+        }
+        // the budget and service times are given in the unit of the original edge weights
+        let original_config = config;
+        let scaled_config;
+        let config = match edge_scaling {
+            Some(scaling) => {
+                println!("Normalized edge weights to {}", scaling);
+                scaled_config = replace_params(
+                    config,
+                    iter::once((
+                        "experiment",
+                        "max_time",
+                        experiment_cfg.max_time / scaling.factor,
+                    )),
+                )?;
+                &scaled_config
+            }
+            None => config,
+        };
+        if let Some(scaling) = edge_scaling {
+            for service_time in service_times.values_mut() {
+                *service_time = R64::from_inner(scaling.apply(service_time.into_inner()));
+            }
+        }
+        println!("Graph: {}", graph);
+        let start_nodes = if start_nodes.is_empty() {
+            let g_nodes = graph.node_ids();
+            let start_count = experiment_cfg.start_count.unwrap_or(1).min(g_nodes.len());
+            let mut start_rng = rng64(experiment_cfg.seed as u128);
+            let mut start_nodes = Vec::with_capacity(start_count);
+            while start_nodes.len() < start_count {
+                let node = g_nodes[gen_range(&mut start_rng, 0..g_nodes.len())];
+                if !start_nodes.contains(&node) {
+                    start_nodes.push(node);
+                }
+            }
+            start_nodes
+        } else {
+            start_nodes
+        };
+        let policy = experiment_cfg.graph_validation.unwrap_or_default();
+        for &start_node in start_nodes.iter() {
+            let report = graph
+                .validate(Some(start_node), policy)
+                .map_err(|report| ExperimentConfigError::InvalidGraphConfig(report.to_string()))?;
+            if !report.is_valid() {
+                eprintln!("Warning: {}", report);
+            }
+        }
+        if mode == Mode::Export {
+            for (i, &start_node) in start_nodes.iter().enumerate() {
+                let dir = if start_nodes.len() == 1 {
+                    filename.to_string()
+                } else {
+                    format!("{}_s{}", filename, i)
+                };
+                Self::export_instance(
+                    config,
+                    &graph,
+                    start_node,
+                    &mandatory_nodes,
+                    &warm_start_route,
+                    Path::new(&dir),
+                )?;
+            }
+            return Ok(RunSummary::default());
+        }
         let graph_rc = RefCell::new(graph);
+        if mode == Mode::Replay {
+            let time_windows = Self::time_windows(&experiment_cfg, &*graph_rc.borrow());
+            return Self::replay_experiment(
+                &graph_rc,
+                &service_times,
+                &time_windows,
+                config.graph_dynamics.as_ref().map(|cfg| cfg.cfg()),
+                nw_generator,
+                ew_generator,
+                start_nodes.len(),
+                filename,
+            );
+        }
+        let context = RunContext::new(original_config).with_scalings(edge_scaling, node_scaling);
+        // best-known values of the change periods are only shared by runs on the same instance
+        let fingerprint = config.graph_dynamics.as_ref().map(|_| {
+            Fingerprint::of(&*graph_rc.borrow(), Fingerprint::DEFAULT_TOLERANCE).to_string()
+        });
+
+        if start_nodes.len() == 1 {
+            let (solution, score, length) = Self::run_start(
+                config,
+                heuristic,
+                heuristic_name,
+                &graph_rc,
+                start_nodes[0],
+                &mandatory_nodes,
+                &warm_start_route,
+                &service_times,
+                paths,
+                layout,
+                &context,
+                nw_generator,
+                ew_generator,
+                filename,
+            )?;
+            if let Some(fingerprint) = &fingerprint {
+                Self::write_metrics(
+                    filename,
+                    fingerprint,
+                    &[(filename.to_string(), start_nodes[0])],
+                )?;
+            }
+            return Ok(RunSummary {
+                score: score.into_inner(),
+                length: length.into_inner(),
+                collected: Self::collected(&graph_rc, &solution),
+            });
+        }
+
+        // every start gets its own log, the best solutions of all starts are collected in a summary
+        let mut summary = Writer::from_path(format!("{}_starts", filename))?;
+        let mut run_summary = RunSummary::default();
+        // graph dynamics change the graph, so every start begins with the original one
+        let original_graph = config
+            .graph_dynamics
+            .as_ref()
+            .map(|_| graph_rc.borrow().clone());
+        for (i, &start_node) in start_nodes.iter().enumerate() {
+            println!(
+                "Running start {}/{} at {}",
+                i + 1,
+                start_nodes.len(),
+                start_node
+            );
+            let (solution, score, length) = Self::run_start(
+                config,
+                heuristic,
+                heuristic_name,
+                &graph_rc,
+                start_node,
+                &mandatory_nodes,
+                &warm_start_route,
+                &service_times,
+                paths,
+                layout,
+                &context,
+                nw_generator,
+                reborrow(&mut ew_generator),
+                format!("{}_s{}", filename, i).as_str(),
+            )?;
+            run_summary.collected +=
+                Self::collected(&graph_rc, &solution) / start_nodes.len() as f64;
+            if let Some(graph) = &original_graph {
+                *graph_rc.borrow_mut() = graph.clone();
+            }
+            summary.serialize(StartRecord {
+                start: i,
+                start_node: start_node.to_string(),
+                score: score.into_inner(),
+                length: length.into_inner(),
+                visited_nodes: solution.unique_nodes().len(),
+            })?;
+            run_summary.score += score.into_inner() / start_nodes.len() as f64;
+            run_summary.length += length.into_inner() / start_nodes.len() as f64;
+        }
+        summary.flush()?;
+        if let Some(fingerprint) = &fingerprint {
+            let starts: Vec<(String, IndexType)> = start_nodes
+                .iter()
+                .enumerate()
+                .map(|(i, &start_node)| (format!("{}_s{}", filename, i), start_node))
+                .collect();
+            Self::write_metrics(filename, fingerprint, &starts)?;
+        }
+
+        Ok(run_summary)
+    }
+
+    /// Returns the time windows of the rewarded nodes of graph, which are drawn in the order of
+    /// the node ids, so every run of the config gets the same ones.
+    fn time_windows<IndexType: Hash + Copy + Eq + Debug + Display + Ord>(
+        experiment_cfg: &FullConfig,
+        graph: &MatrixGraph<IndexType, R64, R64>,
+    ) -> TimeWindows<IndexType, R64> {
+        match experiment_cfg.time_windows {
+            Some(tw_cfg) => random_time_windows(
+                graph
+                    .iter_nodes()
+                    .filter(|(_, weight)| **weight > R64::zero())
+                    .map(|(id, _)| id),
+                &mut rng64(experiment_cfg.seed as u128),
+                tw_cfg.probability,
+                tw_cfg.width_range,
+                experiment_cfg.budget(),
+            ),
+            None => TimeWindows::new(),
+        }
+    }
+
+    fn objective(experiment_cfg: &FullConfig) -> Objective {
+        match experiment_cfg.objective {
+            Some(ObjectiveConfig::MaximizeScore) | None => Objective::MaximizeScore,
+            Some(ObjectiveConfig::MinimizeLength) => Objective::MinimizeLength,
+            Some(ObjectiveConfig::Weighted {
+                score_weight,
+                length_weight,
+            }) => Objective::Weighted {
+                score_weight: R64::from_inner(score_weight),
+                length_weight: R64::from_inner(length_weight),
+            },
+        }
+    }
+
+    /// Runs the configured algorithm from start_node like run_from_start. With a multilevel
+    /// config it runs on a coarse graph instead, whose best route is uncoarsened and refined by
+    /// TwoSwap on graph_rc. The logs are written by the run on the coarse graph, the refined
+    /// route is returned with its score and length.
+    #[allow(clippy::too_many_arguments)]
+    fn run_start<
+        IndexType: 'static
+            + Distance<IndexType>
+            + Locate
+            + Neighborhood
+            + Clone
+            + Hash
+            + Copy
+            + Eq
+            + Debug
+            + Display
+            + Ord
+            + Sync,
+    >(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        start_node: IndexType,
+        mandatory_nodes: &[IndexType],
+        warm_start_route: &[IndexType],
+        service_times: &ServiceTimes<IndexType, R64>,
+        paths: &PathCache<IndexType>,
+        layout: Option<&Layout>,
+        context: &RunContext,
+        nw_generator: &mut dyn FnMut() -> R64,
+        ew_generator: Option<&mut dyn FnMut() -> R64>,
+        filename: &str,
+    ) -> Result<(Solution<IndexType>, R64, R64), ExperimentConfigError> {
+        let experiment_cfg = config.experiment.cfg();
+        let ml_cfg = match experiment_cfg.multilevel {
+            Some(ml_cfg) => ml_cfg,
+            None => {
+                return Self::run_from_start(
+                    config,
+                    heuristic,
+                    heuristic_name,
+                    graph_rc,
+                    start_node,
+                    mandatory_nodes,
+                    warm_start_route,
+                    service_times,
+                    paths,
+                    layout,
+                    context,
+                    nw_generator,
+                    ew_generator,
+                    filename,
+                )
+            }
+        };
+        if config.graph_dynamics.is_some()
+            || experiment_cfg.vehicle_count.unwrap_or(1) > 1
+            || experiment_cfg.time_windows.is_some()
+            || !service_times.is_empty()
+            || experiment_cfg.edge_uncertainty.is_some()
+            || matches!(
+                experiment_cfg.mandatory_nodes,
+                Some(MandatoryNodesConfig::Fraction { .. })
+            )
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "multilevel is only supported with a single vehicle and without graph_dynamics, \
+                 time_windows, service_times, edge_uncertainty and a fraction of mandatory nodes."
+                    .to_string(),
+            ));
+        }
+
+        let keep: HashSet<IndexType> = std::iter::once(start_node)
+            .chain(mandatory_nodes.iter().copied())
+            .chain(warm_start_route.iter().copied())
+            .collect();
+        let coarsening = Coarsening::new(
+            &*graph_rc.borrow(),
+            &keep,
+            R64::from_inner(ml_cfg.max_reward),
+        )
+        .map_err(|err| ExperimentConfigError::InvalidGraphConfig(err.to_string()))?;
+        println!(
+            "Multilevel: contracted {} nodes into {} super edges, coarse graph: {}",
+            coarsening.contracted(),
+            coarsening.super_edges(),
+            coarsening.graph()
+        );
+        // the cached paths belong to the full graph
+        let coarse_rc = RefCell::new(coarsening.graph().clone());
+        let (coarse_solution, _, length) = Self::run_from_start(
+            config,
+            heuristic,
+            heuristic_name,
+            &coarse_rc,
+            start_node,
+            mandatory_nodes,
+            warm_start_route,
+            service_times,
+            &PathCache::new(),
+            layout,
+            context,
+            nw_generator,
+            ew_generator,
+            filename,
+        )?;
+
+        let revisit_penalty = experiment_cfg
+            .revisit_penalty
+            .map(|penalty| {
+                RevisitPenalty::new(R64::from_inner(penalty.node), R64::from_inner(penalty.edge))
+            })
+            .unwrap_or_default();
+        // scored like post-processed routes, so both routes are compared on the full graph
+        let rescore = |solution| {
+            solution_score_with_penalties(
+                solution,
+                graph_rc,
+                heuristic,
+                &TimeWindows::new(),
+                &revisit_penalty,
+            )
+            .unwrap_or_else(|_| R64::zero())
+        };
+        let uncoarsened = coarsening.uncoarsen(&coarse_solution);
+        let score = rescore(&uncoarsened);
+        let instance = ProblemInstance::new(
+            graph_rc,
+            start_node,
+            R64::from_inner(experiment_cfg.budget()),
+        )
+        .with_mandatory_nodes(mandatory_nodes.iter().copied().collect())
+        .with_revisit_penalty(revisit_penalty)
+        .with_objective(Self::objective(&experiment_cfg));
+        let supervisor = two_swap::Supervisor::new(1, io::sink());
+        let mut two_swap_algo =
+            TwoSwap::new(instance, two_swap::Params::new(heuristic), supervisor);
+        two_swap_algo.warm_start(&uncoarsened);
+        while two_swap_algo.single_iteration().is_some() {}
+        let (refined, _, refined_length) = two_swap_algo.current_solution();
+        let refined_score = rescore(refined);
+        if refined_score < score || !refined.visits_all(&keep) {
+            return Ok((uncoarsened, score, length));
+        }
+        println!(
+            "Multilevel: refined the uncoarsened route from score {:.2} to {:.2}",
+            score.into_inner(),
+            refined_score.into_inner()
+        );
+        Ok((refined.clone(), refined_score, refined_length))
+    }
+
+    /// Runs the configured algorithm with start_node as goal point and logs to filename.
+    /// Returns the best found solution together with its score and length.
+    #[allow(clippy::too_many_arguments)]
+    fn run_from_start<
+        IndexType: 'static
+            + Distance<IndexType>
+            + Locate
+            + Neighborhood
+            + Clone
+            + Hash
+            + Copy
+            + Eq
+            + Debug
+            + Display
+            + Ord
+            + Sync,
+    >(
+        config: &ExperimentConfig,
+        heuristic: &SyncHeuristic<R64, R64>,
+        heuristic_name: &str,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        start_node: IndexType,
+        mandatory_nodes: &[IndexType],
+        warm_start_route: &[IndexType],
+        service_times: &ServiceTimes<IndexType, R64>,
+        paths: &PathCache<IndexType>,
+        layout: Option<&Layout>,
+        context: &RunContext,
+        nw_generator: &mut dyn FnMut() -> R64,
+        mut ew_generator: Option<&mut dyn FnMut() -> R64>,
+        filename: &str,
+    ) -> Result<(Solution<IndexType>, R64, R64), ExperimentConfigError> {
+        let experiment_cfg = config.experiment.cfg();
+        let rng_cfg = config.rng.unwrap_or_default();
+        // os seeded runs draw their seed once, so it is recorded in their meta files
+        let drawn_seed = rng_cfg.draw_seed();
+        let algorithm_seed = |seed: u64| drawn_seed.unwrap_or(seed as u128);
+        let context = &context.clone().with_drawn_seed(drawn_seed);
+        let vehicle_count = experiment_cfg.vehicle_count.unwrap_or(1);
+        let is_aco = config.algorithm.aco().is_ok() || config.algorithm.mm_aco().is_ok();
+        if (vehicle_count > 1
+            || experiment_cfg.time_windows.is_some()
+            || experiment_cfg.edge_uncertainty.is_some())
+            && !is_aco
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "vehicle_count, time_windows and edge_uncertainty are only supported by Aco and MMAco."
+                    .to_string(),
+            ));
+        }
+        if (experiment_cfg.mandatory_nodes.is_some() || experiment_cfg.revisit_penalty.is_some())
+            && !is_aco
+            && config.algorithm.two_swap().is_err()
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "mandatory_nodes and revisit_penalty are only supported by Aco, MMAco and TwoSwap."
+                    .to_string(),
+            ));
+        }
+        if !service_times.is_empty()
+            && !is_aco
+            && config.algorithm.acs().is_err()
+            && config.algorithm.two_swap().is_err()
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "service_times are only supported by Aco, MMAco, Acs and TwoSwap.".to_string(),
+            ));
+        }
+        if experiment_cfg.post_process
+            && (vehicle_count > 1 || experiment_cfg.edge_uncertainty.is_some())
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "post_process is only supported with a single vehicle and without edge_uncertainty."
+                    .to_string(),
+            ));
+        }
+        let initial_pheromone = match (config.algorithm.aco(), config.algorithm.mm_aco()) {
+            (Ok(aco_cfg), _) => aco_cfg.initial_pheromone,
+            (_, Ok(mmaco_cfg)) => mmaco_cfg.initial_pheromone,
+            _ => None,
+        };
+        if initial_pheromone.is_some_and(|level| level <= 0.0) {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "initial_pheromone has to be positive.".to_string(),
+            ));
+        }
+        if let Ok(mmaco_cfg) = config.algorithm.mm_aco() {
+            if let (Some(tau_max), Some(tau_min)) = (mmaco_cfg.tau_max, mmaco_cfg.tau_min) {
+                if tau_min > tau_max {
+                    return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                        "tau_min can't be larger than tau_max.".to_string(),
+                    ));
+                }
+            }
+        }
+        let bidirectional = match (config.algorithm.aco(), config.algorithm.mm_aco()) {
+            (Ok(aco_cfg), _) => aco_cfg.bidirectional.then_some((
+                aco_cfg.return_paths.unwrap_or(1),
+                aco_cfg.tabu_window.unwrap_or(0),
+            )),
+            (_, Ok(mmaco_cfg)) => mmaco_cfg.bidirectional.then_some((
+                mmaco_cfg.return_paths.unwrap_or(1),
+                mmaco_cfg.tabu_window.unwrap_or(0),
+            )),
+            _ => None,
+        };
+        if let Some((return_paths, tabu_window)) = bidirectional {
+            if vehicle_count > 1
+                || experiment_cfg.time_windows.is_some()
+                || !service_times.is_empty()
+                || experiment_cfg.edge_uncertainty.is_some()
+                || return_paths > 1
+                || tabu_window > 0
+            {
+                return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                    "bidirectional is only supported with a single vehicle and without time_windows, \
+                     service_times, edge_uncertainty, return_paths and tabu_window."
+                        .to_string(),
+                ));
+            }
+        }
+        if experiment_cfg.warm_start.is_some() && !is_aco && config.algorithm.acs().is_err() {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "warm_start is only supported by Aco, MMAco and Acs.".to_string(),
+            ));
+        }
+        if config.graph_dynamics.is_some()
+            && (!is_aco && config.algorithm.acs().is_err()
+                || vehicle_count > 1
+                || experiment_cfg.edge_uncertainty.is_some())
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "graph_dynamics are only supported by Aco, MMAco and Acs with a single vehicle and without edge_uncertainty."
+                    .to_string(),
+            ));
+        }
+        if config.pheromone_heatmap.is_some()
+            && (!is_aco && config.algorithm.acs().is_err()
+                || IndexType::locate(&graph_rc.borrow().node_ids(), layout).is_none())
+        {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+                "pheromone_heatmap is only supported by Aco, MMAco and Acs on graphs with node positions."
+                    .to_string(),
+            ));
+        }
+        let time_windows = Self::time_windows(&experiment_cfg, &*graph_rc.borrow());
+        let mandatory_nodes = match experiment_cfg.mandatory_nodes {
+            Some(MandatoryNodesConfig::Fraction { fraction }) => {
+                let mut candidates = graph_rc.borrow().node_ids();
+                candidates.retain(|&node| node != start_node);
+                let count =
+                    ((candidates.len() as f64 * fraction).ceil() as usize).min(candidates.len());
+                let mut rng = rng64(experiment_cfg.seed as u128);
+                let mut chosen = HashSet::with_capacity(count);
+                while chosen.len() < count {
+                    chosen.insert(candidates[gen_range(&mut rng, 0..candidates.len())]);
+                }
+                chosen
+            }
+            _ => mandatory_nodes.iter().copied().collect(),
+        };
+        let stochastic_edges = match experiment_cfg.edge_uncertainty {
+            Some(eu_cfg) => StochasticEdges::new(
+                random_variances(
+                    graph_rc
+                        .borrow()
+                        .iter_edges()
+                        .map(|(edge, &weight)| (edge, weight)),
+                    &mut rng64(experiment_cfg.seed as u128),
+                    eu_cfg.cv_range,
+                ),
+                eu_cfg.risk_aversion,
+            ),
+            None => StochasticEdges::default(),
+        };
+        let revisit_penalty = experiment_cfg
+            .revisit_penalty
+            .map(|penalty| {
+                RevisitPenalty::new(R64::from_inner(penalty.node), R64::from_inner(penalty.edge))
+            })
+            .unwrap_or_default();
+        let objective = Self::objective(&experiment_cfg);
+        let warm_start = match &experiment_cfg.warm_start {
+            Some(ws_cfg) => {
+                let solution = Self::warm_start_solution(
+                    heuristic,
+                    graph_rc,
+                    start_node,
+                    R64::from_inner(experiment_cfg.budget()),
+                    &mandatory_nodes,
+                    revisit_penalty,
+                    warm_start_route,
+                )?;
+                Some((solution, ws_cfg.strength))
+            }
+            None => None,
+        };
+        // kept to rescore the final route, once the instance owns them
+        let scoring = (
+            mandatory_nodes.clone(),
+            time_windows.clone(),
+            revisit_penalty,
+            service_times.clone(),
+        );
+        let post_process = |solution: &Solution<IndexType>, score: R64, length: R64| {
+            if !experiment_cfg.post_process {
+                return (solution.clone(), score, length);
+            }
+            Self::post_process(heuristic, graph_rc, &scoring, solution, score, length)
+        };
+        // post_process only shortens single routes, which are summarized by the whole solution
+        let routes = |routes: Vec<(R64, R64)>| {
+            if vehicle_count > 1 {
+                routes
+            } else {
+                Vec::new()
+            }
+        };
         let instance = ProblemInstance::new(
-            &graph_rc,
+            graph_rc,
             start_node,
-            R64::from_inner(experiment_cfg.max_time),
+            R64::from_inner(experiment_cfg.budget()),
+        )
+        .with_vehicle_count(vehicle_count)
+        .with_time_windows(time_windows)
+        .with_mandatory_nodes(mandatory_nodes)
+        .with_stochastic_edges(stochastic_edges)
+        .with_revisit_penalty(revisit_penalty)
+        .with_service_times(service_times.clone())
+        .with_objective(objective)
+        .with_return_costs(ReturnCosts::from_paths(
+            &paths.inv_shortest_paths(&graph_rc.borrow(), start_node),
+        ))
+        .validate()
+        .map_err(|err| ExperimentConfigError::InvalidInstance(err.to_string()))?;
+        if let Some(density) = instance.reward_density() {
+            let top: Vec<String> = instance
+                .top_reward_nodes(5)
+                .iter()
+                .map(|node| {
+                    format!(
+                        "{} ({:.2} for {:.2})",
+                        node.node,
+                        node.reward.into_inner(),
+                        node.round_trip.into_inner()
+                    )
+                })
+                .collect();
+            println!("Rewards: {}, top nodes {}", density, top.join(", "));
+        }
+        let started = Instant::now();
+        // taken before graph dynamics change the graph, so runs on the same instance share it
+        let fingerprint = Fingerprint::of(&*graph_rc.borrow(), Fingerprint::DEFAULT_TOLERANCE);
+        let bound = reward_bound(
+            &*graph_rc.borrow(),
+            start_node,
+            R64::from_inner(experiment_cfg.budget()),
+            vehicle_count,
         );
-        let fw = File::create(filename).unwrap();
+        let fw = File::create(filename)?;
+        let params = config.algorithm.params_string();
+        let run = |algorithm: &str| RunInfo {
+            algorithm: algorithm.to_string(),
+            heuristic: heuristic_name.to_string(),
+            run_id: Path::new(filename)
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            params: params.clone(),
+        };
+        let mut replay = ReplayLog::new(Writer::from_path(format!("{}_replay", filename))?)
+            .with_dynamics_seed(config.graph_dynamics.as_ref().map(|cfg| cfg.cfg().seed));
 
         if let Ok(aco_cfg) = config.algorithm.aco() {
-            let inv_shortest_paths = graph_rc.borrow().inv_shortest_paths(start_node);
+            let inv_shortest_paths = paths.inv_shortest_paths(&graph_rc.borrow(), start_node);
             let params = aco::Params::new(
                 heuristic,
                 aco_cfg.alpha,
                 aco_cfg.beta,
                 aco_cfg.rho,
                 aco_cfg.q_0,
-                Some(aco_cfg.seed as u128),
+                Some(algorithm_seed(aco_cfg.seed)),
                 aco_cfg.ant_count,
                 inv_shortest_paths,
-            );
-            let supervisor =
-                aco::Supervisor::new(experiment_cfg.aggregation_rate, Writer::from_writer(fw));
+            )
+            .with_rng(rng_cfg.algorithm)
+            .with_construction_limits(ConstructionLimits {
+                step_limit: aco_cfg.step_limit,
+                tabu_window: aco_cfg.tabu_window.unwrap_or(0),
+            })
+            .with_return_paths(aco_cfg.return_paths.unwrap_or(1))
+            .with_initial_pheromone(aco_cfg.initial_pheromone.unwrap_or(1.0))
+            .with_bidirectional(aco_cfg.bidirectional);
+            let supervisor = aco::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("aco"));
             let mut aco_algo = Aco::new(instance, params, supervisor);
+            if let Some((solution, strength)) = &warm_start {
+                aco_algo.warm_start(solution, *strength);
+            }
 
-            for _ in (0..aco_cfg.iterations).progress() {
-                aco_algo.single_iteration();
+            let mut termination =
+                Termination::new(Some(aco_cfg.iterations), experiment_cfg.max_evaluations);
+            let mut i = 0;
+            while termination.proceed(i, aco_algo.supervisor.evaluations()) {
+                let rng_state = aco_algo.rng_state();
+                let improved = aco_algo.single_iteration().is_some();
+                Self::write_heatmap(config, aco_algo.pheromones(), layout, filename, i);
+                let (solution, score, length) = aco_algo.current_solution();
+                replay.record(Some(rng_state), improved, solution, score, length)?;
+                aco_algo.supervisor.end_iteration(i);
+                i += 1;
+            }
+            replay.flush()?;
+            if let Some(dynamics_cfg) = &config.graph_dynamics {
+                let run = aco_algo.supervisor.run().clone();
+                i += Self::reoptimize(
+                    &mut aco_algo,
+                    &mut replay,
+                    graph_rc,
+                    &dynamics_cfg.cfg(),
+                    nw_generator,
+                    reborrow(&mut ew_generator),
+                    &run,
+                    filename,
+                )?;
             }
             aco_algo.supervisor.aggregate_receive();
+            let (solution, score, length) = aco_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                aco_algo
+                    .supervisor
+                    .summary(
+                        i,
+                        score.into_inner(),
+                        length.into_inner(),
+                        started.elapsed(),
+                    )
+                    .with_routes(&routes(aco_algo.best_routes())),
+            )?;
+            Ok((solution, score, length))
         } else if let Ok(mmaco_cfg) = config.algorithm.mm_aco() {
-            let inv_shortest_paths = graph_rc.borrow().inv_shortest_paths(start_node);
+            let inv_shortest_paths = paths.inv_shortest_paths(&graph_rc.borrow(), start_node);
             let params = mm_aco::Params::new(
                 heuristic,
                 mmaco_cfg.alpha,
                 mmaco_cfg.beta,
                 mmaco_cfg.rho,
-                Some(mmaco_cfg.seed as u128),
+                Some(algorithm_seed(mmaco_cfg.seed)),
                 mmaco_cfg.ant_count,
                 mmaco_cfg.p_best,
                 inv_shortest_paths,
-            );
-            let supervisor =
-                aco::Supervisor::new(experiment_cfg.aggregation_rate, Writer::from_writer(fw));
+            )
+            .with_rng(rng_cfg.algorithm)
+            .with_construction_limits(ConstructionLimits {
+                step_limit: mmaco_cfg.step_limit,
+                tabu_window: mmaco_cfg.tabu_window.unwrap_or(0),
+            })
+            .with_return_paths(mmaco_cfg.return_paths.unwrap_or(1))
+            .with_initial_pheromone(mmaco_cfg.initial_pheromone.unwrap_or(1.0))
+            .with_bidirectional(mmaco_cfg.bidirectional)
+            .with_tau_bounds(mm_aco::TauBounds {
+                max: mmaco_cfg.tau_max,
+                min: mmaco_cfg.tau_min,
+                warm_up: mmaco_cfg.warm_up.unwrap_or(0),
+            });
+            let params = match mmaco_cfg.avg_options {
+                Some(avg_options) => params.with_avg_options(avg_options),
+                None => params,
+            };
+            let supervisor = aco::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("mm_aco"));
             let mut mmaco_algo = MMAco::new(instance, params, supervisor);
+            if let Some((solution, strength)) = &warm_start {
+                mmaco_algo.warm_start(solution, *strength);
+            }
 
-            for _i in (0..mmaco_cfg.iterations).progress() {
-                mmaco_algo.single_iteration();
+            let mut termination =
+                Termination::new(Some(mmaco_cfg.iterations), experiment_cfg.max_evaluations);
+            let mut i = 0;
+            while termination.proceed(i, mmaco_algo.supervisor.evaluations()) {
+                let rng_state = mmaco_algo.rng_state();
+                let improved = mmaco_algo.single_iteration().is_some();
+                Self::write_heatmap(config, mmaco_algo.pheromones(), layout, filename, i);
+                let (solution, score, length) = mmaco_algo.current_solution();
+                replay.record(Some(rng_state), improved, solution, score, length)?;
+                mmaco_algo.supervisor.end_iteration(i);
+                i += 1;
+            }
+            replay.flush()?;
+            if let Some(dynamics_cfg) = &config.graph_dynamics {
+                let run = mmaco_algo.supervisor.run().clone();
+                i += Self::reoptimize(
+                    &mut mmaco_algo,
+                    &mut replay,
+                    graph_rc,
+                    &dynamics_cfg.cfg(),
+                    nw_generator,
+                    reborrow(&mut ew_generator),
+                    &run,
+                    filename,
+                )?;
             }
             mmaco_algo.supervisor.aggregate_receive();
+            let (solution, score, length) = mmaco_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                mmaco_algo
+                    .supervisor
+                    .summary(
+                        i,
+                        score.into_inner(),
+                        length.into_inner(),
+                        started.elapsed(),
+                    )
+                    .with_routes(&routes(mmaco_algo.best_routes())),
+            )?;
+            Ok((solution, score, length))
         } else if let Ok(acs_cfg) = config.algorithm.acs() {
-            let inv_shortest_paths = graph_rc.borrow().inv_shortest_paths(start_node);
+            let inv_shortest_paths = paths.inv_shortest_paths(&graph_rc.borrow(), start_node);
             let params = acs::Params::new(
                 heuristic,
                 acs_cfg.alpha,
@@ -222,137 +1659,739 @@ impl DynamicGraphExperiment {
                 acs_cfg.rho,
                 acs_cfg.q_0,
                 acs_cfg.t_0,
-                Some(acs_cfg.seed as u128),
+                Some(algorithm_seed(acs_cfg.seed)),
                 acs_cfg.ant_count,
                 inv_shortest_paths,
-            );
-            let supervisor =
-                aco::Supervisor::new(experiment_cfg.aggregation_rate, Writer::from_writer(fw));
+            )
+            .with_rng(rng_cfg.algorithm)
+            .with_construction_limits(ConstructionLimits {
+                step_limit: acs_cfg.step_limit,
+                tabu_window: acs_cfg.tabu_window.unwrap_or(0),
+            })
+            .with_return_paths(acs_cfg.return_paths.unwrap_or(1));
+            let supervisor = aco::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("acs"));
             let mut acs_algo = Acs::new(instance, params, supervisor);
+            if let Some((solution, strength)) = &warm_start {
+                acs_algo.warm_start(solution, *strength);
+            }
 
-            for _i in (0..acs_cfg.iterations).progress() {
-                acs_algo.single_iteration();
+            let mut termination =
+                Termination::new(Some(acs_cfg.iterations), experiment_cfg.max_evaluations);
+            let mut i = 0;
+            while termination.proceed(i, acs_algo.supervisor.evaluations()) {
+                let rng_state = acs_algo.rng_state();
+                let improved = acs_algo.single_iteration().is_some();
+                Self::write_heatmap(config, &acs_algo.pheromones(), layout, filename, i);
+                let (solution, score, length) = acs_algo.current_solution();
+                replay.record(Some(rng_state), improved, solution, score, length)?;
+                acs_algo.supervisor.end_iteration(i);
+                i += 1;
+            }
+            replay.flush()?;
+            if let Some(dynamics_cfg) = &config.graph_dynamics {
+                let run = acs_algo.supervisor.run().clone();
+                i += Self::reoptimize(
+                    &mut acs_algo,
+                    &mut replay,
+                    graph_rc,
+                    &dynamics_cfg.cfg(),
+                    nw_generator,
+                    reborrow(&mut ew_generator),
+                    &run,
+                    filename,
+                )?;
             }
             acs_algo.supervisor.aggregate_receive();
-        } else if config.algorithm.two_swap().is_ok() {
+            let (solution, score, length) = acs_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                acs_algo
+                    .supervisor
+                    .summary(
+                        i,
+                        score.into_inner(),
+                        length.into_inner(),
+                        started.elapsed(),
+                    )
+                    .with_routes(&routes(acs_algo.best_routes())),
+            )?;
+            Ok((solution, score, length))
+        } else if let Ok(two_swap_cfg) = config.algorithm.two_swap() {
             let params = two_swap::Params::new(heuristic);
-            let supervisor =
-                two_swap::Supervisor::new(experiment_cfg.aggregation_rate, Writer::from_writer(fw));
-            let mut two_swap_algo = TwoSwap::new(instance, params, supervisor);
+            let supervisor = two_swap::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("two_swap"));
+            let mut two_swap_algo = TwoSwap::new(instance, params, supervisor)
+                .with_threads(heuristic, two_swap_cfg.threads.unwrap_or(1));
 
+            let mut termination = Termination::new(None, experiment_cfg.max_evaluations);
             let mut i = 0;
-            while two_swap_algo.single_iteration().is_some() {
+            while termination.proceed(i, two_swap_algo.supervisor.evaluations())
+                && two_swap_algo.single_iteration().is_some()
+            {
+                let (solution, score, length) = two_swap_algo.current_solution();
+                replay.record(None, true, solution, score, length)?;
+                two_swap_algo.supervisor.end_iteration(i);
                 i += 1;
             }
+            replay.flush()?;
             println!("Took {} iterations", i);
             two_swap_algo.supervisor.aggregate_receive();
+            let (solution, score, length) = two_swap_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                two_swap_algo.supervisor.summary(
+                    i,
+                    score.into_inner(),
+                    length.into_inner(),
+                    started.elapsed(),
+                ),
+            )?;
+            Ok((solution, score, length))
         } else if let Ok(random_cfg) = config.algorithm.random() {
-            let inv_shortest_paths = graph_rc.borrow().inv_shortest_paths(start_node);
-            let params =
-                random_search::Params::new(heuristic, &inv_shortest_paths, random_cfg.seed as u128);
-            let supervisor = random_search::Supervisor::new(
-                experiment_cfg.aggregation_rate,
-                Writer::from_writer(fw),
-            );
+            let inv_shortest_paths = paths.inv_shortest_paths(&graph_rc.borrow(), start_node);
+            let params = random_search::Params::new(
+                heuristic,
+                &inv_shortest_paths,
+                algorithm_seed(random_cfg.seed),
+            )
+            .with_rng(rng_cfg.algorithm);
+            let supervisor = random_search::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("random"));
             let mut random_algo = RandomSearch::new(instance, params, supervisor);
-            for _ in (0..random_cfg.iterations).progress() {
+            let mut termination =
+                Termination::new(Some(random_cfg.iterations), experiment_cfg.max_evaluations);
+            let mut i = 0;
+            while termination.proceed(i, random_algo.supervisor.evaluations()) {
+                let rng_state = random_algo.rng_state();
+                let previous_score = random_algo.current_solution().1;
                 random_algo.generate(Instant::now());
+                let (solution, score, length) = random_algo.current_solution();
+                replay.record(
+                    Some(rng_state),
+                    score != previous_score,
+                    solution,
+                    score,
+                    length,
+                )?;
+                random_algo.supervisor.end_iteration(i);
+                i += 1;
             }
+            replay.flush()?;
             random_algo.supervisor.aggregate_receive();
+            let (solution, score, length) = random_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                random_algo.supervisor.summary(
+                    i,
+                    score.into_inner(),
+                    length.into_inner(),
+                    started.elapsed(),
+                ),
+            )?;
+            Ok((solution, score, length))
+        } else if let Ok(exact_cfg) = config.algorithm.exact() {
+            let reward_nodes = exact::reward_nodes(
+                &*graph_rc.borrow(),
+                start_node,
+                R64::from_inner(experiment_cfg.budget()),
+            )
+            .len();
+            if reward_nodes > exact_cfg.node_limit.min(exact::MAX_NODES) {
+                return Err(ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                    "Exact is limited to {} nodes with a reward, but {} are reachable.",
+                    exact_cfg.node_limit.min(exact::MAX_NODES),
+                    reward_nodes
+                )));
+            }
+            let unsupported = exact::unsupported(&instance);
+            if !unsupported.is_empty() {
+                return Err(ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                    "Exact does not support {}.",
+                    unsupported.join(", ")
+                )));
+            }
+            let params = exact::Params::new(heuristic);
+            let supervisor = exact::Supervisor::new(experiment_cfg.aggregation_rate, fw)
+                .with_flush_every(experiment_cfg.flush_every)
+                .with_route_log(Self::route_log(experiment_cfg.route_log, filename))
+                .with_run(run("exact"));
+            let mut exact_algo = Exact::new(instance, params, supervisor);
+            let improved = exact_algo.single_iteration().is_some();
+            let (solution, score, length) = exact_algo.current_solution();
+            replay.record(None, improved, solution, score, length)?;
+            replay.flush()?;
+            exact_algo.supervisor.aggregate_receive();
+            let (solution, score, length) = exact_algo.current_solution();
+            let (solution, score, length) = post_process(solution, score, length);
+            Self::write_summary(
+                filename,
+                context,
+                graph_rc,
+                &solution,
+                bound,
+                fingerprint,
+                exact_algo.supervisor.summary(
+                    1,
+                    score.into_inner(),
+                    length.into_inner(),
+                    started.elapsed(),
+                ),
+            )?;
+            Ok((solution, score, length))
         } else {
-            return Err(ExperimentConfigError::InvalidAlgorithmConfig(
+            Err(ExperimentConfigError::InvalidAlgorithmConfig(
                 "No valid Algorithm config supplied.".to_string(),
-            ));
+            ))
+        }
+    }
+
+    /// Changes the graph after a run and lets algo recover from each change, for
+    /// dynamics_cfg.change_after_i iterations or recovery_time_ms. With regeneration the rewards
+    /// regenerate before every recovery iteration. Writes a RecoveryRecord per change to a
+    /// `_recovery` file next to the log and returns the amount of iterations run. The best score
+    /// after every iteration is logged to a `_periods` file, the iterations themselves to replay
+    /// together with the position of the dynamics.
+    #[allow(clippy::too_many_arguments)]
+    fn reoptimize<'a, IndexType, A>(
+        algo: &mut A,
+        replay: &mut ReplayLog<File>,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        dynamics_cfg: &DynamicsConfig,
+        nw_generator: &mut dyn FnMut() -> R64,
+        mut ew_generator: Option<&mut dyn FnMut() -> R64>,
+        run: &RunInfo,
+        filename: &str,
+    ) -> Result<usize, ExperimentConfigError>
+    where
+        IndexType: 'static + Neighborhood + Clone + Hash + Copy + Eq + Debug + Display + Ord,
+        A: Metaheuristic<'a, IndexType, R64, R64> + Reoptimize<IndexType>,
+    {
+        let mut engine = DynamicsEngine::new(
+            graph_rc,
+            *dynamics_cfg,
+            &mut *nw_generator,
+            reborrow(&mut ew_generator),
+        );
+        let mut writer = Writer::from_path(format!("{}_recovery", filename))?;
+        let mut periods = Writer::from_path(format!("{}_periods", filename))?;
+
+        let mut iterations = 0;
+        for change in 0..dynamics_cfg.changes {
+            let pre_change_score = algo.best_score();
+            let pre_change_solution = algo.best_solution().clone();
+            engine.apply(graph_rc);
+            println!("{} nodes with weight", engine.stats().rewarded_nodes);
+            algo.graph_changed();
+            let post_change_score = algo.best_score();
+
+            let start = Instant::now();
+            let mut recovery_time = Duration::default();
+            let mut best_score = post_change_score;
+            let mut score_sum = 0.0;
+            let mut recovery_iterations = 0;
+            periods.serialize(PeriodRecord {
+                period: change,
+                iteration: 0,
+                best_score: best_score.into_inner(),
+            })?;
+            loop {
+                let done = match dynamics_cfg.recovery_time_ms {
+                    Some(ms) => start.elapsed() >= Duration::from_millis(ms),
+                    None => recovery_iterations as u64 >= dynamics_cfg.change_after_i,
+                };
+                if done {
+                    break;
+                }
+                if engine.step(graph_rc) {
+                    algo.graph_changed();
+                }
+                let rng_state = algo.rng_state();
+                let improved = algo.single_iteration().is_some();
+                recovery_iterations += 1;
+                replay.set_dynamics_position(change, recovery_iterations);
+                replay.record(
+                    Some(rng_state),
+                    improved,
+                    algo.best_solution(),
+                    algo.best_score(),
+                    algo.best_length(),
+                )?;
+                let score = algo.best_score();
+                score_sum += score.into_inner();
+                if score > best_score {
+                    best_score = score;
+                    recovery_time = start.elapsed();
+                }
+                periods.serialize(PeriodRecord {
+                    period: change,
+                    iteration: recovery_iterations,
+                    best_score: best_score.into_inner(),
+                })?;
+            }
+            iterations += recovery_iterations;
+
+            let drift = Similarity::between(&pre_change_solution, algo.best_solution());
+            writer.serialize(RecoveryRecord {
+                algorithm: run.algorithm.clone(),
+                heuristic: run.heuristic.clone(),
+                run_id: run.run_id.clone(),
+                change,
+                pre_change_score: pre_change_score.into_inner(),
+                post_change_score: post_change_score.into_inner(),
+                recovered_score: best_score.into_inner(),
+                mean_score: score_sum / recovery_iterations.max(1) as f64,
+                recovery_iterations,
+                recovery_time_us: recovery_time.as_micros(),
+                route_edge_overlap: drift.edge_overlap,
+                route_jaccard: drift.jaccard,
+                route_levenshtein: drift.levenshtein,
+            })?;
+        }
+        writer.flush()?;
+        periods.flush()?;
+        replay.flush()?;
+
+        Ok(iterations)
+    }
+
+    /// Writes the graph of the experiment with anonymized node ids, start_node as goal point and
+    /// the budget to dir, see instance::write_instance. Next to it a config.yaml runs the
+    /// experiment on the exported instance, it refers to dir relative to the working directory.
+    fn export_instance<IndexType: Copy + Hash + Eq + Debug + Display + Ord>(
+        config: &ExperimentConfig,
+        graph: &MatrixGraph<IndexType, R64, R64>,
+        start_node: IndexType,
+        mandatory_nodes: &[IndexType],
+        warm_start_route: &[IndexType],
+        dir: &Path,
+    ) -> Result<(), ExperimentConfigError> {
+        let invalid = |e: &dyn Display| ExperimentConfigError::InvalidGraphConfig(e.to_string());
+        let (seed, nw_range, ew_range) = if let Ok(f) = config.graph_creation.file() {
+            (f.seed, f.nw_range, None)
+        } else if let Ok(grid) = config.graph_creation.grid() {
+            (grid.seed, grid.nw_range, Some(grid.ew_range))
+        } else if let Ok(er) = config.graph_creation.erdos_renyi() {
+            (er.seed, er.nw_range, Some(er.ew_range))
+        } else if let Ok(planted) = config.graph_creation.planted_route() {
+            (planted.seed, planted.nw_range, Some(planted.ew_range))
+        } else {
+            let instance_cfg = config.graph_creation.instance()?;
+            let (_, instance) =
+                load_instance(Path::new(&instance_cfg.instance)).map_err(|e| invalid(&e))?;
+            (instance.seed, instance.nw_range, instance.ew_range)
+        };
+        let mut experiment_cfg = config.experiment.cfg();
+        let (graph, ids) = anonymize(graph);
+        let instance = InstanceConfig {
+            goal_point: ids[&start_node],
+            max_time: experiment_cfg.budget(),
+            seed,
+            nw_range,
+            ew_range,
+        };
+        write_instance(&graph, &instance, dir).map_err(|e| invalid(&e))?;
+
+        experiment_cfg.finished = false;
+        experiment_cfg.max_time = instance.max_time;
+        experiment_cfg.max_time_unit = None;
+        experiment_cfg.goal_point = None;
+        experiment_cfg.goal_points = None;
+        experiment_cfg.start_count = None;
+        if !mandatory_nodes.is_empty() {
+            experiment_cfg.mandatory_nodes = Some(MandatoryNodesConfig::Ids {
+                ids: mandatory_nodes.iter().map(|node| ids[node]).collect(),
+            });
+        }
+        if let Some(warm_start) = experiment_cfg
+            .warm_start
+            .as_mut()
+            .filter(|_| !warm_start_route.is_empty())
+        {
+            warm_start.route = Some(WarmStartRoute::Ids {
+                ids: warm_start_route.iter().map(|node| ids[node]).collect(),
+            });
+        }
+        // the config has no Clone, a copy is made by serializing it
+        let yaml = serde_yaml::to_string(config).map_err(|e| invalid(&e))?;
+        let mut exported: ExperimentConfig =
+            serde_yaml::from_str(&yaml).map_err(|e| invalid(&e))?;
+        exported.experiment = GeneralExperimentConfig::Full(experiment_cfg);
+        exported.graph_creation = GraphCreationConfig::Instance(InstanceLoad {
+            instance: dir.to_string_lossy().into_owned(),
+        });
+        let yaml = serde_yaml::to_string(&exported).map_err(|e| invalid(&e))?;
+        std::fs::write(dir.join("config.yaml"), yaml).map_err(|e| invalid(&e))
+    }
+
+    /// Computes the dynamic optimization metrics of the runs logged to the `_periods` files of
+    /// the run files of starts and writes them to a `_metrics` file next to filename. The runs
+    /// are the starts of an experiment on the graph with fingerprint, which all go through the
+    /// same graph changes. Runs are only compared to runs from the same start node.
+    fn write_metrics<IndexType: Display>(
+        filename: &str,
+        fingerprint: &str,
+        starts: &[(String, IndexType)],
+    ) -> Result<(), ExperimentConfigError> {
+        let mut runs = Vec::new();
+        for (run_file, start_node) in starts {
+            let path = format!("{}_periods", run_file);
+            match File::open(&path).map(read_periods) {
+                Ok(Ok(records)) => {
+                    let run_id = Path::new(run_file)
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    runs.push(PeriodRun {
+                        run_id,
+                        fingerprint: fingerprint.to_string(),
+                        start_node: start_node.to_string(),
+                        records,
+                    });
+                }
+                Ok(Err(e)) => eprintln!("Invalid period log {}: {}", path, e),
+                Err(e) => eprintln!("Could not open period log {}: {}", path, e),
+            }
         }
 
+        let mut writer = Writer::from_path(format!("{}_metrics", filename))?;
+        for metrics in report(&runs) {
+            writer.serialize(metrics)?;
+        }
+        writer.flush()?;
         Ok(())
     }
-}
 
-fn change_graph<IndexType: 'static + Clone + Hash + Copy + Eq + Debug + Display + Ord>(
-    graph: &RefCell<MatrixGraph<IndexType, R64, R64>>,
-    dynamics_cfg: &GraphDynamicsConfig,
-    rng: &mut Rand64,
-    nw_generator: &mut dyn FnMut() -> R64,
-    ew_generator: Option<&mut dyn FnMut() -> R64>,
-    original_node_weights: &mut HashMap<IndexType, R64>,
-    original_edge_weights: &mut HashMap<Edge<IndexType>, R64>,
-) {
-    let dynamics_cfg = dynamics_cfg.cfg();
-
-    // determine which nodes will be changed
-    let mut change_nodes = Vec::new();
-    for nid in graph.borrow().iter_node_ids() {
-        if rng.rand_float() < dynamics_cfg.node_change_probability {
-            change_nodes.push(nid);
-        }
-    }
-
-    // determine which edges will be changed
-    let mut change_edges = Vec::new();
-    for eid in graph.borrow().iter_edge_ids() {
-        if rng.rand_float() < dynamics_cfg.edge_change_probability {
-            change_edges.push(eid);
-        }
-    }
-
-    let mut mut_graph = graph.borrow_mut();
-    // change nodes
-    for nid in change_nodes {
-        // this should always contain a value, since all nodes in our graph should be initialized with a min value
-        if let (&c_val, Some(&o_val)) = (
-            mut_graph.node_weight(nid).unwrap(),
-            original_node_weights.get(&nid),
-        ) {
-            // if we already have a value we reset it to 0 otherwise we take the original value and add onto it.
-            // if the original value was the min value we create a new original value for this node and add onto it.
-            if c_val > R64::small() {
-                mut_graph.change_node(nid, R64::small());
-            } else if o_val > R64::small() {
-                let n_val = o_val + o_val * rng.rand_float() * dynamics_cfg.node_change_intensity;
-                mut_graph.change_node(nid, n_val);
-            } else {
-                let p_val = (nw_generator)();
-                original_node_weights.insert(nid, p_val);
-                let n_val = p_val + p_val * rng.rand_float() * dynamics_cfg.node_change_intensity;
-                mut_graph.change_node(nid, n_val);
+    /// Opens the `_routes.jsonl` file next to the log, if the route log is enabled.
+    fn route_log(enabled: bool, filename: &str) -> Option<Box<dyn io::Write>> {
+        if !enabled {
+            return None;
+        }
+        let path = format!("{}_routes.jsonl", filename);
+        match File::create(&path) {
+            Ok(file) => Some(Box::new(BufWriter::new(file))),
+            Err(e) => {
+                eprintln!("Could not create route log {}: {}", path, e);
+                None
             }
         }
     }
 
-    // change edges
-    let mut ew_gen = ew_generator;
-    for eid in change_edges {
-        let mut previous_val = R64::zero();
-        if let Some(&val) = original_edge_weights.get(&eid) {
-            if val > f64::small() {
-                previous_val = val;
-            } else {
-                match ew_gen {
-                    Some(ref mut gen) => {
-                        previous_val = (gen)();
-                        original_edge_weights.insert(eid, previous_val);
+    /// Draws the pheromones after the iteration with index iteration to a `_heatmap_` file next
+    /// to the log, numbered by the amount of iterations, if a heatmap is due after it.
+    fn write_heatmap<IndexType: Locate + Copy + Hash + Eq + Debug + Display + Ord>(
+        config: &ExperimentConfig,
+        pheromones: &Pheromones<IndexType>,
+        layout: Option<&Layout>,
+        filename: &str,
+        iteration: usize,
+    ) {
+        if let Some(heatmap_cfg) = config.pheromone_heatmap.filter(|cfg| cfg.due(iteration)) {
+            let path = format!("{}_heatmap_{}.svg", filename, iteration + 1);
+            let svg = Svg::new(heatmap_cfg.width, heatmap_cfg.height)
+                .with_title(&format!("Iteration {}", iteration + 1));
+            let result = File::create(&path)
+                .and_then(|file| export_heatmap(pheromones, layout, &svg, BufWriter::new(file)));
+            if let Err(e) = result {
+                eprintln!("Could not write heatmap {}: {}", path, e);
+            }
+        }
+    }
+
+    /// Shortens the final route of a run with shorten_route, keeping the rewarded and mandatory
+    /// nodes it visits. The shortened route is only taken, if it scores at least as well with
+    /// the time windows and revisit penalty of the run, and its improvement is logged.
+    fn post_process<IndexType: Distance<IndexType> + Copy + Hash + Eq + Debug + Display + Ord>(
+        heuristic: &SyncHeuristic<R64, R64>,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        (mandatory_nodes, time_windows, revisit_penalty, service_times): &(
+            HashSet<IndexType>,
+            TimeWindows<IndexType, R64>,
+            RevisitPenalty,
+            ServiceTimes<IndexType, R64>,
+        ),
+        solution: &Solution<IndexType>,
+        score: R64,
+        length: R64,
+    ) -> (Solution<IndexType>, R64, R64) {
+        let keep: HashSet<IndexType> = solution
+            .iter_unique_nodes()
+            .filter(|node| {
+                mandatory_nodes.contains(node)
+                    || graph_rc
+                        .borrow()
+                        .node_weight(*node)
+                        .is_ok_and(|weight| *weight > R64::zero())
+            })
+            .collect();
+        let shortened = shorten_route(solution, &*graph_rc.borrow(), &keep);
+        let (shortened, shortened_length) = match shortened {
+            Some(shortened) => shortened,
+            None => return (solution.clone(), score, length),
+        };
+        // shorten_route only measures the edges
+        let shortened_length = if service_times.is_empty() {
+            shortened_length
+        } else {
+            match solution_length_with_service_times(
+                &shortened,
+                graph_rc,
+                service_times,
+                time_windows,
+            ) {
+                Ok(shortened_length) => shortened_length,
+                Err(_) => return (solution.clone(), score, length),
+            }
+        };
+        let rescore = |solution| {
+            solution_score_with_service_times(
+                solution,
+                graph_rc,
+                heuristic,
+                time_windows,
+                revisit_penalty,
+                service_times,
+            )
+            .unwrap_or_else(|_| R64::zero())
+        };
+        let shortened_score = rescore(&shortened);
+        if shortened_score < rescore(solution) {
+            return (solution.clone(), score, length);
+        }
+        println!(
+            "Post-processing shortened the route from {:.2} to {:.2}, score {:.2} to {:.2}",
+            length.into_inner(),
+            shortened_length.into_inner(),
+            score.into_inner(),
+            shortened_score.into_inner()
+        );
+        (shortened, shortened_score, shortened_length)
+    }
+
+    /// Writes the final summary of a run to a `_summary` file next to its log, together with the
+    /// node weight collected by solution and the reward bound of the instance. The meta data of
+    /// the run goes to a `_run_meta.json` file, see RunContext.
+    fn write_summary<IndexType: Copy + Hash + Eq + Debug + Display + Ord>(
+        filename: &str,
+        context: &RunContext,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        solution: &Solution<IndexType>,
+        reward_bound: f64,
+        fingerprint: Fingerprint,
+        mut summary: Summary,
+    ) -> Result<(), ExperimentConfigError> {
+        summary.collected = Self::collected(graph_rc, solution);
+        summary.reward_bound = reward_bound;
+        summary.graph_fingerprint = fingerprint.to_string();
+        if summary.route_lengths.is_empty() {
+            summary = summary.with_routes(&[]);
+        }
+        context.write_meta(filename, fingerprint, &summary)?;
+        let mut writer = Writer::from_path(format!("{}_summary", filename))?;
+        writer.serialize(summary)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the node weight of the nodes solution visits in graph.
+    fn collected<IndexType: Copy + Hash + Eq + Debug + Display + Ord>(
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        solution: &Solution<IndexType>,
+    ) -> f64 {
+        solution
+            .iter_unique_nodes()
+            .filter_map(|node| graph_rc.borrow().node_weight(node).ok().copied())
+            .map(|weight| weight.into_inner())
+            .sum()
+    }
+
+    /// Reconstructs the best solutions over time from the replay logs of an earlier run with
+    /// start_count starts and writes them to a `_replayed` file next to each log, together with
+    /// their length and collected value in graph. With graph dynamics, every record is
+    /// reconstructed in the graph state of its iteration, which is restored by repeating the
+    /// dynamics with the logged seed and the generators of the run. Logged lengths, that differ
+    /// from the length in graph, are reported. This is expected for stochastic edge weights,
+    /// where logged lengths are sampled. Returns the average score and length of the final
+    /// solutions.
+    #[allow(clippy::too_many_arguments)]
+    fn replay_experiment<
+        IndexType: 'static
+            + Distance<IndexType>
+            + Neighborhood
+            + Clone
+            + Hash
+            + Copy
+            + Eq
+            + Debug
+            + Display
+            + Ord,
+    >(
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        service_times: &ServiceTimes<IndexType, R64>,
+        time_windows: &TimeWindows<IndexType, R64>,
+        dynamics_cfg: Option<DynamicsConfig>,
+        nw_generator: &mut dyn FnMut() -> R64,
+        mut ew_generator: Option<&mut dyn FnMut() -> R64>,
+        start_count: usize,
+        filename: &str,
+    ) -> Result<RunSummary, ExperimentConfigError> {
+        let files = if start_count == 1 {
+            vec![filename.to_string()]
+        } else {
+            (0..start_count)
+                .map(|i| format!("{}_s{}", filename, i))
+                .collect()
+        };
+
+        let mut run_summary = RunSummary::default();
+        // every start of the run began with the original graph
+        let original_graph = graph_rc.borrow().clone();
+        for file in files.iter() {
+            let log = File::open(format!("{}_replay", file)).map_err(|e| {
+                ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                    "Replay log of {} can't be opened: {}",
+                    file, e
+                ))
+            })?;
+            let records = read_records(log)
+                .map_err(|e| ExperimentConfigError::InvalidGraphConfig(e.to_string()))?;
+            let steps = match dynamics_cfg {
+                Some(mut cfg) => {
+                    *graph_rc.borrow_mut() = original_graph.clone();
+                    if let Some(seed) = records.iter().find_map(|record| record.dynamics_seed) {
+                        cfg.seed = seed;
                     }
-                    _ => {
-                        previous_val = val;
+                    let engine = DynamicsEngine::new(
+                        graph_rc,
+                        cfg,
+                        &mut *nw_generator,
+                        reborrow(&mut ew_generator),
+                    );
+                    let mut dynamics = DynamicsReplay::new(engine);
+                    let mut steps = Vec::with_capacity(records.len());
+                    for record in records {
+                        dynamics.advance(graph_rc, &record);
+                        steps.extend(
+                            reconstruct(graph_rc, vec![record], service_times, time_windows)
+                                .map_err(|e| {
+                                    ExperimentConfigError::InvalidGraphConfig(e.to_string())
+                                })?,
+                        );
                     }
-                };
+                    dynamics.finish(graph_rc, cfg.changes);
+                    steps
+                }
+                None => reconstruct(graph_rc, records, service_times, time_windows)
+                    .map_err(|e| ExperimentConfigError::InvalidGraphConfig(e.to_string()))?,
+            };
+
+            let mut writer = Writer::from_path(format!("{}_replayed", file))?;
+            for step in steps.iter() {
+                writer.serialize(ReplayedRecord {
+                    iteration: step.record.iteration,
+                    improved: step.record.improved,
+                    score: step.record.score,
+                    logged_length: step.record.length,
+                    length: step.length,
+                    collected: step.collected,
+                    consistent: step.consistent(),
+                    solution: step.record.solution.clone(),
+                })?;
+            }
+            writer.flush()?;
+
+            let inconsistent = steps.iter().filter(|step| !step.consistent()).count();
+            if inconsistent > 0 {
+                eprintln!(
+                    "{} of {} iterations in {} don't match the graph",
+                    inconsistent,
+                    steps.len(),
+                    file
+                );
+            }
+            if let Some(last) = steps.last() {
+                run_summary.score += last.record.score / files.len() as f64;
+                run_summary.length +=
+                    last.length.unwrap_or(last.record.length) / files.len() as f64;
+                run_summary.collected += last.collected / files.len() as f64;
             }
-        } else if let Some(ref mut gen) = ew_gen {
-            previous_val = (gen)();
-            original_edge_weights.insert(eid, previous_val);
         }
 
-        let val =
-            previous_val + previous_val * rng.rand_float() * dynamics_cfg.edge_change_intensity;
-        mut_graph.change_edge(eid, val).unwrap();
+        Ok(run_summary)
     }
 
-    let mut i = 0;
-    for node in mut_graph.iter_nodes() {
-        if node.1 > &R64::small() {
-            i += 1;
+    /// Returns the initial solution for a warm start. Consecutive waypoints of route are
+    /// connected by shortest paths, without waypoints TwoSwap is run from start_node instead.
+    #[allow(clippy::too_many_arguments)]
+    fn warm_start_solution<
+        IndexType: 'static + Distance<IndexType> + Clone + Hash + Copy + Eq + Debug + Display + Ord,
+    >(
+        heuristic: &SyncHeuristic<R64, R64>,
+        graph_rc: &RefCell<MatrixGraph<IndexType, R64, R64>>,
+        start_node: IndexType,
+        max_time: R64,
+        mandatory_nodes: &HashSet<IndexType>,
+        revisit_penalty: RevisitPenalty,
+        route: &[IndexType],
+    ) -> Result<Solution<IndexType>, ExperimentConfigError> {
+        if route.is_empty() {
+            let instance = ProblemInstance::new(graph_rc, start_node, max_time)
+                .with_mandatory_nodes(mandatory_nodes.clone())
+                .with_revisit_penalty(revisit_penalty);
+            let supervisor = two_swap::Supervisor::new(1, io::sink());
+            let mut two_swap_algo =
+                TwoSwap::new(instance, two_swap::Params::new(heuristic), supervisor);
+            while two_swap_algo.single_iteration().is_some() {}
+            return Ok(two_swap_algo.current_solution().0.clone());
+        }
+
+        let graph = graph_rc.borrow();
+        let mut solution = Solution::from_nodes(vec![route[0]]);
+        for waypoints in route.windows(2) {
+            match graph
+                .shortest_paths(waypoints[0])
+                .remove(&waypoints[1])
+                .flatten()
+            {
+                Some((path, _)) => {
+                    for &node in path.iter_nodes().skip(1) {
+                        solution.push_node(node);
+                    }
+                }
+                None => {
+                    return Err(ExperimentConfigError::InvalidGraphConfig(format!(
+                        "warm start node {} is not reachable from {}.",
+                        waypoints[1], waypoints[0]
+                    )))
+                }
+            }
         }
+
+        Ok(solution)
     }
-    println!("{} nodes with weight", i);
 }
@@ -0,0 +1,601 @@
+use decorum::R64;
+use num_traits::Zero;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+use crate::experiment_config::graph_dynamics_config::{
+    CongestionConfig, FullConfig, RegenerationConfig,
+};
+use crate::geo::{geodistance_haversine, GeoPoint};
+use crate::graph::{Edge, GenericWeightedGraph, GraphCell, MatrixGraph};
+use crate::rng::{gen_range, rng64, Rng};
+use crate::util::SmallVal;
+
+/// Counts of the changes a DynamicsEngine applied so far.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DynamicsStats {
+    /// Amount of calls to apply.
+    pub changes: usize,
+    /// Node weights changed over all changes.
+    pub changed_nodes: usize,
+    /// Edge weights changed over all changes.
+    pub changed_edges: usize,
+    /// Nodes with a reward after the last change.
+    pub rewarded_nodes: usize,
+}
+
+/// Node ids, which span the regions congested by a DynamicsEngine.
+pub trait Neighborhood: Sized + Clone {
+    /// Returns the nodes of graph within radius of center, including center itself.
+    fn neighborhood(
+        graph: &MatrixGraph<Self, R64, R64>,
+        center: Self,
+        radius: f64,
+    ) -> HashSet<Self>;
+}
+
+/// The radius is a distance in km.
+impl Neighborhood for GeoPoint {
+    fn neighborhood(
+        graph: &MatrixGraph<Self, R64, R64>,
+        center: Self,
+        radius: f64,
+    ) -> HashSet<Self> {
+        graph
+            .iter_node_ids()
+            .filter(|node| geodistance_haversine(center, *node) <= radius)
+            .collect()
+    }
+}
+
+/// Without locations the radius is the amount of edges to follow from center.
+impl Neighborhood for usize {
+    fn neighborhood(
+        graph: &MatrixGraph<Self, R64, R64>,
+        center: Self,
+        radius: f64,
+    ) -> HashSet<Self> {
+        let mut found: HashSet<usize> = [center].iter().copied().collect();
+        let mut queue = VecDeque::new();
+        queue.push_back((center, 0));
+        while let Some((node, hops)) = queue.pop_front() {
+            if (hops + 1) as f64 > radius {
+                continue;
+            }
+            for neighbor in graph.iter_neighbor_ids(node).unwrap() {
+                if found.insert(neighbor) {
+                    queue.push_back((neighbor, hops + 1));
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Edges congested by one change, together with the recovery iterations it still lasts.
+struct Congestion<IndexType> {
+    edges: Vec<Edge<IndexType>>,
+    remaining: u64,
+}
+
+/// Changes the node and edge weights of a graph as described by a dynamics config.
+/// The engine remembers the original weight of every node and edge, changes always start from
+/// them, so the graph does not drift away from its original over many changes.
+/// Original weights which are missing or too small are drawn from the generators once.
+pub struct DynamicsEngine<'g, IndexType> {
+    cfg: FullConfig,
+    rng: Rng,
+    nw_generator: &'g mut dyn FnMut() -> R64,
+    ew_generator: Option<&'g mut dyn FnMut() -> R64>,
+    original_node_weights: HashMap<IndexType, R64>,
+    original_edge_weights: HashMap<Edge<IndexType>, R64>,
+    congestions: Vec<Congestion<IndexType>>,
+    stats: DynamicsStats,
+}
+
+impl<'g, IndexType> DynamicsEngine<'g, IndexType>
+where
+    IndexType: 'static + Neighborhood + Clone + Hash + Copy + Eq + Debug + Display + Ord,
+{
+    /// Creates an engine for graph, seeded with the seed of cfg.
+    /// The current weights of graph are taken as its original weights.
+    pub fn new<C>(
+        graph: &C,
+        cfg: FullConfig,
+        nw_generator: &'g mut dyn FnMut() -> R64,
+        ew_generator: Option<&'g mut dyn FnMut() -> R64>,
+    ) -> Self
+    where
+        C: GraphCell<Graph = MatrixGraph<IndexType, R64, R64>> + ?Sized,
+    {
+        let original_node_weights = graph
+            .read()
+            .iter_nodes()
+            .map(|(node, &weight)| (node, weight))
+            .collect();
+        let original_edge_weights = graph
+            .read()
+            .iter_edges()
+            .map(|(edge, &weight)| (edge, weight))
+            .collect();
+        DynamicsEngine {
+            cfg,
+            rng: rng64(cfg.seed as u128),
+            nw_generator,
+            ew_generator,
+            original_node_weights,
+            original_edge_weights,
+            congestions: Vec::new(),
+            stats: DynamicsStats::default(),
+        }
+    }
+
+    /// Applies one change to graph.
+    /// Each node changes with node_change_probability. Rewarded nodes lose their reward, the
+    /// others get their original reward, raised by up to node_change_intensity times itself.
+    /// Each edge changes with edge_change_probability to its original weight, raised by up to
+    /// edge_change_intensity times itself.
+    /// With regeneration, changed nodes only lose their reward, which step gives back over time.
+    /// With congestion, the edges of a random region are congested instead, see CongestionConfig.
+    pub fn apply<C>(&mut self, graph: &C)
+    where
+        C: GraphCell<Graph = MatrixGraph<IndexType, R64, R64>> + ?Sized,
+    {
+        // determine which nodes will be changed
+        let mut change_nodes = Vec::new();
+        for nid in graph.read().iter_node_ids() {
+            if self.rng.rand_float() < self.cfg.node_change_probability {
+                change_nodes.push(nid);
+            }
+        }
+
+        // determine which edges will be changed
+        let mut change_edges = Vec::new();
+        if self.cfg.congestion.is_none() {
+            for eid in graph.read().iter_edge_ids() {
+                if self.rng.rand_float() < self.cfg.edge_change_probability {
+                    change_edges.push(eid);
+                }
+            }
+        }
+
+        let mut mut_graph = graph.write();
+        // change nodes
+        for &nid in &change_nodes {
+            // this should always contain a value, since all nodes in our graph should be initialized with a min value
+            if let (weight, Some(&o_val)) = (
+                mut_graph.node_weight_mut(nid).unwrap(),
+                self.original_node_weights.get(&nid),
+            ) {
+                // if we already have a value we reset it to 0 otherwise we take the original value and add onto it.
+                // if the original value was the min value we create a new original value for this node and add onto it.
+                if *weight > R64::small() || self.cfg.regeneration.is_some() {
+                    *weight = R64::small();
+                } else if o_val > R64::small() {
+                    *weight =
+                        o_val + o_val * self.rng.rand_float() * self.cfg.node_change_intensity;
+                } else {
+                    let p_val = (self.nw_generator)();
+                    self.original_node_weights.insert(nid, p_val);
+                    *weight =
+                        p_val + p_val * self.rng.rand_float() * self.cfg.node_change_intensity;
+                }
+            }
+        }
+
+        // change edges
+        for &eid in &change_edges {
+            let mut previous_val = R64::zero();
+            if let Some(&val) = self.original_edge_weights.get(&eid) {
+                if val > f64::small() {
+                    previous_val = val;
+                } else {
+                    match self.ew_generator {
+                        Some(ref mut gen) => {
+                            previous_val = (gen)();
+                            self.original_edge_weights.insert(eid, previous_val);
+                        }
+                        _ => {
+                            previous_val = val;
+                        }
+                    };
+                }
+            } else if let Some(ref mut gen) = self.ew_generator {
+                previous_val = (gen)();
+                self.original_edge_weights.insert(eid, previous_val);
+            }
+
+            let val = previous_val
+                + previous_val * self.rng.rand_float() * self.cfg.edge_change_intensity;
+            *mut_graph.edge_weight_mut(eid).unwrap() = val;
+        }
+
+        if let Some(congestion) = self.cfg.congestion {
+            change_edges = self.congest(&mut mut_graph, congestion);
+        }
+
+        self.stats.changes += 1;
+        self.stats.changed_nodes += change_nodes.len();
+        self.stats.changed_edges += change_edges.len();
+        self.stats.rewarded_nodes = mut_graph
+            .iter_nodes()
+            .filter(|(_, &weight)| weight > R64::small())
+            .count();
+    }
+
+    /// Advances the dynamics of graph by one iteration. Congestions, which ran out, are lifted
+    /// and with regeneration the rewards regenerate and decay, see RegenerationConfig.
+    /// Returns whether graph changed, which is never the case with the default dynamics.
+    pub fn step<C>(&mut self, graph: &C) -> bool
+    where
+        C: GraphCell<Graph = MatrixGraph<IndexType, R64, R64>> + ?Sized,
+    {
+        let mut mut_graph = graph.write();
+        let lifted = self.lift_congestions(&mut mut_graph);
+        match self.cfg.regeneration {
+            Some(regeneration) => {
+                self.regenerate(&mut mut_graph, regeneration);
+                true
+            }
+            None => lifted,
+        }
+    }
+
+    /// Multiplies the original weights of all edges within the region around a random center
+    /// with the congestion multiplier and returns the congested edges.
+    fn congest(
+        &mut self,
+        graph: &mut MatrixGraph<IndexType, R64, R64>,
+        congestion: CongestionConfig,
+    ) -> Vec<Edge<IndexType>> {
+        let nodes: Vec<IndexType> = graph.iter_node_ids().collect();
+        if nodes.is_empty() {
+            return Vec::new();
+        }
+        let center = nodes[gen_range(&mut self.rng, 0..nodes.len())];
+        let region = IndexType::neighborhood(graph, center, congestion.radius);
+        let edges: Vec<Edge<IndexType>> = graph
+            .iter_edge_ids()
+            .filter(|(from, to)| region.contains(from) && region.contains(to))
+            .collect();
+        for &eid in &edges {
+            let weight = graph.edge_weight_mut(eid).unwrap();
+            let original = self
+                .original_edge_weights
+                .get(&eid)
+                .copied()
+                .unwrap_or(*weight);
+            // overlapping congestions don't add up, each starts from the original weight
+            *weight = original * congestion.multiplier;
+        }
+        self.congestions.push(Congestion {
+            edges: edges.clone(),
+            remaining: congestion.duration,
+        });
+        edges
+    }
+
+    /// Counts down the iterations of all congestions and resets the edges of the ones which
+    /// ran out to their original weight, unless another congestion still covers them.
+    /// Returns whether any congestion was lifted.
+    fn lift_congestions(&mut self, graph: &mut MatrixGraph<IndexType, R64, R64>) -> bool {
+        let (expired, active): (Vec<_>, Vec<_>) = self
+            .congestions
+            .drain(..)
+            .partition(|congestion| congestion.remaining == 0);
+        self.congestions = active;
+        for congestion in &mut self.congestions {
+            congestion.remaining -= 1;
+        }
+        let still_congested: HashSet<Edge<IndexType>> = self
+            .congestions
+            .iter()
+            .flat_map(|congestion| congestion.edges.iter().copied())
+            .collect();
+        for congestion in &expired {
+            for eid in &congestion.edges {
+                if still_congested.contains(eid) {
+                    continue;
+                }
+                if let (Ok(weight), Some(&original)) = (
+                    graph.edge_weight_mut(*eid),
+                    self.original_edge_weights.get(eid),
+                ) {
+                    *weight = original;
+                }
+            }
+        }
+        !expired.is_empty()
+    }
+
+    /// Lets the rewards of graph regenerate and decay for one iteration.
+    /// Nodes without an original reward are left as they are.
+    fn regenerate(
+        &mut self,
+        graph: &mut MatrixGraph<IndexType, R64, R64>,
+        regeneration: RegenerationConfig,
+    ) {
+        let mut rewarded_nodes = 0;
+        for (&nid, &o_val) in &self.original_node_weights {
+            if o_val <= R64::small() {
+                continue;
+            }
+            if let Ok(weight) = graph.node_weight_mut(nid) {
+                let regenerated = (*weight + o_val * regeneration.rate).min(o_val);
+                *weight = (regenerated * (1.0 - regeneration.decay)).max(R64::small());
+                if *weight > R64::small() {
+                    rewarded_nodes += 1;
+                }
+            }
+        }
+        self.stats.rewarded_nodes = rewarded_nodes;
+    }
+
+    /// Returns the counts of all changes applied so far.
+    pub fn stats(&self) -> DynamicsStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::SyncGraph;
+    use std::cell::RefCell;
+
+    fn cfg(node_change_probability: f64, edge_change_probability: f64) -> FullConfig {
+        FullConfig {
+            seed: 7,
+            changes: 1,
+            change_after_i: 10,
+            recovery_time_ms: None,
+            edge_change_probability,
+            node_change_probability,
+            edge_change_intensity: 0.5,
+            node_change_intensity: 0.5,
+            regeneration: None,
+            congestion: None,
+        }
+    }
+
+    fn graph() -> RefCell<MatrixGraph<usize, R64, R64>> {
+        RefCell::new(
+            MatrixGraph::new_usize_indexed(
+                vec![R64::small(), R64::from_inner(2.0), R64::small()],
+                vec![
+                    (0, 1, R64::from_inner(1.0)),
+                    (1, 2, R64::from_inner(4.0)),
+                    (2, 0, R64::from_inner(2.0)),
+                ],
+            )
+            .unwrap(),
+        )
+    }
+
+    fn weights(
+        graph: &RefCell<MatrixGraph<usize, R64, R64>>,
+    ) -> (Vec<R64>, Vec<(Edge<usize>, R64)>) {
+        let graph = graph.borrow();
+        (
+            graph.iter_nodes().map(|(_, &weight)| weight).collect(),
+            graph
+                .iter_edges()
+                .map(|(edge, &weight)| (edge, weight))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn no_probability_changes_nothing() {
+        let graph = graph();
+        let before = weights(&graph);
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, cfg(0.0, 0.0), &mut nw_gen, None);
+        engine.apply(&graph);
+
+        assert_eq!(weights(&graph), before);
+        assert_eq!(
+            engine.stats(),
+            DynamicsStats {
+                changes: 1,
+                changed_nodes: 0,
+                changed_edges: 0,
+                rewarded_nodes: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn rewards_toggle_and_start_from_the_original() {
+        let graph = graph();
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, cfg(1.0, 0.0), &mut nw_gen, None);
+
+        engine.apply(&graph);
+        let (nodes, _) = weights(&graph);
+        assert!(nodes[0] >= R64::from_inner(1.0) && nodes[0] <= R64::from_inner(1.5));
+        assert_eq!(nodes[1], R64::small());
+        assert!(nodes[2] >= R64::from_inner(1.0) && nodes[2] <= R64::from_inner(1.5));
+        assert_eq!(engine.stats().rewarded_nodes, 2);
+
+        engine.apply(&graph);
+        let (nodes, _) = weights(&graph);
+        assert_eq!(nodes[0], R64::small());
+        assert!(nodes[1] >= R64::from_inner(2.0) && nodes[1] <= R64::from_inner(3.0));
+        assert_eq!(nodes[2], R64::small());
+        assert_eq!(engine.stats().changed_nodes, 6);
+    }
+
+    #[test]
+    fn edges_change_relative_to_their_original_weight() {
+        let graph = graph();
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, cfg(0.0, 1.0), &mut nw_gen, None);
+        for _ in 0..5 {
+            engine.apply(&graph);
+        }
+
+        let weight = *graph.borrow().edge_weight((1, 2)).unwrap();
+        assert!(weight >= R64::from_inner(4.0) && weight <= R64::from_inner(6.0));
+        assert_eq!(engine.stats().changed_edges, 15);
+    }
+
+    #[test]
+    fn equal_seeds_apply_equal_changes() {
+        let (graph_a, graph_b) = (graph(), graph());
+        let mut nw_gen_a = || R64::from_inner(1.0);
+        let mut nw_gen_b = || R64::from_inner(1.0);
+        let mut engine_a = DynamicsEngine::new(&graph_a, cfg(0.5, 0.5), &mut nw_gen_a, None);
+        let mut engine_b = DynamicsEngine::new(&graph_b, cfg(0.5, 0.5), &mut nw_gen_b, None);
+        for _ in 0..3 {
+            engine_a.apply(&graph_a);
+            engine_b.apply(&graph_b);
+        }
+
+        assert_eq!(weights(&graph_a), weights(&graph_b));
+        assert_eq!(engine_a.stats(), engine_b.stats());
+    }
+
+    #[test]
+    fn sync_graphs_get_the_same_changes() {
+        let graph_a = graph();
+        let graph_b = SyncGraph::new(graph().into_inner());
+        let mut nw_gen_a = || R64::from_inner(1.0);
+        let mut nw_gen_b = || R64::from_inner(1.0);
+        let mut engine_a = DynamicsEngine::new(&graph_a, cfg(0.5, 0.5), &mut nw_gen_a, None);
+        let mut engine_b = DynamicsEngine::new(&graph_b, cfg(0.5, 0.5), &mut nw_gen_b, None);
+        for _ in 0..3 {
+            engine_a.apply(&graph_a);
+            engine_b.apply(&graph_b);
+        }
+
+        assert_eq!(
+            weights(&graph_a),
+            weights(&RefCell::new(graph_b.into_inner()))
+        );
+    }
+
+    #[test]
+    fn collected_rewards_regenerate_up_to_their_original() {
+        let graph = graph();
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut cfg = cfg(1.0, 0.0);
+        cfg.regeneration = Some(RegenerationConfig {
+            rate: 0.25,
+            decay: 0.0,
+        });
+        let mut engine = DynamicsEngine::new(&graph, cfg, &mut nw_gen, None);
+
+        engine.apply(&graph);
+        // the only reward is collected, nodes without one stay without one
+        assert_eq!(weights(&graph).0, vec![R64::small(); 3]);
+        assert!(engine.step(&graph));
+        assert_eq!(weights(&graph).0[1], R64::small() + R64::from_inner(0.5));
+        for _ in 0..4 {
+            engine.step(&graph);
+        }
+        assert_eq!(
+            weights(&graph).0,
+            vec![R64::small(), R64::from_inner(2.0), R64::small()]
+        );
+        assert_eq!(engine.stats().rewarded_nodes, 1);
+    }
+
+    #[test]
+    fn uncollected_rewards_decay() {
+        let graph = graph();
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut cfg = cfg(0.0, 0.0);
+        cfg.regeneration = Some(RegenerationConfig {
+            rate: 0.0,
+            decay: 0.25,
+        });
+        let mut engine = DynamicsEngine::new(&graph, cfg, &mut nw_gen, None);
+
+        engine.step(&graph);
+        assert_eq!(weights(&graph).0[1], R64::from_inner(1.5));
+        engine.step(&graph);
+        assert_eq!(weights(&graph).0[1], R64::from_inner(1.125));
+        engine.step(&graph);
+        // rewards never fall below the minimal node weight
+        assert_eq!(weights(&graph).0[1], R64::small());
+    }
+
+    #[test]
+    fn toggling_rewards_do_not_step() {
+        let graph = graph();
+        let before = weights(&graph);
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, cfg(1.0, 1.0), &mut nw_gen, None);
+
+        assert!(!engine.step(&graph));
+        assert_eq!(weights(&graph), before);
+    }
+
+    fn congestion(radius: f64, duration: u64) -> FullConfig {
+        let mut cfg = cfg(0.0, 1.0);
+        cfg.congestion = Some(CongestionConfig {
+            radius,
+            multiplier: 3.0,
+            duration,
+        });
+        cfg
+    }
+
+    #[test]
+    fn usize_neighborhoods_follow_edges() {
+        let graph = graph();
+        let graph = graph.borrow();
+
+        assert_eq!(
+            usize::neighborhood(&graph, 0, 0.0),
+            [0].iter().copied().collect()
+        );
+        assert_eq!(
+            usize::neighborhood(&graph, 0, 1.0),
+            [0, 1].iter().copied().collect()
+        );
+        assert_eq!(
+            usize::neighborhood(&graph, 0, 2.0),
+            [0, 1, 2].iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn congestions_multiply_the_edges_of_a_region() {
+        let graph = graph();
+        let (_, before) = weights(&graph);
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, congestion(1.0, 1), &mut nw_gen, None);
+        engine.apply(&graph);
+
+        // a radius of one edge only covers the edge leaving the center
+        let (_, after) = weights(&graph);
+        let congested: Vec<_> = before
+            .iter()
+            .zip(after.iter())
+            .filter(|(original, changed)| original != changed)
+            .collect();
+        assert_eq!(congested.len(), 1);
+        let ((_, original), (_, changed)) = congested[0];
+        assert_eq!(*changed, *original * 3.0);
+        assert_eq!(engine.stats().changed_edges, 1);
+    }
+
+    #[test]
+    fn congestions_are_lifted_after_their_duration() {
+        let graph = graph();
+        let before = weights(&graph);
+        let mut nw_gen = || R64::from_inner(1.0);
+        let mut engine = DynamicsEngine::new(&graph, congestion(2.0, 2), &mut nw_gen, None);
+        engine.apply(&graph);
+        let congested = weights(&graph);
+
+        assert!(!engine.step(&graph));
+        assert!(!engine.step(&graph));
+        assert_eq!(weights(&graph), congested);
+        assert!(engine.step(&graph));
+        assert_eq!(weights(&graph), before);
+    }
+}
@@ -2,15 +2,28 @@ pub mod algo_config;
 pub mod general_experiment_config;
 pub mod graph_creation_config;
 pub mod graph_dynamics_config;
+pub mod heatmap_config;
+pub mod rng_config;
+pub mod sweep_config;
+pub mod tuning_config;
 
 pub use algo_config::{AcoExperiment, AlgoConfig, TwoSwapExperiment};
 pub use general_experiment_config::GeneralExperimentConfig;
 pub use graph_creation_config::GraphCreationConfig;
 pub use graph_dynamics_config::GraphDynamicsConfig;
+pub use heatmap_config::HeatmapConfig;
+pub use rng_config::RngConfig;
+pub use sweep_config::SweepConfig;
+pub use tuning_config::TuningConfig;
 
+use crate::rng::{derive_seed, os_random_seed};
+
+use serde::de::{self, Deserializer, Visitor};
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 pub trait Algorithm {
     fn finished(&self) -> bool;
@@ -31,43 +44,309 @@ pub trait Fix<CorrectType> {
     fn to_fixed(&self) -> CorrectType;
 }
 
+/// Configs without a seed, which are completed by a seed from outside.
+pub trait FixWithSeed<CorrectType> {
+    fn with_seed(&self, seed: u64) -> CorrectType;
+}
+
+/// Configs used without resolving their seeds first draw a seed from the operating system.
+impl<T: FixWithSeed<CorrectType>, CorrectType> Fix<CorrectType> for T {
+    fn to_fixed(&self) -> CorrectType {
+        self.with_seed((os_random_seed() >> 64) as u64)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct ExperimentConfig {
+    /// Master seed, from which the seeds of all sections without one are derived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
     pub experiment: GeneralExperimentConfig,
     pub algorithm: AlgoConfig,
     pub graph_creation: GraphCreationConfig,
-    // pub graph_dynamics: GraphDynamicsConfig,
+    /// Changes the graph after the run and lets the algorithm recover, to measure how it copes
+    /// with dynamic instances.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_dynamics: Option<GraphDynamicsConfig>,
+    /// Rng algorithm and seeding of the algorithms, pcg with the configured seeds by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rng: Option<RngConfig>,
+    /// Draws the pheromones of ant colony algorithms as heatmaps while they run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pheromone_heatmap: Option<HeatmapConfig>,
+    /// Runs the experiment once for every parameter set of the sweep instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sweep: Option<SweepConfig>,
+    /// Tunes the algorithm parameters instead of running the experiment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<TuningConfig>,
+}
+
+impl ExperimentConfig {
+    /// Reads the config from yaml. serde can't tell which variant of an untagged section was
+    /// meant, so it doesn't name misspelled fields, the error names the keys of sections no
+    /// variant of the section has instead.
+    pub fn from_yaml(yaml: &str) -> Result<Self, ExperimentConfigError> {
+        serde_yaml::from_str(yaml).map_err(|err| {
+            let unknown = serde_yaml::from_str(yaml)
+                .map(|config| unknown_fields(&config))
+                .unwrap_or_default();
+            ExperimentConfigError::InvalidConfig(if unknown.is_empty() {
+                err.to_string()
+            } else {
+                format!("{}, unknown fields: {}", err, unknown.join(", "))
+            })
+        })
+    }
+
+    /// Gives every section without a seed the seed derived from the master seed and the name
+    /// of the section. A missing master seed is drawn from the operating system first, so
+    /// writing the resolved config back makes the run reproducible.
+    pub fn with_resolved_seeds(mut self) -> Self {
+        let master_seed = *self
+            .seed
+            .get_or_insert_with(|| (os_random_seed() >> 64) as u64);
+        let seed = |section| derive_seed(master_seed, section);
+
+        self.experiment = self.experiment.seeded(seed("experiment"));
+        self.algorithm = self.algorithm.seeded(seed("algorithm"));
+        self.graph_creation = self.graph_creation.seeded(seed("graph_creation"));
+        self.graph_dynamics = self
+            .graph_dynamics
+            .map(|dynamics| dynamics.seeded(seed("graph_dynamics")));
+        self
+    }
+}
+
+/// Returns the keys of the sections of config, which none of the variants of their section
+/// has as field, as section.key.
+fn unknown_fields(config: &Value) -> Vec<String> {
+    let sections = [
+        ("experiment", GeneralExperimentConfig::fields()),
+        ("algorithm", AlgoConfig::fields()),
+        ("graph_creation", GraphCreationConfig::fields()),
+        ("graph_dynamics", GraphDynamicsConfig::fields()),
+    ];
+    let mut unknown = Vec::new();
+    for (section, fields) in sections.iter() {
+        if let Some(mapping) = config.get(section).and_then(Value::as_mapping) {
+            for key in mapping.iter().filter_map(|(key, _)| key.as_str()) {
+                if !fields.contains(&key) {
+                    unknown.push(format!("{}.{}", section, key));
+                }
+            }
+        }
+    }
+    unknown
+}
+
+/// Returns the fields of the struct T, as taken by its derived Deserialize impl.
+pub(crate) fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _res = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// A deserializer, which only takes the field names a struct asks it for.
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only structs have field names"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("only the field names are taken"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
 }
 
 #[derive(Debug)]
 pub enum ExperimentConfigError {
+    /// The yaml is no valid config.
+    InvalidConfig(String),
     NotAco,
     NotMMAco,
     NotAcs,
     NotTwoSwap,
     NotRandom,
+    NotExact,
     InvalidAlgorithmConfig(String),
     NotFileBased,
     NotGrid,
     NotErdosRenyi,
+    NotPlantedRoute,
+    NotInstance,
     InvalidGraphConfig(String),
+    InvalidInstance(String),
+    /// Results of the experiment could not be written.
+    Output(csv::Error),
 }
 
 impl fmt::Display for ExperimentConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::InvalidConfig(msg) => write!(f, "{}", msg),
             Self::NotAco => write!(f, "Config is not a valid ACO config."),
             Self::NotMMAco => write!(f, "Config is not a valid MMAco config."),
             Self::NotAcs => write!(f, "Config is not a valid ACS config."),
             Self::NotTwoSwap => write!(f, "Config is not a valid TwoSwap config."),
             Self::NotRandom => write!(f, "Config is not a valid RandomSearch config."),
+            Self::NotExact => write!(f, "Config is not a valid Exact config."),
             Self::InvalidAlgorithmConfig(msg) => write!(f, "{}", msg),
             Self::NotFileBased => write!(f, "Config is not a valid file import config."),
             Self::NotGrid => write!(f, "Config is not a valid generation config."),
             Self::NotErdosRenyi => write!(f, "Config is not a valid ErdosRenyi generation config."),
+            Self::NotPlantedRoute => {
+                write!(f, "Config is not a valid planted route generation config.")
+            }
+            Self::NotInstance => write!(f, "Config is not a valid instance load config."),
             Self::InvalidGraphConfig(msg) => write!(f, "{}", msg),
+            Self::InvalidInstance(msg) => write!(f, "{}", msg),
+            Self::Output(e) => write!(f, "Could not write results: {}", e),
         }
     }
 }
 
 impl Error for ExperimentConfigError {}
+
+impl From<csv::Error> for ExperimentConfigError {
+    fn from(e: csv::Error) -> Self {
+        Self::Output(e)
+    }
+}
+
+impl From<io::Error> for ExperimentConfigError {
+    fn from(e: io::Error) -> Self {
+        Self::Output(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(seed: &str) -> String {
+        format!(
+            "{}
+experiment:
+  aggregation_rate: 1
+  max_time: 100.0
+algorithm:
+  alpha: 1.0
+  beta: 1.0
+  rho: 0.1
+  q_0: 0.5
+  ant_count: 10
+  iterations: 100
+graph_creation:
+  seed: 3
+  size: [4, 4]
+  nw_range: [0.0, 1.0]
+  ew_range: [1.0, 2.0]
+  node_weight_probability: 0.5",
+            seed
+        )
+    }
+
+    fn config(seed: &str) -> ExperimentConfig {
+        serde_yaml::from_str(&yaml(seed)).unwrap()
+    }
+
+    #[test]
+    fn misspelled_fields_are_named() {
+        assert!(ExperimentConfig::from_yaml(&yaml("")).is_ok());
+        let err = ExperimentConfig::from_yaml(&yaml("").replace("ant_count", "ant_cout"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("algorithm.ant_cout"), "{}", err);
+        let err =
+            ExperimentConfig::from_yaml(&yaml("").replace("max_time", "flush_evry: 2\n  max_time"))
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("experiment.flush_evry"), "{}", err);
+        assert!(!err.contains("max_time"), "{}", err);
+    }
+
+    #[test]
+    fn missing_seeds_are_derived_from_the_master_seed() {
+        let experiment = config("seed: 7").with_resolved_seeds();
+        assert_eq!(experiment.seed, Some(7));
+        assert_eq!(
+            experiment.experiment.cfg().seed,
+            derive_seed(7, "experiment")
+        );
+        assert_eq!(
+            experiment.algorithm.aco().unwrap().seed,
+            derive_seed(7, "algorithm")
+        );
+        assert!(matches!(experiment.algorithm, AlgoConfig::Aco(_)));
+        // configured seeds are kept
+        assert_eq!(experiment.graph_creation.grid().unwrap().seed, 3);
+
+        let experiment = config("").with_resolved_seeds();
+        let master_seed = experiment.seed.unwrap();
+        assert_eq!(
+            experiment.algorithm.aco().unwrap().seed,
+            derive_seed(master_seed, "algorithm")
+        );
+    }
+
+    #[test]
+    fn acs_configs_survive_the_rewrite() {
+        let acs_yaml = yaml("seed: 7").replace("  q_0: 0.5", "  q_0: 0.5\n  t_0: 0.2");
+        let experiment: ExperimentConfig = serde_yaml::from_str(&acs_yaml).unwrap();
+        let experiment = experiment.with_resolved_seeds();
+        assert!(matches!(experiment.algorithm, AlgoConfig::Acs(_)));
+
+        // the binary writes the resolved config back, which has to be read as the same algorithm
+        let rewritten: ExperimentConfig =
+            serde_yaml::from_str(&serde_yaml::to_string(&experiment).unwrap()).unwrap();
+        let acs = rewritten.algorithm.acs().unwrap();
+        assert_eq!(acs.t_0, 0.2);
+        assert_eq!(acs.seed, derive_seed(7, "algorithm"));
+        assert!(rewritten.algorithm.aco().is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        for (field, typo) in [
+            ("ant_count", "ant_cout"),
+            ("aggregation_rate", "aggregation_rte"),
+            ("node_weight_probability", "node_weight_prob"),
+            (
+                "max_time: 100.0",
+                "max_time: 100.0\n  multilevel: {max_rewrd: 1.0}",
+            ),
+        ] {
+            let text = yaml("seed: 7").replace(field, typo);
+            assert!(serde_yaml::from_str::<ExperimentConfig>(&text).is_err());
+        }
+        let text = format!("{}\ngraph_dynamic: ~", yaml(""));
+        let err = serde_yaml::from_str::<ExperimentConfig>(&text).unwrap_err();
+        assert!(err.to_string().starts_with("unknown field `graph_dynamic`"));
+    }
+
+    #[test]
+    fn params_string_lists_the_algorithm_parameters() {
+        let experiment = config("seed: 7").with_resolved_seeds();
+        let params = experiment.algorithm.params_string();
+        assert!(params.starts_with("alpha=1 beta=1 rho=0.1 q_0=0.5 seed="));
+        assert!(params.ends_with(" ant_count=10 iterations=100"));
+        assert!(!params.contains("finished"));
+    }
+}
@@ -1,18 +1,24 @@
 mod aco_experiment;
 mod acs_experiment;
+mod exact_experiment;
 mod mm_aco_experiment;
 mod random_search_experiment;
 mod two_swap_experiment;
 
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 
-use crate::experiment_config::{ExperimentConfigError, Fix};
+use crate::experiment_config::{struct_fields, ExperimentConfigError, Fix, FixWithSeed};
 pub use aco_experiment::{AcoExperiment, UnseededAcoExperiment};
 pub use acs_experiment::{AcsExperiment, UnseededAcsExperiment};
+pub use exact_experiment::ExactExperiment;
 pub use mm_aco_experiment::{MMAcoExperiment, UnseededMMAcoExperiment};
 pub use random_search_experiment::{RandomSearchExperiment, UnseededRandomSearchExperiment};
 pub use two_swap_experiment::TwoSwapExperiment;
 
+/// Names of all algorithms, as written into their logs.
+pub const ALGORITHMS: &[&str] = &["aco", "mm_aco", "acs", "two_swap", "random", "exact"];
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
 pub enum AlgoConfig {
@@ -24,10 +30,28 @@ pub enum AlgoConfig {
     UnseededAco(UnseededAcoExperiment),
     Random(RandomSearchExperiment),
     UnseededRandom(UnseededRandomSearchExperiment),
+    Exact(ExactExperiment),
     TwoSwap(TwoSwapExperiment),
 }
 
 impl AlgoConfig {
+    /// Returns the fields of all variants.
+    pub fn fields() -> Vec<&'static str> {
+        [
+            struct_fields::<MMAcoExperiment>(),
+            struct_fields::<UnseededMMAcoExperiment>(),
+            struct_fields::<AcsExperiment>(),
+            struct_fields::<UnseededAcsExperiment>(),
+            struct_fields::<AcoExperiment>(),
+            struct_fields::<UnseededAcoExperiment>(),
+            struct_fields::<RandomSearchExperiment>(),
+            struct_fields::<UnseededRandomSearchExperiment>(),
+            struct_fields::<ExactExperiment>(),
+            struct_fields::<TwoSwapExperiment>(),
+        ]
+        .concat()
+    }
+
     pub fn aco(&self) -> Result<AcoExperiment, ExperimentConfigError> {
         match self {
             AlgoConfig::Aco(aco) => Ok(*aco),
@@ -66,4 +90,99 @@ impl AlgoConfig {
             _ => Err(ExperimentConfigError::NotRandom),
         }
     }
+
+    pub fn exact(&self) -> Result<ExactExperiment, ExperimentConfigError> {
+        match self {
+            AlgoConfig::Exact(exact) => Ok(*exact),
+            _ => Err(ExperimentConfigError::NotExact),
+        }
+    }
+
+    /// Returns the seeded variant of the config, using seed if the config has none.
+    /// Algorithms without randomness are returned unchanged.
+    pub fn seeded(self, seed: u64) -> Self {
+        match self {
+            Self::UnseededMMAco(mmaco) => Self::MMAco(mmaco.with_seed(seed)),
+            Self::UnseededAcs(acs) => Self::Acs(acs.with_seed(seed)),
+            Self::UnseededAco(aco) => Self::Aco(aco.with_seed(seed)),
+            Self::UnseededRandom(random) => Self::Random(random.with_seed(seed)),
+            cfg => cfg,
+        }
+    }
+
+    /// Returns the name of the algorithm, as written into its logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MMAco(_) | Self::UnseededMMAco(_) => "mm_aco",
+            Self::Acs(_) | Self::UnseededAcs(_) => "acs",
+            Self::Aco(_) | Self::UnseededAco(_) => "aco",
+            Self::Random(_) | Self::UnseededRandom(_) => "random",
+            Self::Exact(_) => "exact",
+            Self::TwoSwap(_) => "two_swap",
+        }
+    }
+
+    /// Returns the configured iterations of the algorithm, None for TwoSwap, which iterates
+    /// until it finds no improvement.
+    pub fn iterations(&self) -> Option<usize> {
+        match self {
+            Self::MMAco(mmaco) => Some(mmaco.iterations),
+            Self::UnseededMMAco(mmaco) => Some(mmaco.iterations),
+            Self::Acs(acs) => Some(acs.iterations),
+            Self::UnseededAcs(acs) => Some(acs.iterations),
+            Self::Aco(aco) => Some(aco.iterations),
+            Self::UnseededAco(aco) => Some(aco.iterations),
+            Self::Random(random) => Some(random.iterations),
+            Self::UnseededRandom(random) => Some(random.iterations),
+            Self::Exact(_) => Some(1),
+            Self::TwoSwap(_) => None,
+        }
+    }
+
+    /// Returns the seed of the algorithm, None for algorithms without randomness and for
+    /// unseeded configs, which draw a new seed for every run.
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Self::MMAco(mmaco) => Some(mmaco.seed),
+            Self::Acs(acs) => Some(acs.seed),
+            Self::Aco(aco) => Some(aco.seed),
+            Self::Random(random) => Some(random.seed),
+            _ => None,
+        }
+    }
+
+    /// Renders the parameters as space separated key=value pairs, which identify the
+    /// parameter set in the logs of a run.
+    pub fn params_string(&self) -> String {
+        match serde_yaml::to_value(self) {
+            Ok(Value::Mapping(params)) => params
+                .iter()
+                .filter(|(key, _)| key.as_str() != Some("finished"))
+                .map(|(key, value)| format!("{}={}", render(key), render(value)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => String::new(),
+        }
+    }
+}
+
+fn render(value: &Value) -> String {
+    match value {
+        Value::Null => "~".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Sequence(values) => format!(
+            "[{}]",
+            values.iter().map(render).collect::<Vec<_>>().join(",")
+        ),
+        Value::Mapping(values) => format!(
+            "{{{}}}",
+            values
+                .iter()
+                .map(|(key, value)| format!("{}={}", render(key), render(value)))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
 }
@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct AcoExperiment {
     pub alpha: f64,
     pub beta: f64,
@@ -12,9 +12,28 @@ pub struct AcoExperiment {
     pub seed: u64,
     pub ant_count: usize,
     pub iterations: usize,
+    /// Moves after which an ant aborts its construction and returns to the goal point.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_limit: Option<usize>,
+    /// Amount of recently left nodes, which ants only move back to if there is no other option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabu_window: Option<usize>,
+    /// Amount of shortest paths back to the goal point, among which ants choose once they
+    /// stop collecting. Only the shortest path is taken by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_paths: Option<usize>,
+    /// Pheromone level of every edge before the first iteration, 1 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_pheromone: Option<f64>,
+    /// Lets the ants grow their routes at both ends, joined by a shortest path at the end.
+    /// Only supported with a single vehicle and without time windows, edge uncertainty, return
+    /// paths and tabu windows.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bidirectional: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededAcoExperiment {
     pub alpha: f64,
     pub beta: f64,
@@ -22,18 +41,33 @@ pub struct UnseededAcoExperiment {
     pub q_0: f64,
     pub ant_count: usize,
     pub iterations: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabu_window: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_paths: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_pheromone: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bidirectional: bool,
 }
 
-impl Fix<AcoExperiment> for UnseededAcoExperiment {
-    fn to_fixed(&self) -> AcoExperiment {
+impl FixWithSeed<AcoExperiment> for UnseededAcoExperiment {
+    fn with_seed(&self, seed: u64) -> AcoExperiment {
         AcoExperiment {
             alpha: self.alpha,
             beta: self.beta,
             rho: self.rho,
             q_0: self.q_0,
             ant_count: self.ant_count,
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             iterations: self.iterations,
+            step_limit: self.step_limit,
+            tabu_window: self.tabu_window,
+            return_paths: self.return_paths,
+            initial_pheromone: self.initial_pheromone,
+            bidirectional: self.bidirectional,
         }
     }
 }
@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct AcsExperiment {
 	pub alpha: f64,
 	pub beta: f64,
@@ -13,9 +13,20 @@ pub struct AcsExperiment {
 	pub seed: u64,
 	pub ant_count: usize,
 	pub iterations: usize,
+	/// Moves after which an ant aborts its construction and returns to the goal point.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub step_limit: Option<usize>,
+	/// Amount of recently left nodes, which ants only move back to if there is no other option.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tabu_window: Option<usize>,
+	/// Amount of shortest paths back to the goal point, among which ants choose once they
+	/// stop collecting. Only the shortest path is taken by default.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub return_paths: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededAcsExperiment {
 	pub alpha: f64,
 	pub beta: f64,
@@ -24,10 +35,16 @@ pub struct UnseededAcsExperiment {
 	pub t_0: f64,
 	pub ant_count: usize,
 	pub iterations: usize,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub step_limit: Option<usize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tabu_window: Option<usize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub return_paths: Option<usize>,
 }
 
-impl Fix<AcsExperiment> for UnseededAcsExperiment {
-	fn to_fixed(&self) -> AcsExperiment {
+impl FixWithSeed<AcsExperiment> for UnseededAcsExperiment {
+	fn with_seed(&self, seed: u64) -> AcsExperiment {
 		AcsExperiment {
 			alpha: self.alpha,
 			beta: self.beta,
@@ -35,8 +52,11 @@ impl Fix<AcsExperiment> for UnseededAcsExperiment {
 			q_0: self.q_0,
 			t_0: self.t_0,
 			ant_count: self.ant_count,
-			seed: (os_random_seed() >> 64) as u64,
+			seed,
 			iterations: self.iterations,
+			step_limit: self.step_limit,
+			tabu_window: self.tabu_window,
+			return_paths: self.return_paths,
 		}
 	}
 }
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExactExperiment {
+    /// Largest amount of reachable nodes with a reward, for which the search is started. Larger
+    /// instances are rejected, as the search time grows exponentially with them.
+    pub node_limit: usize,
+}
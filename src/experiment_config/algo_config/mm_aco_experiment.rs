@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct MMAcoExperiment {
     pub alpha: f64,
     pub beta: f64,
@@ -12,9 +12,41 @@ pub struct MMAcoExperiment {
     pub ant_count: usize,
     pub p_best: f64,
     pub iterations: usize,
+    /// Moves after which an ant aborts its construction and returns to the goal point.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_limit: Option<usize>,
+    /// Amount of recently left nodes, which ants only move back to if there is no other option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabu_window: Option<usize>,
+    /// Amount of shortest paths back to the goal point, among which ants choose once they
+    /// stop collecting. Only the shortest path is taken by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_paths: Option<usize>,
+    /// Average amount of options per ant move used for the lower pheromone bound, instead of
+    /// the average out-degree of the graph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_options: Option<f64>,
+    /// Pheromone level of every edge before the first iteration, 1 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_pheromone: Option<f64>,
+    /// Upper pheromone bound instead of the one derived from the best score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tau_max: Option<f64>,
+    /// Lower pheromone bound instead of the one derived from tau_max and p_best.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tau_min: Option<f64>,
+    /// Iterations before the pheromone levels are first clamped to the bounds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_up: Option<usize>,
+    /// Lets the ants grow their routes at both ends, joined by a shortest path at the end.
+    /// Only supported with a single vehicle and without time windows, edge uncertainty, return
+    /// paths and tabu windows.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bidirectional: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededMMAcoExperiment {
     pub alpha: f64,
     pub beta: f64,
@@ -22,18 +54,45 @@ pub struct UnseededMMAcoExperiment {
     pub ant_count: usize,
     pub p_best: f64,
     pub iterations: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tabu_window: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_paths: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_options: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_pheromone: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tau_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tau_min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_up: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bidirectional: bool,
 }
 
-impl Fix<MMAcoExperiment> for UnseededMMAcoExperiment {
-    fn to_fixed(&self) -> MMAcoExperiment {
+impl FixWithSeed<MMAcoExperiment> for UnseededMMAcoExperiment {
+    fn with_seed(&self, seed: u64) -> MMAcoExperiment {
         MMAcoExperiment {
             alpha: self.alpha,
             beta: self.beta,
             rho: self.rho,
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             ant_count: self.ant_count,
             p_best: self.p_best,
             iterations: self.iterations,
+            step_limit: self.step_limit,
+            tabu_window: self.tabu_window,
+            return_paths: self.return_paths,
+            avg_options: self.avg_options,
+            initial_pheromone: self.initial_pheromone,
+            tau_max: self.tau_max,
+            tau_min: self.tau_min,
+            warm_up: self.warm_up,
+            bidirectional: self.bidirectional,
         }
     }
 }
@@ -1,23 +1,24 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct RandomSearchExperiment {
     pub seed: u64,
     pub iterations: usize,
 }
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededRandomSearchExperiment {
     pub iterations: usize,
 }
 
-impl Fix<RandomSearchExperiment> for UnseededRandomSearchExperiment {
-    fn to_fixed(&self) -> RandomSearchExperiment {
+impl FixWithSeed<RandomSearchExperiment> for UnseededRandomSearchExperiment {
+    fn with_seed(&self, seed: u64) -> RandomSearchExperiment {
         RandomSearchExperiment {
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             iterations: self.iterations,
         }
     }
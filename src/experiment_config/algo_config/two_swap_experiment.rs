@@ -1,4 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
-pub struct TwoSwapExperiment {}
+#[serde(deny_unknown_fields)]
+pub struct TwoSwapExperiment {
+    /// Threads the candidates of each expansion are scored on, one if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+}
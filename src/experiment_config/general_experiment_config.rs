@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::experiment;
-use crate::experiment_config::{Algorithm, Fix};
-use crate::rng::os_random_seed;
+use crate::experiment_config::{struct_fields, Algorithm, Fix, FixWithSeed};
+use crate::graph::{Normalization, ValidationPolicy};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
@@ -14,31 +14,177 @@ pub enum GeneralExperimentConfig {
 }
 
 impl GeneralExperimentConfig {
+    /// Returns the fields of all variants.
+    pub fn fields() -> Vec<&'static str> {
+        [
+            struct_fields::<FullConfig>(),
+            struct_fields::<NoStatConfig>(),
+            struct_fields::<UnseededConfig>(),
+            struct_fields::<AggregationOnly>(),
+        ]
+        .concat()
+    }
+
     pub fn cfg(&self) -> FullConfig {
         match self {
-            Self::Full(cfg) => *cfg,
+            Self::Full(cfg) => cfg.clone(),
             Self::NoStat(cfg) => cfg.to_fixed(),
             Self::Unseeded(cfg) => cfg.to_fixed(),
             Self::AggregationOnly(cfg) => cfg.to_fixed(),
         }
     }
+
+    /// Returns the full config, using seed if the config has none.
+    pub fn seeded(self, seed: u64) -> Self {
+        Self::Full(match self {
+            Self::Full(cfg) => cfg,
+            Self::NoStat(cfg) => cfg.to_fixed(),
+            Self::Unseeded(cfg) => cfg.with_seed(seed),
+            Self::AggregationOnly(cfg) => cfg.with_seed(seed),
+        })
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct FullConfig {
     pub finished: bool,
     pub seed: u64,
+    /// Amount of iterations aggregated into a single log record.
     pub aggregation_rate: usize,
+    /// Writes completed log records every flush_every iterations, which bounds the data lost
+    /// when a run crashes. Without it, logs are written at the end of each run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_every: Option<usize>,
     pub max_time: f64,
+    /// Unit of max_time for pbf imports, whose edge weights are travel minutes.
+    /// Without it, max_time is used as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_unit: Option<BudgetUnit>,
+    /// Ends runs after this amount of heuristic evaluations instead of after the iterations of
+    /// the algorithm. Runs end with the iteration, in which the budget is used up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_evaluations: Option<usize>,
+    /// Optional (lat, lon) in degrees, the closest node to it is used as goal point.
+    /// Only valid for GeoPoint indexed graphs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_point: Option<(f64, f64)>,
+    /// Runs the experiment once for every node closest to one of these (lat, lon) pairs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_points: Option<Vec<(f64, f64)>>,
+    /// Runs the experiment from this many randomly sampled goal points, if no goal points are given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_count: Option<usize>,
+    /// Amount of routes in a solution, which makes this a team orienteering problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_count: Option<usize>,
+    /// Randomly assigned time windows, in which node values can be collected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<TimeWindowConfig>,
+    /// Randomly assigned durations, which are spent at nodes with a value when collecting it and
+    /// use up the time budget like travelling. Only supported by Aco, MMAco, Acs and TwoSwap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_times: Option<ServiceTimeConfig>,
+    /// Nodes, which have to be part of every feasible solution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mandatory_nodes: Option<MandatoryNodesConfig>,
+    /// Makes edge traversal times random, with the graph weights as means.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_uncertainty: Option<EdgeUncertaintyConfig>,
+    /// Penalties subtracted from the score for visiting nodes or edges more than once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revisit_penalty: Option<RevisitPenaltyConfig>,
+    /// What the algorithms optimize, by default the score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<ObjectiveConfig>,
+    /// Deposits pheromone along an initial route before the first iteration of Aco, MMAco or Acs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_start: Option<WarmStartConfig>,
+    /// Appends the best route of every aggregation_rate-th iteration to routes.jsonl next to
+    /// the logs, to follow how routes evolve.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub route_log: bool,
+    /// Shortens the final best route of every algorithm with 2-opt and Or-opt moves before it
+    /// is reported, keeping the rewarded and mandatory nodes it visits.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub post_process: bool,
+    /// Names of the heuristics to run the algorithm with, see metaheuristic::heuristics, or
+    /// compositions of them like `mixed = 0.5 * value + value_per_cost`, see heuristics::parse.
+    /// The logs of each run are named after its heuristic. Without it, the default heuristics
+    /// of the algorithm are run and logged as h1, h2, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heuristics: Option<Vec<String>>,
+    /// Checks the graph for self-loops, invalid weights and nodes without a route from the goal
+    /// point after generating or importing it, and either warns about or rejects problems.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_validation: Option<ValidationPolicy>,
+    /// Runs the algorithm on a coarser graph, in which chains of low reward nodes are
+    /// contracted, and refines its route on the full graph with TwoSwap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multilevel: Option<MultilevelConfig>,
+    /// Normalizes the edge weights and rewards of the graph before the algorithms run on it.
+    /// max_time and the service times stay in the original units and are scaled along with the
+    /// edge weights. Edge weights can't be normalized by z_score, which makes them negative.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConfig>,
 }
 
 experiment! {FullConfig}
 
+impl FullConfig {
+    /// Returns max_time in the unit of the edge weights.
+    pub fn budget(&self) -> f64 {
+        self.max_time_unit
+            .map_or(self.max_time, |unit| unit.to_minutes(self.max_time))
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct NoStatConfig {
     pub seed: u64,
     pub aggregation_rate: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_every: Option<usize>,
     pub max_time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_unit: Option<BudgetUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_evaluations: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_point: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_points: Option<Vec<(f64, f64)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<TimeWindowConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_times: Option<ServiceTimeConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mandatory_nodes: Option<MandatoryNodesConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_uncertainty: Option<EdgeUncertaintyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revisit_penalty: Option<RevisitPenaltyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<ObjectiveConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_start: Option<WarmStartConfig>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub route_log: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub post_process: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heuristics: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_validation: Option<ValidationPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multilevel: Option<MultilevelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConfig>,
 }
 
 impl Fix<FullConfig> for NoStatConfig {
@@ -47,42 +193,366 @@ impl Fix<FullConfig> for NoStatConfig {
             finished: false,
             seed: self.seed,
             aggregation_rate: self.aggregation_rate,
+            flush_every: self.flush_every,
             max_time: self.max_time,
+            max_time_unit: self.max_time_unit,
+            max_evaluations: self.max_evaluations,
+            goal_point: self.goal_point,
+            goal_points: self.goal_points.clone(),
+            start_count: self.start_count,
+            vehicle_count: self.vehicle_count,
+            time_windows: self.time_windows,
+            service_times: self.service_times,
+            mandatory_nodes: self.mandatory_nodes.clone(),
+            edge_uncertainty: self.edge_uncertainty,
+            revisit_penalty: self.revisit_penalty,
+            objective: self.objective,
+            warm_start: self.warm_start.clone(),
+            route_log: self.route_log,
+            post_process: self.post_process,
+            heuristics: self.heuristics.clone(),
+            graph_validation: self.graph_validation,
+            multilevel: self.multilevel,
+            normalization: self.normalization,
         }
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededConfig {
     pub finished: bool,
     pub aggregation_rate: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_every: Option<usize>,
     pub max_time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_unit: Option<BudgetUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_evaluations: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_point: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_points: Option<Vec<(f64, f64)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<TimeWindowConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_times: Option<ServiceTimeConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mandatory_nodes: Option<MandatoryNodesConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_uncertainty: Option<EdgeUncertaintyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revisit_penalty: Option<RevisitPenaltyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<ObjectiveConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_start: Option<WarmStartConfig>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub route_log: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub post_process: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heuristics: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_validation: Option<ValidationPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multilevel: Option<MultilevelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConfig>,
 }
 
-impl Fix<FullConfig> for UnseededConfig {
-    fn to_fixed(&self) -> FullConfig {
+impl FixWithSeed<FullConfig> for UnseededConfig {
+    fn with_seed(&self, seed: u64) -> FullConfig {
         FullConfig {
             finished: self.finished,
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             aggregation_rate: self.aggregation_rate,
+            flush_every: self.flush_every,
             max_time: self.max_time,
+            max_time_unit: self.max_time_unit,
+            max_evaluations: self.max_evaluations,
+            goal_point: self.goal_point,
+            goal_points: self.goal_points.clone(),
+            start_count: self.start_count,
+            vehicle_count: self.vehicle_count,
+            time_windows: self.time_windows,
+            service_times: self.service_times,
+            mandatory_nodes: self.mandatory_nodes.clone(),
+            edge_uncertainty: self.edge_uncertainty,
+            revisit_penalty: self.revisit_penalty,
+            objective: self.objective,
+            warm_start: self.warm_start.clone(),
+            route_log: self.route_log,
+            post_process: self.post_process,
+            heuristics: self.heuristics.clone(),
+            graph_validation: self.graph_validation,
+            multilevel: self.multilevel,
+            normalization: self.normalization,
         }
     }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct AggregationOnly {
     pub aggregation_rate: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_every: Option<usize>,
     pub max_time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_unit: Option<BudgetUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_evaluations: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_point: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_points: Option<Vec<(f64, f64)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<TimeWindowConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_times: Option<ServiceTimeConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mandatory_nodes: Option<MandatoryNodesConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_uncertainty: Option<EdgeUncertaintyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revisit_penalty: Option<RevisitPenaltyConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<ObjectiveConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warm_start: Option<WarmStartConfig>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub route_log: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub post_process: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heuristics: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_validation: Option<ValidationPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multilevel: Option<MultilevelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConfig>,
 }
 
-impl Fix<FullConfig> for AggregationOnly {
-    fn to_fixed(&self) -> FullConfig {
+impl FixWithSeed<FullConfig> for AggregationOnly {
+    fn with_seed(&self, seed: u64) -> FullConfig {
         FullConfig {
             finished: false,
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             aggregation_rate: self.aggregation_rate,
+            flush_every: self.flush_every,
             max_time: self.max_time,
+            max_time_unit: self.max_time_unit,
+            max_evaluations: self.max_evaluations,
+            goal_point: self.goal_point,
+            goal_points: self.goal_points.clone(),
+            start_count: self.start_count,
+            vehicle_count: self.vehicle_count,
+            time_windows: self.time_windows,
+            service_times: self.service_times,
+            mandatory_nodes: self.mandatory_nodes.clone(),
+            edge_uncertainty: self.edge_uncertainty,
+            revisit_penalty: self.revisit_penalty,
+            objective: self.objective,
+            warm_start: self.warm_start.clone(),
+            route_log: self.route_log,
+            post_process: self.post_process,
+            heuristics: self.heuristics.clone(),
+            graph_validation: self.graph_validation,
+            multilevel: self.multilevel,
+            normalization: self.normalization,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetUnit {
+    Minutes,
+    Hours,
+    /// Distance, which is travelled at an average speed of km_per_hour.
+    Km {
+        km_per_hour: f64,
+    },
+}
+
+impl BudgetUnit {
+    pub fn to_minutes(self, budget: f64) -> f64 {
+        match self {
+            Self::Minutes => budget,
+            Self::Hours => budget * 60.0,
+            Self::Km { km_per_hour } => budget / km_per_hour * 60.0,
         }
     }
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct TimeWindowConfig {
+    /// Probability of a node to get a time window.
+    pub probability: f64,
+    pub width_range: (f64, f64),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceTimeConfig {
+    /// Probability of a node with a value to get a service time, by default all get one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probability: Option<f64>,
+    pub range: (f64, f64),
+}
+
+/// Mandatory nodes given as ids for usize indexed graphs, as (lat, lon) points for GeoPoint
+/// indexed graphs or as fraction of randomly chosen nodes for any graph.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+#[serde(deny_unknown_fields)]
+pub enum MandatoryNodesConfig {
+    Ids { ids: Vec<usize> },
+    Points { points: Vec<(f64, f64)> },
+    Fraction { fraction: f64 },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeUncertaintyConfig {
+    /// Range from which the coefficient of variation (std_dev / mean) of each edge is drawn.
+    pub cv_range: (f64, f64),
+    /// Factor of the standard deviation, that is added to edge weights when checking the budget.
+    /// 0 means risk neutral.
+    #[serde(default)]
+    pub risk_aversion: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct RevisitPenaltyConfig {
+    #[serde(default)]
+    pub node: f64,
+    #[serde(default)]
+    pub edge: f64,
+}
+
+/// What the algorithms optimize, see metaheuristic::Objective.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveConfig {
+    MaximizeScore,
+    MinimizeLength,
+    Weighted {
+        score_weight: f64,
+        length_weight: f64,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WarmStartConfig {
+    /// Waypoints of the initial route, consecutive waypoints are connected by shortest paths.
+    /// Without a route, the solution of a TwoSwap run on the same instance is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<WarmStartRoute>,
+    /// Pheromone added to every edge of the route, relative to the initial pheromone level.
+    pub strength: f64,
+}
+
+/// Waypoints given as ids for usize indexed graphs or as (lat, lon) points for GeoPoint
+/// indexed graphs.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+#[serde(deny_unknown_fields)]
+pub enum WarmStartRoute {
+    Ids { ids: Vec<usize> },
+    Points { points: Vec<(f64, f64)> },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct MultilevelConfig {
+    /// Nodes with a reward of at most max_reward can be contracted, 0 by default.
+    #[serde(default)]
+    pub max_reward: f64,
+}
+
+/// Normalizations of the edge weights and rewards, either can be left out to keep them.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct NormalizationConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edges: Option<Normalization>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<Normalization>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budgets_are_converted_to_minutes() {
+        let cfg: UnseededConfig = serde_yaml::from_str(
+            "finished: false
+aggregation_rate: 1
+max_time: 1.5
+max_time_unit: hours",
+        )
+        .unwrap();
+        assert_eq!(cfg.with_seed(0).budget(), 90.0);
+
+        let cfg: UnseededConfig = serde_yaml::from_str(
+            "finished: false
+aggregation_rate: 1
+max_time: 10.0
+max_time_unit:
+  km:
+    km_per_hour: 40.0",
+        )
+        .unwrap();
+        assert_eq!(cfg.with_seed(0).budget(), 15.0);
+
+        let cfg: UnseededConfig =
+            serde_yaml::from_str("finished: false\naggregation_rate: 1\nmax_time: 10.0").unwrap();
+        assert_eq!(cfg.with_seed(0).budget(), 10.0);
+    }
+
+    #[test]
+    fn objectives_are_parsed() {
+        let cfg: UnseededConfig = serde_yaml::from_str(
+            "finished: false
+aggregation_rate: 1
+max_time: 10.0
+objective: minimize_length",
+        )
+        .unwrap();
+        assert_eq!(cfg.objective, Some(ObjectiveConfig::MinimizeLength));
+
+        let cfg: UnseededConfig = serde_yaml::from_str(
+            "finished: false
+aggregation_rate: 1
+max_time: 10.0
+objective:
+  weighted:
+    score_weight: 1.0
+    length_weight: 0.5",
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.with_seed(0).objective,
+            Some(ObjectiveConfig::Weighted {
+                score_weight: 1.0,
+                length_weight: 0.5
+            })
+        );
+    }
+}
@@ -1,14 +1,18 @@
 mod erdos_renyi_generation;
 mod file_load;
 mod grid_generation;
+mod instance_load;
+mod planted_route_generation;
 
 pub use erdos_renyi_generation::{ErdosRenyiGeneration, UnseededErdosRenyiGeneration};
 pub use file_load::{FileLoad, UnseededFileLoad};
 pub use grid_generation::{GridGeneration, UnseededGridGeneration};
+pub use instance_load::InstanceLoad;
+pub use planted_route_generation::{PlantedRouteGeneration, UnseededPlantedRouteGeneration};
 
 use serde::{Deserialize, Serialize};
 
-use super::{ExperimentConfigError, Fix};
+use super::{struct_fields, ExperimentConfigError, Fix, FixWithSeed};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
@@ -19,9 +23,28 @@ pub enum GraphCreationConfig {
     UnseededGrid(UnseededGridGeneration),
     ErdosRenyi(ErdosRenyiGeneration),
     UnseededErdosRenyi(UnseededErdosRenyiGeneration),
+    PlantedRoute(PlantedRouteGeneration),
+    UnseededPlantedRoute(UnseededPlantedRouteGeneration),
+    Instance(InstanceLoad),
 }
 
 impl GraphCreationConfig {
+    /// Returns the fields of all variants.
+    pub fn fields() -> Vec<&'static str> {
+        [
+            struct_fields::<FileLoad>(),
+            struct_fields::<UnseededFileLoad>(),
+            struct_fields::<GridGeneration>(),
+            struct_fields::<UnseededGridGeneration>(),
+            struct_fields::<ErdosRenyiGeneration>(),
+            struct_fields::<UnseededErdosRenyiGeneration>(),
+            struct_fields::<PlantedRouteGeneration>(),
+            struct_fields::<UnseededPlantedRouteGeneration>(),
+            struct_fields::<InstanceLoad>(),
+        ]
+        .concat()
+    }
+
     pub fn file(&self) -> Result<FileLoad, ExperimentConfigError> {
         match self {
             Self::File(file) => Ok(file.clone()),
@@ -45,4 +68,30 @@ impl GraphCreationConfig {
             _ => Err(ExperimentConfigError::NotErdosRenyi),
         }
     }
+
+    pub fn planted_route(&self) -> Result<PlantedRouteGeneration, ExperimentConfigError> {
+        match self {
+            Self::PlantedRoute(planted) => Ok(*planted),
+            Self::UnseededPlantedRoute(planted) => Ok(planted.to_fixed()),
+            _ => Err(ExperimentConfigError::NotPlantedRoute),
+        }
+    }
+
+    pub fn instance(&self) -> Result<InstanceLoad, ExperimentConfigError> {
+        match self {
+            Self::Instance(instance) => Ok(instance.clone()),
+            _ => Err(ExperimentConfigError::NotInstance),
+        }
+    }
+
+    /// Returns the seeded variant of the config, using seed if the config has none.
+    pub fn seeded(self, seed: u64) -> Self {
+        match self {
+            Self::UnseededFile(file) => Self::File(file.with_seed(seed)),
+            Self::UnseededGrid(grid) => Self::Grid(grid.with_seed(seed)),
+            Self::UnseededErdosRenyi(erdos_renyi) => Self::ErdosRenyi(erdos_renyi.with_seed(seed)),
+            Self::UnseededPlantedRoute(planted) => Self::PlantedRoute(planted.with_seed(seed)),
+            cfg => cfg,
+        }
+    }
 }
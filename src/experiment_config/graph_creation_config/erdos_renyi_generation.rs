@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct ErdosRenyiGeneration {
     pub seed: u64,
     pub size: u64,
@@ -11,26 +11,43 @@ pub struct ErdosRenyiGeneration {
     pub ew_range: (f64, f64),
     pub node_weight_probability: f64,
     pub connection_probability: f64,
+    /// Least amount of outgoing edges of every node, missing edges lead to random nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_out_degree: Option<usize>,
+    /// Adds the fewest edges needed to make the graph strongly connected, so the goal point
+    /// is reachable from all nodes regardless which node it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair_connectivity: Option<bool>,
 }
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededErdosRenyiGeneration {
     pub size: u64,
     pub nw_range: (f64, f64),
     pub ew_range: (f64, f64),
     pub node_weight_probability: f64,
     pub connection_probability: f64,
+    /// Least amount of outgoing edges of every node, missing edges lead to random nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_out_degree: Option<usize>,
+    /// Adds the fewest edges needed to make the graph strongly connected, so the goal point
+    /// is reachable from all nodes regardless which node it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair_connectivity: Option<bool>,
 }
 
-impl Fix<ErdosRenyiGeneration> for UnseededErdosRenyiGeneration {
-    fn to_fixed(&self) -> ErdosRenyiGeneration {
+impl FixWithSeed<ErdosRenyiGeneration> for UnseededErdosRenyiGeneration {
+    fn with_seed(&self, seed: u64) -> ErdosRenyiGeneration {
         ErdosRenyiGeneration {
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             size: self.size,
             nw_range: self.nw_range,
             ew_range: self.ew_range,
             node_weight_probability: self.node_weight_probability,
             connection_probability: self.connection_probability,
+            min_out_degree: self.min_out_degree,
+            repair_connectivity: self.repair_connectivity,
         }
     }
 }
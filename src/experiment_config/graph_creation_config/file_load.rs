@@ -1,30 +1,44 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
+use crate::geo::Region;
+use crate::graph::import::Cleanup;
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct FileLoad {
     pub filename: String,
     pub seed: u64,
     pub nw_range: (f64, f64),
     pub node_weight_probability: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+    /// Cleanup passes run on the imported graph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<Cleanup>,
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededFileLoad {
     pub filename: String,
     pub nw_range: (f64, f64),
     pub node_weight_probability: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<Cleanup>,
 }
 
-impl Fix<FileLoad> for UnseededFileLoad {
-    fn to_fixed(&self) -> FileLoad {
+impl FixWithSeed<FileLoad> for UnseededFileLoad {
+    fn with_seed(&self, seed: u64) -> FileLoad {
         FileLoad {
             filename: self.filename.clone(),
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             nw_range: self.nw_range,
             node_weight_probability: self.node_weight_probability,
+            region: self.region.clone(),
+            cleanup: self.cleanup,
         }
     }
 }
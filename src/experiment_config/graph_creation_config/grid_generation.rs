@@ -1,33 +1,57 @@
 use serde::{Deserialize, Serialize};
 
-use crate::experiment_config::Fix;
-use crate::rng::os_random_seed;
+use crate::experiment_config::FixWithSeed;
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct GridGeneration {
     pub seed: u64,
     pub size: (u64, u64),
     pub nw_range: (f64, f64),
     pub ew_range: (f64, f64),
     pub node_weight_probability: f64,
+    /// Range of the weights of edges between horizontal neighbors, defaults to ew_range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub horizontal_ew_range: Option<(f64, f64)>,
+    /// Range of the weights of edges between vertical neighbors, defaults to ew_range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertical_ew_range: Option<(f64, f64)>,
+    /// Factor for the weights of edges leading right or down, to emulate one-way-heavy
+    /// street grids. Edges leading left or up keep their weight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asymmetry: Option<f64>,
 }
 
 #[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededGridGeneration {
     pub size: (u64, u64),
     pub nw_range: (f64, f64),
     pub ew_range: (f64, f64),
     pub node_weight_probability: f64,
+    /// Range of the weights of edges between horizontal neighbors, defaults to ew_range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub horizontal_ew_range: Option<(f64, f64)>,
+    /// Range of the weights of edges between vertical neighbors, defaults to ew_range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertical_ew_range: Option<(f64, f64)>,
+    /// Factor for the weights of edges leading right or down, to emulate one-way-heavy
+    /// street grids. Edges leading left or up keep their weight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asymmetry: Option<f64>,
 }
 
-impl Fix<GridGeneration> for UnseededGridGeneration {
-    fn to_fixed(&self) -> GridGeneration {
+impl FixWithSeed<GridGeneration> for UnseededGridGeneration {
+    fn with_seed(&self, seed: u64) -> GridGeneration {
         GridGeneration {
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
             size: self.size,
             nw_range: self.nw_range,
             ew_range: self.ew_range,
             node_weight_probability: self.node_weight_probability,
+            horizontal_ew_range: self.horizontal_ew_range,
+            vertical_ew_range: self.vertical_ew_range,
+            asymmetry: self.asymmetry,
         }
     }
 }
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Loads a problem instance written by the export command. Its graph is usize indexed.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct InstanceLoad {
+    /// Directory of the instance.
+    pub instance: String,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::experiment_config::FixWithSeed;
+
+/// Graph around a planted route with known optimal score, see graph::generate::PlantedRoute.
+/// The goal point is node 0, the route splits the budget of the experiment.
+#[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PlantedRouteGeneration {
+    pub seed: u64,
+    pub size: u64,
+    /// Amount of nodes on the planted route, including the goal point.
+    pub route_size: u64,
+    pub nw_range: (f64, f64),
+    pub ew_range: (f64, f64),
+    pub noise_probability: f64,
+}
+
+#[derive(Copy, Clone, Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UnseededPlantedRouteGeneration {
+    pub size: u64,
+    pub route_size: u64,
+    pub nw_range: (f64, f64),
+    pub ew_range: (f64, f64),
+    pub noise_probability: f64,
+}
+
+impl FixWithSeed<PlantedRouteGeneration> for UnseededPlantedRouteGeneration {
+    fn with_seed(&self, seed: u64) -> PlantedRouteGeneration {
+        PlantedRouteGeneration {
+            seed,
+            size: self.size,
+            route_size: self.route_size,
+            nw_range: self.nw_range,
+            ew_range: self.ew_range,
+            noise_probability: self.noise_probability,
+        }
+    }
+}
@@ -1,7 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::Fix;
-use crate::rng::os_random_seed;
+use super::{struct_fields, Fix, FixWithSeed};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
@@ -11,42 +10,117 @@ pub enum GraphDynamicsConfig {
 }
 
 impl GraphDynamicsConfig {
+    /// Returns the fields of all variants.
+    pub fn fields() -> Vec<&'static str> {
+        [
+            struct_fields::<FullConfig>(),
+            struct_fields::<UnseededConfig>(),
+        ]
+        .concat()
+    }
+
     pub fn cfg(&self) -> FullConfig {
         match self {
             Self::Full(cfg) => *cfg,
             Self::Unseeded(cfg) => cfg.to_fixed(),
         }
     }
+
+    /// Returns the full config, using seed if the config has none.
+    pub fn seeded(self, seed: u64) -> Self {
+        match self {
+            Self::Unseeded(cfg) => Self::Full(cfg.with_seed(seed)),
+            cfg => cfg,
+        }
+    }
 }
 
+/// Changes of node and edge weights after a run, each followed by a recovery phase of the
+/// algorithm.
 #[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
 pub struct FullConfig {
     pub seed: u64,
+    /// Amount of changes.
+    #[serde(default = "default_changes")]
+    pub changes: usize,
+    /// Iterations the algorithm recovers for after each change.
     pub change_after_i: u64,
+    /// Recovers for this many milliseconds after each change instead of change_after_i
+    /// iterations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_time_ms: Option<u64>,
     pub edge_change_probability: f64,
     pub node_change_probability: f64,
     pub edge_change_intensity: f64,
     pub node_change_intensity: f64,
+    /// Lets collected rewards regenerate over the recovery iterations instead of toggling them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regeneration: Option<RegenerationConfig>,
+    /// Congests the edges of a random region instead of changing edges independently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub congestion: Option<CongestionConfig>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UnseededConfig {
+    #[serde(default = "default_changes")]
+    pub changes: usize,
     pub change_after_i: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_time_ms: Option<u64>,
     pub edge_change_probability: f64,
     pub node_change_probability: f64,
     pub edge_change_intensity: f64,
     pub node_change_intensity: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regeneration: Option<RegenerationConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub congestion: Option<CongestionConfig>,
 }
 
-impl Fix<FullConfig> for UnseededConfig {
-    fn to_fixed(&self) -> FullConfig {
+/// Continuous reward model for patrolling-like objectives.
+/// Each change collects the rewards of the changed nodes, afterwards every iteration gives each
+/// node rate times its original reward back, up to the original, and takes decay times its
+/// current reward away.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RegenerationConfig {
+    pub rate: f64,
+    #[serde(default)]
+    pub decay: f64,
+}
+
+/// Traffic-like edge dynamics. Each change picks a random center node and multiplies the
+/// original weight of all edges between nodes within radius of it with multiplier, for duration
+/// recovery iterations.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CongestionConfig {
+    /// Distance in km for graphs of GeoPoints, edges to follow from the center otherwise.
+    pub radius: f64,
+    pub multiplier: f64,
+    pub duration: u64,
+}
+
+impl FixWithSeed<FullConfig> for UnseededConfig {
+    fn with_seed(&self, seed: u64) -> FullConfig {
         FullConfig {
-            seed: (os_random_seed() >> 64) as u64,
+            seed,
+            changes: self.changes,
             change_after_i: self.change_after_i,
+            recovery_time_ms: self.recovery_time_ms,
             edge_change_probability: self.edge_change_probability,
             node_change_probability: self.node_change_probability,
             edge_change_intensity: self.edge_change_intensity,
             node_change_intensity: self.node_change_intensity,
+            regeneration: self.regeneration,
+            congestion: self.congestion,
         }
     }
 }
+
+fn default_changes() -> usize {
+    1
+}
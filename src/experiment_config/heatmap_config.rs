@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Pheromone heatmaps of Aco, MMAco and Acs, drawn as svg next to the experiment logs to follow
+/// how the trails converge.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HeatmapConfig {
+    /// Draws a heatmap after every this many iterations.
+    pub every: usize,
+    #[serde(default = "default_size")]
+    pub width: usize,
+    #[serde(default = "default_size")]
+    pub height: usize,
+}
+
+fn default_size() -> usize {
+    800
+}
+
+impl HeatmapConfig {
+    /// Returns whether a heatmap is drawn after the iteration with index iteration.
+    pub fn due(&self, iteration: usize) -> bool {
+        self.every > 0 && (iteration + 1).is_multiple_of(self.every)
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{os_random_seed, RngKind};
+
+/// Random number generation of the algorithms.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RngConfig {
+    #[serde(default)]
+    pub algorithm: RngKind,
+    /// Seeds the algorithms from the randomness of the operating system instead of their
+    /// configured seed. The rng states in the replay logs still allow to repeat iterations.
+    #[serde(default)]
+    pub os_seeded: bool,
+}
+
+impl RngConfig {
+    /// Draws the seed of a run from the operating system, if it is os seeded. Otherwise the
+    /// algorithms use their configured seed and None is returned.
+    pub fn draw_seed(&self) -> Option<u128> {
+        self.os_seeded.then(os_random_seed)
+    }
+}
@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Number, Value};
+use std::collections::BTreeMap;
+
+use super::{ExperimentConfig, ExperimentConfigError};
+use crate::rng::rng64;
+
+/// Declares values for algorithm parameters, which are combined into multiple experiments.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SweepConfig {
+    /// Values for each swept parameter of the algorithm section, e.g. alpha or ant_count.
+    pub params: BTreeMap<String, SweepValues>,
+    /// Runs this many randomly drawn parameter sets instead of the full cross product.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<usize>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Values of a swept parameter, either listed explicitly or as range.
+/// In a grid sweep a range is split into steps evenly spaced values including min and max,
+/// random sweeps draw uniformly from [min, max].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+#[serde(deny_unknown_fields)]
+pub enum SweepValues {
+    List(Vec<f64>),
+    Range { min: f64, max: f64, steps: usize },
+}
+
+/// One concrete experiment of a sweep together with the parameter values it was created from.
+pub struct SweepPoint {
+    pub params: BTreeMap<String, f64>,
+    pub config: ExperimentConfig,
+}
+
+impl SweepValues {
+    fn grid_values(&self) -> Vec<f64> {
+        match self {
+            Self::List(values) => values.clone(),
+            Self::Range { min, max, steps } => match steps {
+                0 => Vec::new(),
+                1 => vec![*min],
+                _ => (0..*steps)
+                    .map(|i| min + (max - min) * i as f64 / (steps - 1) as f64)
+                    .collect(),
+            },
+        }
+    }
+
+    /// Returns the value at position rand in [0, 1) of the list or range, None for empty lists.
+    pub fn sample(&self, rand: f64) -> Option<f64> {
+        match self {
+            Self::List(values) if values.is_empty() => None,
+            Self::List(values) => Some(values[(rand * values.len() as f64) as usize]),
+            Self::Range { min, max, .. } => Some(min + (max - min) * rand),
+        }
+    }
+}
+
+impl SweepConfig {
+    /// Returns all parameter sets of this sweep, either the cross product of all values
+    /// or samples many randomly drawn ones.
+    pub fn parameter_sets(&self) -> Vec<BTreeMap<String, f64>> {
+        if let Some(samples) = self.samples {
+            let mut rng = rng64(self.seed as u128);
+            return (0..samples)
+                .filter_map(|_| {
+                    self.params
+                        .iter()
+                        .map(|(name, values)| {
+                            values.sample(rng.rand_float()).map(|v| (name.clone(), v))
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        let mut sets = vec![BTreeMap::new()];
+        for (name, values) in self.params.iter() {
+            let values = values.grid_values();
+            sets = sets
+                .into_iter()
+                .flat_map(|set| {
+                    values.iter().map(move |&value| {
+                        let mut set = set.clone();
+                        set.insert(name.clone(), value);
+                        set
+                    })
+                })
+                .collect();
+        }
+
+        sets
+    }
+
+    /// Creates one experiment config per parameter set, with the swept parameters of the
+    /// algorithm section replaced.
+    pub fn expand(
+        &self,
+        config: &ExperimentConfig,
+    ) -> Result<Vec<SweepPoint>, ExperimentConfigError> {
+        self.parameter_sets()
+            .into_iter()
+            .map(|params| {
+                let config = replace_params(
+                    config,
+                    params
+                        .iter()
+                        .map(|(name, &value)| ("algorithm", name.as_str(), value)),
+                )?;
+                Ok(SweepPoint { params, config })
+            })
+            .collect()
+    }
+}
+
+/// Returns a copy of config with the given (section, name, value) parameters replaced.
+/// Integer parameters are rounded. Sweep and tuning sections are removed from the copy,
+/// so it describes a single experiment.
+pub fn replace_params<'a>(
+    config: &ExperimentConfig,
+    params: impl Iterator<Item = (&'a str, &'a str, f64)>,
+) -> Result<ExperimentConfig, ExperimentConfigError> {
+    let mut value = serde_yaml::to_value(config)
+        .map_err(|e| ExperimentConfigError::InvalidAlgorithmConfig(e.to_string()))?;
+    let root = value.as_mapping_mut().unwrap();
+    root.remove(&Value::from("sweep"));
+    root.remove(&Value::from("tuning"));
+    for (section, name, param) in params {
+        let mapping = root
+            .get_mut(&Value::from(section))
+            .and_then(Value::as_mapping_mut)
+            .ok_or_else(|| {
+                ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                    "{} is not a section of the config.",
+                    section
+                ))
+            })?;
+        set_param(mapping, section, name, param)?;
+    }
+
+    serde_yaml::from_value(value)
+        .map_err(|e| ExperimentConfigError::InvalidAlgorithmConfig(e.to_string()))
+}
+
+/// Replaces the parameter name of section, integer parameters are rounded.
+fn set_param(
+    section: &mut Mapping,
+    section_name: &str,
+    name: &str,
+    param: f64,
+) -> Result<(), ExperimentConfigError> {
+    let key = Value::from(name);
+    let new_value = match section.get(&key) {
+        Some(Value::Number(old)) if old.is_f64() => Value::Number(Number::from(param)),
+        Some(Value::Number(_)) => Value::Number(Number::from(param.round().max(0.0) as u64)),
+        _ => {
+            return Err(ExperimentConfigError::InvalidAlgorithmConfig(format!(
+                "{} is not a numeric parameter of {}.",
+                name, section_name
+            )))
+        }
+    };
+    section.insert(key, new_value);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ExperimentConfig {
+        serde_yaml::from_str(
+            "experiment:
+  finished: false
+  seed: 1
+  aggregation_rate: 1
+  max_time: 100.0
+algorithm:
+  alpha: 1.0
+  beta: 1.0
+  rho: 0.1
+  q_0: 0.5
+  seed: 2
+  ant_count: 10
+  iterations: 100
+graph_creation:
+  seed: 3
+  size: 10
+  connection_probability: 0.5
+  nw_range: [0.0, 1.0]
+  ew_range: [1.0, 2.0]
+  node_weight_probability: 0.5",
+        )
+        .unwrap()
+    }
+
+    fn sweep(samples: Option<usize>) -> SweepConfig {
+        let mut params = BTreeMap::new();
+        params.insert("alpha".to_string(), SweepValues::List(vec![0.5, 2.0]));
+        params.insert(
+            "ant_count".to_string(),
+            SweepValues::Range {
+                min: 5.0,
+                max: 15.0,
+                steps: 3,
+            },
+        );
+        SweepConfig {
+            params,
+            samples,
+            seed: 4,
+        }
+    }
+
+    #[test]
+    fn grid_is_cross_product() {
+        let sets = sweep(None).parameter_sets();
+
+        assert_eq!(sets.len(), 6);
+        assert_eq!(sets[0]["alpha"], 0.5);
+        assert_eq!(sets[0]["ant_count"], 5.0);
+        assert_eq!(sets[5]["alpha"], 2.0);
+        assert_eq!(sets[5]["ant_count"], 15.0);
+    }
+
+    #[test]
+    fn random_samples_stay_within_values() {
+        let sets = sweep(Some(20)).parameter_sets();
+
+        assert_eq!(sets.len(), 20);
+        for set in sets {
+            assert!(set["alpha"] == 0.5 || set["alpha"] == 2.0);
+            assert!(set["ant_count"] >= 5.0 && set["ant_count"] <= 15.0);
+        }
+    }
+
+    #[test]
+    fn expand_sets_algorithm_params() {
+        let points = sweep(None).expand(&config()).unwrap();
+        let aco = points[1].config.algorithm.aco().unwrap();
+
+        assert_eq!(points.len(), 6);
+        assert_eq!(aco.alpha, 0.5);
+        assert_eq!(aco.ant_count, 10);
+        assert_eq!(aco.beta, 1.0);
+        assert!(points[1].config.sweep.is_none());
+    }
+
+    #[test]
+    fn expand_rejects_unknown_params() {
+        let mut sweep = sweep(None);
+        sweep
+            .params
+            .insert("gamma".to_string(), SweepValues::List(vec![1.0]));
+
+        assert!(sweep.expand(&config()).is_err());
+    }
+}
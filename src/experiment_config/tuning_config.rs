@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::sweep_config::SweepValues;
+
+/// Adaptive tuning of algorithm parameters by racing candidate configurations.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TuningConfig {
+    /// Parameter space of the algorithm section, declared like in sweeps.
+    pub params: BTreeMap<String, SweepValues>,
+    /// Name of the heuristic, whose average score is maximized.
+    pub heuristic: String,
+    /// Amount of configurations raced against each other in every round.
+    pub candidates: usize,
+    pub rounds: usize,
+    /// Amount of instances every configuration is evaluated on. Each instance uses
+    /// different seeds for the graph creation, the experiment and the algorithm.
+    pub instances: usize,
+    /// Amount of instances, which are evaluated before the first configurations are pruned.
+    #[serde(default = "default_first_test")]
+    pub first_test: usize,
+    /// Fraction of the configurations, which survives each step of a race.
+    #[serde(default = "default_survival_rate")]
+    pub survival_rate: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_first_test() -> usize {
+    2
+}
+
+fn default_survival_rate() -> f64 {
+    0.5
+}
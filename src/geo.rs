@@ -1,5 +1,9 @@
 mod geo_point;
 mod geodistance;
+mod region;
+mod spatial_index;
 
 pub use geo_point::GeoPoint;
 pub use geodistance::geodistance_haversine;
+pub use region::{BoundingBox, Polygon, Region};
+pub use spatial_index::SpatialIndex;
@@ -0,0 +1,130 @@
+use super::GeoPoint;
+use serde::{Deserialize, Serialize};
+
+/// A geographic area given either as bounding box or as simple polygon in degrees.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Region {
+    BoundingBox(BoundingBox),
+    Polygon(Polygon),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+/// A simple (non self intersecting) polygon given by its corners as (lat, lon) pairs.
+/// The polygon is closed implicitly, so the first corner does not need to be repeated.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Polygon {
+    pub polygon: Vec<(f64, f64)>,
+}
+
+impl Region {
+    /// Returns true if the point lies inside the region, or false otherwise.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        match self {
+            Self::BoundingBox(bbox) => bbox.contains(point),
+            Self::Polygon(polygon) => polygon.contains(point),
+        }
+    }
+}
+
+impl BoundingBox {
+    /// Returns true if the point lies inside the box or on its border, or false otherwise.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        let (lat, lon) = (point.lat(), point.lon());
+        lat >= self.min_lat && lat <= self.max_lat && lon >= self.min_lon && lon <= self.max_lon
+    }
+}
+
+impl Polygon {
+    /// Returns true if the point lies inside the polygon, or false otherwise.
+    /// Done using the even-odd rule from https://en.wikipedia.org/wiki/Point_in_polygon.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        let (lat, lon) = (point.lat(), point.lon());
+        let corners = &self.polygon;
+        if corners.len() < 3 {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut j = corners.len() - 1;
+        for i in 0..corners.len() {
+            let (lat_i, lon_i) = corners[i];
+            let (lat_j, lon_j) = corners[j];
+            // count the crossings of a ray going from the point in direction of increasing lon
+            if (lat_i > lat) != (lat_j > lat)
+                && lon < (lon_j - lon_i) * (lat - lat_i) / (lat_j - lat_i) + lon_i
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+
+        inside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox() -> Region {
+        Region::BoundingBox(BoundingBox {
+            min_lat: 50.0,
+            min_lon: 8.0,
+            max_lat: 51.0,
+            max_lon: 9.0,
+        })
+    }
+
+    fn triangle() -> Region {
+        Region::Polygon(Polygon {
+            polygon: vec![(50.0, 8.0), (50.0, 9.0), (51.0, 8.0)],
+        })
+    }
+
+    #[test]
+    fn bounding_box_contains_works() {
+        let region = bbox();
+
+        assert!(region.contains(&GeoPoint::from_degrees(50.5, 8.5)));
+        assert!(!region.contains(&GeoPoint::from_degrees(51.5, 8.5)));
+        assert!(!region.contains(&GeoPoint::from_degrees(50.5, 7.5)));
+    }
+
+    #[test]
+    fn polygon_contains_works() {
+        let region = triangle();
+
+        assert!(region.contains(&GeoPoint::from_degrees(50.2, 8.2)));
+        assert!(!region.contains(&GeoPoint::from_degrees(50.8, 8.8)));
+        assert!(!region.contains(&GeoPoint::from_degrees(49.0, 8.5)));
+    }
+
+    #[test]
+    fn degenerate_polygon_contains_nothing() {
+        let region = Region::Polygon(Polygon {
+            polygon: vec![(50.0, 8.0), (51.0, 9.0)],
+        });
+
+        assert!(!region.contains(&GeoPoint::from_degrees(50.5, 8.5)));
+    }
+
+    #[test]
+    fn region_deserializes_untagged() {
+        let bbox: Region =
+            serde_yaml::from_str("min_lat: 1.0\nmin_lon: 2.0\nmax_lat: 3.0\nmax_lon: 4.0").unwrap();
+        let polygon: Region =
+            serde_yaml::from_str("polygon:\n  - [1.0, 2.0]\n  - [3.0, 4.0]\n  - [5.0, 2.0]")
+                .unwrap();
+
+        assert!(matches!(bbox, Region::BoundingBox(_)));
+        assert!(matches!(polygon, Region::Polygon(_)));
+    }
+}
@@ -0,0 +1,251 @@
+use super::{geodistance_haversine, GeoPoint};
+use crate::graph::GeoGraph;
+
+use std::collections::HashMap;
+
+/// Average length of one degree of latitude in km, assuming an earth radius of 6371km.
+const KM_PER_DEGREE: f64 = 6371.0 * std::f64::consts::PI / 180.0;
+
+/// A uniform grid over lat/lon space, which buckets points into square cells.
+/// This allows nearest neighbor and radius queries without checking every point.
+pub struct SpatialIndex {
+    cell_size: f64,
+    cells: HashMap<(i32, i32), Vec<GeoPoint>>,
+    min_cell: (i32, i32),
+    max_cell: (i32, i32),
+    // cosine of the largest absolute latitude of the points, used to bound longitudinal distances
+    min_cos: f64,
+}
+
+impl SpatialIndex {
+    /// Creates a new index over the given points, using cells with a side length of cell_size degrees.
+    pub fn new(points: impl Iterator<Item = GeoPoint>, cell_size: f64) -> Self {
+        let mut index = SpatialIndex {
+            cell_size,
+            cells: HashMap::new(),
+            min_cell: (i32::MAX, i32::MAX),
+            max_cell: (i32::MIN, i32::MIN),
+            min_cos: 1.0,
+        };
+
+        let mut max_abs_lat: f64 = 0.0;
+        for point in points {
+            let cell = index.cell_of(&point);
+            index.min_cell = (index.min_cell.0.min(cell.0), index.min_cell.1.min(cell.1));
+            index.max_cell = (index.max_cell.0.max(cell.0), index.max_cell.1.max(cell.1));
+            max_abs_lat = max_abs_lat.max(point.lat().abs());
+            index.cells.entry(cell).or_default().push(point);
+        }
+        index.min_cos = max_abs_lat.to_radians().cos().max(f64::EPSILON);
+
+        index
+    }
+
+    /// Creates a new index over all node ids of the given graph.
+    /// The cell size is chosen, so that on average there is about one node per cell.
+    pub fn from_graph<Nw, Ew>(
+        graph: &dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+    ) -> Self {
+        let (min_lat, min_lon, max_lat, max_lon) =
+            graph
+                .iter_node_ids()
+                .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |acc, point| {
+                    (
+                        acc.0.min(point.lat()),
+                        acc.1.min(point.lon()),
+                        acc.2.max(point.lat()),
+                        acc.3.max(point.lon()),
+                    )
+                });
+        let area = (max_lat - min_lat) * (max_lon - min_lon);
+        let cell_size = if graph.order() > 1 && area > 0.0 {
+            (area / graph.order() as f64).sqrt()
+        } else {
+            0.01
+        };
+
+        Self::new(graph.iter_node_ids(), cell_size)
+    }
+
+    /// Returns the amount of points in this index.
+    pub fn len(&self) -> usize {
+        self.cells.values().map(|points| points.len()).sum()
+    }
+
+    /// Returns true if there are no points in this index, or false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the smallest cosine of the latitudes of point and of the indexed points. Distances
+    /// between them shrink with it, so it bounds the longitudinal distance of cells from below.
+    fn min_cos(&self, point: &GeoPoint) -> f64 {
+        self.min_cos
+            .min(point.lat().abs().to_radians().cos())
+            .max(f64::EPSILON)
+    }
+
+    fn cell_of(&self, point: &GeoPoint) -> (i32, i32) {
+        (
+            (point.lat() / self.cell_size).floor() as i32,
+            (point.lon() / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Returns all points in cells with the given chebyshev distance to the center cell.
+    fn ring(&self, center: (i32, i32), radius: i32) -> impl Iterator<Item = &GeoPoint> + '_ {
+        let lats = center.0 - radius..=center.0 + radius;
+        lats.flat_map(move |lat| {
+            let lons = center.1 - radius..=center.1 + radius;
+            lons.filter(move |lon| {
+                (lat - center.0).abs() == radius || (lon - center.1).abs() == radius
+            })
+            .map(move |lon| (lat, lon))
+        })
+        .filter_map(move |cell| self.cells.get(&cell))
+        .flatten()
+    }
+
+    /// Returns the point closest to the given one together with its distance in km.
+    /// Returns None if the index is empty.
+    pub fn nearest_node(&self, point: &GeoPoint) -> Option<(GeoPoint, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let center = self.cell_of(point);
+        // after this many rings all cells of the index have been checked
+        let max_radius = [
+            center.0 - self.min_cell.0,
+            self.max_cell.0 - center.0,
+            center.1 - self.min_cell.1,
+            self.max_cell.1 - center.1,
+        ]
+        .iter()
+        .copied()
+        .max()
+        .unwrap()
+        .max(0);
+
+        let min_cos = self.min_cos(point);
+        let mut best: Option<(GeoPoint, f64)> = None;
+        for radius in 0..=max_radius {
+            // every point in this ring is at least radius - 1 full cells away
+            let lower_bound = (radius - 1).max(0) as f64 * self.cell_size * KM_PER_DEGREE * min_cos;
+            if let Some((_, distance)) = best {
+                if distance <= lower_bound {
+                    break;
+                }
+            }
+
+            for candidate in self.ring(center, radius) {
+                let distance = geodistance_haversine(*point, *candidate);
+                if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                    best = Some((*candidate, distance));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Returns all points within radius km of the given point.
+    pub fn nodes_within(&self, point: &GeoPoint, radius: f64) -> Vec<GeoPoint> {
+        let center = self.cell_of(point);
+        let lat_cells = (radius / (self.cell_size * KM_PER_DEGREE)).ceil() as i32;
+        let lon_cells =
+            (radius / (self.cell_size * KM_PER_DEGREE * self.min_cos(point))).ceil() as i32;
+
+        let mut found = Vec::new();
+        for lat in center.0 - lat_cells..=center.0 + lat_cells {
+            for lon in center.1 - lon_cells..=center.1 + lon_cells {
+                if let Some(points) = self.cells.get(&(lat, lon)) {
+                    found.extend(
+                        points.iter().filter(|candidate| {
+                            geodistance_haversine(*point, **candidate) <= radius
+                        }),
+                    );
+                }
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points() -> Vec<GeoPoint> {
+        vec![
+            GeoPoint::from_degrees(50.10, 8.60),
+            GeoPoint::from_degrees(50.11, 8.68),
+            GeoPoint::from_degrees(50.12, 8.70),
+            GeoPoint::from_degrees(50.20, 8.90),
+            GeoPoint::from_degrees(49.90, 8.40),
+        ]
+    }
+
+    #[test]
+    fn nearest_node_works() {
+        let index = SpatialIndex::new(points().into_iter(), 0.05);
+        let (nearest, _) = index
+            .nearest_node(&GeoPoint::from_degrees(50.115, 8.69))
+            .unwrap();
+
+        assert_eq!(nearest, GeoPoint::from_degrees(50.12, 8.70));
+    }
+
+    #[test]
+    fn nearest_node_matches_linear_search() {
+        let points = points();
+        let index = SpatialIndex::new(points.clone().into_iter(), 0.01);
+        let query = GeoPoint::from_degrees(50.5, 9.5);
+        let linear = points
+            .iter()
+            .min_by(|a, b| {
+                geodistance_haversine(query, **a)
+                    .partial_cmp(&geodistance_haversine(query, **b))
+                    .unwrap()
+            })
+            .unwrap();
+
+        assert_eq!(index.nearest_node(&query).unwrap().0, *linear);
+    }
+
+    #[test]
+    fn nearest_node_of_empty_index_is_none() {
+        let index = SpatialIndex::new(Vec::new().into_iter(), 0.01);
+
+        assert!(index
+            .nearest_node(&GeoPoint::from_degrees(50.0, 8.0))
+            .is_none());
+    }
+
+    #[test]
+    fn nodes_within_cover_queries_closer_to_the_poles() {
+        // the query is further north than every point, so their latitudes understate how many
+        // longitudinal cells the radius spans
+        let far = GeoPoint::from_degrees(88.0, 179.0);
+        let index = SpatialIndex::new(vec![far, GeoPoint::from_degrees(0.0, 0.0)].into_iter(), 1.0);
+        let within = index.nodes_within(&GeoPoint::from_degrees(89.9, 0.0), 300.0);
+
+        assert_eq!(within, vec![far]);
+    }
+
+    #[test]
+    fn nodes_within_works() {
+        let index = SpatialIndex::new(points().into_iter(), 0.05);
+        let mut within = index.nodes_within(&GeoPoint::from_degrees(50.11, 8.68), 3.0);
+        within.sort();
+
+        assert_eq!(
+            within,
+            vec![
+                GeoPoint::from_degrees(50.11, 8.68),
+                GeoPoint::from_degrees(50.12, 8.70)
+            ]
+        );
+    }
+}
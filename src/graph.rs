@@ -1,21 +1,49 @@
+mod builder;
+mod coarsening;
+mod edge_weight;
 mod error;
+mod fingerprint;
+mod graph_cell;
 
 pub mod export;
 pub mod generate;
-pub mod geo;
 pub mod import;
+pub mod layout;
 mod matrix_graph;
+mod normalization;
+mod summary;
+mod validation;
 
 use crate::geo::GeoPoint;
 use crate::metaheuristic::Solution;
+pub use builder::GraphBuilder;
+pub use coarsening::Coarsening;
+pub use edge_weight::{random_variances, EdgeWeight, StochasticEdges, StochasticWeight};
 pub use error::GraphError;
-pub use matrix_graph::MatrixGraph;
+pub(crate) use fingerprint::stable_hash;
+pub use fingerprint::Fingerprint;
+pub use graph_cell::{GraphCell, SyncGraph};
+pub use matrix_graph::{DuplicatePolicy, MatrixGraph, NegativeWeightPolicy};
+pub use normalization::{normalize_edge_weights, normalize_node_weights, Normalization, Scaling};
+pub use summary::GraphSummary;
+pub use validation::{ValidationPolicy, ValidationReport};
 
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
 
 pub type Edge<IndexType> = (IndexType, IndexType);
 
+/// Neighbor ids of a node, each with a reference to the weight of the edge to it, or the error
+/// for a node missing from the graph.
+pub type Neighbors<'a, IndexType, Ew> = Result<Vec<(IndexType, &'a Ew)>, GraphError<IndexType>>;
+
+/// Iterator over all edges of a graph with a reference to their weights.
+pub type EdgeIter<'a, IndexType, Ew> = Box<dyn Iterator<Item = (Edge<IndexType>, &'a Ew)> + 'a>;
+
+/// The shortest path between a node and each other node with its length, None for nodes
+/// without a path.
+pub type ShortestPaths<IndexType, Ew> = BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>;
+
 pub trait GenericWeightedGraph {
     type IndexType: Debug + Display;
     type NodeWeightType;
@@ -34,7 +62,9 @@ pub trait GenericWeightedGraph {
     fn iter_node_ids(&self) -> Box<dyn Iterator<Item = Self::IndexType> + '_>;
 
     /// Returns the node ids of this graph.
-    fn node_ids(&self) -> Vec<Self::IndexType>;
+    fn node_ids(&self) -> Vec<Self::IndexType> {
+        self.iter_node_ids().collect()
+    }
 
     /// Returns an iterator over the node ids and a reference to their weight.
     fn iter_nodes(&self)
@@ -46,6 +76,13 @@ pub trait GenericWeightedGraph {
         id: Self::IndexType,
     ) -> Result<&Self::NodeWeightType, GraphError<Self::IndexType>>;
 
+    /// Returns a mutable reference to the weight of node with id, which allows changing the
+    /// weight in place instead of reading it first and writing it back with change_node.
+    fn node_weight_mut(
+        &mut self,
+        id: Self::IndexType,
+    ) -> Result<&mut Self::NodeWeightType, GraphError<Self::IndexType>>;
+
     /// Returns an iterator over the neighboring ids.
     /// Returns GraphError, if the specified node id is not in the graph.
     fn iter_neighbor_ids(
@@ -58,7 +95,9 @@ pub trait GenericWeightedGraph {
     fn neighbor_ids(
         &self,
         id: Self::IndexType,
-    ) -> Result<Vec<Self::IndexType>, GraphError<Self::IndexType>>;
+    ) -> Result<Vec<Self::IndexType>, GraphError<Self::IndexType>> {
+        Ok(self.iter_neighbor_ids(id)?.collect())
+    }
 
     /// Returns an iterator over the neighbor ids with a reference to that edges weight
     /// Returns an error if the node is not in the graph.
@@ -71,10 +110,14 @@ pub trait GenericWeightedGraph {
         GraphError<Self::IndexType>,
     >;
 
+    /// Returns the neighbor ids with a reference to that edges weight.
+    /// Returns an error if the node is not in the graph.
     fn neighbors(
         &self,
         id: Self::IndexType,
-    ) -> Result<Vec<(Self::IndexType, &Self::EdgeWeightType)>, GraphError<Self::IndexType>>;
+    ) -> Neighbors<'_, Self::IndexType, Self::EdgeWeightType> {
+        Ok(self.iter_neighbors(id)?.collect())
+    }
 
     /// Returns true if node with id is a member, or false otherwise.
     fn has_node(&self, id: Self::IndexType) -> bool;
@@ -103,15 +146,17 @@ pub trait GenericWeightedGraph {
     fn iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<Self::IndexType>> + '_>;
 
     /// Returns a vec of all edge ids in the form (from_id, to_id)
-    fn edge_ids(&self) -> Vec<Edge<Self::IndexType>>;
+    fn edge_ids(&self) -> Vec<Edge<Self::IndexType>> {
+        self.iter_edge_ids().collect()
+    }
 
     /// Returns an iterator over all edges with their according weights
-    fn iter_edges(
-        &self,
-    ) -> Box<dyn Iterator<Item = (Edge<Self::IndexType>, &Self::EdgeWeightType)> + '_>;
+    fn iter_edges(&self) -> EdgeIter<'_, Self::IndexType, Self::EdgeWeightType>;
 
     /// Returns a vec of all edges and a reference to their weights
-    fn edges(&self) -> Vec<(Edge<Self::IndexType>, &Self::EdgeWeightType)>;
+    fn edges(&self) -> Vec<(Edge<Self::IndexType>, &Self::EdgeWeightType)> {
+        self.iter_edges().collect()
+    }
 
     /// Returns the weight of an edge.
     fn edge_weight(
@@ -119,6 +164,29 @@ pub trait GenericWeightedGraph {
         edge: Edge<Self::IndexType>,
     ) -> Result<&Self::EdgeWeightType, GraphError<Self::IndexType>>;
 
+    /// Returns a mutable reference to the weight of an edge.
+    /// Returns MissingEdge if the edge or one of its nodes is not in the graph.
+    fn edge_weight_mut(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+    ) -> Result<&mut Self::EdgeWeightType, GraphError<Self::IndexType>>;
+
+    /// Calls update with every edge and a mutable reference to its weight, in the order of
+    /// iter_edges. Implementations should override this with a single pass over their edges.
+    #[allow(clippy::type_complexity)]
+    fn update_edges(
+        &mut self,
+        update: &mut dyn FnMut(Edge<Self::IndexType>, &mut Self::EdgeWeightType),
+    ) where
+        Self::IndexType: Clone,
+    {
+        for edge in self.edge_ids() {
+            if let Ok(weight) = self.edge_weight_mut(edge.clone()) {
+                update(edge, weight);
+            }
+        }
+    }
+
     /// Returns true if the edge exists, or false otherwise.
     /// Returns MissingNode if either starting or ending nodes of the edge are not in the graph.
     fn has_edge(&self, edge: Edge<Self::IndexType>) -> bool;
@@ -147,84 +215,190 @@ pub trait GenericWeightedGraph {
     fn shortest_paths(
         &self,
         from_node: Self::IndexType,
-    ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Self::EdgeWeightType)>>;
+    ) -> ShortestPaths<Self::IndexType, Self::EdgeWeightType>;
 
     /// Calculates the shortest path to the given node from all other nodes.
     fn inv_shortest_paths(
         &self,
         to_node: Self::IndexType,
-    ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Self::EdgeWeightType)>>;
+    ) -> ShortestPaths<Self::IndexType, Self::EdgeWeightType>;
+
+    /// Calculates up to k shortest paths from from_node to to_node, which don't visit any node
+    /// twice, ordered by their length. There are fewer paths, if the graph has no more of them.
+    fn k_shortest_paths(
+        &self,
+        from_node: Self::IndexType,
+        to_node: Self::IndexType,
+        k: usize,
+    ) -> Vec<(Solution<Self::IndexType>, Self::EdgeWeightType)>;
 }
 
-pub trait WeightedGraph: GenericWeightedGraph<IndexType = usize> {}
-impl<T> WeightedGraph for T where T: GenericWeightedGraph<IndexType = usize> {}
+/// Mutably borrowed graphs are graphs as well, so a graph owned elsewhere can be put into a
+/// RefCell for the metaheuristics, without moving it, as in RefCell::new(&mut graph).
+impl<G: GenericWeightedGraph + ?Sized> GenericWeightedGraph for &mut G {
+    type IndexType = G::IndexType;
+    type NodeWeightType = G::NodeWeightType;
+    type EdgeWeightType = G::EdgeWeightType;
 
-pub trait GeoGraph: GenericWeightedGraph<IndexType = GeoPoint> {}
-impl<T> GeoGraph for T where T: GenericWeightedGraph<IndexType = GeoPoint> {}
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
 
-pub trait GenericGraph {
-    type IndexType: Debug + Display;
+    fn order(&self) -> usize {
+        (**self).order()
+    }
 
-    /// Returns true if there are no nodes, or false otherwise.
-    fn is_empty(&self) -> bool;
+    fn size(&self) -> usize {
+        (**self).size()
+    }
 
-    /// Returns the number of nodes in this graph.
-    fn order(&self) -> usize;
+    fn iter_node_ids(&self) -> Box<dyn Iterator<Item = Self::IndexType> + '_> {
+        (**self).iter_node_ids()
+    }
 
-    /// Returns the number of edges in this graph.
-    fn size(&self) -> usize;
+    fn iter_nodes(
+        &self,
+    ) -> Box<dyn Iterator<Item = (Self::IndexType, &Self::NodeWeightType)> + '_> {
+        (**self).iter_nodes()
+    }
 
-    /// Returns an iterator over all nodes of this graph.
-    fn iter_nodes(&self) -> Box<dyn Iterator<Item = Self::IndexType>>;
+    fn node_weight(
+        &self,
+        id: Self::IndexType,
+    ) -> Result<&Self::NodeWeightType, GraphError<Self::IndexType>> {
+        (**self).node_weight(id)
+    }
 
-    /// Returns the nodes of this graph.
-    fn nodes(&self) -> Vec<Self::IndexType>;
+    fn node_weight_mut(
+        &mut self,
+        id: Self::IndexType,
+    ) -> Result<&mut Self::NodeWeightType, GraphError<Self::IndexType>> {
+        (**self).node_weight_mut(id)
+    }
 
-    /// Returns an iterator over the neighbors of node with given id.
-    /// Returns an error if that node is not in the graph.
-    fn iter_neighbors(
+    fn iter_neighbor_ids(
         &self,
         id: Self::IndexType,
-    ) -> Result<Box<dyn Iterator<Item = Self::IndexType> + '_>, GraphError<Self::IndexType>>;
+    ) -> Result<Box<dyn Iterator<Item = Self::IndexType> + '_>, GraphError<Self::IndexType>> {
+        (**self).iter_neighbor_ids(id)
+    }
 
-    /// Returns the neighbors of the node with id.
-    /// Returns an error if node is not in graph.
-    fn neighbors(
+    #[allow(clippy::type_complexity)]
+    fn iter_neighbors(
         &self,
         id: Self::IndexType,
-    ) -> Result<Vec<Self::IndexType>, GraphError<Self::IndexType>>;
+    ) -> Result<
+        Box<dyn Iterator<Item = (Self::IndexType, &Self::EdgeWeightType)> + '_>,
+        GraphError<Self::IndexType>,
+    > {
+        (**self).iter_neighbors(id)
+    }
 
-    /// Returns true if node with id is a member, or false otherwise.
-    fn has_node(&self, id: Self::IndexType) -> bool;
+    fn has_node(&self, id: Self::IndexType) -> bool {
+        (**self).has_node(id)
+    }
 
-    /// Adds a new node to the graph.
-    /// Returns an error if a node with the same id already exists.
-    fn add_node(&mut self, id: Self::IndexType) -> Result<(), GraphError<Self::IndexType>>;
+    fn add_node(
+        &mut self,
+        id: Self::IndexType,
+        weight: Self::NodeWeightType,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        (**self).add_node(id, weight)
+    }
 
-    /// Removes a node from the graph.
-    /// This in turn means all edges from or to this node will be removed.
-    fn remove_node(&mut self, id: Self::IndexType);
+    fn remove_node(&mut self, id: Self::IndexType) {
+        (**self).remove_node(id)
+    }
 
-    /// Returns the count of neighbors at node with given id.
-    /// Returns an error if the node is not in the graph.
-    fn degree(&self, id: Self::IndexType) -> Result<Self::IndexType, GraphError<Self::IndexType>>;
+    fn change_node(&mut self, id: Self::IndexType, weight: Self::NodeWeightType) {
+        (**self).change_node(id, weight)
+    }
 
-    /// Returns an iterator over the edges of this graph.
-    fn iter_edges(&self) -> Box<dyn Iterator<Item = Edge<Self::IndexType>> + '_>;
+    fn degree(&self, id: Self::IndexType) -> Result<usize, GraphError<Self::IndexType>> {
+        (**self).degree(id)
+    }
 
-    /// Returns the edges of this graph.
-    fn edges(&self) -> Vec<(Self::IndexType, Self::IndexType)>;
+    fn iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<Self::IndexType>> + '_> {
+        (**self).iter_edge_ids()
+    }
 
-    /// Returns true if the edge exists, or false otherwise.
-    /// Returns MissingNode if either starting or ending nodes of the edge are not in the graph.
-    fn has_edge(&self, edge: Edge<Self::IndexType>) -> bool;
+    fn iter_edges(&self) -> EdgeIter<'_, Self::IndexType, Self::EdgeWeightType> {
+        (**self).iter_edges()
+    }
 
-    /// Adds a new edge to the graph.
-    /// Returns an error if the edge already exists or one of the nodes is missing.
-    fn add_edge(&mut self, edge: Edge<Self::IndexType>) -> Result<(), GraphError<Self::IndexType>>;
+    fn edge_weight(
+        &self,
+        edge: Edge<Self::IndexType>,
+    ) -> Result<&Self::EdgeWeightType, GraphError<Self::IndexType>> {
+        (**self).edge_weight(edge)
+    }
 
-    /// Removes an edge from the graph.
-    fn remove_edge(&mut self, edge: Edge<Self::IndexType>);
+    fn edge_weight_mut(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+    ) -> Result<&mut Self::EdgeWeightType, GraphError<Self::IndexType>> {
+        (**self).edge_weight_mut(edge)
+    }
+
+    fn update_edges(
+        &mut self,
+        update: &mut dyn FnMut(Edge<Self::IndexType>, &mut Self::EdgeWeightType),
+    ) where
+        Self::IndexType: Clone,
+    {
+        (**self).update_edges(update)
+    }
+
+    fn has_edge(&self, edge: Edge<Self::IndexType>) -> bool {
+        (**self).has_edge(edge)
+    }
+
+    fn add_edge(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+        weight: Self::EdgeWeightType,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        (**self).add_edge(edge, weight)
+    }
+
+    fn remove_edge(&mut self, edge: Edge<Self::IndexType>) {
+        (**self).remove_edge(edge)
+    }
+
+    fn change_edge(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+        weight: Self::EdgeWeightType,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        (**self).change_edge(edge, weight)
+    }
+
+    fn shortest_paths(
+        &self,
+        from_node: Self::IndexType,
+    ) -> ShortestPaths<Self::IndexType, Self::EdgeWeightType> {
+        (**self).shortest_paths(from_node)
+    }
+
+    fn inv_shortest_paths(
+        &self,
+        to_node: Self::IndexType,
+    ) -> ShortestPaths<Self::IndexType, Self::EdgeWeightType> {
+        (**self).inv_shortest_paths(to_node)
+    }
+
+    fn k_shortest_paths(
+        &self,
+        from_node: Self::IndexType,
+        to_node: Self::IndexType,
+        k: usize,
+    ) -> Vec<(Solution<Self::IndexType>, Self::EdgeWeightType)> {
+        (**self).k_shortest_paths(from_node, to_node, k)
+    }
 }
 
-pub trait Graph: GenericGraph<IndexType = usize> {}
+pub trait WeightedGraph: GenericWeightedGraph<IndexType = usize> {}
+impl<T> WeightedGraph for T where T: GenericWeightedGraph<IndexType = usize> {}
+
+pub trait GeoGraph: GenericWeightedGraph<IndexType = GeoPoint> {}
+impl<T> GeoGraph for T where T: GenericWeightedGraph<IndexType = GeoPoint> {}
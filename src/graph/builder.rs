@@ -0,0 +1,154 @@
+use num_traits::Zero;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::graph::{DuplicatePolicy, Edge, GraphError, MatrixGraph};
+use crate::util::Max;
+
+/// Collects nodes and edges one by one and builds a MatrixGraph from them, which spares
+/// writing out the lists of MatrixGraph::new and catches nodes and edges added twice.
+///
+/// ```
+/// use dop_with_aco::graph::GraphBuilder;
+///
+/// let graph = GraphBuilder::new()
+///     .node(0, 0)
+///     .node(1, 5)
+///     .bidirectional_edge(0, 1, 2usize)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphBuilder<IndexType, Nw, Ew> {
+    nodes: Vec<(IndexType, Nw)>,
+    edges: Vec<(Edge<IndexType>, Ew)>,
+    duplicate_edges: DuplicatePolicy,
+}
+
+impl<IndexType, Nw, Ew> GraphBuilder<IndexType, Nw, Ew> {
+    pub fn new() -> Self {
+        GraphBuilder {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            duplicate_edges: DuplicatePolicy::Error,
+        }
+    }
+
+    /// Adds the node with id and weight.
+    pub fn node(mut self, id: IndexType, weight: Nw) -> Self {
+        self.nodes.push((id, weight));
+        self
+    }
+
+    /// Adds all nodes, each given by its id and weight.
+    pub fn nodes(mut self, nodes: impl IntoIterator<Item = (IndexType, Nw)>) -> Self {
+        self.nodes.extend(nodes);
+        self
+    }
+
+    /// Adds the directed edge from `from` to `to` with weight.
+    pub fn edge(mut self, from: IndexType, to: IndexType, weight: Ew) -> Self {
+        self.edges.push(((from, to), weight));
+        self
+    }
+
+    /// Sets how edges added twice are resolved, instead of returning DuplicateEdge.
+    pub fn duplicate_edges(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_edges = policy;
+        self
+    }
+
+    /// Adds an edge with weight in both directions between a and b.
+    pub fn bidirectional_edge(self, a: IndexType, b: IndexType, weight: Ew) -> Self
+    where
+        IndexType: Copy,
+        Ew: Copy,
+    {
+        self.edge(a, b, weight).edge(b, a, weight)
+    }
+}
+
+impl<IndexType, Nw, Ew> Default for GraphBuilder<IndexType, Nw, Ew> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<IndexType, Nw, Ew> GraphBuilder<IndexType, Nw, Ew>
+where
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: Copy,
+    Ew: Copy + Max + Zero + Add + Debug + Ord,
+{
+    /// Builds the graph, in which nodes have the indices in the order they were added.
+    /// Returns DuplicateNode or DuplicateEdge for the first node or edge added twice, unless
+    /// duplicate edges are resolved by another policy, and MissingNode for edges to nodes,
+    /// which were never added.
+    pub fn build(self) -> Result<MatrixGraph<IndexType, Nw, Ew>, GraphError<IndexType>> {
+        let mut ids = HashSet::with_capacity(self.nodes.len());
+        if let Some(&(id, _)) = self.nodes.iter().find(|(id, _)| !ids.insert(*id)) {
+            return Err(GraphError::DuplicateNode(id));
+        }
+        MatrixGraph::new_with_duplicates(self.nodes, self.edges, self.duplicate_edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::GenericWeightedGraph;
+
+    use decorum::R64;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn graphs_are_built_from_added_nodes_and_edges() {
+        let graph = GraphBuilder::new()
+            .nodes(vec![(0, r(0.0)), (1, r(2.0))])
+            .node(2, r(1.0))
+            .edge(0, 1, r(1.0))
+            .bidirectional_edge(1, 2, r(3.0))
+            .build()
+            .unwrap();
+        assert_eq!(graph.order(), 3);
+        assert_eq!(graph.size(), 3);
+        assert_eq!(graph.node_weight(1), Ok(&r(2.0)));
+        assert_eq!(graph.edge_weight((2, 1)), Ok(&r(3.0)));
+        assert!(!graph.has_edge((1, 0)));
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        let builder = GraphBuilder::new().node(0, r(0.0)).node(1, r(0.0));
+        assert_eq!(
+            builder.clone().node(1, r(1.0)).build().unwrap_err(),
+            GraphError::DuplicateNode(1)
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .edge(0, 1, r(1.0))
+                .bidirectional_edge(1, 0, r(1.0))
+                .build()
+                .unwrap_err(),
+            GraphError::DuplicateEdge((0, 1))
+        );
+        assert_eq!(
+            builder.clone().edge(0, 2, r(1.0)).build().unwrap_err(),
+            GraphError::MissingNode(2)
+        );
+
+        let graph = builder
+            .edge(0, 1, r(1.0))
+            .edge(0, 1, r(0.5))
+            .duplicate_edges(DuplicatePolicy::KeepMax)
+            .build()
+            .unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&r(1.0)));
+    }
+}
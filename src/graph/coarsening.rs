@@ -0,0 +1,255 @@
+use num_traits::Zero;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::graph::{Edge, GenericWeightedGraph, GraphError, MatrixGraph};
+use crate::metaheuristic::Solution;
+use crate::util::Max;
+
+/// A coarser version of a graph, in which chains of low reward nodes with only two neighbors
+/// are contracted into super edges, together with the mapping back to the original graph.
+/// Routes on the coarse graph have the same length as their uncoarsened routes, but don't
+/// collect the rewards of the contracted nodes.
+pub struct Coarsening<IndexType: Clone, Nw, Ew> {
+    graph: MatrixGraph<IndexType, Nw, Ew>,
+    /// Contracted nodes of every super edge, in the order in which it passes them.
+    super_edges: HashMap<Edge<IndexType>, Vec<IndexType>>,
+    contracted: usize,
+}
+
+impl<IndexType, Nw, Ew> Coarsening<IndexType, Nw, Ew>
+where
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: Copy + PartialOrd,
+    Ew: Copy + Max + Zero + Add + Debug + Ord,
+{
+    /// Contracts every chain of nodes, which are not in keep, have a reward of at most
+    /// max_reward and are connected to exactly two other nodes, into a super edge between the
+    /// ends of the chain in each direction, in which the chain can be passed. Super edges,
+    /// which are longer than an existing edge between the ends, are left out. Chains, which
+    /// can't be passed in any direction or start and end at the same node, stay as they are.
+    pub fn new<G>(
+        graph: &G,
+        keep: &HashSet<IndexType>,
+        max_reward: Nw,
+    ) -> Result<Self, GraphError<IndexType>>
+    where
+        G: GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + ?Sized,
+    {
+        let mut neighbors: BTreeMap<IndexType, BTreeSet<IndexType>> = BTreeMap::new();
+        for (from, to) in graph.iter_edge_ids() {
+            neighbors.entry(from).or_default().insert(to);
+            neighbors.entry(to).or_default().insert(from);
+        }
+        let contractible = |node: IndexType| {
+            !keep.contains(&node)
+                && graph
+                    .node_weight(node)
+                    .is_ok_and(|&weight| weight <= max_reward)
+                && neighbors
+                    .get(&node)
+                    .is_some_and(|around| around.len() == 2 && !around.contains(&node))
+        };
+
+        let mut edges: HashMap<Edge<IndexType>, (Ew, Vec<IndexType>)> = graph
+            .iter_edges()
+            .map(|(edge, &weight)| (edge, (weight, Vec::new())))
+            .collect();
+        let mut removed = HashSet::new();
+        let mut seen = HashSet::new();
+        for node in graph.iter_node_ids() {
+            if seen.contains(&node) || !contractible(node) {
+                continue;
+            }
+            let chain = match Self::chain(node, &neighbors, &contractible) {
+                Some(chain) => chain,
+                None => {
+                    seen.insert(node);
+                    continue;
+                }
+            };
+            seen.extend(chain[1..chain.len() - 1].iter().copied());
+
+            let mut passable = false;
+            for path in [chain.clone(), chain.iter().rev().copied().collect()] {
+                let length = path.windows(2).try_fold(Ew::zero(), |length, edge| {
+                    Some(length + *graph.edge_weight((edge[0], edge[1])).ok()?)
+                });
+                if let Some(length) = length {
+                    passable = true;
+                    let end = (path[0], path[path.len() - 1]);
+                    let inner = path[1..path.len() - 1].to_vec();
+                    match edges.get(&end) {
+                        Some((existing, _)) if *existing <= length => {}
+                        _ => {
+                            edges.insert(end, (length, inner));
+                        }
+                    }
+                }
+            }
+            if passable {
+                removed.extend(chain[1..chain.len() - 1].iter().copied());
+            }
+        }
+
+        edges.retain(|&(from, to), _| !removed.contains(&from) && !removed.contains(&to));
+        let nodes: Vec<(IndexType, Nw)> = graph
+            .iter_nodes()
+            .filter(|(node, _)| !removed.contains(node))
+            .map(|(node, &weight)| (node, weight))
+            .collect();
+        let mut super_edges = HashMap::new();
+        let mut coarse_edges = Vec::with_capacity(edges.len());
+        for (edge, (weight, inner)) in edges {
+            if !inner.is_empty() {
+                super_edges.insert(edge, inner);
+            }
+            coarse_edges.push((edge, weight));
+        }
+        // sorted, so the coarse graph doesn't depend on the order of the hash map
+        coarse_edges.sort_by_key(|&(edge, _)| edge);
+
+        Ok(Coarsening {
+            graph: MatrixGraph::new(nodes, coarse_edges)?,
+            super_edges,
+            contracted: removed.len(),
+        })
+    }
+
+    /// Returns the contractible chain through node, starting and ending with the first nodes,
+    /// which can't be contracted. Returns None if the chain is a cycle or starts and ends at
+    /// the same node.
+    fn chain(
+        node: IndexType,
+        neighbors: &BTreeMap<IndexType, BTreeSet<IndexType>>,
+        contractible: &impl Fn(IndexType) -> bool,
+    ) -> Option<Vec<IndexType>> {
+        let mut ends = neighbors[&node].iter().map(|&next| {
+            let mut part = Vec::new();
+            let (mut previous, mut current) = (node, next);
+            while contractible(current) {
+                if current == node {
+                    return None;
+                }
+                part.push(current);
+                let next = *neighbors[&current]
+                    .iter()
+                    .find(|&&other| other != previous)?;
+                previous = current;
+                current = next;
+            }
+            part.push(current);
+            Some(part)
+        });
+        let mut before = ends.next()??;
+        let after = ends.next()??;
+        if before.last() == after.last() {
+            return None;
+        }
+        before.reverse();
+        before.push(node);
+        before.extend(after);
+        Some(before)
+    }
+
+    pub fn graph(&self) -> &MatrixGraph<IndexType, Nw, Ew> {
+        &self.graph
+    }
+
+    /// Returns the amount of nodes, which were contracted into super edges.
+    pub fn contracted(&self) -> usize {
+        self.contracted
+    }
+
+    /// Returns the amount of super edges of the coarse graph.
+    pub fn super_edges(&self) -> usize {
+        self.super_edges.len()
+    }
+
+    /// Returns the route on the original graph, which takes the same way as solution on the
+    /// coarse graph, by replacing each of its super edges by the nodes contracted into it.
+    pub fn uncoarsen(&self, solution: &Solution<IndexType>) -> Solution<IndexType> {
+        let mut nodes = Vec::new();
+        let mut previous = None;
+        for &node in solution.iter_nodes() {
+            if let Some(inner) = previous.and_then(|from| self.super_edges.get(&(from, node))) {
+                nodes.extend(inner.iter().copied());
+            }
+            nodes.push(node);
+            previous = Some(node);
+        }
+        Solution::from_nodes(nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::GraphBuilder;
+
+    use decorum::R64;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn chains_are_contracted_and_uncoarsened() {
+        // the square 0, 1, 2, 3 with a two way chain 4, 5 between 1 and 3 and the one way
+        // chain 6 from 2 to 0
+        let rewards = vec![r(0.0), r(1.0), r(1.0), r(1.0), r(0.0), r(0.5), r(0.0)];
+        let graph = GraphBuilder::new()
+            .nodes((0..).zip(rewards))
+            .bidirectional_edge(0, 1, r(1.0))
+            .bidirectional_edge(1, 2, r(1.0))
+            .bidirectional_edge(2, 3, r(1.0))
+            .bidirectional_edge(3, 0, r(1.0))
+            .bidirectional_edge(1, 4, r(1.0))
+            .bidirectional_edge(4, 5, r(1.0))
+            .bidirectional_edge(5, 3, r(1.0))
+            .edge(2, 6, r(1.0))
+            .edge(6, 0, r(1.0))
+            .build()
+            .unwrap();
+
+        let keep = vec![0].into_iter().collect();
+        let coarsening = Coarsening::new(&graph, &keep, r(0.5)).unwrap();
+        assert_eq!(coarsening.contracted(), 3);
+        assert_eq!(coarsening.super_edges(), 3);
+        let coarse = coarsening.graph();
+        assert_eq!(coarse.order(), 4);
+        assert_eq!(coarse.edge_weight((1, 3)), Ok(&r(3.0)));
+        assert_eq!(coarse.edge_weight((3, 1)), Ok(&r(3.0)));
+        assert_eq!(coarse.edge_weight((2, 0)), Ok(&r(2.0)));
+        assert!(!coarse.has_edge((0, 2)));
+
+        let route = Solution::from_nodes(vec![0, 1, 3, 2, 0]);
+        assert_eq!(
+            coarsening.uncoarsen(&route).nodes(),
+            vec![0, 1, 4, 5, 3, 2, 6, 0]
+        );
+
+        // rewarded nodes stay part of the coarse graph
+        let coarsening = Coarsening::new(&graph, &keep, r(0.0)).unwrap();
+        assert_eq!(coarsening.contracted(), 2);
+        assert!(coarsening.graph().has_node(5));
+        assert_eq!(coarsening.graph().edge_weight((1, 5)), Ok(&r(2.0)));
+    }
+
+    #[test]
+    fn shorter_edges_between_chain_ends_are_kept() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(0.0), r(1.0)],
+            vec![(0, 1, r(1.0)), (1, 2, r(1.0)), (0, 2, r(1.5))],
+        )
+        .unwrap();
+        let keep = vec![0].into_iter().collect();
+        let coarsening = Coarsening::new(&graph, &keep, r(0.0)).unwrap();
+        assert_eq!(coarsening.contracted(), 1);
+        assert_eq!(coarsening.super_edges(), 0);
+        assert_eq!(coarsening.graph().edge_weight((0, 2)), Ok(&r(1.5)));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::graph::Edge;
+use crate::rng::{gen_float_range, Rng};
+
+use decorum::{Real, R64};
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The traversal time of an edge, which can either be fixed or follow a distribution.
+pub trait EdgeWeight {
+    fn mean(&self) -> R64;
+    fn std_dev(&self) -> R64;
+    /// Draws a traversal time from the underlying distribution.
+    fn sample(&self, rng: &mut Rng) -> R64;
+
+    /// Returns mean + risk_aversion * std_dev, which is used as a pessimistic estimate of the weight.
+    fn risk_adjusted(&self, risk_aversion: f64) -> R64 {
+        self.mean() + R64::from_inner(risk_aversion) * self.std_dev()
+    }
+}
+
+impl EdgeWeight for R64 {
+    fn mean(&self) -> R64 {
+        *self
+    }
+
+    fn std_dev(&self) -> R64 {
+        R64::zero()
+    }
+
+    fn sample(&self, _rng: &mut Rng) -> R64 {
+        *self
+    }
+}
+
+/// A normally distributed edge weight, which is cut off at 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticWeight {
+    pub mean: R64,
+    pub variance: R64,
+}
+
+impl StochasticWeight {
+    pub fn new(mean: R64, variance: R64) -> Self {
+        StochasticWeight { mean, variance }
+    }
+}
+
+impl EdgeWeight for StochasticWeight {
+    fn mean(&self) -> R64 {
+        self.mean
+    }
+
+    fn std_dev(&self) -> R64 {
+        self.variance.sqrt()
+    }
+
+    fn sample(&self, rng: &mut Rng) -> R64 {
+        // Box-Muller transform, 1 - u avoids taking the logarithm of 0
+        let u1 = 1.0 - rng.rand_float();
+        let u2 = rng.rand_float();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        (self.mean + self.std_dev() * R64::from_inner(z)).max(R64::zero())
+    }
+}
+
+/// Variances of edge weights, whose means are given by the graph.
+/// Edges without a variance are treated as deterministic.
+#[derive(Debug, Clone)]
+pub struct StochasticEdges<IndexType> {
+    pub variances: HashMap<Edge<IndexType>, R64>,
+    /// Factor of the standard deviation, that is added to the mean when checking the budget.
+    pub risk_aversion: f64,
+}
+
+impl<IndexType> Default for StochasticEdges<IndexType> {
+    fn default() -> Self {
+        StochasticEdges {
+            variances: HashMap::new(),
+            risk_aversion: 0.0,
+        }
+    }
+}
+
+impl<IndexType: Hash + Eq> StochasticEdges<IndexType> {
+    pub fn new(variances: HashMap<Edge<IndexType>, R64>, risk_aversion: f64) -> Self {
+        StochasticEdges {
+            variances,
+            risk_aversion,
+        }
+    }
+
+    /// Returns the distribution of the edge with the given mean weight.
+    pub fn weight(&self, edge: Edge<IndexType>, mean: R64) -> StochasticWeight {
+        let variance = self.variances.get(&edge).copied().unwrap_or_else(R64::zero);
+        StochasticWeight::new(mean, variance)
+    }
+
+    /// Returns the weight of the edge, which should be assumed when checking the time budget.
+    pub fn budget_weight(&self, edge: Edge<IndexType>, mean: R64) -> R64 {
+        self.weight(edge, mean).risk_adjusted(self.risk_aversion)
+    }
+
+    /// Samples the traversal time of the edge. For deterministic edges no random number is drawn.
+    pub fn sample(&self, edge: Edge<IndexType>, mean: R64, rng: &mut Rng) -> R64 {
+        if self.variances.contains_key(&edge) {
+            self.weight(edge, mean).sample(rng)
+        } else {
+            mean
+        }
+    }
+}
+
+/// Assigns a variance to each of the given edges, so that its standard deviation is the mean
+/// weight times a coefficient of variation drawn uniformly from cv_range.
+pub fn random_variances<IndexType: Hash + Eq>(
+    edges: impl Iterator<Item = (Edge<IndexType>, R64)>,
+    rng: &mut Rng,
+    cv_range: (f64, f64),
+) -> HashMap<Edge<IndexType>, R64> {
+    edges
+        .map(|(edge, mean)| {
+            let cv = gen_float_range(rng, cv_range.0..cv_range.1);
+            let std_dev = mean * R64::from_inner(cv);
+            (edge, std_dev * std_dev)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::rng64;
+
+    #[test]
+    fn deterministic_weights_are_fixed() {
+        let mut rng = rng64(1);
+        let weight = R64::from_inner(3.0);
+
+        assert_eq!(weight.sample(&mut rng), 3.0);
+        assert_eq!(weight.risk_adjusted(2.0), 3.0);
+    }
+
+    #[test]
+    fn risk_adjusted_adds_std_dev() {
+        let weight = StochasticWeight::new(R64::from_inner(3.0), R64::from_inner(4.0));
+
+        assert_eq!(weight.risk_adjusted(0.0), 3.0);
+        assert_eq!(weight.risk_adjusted(1.5), 6.0);
+    }
+
+    #[test]
+    fn samples_match_distribution() {
+        let mut rng = rng64(12345);
+        let weight = StochasticWeight::new(R64::from_inner(10.0), R64::from_inner(4.0));
+        let samples: Vec<f64> = (0..10000)
+            .map(|_| weight.sample(&mut rng).into_inner())
+            .collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+
+        assert!((mean - 10.0).abs() < 0.1);
+        assert!((variance - 4.0).abs() < 0.3);
+    }
+
+    #[test]
+    fn stochastic_edges_only_sample_known_edges() {
+        let mut variances = HashMap::new();
+        variances.insert((0, 1), R64::from_inner(1.0));
+        let edges = StochasticEdges::new(variances, 2.0);
+        let mut rng = rng64(1);
+
+        assert_eq!(edges.budget_weight((0, 1), R64::from_inner(5.0)), 7.0);
+        assert_eq!(edges.budget_weight((1, 0), R64::from_inner(5.0)), 5.0);
+        assert_eq!(edges.sample((1, 0), R64::from_inner(5.0), &mut rng), 5.0);
+    }
+
+    #[test]
+    fn random_variances_stay_within_cv_range() {
+        let mut rng = rng64(12345);
+        let edges = (0..100).map(|i| ((i, i + 1), R64::from_inner(2.0)));
+        let variances = random_variances(edges, &mut rng, (0.1, 0.5));
+
+        assert_eq!(variances.len(), 100);
+        for variance in variances.values() {
+            assert!(*variance >= 0.04 - 1e-9 && *variance <= 1.0 + 1e-9);
+        }
+    }
+}
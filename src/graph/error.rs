@@ -7,6 +7,7 @@ pub enum GraphError<IndexType: fmt::Debug + fmt::Display> {
     DuplicateEdge((IndexType, IndexType)),
     MissingNode(IndexType),
     DuplicateNode(IndexType),
+    NegativeWeight((IndexType, IndexType)),
 }
 
 impl<IndexType: fmt::Debug + fmt::Display> fmt::Display for GraphError<IndexType> {
@@ -16,6 +17,7 @@ impl<IndexType: fmt::Debug + fmt::Display> fmt::Display for GraphError<IndexType
             Self::DuplicateEdge(edge) => write!(f, "Edge {:?} already in graph.", edge),
             Self::MissingNode(node) => write!(f, "Node {} not in graph.", node),
             Self::DuplicateNode(node) => write!(f, "Node {} already in graph.", node),
+            Self::NegativeWeight(edge) => write!(f, "Edge {:?} has a negative weight.", edge),
         }
     }
 }
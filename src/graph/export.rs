@@ -1,23 +1,25 @@
 mod dot;
+mod geojson;
 pub mod svg;
 
-pub use dot::Dot;
-pub use svg::Svg;
+pub use dot::{Dot, DotOptions};
+pub use geojson::GeoJson;
+pub use svg::{Locate, Svg};
 
-use crate::graph::WeightedGraph;
-use std::fmt::Display;
+use std::io;
+use std::io::Write;
 
-/// An exporter for weighted graphs.
-pub trait Export {
-    /// Returns a String representation of the graph according to the export format used.
-    fn from_weighted_graph<Nw: Display, Ew: Display>(
-        graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
-        name: &str,
-    ) -> String;
+/// An exporter for graphs of type G, usually a graph trait object like dyn WeightedGraph.
+pub trait Export<G: ?Sized> {
+    /// Writes the graph in the export format to writer as it goes, so even large graphs are
+    /// never held in memory as a whole. Wrap files in a BufWriter.
+    fn export<W: Write>(&self, graph: &G, writer: W) -> io::Result<()>;
 
-    /// Returns a String representation of the graph according to the export format used.
-    fn from_usize_weighted_graph(
-        graph: &dyn WeightedGraph<NodeWeightType = usize, EdgeWeightType = usize>,
-        name: &str,
-    ) -> String;
+    /// Returns the export as String, which is only sensible for small graphs.
+    fn export_to_string(&self, graph: &G) -> String {
+        let mut out = Vec::new();
+        self.export(graph, &mut out)
+            .expect("Writing to a Vec can't fail");
+        String::from_utf8(out).expect("Exports are valid utf-8")
+    }
 }
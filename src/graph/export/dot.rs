@@ -1,69 +1,265 @@
 use super::Export;
+use crate::graph::layout::Layout;
 use crate::graph::WeightedGraph;
+use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::io;
+use std::io::Write;
 
-/// Empty struct. Only implements GraphExport trait functionalities.
-#[allow(dead_code)]
-pub struct Dot {}
+/// Exports graphs in Graphviz dot format as digraph name, as configured by its options.
+pub struct Dot<'a> {
+    pub name: String,
+    pub options: DotOptions<'a>,
+}
 
-impl Export for Dot {
-    /// Returns a string representing the graph in Graphviz dot format.
-    /// The weights are just added as a label for the corresponding node / edge.
-    fn from_weighted_graph<Nw: Display, Ew: Display>(
-        graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
-        name: &str,
-    ) -> String {
-        let mut out = format!("digraph {} {{\n", name);
+/// Options of a dot export, built up from DotOptions::new(), e.g.
+/// `DotOptions::new().precision(2).rankdir("LR")`.
+/// By default node and edge weights are added as labels, as they are written by Display.
+pub struct DotOptions<'a> {
+    node_labels: bool,
+    edge_labels: bool,
+    precision: Option<usize>,
+    cluster: Option<Box<dyn Fn(usize) -> Option<String> + 'a>>,
+    attributes: BTreeMap<String, String>,
+    layout: Option<&'a Layout>,
+}
 
-        for (node, weight) in graph.iter_nodes() {
-            out.push_str(format!("\t{} [label=\"{}\"]\n", node, weight).as_str());
+impl<'a> Default for DotOptions<'a> {
+    fn default() -> Self {
+        DotOptions {
+            node_labels: true,
+            edge_labels: true,
+            precision: None,
+            cluster: None,
+            attributes: BTreeMap::new(),
+            layout: None,
         }
+    }
+}
 
-        for (edge, weight) in graph.iter_edges() {
-            out.push_str(format!("\t{} -> {} [label=\"{}\"]\n", edge.0, edge.1, weight).as_str());
+impl<'a> DotOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn node_labels(mut self, node_labels: bool) -> Self {
+        self.node_labels = node_labels;
+        self
+    }
+
+    pub fn edge_labels(mut self, edge_labels: bool) -> Self {
+        self.edge_labels = edge_labels;
+        self
+    }
+
+    /// Writes weight labels with this many decimal places.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Draws the nodes, for which cluster returns a name, in a cluster with that name, e.g. the
+    /// blocks of a stochastic block graph with `|node| Some((node / community_size).to_string())`.
+    pub fn cluster_by(mut self, cluster: impl Fn(usize) -> Option<String> + 'a) -> Self {
+        self.cluster = Some(Box::new(cluster));
+        self
+    }
+
+    /// Sets an attribute of the whole graph.
+    pub fn attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the direction of the layout, one of TB, LR, BT and RL.
+    pub fn rankdir(self, rankdir: &str) -> Self {
+        self.attribute("rankdir", rankdir)
+    }
+
+    /// Sets how overlapping nodes are removed by layouts like neato, e.g. false or scale.
+    pub fn overlap(self, overlap: &str) -> Self {
+        self.attribute("overlap", overlap)
+    }
+
+    /// Pins the nodes to the positions of layout, in inches. Only layout engines like neato
+    /// keep pinned positions, dot ignores them.
+    pub fn layout(mut self, layout: &'a Layout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    fn label<W: Display>(&self, weight: W) -> String {
+        match self.precision {
+            Some(precision) => format!("[label=\"{:.*}\"]", precision, weight),
+            None => format!("[label=\"{}\"]", weight),
         }
+    }
+}
 
-        out.push('}');
-        out
+impl<'a> Dot<'a> {
+    pub fn new(name: &str) -> Self {
+        Dot {
+            name: name.to_string(),
+            options: DotOptions::default(),
+        }
     }
 
-    /// Returns a string representing the graph in Graphviz dot format.
-    /// The edge weights are used to set the weight parameter of the edge in Graphviz.
-    /// This makes edges with a low weight more likely to be short than ones with a high weight.
-    /// All weights are also added as label for the node / edge.
-    fn from_usize_weighted_graph(
+    pub fn with_options(mut self, options: DotOptions<'a>) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes the graph like export, but the edge weights also set the weight parameter of the
+    /// edge in Graphviz. This makes edges with a low weight more likely to be short than ones
+    /// with a high weight.
+    pub fn export_usize_weighted<W: Write>(
+        &self,
         graph: &dyn WeightedGraph<NodeWeightType = usize, EdgeWeightType = usize>,
-        name: &str,
-    ) -> String {
-        let mut out = format!("digraph {} {{\n", name);
+        mut writer: W,
+    ) -> io::Result<()> {
+        let max_len = graph
+            .iter_edges()
+            .map(|(_, &weight)| weight)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        self.write_graph(graph, &mut writer, |writer, weight| {
+            write!(writer, " [weight={}]", max_len as f64 / *weight as f64)
+        })
+    }
 
-        for (node, weight) in graph.iter_nodes() {
-            out.push_str(format!("\t{} [label=\"{}\"]\n", node, weight).as_str());
+    /// Writes the graph, edge_attributes can add attributes to each edge after its label.
+    fn write_graph<Nw: Display, Ew: Display, W: Write>(
+        &self,
+        graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+        writer: &mut W,
+        edge_attributes: impl Fn(&mut W, &Ew) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let options = &self.options;
+        writeln!(writer, "digraph {} {{", self.name)?;
+        for (attribute, value) in options.attributes.iter() {
+            writeln!(writer, "\t{}=\"{}\"", attribute, value)?;
         }
 
-        let mut max_len = 1;
-        for (_, weight) in graph.iter_edges() {
-            if weight > &max_len {
-                max_len = *weight;
+        let write_node = |writer: &mut W, indent: &str, node: usize, weight: &Nw| {
+            write!(writer, "{}{}", indent, node)?;
+            if options.node_labels {
+                write!(writer, " {}", options.label(weight))?;
+            }
+            if let Some(point) = options.layout.and_then(|layout| layout.get(&node)) {
+                write!(writer, " [pos=\"{},{}!\"]", point.x, point.y)?;
+            }
+            writeln!(writer)
+        };
+        // only the ids of clustered nodes are kept, to write them cluster by cluster
+        let mut clusters: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (id, weight) in graph.iter_nodes() {
+            match options.cluster.as_ref().and_then(|cluster| cluster(id)) {
+                Some(cluster) => clusters.entry(cluster).or_default().push(id),
+                None => write_node(writer, "\t", id, weight)?,
+            }
+        }
+        for (i, (cluster, nodes)) in clusters.iter().enumerate() {
+            writeln!(writer, "\tsubgraph cluster_{} {{", i)?;
+            writeln!(writer, "\t\tlabel=\"{}\"", cluster)?;
+            for &node in nodes {
+                if let Ok(weight) = graph.node_weight(node) {
+                    write_node(writer, "\t\t", node, weight)?;
+                }
             }
+            writeln!(writer, "\t}}")?;
         }
 
         for (edge, weight) in graph.iter_edges() {
-            let weight = *weight as f64;
-
-            out.push_str(
-                format!(
-                    "\t{} -> {} [weight={} label={}]\n",
-                    edge.0,
-                    edge.1,
-                    max_len as f64 / weight,
-                    weight
-                )
-                .as_str(),
-            );
+            write!(writer, "\t{} -> {}", edge.0, edge.1)?;
+            if options.edge_labels {
+                write!(writer, " {}", options.label(weight))?;
+            }
+            edge_attributes(writer, weight)?;
+            writeln!(writer)?;
         }
 
-        out.push('}');
-        out
+        write!(writer, "}}")
+    }
+}
+
+impl<'a, Nw: Display, Ew: Display>
+    Export<dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>> for Dot<'a>
+{
+    /// Writes the graph in Graphviz dot format.
+    fn export<W: Write>(
+        &self,
+        graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+        mut writer: W,
+    ) -> io::Result<()> {
+        self.write_graph(graph, &mut writer, |_, _| Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use decorum::R64;
+
+    type Graph = dyn WeightedGraph<NodeWeightType = R64, EdgeWeightType = R64>;
+
+    fn graph() -> MatrixGraph<usize, R64, R64> {
+        let r = R64::from_inner;
+        MatrixGraph::new_usize_indexed(
+            vec![r(1.0), r(2.5), r(3.0)],
+            vec![(0, 1, r(0.25)), (1, 2, r(1.0))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn default_export_labels_all_weights() {
+        assert_eq!(
+            Dot::new("g").export_to_string(&graph() as &Graph),
+            "digraph g {\n\t0 [label=\"1\"]\n\t1 [label=\"2.5\"]\n\t2 [label=\"3\"]\n\t0 -> 1 [label=\"0.25\"]\n\t1 -> 2 [label=\"1\"]\n}"
+        );
+    }
+
+    #[test]
+    fn usize_weights_set_the_edge_weight() {
+        let graph =
+            MatrixGraph::new_usize_indexed(vec![1usize, 2], vec![(0, 1, 2usize), (1, 0, 4)])
+                .unwrap();
+        let mut out = Vec::new();
+        Dot::new("g")
+            .export_usize_weighted(&graph, &mut out)
+            .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\t0 -> 1 [label=\"2\"] [weight=2]\n"));
+        assert!(out.contains("\t1 -> 0 [label=\"4\"] [weight=1]\n"));
+    }
+
+    #[test]
+    fn options_are_applied() {
+        let options = DotOptions::new()
+            .edge_labels(false)
+            .precision(1)
+            .rankdir("LR")
+            .overlap("false")
+            .cluster_by(|node| (node > 0).then(|| "b".to_string()));
+
+        assert_eq!(
+            Dot::new("g")
+                .with_options(options)
+                .export_to_string(&graph() as &Graph),
+            "digraph g {\n\toverlap=\"false\"\n\trankdir=\"LR\"\n\t0 [label=\"1.0\"]\n\tsubgraph cluster_0 {\n\t\tlabel=\"b\"\n\t\t1 [label=\"2.5\"]\n\t\t2 [label=\"3.0\"]\n\t}\n\t0 -> 1\n\t1 -> 2\n}"
+        );
+    }
+
+    #[test]
+    fn layouts_pin_the_nodes() {
+        let layout = crate::graph::layout::grid((3, 1));
+        let options = DotOptions::new().node_labels(false).layout(&layout);
+        let dot = Dot::new("g")
+            .with_options(options)
+            .export_to_string(&graph() as &Graph);
+        assert!(dot.contains("\t0 [pos=\"0,0!\"]\n\t1 [pos=\"1,0!\"]\n\t2 [pos=\"2,0!\"]\n"));
     }
 }
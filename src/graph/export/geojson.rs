@@ -0,0 +1,98 @@
+use super::Export;
+use crate::geo::GeoPoint;
+use crate::graph::GeoGraph;
+
+use std::io;
+use std::io::Write;
+
+/// Exports graphs with locations as node ids as GeoJSON FeatureCollection. Nodes become Point
+/// features and edges LineString features, both with their weight as property.
+#[derive(Default)]
+pub struct GeoJson {}
+
+/// Writes a weight as json number, or null if json can't represent it.
+fn write_weight<W: Write>(writer: &mut W, weight: f64) -> io::Result<()> {
+    if weight.is_finite() {
+        write!(writer, "{}", weight)
+    } else {
+        write!(writer, "null")
+    }
+}
+
+/// GeoJSON positions are written as longitude first.
+fn write_position<W: Write>(writer: &mut W, point: &GeoPoint) -> io::Result<()> {
+    write!(writer, "[{},{}]", point.lon(), point.lat())
+}
+
+impl<Nw, Ew> Export<dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>> for GeoJson
+where
+    Nw: Copy + Into<f64>,
+    Ew: Copy + Into<f64>,
+{
+    fn export<W: Write>(
+        &self,
+        graph: &dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+        mut writer: W,
+    ) -> io::Result<()> {
+        write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+        let mut first = true;
+        let mut separate = |writer: &mut W| {
+            let result = if first { Ok(()) } else { writeln!(writer, ",") };
+            first = false;
+            result
+        };
+
+        for (id, &weight) in graph.iter_nodes() {
+            separate(&mut writer)?;
+            write!(
+                writer,
+                "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Point\",\"coordinates\":"
+            )?;
+            write_position(&mut writer, &id)?;
+            write!(writer, "}},\"properties\":{{\"weight\":")?;
+            write_weight(&mut writer, weight.into())?;
+            write!(writer, "}}}}")?;
+        }
+        for ((from, to), &weight) in graph.iter_edges() {
+            separate(&mut writer)?;
+            write!(
+                writer,
+                "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\"coordinates\":["
+            )?;
+            write_position(&mut writer, &from)?;
+            write!(writer, ",")?;
+            write_position(&mut writer, &to)?;
+            write!(writer, "]}},\"properties\":{{\"weight\":")?;
+            write_weight(&mut writer, weight.into())?;
+            write!(writer, "}}}}")?;
+        }
+
+        writeln!(writer, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use decorum::R64;
+
+    #[test]
+    fn nodes_and_edges_become_features() {
+        let r = R64::from_inner;
+        let a = GeoPoint::from_degrees(52.0, 13.0);
+        let b = GeoPoint::from_degrees(52.5, 13.5);
+        let graph =
+            MatrixGraph::new(vec![(a, r(1.0)), (b, r(2.0))], vec![((a, b), r(0.5))]).unwrap();
+        let graph: &dyn GeoGraph<NodeWeightType = R64, EdgeWeightType = R64> = &graph;
+
+        let json = GeoJson::default().export_to_string(graph);
+        assert!(json.starts_with("{\"type\":\"FeatureCollection\""));
+        assert_eq!(json.matches("\"type\":\"Feature\"").count(), 3);
+        assert!(json.contains("\"coordinates\":[13,52]},\"properties\":{\"weight\":1}"));
+        assert!(
+            json.contains("\"coordinates\":[[13,52],[13.5,52.5]]},\"properties\":{\"weight\":0.5}")
+        );
+        assert!(json.ends_with("]}\n"));
+    }
+}
@@ -1,36 +1,215 @@
+use super::Export;
 use crate::geo::GeoPoint;
+use crate::graph::layout::Layout;
 use crate::graph::{GeoGraph, WeightedGraph};
-use crate::util::{scale::GeoPointScaler, scale::PointScaler, Point};
+use crate::util::Point;
 
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::Hash;
+use std::io;
 use std::io::prelude::*;
 use tera::Context;
 use tera::Tera;
 
+/// Colors of the viridis color map at evenly spaced positions, edge weights are interpolated
+/// between them.
+const COLOR_MAP: [(u8, u8, u8); 5] = [
+    (0x44, 0x01, 0x54),
+    (0x3b, 0x52, 0x8b),
+    (0x21, 0x91, 0x8c),
+    (0x5e, 0xc9, 0x62),
+    (0xfd, 0xe7, 0x25),
+];
+
+/// Stroke width of edges, heatmaps draw edges with the highest level this many times wider.
+const STROKE_WIDTH: f64 = 2.0;
+const HEAT_STROKE_FACTOR: f64 = 4.0;
+
+/// Exports graphs with coordinates to svg.
+///
+/// The coordinates are fitted into the canvas of width x height without distorting them,
+/// surrounded by padding, which also holds the title and the legend. Edges are colored by their
+/// weight and the radius of nodes grows with their weight up to max_radius.
 pub struct Svg {
+    pub title: String,
     pub width: usize,
     pub height: usize,
     pub padding: usize,
+    pub max_radius: f64,
+    /// Draws a color bar with the range of the edge weights below the graph.
+    pub legend: bool,
 }
 
-impl Svg {
-    fn scaled_point(&self, point: &Point, scaler: &PointScaler) -> Point {
-        let scaled_point = scaler.scale_point(point);
+impl Default for Svg {
+    fn default() -> Self {
+        Svg {
+            title: String::new(),
+            width: 800,
+            height: 800,
+            padding: 50,
+            max_radius: 6.0,
+            legend: true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Circle {
+    x: f64,
+    y: f64,
+    r: f64,
+}
+
+#[derive(Serialize)]
+struct Legend {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    /// Offset in percent and color of the stops of the gradient.
+    stops: Vec<(f64, String)>,
+    min: String,
+    max: String,
+}
+
+/// Maps coordinates onto the canvas. Both axes share the same scale, so distances keep their
+/// proportions, and y points upwards like in a map.
+struct Frame {
+    min: Point,
+    scale: f64,
+    offset: Point,
+}
 
-        // The scaled point needs to be adjusted to our SVG canvas size and padding.
+impl Frame {
+    fn new(points: &[Point], svg: &Svg) -> Self {
+        let (min, max) = points.iter().fold(
+            (
+                Point {
+                    x: f64::MAX,
+                    y: f64::MAX,
+                },
+                Point {
+                    x: f64::MIN,
+                    y: f64::MIN,
+                },
+            ),
+            |(min, max), p| {
+                (
+                    Point {
+                        x: min.x.min(p.x),
+                        y: min.y.min(p.y),
+                    },
+                    Point {
+                        x: max.x.max(p.x),
+                        y: max.y.max(p.y),
+                    },
+                )
+            },
+        );
+        let (dx, dy) = ((max.x - min.x).max(0.0), (max.y - min.y).max(0.0));
+        let scale = match (dx > 0.0, dy > 0.0) {
+            (true, true) => (svg.width as f64 / dx).min(svg.height as f64 / dy),
+            (true, false) => svg.width as f64 / dx,
+            (false, true) => svg.height as f64 / dy,
+            (false, false) => 1.0,
+        };
+        // centers the graph in the dimension it does not fill
+        let offset = Point {
+            x: svg.padding as f64 + (svg.width as f64 - dx * scale) / 2.0,
+            y: svg.padding as f64 + (svg.height as f64 + dy * scale) / 2.0,
+        };
+
+        Frame { min, scale, offset }
+    }
+
+    fn map(&self, point: &Point) -> Point {
         Point {
-            x: (scaled_point.x * self.width as f64) + self.padding as f64,
-            y: (scaled_point.y * (self.height as f64 * -1.0)) + (self.padding + self.height) as f64,
+            x: self.offset.x + (point.x - self.min.x) * self.scale,
+            y: self.offset.y - (point.y - self.min.y) * self.scale,
         }
     }
+}
 
-    fn scaled_geopoint(&self, point: &GeoPoint, scaler: &GeoPointScaler) -> GeoPoint {
-        let scaled_point = scaler.scale_point(point);
-        GeoPoint::from_micro_degrees(
-            (scaled_point.micro_lat() * self.width as i32) + self.padding as i32,
-            (scaled_point.micro_lon() * (-(self.height as i32)))
-                + (self.padding + self.height) as i32,
-        )
+/// Returns the color of the color map at t in [0, 1] as hex string.
+pub(crate) fn color(t: f64) -> String {
+    let position = t.clamp(0.0, 1.0) * (COLOR_MAP.len() - 1) as f64;
+    let i = (position.floor() as usize).min(COLOR_MAP.len() - 2);
+    let fraction = position - i as f64;
+    let (from, to) = (COLOR_MAP[i], COLOR_MAP[i + 1]);
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(from.0, to.0),
+        channel(from.1, to.1),
+        channel(from.2, to.2)
+    )
+}
+
+/// Returns the smallest and largest value, (0, 0) if there are none.
+pub(crate) fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values
+        .fold(None, |range: Option<(f64, f64)>, value| match range {
+            Some((min, max)) => Some((min.min(value), max.max(value))),
+            None => Some((value, value)),
+        })
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Position of value within range, 0 for empty ranges.
+fn relative(value: f64, (min, max): (f64, f64)) -> f64 {
+    if max > min {
+        (value - min) / (max - min)
+    } else {
+        0.0
+    }
+}
+
+/// Returns the index of every id in ids, edges are drawn between the indices of their nodes.
+fn indices<IndexType: Copy + Hash + Eq>(ids: &[IndexType]) -> HashMap<IndexType, usize> {
+    ids.iter().enumerate().map(|(i, &id)| (id, i)).collect()
+}
+
+/// Projects a location onto a plane with the equirectangular projection around the latitude
+/// lat_0, in which distances close to lat_0 keep their proportions.
+fn project(point: &GeoPoint, lat_0: f64) -> Point {
+    Point {
+        x: point.lon_rad() * lat_0.cos(),
+        y: point.lat_rad(),
+    }
+}
+
+/// Node ids, which determine where their nodes are drawn.
+pub trait Locate: Sized {
+    /// Returns the positions of the nodes with the given ids, taken from layout if the ids
+    /// carry none. None if some node has no position.
+    fn locate(ids: &[Self], layout: Option<&Layout>) -> Option<Vec<Point>>;
+}
+
+/// Locations are projected around their mean latitude.
+impl Locate for GeoPoint {
+    fn locate(ids: &[Self], _layout: Option<&Layout>) -> Option<Vec<Point>> {
+        let lat_0 = ids.iter().map(|id| id.lat_rad()).sum::<f64>() / ids.len().max(1) as f64;
+        Some(ids.iter().map(|id| project(id, lat_0)).collect())
+    }
+}
+
+/// Generated graphs are drawn with the layout they were generated in.
+impl Locate for usize {
+    fn locate(ids: &[Self], layout: Option<&Layout>) -> Option<Vec<Point>> {
+        let layout = layout?;
+        ids.iter().map(|id| layout.get(id).copied()).collect()
+    }
+}
+
+impl Svg {
+    pub fn new(width: usize, height: usize) -> Self {
+        Svg {
+            width,
+            height,
+            ..Svg::default()
+        }
     }
 
     fn initial_context(&self) -> Context {
@@ -39,80 +218,290 @@ impl Svg {
         context.insert("width", &self.width);
         context.insert("height", &self.height);
         context.insert("padding", &self.padding);
+        context.insert("name", &self.title);
 
         context
     }
 
-    pub fn export_coordinate_graph<Nw, Ew>(
-        &self,
-        graph: &dyn WeightedGraph<NodeWeightType = (Point, Nw), EdgeWeightType = Ew>,
-        name: &str,
-    ) -> String {
-        let mut context = self.initial_context();
-        context.insert("name", &name);
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
 
-        let point_iter = graph.iter_nodes().map(|(_, weight)| weight.0);
-        let scaler = PointScaler::from_point_iterator(point_iter);
+    /// Draws a heat overlay of the levels of edges, e.g. their pheromones, between the nodes at
+    /// points. Edges with higher levels get warmer colors and thicker strokes and are drawn on
+    /// top of the others.
+    pub fn heatmap<W: Write>(
+        &self,
+        points: Vec<Point>,
+        mut levels: Vec<(usize, usize, f64)>,
+        writer: W,
+    ) -> io::Result<()> {
+        levels.sort_by(|a, b| a.2.total_cmp(&b.2));
+        let nodes = points.into_iter().map(|point| (point, 0.0)).collect();
+        self.render(nodes, levels, true, writer)
+    }
 
-        let nodes: Vec<(Point, &str)> = graph
-            .iter_nodes()
-            .map(|(_, weight)| (self.scaled_point(&weight.0, &scaler), "black"))
-            .collect();
-        // let nodes = Vec::<(Point, &str)>::new();
-
-        let paths: Vec<(String, &str)> = graph
-            .iter_edge_ids()
-            .map(|(f_id, t_id)| {
-                let p1 = self.scaled_point(&graph.node_weight(f_id).unwrap().0, &scaler);
-                let p2 = self.scaled_point(&graph.node_weight(t_id).unwrap().0, &scaler);
-                (format!("M {} {} L {} {}", p1.x, p1.y, p2.x, p2.y), "black")
+    /// Exports a graph without coordinates with its nodes at the positions of layout.
+    pub fn export_with_layout<Nw, Ew, W>(
+        &self,
+        graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+        layout: &Layout,
+        writer: W,
+    ) -> io::Result<()>
+    where
+        Nw: Copy + Into<f64>,
+        Ew: Copy + Into<f64>,
+        W: Write,
+    {
+        let ids = graph.node_ids();
+        let nodes = ids
+            .iter()
+            .map(|id| match (layout.get(id), graph.node_weight(*id)) {
+                (Some(&point), Ok(&weight)) => Ok((point, weight.into())),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Node {} has no position in the layout.", id),
+                )),
             })
+            .collect::<io::Result<_>>()?;
+        let index = indices(&ids);
+        let edges = graph
+            .iter_edges()
+            .map(|((from, to), &weight)| (index[&from], index[&to], weight.into()))
             .collect();
 
-        context.insert("points", &nodes);
-        context.insert("paths", &paths);
-
-        let mut reader = File::open("src/templates/graph.svg").unwrap();
-        let mut template = String::new();
-        reader.read_to_string(&mut template).unwrap();
-        Tera::one_off(&template, &context, true).expect("Could not draw graph")
+        self.render(nodes, edges, false, writer)
     }
 
-    pub fn export_geo_graph<Nw, Ew>(
+    /// Renders nodes at points with their weights and edges between the indices of nodes with
+    /// their weights into writer. With scale_edges, the stroke width grows with the edge weight.
+    fn render<W: Write>(
         &self,
-        graph: &dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
-        name: &str,
-    ) -> String {
+        nodes: Vec<(Point, f64)>,
+        edges: Vec<(usize, usize, f64)>,
+        scale_edges: bool,
+        writer: W,
+    ) -> io::Result<()> {
         let mut context = self.initial_context();
-        context.insert("name", &name);
 
-        let point_iter = graph.iter_node_ids();
-        let scaler = GeoPointScaler::from_point_iterator(point_iter);
+        let points: Vec<Point> = nodes.iter().map(|(point, _)| *point).collect();
+        let frame = Frame::new(&points, self);
+        let points: Vec<Point> = points.iter().map(|point| frame.map(point)).collect();
 
-        let nodes: Vec<(GeoPoint, &str)> = graph
-            .iter_node_ids()
-            .map(|location| (self.scaled_geopoint(&location, &scaler), "black"))
+        let max_weight = range(nodes.iter().map(|&(_, weight)| weight)).1;
+        let circles: Vec<Circle> = nodes
+            .iter()
+            .zip(points.iter())
+            .map(|(&(_, weight), point)| Circle {
+                x: point.x,
+                y: point.y,
+                r: if max_weight > 0.0 {
+                    (self.max_radius * weight.max(0.0) / max_weight).max(1.0)
+                } else {
+                    1.0
+                },
+            })
             .collect();
-        // let nodes = Vec::<(Point, &str)>::new();
 
-        let paths: Vec<(String, &str)> = graph
-            .iter_edge_ids()
-            .map(|(f_id, t_id)| {
-                let p1 = self.scaled_geopoint(&f_id, &scaler);
-                let p2 = self.scaled_geopoint(&t_id, &scaler);
+        let weight_range = range(edges.iter().map(|&(_, _, weight)| weight));
+        let paths: Vec<(String, String, f64)> = edges
+            .iter()
+            .map(|&(from, to, weight)| {
+                let (p1, p2) = (points[from], points[to]);
+                let t = relative(weight, weight_range);
+                let width = if scale_edges {
+                    STROKE_WIDTH * (1.0 + (HEAT_STROKE_FACTOR - 1.0) * t)
+                } else {
+                    STROKE_WIDTH
+                };
                 (
-                    format!("M {} {} L {} {}", p1.lat(), p1.lon(), p2.lat(), p2.lon()),
-                    "black",
+                    format!("M {} {} L {} {}", p1.x, p1.y, p2.x, p2.y),
+                    color(t),
+                    width,
                 )
             })
             .collect();
 
-        context.insert("geopoints", &nodes);
+        context.insert("points", &circles);
         context.insert("paths", &paths);
+        if self.legend && !edges.is_empty() {
+            let stops = (0..COLOR_MAP.len())
+                .map(|i| {
+                    let t = i as f64 / (COLOR_MAP.len() - 1) as f64;
+                    (t * 100.0, color(t))
+                })
+                .collect();
+            let legend = Legend {
+                x: self.padding as f64 + self.width as f64 / 4.0,
+                y: (self.height + self.padding) as f64 + self.padding as f64 * 0.4,
+                width: self.width as f64 / 2.0,
+                height: self.padding as f64 * 0.2,
+                stops,
+                min: format!("{:.2}", weight_range.0),
+                max: format!("{:.2}", weight_range.1),
+            };
+            context.insert("legend", &legend);
+        }
 
-        let mut reader = File::open("src/templates/graph.svg").unwrap();
         let mut template = String::new();
-        reader.read_to_string(&mut template).unwrap();
-        Tera::one_off(&template, &context, true).expect("Could not draw graph")
+        File::open("src/templates/graph.svg")?.read_to_string(&mut template)?;
+        let to_io_error = io::Error::other;
+        let mut tera = Tera::default();
+        tera.add_raw_template("graph.svg", &template)
+            .map_err(to_io_error)?;
+        tera.autoescape_on(vec!["graph.svg"]);
+        tera.render_to("graph.svg", &context, writer)
+            .map_err(to_io_error)
+    }
+}
+
+impl<Nw, Ew> Export<dyn WeightedGraph<NodeWeightType = (Point, Nw), EdgeWeightType = Ew>> for Svg
+where
+    Nw: Copy + Into<f64>,
+    Ew: Copy + Into<f64>,
+{
+    /// Exports a graph with coordinates and weights as node weights.
+    fn export<W: Write>(
+        &self,
+        graph: &dyn WeightedGraph<NodeWeightType = (Point, Nw), EdgeWeightType = Ew>,
+        writer: W,
+    ) -> io::Result<()> {
+        let ids = graph.node_ids();
+        let nodes = ids
+            .iter()
+            .map(|&id| {
+                let (point, weight) = graph.node_weight(id).unwrap();
+                (*point, (*weight).into())
+            })
+            .collect();
+        let index = indices(&ids);
+        let edges = graph
+            .iter_edges()
+            .map(|((from, to), &weight)| (index[&from], index[&to], weight.into()))
+            .collect();
+
+        self.render(nodes, edges, false, writer)
+    }
+}
+
+impl<Nw, Ew> Export<dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>> for Svg
+where
+    Nw: Copy + Into<f64>,
+    Ew: Copy + Into<f64>,
+{
+    /// Exports a graph with locations as node ids, projected around their mean latitude.
+    fn export<W: Write>(
+        &self,
+        graph: &dyn GeoGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+        writer: W,
+    ) -> io::Result<()> {
+        let ids = graph.node_ids();
+        let nodes = GeoPoint::locate(&ids, None)
+            .unwrap()
+            .into_iter()
+            .zip(ids.iter())
+            .map(|(point, &id)| (point, (*graph.node_weight(id).unwrap()).into()))
+            .collect();
+        let index = indices(&ids);
+        let edges = graph
+            .iter_edges()
+            .map(|((from, to), &weight)| (index[&from], index[&to], weight.into()))
+            .collect();
+
+        self.render(nodes, edges, false, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use decorum::R64;
+
+    #[test]
+    fn colors_are_interpolated() {
+        assert_eq!(color(0.0), "#440154");
+        assert_eq!(color(1.0), "#fde725");
+        assert_eq!(color(2.0), "#fde725");
+        assert_eq!(color(0.125), "#402a70");
+    }
+
+    #[test]
+    fn coordinates_keep_their_proportions() {
+        let svg = Svg::new(100, 100);
+        let points = vec![Point { x: 0.0, y: 0.0 }, Point { x: 2.0, y: 1.0 }];
+        let frame = Frame::new(&points, &svg);
+
+        // the wider axis fills the canvas, the other one is centered
+        assert_eq!(frame.map(&points[0]), Point { x: 50.0, y: 125.0 });
+        assert_eq!(frame.map(&points[1]), Point { x: 150.0, y: 75.0 });
+    }
+
+    #[test]
+    fn geo_graphs_are_drawn_with_legend() {
+        let r = R64::from_inner;
+        let a = GeoPoint::from_degrees(52.0, 13.0);
+        let b = GeoPoint::from_degrees(52.1, 13.1);
+        let graph = MatrixGraph::new(
+            vec![(a, r(0.0)), (b, r(2.0))],
+            vec![((a, b), r(1.0)), ((b, a), r(3.0))],
+        )
+        .unwrap();
+
+        let graph: &dyn GeoGraph<NodeWeightType = R64, EdgeWeightType = R64> = &graph;
+
+        let svg = Svg::default().with_title("test").export_to_string(graph);
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains("r=\"6\""));
+        assert!(svg.contains("stroke=\"#440154\""));
+        assert!(svg.contains("stroke=\"#fde725\""));
+        assert!(svg.contains("3.00"));
+
+        let svg = Svg {
+            legend: false,
+            ..Svg::default()
+        }
+        .export_to_string(graph);
+        assert!(!svg.contains("linearGradient"));
+    }
+
+    #[test]
+    fn graphs_are_drawn_with_layouts() {
+        let r = R64::from_inner;
+        let graph =
+            MatrixGraph::new_usize_indexed(vec![r(1.0), r(1.0)], vec![(0, 1, r(1.0))]).unwrap();
+        let graph: &dyn WeightedGraph<NodeWeightType = R64, EdgeWeightType = R64> = &graph;
+
+        let layout = crate::graph::layout::grid((2, 1));
+        let mut out = Vec::new();
+        Svg::new(100, 100)
+            .export_with_layout(graph, &layout, &mut out)
+            .unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains("cx=\"50\" cy=\"100\""));
+        assert!(svg.contains("cx=\"150\" cy=\"100\""));
+
+        let layout = crate::graph::layout::grid((1, 1));
+        assert!(Svg::default()
+            .export_with_layout(graph, &layout, Vec::new())
+            .is_err());
+    }
+
+    #[test]
+    fn heatmap_edges_grow_with_their_level() {
+        let points = vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }];
+        let mut out = Vec::new();
+        Svg::default()
+            .heatmap(points, vec![(1, 0, 4.0), (0, 1, 1.0)], &mut out)
+            .unwrap();
+
+        let svg = String::from_utf8(out).unwrap();
+        let weak = svg.find("stroke=\"#440154\"").unwrap();
+        let strong = svg.find("stroke=\"#fde725\"").unwrap();
+        // the strongest trail is drawn last, with the widest stroke
+        assert!(weak < strong);
+        assert!(svg.contains("stroke-width=\"2\""));
+        assert!(svg.contains("stroke-width=\"8\""));
     }
 }
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::graph::GenericWeightedGraph;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Stable content hash of a graph, so results of identical instances can be grouped and
+/// shared, even if they come from different configs.
+/// The hash covers the node ids, the edges and all weights, rounded to multiples of a tolerance.
+/// It doesn't depend on the order in which nodes and edges were added, but relabeling the
+/// nodes changes it, isomorphic graphs are not detected.
+/// Weights close to the middle between two multiples of the tolerance can be rounded apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Fingerprint(pub u64);
+
+impl Fingerprint {
+    /// Weights closer than this are treated as equal by fingerprints of experiment graphs.
+    pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+    pub fn of<G>(graph: &G, tolerance: f64) -> Self
+    where
+        G: GenericWeightedGraph + ?Sized,
+        G::NodeWeightType: Copy + Into<f64>,
+        G::EdgeWeightType: Copy + Into<f64>,
+    {
+        let quantize = |weight: f64| (weight / tolerance).round() as i64;
+        let mut nodes: Vec<(String, i64)> = graph
+            .iter_nodes()
+            .map(|(id, &weight)| (id.to_string(), quantize(weight.into())))
+            .collect();
+        nodes.sort_unstable();
+        let mut edges: Vec<(String, String, i64)> = graph
+            .iter_edges()
+            .map(|((from, to), &weight)| {
+                (from.to_string(), to.to_string(), quantize(weight.into()))
+            })
+            .collect();
+        edges.sort_unstable();
+
+        let mut hash = Fnv(FNV_OFFSET);
+        hash.write_usize(nodes.len());
+        for (id, weight) in &nodes {
+            hash.write_str(id);
+            hash.write(&weight.to_le_bytes());
+        }
+        hash.write_usize(edges.len());
+        for (from, to, weight) in &edges {
+            hash.write_str(from);
+            hash.write_str(to);
+            hash.write(&weight.to_le_bytes());
+        }
+
+        Fingerprint(hash.0)
+    }
+}
+
+/// Returns the FNV-1a hash of bytes, which stays the same across platforms and compiler
+/// versions, unlike the hasher of the standard library.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash = Fnv(FNV_OFFSET);
+    hash.write(bytes);
+    hash.0
+}
+
+/// FNV-1a, which unlike the hasher of the standard library is guaranteed to stay the same.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    // the length prefix keeps adjacent ids like 1, 23 and 12, 3 apart
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.write(value.as_bytes());
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    use decorum::R64;
+
+    fn graph(nodes: Vec<f64>, edges: Vec<(usize, usize, f64)>) -> MatrixGraph<usize, R64, R64> {
+        MatrixGraph::new_usize_indexed(
+            nodes.into_iter().map(R64::from_inner).collect(),
+            edges
+                .into_iter()
+                .map(|(from, to, weight)| (from, to, R64::from_inner(weight)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn equal_graphs_have_equal_fingerprints() {
+        let first = graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5), (1, 2, 2.5)]);
+        let reordered = graph(vec![0.0, 1.0, 2.0], vec![(1, 2, 2.5), (0, 1, 1.5)]);
+        let close = graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5 + 1e-12), (1, 2, 2.5)]);
+
+        let fingerprint = Fingerprint::of(&first, Fingerprint::DEFAULT_TOLERANCE);
+        assert_eq!(
+            fingerprint,
+            Fingerprint::of(&reordered, Fingerprint::DEFAULT_TOLERANCE)
+        );
+        assert_eq!(
+            fingerprint,
+            Fingerprint::of(&close, Fingerprint::DEFAULT_TOLERANCE)
+        );
+        assert_eq!(fingerprint.to_string().len(), 16);
+    }
+
+    #[test]
+    fn changes_change_the_fingerprint() {
+        let base = graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5), (1, 2, 2.5)]);
+        let fingerprint = Fingerprint::of(&base, Fingerprint::DEFAULT_TOLERANCE);
+
+        let others = [
+            graph(vec![0.0, 1.0, 3.0], vec![(0, 1, 1.5), (1, 2, 2.5)]),
+            graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5), (1, 2, 2.6)]),
+            graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5), (2, 1, 2.5)]),
+            graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5)]),
+        ];
+        for other in others.iter() {
+            assert_ne!(
+                fingerprint,
+                Fingerprint::of(other, Fingerprint::DEFAULT_TOLERANCE)
+            );
+        }
+        assert_eq!(
+            Fingerprint::of(&base, 0.5),
+            Fingerprint::of(
+                &graph(vec![0.0, 1.0, 2.0], vec![(0, 1, 1.5), (1, 2, 2.6)]),
+                0.5
+            ),
+            "2.5 and 2.6 are equal with a tolerance of 0.5"
+        );
+    }
+}
@@ -2,11 +2,13 @@
 mod erdos_renyi;
 #[allow(dead_code)]
 mod grid;
+mod planted_route;
 #[allow(dead_code)]
 mod stochastic_block;
 
 pub use erdos_renyi::ErdosRenyi;
 pub use grid::Grid;
+pub use planted_route::PlantedRoute;
 pub use stochastic_block::StochasticBlock;
 
 use crate::graph::MatrixGraph;
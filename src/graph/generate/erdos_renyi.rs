@@ -1,6 +1,6 @@
 use super::Generate;
 use crate::graph::{GenericWeightedGraph, MatrixGraph};
-use crate::rng::preseeded_rng64;
+use crate::rng::{gen_range, preseeded_rng64};
 use crate::util::Max;
 
 use num_traits::Zero;
@@ -16,6 +16,10 @@ where
     connection_probability: f64,
     nw_generator: &'a mut dyn FnMut() -> Nw,
     ew_generator: &'a mut dyn FnMut() -> Ew,
+    min_out_degree: usize,
+    repair_goal: Option<usize>,
+    degree_edges: usize,
+    repaired_edges: usize,
 }
 
 impl<'a, Nw: Clone, Ew: Clone> ErdosRenyi<'a, Nw, Ew> {
@@ -30,8 +34,39 @@ impl<'a, Nw: Clone, Ew: Clone> ErdosRenyi<'a, Nw, Ew> {
             connection_probability,
             nw_generator,
             ew_generator,
+            min_out_degree: 0,
+            repair_goal: None,
+            degree_edges: 0,
+            repaired_edges: 0,
         }
     }
+
+    /// Adds edges to random other nodes, until every node has at least degree outgoing edges,
+    /// or is connected to all other nodes.
+    pub fn with_min_out_degree(mut self, degree: usize) -> Self {
+        self.min_out_degree = degree;
+        self
+    }
+
+    /// Adds edges after generation, so goal is reachable from all nodes and all nodes are
+    /// reachable from goal. The graph is strongly connected afterwards, so every node can serve
+    /// as goal point.
+    /// Each pass adds the fewest edges possible, one for every strongly connected component
+    /// without a path to the goal or without a path from it.
+    pub fn with_connectivity_repair(mut self, goal: usize) -> Self {
+        self.repair_goal = Some(goal);
+        self
+    }
+
+    /// Returns how many edges the last generation added for the minimum out degree.
+    pub fn degree_edges(&self) -> usize {
+        self.degree_edges
+    }
+
+    /// Returns how many edges the last generation added to repair the connectivity.
+    pub fn repaired_edges(&self) -> usize {
+        self.repaired_edges
+    }
 }
 
 impl<'a, Nw, Ew> Generate<Nw, Ew> for ErdosRenyi<'a, Nw, Ew>
@@ -41,7 +76,7 @@ where
 {
     fn generate(&mut self) -> MatrixGraph<usize, Nw, Ew> {
         let mut rng = preseeded_rng64();
-        let mut graph = MatrixGraph::<usize, Nw, Ew>::with_size(self.size);
+        let mut graph = MatrixGraph::<usize, Nw, Ew>::with_size_usize_indexed(self.size);
 
         // Populate nodes with random weights in range.
         for i in 0..self.size {
@@ -59,6 +94,223 @@ where
             }
         }
 
+        self.degree_edges = 0;
+        for i in 0..self.size {
+            let mut candidates: Vec<usize> = (0..self.size)
+                .filter(|&j| j != i && !graph.has_edge((i, j)))
+                .collect();
+            let mut degree = self.size - 1 - candidates.len();
+            while degree < self.min_out_degree && !candidates.is_empty() {
+                let j = candidates.swap_remove(gen_range(&mut rng, 0..candidates.len()));
+                graph.add_edge((i, j), (self.ew_generator)()).unwrap();
+                self.degree_edges += 1;
+                degree += 1;
+            }
+        }
+
+        self.repaired_edges = 0;
+        if let Some(goal) = self.repair_goal.filter(|&goal| goal < self.size) {
+            // nodes without a path to the goal get an edge to it
+            let (outgoing, incoming) = adjacency(&graph, self.size);
+            let reaching = reached(&incoming, goal);
+            let unreaching: Vec<bool> = reaching.iter().map(|&r| !r).collect();
+            for node in sink_components(&outgoing, &unreaching) {
+                graph.add_edge((node, goal), (self.ew_generator)()).unwrap();
+                self.repaired_edges += 1;
+            }
+
+            // nodes without a path from the goal get an edge from it
+            let (outgoing, incoming) = adjacency(&graph, self.size);
+            let unreached: Vec<bool> = reached(&outgoing, goal).iter().map(|&r| !r).collect();
+            for node in sink_components(&incoming, &unreached) {
+                graph.add_edge((goal, node), (self.ew_generator)()).unwrap();
+                self.repaired_edges += 1;
+            }
+        }
+
         graph
     }
 }
+
+/// Returns the outgoing and incoming neighbors of every node.
+fn adjacency<Nw, Ew>(
+    graph: &MatrixGraph<usize, Nw, Ew>,
+    size: usize,
+) -> (Vec<Vec<usize>>, Vec<Vec<usize>>)
+where
+    Nw: Copy,
+    Ew: Copy + Ord + Zero + Debug + Add + Max,
+{
+    let mut outgoing = vec![Vec::new(); size];
+    let mut incoming = vec![Vec::new(); size];
+    for (from, to) in graph.iter_edge_ids() {
+        outgoing[from].push(to);
+        incoming[to].push(from);
+    }
+    (outgoing, incoming)
+}
+
+/// Returns which nodes are reachable from start by following adjacency.
+fn reached(adjacency: &[Vec<usize>], start: usize) -> Vec<bool> {
+    let mut reached = vec![false; adjacency.len()];
+    reached[start] = true;
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        for &next in &adjacency[node] {
+            if !reached[next] {
+                reached[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Returns the smallest node of every strongly connected component of the subgraph induced by
+/// the nodes in subgraph, which has no edges to other components of the subgraph.
+/// The components are found with Kosaraju's algorithm.
+fn sink_components(adjacency: &[Vec<usize>], subgraph: &[bool]) -> Vec<usize> {
+    let size = adjacency.len();
+    let mut reverse = vec![Vec::new(); size];
+    for from in (0..size).filter(|&node| subgraph[node]) {
+        for &to in adjacency[from].iter().filter(|&&to| subgraph[to]) {
+            reverse[to].push(from);
+        }
+    }
+
+    // orders the nodes by the time their depth first search finishes
+    let mut visited = vec![false; size];
+    let mut order = Vec::new();
+    for start in (0..size).filter(|&node| subgraph[node]) {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some(top) = stack.last_mut() {
+            let node = top.0;
+            match adjacency[node].get(top.1) {
+                Some(&next) => {
+                    top.1 += 1;
+                    if subgraph[next] && !visited[next] {
+                        visited[next] = true;
+                        stack.push((next, 0));
+                    }
+                }
+                None => {
+                    order.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut component = vec![None; size];
+    let mut count = 0;
+    for &start in order.iter().rev() {
+        if component[start].is_some() {
+            continue;
+        }
+        component[start] = Some(count);
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &next in &reverse[node] {
+                if component[next].is_none() {
+                    component[next] = Some(count);
+                    stack.push(next);
+                }
+            }
+        }
+        count += 1;
+    }
+
+    let mut is_sink = vec![true; count];
+    for from in (0..size).filter(|&node| subgraph[node]) {
+        for &to in adjacency[from].iter().filter(|&&to| subgraph[to]) {
+            if component[from] != component[to] {
+                is_sink[component[from].unwrap()] = false;
+            }
+        }
+    }
+    let mut representatives = Vec::new();
+    for (node, &c) in component.iter().enumerate() {
+        if let Some(c) = c {
+            if is_sink[c] {
+                is_sink[c] = false;
+                representatives.push(node);
+            }
+        }
+    }
+    representatives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use decorum::R64;
+
+    fn generator<'a>(
+        size: usize,
+        connection_probability: f64,
+        nw_generator: &'a mut dyn FnMut() -> R64,
+        ew_generator: &'a mut dyn FnMut() -> R64,
+    ) -> ErdosRenyi<'a, R64, R64> {
+        ErdosRenyi::new(size, connection_probability, nw_generator, ew_generator)
+    }
+
+    #[test]
+    fn min_out_degree_is_guaranteed() {
+        let mut node_gen = || R64::from_inner(1.0);
+        let mut edge_gen = || R64::from_inner(2.0);
+        let mut gen = generator(6, 0.0, &mut node_gen, &mut edge_gen).with_min_out_degree(2);
+        let graph = gen.generate();
+
+        for node in 0..6 {
+            let others = graph
+                .iter_neighbor_ids(node)
+                .unwrap()
+                .filter(|&neighbor| neighbor != node)
+                .count();
+            assert_eq!(others, 2, "node {} should have 2 outgoing edges", node);
+        }
+        assert_eq!(gen.degree_edges(), 12);
+        assert_eq!(gen.repaired_edges(), 0);
+    }
+
+    #[test]
+    fn repair_makes_the_graph_strongly_connected() {
+        let mut node_gen = || R64::from_inner(1.0);
+        let mut edge_gen = || R64::from_inner(2.0);
+        let mut gen = generator(6, 0.0, &mut node_gen, &mut edge_gen).with_connectivity_repair(0);
+        let graph = gen.generate();
+
+        // every isolated node needs an edge to and one from the goal
+        assert_eq!(gen.repaired_edges(), 10);
+        let (outgoing, incoming) = adjacency(&graph, 6);
+        assert!(reached(&outgoing, 0).iter().all(|&r| r));
+        assert!(reached(&incoming, 0).iter().all(|&r| r));
+    }
+
+    #[test]
+    fn repair_adds_one_edge_per_component() {
+        // 1 <-> 2 and 3 <-> 4 are cycles, which can't reach the goal 0, 5 reaches 1
+        let adjacency = vec![vec![5], vec![2], vec![1], vec![4], vec![3], vec![1]];
+        let subgraph = vec![false, true, true, true, true, true];
+        assert_eq!(sink_components(&adjacency, &subgraph), vec![1, 3]);
+    }
+
+    #[test]
+    fn connected_graphs_are_not_repaired() {
+        let mut node_gen = || R64::from_inner(1.0);
+        let mut edge_gen = || R64::from_inner(2.0);
+        let mut gen = generator(5, 1.0, &mut node_gen, &mut edge_gen)
+            .with_min_out_degree(3)
+            .with_connectivity_repair(2);
+        let graph = gen.generate();
+
+        assert_eq!(graph.size(), 25);
+        assert_eq!(gen.degree_edges(), 0);
+        assert_eq!(gen.repaired_edges(), 0);
+    }
+}
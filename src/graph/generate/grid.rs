@@ -7,7 +7,15 @@ use num_traits::Zero;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use std::ops::Add;
+use std::ops::{Add, Mul};
+
+/// Orientation of a grid edge, i runs horizontally and j vertically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
 
 pub struct Grid<'a, Nw, Ew>
 where
@@ -17,6 +25,9 @@ where
     size: (usize, usize),
     nw_generator: &'a mut dyn FnMut() -> Nw,
     ew_generator: &'a mut dyn FnMut() -> Ew,
+    horizontal_generator: Option<&'a mut dyn FnMut() -> Ew>,
+    vertical_generator: Option<&'a mut dyn FnMut() -> Ew>,
+    asymmetry: Option<Ew>,
     phantom: PhantomData<(Nw, Ew)>,
 }
 
@@ -30,9 +41,54 @@ impl<'a, Nw: Clone, Ew: Clone> Grid<'a, Nw, Ew> {
             size,
             nw_generator,
             ew_generator,
+            horizontal_generator: None,
+            vertical_generator: None,
+            asymmetry: None,
             phantom: PhantomData,
         }
     }
+
+    /// Weights of edges between horizontal neighbors are drawn from generator instead of the
+    /// edge weight generator.
+    pub fn with_horizontal_weights(mut self, generator: &'a mut dyn FnMut() -> Ew) -> Self {
+        self.horizontal_generator = Some(generator);
+        self
+    }
+
+    /// Weights of edges between vertical neighbors are drawn from generator instead of the
+    /// edge weight generator.
+    pub fn with_vertical_weights(mut self, generator: &'a mut dyn FnMut() -> Ew) -> Self {
+        self.vertical_generator = Some(generator);
+        self
+    }
+
+    /// Multiplies the weights of all edges leading right or down by factor, so the grid
+    /// resembles streets, which are cheaper to travel in one direction.
+    /// Edges leading left or up keep their drawn weight.
+    pub fn with_asymmetry(mut self, factor: Ew) -> Self {
+        self.asymmetry = Some(factor);
+        self
+    }
+
+    /// Draws the weight of an edge, forward edges lead towards higher coordinates.
+    fn edge_weight(&mut self, orientation: Orientation, forward: bool) -> Ew
+    where
+        Ew: Copy + Mul<Output = Ew>,
+    {
+        let generator = match orientation {
+            Orientation::Horizontal => self.horizontal_generator.as_mut(),
+            Orientation::Vertical => self.vertical_generator.as_mut(),
+            Orientation::Diagonal => None,
+        };
+        let weight = match generator {
+            Some(generator) => generator(),
+            None => (self.ew_generator)(),
+        };
+        match self.asymmetry {
+            Some(factor) if forward => weight * factor,
+            _ => weight,
+        }
+    }
 }
 
 /// 'static lifetime needed here. See https://stackoverflow.com/questions/32625583/parameter-type-may-not-live-long-enough for explanation.
@@ -40,10 +96,11 @@ impl<'a, Nw: Clone, Ew: Clone> Grid<'a, Nw, Ew> {
 impl<'a, Nw, Ew> Generate<Nw, Ew> for Grid<'a, Nw, Ew>
 where
     Nw: 'static + Copy + Debug,
-    Ew: 'static + Copy + Ord + Zero + Debug + Add + Max,
+    Ew: 'static + Copy + Ord + Zero + Debug + Add + Mul<Output = Ew> + Max,
 {
     fn generate(&mut self) -> MatrixGraph<usize, Nw, Ew> {
-        let mut graph = MatrixGraph::<usize, Nw, Ew>::with_size(self.size.0 * self.size.1);
+        let mut graph =
+            MatrixGraph::<usize, Nw, Ew>::with_size_usize_indexed(self.size.0 * self.size.1);
 
         // count is used to generate consecutive numbered ids.
         // This means we need to remember which id an abstract (i, j) edge corresponds to.
@@ -65,7 +122,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i + 1, j)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Horizontal, true),
                         )
                         .unwrap();
                 }
@@ -74,7 +131,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i - 1, j)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Horizontal, false),
                         )
                         .unwrap();
                 }
@@ -83,7 +140,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i, j + 1)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Vertical, true),
                         )
                         .unwrap();
                 }
@@ -92,7 +149,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i, j - 1)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Vertical, false),
                         )
                         .unwrap();
                 }
@@ -101,7 +158,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i + 1, j + 1)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Diagonal, true),
                         )
                         .unwrap();
                 }
@@ -110,7 +167,7 @@ where
                     graph
                         .add_edge(
                             (id_map[&(i, j)], id_map[&(i - 1, j - 1)]),
-                            (self.ew_generator)(),
+                            self.edge_weight(Orientation::Diagonal, false),
                         )
                         .unwrap();
                 }
@@ -192,4 +249,27 @@ mod tests {
             "A 5x5 triangular grid graph should have 112 edges."
         );
     }
+
+    #[test]
+    fn direction_weights_work() {
+        let mut node_gen = || R64::from_inner(1.0);
+        let mut edge_gen = || R64::from_inner(2.0);
+        let mut horizontal_gen = || R64::from_inner(3.0);
+        let mut vertical_gen = || R64::from_inner(4.0);
+        let graph = Grid::new((3, 2), &mut node_gen, &mut edge_gen)
+            .with_horizontal_weights(&mut horizontal_gen)
+            .with_vertical_weights(&mut vertical_gen)
+            .with_asymmetry(R64::from_inner(0.5))
+            .generate();
+
+        assert_eq!(graph.order(), 6, "A 3x2 grid graph should have 6 nodes.");
+        // node (i, j) has id 2 * i + j
+        let weight = |from, to| *graph.edge_weight((from, to)).unwrap();
+        assert_eq!(weight(0, 2), 1.5, "Edges leading right are cheaper.");
+        assert_eq!(weight(2, 0), 3.0);
+        assert_eq!(weight(0, 1), 2.0, "Edges leading down are cheaper.");
+        assert_eq!(weight(1, 0), 4.0);
+        assert_eq!(weight(0, 3), 1.0, "Diagonal edges use the edge generator.");
+        assert_eq!(weight(3, 0), 2.0);
+    }
 }
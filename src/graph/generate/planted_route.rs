@@ -0,0 +1,190 @@
+use super::Generate;
+use crate::graph::{GenericWeightedGraph, MatrixGraph};
+use crate::metaheuristic::Solution;
+use crate::rng::{gen_range, Rng};
+
+use decorum::R64;
+use num_traits::Zero;
+
+/// Generates graphs around a planted route with known optimal score, to measure how well
+/// algorithms recover optimal solutions.
+/// The route starts and ends at the goal point 0 and visits route_size - 1 other nodes, whose
+/// rewards are drawn from the node weight generator. Its edges split the budget evenly, leaving
+/// the share of one edge as slack, so the route is feasible.
+/// All other nodes are decoys with rewards as well, but each of their edges is longer than half
+/// the budget. A route has to enter and leave a decoy, so no feasible route visits one and the
+/// planted route collects all rewards, which can be collected within the budget.
+/// Noise edges between route nodes can shorten the route, but can't improve its score.
+/// The edge weight generator has to produce positive weights.
+pub struct PlantedRoute<'a> {
+    size: usize,
+    route_size: usize,
+    budget: R64,
+    noise_probability: f64,
+    nw_generator: &'a mut dyn FnMut() -> R64,
+    ew_generator: &'a mut dyn FnMut() -> R64,
+    rng: &'a mut Rng,
+    route: Solution<usize>,
+    score: R64,
+    length: R64,
+}
+
+impl<'a> PlantedRoute<'a> {
+    pub fn new(
+        size: usize,
+        route_size: usize,
+        budget: R64,
+        nw_generator: &'a mut dyn FnMut() -> R64,
+        ew_generator: &'a mut dyn FnMut() -> R64,
+        rng: &'a mut Rng,
+    ) -> PlantedRoute<'a> {
+        PlantedRoute {
+            size,
+            route_size: route_size.clamp(1, size.max(1)),
+            budget,
+            noise_probability: 0.0,
+            nw_generator,
+            ew_generator,
+            rng,
+            route: Solution::new(),
+            score: R64::zero(),
+            length: R64::zero(),
+        }
+    }
+
+    /// Adds every possible edge besides the planted ones with probability.
+    pub fn with_noise(mut self, probability: f64) -> Self {
+        self.noise_probability = probability;
+        self
+    }
+
+    /// Returns the route planted by the last generation.
+    pub fn route(&self) -> &Solution<usize> {
+        &self.route
+    }
+
+    /// Returns the score of the planted route, which is optimal.
+    pub fn score(&self) -> R64 {
+        self.score
+    }
+
+    /// Returns the length of the planted route, shorter routes with the same score can exist.
+    pub fn length(&self) -> R64 {
+        self.length
+    }
+}
+
+impl<'a> Generate<R64, R64> for PlantedRoute<'a> {
+    fn generate(&mut self) -> MatrixGraph<usize, R64, R64> {
+        let mut graph = MatrixGraph::<usize, R64, R64>::with_size_usize_indexed(self.size);
+
+        // the goal point is 0, the other route nodes are a random selection of the rest
+        let mut others: Vec<usize> = (1..self.size).collect();
+        let mut route = vec![0];
+        while route.len() < self.route_size {
+            let index = gen_range(self.rng, 0..others.len());
+            route.push(others.swap_remove(index));
+        }
+        let mut on_route = vec![false; self.size];
+        for &node in route.iter() {
+            on_route[node] = true;
+        }
+
+        self.score = R64::zero();
+        for (node, &planted) in on_route.iter().enumerate() {
+            let weight = if node == 0 {
+                R64::zero()
+            } else {
+                (self.nw_generator)()
+            };
+            if planted {
+                self.score += weight;
+            }
+            // Unwrapping is fine, because the graph was just created, so we cant insert duplicates.
+            graph.add_node(node, weight).unwrap();
+        }
+
+        self.length = R64::zero();
+        if route.len() > 1 {
+            route.push(0);
+            let edge_weight = self.budget / R64::from_inner(route.len() as f64);
+            for edge in route.windows(2) {
+                graph.add_edge((edge[0], edge[1]), edge_weight).unwrap();
+                self.length += edge_weight;
+            }
+        }
+        self.route = Solution::from_nodes(route);
+
+        let half_budget = self.budget / R64::from_inner(2.0);
+        for from in 0..self.size {
+            for to in (0..self.size).filter(|&to| to != from) {
+                if graph.has_edge((from, to)) || self.rng.rand_float() >= self.noise_probability {
+                    continue;
+                }
+                let weight = if on_route[from] && on_route[to] {
+                    (self.ew_generator)()
+                } else {
+                    half_budget + (self.ew_generator)()
+                };
+                graph.add_edge((from, to), weight).unwrap();
+            }
+        }
+
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::preseeded_rng64;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn planted_route_is_feasible_and_collects_all_route_rewards() {
+        let mut node_gen = || r(2.0);
+        let mut edge_gen = || r(1.0);
+        let mut rng = preseeded_rng64();
+        let mut gen = PlantedRoute::new(10, 4, r(20.0), &mut node_gen, &mut edge_gen, &mut rng)
+            .with_noise(0.5);
+        let graph = gen.generate();
+
+        let route = gen.route().nodes();
+        assert_eq!(route.len(), 5);
+        assert_eq!((route[0], route[4]), (0, 0));
+        assert_eq!(gen.score(), r(6.0), "the goal point has no reward");
+        assert_eq!(gen.length(), r(16.0));
+        let length: R64 = route
+            .windows(2)
+            .map(|edge| *graph.edge_weight((edge[0], edge[1])).unwrap())
+            .sum();
+        assert_eq!(length, gen.length());
+    }
+
+    #[test]
+    fn decoys_can_not_be_visited_within_the_budget() {
+        let mut node_gen = || r(2.0);
+        let mut edge_gen = || r(0.5);
+        let mut rng = preseeded_rng64();
+        let mut gen = PlantedRoute::new(8, 3, r(12.0), &mut node_gen, &mut edge_gen, &mut rng)
+            .with_noise(1.0);
+        let graph = gen.generate();
+
+        let route = gen.route().nodes();
+        for ((from, to), &weight) in graph.iter_edges() {
+            if route.contains(&from) && route.contains(&to) {
+                continue;
+            }
+            assert!(
+                weight > r(6.0),
+                "edge ({}, {}) of a decoy is too short",
+                from,
+                to
+            );
+        }
+        assert_eq!(graph.size(), 8 * 7);
+    }
+}
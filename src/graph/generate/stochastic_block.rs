@@ -1,10 +1,9 @@
 use super::Generate;
 use crate::graph::{GenericWeightedGraph, MatrixGraph};
-use crate::rng::preseeded_rng64;
+use crate::rng::{preseeded_rng64, Rng};
 use crate::util::Max;
 
 use num_traits::Zero;
-use oorandom::Rand64;
 use std::fmt::Debug;
 use std::ops::Add;
 
@@ -15,18 +14,18 @@ where
 {
     probability_matrix: Vec<Vec<f64>>,
     community_size: usize,
-    nw_generator: &'a dyn Fn(Rand64) -> Nw,
-    ew_generator: &'a dyn Fn(Rand64) -> Ew,
-    rng: &'a mut Rand64,
+    nw_generator: &'a dyn Fn(Rng) -> Nw,
+    ew_generator: &'a dyn Fn(Rng) -> Ew,
+    rng: &'a mut Rng,
 }
 
 impl<'a, Nw: Clone, Ew: Clone> StochasticBlock<'a, Nw, Ew> {
     pub fn new(
         probability_matrix: Vec<Vec<f64>>,
         community_size: usize,
-        nw_generator: &'a dyn Fn(Rand64) -> Nw,
-        ew_generator: &'a dyn Fn(Rand64) -> Ew,
-        rng: &'a mut Rand64,
+        nw_generator: &'a dyn Fn(Rng) -> Nw,
+        ew_generator: &'a dyn Fn(Rng) -> Ew,
+        rng: &'a mut Rng,
     ) -> StochasticBlock<'a, Nw, Ew> {
         StochasticBlock {
             probability_matrix,
@@ -45,7 +44,7 @@ where
 {
     fn generate(&mut self) -> MatrixGraph<usize, Nw, Ew> {
         let size = self.community_size * self.probability_matrix.len();
-        let mut graph = MatrixGraph::<usize, Nw, Ew>::with_size(size);
+        let mut graph = MatrixGraph::<usize, Nw, Ew>::with_size_usize_indexed(size);
         let mut rng = preseeded_rng64();
 
         // Populate nodes with random weights in range.
@@ -1,3 +0,0 @@
-// mod matrix_graph;
-
-// pub use matrix_graph::MatrixGraph;
@@ -0,0 +1,249 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Shared ownership of a graph, which hands out guards for reading and exclusive writing.
+/// RefCell is the single threaded cell used by the experiments, SyncGraph can be read from
+/// multiple threads at once.
+/// The guards deref to the graph, so its GenericWeightedGraph methods are available on them.
+/// Rc and Arc share the cell they hold, so an Rc<RefCell<G>> or Arc<RwLock<G>> owned by the
+/// caller can be passed to a ProblemInstance directly.
+pub trait GraphCell {
+    type Graph: ?Sized;
+    type Read<'b>: Deref<Target = Self::Graph>
+    where
+        Self: 'b;
+    type Write<'b>: DerefMut<Target = Self::Graph>
+    where
+        Self: 'b;
+
+    /// Returns a guard for reading the graph, which can be held alongside other read guards.
+    fn read(&self) -> Self::Read<'_>;
+
+    /// Returns a guard for changing the graph, which excludes all other guards.
+    fn write(&self) -> Self::Write<'_>;
+}
+
+impl<G: ?Sized> GraphCell for RefCell<G> {
+    type Graph = G;
+    type Read<'b>
+        = Ref<'b, G>
+    where
+        Self: 'b;
+    type Write<'b>
+        = RefMut<'b, G>
+    where
+        Self: 'b;
+
+    /// Panics if the graph is currently written.
+    fn read(&self) -> Ref<'_, G> {
+        self.borrow()
+    }
+
+    /// Panics if the graph is currently read or written.
+    fn write(&self) -> RefMut<'_, G> {
+        self.borrow_mut()
+    }
+}
+
+impl<G: ?Sized> GraphCell for RwLock<G> {
+    type Graph = G;
+    type Read<'b>
+        = RwLockReadGuard<'b, G>
+    where
+        Self: 'b;
+    type Write<'b>
+        = RwLockWriteGuard<'b, G>
+    where
+        Self: 'b;
+
+    fn read(&self) -> RwLockReadGuard<'_, G> {
+        RwLock::read(self).unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, G> {
+        RwLock::write(self).unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<C: GraphCell + ?Sized> GraphCell for Rc<C> {
+    type Graph = C::Graph;
+    type Read<'b>
+        = C::Read<'b>
+    where
+        Self: 'b;
+    type Write<'b>
+        = C::Write<'b>
+    where
+        Self: 'b;
+
+    fn read(&self) -> C::Read<'_> {
+        (**self).read()
+    }
+
+    fn write(&self) -> C::Write<'_> {
+        (**self).write()
+    }
+}
+
+impl<C: GraphCell + ?Sized> GraphCell for Arc<C> {
+    type Graph = C::Graph;
+    type Read<'b>
+        = C::Read<'b>
+    where
+        Self: 'b;
+    type Write<'b>
+        = C::Write<'b>
+    where
+        Self: 'b;
+
+    fn read(&self) -> C::Read<'_> {
+        (**self).read()
+    }
+
+    fn write(&self) -> C::Write<'_> {
+        (**self).write()
+    }
+}
+
+/// Graph behind a RwLock, so ants can read it from multiple threads, while graph dynamics
+/// get exclusive access for their changes.
+/// Reading blocks while the graph is written and the other way around.
+#[derive(Debug, Default)]
+pub struct SyncGraph<G: ?Sized>(RwLock<G>);
+
+impl<G> SyncGraph<G> {
+    pub fn new(graph: G) -> Self {
+        SyncGraph(RwLock::new(graph))
+    }
+
+    pub fn into_inner(self) -> G {
+        self.0
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<G: ?Sized> GraphCell for SyncGraph<G> {
+    type Graph = G;
+    type Read<'b>
+        = RwLockReadGuard<'b, G>
+    where
+        Self: 'b;
+    type Write<'b>
+        = RwLockWriteGuard<'b, G>
+    where
+        Self: 'b;
+
+    /// A writer panicking while holding the lock leaves the graph usable, since changes of
+    /// the dynamics only touch single weights.
+    fn read(&self) -> RwLockReadGuard<'_, G> {
+        self.0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, G> {
+        self.0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GenericWeightedGraph, MatrixGraph};
+
+    use decorum::R64;
+    use std::thread;
+
+    fn graph() -> MatrixGraph<usize, R64, R64> {
+        MatrixGraph::new_usize_indexed(
+            vec![R64::from_inner(0.0), R64::from_inner(1.0)],
+            vec![(0, 1, R64::from_inner(2.0)), (1, 0, R64::from_inner(2.0))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sync_graph_is_read_from_multiple_threads() {
+        let graph = SyncGraph::new(graph());
+        let orders: Vec<usize> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| graph.read().order()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert_eq!(orders, vec![2; 4]);
+    }
+
+    #[test]
+    fn writes_are_visible_to_later_reads() {
+        let graph = SyncGraph::new(graph());
+        *graph.write().node_weight_mut(1).unwrap() = R64::from_inner(5.0);
+        assert_eq!(*graph.read().node_weight(1).unwrap(), R64::from_inner(5.0));
+        assert_eq!(
+            *graph.into_inner().node_weight(1).unwrap(),
+            R64::from_inner(5.0)
+        );
+    }
+
+    #[test]
+    fn sync_graph_holds_graph_trait_objects() {
+        let graph: &SyncGraph<
+            dyn GenericWeightedGraph<IndexType = usize, NodeWeightType = R64, EdgeWeightType = R64>
+                + Send
+                + Sync,
+        > = &SyncGraph::new(graph());
+        assert!(graph.read().has_edge((0, 1)));
+    }
+
+    #[test]
+    fn shared_cells_are_graph_cells() {
+        let shared = Rc::new(RefCell::new(graph()));
+        let clone = Rc::clone(&shared);
+        clone.write().change_node(1, R64::from_inner(3.0));
+        assert_eq!(
+            *GraphCell::read(&shared).node_weight(1).unwrap(),
+            R64::from_inner(3.0)
+        );
+
+        let shared = Arc::new(RwLock::new(graph()));
+        let orders: Vec<usize> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let graph = Arc::clone(&shared);
+                    scope.spawn(move || GraphCell::read(&graph).order())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert_eq!(orders, vec![2; 2]);
+    }
+
+    #[test]
+    fn borrowed_graphs_are_graphs() {
+        let mut owned = graph();
+        {
+            let borrowed = RefCell::new(&mut owned);
+            let graph: &RefCell<
+                dyn GenericWeightedGraph<
+                        IndexType = usize,
+                        NodeWeightType = R64,
+                        EdgeWeightType = R64,
+                    > + '_,
+            > = &borrowed;
+            graph.write().remove_edge((1, 0));
+            assert_eq!(graph.read().size(), 1);
+        }
+        assert!(!owned.has_edge((1, 0)));
+    }
+}
@@ -3,3 +3,28 @@ mod pbf;
 
 pub use error::ImportError;
 pub use pbf::import_pbf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::graph::DuplicatePolicy;
+
+/// Cleanup passes applied to imported graphs, all disabled by default.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cleanup {
+    /// Keeps the fastest of multiple connections between two nodes, instead of the first one.
+    #[serde(default)]
+    pub collapse_parallel_edges: bool,
+    /// See MatrixGraph::prune_dead_ends.
+    #[serde(default)]
+    pub prune_dead_ends: bool,
+    /// Keeps dead ends with a reward when pruning.
+    #[serde(default)]
+    pub keep_rewarded: bool,
+    #[serde(default)]
+    pub remove_isolated_nodes: bool,
+    /// How edges between the same two points are inserted, which distinct OSM nodes at the
+    /// same coordinates produce. Without it the first edge is kept, or the fastest one with
+    /// collapse_parallel_edges. With error, such data fails the import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_edges: Option<DuplicatePolicy>,
+}
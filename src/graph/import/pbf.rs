@@ -1,13 +1,13 @@
 #![allow(clippy::map_entry)]
 use decorum::R64;
 use osmpbfreader::objects::Node;
-use osmpbfreader::OsmPbfReader;
+use osmpbfreader::{OsmPbfReader, Tags};
 use osmpbfreader::{NodeId, OsmId, OsmObj};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs::File;
 
-use crate::geo::{geodistance_haversine, GeoPoint};
-use crate::graph::import::ImportError;
+use crate::geo::{geodistance_haversine, GeoPoint, Region};
+use crate::graph::import::{Cleanup, ImportError};
 use crate::graph::{GenericWeightedGraph, MatrixGraph};
 
 /// Calculates the distance between two nodes in km.
@@ -53,56 +53,171 @@ fn add_btreemaps(map_a: &BTreeMap<String, f64>, map_b: &BTreeMap<String, f64>) -
 }
 
 
+/// Returns whether a way can be travelled in (forward, backward) direction of its nodes.
+/// Motorways and roundabouts are one-way unless tagged otherwise, as in OSM itself.
+fn way_directions(tags: &Tags) -> (bool, bool) {
+    match tags.get("oneway").map(|value| value.as_str()) {
+        Some("yes") | Some("true") | Some("1") => (true, false),
+        Some("-1") | Some("reverse") => (false, true),
+        Some("no") | Some("false") | Some("0") => (true, true),
+        _ => {
+            let implied = tags.contains("highway", "motorway") || tags.contains("junction", "roundabout");
+            (true, !implied)
+        }
+    }
+}
+
+/// Adds the segment from from_key to to_key on a road of type road_type to the adjacency maps.
+/// neighbors[from_key][to_key][road_type] is the distance one would travel on that road type,
+/// so the complete distance is neighbors[from_key][to_key].values().sum().
+/// inv_neighbors[to_key] lists all nodes pointing at to_key.
+fn add_segment(
+    neighbors: &mut BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>>,
+    inv_neighbors: &mut BTreeMap<OsmId, Vec<OsmId>>,
+    from_key: OsmId,
+    to_key: OsmId,
+    road_type: &str,
+    distance: f64,
+) {
+    inv_neighbors.entry(to_key).or_default().push(from_key);
+    neighbors
+        .entry(from_key)
+        .or_default()
+        .entry(to_key)
+        .or_default()
+        .insert(road_type.to_string(), distance);
+}
+
+/// Returns whether node only continues a chain of nodes, instead of being an endpoint of it.
+/// That is the case on a one-way street, where the node has a single predecessor and successor,
+/// and on a two-way street, where it has the same two distinct neighbors in both directions.
+fn is_chain_node(
+    node: &OsmId,
+    successors: &BTreeMap<OsmId, BTreeMap<String, f64>>,
+    predecessors: &BTreeSet<OsmId>,
+) -> bool {
+    if successors.contains_key(node) || predecessors.contains(node) {
+        return false;
+    }
+    match (successors.len(), predecessors.len()) {
+        (1, 1) => successors.keys().next() != predecessors.iter().next(),
+        (2, 2) => successors.keys().eq(predecessors.iter()),
+        _ => false,
+    }
+}
+
+/// Follows the chain from start over first until it reaches an endpoint and returns the endpoint
+/// together with the summed distances on the way.
+/// Returns None if the chain runs into a loop without an endpoint.
+fn follow_chain(
+    start: OsmId,
+    first: OsmId,
+    neighbors: &BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>>,
+    endpoints: &BTreeSet<OsmId>,
+    visited: &mut HashSet<OsmId>,
+) -> Option<(OsmId, BTreeMap<String, f64>)> {
+    let mut distance_map = neighbors[&start][&first].clone();
+    let (mut prev, mut current) = (start, first);
+    let mut walked = HashSet::new();
+    while !endpoints.contains(&current) {
+        if !walked.insert(current) {
+            return None;
+        }
+        visited.insert(current);
+        // on two-way streets one successor leads back to where we came from
+        let (next, distances) = neighbors
+            .get(&current)?
+            .iter()
+            .find(|(next, _)| **next != prev)?;
+        distance_map = add_btreemaps(&distance_map, distances);
+        prev = current;
+        current = *next;
+    }
+    Some((current, distance_map))
+}
+
 /// Contracts all nodes on a single connection path into one endpoint node.
 /// The distances for these nodes are updated according to their original distance with many hops in between.
+/// Of multiple paths between the same endpoints the last one is kept, or the fastest one with keep_fastest.
+/// Paths leading back to their start are dropped, and cycles without any endpoint, which are not
+/// connected to the rest of the graph, are contracted into a single one of their nodes.
+/// Nodes which are not part of any way are dropped.
 fn contract_nodes(
     nodes: BTreeMap<OsmId, OsmObj>,
     neighbors: BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>>,
     inv_neighbors: BTreeMap<OsmId, Vec<OsmId>>,
+    keep_fastest: bool,
 ) -> (BTreeMap<OsmId, OsmObj>, BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>>)
 {
-    let used_nodes: BTreeMap<OsmId, OsmObj> = nodes.iter().filter(|(id, _)| {
-        let ins = neighbors.get(id).map_or(0, |x| x.len());
-        let outs =  inv_neighbors.get(id).map_or(0, |x| x.len());
-        return !(ins == 1 && outs == 1) && (ins > 0 || outs > 0)
-    }).map(|(a, b)| (a.clone(), b.clone())).collect();
-    let mut used_neighbors: BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>> = BTreeMap::new();
+    // the same segment can be part of multiple ways, so predecessors may be listed repeatedly
+    let predecessors: BTreeMap<OsmId, BTreeSet<OsmId>> = inv_neighbors
+        .into_iter()
+        .map(|(id, preds)| (id, preds.into_iter().collect()))
+        .collect();
+    let no_successors = BTreeMap::new();
+    let no_predecessors = BTreeSet::new();
+    let mut endpoints = BTreeSet::new();
+    let mut chain_nodes = Vec::new();
+    for id in nodes.keys() {
+        let successors = neighbors.get(id).unwrap_or(&no_successors);
+        let preds = predecessors.get(id).unwrap_or(&no_predecessors);
+        if is_chain_node(id, successors, preds) {
+            chain_nodes.push(*id);
+        } else if !successors.is_empty() || !preds.is_empty() {
+            endpoints.insert(*id);
+        }
+    }
 
-    for (node, _) in used_nodes.iter() {
-        for (mut neighbor, mut distance_map) in neighbors.get(node).unwrap_or(&BTreeMap::new()).iter() {
-            let mut w_temp = distance_map.clone();
-            let mut prev = neighbor;                
-            while !used_nodes.contains_key(neighbor) {
-                prev = neighbor;
-                let inner = neighbors.get(neighbor).unwrap().first_key_value().unwrap();
-                neighbor = inner.0;
-                distance_map = inner.1;
-                if prev == neighbor {
-                    break
+    let mut used_neighbors: BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>> = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut starts: Vec<OsmId> = endpoints.iter().copied().collect();
+    let mut unvisited_chain_nodes = chain_nodes.into_iter();
+    loop {
+        for start in starts.drain(..) {
+            for first in neighbors.get(&start).unwrap_or(&no_successors).keys() {
+                let (end, distance_map) =
+                    match follow_chain(start, *first, &neighbors, &endpoints, &mut visited) {
+                        Some(path) => path,
+                        None => continue,
+                    };
+                if end == start {
+                    continue;
+                }
+                let paths = used_neighbors.entry(start).or_default();
+                let faster = paths.get(&end).is_none_or(|previous| {
+                    traveltime_from_distance_map(&distance_map) < traveltime_from_distance_map(previous)
+                });
+                if !keep_fastest || faster {
+                    paths.insert(end, distance_map);
                 }
-                w_temp = add_btreemaps(&w_temp, distance_map);
-                let ind = neighbors.get(neighbor).unwrap().len();
-                let outd = inv_neighbors.get(neighbor).unwrap().len();
-            }
-            if neighbor == node {
-                continue
             }
-            let mut new_map = BTreeMap::new();
-            new_map.insert(*neighbor, w_temp.clone());
-            if let Err(_) = used_neighbors.try_insert(*node, new_map) {
-                used_neighbors.get_mut(node).unwrap().insert(*neighbor, w_temp);
+        }
+        // chain nodes not reached from any endpoint form cycles of their own
+        match unvisited_chain_nodes.find(|id| !visited.contains(id)) {
+            Some(id) => {
+                endpoints.insert(id);
+                starts.push(id);
             }
+            None => break,
         }
     }
-    
-    return (used_nodes, used_neighbors)
+
+    let used_nodes = nodes
+        .into_iter()
+        .filter(|(id, _)| endpoints.contains(id))
+        .collect();
+    (used_nodes, used_neighbors)
 }
 
 /// Creates a minimized MatrixGraph from a given pbf file.
 /// The nodes are contracted as to not run out of memory for the MatrixGraph.
+/// If a region is given, only nodes inside of it and ways between those nodes are imported.
+/// The passes enabled in cleanup run on the final graph, after the node weights are drawn.
 pub fn import_pbf(
     path: &str,
     nw_gen: &mut dyn FnMut() -> R64,
+    region: Option<&Region>,
+    cleanup: &Cleanup,
 ) -> Result<MatrixGraph<GeoPoint, R64, R64>, ImportError> {
     let file_open = File::open(path);
     let file;
@@ -117,60 +232,50 @@ pub fn import_pbf(
     let mut nodes = BTreeMap::<OsmId, OsmObj>::new();
     // read all nodes from the pbf to their respective lists.
     // neighbors contain all successors of a node while inv_neighbors contains its predecessors.
+    // Ways which are not one-way add a segment in each direction, so both can be contracted.
     for obj in pbf.iter() {
         let obj = obj.unwrap();
         if obj.is_node() {
-            nodes.insert(obj.id(), obj);
+            // nodes outside of the region are dropped right away
+            let node = obj.node().unwrap();
+            let point = GeoPoint::from_degrees(node.lat(), node.lon());
+            let in_region = match region {
+                Some(r) => r.contains(&point),
+                None => true,
+            };
+            if in_region {
+                nodes.insert(obj.id(), obj);
+            }
         } else if obj.is_way() {
             let mut pid = NodeId(0);
             for (i, &nid) in obj.way().unwrap().nodes.iter().enumerate() {
+                let n_key = OsmId::Node(nid);
+                let p_key = OsmId::Node(pid);
+                // Segments with an endpoint outside of the region are skipped.
+                if i > 0
+                    && region.is_some()
+                    && !(nodes.contains_key(&n_key) && nodes.contains_key(&p_key))
+                {
+                    pid = nid;
+                    continue;
+                }
+
                 if i > 0 {
                     // Loading the nodes from the node array will fail if
                     // they are not listed first in the pbf file.
                     // If the pbf is generated correctly this won't happen.
-                    let n_key = OsmId::Node(nid);
                     let n_node = nodes[&n_key].node().unwrap(); // !!!
-                    let p_key = OsmId::Node(pid);
                     let p_node = nodes[&p_key].node().unwrap(); // !!!
 
-                    // insert all the predecessors of a node into the BTreeMap,
-                    // creating a new vec of neighbors, if there wasnt one before
-                    // This is just a list of neighbors going backwards.
-                    // No further information is encoded.
-                    // Accessing inv_neighbors[to_key] returns a vec of all node ids pointing at to_id
-                    if inv_neighbors.contains_key(&n_key) {
-                        inv_neighbors.get_mut(&n_key).unwrap().push(p_key);
-                    } else {
-                        inv_neighbors.insert(n_key, [p_key].to_vec());
-                    }
-
-                    // create a mapping for all neighbors of a node and their respective distances
-                    // when using a specific road type.
-                    // The map accessed as neighbors[from_key][to_key][road_type] returns
-                    // the distance one would travel on that specific road type.
-                    // Thus the complete distance would be neighbors[from_key][to_key].values().sum()
-                    let road_type = obj.way().unwrap().tags.get("highway").unwrap().to_string();
+                    let way = obj.way().unwrap();
+                    let road_type = way.tags.get("highway").unwrap().to_string();
                     let distance = get_node_distance(p_node, n_node);
-                    // println!("distance between {:?} and {:?} is {:?}km", p_node, n_node, distance);
-
-                    if neighbors.contains_key(&p_key) {
-                        let neighbor_dists = neighbors.get_mut(&p_key).unwrap();
-                        if neighbor_dists.contains_key(&n_key) {
-                            neighbor_dists
-                                .get_mut(&n_key)
-                                .unwrap()
-                                .insert(road_type, distance);
-                        } else {
-                            let mut new_dists = BTreeMap::new();
-                            new_dists.insert(road_type, distance);
-                            neighbor_dists.insert(n_key, new_dists);
-                        }
-                    } else {
-                        let mut outer_map = BTreeMap::new();
-                        let mut inner_map = BTreeMap::new();
-                        inner_map.insert(road_type, distance);
-                        outer_map.insert(n_key, inner_map);
-                        neighbors.insert(p_key, outer_map);
+                    let (forward, backward) = way_directions(&way.tags);
+                    if forward {
+                        add_segment(&mut neighbors, &mut inv_neighbors, p_key, n_key, &road_type, distance);
+                    }
+                    if backward {
+                        add_segment(&mut neighbors, &mut inv_neighbors, n_key, p_key, &road_type, distance);
                     }
                 }
                 pid = nid;
@@ -189,7 +294,7 @@ pub fn import_pbf(
     }
 
     // contract all nodes on single connection paths into one
-    let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors);
+    let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors, cleanup.collapse_parallel_edges);
 
     // Map node ids from osm to consecutive ids starting at 0
     let mut node_map: BTreeMap<OsmId, GeoPoint> = BTreeMap::new();
@@ -215,37 +320,182 @@ pub fn import_pbf(
     for (from_id, neighbor_nodes) in neighbors.iter() {
         for (to_id, dist_map) in neighbor_nodes {
             if node_map.contains_key(from_id) && node_map.contains_key(to_id) && from_id != to_id {
-                // TODO: when logger is here this needs to go to errorlog
-                let _ = mapped_graph.add_edge(
-                    (node_map[from_id], node_map[to_id]),
-                    R64::from_inner(traveltime_from_distance_map(dist_map)),
-                );
+                let edge = (node_map[from_id], node_map[to_id]);
+                let weight = R64::from_inner(traveltime_from_distance_map(dist_map));
+                match cleanup.duplicate_edges {
+                    Some(policy) => mapped_graph
+                        .add_edge_with_duplicates(edge, weight, policy)
+                        .map_err(|e| ImportError::InvalidFormat(e.to_string()))?,
+                    // TODO: when logger is here this needs to go to errorlog
+                    None if cleanup.collapse_parallel_edges => {
+                        let _ = mapped_graph.add_edge_keep_min(edge, weight);
+                    }
+                    None => {
+                        let _ = mapped_graph.add_edge(edge, weight);
+                    }
+                }
             }
         }
     }
 
-    // Insert inverse edges with their weight being the traveltime between each other.
-    for (to_id, neighbor_nodes) in neighbors.iter() {
-        for (from_id, dist_map) in neighbor_nodes {
-            if node_map.contains_key(from_id) && node_map.contains_key(to_id) && from_id != to_id {
-                let m_fid = node_map[from_id];
-                let m_tid = node_map[to_id];
-                if !mapped_graph.has_edge((m_fid, m_tid)) {
-                    // TODO: when logger is here this needs to go to errorlog
-                    let _ = mapped_graph.add_edge(
-                        (m_fid, m_tid),
-                        R64::from_inner(traveltime_from_distance_map(dist_map)),
-                    );
-                }
+    if cleanup.prune_dead_ends {
+        mapped_graph.prune_dead_ends(cleanup.keep_rewarded);
+    }
+    if cleanup.remove_isolated_nodes {
+        mapped_graph.remove_isolated_nodes();
+    }
+
+    Ok(mapped_graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Neighbors = BTreeMap<OsmId, BTreeMap<OsmId, BTreeMap<String, f64>>>;
+
+    fn id(id: i64) -> OsmId {
+        OsmId::Node(NodeId(id))
+    }
+
+    /// Builds the input of contract_nodes from directed segments of 1km on residential roads.
+    fn topology(
+        segments: &[(i64, i64)],
+    ) -> (BTreeMap<OsmId, OsmObj>, Neighbors, BTreeMap<OsmId, Vec<OsmId>>) {
+        let mut nodes = BTreeMap::new();
+        let mut neighbors: Neighbors = BTreeMap::new();
+        let mut inv_neighbors: BTreeMap<OsmId, Vec<OsmId>> = BTreeMap::new();
+        for &(from, to) in segments {
+            for &node in &[from, to] {
+                nodes.entry(id(node)).or_insert_with(|| {
+                    OsmObj::Node(Node {
+                        id: NodeId(node),
+                        tags: Tags::new(),
+                        decimicro_lat: 0,
+                        decimicro_lon: 0,
+                    })
+                });
+                neighbors.entry(id(node)).or_default();
+                inv_neighbors.entry(id(node)).or_default();
             }
+            neighbors
+                .get_mut(&id(from))
+                .unwrap()
+                .insert(id(to), [("residential".to_string(), 1.0)].into());
+            inv_neighbors.get_mut(&id(to)).unwrap().push(id(from));
         }
+        (nodes, neighbors, inv_neighbors)
     }
 
-    println!(
-        "The final graph has {} nodes and {} edges",
-        mapped_graph.order(),
-        mapped_graph.size()
-    );
+    fn two_way(path: &[i64]) -> Vec<(i64, i64)> {
+        path.windows(2)
+            .flat_map(|pair| vec![(pair[0], pair[1]), (pair[1], pair[0])])
+            .collect()
+    }
 
-    Ok(mapped_graph)
+    fn distance(neighbors: &Neighbors, from: i64, to: i64) -> Option<f64> {
+        neighbors
+            .get(&id(from))
+            .and_then(|paths| paths.get(&id(to)))
+            .map(|distances| distances.values().sum())
+    }
+
+    fn ids(nodes: &BTreeMap<OsmId, OsmObj>) -> Vec<OsmId> {
+        nodes.keys().copied().collect()
+    }
+
+    fn tags(pairs: &[(&str, &str)]) -> Tags {
+        let mut tags = Tags::new();
+        for (key, value) in pairs {
+            tags.insert((*key).into(), (*value).into());
+        }
+        tags
+    }
+
+    #[test]
+    fn oneway_tags_decide_the_directions_of_a_way() {
+        assert_eq!(way_directions(&tags(&[("highway", "residential")])), (true, true));
+        assert_eq!(
+            way_directions(&tags(&[("highway", "residential"), ("oneway", "yes")])),
+            (true, false)
+        );
+        assert_eq!(
+            way_directions(&tags(&[("highway", "residential"), ("oneway", "-1")])),
+            (false, true)
+        );
+        assert_eq!(way_directions(&tags(&[("highway", "motorway")])), (true, false));
+        assert_eq!(
+            way_directions(&tags(&[("highway", "motorway"), ("oneway", "no")])),
+            (true, true)
+        );
+        assert_eq!(
+            way_directions(&tags(&[("highway", "primary"), ("junction", "roundabout")])),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn one_way_chains_collapse_into_one_edge() {
+        let (nodes, neighbors, inv_neighbors) =
+            topology(&[(5, 1), (6, 1), (1, 2), (2, 3), (3, 4), (4, 7), (4, 8)]);
+        let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors, false);
+
+        assert_eq!(ids(&nodes), vec![id(1), id(4), id(5), id(6), id(7), id(8)]);
+        assert_eq!(distance(&neighbors, 1, 4), Some(3.0));
+        assert_eq!(distance(&neighbors, 4, 1), None);
+        assert_eq!(distance(&neighbors, 5, 1), Some(1.0));
+        assert_eq!(distance(&neighbors, 4, 8), Some(1.0));
+    }
+
+    #[test]
+    fn two_way_chains_collapse_in_both_directions() {
+        let mut segments = two_way(&[1, 2, 3, 4, 5]);
+        // the one-way street makes 4 a junction
+        segments.push((6, 4));
+        let (nodes, neighbors, inv_neighbors) = topology(&segments);
+        let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors, false);
+
+        assert_eq!(ids(&nodes), vec![id(1), id(4), id(5), id(6)]);
+        assert_eq!(distance(&neighbors, 1, 4), Some(3.0));
+        assert_eq!(distance(&neighbors, 4, 1), Some(3.0));
+        assert_eq!(distance(&neighbors, 4, 5), Some(1.0));
+        assert_eq!(distance(&neighbors, 6, 4), Some(1.0));
+    }
+
+    #[test]
+    fn loops_are_contracted_to_their_junction() {
+        // a roundabout at the end of a two-way street
+        let mut segments = two_way(&[1, 2]);
+        segments.extend(&[(2, 3), (3, 4), (4, 2)]);
+        let (nodes, neighbors, inv_neighbors) = topology(&segments);
+        let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors, false);
+
+        assert_eq!(ids(&nodes), vec![id(1), id(2)]);
+        assert_eq!(distance(&neighbors, 1, 2), Some(1.0));
+        assert_eq!(distance(&neighbors, 2, 1), Some(1.0));
+        assert_eq!(distance(&neighbors, 2, 2), None);
+    }
+
+    #[test]
+    fn isolated_cycles_keep_one_node() {
+        let mut segments = vec![(1, 2), (2, 3), (3, 1)];
+        segments.extend(two_way(&[4, 5, 6, 4]));
+        let (nodes, neighbors, inv_neighbors) = topology(&segments);
+        let (nodes, neighbors) = contract_nodes(nodes, neighbors, inv_neighbors, false);
+
+        assert_eq!(ids(&nodes), vec![id(1), id(4)]);
+        assert!(neighbors.values().all(|paths| paths.is_empty()));
+    }
+
+    #[test]
+    fn parallel_chains_keep_the_fastest_one() {
+        let segments = [(1, 2), (2, 4), (1, 3), (3, 5), (5, 4), (4, 1)];
+        let (nodes, neighbors, inv_neighbors) = topology(&segments);
+        let (_, fastest) =
+            contract_nodes(nodes.clone(), neighbors.clone(), inv_neighbors.clone(), true);
+        let (_, last) = contract_nodes(nodes, neighbors, inv_neighbors, false);
+
+        assert_eq!(distance(&fastest, 1, 4), Some(2.0));
+        assert_eq!(distance(&last, 1, 4), Some(3.0));
+    }
 }
@@ -0,0 +1,157 @@
+use crate::graph::WeightedGraph;
+use crate::rng::rng64;
+use crate::util::Point;
+
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+/// Positions of the nodes of a graph by node id, to draw graphs without coordinates.
+/// All layouts place adjacent nodes about one unit apart.
+pub type Layout = BTreeMap<usize, Point>;
+
+/// Places the nodes of a grid generated with size at their coordinates in the grid, the node
+/// with id i * size.1 + j at (i, j) like Grid numbers them.
+pub fn grid(size: (usize, usize)) -> Layout {
+    (0..size.0 * size.1)
+        .map(|id| {
+            let point = Point {
+                x: (id / size.1) as f64,
+                y: (id % size.1) as f64,
+            };
+            (id, point)
+        })
+        .collect()
+}
+
+/// Places the nodes evenly on a circle in the order of their ids.
+pub fn circular<Nw, Ew>(
+    graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+) -> Layout {
+    let ids = graph.node_ids();
+    let radius = ids.len() as f64 / (2.0 * PI);
+    ids.iter()
+        .enumerate()
+        .map(|(i, &id)| {
+            let angle = 2.0 * PI * i as f64 / ids.len() as f64;
+            let point = Point {
+                x: radius * angle.cos(),
+                y: radius * angle.sin(),
+            };
+            (id, point)
+        })
+        .collect()
+}
+
+/// Fruchterman-Reingold layout, which treats edges as springs pulling their nodes together and
+/// lets all nodes repel each other. Nodes start at random positions drawn with seed, so equal
+/// seeds give equal layouts. The temperature limiting how far nodes move cools down linearly
+/// over the iterations.
+pub fn force_directed<Nw, Ew>(
+    graph: &dyn WeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew>,
+    iterations: usize,
+    seed: u128,
+) -> Layout {
+    let ids = graph.node_ids();
+    let index: HashMap<usize, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let edges: Vec<(usize, usize)> = graph
+        .iter_edge_ids()
+        .map(|(from, to)| (index[&from], index[&to]))
+        .filter(|(from, to)| from != to)
+        .collect();
+
+    // the optimal distance k is 1 in a square with room for all nodes
+    let side = (ids.len() as f64).sqrt();
+    let mut rng = rng64(seed);
+    let mut points: Vec<Point> = ids
+        .iter()
+        .map(|_| Point {
+            x: rng.rand_float() * side,
+            y: rng.rand_float() * side,
+        })
+        .collect();
+
+    for iteration in 0..iterations {
+        let temperature = 0.1 * side * (1.0 - iteration as f64 / iterations as f64);
+        let mut displacements = vec![(0.0, 0.0); points.len()];
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                let (dx, dy, distance) = difference(&points[i], &points[j]);
+                let force = 1.0 / distance;
+                displacements[i].0 += dx / distance * force;
+                displacements[i].1 += dy / distance * force;
+                displacements[j].0 -= dx / distance * force;
+                displacements[j].1 -= dy / distance * force;
+            }
+        }
+        for &(from, to) in &edges {
+            let (dx, dy, distance) = difference(&points[from], &points[to]);
+            let force = distance * distance;
+            displacements[from].0 -= dx / distance * force;
+            displacements[from].1 -= dy / distance * force;
+            displacements[to].0 += dx / distance * force;
+            displacements[to].1 += dy / distance * force;
+        }
+
+        for (point, (dx, dy)) in points.iter_mut().zip(displacements) {
+            let length = (dx * dx + dy * dy).sqrt();
+            if length > 0.0 {
+                let step = length.min(temperature);
+                point.x = (point.x + dx / length * step).clamp(0.0, side);
+                point.y = (point.y + dy / length * step).clamp(0.0, side);
+            }
+        }
+    }
+
+    ids.into_iter().zip(points).collect()
+}
+
+/// Returns the difference between p1 and p2 and its length, which is kept above zero so
+/// nodes at the same position still push each other apart.
+fn difference(p1: &Point, p2: &Point) -> (f64, f64, f64) {
+    let (dx, dy) = (p1.x - p2.x, p1.y - p2.y);
+    let distance = (dx * dx + dy * dy).sqrt().max(1e-6);
+    (dx, dy, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    fn path(order: usize) -> MatrixGraph<usize, usize, usize> {
+        let edges = (1..order)
+            .flat_map(|i| vec![(i - 1, i, 1), (i, i - 1, 1)])
+            .collect();
+        MatrixGraph::new_usize_indexed(vec![1; order], edges).unwrap()
+    }
+
+    fn distance(layout: &Layout, a: usize, b: usize) -> f64 {
+        difference(&layout[&a], &layout[&b]).2
+    }
+
+    #[test]
+    fn grid_layout_follows_the_node_ids() {
+        let layout = grid((2, 3));
+        assert_eq!(layout.len(), 6);
+        assert_eq!(layout[&4], Point { x: 1.0, y: 1.0 });
+        assert_eq!(layout[&5], Point { x: 1.0, y: 2.0 });
+    }
+
+    #[test]
+    fn circular_layout_spaces_nodes_evenly() {
+        let layout = circular(&path(12));
+        for i in 1..12 {
+            assert!((distance(&layout, i - 1, i) - distance(&layout, 0, 1)).abs() < 1e-9);
+        }
+        assert!((distance(&layout, 0, 1) - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn force_directed_layout_pulls_neighbors_together() {
+        let graph = path(6);
+        let layout = force_directed(&graph, 200, 7);
+        assert_eq!(layout, force_directed(&graph, 200, 7));
+        assert!(distance(&layout, 0, 5) > distance(&layout, 0, 1));
+        assert!(distance(&layout, 0, 5) > distance(&layout, 2, 3));
+    }
+}
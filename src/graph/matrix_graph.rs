@@ -1,24 +1,163 @@
 use num_traits::Zero;
+use serde::{Deserialize, Serialize};
 use std::cmp::{Eq, Ord, Ordering};
-use std::collections::{BTreeMap, BinaryHeap};
+use std::collections::{BTreeMap, BinaryHeap, HashSet};
+use std::fmt;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
-use std::marker::PhantomData;
 use std::ops::Add;
 
-use crate::graph::{Edge, GenericWeightedGraph, GraphError};
+use crate::graph::{
+    Edge, GenericWeightedGraph, GraphError, GraphSummary, ValidationPolicy, ValidationReport,
+};
 use crate::metaheuristic::Solution;
 use crate::util::Max;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct MatrixGraph<IndexType: Clone, Nw, Ew> {
     pub adjacency_matrix: Vec<Vec<Option<Ew>>>,
     node_weights: Vec<Option<Nw>>,
     order: usize,
     size: usize,
-    node_map: BTreeMap<IndexType, usize>,
-    inv_node_map: BTreeMap<usize, IndexType>,
-    phantom: PhantomData<IndexType>,
+    mapping: IndexMapping<IndexType>,
+    negative_weights: NegativeWeightPolicy,
+}
+
+/// How a graph deals with negative edge weights, which Dijkstra can't handle.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NegativeWeightPolicy {
+    /// add_edge and change_edge return an error for negative weights. Weights changed in place
+    /// can't be checked there, so debug builds assert before searching shortest paths instead.
+    #[default]
+    Reject,
+    /// Negative weights are allowed and shortest paths are searched with Bellman–Ford, as long
+    /// as the graph has any. Nodes behind a negative cycle have no shortest path.
+    BellmanFord,
+}
+
+/// How an edge is added, if the graph already has an edge between the same nodes. Edge lists
+/// from noisy data or generators can contain such parallel edges, which the adjacency matrix
+/// can't hold.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Returns a DuplicateEdge error.
+    #[default]
+    Error,
+    /// Keeps the edge with the lower weight.
+    KeepMin,
+    /// Keeps the edge with the higher weight.
+    KeepMax,
+    /// Replaces the existing edge, so the last one added is kept.
+    Overwrite,
+}
+
+impl DuplicatePolicy {
+    /// Returns whether weight replaces the weight of an existing edge, or the DuplicateEdge
+    /// error for edge if duplicates are errors.
+    fn replaces<IndexType: Debug + Display, Ew: PartialOrd>(
+        self,
+        edge: Edge<IndexType>,
+        existing: &Ew,
+        weight: &Ew,
+    ) -> Result<bool, GraphError<IndexType>> {
+        match self {
+            DuplicatePolicy::Error => Err(GraphError::DuplicateEdge(edge)),
+            DuplicatePolicy::KeepMin => Ok(weight < existing),
+            DuplicatePolicy::KeepMax => Ok(weight > existing),
+            DuplicatePolicy::Overwrite => Ok(true),
+        }
+    }
+}
+
+/// How the ids of nodes are mapped to the indices of the adjacency matrix.
+#[derive(Debug, Clone)]
+enum IndexMapping<IndexType> {
+    /// usize ids are used as indices directly, which spares the map lookups of Keyed.
+    /// The graph can't know that its IndexType is usize, so the conversions are stored.
+    Identity {
+        to_index: fn(IndexType) -> usize,
+        to_id: fn(usize) -> IndexType,
+    },
+    /// Any other ids are mapped to the first free index when they are added.
+    Keyed {
+        node_map: BTreeMap<IndexType, usize>,
+        inv_node_map: BTreeMap<usize, IndexType>,
+    },
+}
+
+impl IndexMapping<usize> {
+    fn identity() -> Self {
+        IndexMapping::Identity {
+            to_index: |id| id,
+            to_id: |index| index,
+        }
+    }
+}
+
+impl<IndexType: Copy + Ord + Debug + Display> IndexMapping<IndexType> {
+    fn keyed() -> Self {
+        IndexMapping::Keyed {
+            node_map: BTreeMap::new(),
+            inv_node_map: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the index of id, which for Identity may have no node at it.
+    fn index(&self, id: IndexType) -> Option<usize> {
+        match self {
+            IndexMapping::Identity { to_index, .. } => Some(to_index(id)),
+            IndexMapping::Keyed { node_map, .. } => node_map.get(&id).copied(),
+        }
+    }
+
+    fn id(&self, index: usize) -> IndexType {
+        match self {
+            IndexMapping::Identity { to_id, .. } => to_id(index),
+            IndexMapping::Keyed { inv_node_map, .. } => inv_node_map[&index],
+        }
+    }
+
+    fn insert(&mut self, id: IndexType, index: usize) {
+        if let IndexMapping::Keyed {
+            node_map,
+            inv_node_map,
+        } = self
+        {
+            node_map.insert(id, index);
+            inv_node_map.insert(index, id);
+        }
+    }
+
+    /// Maps the indices in an error of the adjacency matrix back to ids.
+    fn mapped_error(&self, error: GraphError<usize>) -> GraphError<IndexType> {
+        match error {
+            GraphError::MissingNode(node) => GraphError::MissingNode(self.id(node)),
+            GraphError::MissingEdge(edge) => {
+                GraphError::MissingEdge((self.id(edge.0), self.id(edge.1)))
+            }
+            GraphError::DuplicateNode(node) => GraphError::DuplicateNode(self.id(node)),
+            GraphError::DuplicateEdge(edge) => {
+                GraphError::DuplicateEdge((self.id(edge.0), self.id(edge.1)))
+            }
+            GraphError::NegativeWeight(edge) => {
+                GraphError::NegativeWeight((self.id(edge.0), self.id(edge.1)))
+            }
+        }
+    }
+
+    fn remove(&mut self, id: IndexType) {
+        if let IndexMapping::Keyed {
+            node_map,
+            inv_node_map,
+        } = self
+        {
+            if let Some(index) = node_map.remove(&id) {
+                inv_node_map.remove(&index);
+            }
+        }
+    }
 }
 
 /// Implements a weighted, directed graph using an adjacency matrix as datastructure.
@@ -28,10 +167,28 @@ impl<Nw: Clone, Ew: Clone> MatrixGraph<usize, Nw, Ew> {
     /// The indices of nodes are inferred from their position in the given array,
     /// meaning the node at nodes[i] will get the index i in the graph instance.
     /// If any of the edges don't fit this scheme an error is returned.
+    /// Of multiple edges between the same nodes the last one is kept, like with
+    /// DuplicatePolicy::Overwrite.
     pub fn new_usize_indexed(
         nodes: Vec<Nw>,
         edges: Vec<(usize, usize, Ew)>,
-    ) -> Result<Self, GraphError<usize>> {
+    ) -> Result<Self, GraphError<usize>>
+    where
+        Ew: PartialOrd,
+    {
+        Self::new_usize_indexed_with_duplicates(nodes, edges, DuplicatePolicy::Overwrite)
+    }
+
+    /// Creates a graph like new_usize_indexed(), which adds multiple edges between the same
+    /// nodes according to duplicates.
+    pub fn new_usize_indexed_with_duplicates(
+        nodes: Vec<Nw>,
+        edges: Vec<(usize, usize, Ew)>,
+        duplicates: DuplicatePolicy,
+    ) -> Result<Self, GraphError<usize>>
+    where
+        Ew: PartialOrd,
+    {
         let node_amount = nodes.len();
 
         let mut graph = MatrixGraph {
@@ -40,10 +197,9 @@ impl<Nw: Clone, Ew: Clone> MatrixGraph<usize, Nw, Ew> {
             adjacency_matrix: (0..node_amount).map(|_| vec![None; node_amount]).collect(),
             node_weights: nodes.into_iter().map(Some).collect(),
             order: node_amount,
-            size: edges.len(),
-            node_map: BTreeMap::new(),
-            inv_node_map: BTreeMap::new(),
-            phantom: PhantomData,
+            size: 0,
+            mapping: IndexMapping::identity(),
+            negative_weights: NegativeWeightPolicy::default(),
         };
 
         for (from, to, weight) in edges.into_iter() {
@@ -53,52 +209,35 @@ impl<Nw: Clone, Ew: Clone> MatrixGraph<usize, Nw, Ew> {
                 return Err(GraphError::MissingNode(to));
             }
 
-            graph.adjacency_matrix[from][to] = Some(weight);
+            match &mut graph.adjacency_matrix[from][to] {
+                Some(existing) => {
+                    if duplicates.replaces((from, to), existing, &weight)? {
+                        *existing = weight;
+                    }
+                }
+                empty => {
+                    *empty = Some(weight);
+                    graph.size += 1;
+                }
+            }
         }
 
         Ok(graph)
     }
-}
 
-// #[allow(dead_code)]
-// impl MatrixGraph<usize, (), ()> {
-//     /// Constructs an unweighted MatrixGraph using the given amount of nodes and list of edges.
-//     pub fn new_unweighted(nodes: usize, edges: &[Edge<usize>]) -> Result<Self, GraphError<usize>> {
-//         // initialization works basically the same way as for generic types.
-//         let mut graph = MatrixGraph {
-//             adjacency_matrix: (0..nodes).map(|_| vec![None; nodes]).collect(),
-//             node_weights: vec![Some(()); nodes],
-//             order: nodes,
-//             size: edges.len(),
-//             node_map: BTreeMap::new(),
-//             inv_node_map: BTreeMap::new(),
-//             phantom: PhantomData,
-//         };
-
-//         // A lot of places where to_owned is used. Could possibly be simplified.
-//         for (from, to) in edges.iter() {
-//             if from >= &nodes {
-//                 return Err(GraphError::MissingNode(*from));
-//             } else if to >= &nodes {
-//                 return Err(GraphError::MissingNode(*to));
-//             }
-
-//             graph.adjacency_matrix[*from][*to] = Some(());
-//         }
-
-//         Ok(graph)
-//     }
-
-//     /// Constructs an empty unweighted MatrixGraph.
-//     pub fn default_unweighted() -> Self {
-//         MatrixGraph::default()
-//     }
-
-//     /// Constructs an unweighted MatrixGraph with capacity for at least the given amount of nodes.
-//     pub fn unweighted_with_size(size: usize) -> Self {
-//         MatrixGraph::with_size(size)
-//     }
-// }
+    /// Constructs an empty MatrixGraph with capacity for at least the given amount of nodes,
+    /// which uses the ids of nodes as their indices like new_usize_indexed().
+    pub fn with_size_usize_indexed(size: usize) -> Self {
+        MatrixGraph {
+            adjacency_matrix: (0..size).map(|_| vec![None; size]).collect(),
+            node_weights: vec![None; size],
+            order: 0,
+            size: 0,
+            mapping: IndexMapping::identity(),
+            negative_weights: NegativeWeightPolicy::default(),
+        }
+    }
+}
 
 impl<IndexType, Nw, Ew> MatrixGraph<IndexType, Nw, Ew>
 where
@@ -106,29 +245,42 @@ where
     Nw: Copy,
     Ew: Copy + Max + Zero + Add + Debug + Ord,
 {
+    /// Creates a graph from nodes and the edges between their ids, in which nodes have the
+    /// indices in the order they are given. Of multiple edges between the same nodes the last
+    /// one is kept, like with DuplicatePolicy::Overwrite.
     pub fn new(
         nodes: Vec<(IndexType, Nw)>,
         edges: Vec<(Edge<IndexType>, Ew)>,
     ) -> Result<Self, GraphError<IndexType>> {
-        let mut node_map = BTreeMap::new();
-        let mut inv_node_map = BTreeMap::new();
+        Self::new_with_duplicates(nodes, edges, DuplicatePolicy::Overwrite)
+    }
+
+    /// Creates a graph like new(), which adds multiple edges between the same nodes according
+    /// to duplicates.
+    pub fn new_with_duplicates(
+        nodes: Vec<(IndexType, Nw)>,
+        edges: Vec<(Edge<IndexType>, Ew)>,
+        duplicates: DuplicatePolicy,
+    ) -> Result<Self, GraphError<IndexType>> {
+        let mut mapping = IndexMapping::keyed();
         for (i, loc) in nodes.iter().enumerate() {
-            node_map.insert(loc.0, i);
-            inv_node_map.insert(i, loc.0);
+            mapping.insert(loc.0, i);
         }
 
         let mut mapped_edges = Vec::with_capacity(edges.len());
-        for (edge, ew) in edges.iter() {
-            if !node_map.contains_key(&edge.0) {
-                return Err(GraphError::MissingNode(edge.0));
-            } else if !node_map.contains_key(&edge.1) {
-                return Err(GraphError::MissingNode(edge.1));
+        for &((from, to), ew) in edges.iter() {
+            match (mapping.index(from), mapping.index(to)) {
+                (None, _) => return Err(GraphError::MissingNode(from)),
+                (_, None) => return Err(GraphError::MissingNode(to)),
+                (Some(from), Some(to)) => mapped_edges.push((from, to, ew)),
             }
-            mapped_edges.push((node_map[&edge.0], node_map[&edge.1], *ew))
         }
 
-        let graph =
-            MatrixGraph::new_usize_indexed(nodes.iter().map(|x| x.1).collect(), mapped_edges);
+        let graph = MatrixGraph::new_usize_indexed_with_duplicates(
+            nodes.iter().map(|x| x.1).collect(),
+            mapped_edges,
+            duplicates,
+        );
 
         match graph {
             Ok(valid_graph) => Ok(MatrixGraph {
@@ -136,41 +288,10 @@ where
                 node_weights: valid_graph.node_weights,
                 order: valid_graph.order,
                 size: valid_graph.size,
-                node_map,
-                inv_node_map,
-                phantom: PhantomData,
+                mapping,
+                negative_weights: valid_graph.negative_weights,
             }),
-            Err(e) => match e {
-                GraphError::MissingNode(node) => Err(GraphError::MissingNode(inv_node_map[&node])),
-                GraphError::MissingEdge(edge) => Err(GraphError::MissingEdge((
-                    inv_node_map[&edge.0],
-                    inv_node_map[&edge.1],
-                ))),
-                GraphError::DuplicateNode(node) => {
-                    Err(GraphError::DuplicateNode(inv_node_map[&node]))
-                }
-                GraphError::DuplicateEdge(edge) => Err(GraphError::DuplicateEdge((
-                    inv_node_map[&edge.0],
-                    inv_node_map[&edge.1],
-                ))),
-            },
-        }
-    }
-
-    #[allow(dead_code)]
-    fn cast_usize_to_generic_graph(
-        ugraph: MatrixGraph<usize, Nw, Ew>,
-        nmap: BTreeMap<IndexType, usize>,
-        imap: BTreeMap<usize, IndexType>,
-    ) -> MatrixGraph<IndexType, Nw, Ew> {
-        MatrixGraph {
-            adjacency_matrix: ugraph.adjacency_matrix,
-            node_weights: ugraph.node_weights,
-            order: ugraph.order,
-            size: ugraph.size,
-            node_map: nmap,
-            inv_node_map: imap,
-            phantom: PhantomData,
+            Err(e) => Err(mapping.mapped_error(e)),
         }
     }
 
@@ -183,9 +304,8 @@ where
             node_weights: Vec::new(),
             order: 0,
             size: 0,
-            node_map: BTreeMap::new(),
-            inv_node_map: BTreeMap::new(),
-            phantom: PhantomData,
+            mapping: IndexMapping::keyed(),
+            negative_weights: NegativeWeightPolicy::default(),
         }
     }
 
@@ -196,9 +316,94 @@ where
             node_weights: vec![None; size],
             order: 0,
             size: 0,
-            node_map: BTreeMap::new(),
-            inv_node_map: BTreeMap::new(),
-            phantom: PhantomData,
+            mapping: IndexMapping::keyed(),
+            negative_weights: NegativeWeightPolicy::default(),
+        }
+    }
+
+    /// Returns an iterator over the neighbor ids of the node with id.
+    /// Unlike GenericWeightedGraph::iter_neighbor_ids the iterator is not boxed, so iterating
+    /// a concrete MatrixGraph doesn't allocate. Trait objects still use the boxed variant.
+    pub fn iter_neighbor_ids(
+        &self,
+        id: IndexType,
+    ) -> Result<impl Iterator<Item = IndexType> + '_, GraphError<IndexType>> {
+        Ok(self.iter_neighbors(id)?.map(|(id, _)| id))
+    }
+
+    /// Returns an iterator over the neighbor ids with a reference to the weight of the edge to
+    /// them, without boxing it like GenericWeightedGraph::iter_neighbors.
+    pub fn iter_neighbors(
+        &self,
+        id: IndexType,
+    ) -> Result<impl Iterator<Item = (IndexType, &Ew)> + '_, GraphError<IndexType>> {
+        let index = self.index(id).ok_or(GraphError::MissingNode(id))?;
+        let iterator = self.mapped_result(self._iter_neighbors(index))?;
+        Ok(iterator.map(move |(index, weight)| (self.mapping.id(index), weight)))
+    }
+
+    /// Returns an iterator over the edge ids, without boxing it like
+    /// GenericWeightedGraph::iter_edge_ids.
+    pub fn iter_edge_ids(&self) -> impl Iterator<Item = Edge<IndexType>> + '_ {
+        self._iter_edge_ids()
+            .map(move |(from, to)| (self.mapping.id(from), self.mapping.id(to)))
+    }
+
+    /// Sets how the graph deals with negative edge weights.
+    pub fn with_negative_weights(mut self, policy: NegativeWeightPolicy) -> Self {
+        self.negative_weights = policy;
+        self
+    }
+
+    /// Returns how the graph deals with negative edge weights.
+    pub fn negative_weights(&self) -> NegativeWeightPolicy {
+        self.negative_weights
+    }
+
+    /// Returns NegativeWeight for negative weights of edge, if the policy rejects them.
+    fn check_weight(&self, edge: Edge<IndexType>, weight: Ew) -> Result<(), GraphError<IndexType>> {
+        match self.negative_weights {
+            NegativeWeightPolicy::Reject if weight < Ew::zero() => {
+                Err(GraphError::NegativeWeight(edge))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Searches the shortest paths from the node at index, or towards it if inverse.
+    /// Bellman–Ford is only used for graphs with negative weights, if the policy allows them.
+    fn search_paths(&self, index: usize, inverse: bool) -> (Vec<Option<usize>>, Vec<Ew>) {
+        match self.negative_weights {
+            NegativeWeightPolicy::BellmanFord if self._has_negative_weights() => {
+                self._bellman_ford(index, inverse)
+            }
+            _ => {
+                debug_assert!(
+                    !self._has_negative_weights(),
+                    "Dijkstra can't search graphs with negative weights, \
+                     use NegativeWeightPolicy::BellmanFord for them."
+                );
+                if inverse {
+                    self._inv_shortest_paths(index)
+                } else {
+                    self._shortest_paths(index)
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the node with id, or None if there is no such node.
+    fn index(&self, id: IndexType) -> Option<usize> {
+        self.mapping
+            .index(id)
+            .filter(|&index| self._has_node(index))
+    }
+
+    fn edge_index(&self, edge: Edge<IndexType>) -> Result<Edge<usize>, GraphError<IndexType>> {
+        match (self.index(edge.0), self.index(edge.1)) {
+            (None, _) => Err(GraphError::MissingNode(edge.0)),
+            (_, None) => Err(GraphError::MissingNode(edge.1)),
+            (Some(from), Some(to)) => Ok((from, to)),
         }
     }
 
@@ -206,23 +411,56 @@ where
         &self,
         result: Result<CorrectType, GraphError<usize>>,
     ) -> Result<CorrectType, GraphError<IndexType>> {
-        match result {
-            Err(GraphError::MissingNode(node)) => {
-                Err(GraphError::MissingNode(self.inv_node_map[&node]))
+        result.map_err(|e| self.mapping.mapped_error(e))
+    }
+
+    /// Collects the paths to all nodes from the predecessors found by a shortest path search.
+    /// Paths are followed back from every node, so those of a search from a start node need to
+    /// be reversed, while those of an inverse search towards a node already lead there.
+    fn collect_paths(
+        &self,
+        prevs: &[Option<usize>],
+        dists: &[Ew],
+        reverse: bool,
+    ) -> BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>> {
+        let mut res = BTreeMap::new();
+
+        // indices without a node, e.g. of removed ones, are skipped
+        for i in self._iter_node_ids() {
+            let id = self.mapping.id(i);
+            let mut created = false;
+            let mut solution: Solution<IndexType> = Solution::from_nodes(vec![id]);
+            let mut prev = prevs[i];
+            while let Some(node) = prev {
+                created = true;
+
+                // only push self once (probably only important for to_node)
+                if i != node {
+                    solution.push_node(self.mapping.id(node));
+                }
+
+                let n_prev = prevs[node];
+                // only to_node has prev[node] == node so we iterate as long as we haven't reached that
+                if n_prev != prev {
+                    prev = n_prev
+                } else {
+                    prev = None
+                }
             }
-            Err(GraphError::MissingEdge(edge)) => Err(GraphError::MissingEdge((
-                self.inv_node_map[&edge.0],
-                self.inv_node_map[&edge.1],
-            ))),
-            Err(GraphError::DuplicateNode(node)) => {
-                Err(GraphError::DuplicateNode(self.inv_node_map[&node]))
+
+            if reverse {
+                solution.reverse();
+            }
+
+            // if we can reach the node we created a solution
+            if created {
+                res.insert(id, Some((solution, dists[i])));
+            } else {
+                res.insert(id, None);
             }
-            Err(GraphError::DuplicateEdge(edge)) => Err(GraphError::DuplicateEdge((
-                self.inv_node_map[&edge.0],
-                self.inv_node_map[&edge.1],
-            ))),
-            Ok(some) => Ok(some),
         }
+
+        res
     }
 
     fn _is_empty(&self) -> bool {
@@ -237,24 +475,19 @@ where
         self.size
     }
 
-    fn _iter_node_ids(&self) -> Box<dyn Iterator<Item = usize> + '_> {
-        Box::new(
-            self.node_weights
-                .iter()
-                .enumerate()
-                .filter(|(_, x)| x.is_some())
-                .map(move |(i, _)| i),
-        )
+    fn _iter_node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.node_weights
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.is_some())
+            .map(move |(i, _)| i)
     }
 
-    fn _iter_nodes(&self) -> Box<dyn Iterator<Item = (usize, &Nw)> + '_> {
-        Box::new(
-            self.node_weights
-                .iter()
-                .enumerate()
-                .filter(|(_, x)| x.is_some())
-                .map(move |(i, _)| (i, self._node_weight(i).unwrap())),
-        )
+    fn _iter_nodes(&self) -> impl Iterator<Item = (usize, &Nw)> + '_ {
+        self.node_weights
+            .iter()
+            .enumerate()
+            .filter_map(|(i, weight)| weight.as_ref().map(|weight| (i, weight)))
     }
 
     fn _node_weight(&self, id: usize) -> Result<&Nw, GraphError<usize>> {
@@ -269,19 +502,8 @@ where
     fn _iter_neighbor_ids(
         &self,
         id: usize,
-    ) -> Result<Box<dyn Iterator<Item = usize> + '_>, GraphError<usize>> {
-        if !self._has_node(id) {
-            return Err(GraphError::MissingNode(id));
-        }
-
-        // Get the ids of nodes to which a weighted edge from id exists.
-        Ok(Box::new(
-            self.adjacency_matrix[id]
-                .iter()
-                .enumerate()
-                .filter(|(_, x)| x.is_some())
-                .map(move |(i, _)| i),
-        ))
+    ) -> Result<impl Iterator<Item = usize> + '_, GraphError<usize>> {
+        Ok(self._iter_neighbors(id)?.map(|(i, _)| i))
     }
 
     fn _neighbor_ids(&self, id: usize) -> Result<Vec<usize>, GraphError<usize>> {
@@ -291,42 +513,35 @@ where
         }
     }
 
-    #[allow(clippy::type_complexity)]
     fn _iter_neighbors(
         &self,
         id: usize,
-    ) -> Result<Box<dyn Iterator<Item = (usize, &Ew)> + '_>, GraphError<usize>> {
+    ) -> Result<impl Iterator<Item = (usize, &Ew)> + '_, GraphError<usize>> {
         if !self._has_node(id) {
             return Err(GraphError::MissingNode(id));
         }
 
         // Get the ids of nodes to which a weighted edge from id exists.
-        Ok(Box::new(
-            self.adjacency_matrix[id]
-                .iter()
-                .enumerate()
-                .filter(|(_, x)| x.is_some())
-                .map(move |(i, _)| (i, self._edge_weight((id, i)).unwrap())),
-        ))
+        Ok(self.adjacency_matrix[id]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, weight)| weight.as_ref().map(|weight| (i, weight))))
     }
 
     fn _iter_inv_neighbors(
         &self,
         id: usize,
-    ) -> Result<Box<dyn Iterator<Item = (usize, &Ew)> + '_>, GraphError<usize>> {
+    ) -> Result<impl Iterator<Item = (usize, &Ew)> + '_, GraphError<usize>> {
         if !self._has_node(id) {
             return Err(GraphError::MissingNode(id));
         }
 
-        // Get the ids of nodes to which a weighted edge from id exists.
-        Ok(Box::new(
-            self.adjacency_matrix
-                .iter()
-                .map(move |col| col[id])
-                .enumerate()
-                .filter(|(_, x)| x.is_some())
-                .map(move |(i, _)| (i, self._edge_weight((i, id)).unwrap())),
-        ))
+        // Get the ids of nodes from which a weighted edge to id exists.
+        Ok(self
+            .adjacency_matrix
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, weights)| weights[id].as_ref().map(|weight| (i, weight))))
     }
 
     fn _has_node(&self, id: usize) -> bool {
@@ -356,7 +571,7 @@ where
     fn _remove_node(&mut self, id: usize) {
         if self._has_node(id) {
             // If a node is removed from the graph there can't be any edges to or from it.
-            for i in 0..self.order {
+            for i in 0..self.adjacency_matrix.len() {
                 self._remove_edge((i, id));
                 self._remove_edge((id, i));
             }
@@ -381,38 +596,24 @@ where
         Ok(self._neighbor_ids(id)?.len())
     }
 
-    fn _iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<usize>> + '_> {
-        Box::new(
-            self.adjacency_matrix
-                .iter()
-                .enumerate()
-                .flat_map(|(i, edges)| {
-                    edges
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, weight)| weight.is_some())
-                        .map(move |(j, _)| (i, j))
-                }),
-        )
+    fn _iter_edge_ids(&self) -> impl Iterator<Item = Edge<usize>> + '_ {
+        self._iter_edges().map(|(edge, _)| edge)
     }
 
     fn _edge_ids(&self) -> Vec<Edge<usize>> {
         self._iter_edge_ids().collect()
     }
 
-    fn _iter_edges(&self) -> Box<dyn Iterator<Item = (Edge<usize>, &Ew)> + '_> {
-        Box::new(
-            self.adjacency_matrix
-                .iter()
-                .enumerate()
-                .flat_map(move |(i, edges)| {
-                    edges
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, weight)| weight.is_some())
-                        .map(move |(j, _)| ((i, j), self._edge_weight((i, j)).unwrap()))
-                }),
-        )
+    fn _iter_edges(&self) -> impl Iterator<Item = (Edge<usize>, &Ew)> + '_ {
+        self.adjacency_matrix
+            .iter()
+            .enumerate()
+            .flat_map(|(i, edges)| {
+                edges
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(j, weight)| weight.as_ref().map(|weight| ((i, j), weight)))
+            })
     }
 
     fn _edge_weight(&self, edge: Edge<usize>) -> Result<&Ew, GraphError<usize>> {
@@ -568,6 +769,203 @@ where
 
         (prev, dist)
     }
+
+    fn _has_negative_weights(&self) -> bool {
+        self._iter_edges().any(|(_, &weight)| weight < Ew::zero())
+    }
+
+    /// Bellman–Ford from from_node, or towards it if inverse, which unlike Dijkstra handles
+    /// negative weights. Nodes reachable over a negative cycle are left unreachable, as they
+    /// have no shortest path.
+    fn _bellman_ford(&self, from_node: usize, inverse: bool) -> (Vec<Option<usize>>, Vec<Ew>) {
+        let node_count = self.adjacency_matrix.len();
+        let mut dist: Vec<_> = (0..node_count).map(|_| <Ew as Max>::max()).collect();
+        let mut prev: Vec<Option<usize>> = vec![None; node_count];
+        dist[from_node] = Ew::zero();
+        prev[from_node] = Some(from_node);
+
+        // the inverse search follows the edges backwards
+        let edges: Vec<(Edge<usize>, Ew)> = self
+            ._iter_edges()
+            .map(|((from, to), &weight)| match inverse {
+                true => ((to, from), weight),
+                false => ((from, to), weight),
+            })
+            .collect();
+        let improves = |dist: &[Ew], prev: &[Option<usize>], (from, to): Edge<usize>, weight| {
+            prev[from].is_some() && dist[from] + weight < dist[to]
+        };
+
+        // without negative cycles no shortest path has more than node_count - 1 edges
+        for _ in 1..node_count {
+            let mut relaxed = false;
+            for &(edge, weight) in edges.iter() {
+                if improves(&dist, &prev, edge, weight) {
+                    dist[edge.1] = dist[edge.0] + weight;
+                    prev[edge.1] = Some(edge.0);
+                    relaxed = true;
+                }
+            }
+            if !relaxed {
+                break;
+            }
+        }
+
+        // edges, which still improve a distance, lead into a negative cycle
+        let mut stack: Vec<usize> = edges
+            .iter()
+            .filter(|&&(edge, weight)| improves(&dist, &prev, edge, weight))
+            .map(|&((_, to), _)| to)
+            .collect();
+        let mut cyclic = vec![false; node_count];
+        while let Some(node) = stack.pop() {
+            if !cyclic[node] {
+                cyclic[node] = true;
+                stack.extend(
+                    edges
+                        .iter()
+                        .filter(|((from, _), _)| *from == node)
+                        .map(|&((_, to), _)| to),
+                );
+            }
+        }
+        for node in (0..node_count).filter(|&node| cyclic[node]) {
+            dist[node] = <Ew as Max>::max();
+            prev[node] = None;
+        }
+
+        (prev, dist)
+    }
+
+    /// Returns the shortest path from from_node to to_node and its length, which neither
+    /// passes banned nodes nor traverses banned edges, or None if there is no such path.
+    fn _shortest_path_avoiding(
+        &self,
+        from_node: usize,
+        to_node: usize,
+        banned_nodes: &[bool],
+        banned_edges: &HashSet<Edge<usize>>,
+    ) -> Option<(Vec<usize>, Ew)> {
+        let node_count = self.adjacency_matrix.len();
+        let mut dist: Vec<_> = (0..node_count).map(|_| <Ew as Max>::max()).collect();
+        let mut prev: Vec<Option<usize>> = vec![None; node_count];
+        let mut visited: Vec<bool> = vec![false; node_count];
+        let mut heap: BinaryHeap<State<usize, Ew>> = BinaryHeap::new();
+
+        dist[from_node] = Ew::zero();
+        heap.push(State {
+            cost: Ew::zero(),
+            position: from_node,
+        });
+
+        while let Some(State { cost, position }) = heap.pop() {
+            if visited[position] || cost > dist[position] {
+                continue;
+            }
+            visited[position] = true;
+            // the first time to_node is popped, its path can't get any shorter
+            if position == to_node {
+                let mut path = vec![to_node];
+                while let Some(node) = prev[*path.last().unwrap()] {
+                    path.push(node);
+                }
+                path.reverse();
+                return Some((path, cost));
+            }
+
+            for (other, &cost_to) in self._iter_neighbors(position).unwrap() {
+                if banned_nodes[other] || banned_edges.contains(&(position, other)) {
+                    continue;
+                }
+                let next = State {
+                    cost: cost + cost_to,
+                    position: other,
+                };
+                if next.cost < dist[other] {
+                    if !visited[other] {
+                        heap.push(next);
+                    }
+                    dist[other] = next.cost;
+                    prev[other] = Some(position);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Yen's algorithm, see GenericWeightedGraph::k_shortest_paths.
+    /// Every further path branches off one of the paths found so far at a spur node. The root
+    /// of the path up to the spur node is kept, while the edges other paths with the same root
+    /// take from the spur node and the nodes of the root are banned from the rest of the path.
+    fn _k_shortest_paths(
+        &self,
+        from_node: usize,
+        to_node: usize,
+        k: usize,
+    ) -> Vec<(Vec<usize>, Ew)> {
+        let node_count = self.adjacency_matrix.len();
+        let mut paths: Vec<(Vec<usize>, Ew)> = Vec::with_capacity(k);
+        let mut candidates: Vec<(Vec<usize>, Ew)> = Vec::new();
+        if k == 0 {
+            return paths;
+        }
+        match self._shortest_path_avoiding(
+            from_node,
+            to_node,
+            &vec![false; node_count],
+            &HashSet::new(),
+        ) {
+            Some(path) => paths.push(path),
+            None => return paths,
+        }
+
+        while paths.len() < k {
+            let last = paths.last().unwrap().0.clone();
+            let mut root_length = Ew::zero();
+            for i in 0..last.len() - 1 {
+                let spur_node = last[i];
+                let root = &last[..=i];
+                let banned_edges: HashSet<Edge<usize>> = paths
+                    .iter()
+                    .filter(|(path, _)| path.len() > i + 1 && path[..=i] == *root)
+                    .map(|(path, _)| (path[i], path[i + 1]))
+                    .collect();
+                let mut banned_nodes = vec![false; node_count];
+                for &node in &root[..i] {
+                    banned_nodes[node] = true;
+                }
+
+                if let Some((spur_path, spur_length)) =
+                    self._shortest_path_avoiding(spur_node, to_node, &banned_nodes, &banned_edges)
+                {
+                    let mut path = root[..i].to_vec();
+                    path.extend(spur_path);
+                    if !candidates.iter().any(|(candidate, _)| *candidate == path) {
+                        candidates.push((path, root_length + spur_length));
+                    }
+                }
+                root_length = root_length + self.adjacency_matrix[spur_node][last[i + 1]].unwrap();
+            }
+
+            // the shortest candidate is the next path, ties are broken by fewer nodes
+            let next = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, (path_a, length_a)), (_, (path_b, length_b))| {
+                    length_a
+                        .cmp(length_b)
+                        .then_with(|| path_a.len().cmp(&path_b.len()))
+                })
+                .map(|(index, _)| index);
+            match next {
+                Some(index) => paths.push(candidates.remove(index)),
+                None => break,
+            }
+        }
+
+        paths
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -598,481 +996,419 @@ impl<IndexType: Ord, CostType: Ord> PartialOrd for State<IndexType, CostType> {
     }
 }
 
-#[allow(dead_code, clippy::map_entry)]
-impl<IndexType, Nw, Ew> GenericWeightedGraph for MatrixGraph<IndexType, Nw, Ew>
+impl<IndexType, Nw, Ew> MatrixGraph<IndexType, Nw, Ew>
 where
     IndexType: Hash + Copy + Eq + Display + Debug + Ord,
-    Nw: Copy,
+    Nw: Copy + PartialOrd + Zero,
     Ew: Copy + Max + Zero + Add + Debug + Ord,
 {
-    type IndexType = IndexType;
-    type NodeWeightType = Nw;
-    type EdgeWeightType = Ew;
-
-    default fn is_empty(&self) -> bool {
-        self._is_empty()
-    }
-
-    default fn order(&self) -> usize {
-        self._order()
-    }
-
-    default fn size(&self) -> usize {
-        self._size()
-    }
-
-    default fn iter_node_ids(&self) -> Box<dyn Iterator<Item = IndexType> + '_> {
-        Box::new(self.node_map.keys().copied())
-    }
-
-    default fn node_ids(&self) -> Vec<Self::IndexType> {
-        self.iter_node_ids().collect()
+    pub fn summary(&self) -> GraphSummary<Nw, Ew> {
+        GraphSummary::of(self)
     }
 
-    default fn iter_nodes(
+    /// Checks the graph for self-loops, invalid weights and nodes, which aren't on any route from
+    /// goal_point. Under the warn policy the report is returned anyway, so the caller can report
+    /// its problems, under the fail policy graphs with problems return their report as error.
+    pub fn validate(
         &self,
-    ) -> Box<dyn Iterator<Item = (Self::IndexType, &Self::NodeWeightType)> + '_> {
-        Box::new(
-            self._iter_nodes()
-                .map(move |(id, node)| (self.inv_node_map[&id], node)),
-        )
-    }
-
-    default fn node_weight(
-        &self,
-        id: Self::IndexType,
-    ) -> Result<&Self::NodeWeightType, GraphError<Self::IndexType>> {
-        if !self.node_map.contains_key(&id) {
-            return Err(GraphError::MissingNode(id));
-        }
-
-        let weight = self._node_weight(self.node_map[&id]);
-        self.mapped_result(weight)
-    }
-
-    default fn iter_neighbor_ids(
-        &self,
-        id: Self::IndexType,
-    ) -> Result<Box<dyn Iterator<Item = Self::IndexType> + '_>, GraphError<Self::IndexType>> {
-        if !self.node_map.contains_key(&id) {
-            return Err(GraphError::MissingNode(id));
-        }
-
-        let inner = self._iter_neighbor_ids(self.node_map[&id]);
-        let res = self.mapped_result(inner);
-        match res {
-            Ok(iterator) => Ok(Box::new(iterator.map(move |id| self.inv_node_map[&id]))),
-            Err(e) => Err(e),
+        goal_point: Option<IndexType>,
+        policy: ValidationPolicy,
+    ) -> Result<ValidationReport<IndexType>, ValidationReport<IndexType>>
+    where
+        Nw: Into<f64>,
+        Ew: Into<f64>,
+    {
+        let report = ValidationReport::of(self, goal_point);
+        match policy {
+            ValidationPolicy::Fail if !report.is_valid() => Err(report),
+            _ => Ok(report),
         }
     }
 
-    default fn neighbor_ids(
-        &self,
-        id: Self::IndexType,
-    ) -> Result<Vec<Self::IndexType>, GraphError<Self::IndexType>> {
-        let res = self.iter_neighbor_ids(id);
-        match res {
-            Ok(iterator) => Ok(iterator.collect()),
-            Err(e) => Err(e),
-        }
-    }
-
-    #[allow(clippy::type_complexity)]
-    default fn iter_neighbors(
-        &self,
-        id: Self::IndexType,
-    ) -> Result<
-        Box<dyn Iterator<Item = (Self::IndexType, &Self::EdgeWeightType)> + '_>,
-        GraphError<Self::IndexType>,
-    > {
-        let inner = self._iter_neighbors(self.node_map[&id]);
-        let res = self.mapped_result(inner);
-        match res {
-            Ok(iterator) => Ok(Box::new(
-                iterator.map(move |(id, point)| (self.inv_node_map[&id], point)),
-            )),
-            Err(e) => Err(e),
+    /// Removes all nodes without any edges, which no route can reach.
+    /// Returns the amount of removed nodes.
+    pub fn remove_isolated_nodes(&mut self) -> usize {
+        let isolated: Vec<usize> = self
+            ._iter_node_ids()
+            .filter(|&index| self._iter_adjacent(index).next().is_none())
+            .collect();
+        for &index in isolated.iter() {
+            self.remove_node(self.mapping.id(index));
         }
-    }
-
-    default fn neighbors(
-        &self,
-        id: Self::IndexType,
-    ) -> Result<Vec<(Self::IndexType, &Self::EdgeWeightType)>, GraphError<Self::IndexType>> {
-        let res = self.iter_neighbors(id);
-        match res {
-            Ok(iter) => Ok(iter.collect()),
-            Err(e) => Err(e),
+        isolated.len()
+    }
+
+    /// Removes dead ends, nodes connected to at most one other node, until none are left.
+    /// Routes can only leave them the way they came, so chains of them are detours without a
+    /// reward. With keep_rewarded, nodes weighted above zero and the chains leading to them
+    /// are kept. Returns the amount of removed nodes.
+    pub fn prune_dead_ends(&mut self, keep_rewarded: bool) -> usize {
+        let mut candidates: Vec<usize> = self._iter_node_ids().collect();
+        let mut removed = 0;
+        while let Some(index) = candidates.pop() {
+            let rewarded = self.node_weights[index].is_some_and(|weight| weight > Nw::zero());
+            if !self._has_node(index) || (keep_rewarded && rewarded) || !self._is_dead_end(index) {
+                continue;
+            }
+            // removing the node can turn its neighbor into a dead end
+            candidates.extend(self._iter_adjacent(index));
+            self.remove_node(self.mapping.id(index));
+            removed += 1;
         }
+        removed
     }
 
-    default fn has_node(&self, id: Self::IndexType) -> bool {
-        self.node_map.contains_key(&id) && self._has_node(self.node_map[&id])
+    /// Adds edge, or lowers the weight of the existing edge to weight. Adding parallel edges
+    /// this way collapses them into one with the minimal weight.
+    pub fn add_edge_keep_min(
+        &mut self,
+        edge: Edge<IndexType>,
+        weight: Ew,
+    ) -> Result<(), GraphError<IndexType>> {
+        self.add_edge_with_duplicates(edge, weight, DuplicatePolicy::KeepMin)
     }
 
-    default fn add_node(
+    /// Adds edge, or resolves it with the existing edge between the same nodes according to
+    /// duplicates. add_edge() is the same as DuplicatePolicy::Error.
+    pub fn add_edge_with_duplicates(
         &mut self,
-        id: Self::IndexType,
-        weight: Self::NodeWeightType,
-    ) -> Result<(), GraphError<Self::IndexType>> {
-        if self.node_map.contains_key(&id) {
-            return Err(GraphError::DuplicateNode(id));
-        }
-
-        // order is always amount of nodes + 1, so we can use it as our new id for internal
-        let inner_id = self.order();
-        let res = self._add_node(inner_id, weight);
-        let mapped_res = self.mapped_result(res);
-        match mapped_res {
-            Ok(_) => {
-                self.node_map.insert(id, inner_id);
-                self.inv_node_map.insert(inner_id, id);
-                Ok(())
+        edge: Edge<IndexType>,
+        weight: Ew,
+        duplicates: DuplicatePolicy,
+    ) -> Result<(), GraphError<IndexType>> {
+        let (from, to) = self.edge_index(edge)?;
+        self.check_weight(edge, weight)?;
+        if let Some(existing) = &self.adjacency_matrix[from][to] {
+            if !duplicates.replaces(edge, existing, &weight)? {
+                return Ok(());
             }
-            Err(e) => Err(e),
         }
-    }
-
-    default fn remove_node(&mut self, id: Self::IndexType) {
-        if let Some(&inner_id) = self.node_map.get(&id) {
-            self.node_map.remove(&id);
-            self.inv_node_map.remove(&inner_id);
-            self._remove_node(inner_id);
+        let result = self._change_edge((from, to), weight);
+        self.mapped_result(result)
+    }
+
+    /// Returns an iterator over the indices of nodes with an edge from or to the node at index,
+    /// nodes with edges in both directions are returned twice.
+    fn _iter_adjacent(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let row = self.adjacency_matrix[index].iter().enumerate();
+        let column = self
+            .adjacency_matrix
+            .iter()
+            .map(move |row| &row[index])
+            .enumerate();
+        row.chain(column)
+            .filter(move |(other, weight)| *other != index && weight.is_some())
+            .map(|(other, _)| other)
+    }
+
+    fn _is_dead_end(&self, index: usize) -> bool {
+        let mut adjacent = self._iter_adjacent(index);
+        match adjacent.next() {
+            None => true,
+            Some(first) => adjacent.all(|other| other == first),
         }
     }
+}
 
-    default fn change_node(&mut self, id: Self::IndexType, weight: Self::NodeWeightType) {
-        if self.node_map.contains_key(&id) {
-            self._change_node(self.node_map[&id], weight);
-        } else {
-            let inner_id = self.order();
-            self.node_map.insert(id, inner_id);
-            self.inv_node_map.insert(inner_id, id);
-        }
+/// Shows the summary of the graph, the nodes and edges are only part of the alternate Debug.
+impl<IndexType, Nw, Ew> fmt::Display for MatrixGraph<IndexType, Nw, Ew>
+where
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: Copy + PartialOrd + Zero + Display,
+    Ew: Copy + Max + Zero + Add + Debug + Ord + Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.summary(), f)
     }
+}
 
-    default fn degree(&self, id: Self::IndexType) -> Result<usize, GraphError<Self::IndexType>> {
-        if !self.node_map.contains_key(&id) {
-            return Err(GraphError::MissingNode(id));
+/// Shows the fields of the summary, as the adjacency matrix of larger graphs can't be read
+/// anyway. The alternate form {:#?} lists the nodes and edges with their weights as well.
+impl<IndexType, Nw, Ew> fmt::Debug for MatrixGraph<IndexType, Nw, Ew>
+where
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: Copy + PartialOrd + Zero + Debug,
+    Ew: Copy + Max + Zero + Add + Debug + Ord,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self.summary();
+        let alternate = f.alternate();
+        let mut debug = f.debug_struct("MatrixGraph");
+        debug
+            .field("order", &summary.order)
+            .field("size", &summary.size)
+            .field("node_weights", &summary.node_weights)
+            .field("weighted_nodes", &summary.weighted_nodes)
+            .field("edge_weights", &summary.edge_weights);
+        if alternate {
+            debug
+                .field("nodes", &self.iter_nodes().collect::<Vec<_>>())
+                .field("edges", &self.iter_edges().collect::<Vec<_>>());
         }
-
-        let degree = self._degree(self.node_map[&id]);
-        self.mapped_result(degree)
-    }
-
-    default fn iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<Self::IndexType>> + '_> {
-        Box::new(
-            self._iter_edge_ids()
-                .map(move |(f_id, t_id)| (self.inv_node_map[&f_id], self.inv_node_map[&t_id])),
-        )
-    }
-
-    default fn edge_ids(&self) -> Vec<Edge<Self::IndexType>> {
-        self.iter_edge_ids().collect()
-    }
-
-    default fn iter_edges(
-        &self,
-    ) -> Box<dyn Iterator<Item = (Edge<Self::IndexType>, &Self::EdgeWeightType)> + '_> {
-        Box::new(self._iter_edges().map(move |((f_id, t_id), weight)| {
-            ((self.inv_node_map[&f_id], self.inv_node_map[&t_id]), weight)
-        }))
-    }
-
-    default fn edges(&self) -> Vec<(Edge<Self::IndexType>, &Self::EdgeWeightType)> {
-        self.iter_edges().collect()
-    }
-
-    default fn edge_weight(
-        &self,
-        edge: Edge<Self::IndexType>,
-    ) -> Result<&Self::EdgeWeightType, GraphError<Self::IndexType>> {
-        let weight = self._edge_weight((self.node_map[&edge.0], self.node_map[&edge.1]));
-        self.mapped_result(weight)
+        debug.finish()
     }
+}
 
-    default fn has_edge(&self, edge: Edge<Self::IndexType>) -> bool {
-        self._has_edge((self.node_map[&edge.0], self.node_map[&edge.1]))
-    }
+impl<IndexType, Nw, Ew> GenericWeightedGraph for MatrixGraph<IndexType, Nw, Ew>
+where
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: Copy,
+    Ew: Copy + Max + Zero + Add + Debug + Ord,
+{
+    type IndexType = IndexType;
+    type NodeWeightType = Nw;
+    type EdgeWeightType = Ew;
 
-    default fn add_edge(
-        &mut self,
-        edge: Edge<Self::IndexType>,
-        weight: Ew,
-    ) -> Result<(), GraphError<Self::IndexType>> {
-        if !self.node_map.contains_key(&edge.0) {
-            return Err(GraphError::MissingNode(edge.0));
-        } else if !self.node_map.contains_key(&edge.1) {
-            return Err(GraphError::MissingNode(edge.1));
-        }
-        let edge = self._add_edge((self.node_map[&edge.0], self.node_map[&edge.1]), weight);
-        self.mapped_result(edge)
+    fn is_empty(&self) -> bool {
+        self._is_empty()
     }
 
-    default fn remove_edge(&mut self, edge: Edge<Self::IndexType>) {
-        self._remove_edge((self.node_map[&edge.0], self.node_map[&edge.1]));
+    fn order(&self) -> usize {
+        self._order()
     }
 
-    default fn change_edge(
-        &mut self,
-        edge: Edge<Self::IndexType>,
-        weight: Self::EdgeWeightType,
-    ) -> Result<(), GraphError<Self::IndexType>> {
-        let edge = self._change_edge((self.node_map[&edge.0], self.node_map[&edge.1]), weight);
-        self.mapped_result(edge)
+    fn size(&self) -> usize {
+        self._size()
     }
 
-    default fn shortest_paths(
-        &self,
-        from_node: Self::IndexType,
-    ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Ew)>> {
-        let (prevs, dists) = self._shortest_paths(self.node_map[&from_node]);
-        let mut res = BTreeMap::new();
-
-        for i in 0..prevs.len() {
-            let mut created = false;
-            let mut solution: Solution<Self::IndexType> =
-                Solution::from_nodes(vec![self.inv_node_map[&i]]);
-            let mut prev = prevs[i];
-            while let Some(node) = prev {
-                created = true;
-
-                // only push self once (probably only important for to_node)
-                if i != node {
-                    solution.push_node(self.inv_node_map[&node]);
-                }
-
-                let n_prev = prevs[node];
-                // only to_node has prev[node] == node so we iterate as long as we haven't reached that
-                if n_prev != prev {
-                    prev = n_prev
-                } else {
-                    prev = None
-                }
-            }
-
-            // the solution needs to be reversed for us to start at the from_node
-            solution.reverse();
-
-            if created {
-                res.insert(self.inv_node_map[&i], Some((solution, dists[i])));
-            } else {
-                res.insert(self.inv_node_map[&i], None);
-            }
+    fn iter_node_ids(&self) -> Box<dyn Iterator<Item = IndexType> + '_> {
+        match &self.mapping {
+            IndexMapping::Identity { to_id, .. } => Box::new(self._iter_node_ids().map(*to_id)),
+            IndexMapping::Keyed { node_map, .. } => Box::new(node_map.keys().copied()),
         }
-
-        res
     }
 
-    default fn inv_shortest_paths(
+    fn iter_nodes(
         &self,
-        to_node: Self::IndexType,
-    ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Ew)>> {
-        let (prevs, dists) = self._inv_shortest_paths(self.node_map[&to_node]);
-        let mut res = BTreeMap::new();
-
-        for i in 0..prevs.len() {
-            let mut created = false;
-            let mut solution: Solution<Self::IndexType> =
-                Solution::from_nodes(vec![self.inv_node_map[&i]]);
-            let mut prev = prevs[i];
-            while let Some(node) = prev {
-                created = true;
-
-                // only push self once (probably only important for to_node)
-                if i != node {
-                    solution.push_node(self.inv_node_map[&node]);
-                }
-
-                let n_prev = prevs[node];
-                // only to_node has prev[node] == node so we iterate as long as we haven't reached that
-                if n_prev != prev {
-                    prev = n_prev
-                } else {
-                    prev = None
-                }
-            }
-            if created {
-                res.insert(self.inv_node_map[&i], Some((solution, dists[i])));
-            } else {
-                res.insert(self.inv_node_map[&i], None);
-            }
-        }
-
-        res
-    }
-}
-
-impl<Nw: Copy, Ew> GenericWeightedGraph for MatrixGraph<usize, Nw, Ew>
-where
-    Nw: Copy,
-    Ew: Copy + Max + Zero + Add + Debug + Ord,
-{
-    fn iter_node_ids(&self) -> Box<dyn Iterator<Item = usize> + '_> {
-        self._iter_node_ids()
+    ) -> Box<dyn Iterator<Item = (Self::IndexType, &Self::NodeWeightType)> + '_> {
+        Box::new(
+            self._iter_nodes()
+                .map(move |(index, node)| (self.mapping.id(index), node)),
+        )
     }
-    fn iter_nodes(&self) -> Box<dyn Iterator<Item = (usize, &Nw)> + '_> {
-        self._iter_nodes()
+
+    fn node_weight(
+        &self,
+        id: Self::IndexType,
+    ) -> Result<&Self::NodeWeightType, GraphError<Self::IndexType>> {
+        let index = self.index(id).ok_or(GraphError::MissingNode(id))?;
+        self.mapped_result(self._node_weight(index))
     }
 
-    fn node_weight(&self, id: usize) -> Result<&Nw, GraphError<usize>> {
-        self._node_weight(id)
+    fn node_weight_mut(
+        &mut self,
+        id: Self::IndexType,
+    ) -> Result<&mut Self::NodeWeightType, GraphError<Self::IndexType>> {
+        let index = self.index(id).ok_or(GraphError::MissingNode(id))?;
+        // Unwrapping is ok here, because index() only returns indices with a weight.
+        Ok(self.node_weights[index].as_mut().unwrap())
     }
 
     fn iter_neighbor_ids(
         &self,
-        id: usize,
-    ) -> Result<Box<dyn Iterator<Item = usize> + '_>, GraphError<usize>> {
-        self._iter_neighbor_ids(id)
+        id: Self::IndexType,
+    ) -> Result<Box<dyn Iterator<Item = Self::IndexType> + '_>, GraphError<Self::IndexType>> {
+        // calls the unboxed inherent method
+        Ok(Box::new(self.iter_neighbor_ids(id)?))
     }
 
     #[allow(clippy::type_complexity)]
     fn iter_neighbors(
         &self,
-        id: usize,
-    ) -> Result<Box<dyn Iterator<Item = (usize, &Ew)> + '_>, GraphError<usize>> {
-        self._iter_neighbors(id)
+        id: Self::IndexType,
+    ) -> Result<
+        Box<dyn Iterator<Item = (Self::IndexType, &Self::EdgeWeightType)> + '_>,
+        GraphError<Self::IndexType>,
+    > {
+        Ok(Box::new(self.iter_neighbors(id)?))
+    }
+
+    fn has_node(&self, id: Self::IndexType) -> bool {
+        self.index(id).is_some()
     }
 
-    fn has_node(&self, id: usize) -> bool {
-        self._has_node(id)
+    fn add_node(
+        &mut self,
+        id: Self::IndexType,
+        weight: Self::NodeWeightType,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        if self.has_node(id) {
+            return Err(GraphError::DuplicateNode(id));
+        }
+
+        let index = match &self.mapping {
+            IndexMapping::Identity { to_index, .. } => to_index(id),
+            // removed nodes leave free indices behind, which are filled up first
+            IndexMapping::Keyed { .. } => (0..).find(|&i| !self._has_node(i)).unwrap(),
+        };
+        let res = self._add_node(index, weight);
+        self.mapped_result(res)?;
+        self.mapping.insert(id, index);
+        Ok(())
     }
 
-    fn add_node(&mut self, id: usize, weight: Nw) -> Result<(), GraphError<usize>> {
-        self._add_node(id, weight)
+    fn remove_node(&mut self, id: Self::IndexType) {
+        if let Some(index) = self.index(id) {
+            self.mapping.remove(id);
+            self._remove_node(index);
+        }
     }
 
-    fn remove_node(&mut self, id: usize) {
-        self._remove_node(id)
+    fn change_node(&mut self, id: Self::IndexType, weight: Self::NodeWeightType) {
+        match self.index(id) {
+            Some(index) => self._change_node(index, weight),
+            // can't fail, as there is no node with this id yet
+            None => self.add_node(id, weight).unwrap(),
+        }
     }
 
-    fn change_node(&mut self, id: usize, weight: Nw) {
-        self._change_node(id, weight)
+    fn degree(&self, id: Self::IndexType) -> Result<usize, GraphError<Self::IndexType>> {
+        let index = self.index(id).ok_or(GraphError::MissingNode(id))?;
+        self.mapped_result(self._degree(index))
     }
 
-    fn degree(&self, id: usize) -> Result<usize, GraphError<usize>> {
-        self._degree(id)
+    fn iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<Self::IndexType>> + '_> {
+        Box::new(self.iter_edge_ids())
     }
 
-    fn iter_edge_ids(&self) -> Box<dyn Iterator<Item = Edge<usize>> + '_> {
-        self._iter_edge_ids()
+    fn iter_edges(
+        &self,
+    ) -> Box<dyn Iterator<Item = (Edge<Self::IndexType>, &Self::EdgeWeightType)> + '_> {
+        Box::new(self._iter_edges().map(move |((from, to), weight)| {
+            ((self.mapping.id(from), self.mapping.id(to)), weight)
+        }))
     }
 
-    fn iter_edges(&self) -> Box<dyn Iterator<Item = (Edge<usize>, &Ew)> + '_> {
-        self._iter_edges()
+    fn edge_weight(
+        &self,
+        edge: Edge<Self::IndexType>,
+    ) -> Result<&Self::EdgeWeightType, GraphError<Self::IndexType>> {
+        let index = self
+            .edge_index(edge)
+            .map_err(|_| GraphError::MissingEdge(edge))?;
+        self.mapped_result(self._edge_weight(index))
+    }
+
+    fn edge_weight_mut(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+    ) -> Result<&mut Self::EdgeWeightType, GraphError<Self::IndexType>> {
+        let (from, to) = self
+            .edge_index(edge)
+            .map_err(|_| GraphError::MissingEdge(edge))?;
+        self.adjacency_matrix[from][to]
+            .as_mut()
+            .ok_or(GraphError::MissingEdge(edge))
     }
 
-    fn edge_weight(&self, edge: Edge<usize>) -> Result<&Ew, GraphError<usize>> {
-        self._edge_weight(edge)
+    #[allow(clippy::type_complexity)]
+    fn update_edges(
+        &mut self,
+        update: &mut dyn FnMut(Edge<Self::IndexType>, &mut Self::EdgeWeightType),
+    ) {
+        let mapping = &self.mapping;
+        for (from, weights) in self.adjacency_matrix.iter_mut().enumerate() {
+            for (to, weight) in weights.iter_mut().enumerate() {
+                if let Some(weight) = weight {
+                    update((mapping.id(from), mapping.id(to)), weight);
+                }
+            }
+        }
     }
 
-    fn has_edge(&self, edge: Edge<usize>) -> bool {
-        self._has_edge(edge)
+    fn has_edge(&self, edge: Edge<Self::IndexType>) -> bool {
+        match self.edge_index(edge) {
+            Ok(index) => self._has_edge(index),
+            Err(_) => false,
+        }
     }
 
-    fn add_edge(&mut self, edge: Edge<usize>, weight: Ew) -> Result<(), GraphError<usize>> {
-        self._add_edge(edge, weight)
+    fn add_edge(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+        weight: Ew,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        let index = self.edge_index(edge)?;
+        self.check_weight(edge, weight)?;
+        let res = self._add_edge(index, weight);
+        self.mapped_result(res)
     }
 
-    fn remove_edge(&mut self, edge: Edge<usize>) {
-        self._remove_edge(edge)
+    fn remove_edge(&mut self, edge: Edge<Self::IndexType>) {
+        if let Ok(index) = self.edge_index(edge) {
+            self._remove_edge(index);
+        }
     }
 
-    fn change_edge(&mut self, edge: Edge<usize>, weight: Ew) -> Result<(), GraphError<usize>> {
-        self._change_edge(edge, weight)
+    fn change_edge(
+        &mut self,
+        edge: Edge<Self::IndexType>,
+        weight: Self::EdgeWeightType,
+    ) -> Result<(), GraphError<Self::IndexType>> {
+        let index = self.edge_index(edge)?;
+        self.check_weight(edge, weight)?;
+        let res = self._change_edge(index, weight);
+        self.mapped_result(res)
     }
 
+    /// Returns the shortest path from from_node to every node, or None for unreachable nodes.
+    /// No node is reachable from a node, which is not in the graph. Negative weights are handled
+    /// according to the NegativeWeightPolicy of the graph.
     fn shortest_paths(
         &self,
-        from_node: usize,
+        from_node: Self::IndexType,
     ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Ew)>> {
-        let (prevs, dists) = self._shortest_paths(from_node);
-        let mut res = BTreeMap::new();
-
-        for i in 0..prevs.len() {
-            let mut created = false;
-            let mut solution: Solution<Self::IndexType> = Solution::from_nodes(vec![i]);
-            let mut prev = prevs[i];
-            while let Some(node) = prev {
-                created = true;
-
-                // only push self once (probably only important for to_node)
-                if i != node {
-                    solution.push_node(node);
-                }
-
-                let n_prev = prevs[node];
-                // only to_node has prev[node] == node so we iterate as long as we haven't reached that
-                if n_prev != prev {
-                    prev = n_prev
-                } else {
-                    prev = None
-                }
-            }
-
-            // the solution needs to be reversed for us to start at the from_node
-            solution.reverse();
-
-            // if we can reach the node we created a solution
-            if created {
-                res.insert(i, Some((solution, dists[i])));
-            } else {
-                res.insert(i, None);
+        match self.index(from_node) {
+            Some(index) => {
+                let (prevs, dists) = self.search_paths(index, false);
+                self.collect_paths(&prevs, &dists, true)
             }
+            None => self.iter_node_ids().map(|id| (id, None)).collect(),
         }
+    }
 
-        res
+    /// Returns up to k shortest paths from from_node to to_node, which don't visit a node
+    /// twice, with their lengths. There are no paths from or to nodes, which are not in the
+    /// graph.
+    fn k_shortest_paths(
+        &self,
+        from_node: Self::IndexType,
+        to_node: Self::IndexType,
+        k: usize,
+    ) -> Vec<(Solution<Self::IndexType>, Ew)> {
+        match (self.index(from_node), self.index(to_node)) {
+            (Some(from), Some(to)) => self
+                ._k_shortest_paths(from, to, k)
+                .into_iter()
+                .map(|(path, length)| {
+                    let nodes = path.into_iter().map(|index| self.mapping.id(index));
+                    (Solution::from_nodes(nodes.collect()), length)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
     }
 
+    /// Returns the shortest path from every node to to_node, or None for nodes it can't be
+    /// reached from. A node, which is not in the graph, can't be reached at all.
     fn inv_shortest_paths(
         &self,
-        to_node: usize,
+        to_node: Self::IndexType,
     ) -> BTreeMap<Self::IndexType, Option<(Solution<Self::IndexType>, Ew)>> {
-        let (prevs, dists) = self._inv_shortest_paths(to_node);
-        let mut res = BTreeMap::new();
-
-        for i in 0..prevs.len() {
-            let mut created = false;
-            let mut solution: Solution<Self::IndexType> = Solution::from_nodes(vec![i]);
-            let mut prev = prevs[i];
-            while let Some(node) = prev {
-                created = true;
-
-                // only push self once (probably only important for to_node)
-                if i != node {
-                    solution.push_node(node);
-                }
-
-                let n_prev = prevs[node];
-                // only to_node has prev[node] == node so we iterate as long as we haven't reached that
-                if n_prev != prev {
-                    prev = n_prev
-                } else {
-                    prev = None
-                }
-            }
-            // if we can reach the node we created a solution
-            if created {
-                res.insert(i, Some((solution, dists[i])));
-            } else {
-                res.insert(i, None);
+        match self.index(to_node) {
+            Some(index) => {
+                let (prevs, dists) = self.search_paths(index, true);
+                self.collect_paths(&prevs, &dists, false)
             }
+            None => self.iter_node_ids().map(|id| (id, None)).collect(),
         }
-
-        res
     }
 }
 
-// impl<Nw: Copy, Ew: Copy> WeightedGraph for MatrixGraph<usize, Nw, Ew> {}
-
-// impl<Nw: Copy, Ew: Copy> GeoGraph for MatrixGraph<GeoPoint, Nw, Ew> {}
-
 #[cfg(test)]
 mod usize_indexed_tests {
     use super::*;
     use crate::graph::GenericWeightedGraph;
-    use test::Bencher;
-    extern crate test;
 
     fn valid_weighted() -> MatrixGraph<usize, usize, usize> {
         MatrixGraph::new_usize_indexed(
@@ -1215,6 +1551,28 @@ mod usize_indexed_tests {
         );
     }
 
+    #[test]
+    fn new_with_duplicate_edges_follows_policy() {
+        let edges = vec![(0, 1, 5), (1, 0, 1), (0, 1, 3), (0, 1, 4)];
+        let weight = |duplicates| {
+            MatrixGraph::new_usize_indexed_with_duplicates(vec![1, 2], edges.clone(), duplicates)
+                .map(|graph| (graph.edge_weight((0, 1)).copied(), graph.size()))
+        };
+
+        assert_eq!(
+            weight(DuplicatePolicy::Error),
+            Err(GraphError::DuplicateEdge((0, 1)))
+        );
+        assert_eq!(weight(DuplicatePolicy::KeepMin), Ok((Ok(3), 2)));
+        assert_eq!(weight(DuplicatePolicy::KeepMax), Ok((Ok(5), 2)));
+        assert_eq!(weight(DuplicatePolicy::Overwrite), Ok((Ok(4), 2)));
+        assert_eq!(
+            MatrixGraph::new_usize_indexed(vec![1, 2], edges).map(|graph| graph.size()),
+            Ok(2),
+            "Duplicates should only be counted once."
+        );
+    }
+
     #[test]
     fn is_empty_works() {
         let not_empty = valid_weighted();
@@ -1535,6 +1893,31 @@ mod usize_indexed_tests {
         )
     }
 
+    #[test]
+    fn adding_duplicate_edge_follows_policy() {
+        let mut graph = valid_weighted();
+        assert_eq!(
+            graph.add_edge_with_duplicates((0, 1), 50, DuplicatePolicy::Error),
+            Err(GraphError::DuplicateEdge((0, 1)))
+        );
+        graph
+            .add_edge_with_duplicates((0, 1), 150, DuplicatePolicy::KeepMin)
+            .unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&100));
+        graph
+            .add_edge_with_duplicates((0, 1), 150, DuplicatePolicy::KeepMax)
+            .unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&150));
+        graph
+            .add_edge_with_duplicates((0, 1), 120, DuplicatePolicy::Overwrite)
+            .unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&120));
+        graph
+            .add_edge_with_duplicates((0, 2), 10, DuplicatePolicy::Error)
+            .unwrap();
+        assert_eq!(graph.size(), 5);
+    }
+
     #[test]
     fn adding_edge_with_missing_node_errors() {
         let mut graph = valid_weighted();
@@ -1659,6 +2042,210 @@ mod usize_indexed_tests {
         );
     }
 
+    #[test]
+    fn k_shortest_paths_works() {
+        // the example graph of Yen's algorithm with the nodes C, D, E, F, G, H as 0 to 5
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![0; 6],
+            vec![
+                (0, 1, 3),
+                (0, 2, 2),
+                (1, 3, 4),
+                (2, 1, 1),
+                (2, 3, 2),
+                (2, 4, 3),
+                (3, 4, 2),
+                (3, 5, 1),
+                (4, 5, 2),
+            ],
+        )
+        .unwrap();
+
+        let paths = graph.k_shortest_paths(0, 5, 3);
+        assert_eq!(
+            paths,
+            vec![
+                (Solution::from_nodes(vec![0, 2, 3, 5]), 5),
+                (Solution::from_nodes(vec![0, 2, 4, 5]), 7),
+                (Solution::from_nodes(vec![0, 1, 3, 5]), 8),
+            ]
+        );
+
+        let lengths: Vec<_> = graph
+            .k_shortest_paths(0, 5, 10)
+            .into_iter()
+            .map(|(_, length)| length)
+            .collect();
+        assert_eq!(
+            lengths,
+            vec![5, 7, 8, 8, 8, 11, 11],
+            "there are only 7 paths without loops"
+        );
+    }
+
+    #[test]
+    fn k_shortest_paths_handles_missing_paths() {
+        let graph = valid_weighted();
+        assert_eq!(
+            graph.k_shortest_paths(0, 2, 3),
+            vec![(Solution::from_nodes(vec![0, 1, 2]), 201)]
+        );
+        assert!(graph.k_shortest_paths(0, 2, 0).is_empty());
+        assert!(graph.k_shortest_paths(0, 7, 3).is_empty());
+    }
+
+    #[test]
+    fn negative_weights_are_rejected_by_default() {
+        let mut graph = MatrixGraph::new_usize_indexed(vec![0, 0], vec![(0, 1, 2i64)]).unwrap();
+        assert_eq!(
+            graph.change_edge((0, 1), -1),
+            Err(GraphError::NegativeWeight((0, 1)))
+        );
+        assert_eq!(
+            graph.add_edge((1, 0), -1),
+            Err(GraphError::NegativeWeight((1, 0)))
+        );
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&2));
+
+        let mut graph = graph.with_negative_weights(NegativeWeightPolicy::BellmanFord);
+        assert!(graph.change_edge((0, 1), -1).is_ok());
+    }
+
+    #[test]
+    fn bellman_ford_handles_negative_weights() {
+        // the detour over 2 is shorter than the direct edge to 1
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![0, 0, 0],
+            vec![(0, 1, 4i64), (0, 2, 5), (2, 1, -3), (1, 0, 1)],
+        )
+        .unwrap()
+        .with_negative_weights(NegativeWeightPolicy::BellmanFord);
+
+        let map = graph.shortest_paths(0);
+        assert_eq!(map[&1], Some((Solution::from_nodes(vec![0, 2, 1]), 2)));
+        assert_eq!(map[&2], Some((Solution::from_nodes(vec![0, 2]), 5)));
+        let map = graph.inv_shortest_paths(0);
+        assert_eq!(map[&2], Some((Solution::from_nodes(vec![2, 1, 0]), -2)));
+    }
+
+    #[test]
+    fn nodes_behind_negative_cycles_are_unreachable() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![0, 0, 0, 0],
+            vec![(0, 1, 1i64), (1, 2, 1), (2, 1, -2), (2, 3, 1), (0, 3, 5)],
+        )
+        .unwrap()
+        .with_negative_weights(NegativeWeightPolicy::BellmanFord);
+
+        let map = graph.shortest_paths(0);
+        assert_eq!(map[&0], Some((Solution::from_nodes(vec![0]), 0)));
+        assert_eq!(map[&1], None);
+        assert_eq!(map[&2], None);
+        assert_eq!(map[&3], None);
+    }
+
+    fn with_dead_ends() -> MatrixGraph<usize, usize, usize> {
+        // a triangle 0, 1, 2 with the chain 2 - 3 -> 4, the rewarded dead end 6 at 0 and the
+        // isolated node 5
+        let mut weights = vec![0; 7];
+        weights[6] = 5;
+        MatrixGraph::new_usize_indexed(
+            weights,
+            vec![
+                (0, 1, 3),
+                (1, 0, 3),
+                (1, 2, 3),
+                (2, 1, 3),
+                (2, 0, 3),
+                (0, 2, 3),
+                (2, 3, 1),
+                (3, 2, 1),
+                (3, 4, 1),
+                (0, 6, 1),
+                (6, 0, 1),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cleanup_removes_unusable_nodes() {
+        let mut graph = with_dead_ends();
+        assert_eq!(graph.remove_isolated_nodes(), 1);
+        assert_eq!(graph.node_ids(), vec![0, 1, 2, 3, 4, 6]);
+
+        let mut graph = with_dead_ends();
+        assert_eq!(graph.prune_dead_ends(true), 3);
+        assert_eq!(graph.node_ids(), vec![0, 1, 2, 6]);
+        assert_eq!(graph.size(), 8);
+
+        let mut graph = with_dead_ends();
+        assert_eq!(graph.prune_dead_ends(false), 4);
+        assert_eq!(graph.node_ids(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parallel_edges_keep_the_minimal_weight() {
+        let mut graph = with_dead_ends();
+        graph.add_edge_keep_min((0, 1), 5).unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&3));
+        graph.add_edge_keep_min((0, 1), 2).unwrap();
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&2));
+        graph.add_edge_keep_min((4, 3), 7).unwrap();
+        assert_eq!(graph.edge_weight((4, 3)), Ok(&7));
+        assert_eq!(graph.size(), 12);
+        assert_eq!(
+            graph.add_edge_keep_min((0, 9), 1),
+            Err(GraphError::MissingNode(9))
+        );
+    }
+
+    #[test]
+    fn index_mappings_agree() {
+        let mut keyed = MatrixGraph::<usize, usize, usize>::with_size(4);
+        let mut identity = MatrixGraph::<usize, usize, usize>::with_size_usize_indexed(4);
+        for graph in vec![&mut keyed, &mut identity] {
+            // keyed maps 3 to index 0, identity keeps it at 3
+            for &id in &[3, 0, 1, 2] {
+                graph.add_node(id, id).unwrap();
+            }
+            for &edge in &[(3, 0), (0, 1), (1, 2), (3, 2), (2, 3)] {
+                graph.add_edge(edge, 1).unwrap();
+            }
+            graph.remove_node(0);
+        }
+
+        assert_eq!(keyed.node_ids(), identity.node_ids());
+        // edges are iterated in order of their indices
+        let mut edges = keyed.edge_ids();
+        edges.sort_unstable();
+        assert_eq!(edges, identity.edge_ids());
+        assert_eq!(keyed.shortest_paths(1), identity.shortest_paths(1));
+        assert_eq!(keyed.inv_shortest_paths(3), identity.inv_shortest_paths(3));
+        assert_eq!(
+            identity.inv_shortest_paths(3)[&1],
+            Some((Solution::from_nodes(vec![1, 2, 3]), 2))
+        );
+        assert_eq!(identity.shortest_paths(0), keyed.shortest_paths(0));
+        assert_eq!(identity.shortest_paths(0)[&1], None);
+    }
+}
+
+#[cfg(all(test, feature = "nightly"))]
+mod usize_indexed_benches {
+    use super::*;
+    use crate::graph::GenericWeightedGraph;
+    use test::Bencher;
+    extern crate test;
+
+    fn valid_weighted() -> MatrixGraph<usize, usize, usize> {
+        MatrixGraph::new_usize_indexed(
+            vec![1, 2, 3],
+            vec![(0, 1, 100), (1, 2, 101), (2, 1, 50), (2, 0, 200)],
+        )
+        .unwrap()
+    }
+
     #[bench]
     fn bench_iter_edge_ids(b: &mut Bencher) {
         let graph = valid_weighted();
@@ -1692,6 +2279,44 @@ mod usize_indexed_tests {
         })
     }
 
+    fn complete(order: usize) -> MatrixGraph<usize, usize, usize> {
+        let edges = (0..order)
+            .flat_map(|from| (0..order).map(move |to| (from, to, from + to)))
+            .collect();
+        MatrixGraph::new_usize_indexed(vec![1; order], edges).unwrap()
+    }
+
+    #[bench]
+    fn bench_iter_neighbors_boxed(b: &mut Bencher) {
+        let graph = complete(50);
+        let graph: &dyn GenericWeightedGraph<
+            IndexType = usize,
+            NodeWeightType = usize,
+            EdgeWeightType = usize,
+        > = &graph;
+
+        b.iter(|| {
+            for node in 0..50 {
+                for (_, weight) in graph.iter_neighbors(node).unwrap() {
+                    test::black_box(weight);
+                }
+            }
+        })
+    }
+
+    #[bench]
+    fn bench_iter_neighbors(b: &mut Bencher) {
+        let graph = complete(50);
+
+        b.iter(|| {
+            for node in 0..50 {
+                for (_, weight) in graph.iter_neighbors(node).unwrap() {
+                    test::black_box(weight);
+                }
+            }
+        })
+    }
+
     #[bench]
     fn bench_sequential_weights(b: &mut Bencher) {
         let graph = valid_weighted();
@@ -1711,7 +2336,6 @@ mod geopoint_indexed_tests {
     use super::*;
     use crate::geo::GeoPoint;
     use crate::graph::GenericWeightedGraph;
-    extern crate test;
 
     fn valid_weighted() -> MatrixGraph<GeoPoint, usize, usize> {
         let p1 = GeoPoint::from_degrees(12.7, 21.8);
@@ -1756,11 +2380,16 @@ mod geopoint_indexed_tests {
             "Internal graph is not empty."
         );
         assert!(graph.is_empty(), "Graph is not empty.");
-        assert!(graph.node_map.is_empty(), "Node map is not empty.");
-        assert!(
-            graph.inv_node_map.is_empty(),
-            "Inverse node map is not empty."
-        );
+        match graph.mapping {
+            IndexMapping::Keyed {
+                node_map,
+                inv_node_map,
+            } => {
+                assert!(node_map.is_empty(), "Node map is not empty.");
+                assert!(inv_node_map.is_empty(), "Inverse node map is not empty.");
+            }
+            IndexMapping::Identity { .. } => panic!("GeoPoints are not usable as indices."),
+        }
     }
 
     #[test]
@@ -1788,14 +2417,14 @@ mod geopoint_indexed_tests {
         let graph = valid_weighted();
 
         assert_eq!(graph.order(), 3, "Internal graph is too small.");
-        assert!(
-            graph
-                .node_map
-                .contains_key(&GeoPoint::from_degrees(12.7, 21.8)),
+        assert_eq!(
+            graph.mapping.index(GeoPoint::from_degrees(12.7, 21.8)),
+            Some(0),
             "Node map is missing a key."
         );
-        assert!(
-            graph.inv_node_map.contains_key(&2),
+        assert_eq!(
+            graph.mapping.id(2),
+            GeoPoint::from_degrees(11.1, 32.5),
             "Inverse node map is missing a key."
         );
 
@@ -2211,6 +2840,35 @@ mod geopoint_indexed_tests {
         );
     }
 
+    #[test]
+    fn weights_can_be_changed_in_place() {
+        let mut graph = valid_weighted();
+        let p1 = GeoPoint::from_degrees(12.7, 21.8);
+        let p2 = GeoPoint::from_degrees(9.7, 12.5);
+        let p4 = GeoPoint::from_degrees(2.4, 53.3);
+
+        *graph.node_weight_mut(p1).unwrap() += 1;
+        *graph.edge_weight_mut((p1, p2)).unwrap() += 1;
+        assert_eq!(graph.node_weight(p1).unwrap(), &13);
+        assert_eq!(graph.edge_weight((p1, p2)).unwrap(), &101);
+        assert_eq!(
+            graph.node_weight_mut(p4).err(),
+            Some(GraphError::MissingNode(p4))
+        );
+        assert_eq!(
+            graph.edge_weight_mut((p2, p1)).err(),
+            Some(GraphError::MissingEdge((p2, p1)))
+        );
+
+        let mut visited = Vec::new();
+        graph.update_edges(&mut |edge, weight| {
+            visited.push(edge);
+            *weight *= 2;
+        });
+        assert_eq!(visited, graph.edge_ids());
+        assert_eq!(graph.edge_weight((p1, p2)).unwrap(), &202);
+    }
+
     #[test]
     fn shortest_paths_works() {
         let graph = valid_weighted();
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::graph::GenericWeightedGraph;
+
+/// How weights are brought to a common scale, so heuristics mixing rewards and travel times
+/// weigh them alike across instances.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Maps the weights to the range from 0 to 1.
+    MinMax,
+    /// Maps the weights to a mean of 0 and a standard deviation of 1, so the weights below
+    /// the mean become negative. Graph weights have to stay non-negative, so they are only
+    /// divided by the standard deviation.
+    ZScore,
+}
+
+/// The linear map of a normalization, under which a weight becomes
+/// (weight - offset) / factor. Recorded with the results, so they can be converted back.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Scaling {
+    pub offset: f64,
+    pub factor: f64,
+}
+
+impl Scaling {
+    pub const IDENTITY: Scaling = Scaling {
+        offset: 0.0,
+        factor: 1.0,
+    };
+
+    pub fn apply(&self, weight: f64) -> f64 {
+        (weight - self.offset) / self.factor
+    }
+
+    /// Returns the weight, which was normalized to normalized.
+    pub fn restore(&self, normalized: f64) -> f64 {
+        normalized * self.factor + self.offset
+    }
+}
+
+impl fmt::Display for Scaling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(weight - {}) / {}", self.offset, self.factor)
+    }
+}
+
+impl Normalization {
+    /// Returns the scaling, which normalizes values. Values without any spread are only
+    /// shifted, no values are left as they are.
+    pub fn scaling(self, values: impl IntoIterator<Item = f64>) -> Scaling {
+        let values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return Scaling::IDENTITY;
+        }
+        let (offset, spread) = match self {
+            Normalization::MinMax => {
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (min, max - min)
+            }
+            Normalization::ZScore => {
+                let count = values.len() as f64;
+                let mean = values.iter().sum::<f64>() / count;
+                let variance = values
+                    .iter()
+                    .map(|value| (value - mean) * (value - mean))
+                    .sum::<f64>()
+                    / count;
+                (mean, variance.sqrt())
+            }
+        };
+        Scaling {
+            offset,
+            factor: if spread > 0.0 { spread } else { 1.0 },
+        }
+    }
+
+    /// Returns the scaling, which only divides values and keeps their ratios. MinMax divides
+    /// by the largest value, so positive values end up at most 1, ZScore by the standard
+    /// deviation.
+    pub fn ratio_scaling(self, values: impl IntoIterator<Item = f64>) -> Scaling {
+        let spread = match self {
+            Normalization::MinMax => values.into_iter().fold(0.0, f64::max),
+            Normalization::ZScore => self.scaling(values).factor,
+        };
+        Scaling {
+            offset: 0.0,
+            factor: if spread > 0.0 { spread } else { 1.0 },
+        }
+    }
+}
+
+/// Normalizes the weights of all edges of graph with method and returns the scaling used.
+/// Edge weights are only divided by the factor of ratio_scaling and never shifted, as shifting
+/// them would change the length of each route by its amount of edges, which no budget can
+/// follow. The budget and service times just need to be divided by the same factor.
+pub fn normalize_edge_weights<G>(graph: &mut G, method: Normalization) -> Scaling
+where
+    G: GenericWeightedGraph + ?Sized,
+    G::IndexType: Clone,
+    G::EdgeWeightType: Copy + Into<f64> + From<f64>,
+{
+    let scaling = method.ratio_scaling(graph.iter_edges().map(|(_, &weight)| weight.into()));
+    graph.update_edges(&mut |_, weight| *weight = scaling.apply((*weight).into()).into());
+    scaling
+}
+
+/// Normalizes the weights of all nodes of graph with method and returns the scaling used.
+/// ZScore only divides the weights by the factor of ratio_scaling, as shifting them would turn
+/// the rewards below the mean negative, which the heuristics can't weigh.
+pub fn normalize_node_weights<G>(graph: &mut G, method: Normalization) -> Scaling
+where
+    G: GenericWeightedGraph + ?Sized,
+    G::NodeWeightType: Copy + Into<f64> + From<f64>,
+{
+    let weights = graph.iter_nodes().map(|(_, &weight)| weight.into());
+    let scaling = match method {
+        Normalization::MinMax => method.scaling(weights),
+        Normalization::ZScore => method.ratio_scaling(weights),
+    };
+    for node in graph.node_ids() {
+        if let Ok(weight) = graph.node_weight_mut(node) {
+            *weight = scaling.apply((*weight).into()).into();
+        }
+    }
+    scaling
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    use decorum::R64;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn scalings_normalize_values() {
+        let values = vec![2.0, 4.0, 6.0, 8.0];
+        let min_max = Normalization::MinMax.scaling(values.clone());
+        assert_eq!(min_max.apply(2.0), 0.0);
+        assert_eq!(min_max.apply(8.0), 1.0);
+        assert_eq!(min_max.restore(0.5), 5.0);
+
+        let z_score = Normalization::ZScore.scaling(values);
+        assert_eq!(z_score.offset, 5.0);
+        assert_eq!(z_score.factor, 5.0f64.sqrt());
+
+        let flat = Normalization::MinMax.scaling(vec![3.0, 3.0]);
+        assert_eq!(flat.apply(3.0), 0.0);
+        assert_eq!(Normalization::ZScore.scaling(vec![]), Scaling::IDENTITY);
+
+        let ratio = Normalization::MinMax.ratio_scaling(vec![2.0, 8.0]);
+        assert_eq!((ratio.apply(2.0), ratio.apply(8.0)), (0.25, 1.0));
+        assert_eq!(
+            Normalization::ZScore.ratio_scaling(vec![0.0, 4.0]),
+            Scaling {
+                offset: 0.0,
+                factor: 2.0
+            }
+        );
+    }
+
+    #[test]
+    fn graph_weights_are_normalized() {
+        let mut graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(10.0), r(5.0)],
+            vec![(0, 1, r(2.0)), (1, 2, r(4.0)), (2, 0, r(6.0))],
+        )
+        .unwrap();
+        let edges = normalize_edge_weights(&mut graph, Normalization::MinMax);
+        let nodes = normalize_node_weights(&mut graph, Normalization::MinMax);
+        assert_eq!(edges.factor, 6.0);
+        assert_eq!(nodes.factor, 10.0);
+        assert_eq!(graph.edge_weight((0, 1)), Ok(&r(2.0 / 6.0)));
+        assert_eq!(graph.edge_weight((2, 0)), Ok(&r(1.0)));
+        assert_eq!(graph.node_weight(0), Ok(&r(0.0)));
+        assert_eq!(graph.node_weight(2), Ok(&r(0.5)));
+
+        let nodes = normalize_node_weights(&mut graph, Normalization::ZScore);
+        assert_eq!(nodes.offset, 0.0);
+        assert!(graph.iter_nodes().all(|(_, &weight)| weight >= r(0.0)));
+    }
+}
@@ -0,0 +1,118 @@
+use num_traits::Zero;
+use std::fmt;
+
+use crate::graph::GenericWeightedGraph;
+
+/// Size and weight ranges of a graph, to sanity check graphs after loading them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphSummary<Nw, Ew> {
+    pub order: usize,
+    pub size: usize,
+    /// Smallest and largest node weight, None for graphs without nodes.
+    pub node_weights: Option<(Nw, Nw)>,
+    /// Nodes with a weight above zero.
+    pub weighted_nodes: usize,
+    /// Smallest and largest edge weight, None for graphs without edges.
+    pub edge_weights: Option<(Ew, Ew)>,
+}
+
+impl<Nw, Ew> GraphSummary<Nw, Ew>
+where
+    Nw: Copy + PartialOrd + Zero,
+    Ew: Copy + PartialOrd,
+{
+    pub fn of<G>(graph: &G) -> Self
+    where
+        G: GenericWeightedGraph<NodeWeightType = Nw, EdgeWeightType = Ew> + ?Sized,
+    {
+        let node_weights: Vec<Nw> = graph.iter_nodes().map(|(_, weight)| *weight).collect();
+        GraphSummary {
+            order: graph.order(),
+            size: graph.size(),
+            node_weights: range(node_weights.iter().copied()),
+            weighted_nodes: node_weights
+                .iter()
+                .filter(|&&weight| weight > Nw::zero())
+                .count(),
+            edge_weights: range(graph.iter_edges().map(|(_, weight)| *weight)),
+        }
+    }
+}
+
+fn range<T: Copy + PartialOrd>(values: impl Iterator<Item = T>) -> Option<(T, T)> {
+    values.fold(None, |range, value| match range {
+        None => Some((value, value)),
+        Some((min, max)) => Some((
+            if value < min { value } else { min },
+            if value > max { value } else { max },
+        )),
+    })
+}
+
+/// Weights are shown with two decimals, unless another precision is given.
+impl<Nw: fmt::Display, Ew: fmt::Display> fmt::Display for GraphSummary<Nw, Ew> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(2);
+        write!(f, "{} nodes", self.order)?;
+        if let Some((min, max)) = &self.node_weights {
+            write!(
+                f,
+                " ({} weighted, weights {:.*} to {:.*})",
+                self.weighted_nodes, precision, min, precision, max
+            )?;
+        }
+        write!(f, ", {} edges", self.size)?;
+        if let Some((min, max)) = &self.edge_weights {
+            write!(
+                f,
+                " (weights {:.*} to {:.*})",
+                precision, min, precision, max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::MatrixGraph;
+
+    use decorum::R64;
+
+    #[test]
+    fn summaries_show_size_and_weight_ranges() {
+        let r = R64::from_inner;
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(2.0), r(5.0)],
+            vec![(0, 1, r(1.5)), (1, 2, r(4.0)), (2, 0, r(2.0))],
+        )
+        .unwrap();
+        assert_eq!(
+            graph.to_string(),
+            "3 nodes (2 weighted, weights 0.00 to 5.00), 3 edges (weights 1.50 to 4.00)"
+        );
+        assert_eq!(
+            format!("{:.1}", graph.summary()),
+            "3 nodes (2 weighted, weights 0.0 to 5.0), 3 edges (weights 1.5 to 4.0)"
+        );
+
+        let empty =
+            MatrixGraph::<usize, R64, R64>::new_usize_indexed(Vec::new(), Vec::new()).unwrap();
+        assert_eq!(empty.to_string(), "0 nodes, 0 edges");
+    }
+
+    #[test]
+    fn debug_shows_the_summary_and_alternate_debug_the_edges() {
+        let r = R64::from_inner;
+        let graph =
+            MatrixGraph::new_usize_indexed(vec![r(0.0), r(2.0)], vec![(0, 1, r(1.5))]).unwrap();
+        assert_eq!(
+            format!("{:?}", graph),
+            "MatrixGraph { order: 2, size: 1, node_weights: Some((Finite(0.0), Finite(2.0))), \
+             weighted_nodes: 1, edge_weights: Some((Finite(1.5), Finite(1.5))) }"
+        );
+        let alternate = format!("{:#?}", graph);
+        assert!(alternate.starts_with("MatrixGraph {\n    order: 2,\n    size: 1,"));
+        assert!(alternate.contains("nodes: [") && alternate.contains("edges: ["));
+    }
+}
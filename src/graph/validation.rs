@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+
+use crate::graph::{Edge, GenericWeightedGraph};
+
+/// What happens to graphs failing their validation, after they were generated or imported.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationPolicy {
+    /// Reports the problems and continues with the graph.
+    #[default]
+    Warn,
+    /// Rejects the graph.
+    Fail,
+}
+
+/// Problems of a graph, which make routes on it meaningless or let the metaheuristics fail.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport<IndexType> {
+    pub goal_point: Option<IndexType>,
+    /// Nodes with an edge to themselves.
+    pub self_loops: Vec<IndexType>,
+    /// Nodes with a negative, infinite or NaN weight.
+    pub invalid_node_weights: Vec<IndexType>,
+    /// Edges with a negative, infinite or NaN weight.
+    pub invalid_edge_weights: Vec<Edge<IndexType>>,
+    /// Nodes, which can't be reached from the goal point or can't reach it, so no route visits them.
+    pub unreachable: Vec<IndexType>,
+}
+
+impl<IndexType> ValidationReport<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Ord + Debug + Display,
+{
+    /// Validates graph, nodes are only checked for reachability if a goal point is given.
+    pub fn of<G, Nw, Ew>(graph: &G, goal_point: Option<IndexType>) -> Self
+    where
+        G: GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + ?Sized,
+        Nw: Copy + Into<f64>,
+        Ew: Copy + Into<f64>,
+    {
+        let invalid = |weight: f64| !weight.is_finite() || weight < 0.0;
+        let mut self_loops = Vec::new();
+        let mut invalid_edge_weights = Vec::new();
+        // edges leading to each node, to search backwards from the goal point
+        let mut incoming: HashMap<IndexType, Vec<IndexType>> = HashMap::new();
+        for ((from, to), &weight) in graph.iter_edges() {
+            if from == to {
+                self_loops.push(from);
+            }
+            if invalid(weight.into()) {
+                invalid_edge_weights.push((from, to));
+            }
+            incoming.entry(to).or_default().push(from);
+        }
+        let mut invalid_node_weights: Vec<IndexType> = graph
+            .iter_nodes()
+            .filter(|(_, &weight)| invalid(weight.into()))
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut unreachable = Vec::new();
+        if let Some(goal_point) = goal_point {
+            let reached = reachable(goal_point, |node| {
+                graph
+                    .iter_neighbor_ids(node)
+                    .map(|neighbors| neighbors.collect())
+                    .unwrap_or_default()
+            });
+            let returning = reachable(goal_point, |node| {
+                incoming.get(&node).cloned().unwrap_or_default()
+            });
+            unreachable = graph
+                .iter_node_ids()
+                .filter(|node| !reached.contains(node) || !returning.contains(node))
+                .collect();
+        }
+
+        self_loops.sort_unstable();
+        invalid_node_weights.sort_unstable();
+        invalid_edge_weights.sort_unstable();
+        unreachable.sort_unstable();
+        ValidationReport {
+            goal_point,
+            self_loops,
+            invalid_node_weights,
+            invalid_edge_weights,
+            unreachable,
+        }
+    }
+
+    /// Returns true if no problems were found, or false otherwise.
+    pub fn is_valid(&self) -> bool {
+        self.self_loops.is_empty()
+            && self.invalid_node_weights.is_empty()
+            && self.invalid_edge_weights.is_empty()
+            && self.unreachable.is_empty()
+    }
+}
+
+/// Returns all nodes reachable from start, including start, moving along next.
+fn reachable<IndexType: Copy + Hash + Eq>(
+    start: IndexType,
+    mut next: impl FnMut(IndexType) -> Vec<IndexType>,
+) -> HashSet<IndexType> {
+    let mut reached = HashSet::new();
+    reached.insert(start);
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        for neighbor in next(node) {
+            if reached.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+    reached
+}
+
+fn join<T: Debug>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| format!("{:?}", value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<IndexType: Debug + Display> fmt::Display for ValidationReport<IndexType> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems = Vec::new();
+        if !self.self_loops.is_empty() {
+            problems.push(format!("self-loops at nodes {}", join(&self.self_loops)));
+        }
+        if !self.invalid_node_weights.is_empty() {
+            problems.push(format!(
+                "negative or not finite weights at nodes {}",
+                join(&self.invalid_node_weights)
+            ));
+        }
+        if !self.invalid_edge_weights.is_empty() {
+            problems.push(format!(
+                "negative or not finite weights at edges {}",
+                join(&self.invalid_edge_weights)
+            ));
+        }
+        if let (Some(goal_point), false) = (&self.goal_point, self.unreachable.is_empty()) {
+            problems.push(format!(
+                "nodes {} are not on any route from goal point {}",
+                join(&self.unreachable),
+                goal_point
+            ));
+        }
+        if problems.is_empty() {
+            write!(f, "Graph is valid.")
+        } else {
+            write!(f, "Graph has {}.", problems.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    use decorum::R64;
+
+    #[test]
+    fn problems_are_reported() {
+        let r = R64::from_inner;
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![0.0, 1.0, -2.0, 1.0, f64::NAN],
+            vec![
+                (0, 1, r(1.0)),
+                (1, 0, r(1.0)),
+                (1, 1, r(1.0)),
+                (1, 2, r(-1.0)),
+                (3, 0, r(1.0)),
+            ],
+        )
+        .unwrap();
+        let report = ValidationReport::of(&graph, Some(0));
+
+        assert!(!report.is_valid());
+        assert_eq!(report.self_loops, vec![1]);
+        assert_eq!(report.invalid_node_weights, vec![2, 4]);
+        assert_eq!(report.invalid_edge_weights, vec![(1, 2)]);
+        // 2 can't return, 3 can't be reached and 4 has no edges at all
+        assert_eq!(report.unreachable, vec![2, 3, 4]);
+        assert_eq!(
+            report.to_string(),
+            "Graph has self-loops at nodes 1, negative or not finite weights at nodes 2, 4, \
+             negative or not finite weights at edges (1, 2), nodes 2, 3, 4 are not on any route \
+             from goal point 0."
+        );
+        assert!(ValidationReport::of(&graph, None).unreachable.is_empty());
+    }
+
+    #[test]
+    fn policies_decide_about_invalid_graphs() {
+        let r = R64::from_inner;
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(1.0), r(1.0)],
+            vec![(0, 1, r(1.0)), (1, 0, r(1.0)), (0, 2, r(1.0))],
+        )
+        .unwrap();
+        let warned = graph.validate(Some(0), ValidationPolicy::Warn).unwrap();
+        assert!(!warned.is_valid());
+        let report = graph.validate(Some(0), ValidationPolicy::Fail).unwrap_err();
+        assert_eq!(report.unreachable, vec![2]);
+        assert!(graph.validate(None, ValidationPolicy::Fail).is_ok());
+    }
+}
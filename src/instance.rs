@@ -0,0 +1,235 @@
+use crate::graph::{GenericWeightedGraph, GraphError, MatrixGraph};
+
+use csv::{Reader, Writer};
+use decorum::R64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display};
+use std::fs::{create_dir_all, File};
+use std::hash::Hash;
+use std::io;
+use std::path::Path;
+
+pub const NODES_FILE: &str = "nodes.csv";
+pub const EDGES_FILE: &str = "edges.csv";
+pub const INSTANCE_FILE: &str = "instance.yaml";
+
+/// Everything besides the graph, which defines an exported problem instance.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstanceConfig {
+    pub goal_point: usize,
+    /// Budget of the run in the unit of the edge weights.
+    pub max_time: f64,
+    /// Seed and ranges of the weights drawn by graph dynamics.
+    pub seed: u64,
+    pub nw_range: (f64, f64),
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ew_range: Option<(f64, f64)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeRecord {
+    id: usize,
+    reward: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeRecord {
+    from: usize,
+    to: usize,
+    weight: f64,
+}
+
+#[derive(Debug)]
+pub enum InstanceError {
+    Io(io::Error),
+    InvalidCsv(csv::Error),
+    InvalidConfig(serde_yaml::Error),
+    InvalidGraph(GraphError<usize>),
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Could not access instance: {}", e),
+            Self::InvalidCsv(e) => write!(f, "Invalid instance graph: {}", e),
+            Self::InvalidConfig(e) => write!(f, "Invalid {}: {}", INSTANCE_FILE, e),
+            Self::InvalidGraph(e) => write!(f, "Invalid instance graph: {}", e),
+        }
+    }
+}
+
+impl Error for InstanceError {}
+
+impl From<io::Error> for InstanceError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<csv::Error> for InstanceError {
+    fn from(e: csv::Error) -> Self {
+        Self::InvalidCsv(e)
+    }
+}
+
+impl From<serde_yaml::Error> for InstanceError {
+    fn from(e: serde_yaml::Error) -> Self {
+        Self::InvalidConfig(e)
+    }
+}
+
+/// Replaces the node ids of graph by their position in it, which drops the coordinates of
+/// imported graphs, so those are never exported. Returns the new graph and the new id of
+/// every node.
+pub fn anonymize<IndexType>(
+    graph: &dyn GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = R64,
+        EdgeWeightType = R64,
+    >,
+) -> (MatrixGraph<usize, R64, R64>, HashMap<IndexType, usize>)
+where
+    IndexType: Copy + Hash + Eq + Debug + Display,
+{
+    let (ids, nodes): (HashMap<IndexType, usize>, Vec<R64>) = graph
+        .iter_nodes()
+        .enumerate()
+        .map(|(i, (node, weight))| ((node, i), *weight))
+        .unzip();
+    let edges = graph
+        .iter_edges()
+        .map(|((from, to), weight)| (ids[&from], ids[&to], *weight))
+        .collect();
+    let anonymized =
+        MatrixGraph::new_usize_indexed(nodes, edges).expect("all edge ends are nodes of graph");
+    (anonymized, ids)
+}
+
+/// Writes graph and instance to dir, which is created if necessary.
+pub fn write_instance(
+    graph: &MatrixGraph<usize, R64, R64>,
+    instance: &InstanceConfig,
+    dir: &Path,
+) -> Result<(), InstanceError> {
+    create_dir_all(dir)?;
+    let mut nodes = Writer::from_path(dir.join(NODES_FILE))?;
+    for (id, reward) in graph.iter_nodes() {
+        nodes.serialize(NodeRecord {
+            id,
+            reward: reward.into_inner(),
+        })?;
+    }
+    nodes.flush()?;
+    let mut edges = Writer::from_path(dir.join(EDGES_FILE))?;
+    for ((from, to), weight) in graph.iter_edges() {
+        edges.serialize(EdgeRecord {
+            from,
+            to,
+            weight: weight.into_inner(),
+        })?;
+    }
+    edges.flush()?;
+    serde_yaml::to_writer(File::create(dir.join(INSTANCE_FILE))?, instance)?;
+    Ok(())
+}
+
+/// Loads an instance written by write_instance.
+pub fn load_instance(
+    dir: &Path,
+) -> Result<(MatrixGraph<usize, R64, R64>, InstanceConfig), InstanceError> {
+    let instance: InstanceConfig = serde_yaml::from_reader(File::open(dir.join(INSTANCE_FILE))?)?;
+    let mut records: Vec<NodeRecord> = Reader::from_path(dir.join(NODES_FILE))?
+        .deserialize()
+        .collect::<Result<_, _>>()?;
+    records.sort_by_key(|record| record.id);
+    if let Some((i, record)) = records
+        .iter()
+        .enumerate()
+        .find(|(i, record)| record.id != *i)
+    {
+        // ids have to be the positions of the nodes, so a gap or duplicate means a broken file
+        return Err(InstanceError::InvalidGraph(if record.id > i {
+            GraphError::MissingNode(i)
+        } else {
+            GraphError::DuplicateNode(record.id)
+        }));
+    }
+    let nodes = records
+        .into_iter()
+        .map(|record| R64::from_inner(record.reward))
+        .collect();
+    let edges = Reader::from_path(dir.join(EDGES_FILE))?
+        .deserialize()
+        .map(|record| {
+            record
+                .map(|record: EdgeRecord| (record.from, record.to, R64::from_inner(record.weight)))
+        })
+        .collect::<Result<_, _>>()?;
+    let graph =
+        MatrixGraph::new_usize_indexed(nodes, edges).map_err(InstanceError::InvalidGraph)?;
+    if !graph.has_node(instance.goal_point) {
+        return Err(InstanceError::InvalidGraph(GraphError::MissingNode(
+            instance.goal_point,
+        )));
+    }
+    Ok((graph, instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::GeoPoint;
+
+    #[test]
+    fn exported_instances_load_anonymized() {
+        let points = [
+            GeoPoint::from_degrees(52.5, 13.4),
+            GeoPoint::from_degrees(52.6, 13.4),
+            GeoPoint::from_degrees(52.5, 13.5),
+        ];
+        let graph = MatrixGraph::new(
+            vec![
+                (points[0], R64::from_inner(0.0)),
+                (points[1], R64::from_inner(2.0)),
+                (points[2], R64::from_inner(3.0)),
+            ],
+            vec![
+                ((points[0], points[1]), R64::from_inner(1.5)),
+                ((points[1], points[2]), R64::from_inner(2.5)),
+                ((points[2], points[0]), R64::from_inner(3.5)),
+            ],
+        )
+        .unwrap();
+        let (anonymized, ids) = anonymize(&graph);
+        let instance = InstanceConfig {
+            goal_point: ids[&points[0]],
+            max_time: 10.0,
+            seed: 4,
+            nw_range: (0.0, 3.0),
+            ew_range: None,
+        };
+        let dir = std::env::temp_dir().join(format!("instance_test_{}", std::process::id()));
+        write_instance(&anonymized, &instance, &dir).unwrap();
+        let (loaded, loaded_instance) = load_instance(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded_instance, instance);
+        assert_eq!(loaded.order(), 3);
+        for point in points.iter() {
+            assert_eq!(
+                loaded.node_weight(ids[point]).unwrap(),
+                graph.node_weight(*point).unwrap()
+            );
+        }
+        assert_eq!(
+            loaded
+                .edge_weight((ids[&points[1]], ids[&points[2]]))
+                .unwrap(),
+            &R64::from_inner(2.5)
+        );
+        assert!(!loaded.has_edge((ids[&points[1]], ids[&points[0]])));
+    }
+}
@@ -1,8 +1,27 @@
-#![feature(test, min_specialization, map_into_keys_values, total_cmp, map_first_last, map_try_insert)]
+//! Dynamic orienteering problems solved with ant colony optimization.
+//!
+//! The graph, geo, metaheuristic, dynamics and rng modules form the library, [prelude]
+//! re-exports their common types. The remaining modules run and evaluate the experiments of the
+//! binary, which only parses its arguments and calls into them, and may change with it.
+//! The examples directory shows how to use the library without the experiment configs.
+//!
+//! The crate builds on stable. The `nightly` feature enables the benchmarks, which need a
+//! nightly compiler.
+#![cfg_attr(feature = "nightly", feature(test))]
+pub mod bound;
+pub mod commands;
 pub mod dynamic_graph_experiment;
+pub mod dynamics;
 pub mod experiment_config;
 pub mod geo;
 pub mod graph;
+pub mod instance;
 pub mod metaheuristic;
+pub mod plot;
+pub mod prelude;
+pub mod replay;
+pub mod report;
 pub mod rng;
+pub mod run_context;
+pub mod tuning;
 pub mod util;
@@ -1,25 +1,69 @@
 pub mod aco;
 pub mod acs;
+pub mod exact;
+pub mod heuristics;
+pub mod local_search;
 pub mod mm_aco;
+pub mod node_value;
+pub mod objective;
+pub mod observer;
 pub mod random_search;
+pub mod return_costs;
+pub mod revisit_penalty;
+pub mod reward_density;
+pub mod secondary_budget;
+pub mod service_time;
+pub mod similarity;
 mod solution;
 pub mod supervisor;
+pub mod time_window;
 pub mod two_swap;
 
 pub use aco::Aco;
 pub use acs::Acs;
+pub use exact::Exact;
+pub use local_search::shorten_route;
 pub use mm_aco::MMAco;
+pub use node_value::{NodeValue, NodeVector};
+pub use objective::Objective;
+pub use observer::{IterationCallback, IterationEvent, Observer};
 pub use random_search::RandomSearch;
-pub use solution::{solution_length, solution_score, Solution, SolutionError};
+pub use return_costs::{return_paths, GoalDistances, GoalPaths, ReturnCosts, ReturnPaths};
+pub use revisit_penalty::RevisitPenalty;
+pub use reward_density::{DensityStats, RewardNode};
+pub use secondary_budget::SecondaryBudget;
+pub use service_time::ServiceTimes;
+pub use solution::{
+    solution_length, solution_length_with_service_times, solution_score,
+    solution_score_with_penalties, solution_score_with_service_times,
+    solution_score_with_time_windows, Solution, SolutionError,
+};
+pub use time_window::{TimeWindow, TimeWindows};
 pub use two_swap::TwoSwap;
 
 use decorum::R64;
+use num_traits::Zero;
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Add;
+use std::sync::OnceLock;
 
-use crate::graph::GenericWeightedGraph;
+use crate::graph::{GenericWeightedGraph, GraphCell, StochasticEdges};
+use crate::rng::RngState;
+use crate::util::Distance;
 
+/// Scores moving to a node by its weight, the weight of the edge to it, its distance to the goal
+/// point and the share of the budget used up before.
 pub type Heuristic<Nw, Ew> = dyn Fn(Nw, Ew, R64, Ew) -> R64;
 
+/// A heuristic, which can be shared between threads to score TwoSwap candidates on them.
+pub type SyncHeuristic<Nw, Ew> = dyn Fn(Nw, Ew, R64, Ew) -> R64 + Sync;
+
 pub trait Metaheuristic<'a, IndexType, NodeWeightType, EdgeWeightType> {
     type Params;
     type SupervisorType;
@@ -29,39 +73,582 @@ pub trait Metaheuristic<'a, IndexType, NodeWeightType, EdgeWeightType> {
         params: Self::Params,
         supervisor: Self::SupervisorType,
     ) -> Self;
+    /// Runs one iteration and returns the new best solution, if it improved.
+    /// The graph is only borrowed for short lookups during an iteration and never between
+    /// iterations, so graph dynamics can change it in between.
     fn single_iteration(&mut self) -> Option<&Solution<IndexType>>;
+
+    /// Sets a callback, which is called at the end of every following iteration with the best
+    /// solution so far, so plots, dashboards or stopping rules can follow the run without
+    /// changes to the algorithm.
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, EdgeWeightType>);
+}
+
+/// Metaheuristics, which can keep optimizing after the weights of their graph changed.
+pub trait Reoptimize<IndexType> {
+    /// Adapts to the changed graph and rescores the best solution found so far, solutions
+    /// exceeding the time budget or using removed edges are scored with zero.
+    fn graph_changed(&mut self);
+
+    fn best_score(&self) -> R64;
+
+    fn best_length(&self) -> R64;
+
+    fn best_solution(&self) -> &Solution<IndexType>;
+
+    /// Returns the state of the rng before the next iteration, see RngState.
+    fn rng_state(&self) -> RngState;
+}
+
+/// Returns the score and length of solution in the current graph, the score is zero if the
+/// solution is infeasible.
+fn rescore<IndexType>(
+    solution: &Solution<IndexType>,
+    graph: &RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = R64, EdgeWeightType = R64>
+            + '_,
+    >,
+    heuristic: &Heuristic<R64, R64>,
+    max_time: R64,
+    time_windows: &TimeWindows<IndexType, R64>,
+    revisit_penalty: &RevisitPenalty,
+    service_times: &ServiceTimes<IndexType, R64>,
+) -> (R64, R64)
+where
+    IndexType: Distance<IndexType> + Copy + Debug + Display + Hash + Eq,
+{
+    if solution.iter_nodes().next().is_none() {
+        return (R64::zero(), R64::zero());
+    }
+    match solution_length_with_service_times(solution, graph, service_times, time_windows) {
+        Ok(length) if length <= max_time => {
+            let score = solution_score_with_service_times(
+                solution,
+                graph,
+                heuristic,
+                time_windows,
+                revisit_penalty,
+                service_times,
+            )
+            .unwrap_or_else(|_| R64::zero());
+            (score, length)
+        }
+        Ok(length) => (R64::zero(), length),
+        Err(_) => (R64::zero(), R64::zero()),
+    }
+}
+
+/// Reasons for a problem instance to be rejected by ProblemInstance::validate.
+#[derive(Debug, PartialEq)]
+pub enum InstanceError<IndexType: Debug + Display, EdgeWeightType: Debug + Display> {
+    MissingGoalPoint(IndexType),
+    NonPositiveBudget(EdgeWeightType),
+    NoRoundTrip(IndexType),
+    /// The budget and the length of the shortest round trip from the goal point.
+    BudgetBelowRoundTrip(EdgeWeightType, EdgeWeightType),
+}
+
+impl<IndexType, EdgeWeightType> fmt::Display for InstanceError<IndexType, EdgeWeightType>
+where
+    IndexType: Debug + Display,
+    EdgeWeightType: Debug + Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingGoalPoint(node) => write!(f, "Goal point {} not in graph.", node),
+            Self::NonPositiveBudget(max_time) => {
+                write!(f, "Time budget {} has to be positive.", max_time)
+            }
+            Self::NoRoundTrip(node) => {
+                write!(
+                    f,
+                    "No node can be visited from goal point {} and left again.",
+                    node
+                )
+            }
+            Self::BudgetBelowRoundTrip(max_time, shortest) => write!(
+                f,
+                "Time budget {} is below the shortest round trip of length {}.",
+                max_time, shortest
+            ),
+        }
+    }
+}
+
+impl<IndexType, EdgeWeightType> Error for InstanceError<IndexType, EdgeWeightType>
+where
+    IndexType: Debug + Display,
+    EdgeWeightType: Debug + Display,
+{
+}
+
+/// Problems during the construction of a solution, which the algorithms recover from by
+/// returning a degenerate solution instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstructionWarning<IndexType> {
+    /// No neighbor of the goal point can be visited and left again within the time budget,
+    /// for example after the graph changed, so the solution only contains the goal point.
+    NoFeasibleMove(IndexType),
 }
 
-pub struct ProblemInstance<'a, IndexType, NodeWeightType, EdgeWeightType> {
-    graph: &'a RefCell<
+impl<IndexType: Display> fmt::Display for ConstructionWarning<IndexType> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFeasibleMove(node) => write!(
+                f,
+                "No move from goal point {} fits into the time budget, returning the empty solution.",
+                node
+            ),
+        }
+    }
+}
+
+/// The graph is shared through a GraphCell, by default a RefCell as used by all metaheuristics.
+/// A SyncGraph, RwLock or Arc<RwLock<G>> allows reading the graph from multiple threads.
+/// Graphs owned elsewhere are passed to the metaheuristics as RefCell::new(&mut graph), shared
+/// ones as &*rc for an Rc<RefCell<G>>.
+pub struct ProblemInstance<
+    'a,
+    IndexType,
+    NodeWeightType,
+    EdgeWeightType,
+    Cell: ?Sized = RefCell<
         dyn GenericWeightedGraph<
-            IndexType = IndexType,
-            NodeWeightType = NodeWeightType,
-            EdgeWeightType = EdgeWeightType,
-        >,
+                IndexType = IndexType,
+                NodeWeightType = NodeWeightType,
+                EdgeWeightType = EdgeWeightType,
+            > + 'a,
     >,
+> {
+    graph: &'a Cell,
     goal_point: IndexType,
     max_time: EdgeWeightType,
+    vehicle_count: usize,
+    time_windows: TimeWindows<IndexType, EdgeWeightType>,
+    mandatory_nodes: HashSet<IndexType>,
+    stochastic_edges: StochasticEdges<IndexType>,
+    revisit_penalty: RevisitPenalty,
+    objective: Objective,
+    // computed from the graph on first use, unless they were passed with with_return_costs
+    return_costs: OnceLock<ReturnCosts<IndexType, EdgeWeightType>>,
+    secondary_budget: Option<SecondaryBudget<IndexType, EdgeWeightType>>,
+    service_times: ServiceTimes<IndexType, EdgeWeightType>,
+    node_weight_type: PhantomData<NodeWeightType>,
 }
 
 impl<'a, IndexType, NodeWeightType, EdgeWeightType>
     ProblemInstance<'a, IndexType, NodeWeightType, EdgeWeightType>
+where
+    IndexType: Copy + Debug + Display + Hash + Eq,
+    EdgeWeightType: Copy,
 {
     pub fn new(
         graph: &'a RefCell<
             dyn GenericWeightedGraph<
-                IndexType = IndexType,
-                NodeWeightType = NodeWeightType,
-                EdgeWeightType = EdgeWeightType,
-            >,
+                    IndexType = IndexType,
+                    NodeWeightType = NodeWeightType,
+                    EdgeWeightType = EdgeWeightType,
+                > + 'a,
         >,
         goal_point: IndexType,
         max_time: EdgeWeightType,
+    ) -> Self {
+        Self::with_graph_cell(graph, goal_point, max_time)
+    }
+}
+
+impl<'a, IndexType, NodeWeightType, EdgeWeightType, Cell>
+    ProblemInstance<'a, IndexType, NodeWeightType, EdgeWeightType, Cell>
+where
+    IndexType: Copy + Debug + Display + Hash + Eq,
+    EdgeWeightType: Copy,
+    Cell: GraphCell + ?Sized,
+    Cell::Graph: GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = NodeWeightType,
+        EdgeWeightType = EdgeWeightType,
+    >,
+{
+    /// Creates an instance sharing graph through any GraphCell, like a SyncGraph or an
+    /// Arc<RwLock<G>>.
+    pub fn with_graph_cell(
+        graph: &'a Cell,
+        goal_point: IndexType,
+        max_time: EdgeWeightType,
     ) -> Self {
         ProblemInstance {
             graph,
             goal_point,
             max_time,
+            vehicle_count: 1,
+            time_windows: TimeWindows::new(),
+            mandatory_nodes: HashSet::new(),
+            stochastic_edges: StochasticEdges::default(),
+            revisit_penalty: RevisitPenalty::default(),
+            objective: Objective::default(),
+            return_costs: OnceLock::new(),
+            secondary_budget: None,
+            service_times: ServiceTimes::new(),
+            node_weight_type: PhantomData,
+        }
+    }
+
+    /// Takes the return costs from known shortest paths to the goal point, instead of computing
+    /// them from the graph on first use.
+    pub fn with_return_costs(
+        mut self,
+        return_costs: ReturnCosts<IndexType, EdgeWeightType>,
+    ) -> Self {
+        self.return_costs = OnceLock::from(return_costs);
+        self
+    }
+
+    /// Returns the minimal cost of returning from each node to the goal point in the current
+    /// graph.
+    pub fn return_costs(&self) -> &ReturnCosts<IndexType, EdgeWeightType> {
+        self.return_costs.get_or_init(|| {
+            ReturnCosts::from_paths(&self.graph.read().inv_shortest_paths(self.goal_point))
+        })
+    }
+
+    /// Returns the minimal cost of returning from node to the goal point in the current graph,
+    /// or None if the goal point can't be reached from it.
+    pub fn min_return_cost(&self, node: IndexType) -> Option<EdgeWeightType> {
+        self.return_costs().get(node)
+    }
+
+    /// Drops the return costs, so they are recomputed on their next use. Has to be called after
+    /// edge weights of the graph changed.
+    pub fn update_return_costs(&mut self) {
+        self.return_costs = OnceLock::new();
+    }
+
+    /// Turns the instance into a team orienteering problem, where a solution consists of
+    /// vehicle_count routes from the goal point, which each have to stay within max_time.
+    /// The routes only share the goal point, so every reward is collected at most once.
+    /// Currently only supported by Aco and MMAco.
+    pub fn with_vehicle_count(mut self, vehicle_count: usize) -> Self {
+        self.vehicle_count = vehicle_count;
+        self
+    }
+
+    /// Restricts the collection of node values to the given time windows, measured from the start
+    /// of a route. Nodes without a time window can be collected at any time.
+    /// Currently only supported by Aco and MMAco.
+    pub fn with_time_windows(
+        mut self,
+        time_windows: TimeWindows<IndexType, EdgeWeightType>,
+    ) -> Self {
+        self.time_windows = time_windows;
+        self
+    }
+
+    /// Sets the nodes, which every feasible solution has to visit.
+    /// Solutions missing fewer of them are always preferred over ones with a higher score.
+    /// Currently only supported by Aco, MMAco and TwoSwap.
+    pub fn with_mandatory_nodes(mut self, mandatory_nodes: HashSet<IndexType>) -> Self {
+        self.mandatory_nodes = mandatory_nodes;
+        self
+    }
+
+    /// Makes the traversal times of edges random, with the graph weights as their means.
+    /// Ants sample the traversal times while constructing solutions and check the budget
+    /// against the risk adjusted weights.
+    /// Currently only supported by Aco and MMAco.
+    pub fn with_stochastic_edges(mut self, stochastic_edges: StochasticEdges<IndexType>) -> Self {
+        self.stochastic_edges = stochastic_edges;
+        self
+    }
+
+    /// Penalizes visiting nodes or traversing edges more than once, instead of only
+    /// giving them no further reward. The goal point is exempt from node penalties.
+    /// Currently only supported by Aco, MMAco and TwoSwap.
+    pub fn with_revisit_penalty(mut self, revisit_penalty: RevisitPenalty) -> Self {
+        self.revisit_penalty = revisit_penalty;
+        self
+    }
+
+    /// Sets what the metaheuristics optimize, by default they maximize the score.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// Adds a second resource consumed along edges, whose budget every route has to stay
+    /// within next to the time budget.
+    /// Currently only supported by Aco, MMAco and TwoSwap.
+    pub fn with_secondary_budget(
+        mut self,
+        secondary_budget: SecondaryBudget<IndexType, EdgeWeightType>,
+    ) -> Self {
+        self.secondary_budget = Some(secondary_budget);
+        self
+    }
+
+    /// Sets the time spent at nodes to collect their rewards, which uses up the time budget
+    /// like traversing edges. It is only spent by the visit collecting a reward, so routes can
+    /// pass nodes again without it.
+    /// Currently only supported by Aco, MMAco, Acs and TwoSwap.
+    pub fn with_service_times(
+        mut self,
+        service_times: ServiceTimes<IndexType, EdgeWeightType>,
+    ) -> Self {
+        self.service_times = service_times;
+        self
+    }
+}
+
+impl<'a, IndexType, NodeWeightType, EdgeWeightType, Cell>
+    ProblemInstance<'a, IndexType, NodeWeightType, EdgeWeightType, Cell>
+where
+    IndexType: Copy + Debug + Display + Hash + Eq,
+    EdgeWeightType: Copy + Debug + Display + PartialOrd + Zero + Add<Output = EdgeWeightType>,
+    Cell: GraphCell + ?Sized,
+    Cell::Graph: GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = NodeWeightType,
+        EdgeWeightType = EdgeWeightType,
+    >,
+{
+    /// Finishes building the instance, by checking that the goal point is in the graph,
+    /// the time budget is positive and at least one other node can be visited and left again
+    /// towards the goal point within it. Without these, every algorithm can only return the
+    /// empty solution. Errors for too small budgets contain the length of the shortest
+    /// round trip, to help choosing a budget.
+    pub fn validate(self) -> Result<Self, InstanceError<IndexType, EdgeWeightType>> {
+        if !self.graph.read().has_node(self.goal_point) {
+            return Err(InstanceError::MissingGoalPoint(self.goal_point));
         }
+        if self.max_time.partial_cmp(&EdgeWeightType::zero()) != Some(Ordering::Greater) {
+            return Err(InstanceError::NonPositiveBudget(self.max_time));
+        }
+        let shortest = self
+            .graph
+            .read()
+            .shortest_paths(self.goal_point)
+            .into_iter()
+            .filter(|(node, _)| *node != self.goal_point)
+            .filter_map(|(node, path)| Some(path?.1 + self.return_costs().get(node)?))
+            .fold(
+                None,
+                |shortest: Option<EdgeWeightType>, length| match shortest {
+                    Some(shortest) if shortest <= length => Some(shortest),
+                    _ => Some(length),
+                },
+            );
+        match shortest {
+            None => Err(InstanceError::NoRoundTrip(self.goal_point)),
+            Some(shortest) if shortest > self.max_time => {
+                Err(InstanceError::BudgetBelowRoundTrip(self.max_time, shortest))
+            }
+            Some(_) => Ok(self),
+        }
+    }
+
+    /// Returns the rewarded nodes, which can be visited from the goal point and left again,
+    /// with the length of the shortest round trip through them. Nodes are ordered by their
+    /// reward, those with equal rewards by their round trip.
+    pub fn reward_nodes(&self) -> Vec<RewardNode<IndexType, NodeWeightType, EdgeWeightType>>
+    where
+        NodeWeightType: Copy + PartialOrd + Zero,
+    {
+        let graph = self.graph.read();
+        let mut nodes: Vec<_> = graph
+            .shortest_paths(self.goal_point)
+            .into_iter()
+            .filter(|(node, _)| *node != self.goal_point)
+            .filter_map(|(node, path)| {
+                let reward = *graph.node_weight(node).ok()?;
+                let round_trip = path?.1 + self.return_costs().get(node)?;
+                Some(RewardNode {
+                    node,
+                    reward,
+                    round_trip,
+                })
+            })
+            .filter(|node| node.reward > NodeWeightType::zero())
+            .collect();
+        nodes.sort_by(|a, b| {
+            b.reward
+                .partial_cmp(&a.reward)
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    a.round_trip
+                        .partial_cmp(&b.round_trip)
+                        .unwrap_or(Ordering::Equal),
+                )
+        });
+        nodes
+    }
+
+    /// Returns the k nodes with the highest rewards, see reward_nodes.
+    pub fn top_reward_nodes(
+        &self,
+        k: usize,
+    ) -> Vec<RewardNode<IndexType, NodeWeightType, EdgeWeightType>>
+    where
+        NodeWeightType: Copy + PartialOrd + Zero,
+    {
+        let mut nodes = self.reward_nodes();
+        nodes.truncate(k);
+        nodes
+    }
+
+    /// Returns statistics of the reward per round trip cost of the rewarded nodes, or None if
+    /// no rewarded node can be visited from the goal point.
+    pub fn reward_density(&self) -> Option<DensityStats>
+    where
+        NodeWeightType: Copy + PartialOrd + Zero + Into<f64>,
+        EdgeWeightType: Into<f64>,
+    {
+        DensityStats::of(&self.reward_nodes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{MatrixGraph, SyncGraph};
+
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+    use std::sync::{Arc, RwLock};
+
+    fn graph() -> RefCell<MatrixGraph<usize, R64, R64>> {
+        RefCell::new(
+            MatrixGraph::new_usize_indexed(
+                vec![R64::from_inner(1.0); 3],
+                vec![
+                    (0, 1, R64::from_inner(2.0)),
+                    (1, 0, R64::from_inner(2.0)),
+                    (1, 2, R64::from_inner(1.0)),
+                ],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn validate_rejects_unsolvable_instances() {
+        let graph = graph();
+        assert_eq!(
+            ProblemInstance::new(&graph, 5, R64::from_inner(10.0))
+                .validate()
+                .err(),
+            Some(InstanceError::MissingGoalPoint(5))
+        );
+        assert_eq!(
+            ProblemInstance::new(&graph, 0, R64::zero())
+                .validate()
+                .err(),
+            Some(InstanceError::NonPositiveBudget(R64::zero()))
+        );
+        assert_eq!(
+            ProblemInstance::new(&graph, 0, R64::from_inner(3.0))
+                .validate()
+                .err(),
+            Some(InstanceError::BudgetBelowRoundTrip(
+                R64::from_inner(3.0),
+                R64::from_inner(4.0)
+            ))
+        );
+        // node 2 can be reached, but not left again
+        assert_eq!(
+            ProblemInstance::new(&graph, 2, R64::from_inner(10.0))
+                .validate()
+                .err(),
+            Some(InstanceError::NoRoundTrip(2))
+        );
+        assert!(ProblemInstance::new(&graph, 0, R64::from_inner(4.0))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn instances_share_sync_graphs() {
+        let graph: &SyncGraph<
+            dyn GenericWeightedGraph<IndexType = usize, NodeWeightType = R64, EdgeWeightType = R64>
+                + Send
+                + Sync,
+        > = &SyncGraph::new(graph().into_inner());
+        let problem = ProblemInstance::with_graph_cell(graph, 1, R64::from_inner(4.0))
+            .validate()
+            .unwrap();
+        assert_eq!(problem.min_return_cost(2), None);
+        assert_eq!(problem.min_return_cost(0), Some(R64::from_inner(2.0)));
+    }
+
+    #[test]
+    fn instances_accept_borrowed_and_shared_graphs() {
+        let heuristic: &Heuristic<R64, R64> = &|nw, _, _, _| nw;
+        let mut owned = graph().into_inner();
+        {
+            let borrowed = RefCell::new(&mut owned);
+            let mut exact = Exact::new(
+                ProblemInstance::new(&borrowed, 0, R64::from_inner(4.0)),
+                exact::Params::new(heuristic),
+                supervisor::Supervisor::new(1, std::io::sink()),
+            );
+            assert!(exact.single_iteration().is_some());
+            borrowed.write().remove_edge((1, 0));
+        }
+        assert!(!owned.has_edge((1, 0)));
+
+        let shared = Rc::new(graph());
+        assert!(ProblemInstance::new(&*shared, 0, R64::from_inner(4.0))
+            .validate()
+            .is_ok());
+        assert!(
+            ProblemInstance::with_graph_cell(&shared, 0, R64::from_inner(4.0))
+                .validate()
+                .is_ok()
+        );
+        let shared = Arc::new(RwLock::new(graph().into_inner()));
+        let problem = ProblemInstance::with_graph_cell(&shared, 1, R64::from_inner(4.0));
+        assert_eq!(problem.min_return_cost(0), Some(R64::from_inner(2.0)));
+    }
+
+    #[test]
+    fn passed_return_costs_are_kept_until_updated() {
+        let graph = graph();
+        let mut problem = ProblemInstance::new(&graph, 1, R64::from_inner(4.0))
+            .with_return_costs(ReturnCosts::from_paths(&BTreeMap::new()));
+        assert_eq!(problem.min_return_cost(0), None);
+        problem.update_return_costs();
+        assert_eq!(problem.min_return_cost(0), Some(R64::from_inner(2.0)));
+    }
+
+    #[test]
+    fn reward_nodes_are_ordered_by_reward() {
+        let r = R64::from_inner;
+        let graph = RefCell::new(
+            MatrixGraph::new_usize_indexed(
+                vec![r(0.0), r(2.0), r(2.0), r(5.0), r(0.0)],
+                vec![
+                    (0, 1, r(1.0)),
+                    (1, 0, r(1.0)),
+                    (0, 2, r(2.0)),
+                    (2, 0, r(2.0)),
+                    (0, 3, r(4.0)),
+                    (3, 0, r(6.0)),
+                    (0, 4, r(1.0)),
+                    (4, 0, r(1.0)),
+                ],
+            )
+            .unwrap(),
+        );
+        let problem = ProblemInstance::new(&graph, 0, r(10.0));
+        let top: Vec<_> = problem
+            .top_reward_nodes(2)
+            .iter()
+            .map(|node| (node.node, node.round_trip))
+            .collect();
+        assert_eq!(top, vec![(3, r(10.0)), (1, r(2.0))]);
+        assert_eq!(problem.reward_nodes().len(), 3);
+
+        let density = problem.reward_density().unwrap();
+        assert_eq!(density.nodes, 3);
+        assert_eq!(density.min, 0.5);
+        assert_eq!(density.max, 1.0);
     }
 }
@@ -1,33 +1,150 @@
 mod ant;
+mod diversity;
+mod heatmap;
 mod message;
 mod params;
+mod pheromones;
+mod solution_archive;
 mod supervisor;
 
-pub use ant::Ant;
+pub use ant::{Ant, AntWalk, ConstructionLimits, PheromonePolicy};
+pub use diversity::{branching_factor, edge_overlap, pheromone_entropy, Diversity};
+pub use heatmap::export_heatmap;
 pub use message::Message;
 pub use params::Params;
-pub use supervisor::Supervisor;
+pub use pheromones::{PheromoneChange, PheromoneSnapshot, Pheromones, SparsePheromones};
+pub use solution_archive::SolutionArchive;
+pub use supervisor::{AntStats, Record, Supervisor};
 
-use crate::graph::{GenericWeightedGraph, MatrixGraph};
+use crate::graph::{GenericWeightedGraph, StochasticEdges};
+use crate::metaheuristic::supervisor::Phase;
 use crate::metaheuristic::{
-    solution_length, solution_score, Heuristic, Metaheuristic, ProblemInstance, Solution,
+    rescore, GoalPaths, Heuristic, IterationCallback, Metaheuristic, Objective, Observer,
+    ProblemInstance, Reoptimize, RevisitPenalty, SecondaryBudget, ServiceTimes, Solution,
+    TimeWindows,
 };
-use crate::rng::rng64;
+use crate::rng::{Rng, RngState};
 use crate::util::{Distance, SmallVal};
 
 use decorum::R64;
-use num_traits::identities::{One, Zero};
-use oorandom::Rand64;
+use num_traits::identities::Zero;
 use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::io::Write;
 use std::ops::Add;
 use std::time::Instant;
 
+/// Creates pheromones with all levels set to one. Edges, which can't be part of any
+/// route from goal_point back to it within max_time, are left out if prune is true, together with
+/// nodes without any remaining edge. inv_shortest_paths has to contain the shortest paths to
+/// goal_point from all nodes.
+pub fn initial_pheromones<IndexType, Nw>(
+    graph: &dyn GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = Nw,
+        EdgeWeightType = R64,
+    >,
+    goal_point: IndexType,
+    max_time: R64,
+    inv_shortest_paths: &BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+    prune: bool,
+    level: R64,
+) -> Pheromones<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    if !prune {
+        return Pheromones::new(graph.node_ids(), graph.edge_ids(), level);
+    }
+
+    let distance = |paths: &BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+                    node: IndexType| {
+        if node == goal_point {
+            Some(R64::zero())
+        } else {
+            paths
+                .get(&node)
+                .and_then(|path| path.as_ref())
+                .map(|(_, distance)| *distance)
+        }
+    };
+    let shortest_paths = graph.shortest_paths(goal_point);
+    let edges: Vec<_> = graph
+        .iter_edges()
+        .filter(|&((from, to), &weight)| {
+            match (
+                distance(&shortest_paths, from),
+                distance(inv_shortest_paths, to),
+            ) {
+                (Some(to_from), Some(from_to)) => to_from + weight + from_to <= max_time,
+                _ => false,
+            }
+        })
+        .map(|(edge, _)| edge)
+        .collect();
+    let mut nodes: BTreeSet<IndexType> = edges
+        .iter()
+        .flat_map(|&(from, to)| vec![from, to])
+        .collect();
+    nodes.insert(goal_point);
+
+    Pheromones::new(nodes.into_iter().collect(), edges, level)
+}
+
+/// Prunes pheromones again after the edge weights of graph changed, see initial_pheromones.
+/// Remaining edges keep their level, edges becoming reachable start with level.
+pub fn reprune_pheromones<IndexType, Nw>(
+    pheromones: &Pheromones<IndexType>,
+    graph: &dyn GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = Nw,
+        EdgeWeightType = R64,
+    >,
+    goal_point: IndexType,
+    max_time: R64,
+    inv_shortest_paths: &BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+    prune: bool,
+    level: R64,
+) -> Pheromones<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    let mut repruned = initial_pheromones(
+        graph,
+        goal_point,
+        max_time,
+        inv_shortest_paths,
+        prune,
+        level,
+    );
+    repruned.update_edges(|edge, level| {
+        if let Ok(&kept) = pheromones.edge_weight(edge) {
+            *level = kept;
+        }
+    });
+    repruned
+}
+
+/// Adds amount to the pheromone level of every edge of solution.
+/// Edges, which are not part of the pheromone matrix, are skipped.
+pub fn deposit_pheromone<IndexType>(
+    pheromone_matrix: &mut Pheromones<IndexType>,
+    solution: &Solution<IndexType>,
+    amount: R64,
+) where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    for (from, to) in solution.iter_unique_edges() {
+        if let Ok(weight) = pheromone_matrix.edge_weight_mut((*from, *to)) {
+            *weight += amount;
+        }
+    }
+}
+
 pub struct Aco<'a, IndexType, Nw, Ew, W>
 where
     IndexType: Clone,
@@ -36,9 +153,10 @@ where
     Ew: Serialize + Add<Output = Ew>,
 {
     graph: &'a RefCell<
-        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>,
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + 'a,
     >,
-    pheromone_matrix: MatrixGraph<IndexType, (), R64>,
+    pheromone_matrix: Pheromones<IndexType>,
     goal_point: IndexType,
     max_time: Ew,
     heuristic: &'a Heuristic<Nw, Ew>,
@@ -47,13 +165,28 @@ where
     rho: f64,
     q: f64,
     q_0: f64,
+    initial_pheromone: R64,
     ant_count: usize,
+    vehicle_count: usize,
+    time_windows: TimeWindows<IndexType, Ew>,
+    mandatory_nodes: HashSet<IndexType>,
+    stochastic_edges: StochasticEdges<IndexType>,
+    revisit_penalty: RevisitPenalty,
+    service_times: ServiceTimes<IndexType, Ew>,
+    secondary_budget: Option<SecondaryBudget<IndexType, Ew>>,
+    objective: Objective,
+    construction_limits: ConstructionLimits,
+    bidirectional: bool,
     best_solution: Solution<IndexType>,
-    best_score: Nw,
+    best_score: R64,
     best_length: Ew,
+    // length and score of every route of the best solution
+    best_routes: Vec<(Ew, R64)>,
+    best_missing: usize,
     pub supervisor: Supervisor<W, Nw, Ew>,
-    rng: Rand64,
-    inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
+    rng: Rng,
+    goal_paths: GoalPaths<IndexType>,
+    observer: Observer<'a, IndexType, Ew>,
 }
 
 impl<'a, IndexType, Nw, W> Aco<'a, IndexType, Nw, R64, W>
@@ -66,14 +199,8 @@ where
         // let to_add = R64::one() - R64::from_inner(self.q) / solution_score;
         // println!("adding {}", to_add);
 
-        let mut evaporated_pheromones = R64::zero();
         // pheromone decay
-        for edge in self.pheromone_matrix.edge_ids() {
-            let weight = *self.pheromone_matrix.edge_weight(edge).unwrap();
-            let after_decay = R64::from_inner(1.0 - self.rho) * weight;
-            evaporated_pheromones += weight - after_decay;
-            let _res = self.pheromone_matrix.change_edge(edge, after_decay);
-        }
+        let evaporated_pheromones = self.pheromone_matrix.decay(self.rho);
 
         // println!(
         //     "evaporated {} and have {} nodes in new solution. This would lead to {} being added.",
@@ -83,19 +210,51 @@ where
         // );
         let to_add = evaporated_pheromones / R64::from_inner(solution.unique_edges().len() as f64);
         // adding best solution
-        for (from, to) in solution.iter_unique_edges() {
-            let weight = *self.pheromone_matrix.edge_weight((*from, *to)).unwrap();
-            let _res = self
-                .pheromone_matrix
-                .change_edge((*from, *to), weight + to_add);
-        }
+        deposit_pheromone(&mut self.pheromone_matrix, solution, to_add);
+    }
+
+    /// Seeds the pheromone matrix with an initial solution, adding strength times the initial
+    /// pheromone level to each of its edges. Meant to be called before the first iteration.
+    pub fn warm_start(&mut self, solution: &Solution<IndexType>, strength: f64) {
+        deposit_pheromone(
+            &mut self.pheromone_matrix,
+            solution,
+            R64::from_inner(strength) * self.initial_pheromone,
+        );
     }
 
     pub fn set_inv_shortest_paths(
         &mut self,
         inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
     ) {
-        self.inv_shortest_paths = inv_shortest_paths
+        self.goal_paths.set_inv_shortest_paths(
+            &*self.graph.borrow(),
+            self.goal_point,
+            inv_shortest_paths,
+        );
+    }
+
+    /// Returns the state of the rng, from which the next iteration draws its random numbers.
+    pub fn rng_state(&self) -> RngState {
+        self.rng.state()
+    }
+
+    pub fn pheromones(&self) -> &Pheromones<IndexType> {
+        &self.pheromone_matrix
+    }
+
+    pub fn current_solution(&self) -> (&Solution<IndexType>, R64, R64) {
+        (&self.best_solution, self.best_score, self.best_length)
+    }
+
+    /// Returns the length and score of every route of the best solution, a single route has the
+    /// length and score of the solution.
+    pub fn best_routes(&self) -> Vec<(R64, R64)> {
+        if self.vehicle_count == 1 {
+            vec![(self.best_length, self.best_score)]
+        } else {
+            self.best_routes.clone()
+        }
     }
 }
 
@@ -112,15 +271,15 @@ where
         params: Self::Params,
         supervisor: Self::SupervisorType,
     ) -> Self {
-        let graph = problem.graph.borrow();
-        let pheromones = MatrixGraph::new(
-            graph.iter_node_ids().map(|id| (id, ())).collect(),
-            graph
-                .iter_edge_ids()
-                .map(|edge| (edge, R64::from_inner(1.0)))
-                .collect(),
-        )
-        .unwrap();
+        // sampled routes can be shorter than the shortest paths, so stochastic edges prevent pruning
+        let pheromones = initial_pheromones(
+            &*problem.graph.borrow(),
+            problem.goal_point,
+            problem.max_time,
+            &params.inv_shortest_paths,
+            problem.stochastic_edges.variances.is_empty(),
+            R64::from_inner(params.initial_pheromone),
+        );
 
         Aco {
             graph: problem.graph,
@@ -133,22 +292,44 @@ where
             rho: params.rho,
             q: 1.0,
             q_0: params.q_0,
+            initial_pheromone: R64::from_inner(params.initial_pheromone),
             ant_count: params.ant_count,
+            vehicle_count: problem.vehicle_count,
+            time_windows: problem.time_windows,
+            best_missing: problem.mandatory_nodes.len(),
+            mandatory_nodes: problem.mandatory_nodes,
+            stochastic_edges: problem.stochastic_edges,
+            revisit_penalty: problem.revisit_penalty,
+            service_times: problem.service_times,
+            secondary_budget: problem.secondary_budget,
+            objective: problem.objective,
+            construction_limits: params.construction_limits,
+            bidirectional: params.bidirectional,
             best_solution: Solution::new(),
             best_score: R64::zero(),
             best_length: R64::zero(),
+            best_routes: Vec::new(),
             supervisor,
-            rng: rng64(params.seed),
-            inv_shortest_paths: params.inv_shortest_paths,
+            rng: Rng::new(params.rng, params.seed),
+            goal_paths: GoalPaths::new(
+                &*problem.graph.borrow(),
+                problem.goal_point,
+                params.inv_shortest_paths,
+                problem.return_costs.into_inner(),
+                params.return_paths,
+            ),
+            observer: Observer::default(),
         }
     }
 
     fn single_iteration(&mut self) -> Option<&Solution<IndexType>> {
+        let pheromones_before = self.pheromone_matrix.snapshot();
+        let construction_start = Instant::now();
         let mut ants = Vec::with_capacity(self.ant_count);
-        for _ in 0..self.ant_count {
-            let (sender, id) = self.supervisor.new_ant();
+        for id in 1..=self.ant_count {
+            let sender = self.supervisor.sender();
             let seed = self.rng.rand_u64() as u128 + ((self.rng.rand_u64() as u128) << 64);
-            ants.push(Ant::new(
+            let ant = Ant::new(
                 self.graph,
                 &self.pheromone_matrix,
                 self.goal_point,
@@ -160,8 +341,23 @@ where
                 self.q_0,
                 sender,
                 id,
-                &self.inv_shortest_paths,
-            ));
+                &self.goal_paths.inv_shortest_paths,
+                &self.goal_paths.return_costs,
+            )
+            .with_goal_distances(&self.goal_paths.goal_distances)
+            .with_vehicle_count(self.vehicle_count)
+            .with_time_windows(&self.time_windows)
+            .with_mandatory_nodes(&self.mandatory_nodes)
+            .with_stochastic_edges(&self.stochastic_edges)
+            .with_revisit_penalty(self.revisit_penalty)
+            .with_service_times(&self.service_times)
+            .with_construction_limits(self.construction_limits)
+            .with_bidirectional(self.bidirectional)
+            .with_secondary_budget(self.secondary_budget.as_ref());
+            ants.push(match self.goal_paths.return_paths() {
+                Some(return_paths) => ant.with_return_paths(return_paths),
+                None => ant,
+            });
         }
 
         let mut solutions = Vec::new();
@@ -169,6 +365,18 @@ where
             let solution = ant.get_solution();
             solutions.push(solution)
         }
+        self.supervisor
+            .add_time(Phase::Construction, construction_start.elapsed());
+        for warning in solutions
+            .iter()
+            .filter_map(|ant_solution| ant_solution.warning)
+        {
+            self.supervisor.warn(warning);
+        }
+        let diversity = Diversity::new(
+            &self.pheromone_matrix,
+            solutions.iter().map(|ant_solution| &ant_solution.solution),
+        );
 
         let start_time = Instant::now();
         let mut best_length = R64::zero();
@@ -177,9 +385,48 @@ where
         let mut visited_nodes = 0;
         let mut visited_with_val = 0;
         let mut val_sum = R64::zero();
+        let mut route_lengths = Vec::new();
+        let mut route_scores = Vec::new();
+        let mut secondary_resource = R64::zero();
+        let mut best_missing = self.mandatory_nodes.len();
         let mut improvements = 0;
+        let mut archive = SolutionArchive::new();
+        let mut infeasible = 0;
+        let mut over_budget = 0;
+        let mut overshoot = R64::zero();
         for ant_solution in solutions.into_iter() {
-            if ant_solution.length <= self.max_time && ant_solution.score > best_score {
+            // bidirectionally constructed routes are only valid, if they were closed at the goal point
+            let feasible = ant_solution.solution.is_closed_tour(self.goal_point)
+                && ant_solution
+                    .budget_lengths
+                    .iter()
+                    .all(|&length| length <= self.max_time)
+                && ant_solution.fits_secondary_budget(self.secondary_budget.as_ref());
+            if !feasible {
+                infeasible += 1;
+                let exceeded = ant_solution.overshoot(self.max_time);
+                if exceeded > R64::zero() {
+                    over_budget += 1;
+                    overshoot += exceeded;
+                }
+            }
+            // a repeated route is scored like the first ant constructing it, so it can't improve
+            if !archive.insert(&ant_solution.solution) {
+                continue;
+            }
+            let missing = ant_solution
+                .solution
+                .missing_nodes(&self.mandatory_nodes)
+                .len();
+            if feasible
+                && self
+                    .objective
+                    .improves_empty(ant_solution.score, ant_solution.length)
+                && self.objective.replaces_best(
+                    (missing, ant_solution.score, ant_solution.length),
+                    (best_missing, best_score, best_length),
+                )
+            {
                 improvements += 1;
                 best_score = ant_solution.score;
                 best_length = ant_solution.length;
@@ -187,11 +434,27 @@ where
                 visited_nodes = ant_solution.visited_nodes;
                 val_sum = ant_solution.val_sum;
                 visited_with_val = ant_solution.visited_with_val;
+                route_lengths = ant_solution.route_lengths;
+                route_scores = ant_solution.route_scores;
+                secondary_resource = ant_solution.secondary_resource;
+                best_missing = missing;
             }
         }
 
         let duration = start_time.elapsed();
-        let _ = self.supervisor.sender.send(Message::new(
+        self.supervisor.add_time(Phase::Scoring, duration);
+        self.supervisor.log_route(
+            &best_solution,
+            best_score.into_inner(),
+            best_length.into_inner(),
+        );
+        let longest_route = route_lengths
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_else(R64::zero);
+        // sent after the pheromone update, to include the change of the pheromones
+        let message = Message::new(
             0,
             0,
             0,
@@ -204,23 +467,200 @@ where
             visited_nodes,
             visited_with_val,
             val_sum,
-        )); // Ant 0 is always supervisor
-        self.supervisor.prepare_next();
+        )
+        .with_routes(route_lengths.len(), longest_route)
+        .with_diversity(diversity)
+        .with_duplicates(archive.duplicates())
+        .with_infeasible(infeasible, over_budget, overshoot.into_inner())
+        .with_secondary_resource(secondary_resource.into_inner());
 
+        let update_start = Instant::now();
         self.pheromone_update(&best_solution, best_score);
-        if best_score > self.best_score {
+        self.supervisor
+            .add_time(Phase::PheromoneUpdate, update_start.elapsed());
+        let pheromone_change = self
+            .pheromone_matrix
+            .change_since(&pheromones_before)
+            .unwrap_or_default();
+        let _ = self
+            .supervisor
+            .sender()
+            .send(message.with_pheromone_change(pheromone_change)); // Ant 0 is always supervisor
+        if self.objective.replaces_best(
+            (best_missing, best_score, best_length),
+            (self.best_missing, self.best_score, self.best_length),
+        ) {
             // println!("solution improved");
             self.best_solution = best_solution;
             self.best_score = best_score;
             self.best_length = best_length;
+            self.best_routes = route_lengths.iter().copied().zip(route_scores).collect();
+            self.best_missing = best_missing;
+            self.observer
+                .notify(true, &self.best_solution, self.best_score, self.best_length);
 
             return Some(&self.best_solution);
-        } else if best_length < self.best_length && best_score == self.best_score {
-            // println!("solution length improved");
-            self.best_solution = best_solution;
-            self.best_score = best_score;
-            self.best_length = best_length;
         }
+        self.observer.notify(
+            false,
+            &self.best_solution,
+            self.best_score,
+            self.best_length,
+        );
         None
     }
+
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, R64>) {
+        self.observer.set(callback);
+    }
+}
+
+impl<'a, IndexType, W> Reoptimize<IndexType> for Aco<'a, IndexType, R64, R64, W>
+where
+    IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
+    W: Write,
+{
+    fn graph_changed(&mut self) {
+        self.goal_paths
+            .recompute(&*self.graph.borrow(), self.goal_point);
+        self.pheromone_matrix = reprune_pheromones(
+            &self.pheromone_matrix,
+            &*self.graph.borrow(),
+            self.goal_point,
+            self.max_time,
+            &self.goal_paths.inv_shortest_paths,
+            self.stochastic_edges.variances.is_empty(),
+            self.initial_pheromone,
+        );
+        let (score, length) = rescore(
+            &self.best_solution,
+            self.graph,
+            self.heuristic,
+            self.max_time,
+            &self.time_windows,
+            &self.revisit_penalty,
+            &self.service_times,
+        );
+        self.best_score = score;
+        self.best_length = length;
+    }
+
+    fn best_score(&self) -> R64 {
+        self.best_score
+    }
+
+    fn best_length(&self) -> R64 {
+        self.best_length
+    }
+
+    fn best_solution(&self) -> &Solution<IndexType> {
+        &self.best_solution
+    }
+
+    fn rng_state(&self) -> RngState {
+        Aco::rng_state(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use num_traits::One;
+
+    #[test]
+    fn initial_pheromones_are_pruned_to_reachable_edges() {
+        let graph = MatrixGraph::new(
+            vec![
+                (0, R64::zero()),
+                (1, R64::zero()),
+                (2, R64::zero()),
+                (3, R64::zero()),
+            ],
+            vec![
+                ((0, 1), 1.0),
+                ((1, 0), 1.0),
+                ((1, 2), 5.0),
+                ((2, 1), 5.0),
+                ((0, 3), 1.5),
+            ]
+            .into_iter()
+            .map(|(edge, weight)| (edge, R64::from_inner(weight)))
+            .collect(),
+        )
+        .unwrap();
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+
+        let pruned = initial_pheromones(
+            &graph,
+            0,
+            R64::from_inner(4.0),
+            &inv_shortest_paths,
+            true,
+            R64::one(),
+        );
+        assert_eq!(pruned.edge_ids(), vec![(0, 1), (1, 0)]);
+        assert_eq!(pruned.iter_node_ids().collect::<Vec<_>>(), vec![0, 1]);
+
+        let full = initial_pheromones(
+            &graph,
+            0,
+            R64::from_inner(4.0),
+            &inv_shortest_paths,
+            false,
+            R64::one(),
+        );
+        assert_eq!(full.edge_ids().len(), 5);
+    }
+
+    #[test]
+    fn repruned_pheromones_keep_the_levels_of_remaining_edges() {
+        let mut graph = MatrixGraph::new(
+            vec![(0, R64::zero()), (1, R64::zero()), (2, R64::zero())],
+            vec![((0, 1), 1.0), ((1, 0), 1.0), ((0, 2), 5.0), ((2, 0), 5.0)]
+                .into_iter()
+                .map(|(edge, weight)| (edge, R64::from_inner(weight)))
+                .collect(),
+        )
+        .unwrap();
+        let max_time = R64::from_inner(4.0);
+        let mut pheromones = initial_pheromones(
+            &graph,
+            0,
+            max_time,
+            &graph.inv_shortest_paths(0),
+            true,
+            R64::one(),
+        );
+        *pheromones.edge_weight_mut((0, 1)).unwrap() = R64::from_inner(3.0);
+
+        *graph.edge_weight_mut((0, 2)).unwrap() = R64::from_inner(1.0);
+        *graph.edge_weight_mut((2, 0)).unwrap() = R64::from_inner(1.0);
+        let repruned = reprune_pheromones(
+            &pheromones,
+            &graph,
+            0,
+            max_time,
+            &graph.inv_shortest_paths(0),
+            true,
+            R64::from_inner(2.0),
+        );
+        assert_eq!(repruned.edge_ids().len(), 4);
+        assert_eq!(*repruned.edge_weight((0, 1)).unwrap(), 3.0);
+        assert_eq!(*repruned.edge_weight((1, 0)).unwrap(), 1.0);
+        assert_eq!(*repruned.edge_weight((0, 2)).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn deposit_pheromone_skips_missing_edges() {
+        let mut pheromones =
+            Pheromones::new(vec![0, 1, 2], vec![(0, 1), (1, 0), (1, 2)], R64::one());
+        let solution = Solution::from_nodes(vec![0, 1, 2, 0]);
+        deposit_pheromone(&mut pheromones, &solution, R64::from_inner(2.0));
+
+        assert_eq!(*pheromones.edge_weight((0, 1)).unwrap(), 3.0);
+        assert_eq!(*pheromones.edge_weight((1, 2)).unwrap(), 3.0);
+        assert_eq!(*pheromones.edge_weight((1, 0)).unwrap(), 1.0);
+        assert!(pheromones.edge_weight((2, 0)).is_err());
+    }
 }
@@ -1,57 +1,167 @@
-use crate::graph::{Edge, GenericWeightedGraph, MatrixGraph};
-use crate::metaheuristic::aco::Message;
-use crate::metaheuristic::{Heuristic, Solution};
-use crate::rng::rng64;
+use crate::graph::{Edge, GenericWeightedGraph, StochasticEdges};
+use crate::metaheuristic::aco::{Message, Pheromones};
+use crate::metaheuristic::revisit_penalty::edge_traversed;
+use crate::metaheuristic::secondary_budget::SecondaryBudget;
+use crate::metaheuristic::service_time::ServiceTimes;
+use crate::metaheuristic::time_window::{arrives_in_time, TimeWindows};
+use crate::metaheuristic::{
+    ConstructionWarning, GoalDistances, Heuristic, ReturnCosts, ReturnPaths, RevisitPenalty,
+    Solution,
+};
+use crate::rng::{gen_float_range, rng64, Rng};
 use crate::util::Distance;
 
 use decorum::{Real, R64};
 use num_traits::identities::{One, Zero};
-use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::ops::AddAssign;
 use std::sync::mpsc::Sender;
 use std::time::Instant;
 
+/// How an ant reads and changes the pheromone levels while constructing its solution.
+/// This is the only part in which the ants of the different colonies differ.
+pub trait PheromonePolicy<IndexType: Clone> {
+    /// Calls f with the current pheromone levels.
+    fn read<R>(&self, f: impl FnOnce(&Pheromones<IndexType>) -> R) -> R;
+
+    /// Called when the ant moves along edge for the first time in its solution.
+    fn traversed(&self, _edge: Edge<IndexType>) {}
+}
+
+/// Ants of Aco and MMAco only read the pheromones, they are updated once all ants are done.
+impl<IndexType: Clone> PheromonePolicy<IndexType> for &Pheromones<IndexType> {
+    fn read<R>(&self, f: impl FnOnce(&Pheromones<IndexType>) -> R) -> R {
+        f(self)
+    }
+}
+
+pub type Ant<'a, IndexType, Nw> = AntWalk<'a, IndexType, Nw, &'a Pheromones<IndexType>>;
+
+/// Keeps ants from oscillating between nodes until the time budget forces them back.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ConstructionLimits {
+    /// Moves after which an ant aborts its construction and takes the shortest path back.
+    pub step_limit: Option<usize>,
+    /// Amount of nodes most recently left by a route, which it only moves back to if they are
+    /// the only candidates. 0 disables the tabu window.
+    pub tabu_window: usize,
+}
+
+/// End of the route, at which an ant adds the next node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    /// Appends nodes to the route along the outgoing edges of its last node.
+    Forward,
+    /// Prepends nodes to the route along the incoming edges of its first node.
+    Backward,
+}
+
+impl Direction {
+    /// Returns the edge, along which the route grows when adding to at from.
+    fn edge<IndexType>(self, from: IndexType, to: IndexType) -> Edge<IndexType> {
+        match self {
+            Direction::Forward => (from, to),
+            Direction::Backward => (to, from),
+        }
+    }
+}
+
+/// Constructs a solution by walking from the goal point along edges chosen by the pseudo random
+/// proportional rule, until no neighbor can be visited without exceeding the time budget.
+/// The time windows, mandatory nodes, stochastic edges, revisit penalties, service times and
+/// the secondary budget are optional.
 #[derive(Clone)]
-pub struct Ant<'a, IndexType: Clone, Nw, Ew>
-where
-    Ew: Serialize,
-{
+pub struct AntWalk<'a, IndexType, Nw, P> {
     graph: &'a RefCell<
-        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>,
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = R64>
+            + 'a,
     >,
-    pheromone_matrix: &'a MatrixGraph<IndexType, (), R64>,
+    pheromones: P,
     goal_point: IndexType,
-    max_time: Ew,
+    max_time: R64,
     alpha: f64,
     beta: f64,
     q_0: f64,
+    vehicle_count: usize,
     rng_seed: u128,
-    heuristic: &'a Heuristic<Nw, Ew>,
-    sender: Sender<Message<Nw, Ew>>,
+    heuristic: &'a Heuristic<Nw, R64>,
+    sender: Sender<Message<Nw, R64>>,
     id: usize,
-    inv_shortest_paths: &'a BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
+    inv_shortest_paths: &'a BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+    return_costs: &'a ReturnCosts<IndexType, R64>,
+    return_paths: Option<&'a ReturnPaths<IndexType, R64>>,
+    goal_distances: Option<&'a GoalDistances<IndexType>>,
+    time_windows: Option<&'a TimeWindows<IndexType, R64>>,
+    mandatory_nodes: Option<&'a HashSet<IndexType>>,
+    stochastic_edges: Option<&'a StochasticEdges<IndexType>>,
+    revisit_penalty: RevisitPenalty,
+    limits: ConstructionLimits,
+    secondary_budget: Option<&'a SecondaryBudget<IndexType, R64>>,
+    service_times: Option<&'a ServiceTimes<IndexType, R64>>,
+    bidirectional: bool,
+}
+
+/// The solution under construction, which is shared by all routes of an ant.
+struct Tour<IndexType, Nw> {
+    solution: Solution<IndexType>,
+    visited: BTreeSet<IndexType>,
+    traversed: HashSet<Edge<IndexType>>,
+    score: R64,
+    evals: usize,
+    changes: usize,
+    val_sum: Nw,
+    nodes_with_val: usize,
+    // nodes most recently left by the current route, at most tabu_window of them
+    recent: VecDeque<IndexType>,
+    aborted: bool,
+    // consumption of the secondary budget by all routes
+    consumed: R64,
+    // consumption of the secondary budget by every finished route
+    route_consumptions: Vec<R64>,
+    // nodes of the finished routes except the goal point, which later routes don't enter
+    earlier_routes: BTreeSet<IndexType>,
+}
+
+impl<IndexType: Copy + Hash + Ord, Nw: Zero> Tour<IndexType, Nw> {
+    fn new(goal_point: IndexType, tabu_window: usize, vehicle_count: usize) -> Self {
+        Tour {
+            solution: Solution::from_nodes(vec![goal_point]),
+            visited: BTreeSet::new(),
+            traversed: HashSet::new(),
+            score: R64::zero(),
+            evals: 0,
+            changes: 0,
+            val_sum: Nw::zero(),
+            nodes_with_val: 0,
+            recent: VecDeque::with_capacity(tabu_window),
+            aborted: false,
+            consumed: R64::zero(),
+            route_consumptions: Vec::with_capacity(vehicle_count),
+            earlier_routes: BTreeSet::new(),
+        }
+    }
 }
 
-impl<'a, IndexType, Nw> Ant<'a, IndexType, Nw, R64>
+impl<'a, IndexType, Nw, P> AntWalk<'a, IndexType, Nw, P>
 where
     IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
     Nw: Copy + Zero + One + AddAssign<Nw> + PartialEq,
+    P: PheromonePolicy<IndexType>,
 {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         graph: &'a RefCell<
             dyn GenericWeightedGraph<
-                IndexType = IndexType,
-                NodeWeightType = Nw,
-                EdgeWeightType = R64,
-            >,
+                    IndexType = IndexType,
+                    NodeWeightType = Nw,
+                    EdgeWeightType = R64,
+                > + 'a,
         >,
-        pheromone_matrix: &'a MatrixGraph<IndexType, (), R64>,
+        pheromones: P,
         goal_point: IndexType,
         max_time: R64,
         heuristic: &'a Heuristic<Nw, R64>,
@@ -62,10 +172,11 @@ where
         sender: Sender<Message<Nw, R64>>,
         id: usize,
         inv_shortest_paths: &'a BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+        return_costs: &'a ReturnCosts<IndexType, R64>,
     ) -> Self {
-        Ant {
+        AntWalk {
             graph,
-            pheromone_matrix,
+            pheromones,
             goal_point,
             max_time,
             heuristic,
@@ -73,12 +184,96 @@ where
             alpha,
             beta,
             q_0,
+            vehicle_count: 1,
             sender,
             id,
             inv_shortest_paths,
+            return_costs,
+            return_paths: None,
+            goal_distances: None,
+            time_windows: None,
+            mandatory_nodes: None,
+            stochastic_edges: None,
+            revisit_penalty: RevisitPenalty::default(),
+            limits: ConstructionLimits::default(),
+            secondary_budget: None,
+            service_times: None,
+            bidirectional: false,
         }
     }
 
+    /// Lets the ant construct vehicle_count routes from the goal point, which only share the goal
+    /// point, see viable_candidates.
+    pub fn with_vehicle_count(mut self, vehicle_count: usize) -> Self {
+        self.vehicle_count = vehicle_count;
+        self
+    }
+
+    pub fn with_time_windows(mut self, time_windows: &'a TimeWindows<IndexType, R64>) -> Self {
+        self.time_windows = Some(time_windows);
+        self
+    }
+
+    pub fn with_mandatory_nodes(mut self, mandatory_nodes: &'a HashSet<IndexType>) -> Self {
+        self.mandatory_nodes = Some(mandatory_nodes);
+        self
+    }
+
+    pub fn with_stochastic_edges(
+        mut self,
+        stochastic_edges: &'a StochasticEdges<IndexType>,
+    ) -> Self {
+        self.stochastic_edges = Some(stochastic_edges);
+        self
+    }
+
+    pub fn with_revisit_penalty(mut self, revisit_penalty: RevisitPenalty) -> Self {
+        self.revisit_penalty = revisit_penalty;
+        self
+    }
+
+    pub fn with_construction_limits(mut self, limits: ConstructionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Restricts every route to the budget of a second resource consumed along edges.
+    pub fn with_secondary_budget(
+        mut self,
+        secondary_budget: Option<&'a SecondaryBudget<IndexType, R64>>,
+    ) -> Self {
+        self.secondary_budget = secondary_budget;
+        self
+    }
+
+    /// Spends the service time of a node on the time budget, when the ant collects its value.
+    pub fn with_service_times(mut self, service_times: &'a ServiceTimes<IndexType, R64>) -> Self {
+        self.service_times = Some(service_times);
+        self
+    }
+
+    /// Lets the ant return to the goal point along any of the given paths, which still fits
+    /// into the time budget, instead of always taking the shortest one.
+    pub fn with_return_paths(mut self, return_paths: &'a ReturnPaths<IndexType, R64>) -> Self {
+        self.return_paths = Some(return_paths);
+        self
+    }
+
+    /// Looks up the distances to the goal point passed to the heuristic instead of computing
+    /// them for every candidate.
+    pub fn with_goal_distances(mut self, goal_distances: &'a GoalDistances<IndexType>) -> Self {
+        self.goal_distances = Some(goal_distances);
+        self
+    }
+
+    /// Grows every route at both of its ends instead of walking it from the goal point, see
+    /// walk_bidirectional. Time windows, stochastic edges, service times, the secondary budget,
+    /// return paths and the tabu window are not taken into account by bidirectional routes.
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
     fn weighted_heuristic(&self, to: IndexType, edge_weight: R64, tail_length: R64) -> R64 {
         self.weighted_heuristic_with_known_val(
             *self.graph.borrow().node_weight(to).unwrap(),
@@ -96,17 +291,55 @@ where
         tail_length: R64,
     ) -> R64 {
         R64::powf(
-            (self.heuristic)(
-                value,
-                edge_weight,
-                IndexType::distance(self.goal_point, to),
-                tail_length / self.max_time,
-            ),
+            self.heuristic_with_known_val(value, to, edge_weight, tail_length),
             R64::from_inner(self.beta),
         )
     }
 
-    /// If condition is true, node weight is assumed to be 0, else the weight from the graph is used.
+    fn heuristic_with_known_val(
+        &self,
+        value: Nw,
+        to: IndexType,
+        edge_weight: R64,
+        tail_length: R64,
+    ) -> R64 {
+        let goal_distance = match self.goal_distances {
+            Some(distances) => distances.get(to),
+            None => IndexType::distance(self.goal_point, to),
+        };
+        (self.heuristic)(
+            value,
+            edge_weight,
+            goal_distance,
+            tail_length / self.max_time,
+        )
+    }
+
+    /// Returns the score of moving along edge with weight to node, which is the heuristic
+    /// counting the value of node only if it can still be collected, minus the revisit
+    /// penalty. Solutions are scored like this by solution_score_with_penalties and TwoSwap as
+    /// well, the revisit discount only steers which node is selected.
+    fn move_score(
+        &self,
+        tour: &Tour<IndexType, Nw>,
+        node: IndexType,
+        edge: Edge<IndexType>,
+        weight: R64,
+        tail_length: R64,
+    ) -> R64 {
+        let value = if self.collectible(&tour.visited, node, tail_length + weight) {
+            *self.graph.borrow().node_weight(node).unwrap()
+        } else {
+            Nw::zero()
+        };
+        self.heuristic_with_known_val(value, node, weight, tail_length)
+            - self.revisit_penalty.of(
+                node != self.goal_point && tour.visited.contains(&node),
+                edge_traversed(&tour.traversed, edge),
+            )
+    }
+
+    /// If condition is false, node weight is assumed to be 0, else the weight from the graph is used.
     fn conditional_weighted_heuristic(
         &self,
         cond: bool,
@@ -117,74 +350,345 @@ where
         if cond {
             self.weighted_heuristic(to, edge_weight, tail_length)
         } else {
-            // self.weighted_heuristic_with_known_val(Nw::one(), to, edge_weight, tail_length)
             self.weighted_heuristic_with_known_val(Nw::zero(), to, edge_weight, tail_length)
         }
     }
 
-    pub fn get_solution(&self) -> AntSolution<IndexType, Nw> {
-        let start_time = Instant::now();
-        let mut evals = 0;
-        let mut changes = 0;
-        let mut score = R64::zero();
-        let mut rng = rng64(self.rng_seed);
-        let mut solution = Solution::new();
-        solution.push_node(self.goal_point);
+    /// Returns true if the value of node can still be collected when arriving at the given time.
+    fn collectible(&self, visited: &BTreeSet<IndexType>, node: IndexType, arrival: R64) -> bool {
+        !visited.contains(&node)
+            && self
+                .time_windows
+                .is_none_or(|windows| arrives_in_time(windows, &node, arrival))
+    }
 
-        let mut tail_length = R64::zero();
-        let mut next_node = self.goal_point;
-        let mut goal_reached = false;
-        let mut visited: BTreeSet<IndexType> = BTreeSet::new();
-        let mut val_sum = Nw::zero();
-        let mut nodes_with_val = 0;
-        while !goal_reached {
-            let viable_candidates: Vec<_> = self
-                .graph
-                .borrow()
-                .iter_neighbor_ids(next_node)
-                .unwrap()
-                .filter(|node| {
-                    if let Some((_, weight)) = &self.inv_shortest_paths[node] {
-                        let &weight_to =
-                            self.graph.borrow().edge_weight((next_node, *node)).unwrap();
-                        if tail_length + *weight + weight_to <= self.max_time {
-                            return true;
-                        }
-                    }
+    /// Returns the service time spent at node when arriving at the given time, which is only
+    /// spent if its value is collected.
+    fn service_time(&self, visited: &BTreeSet<IndexType>, node: IndexType, arrival: R64) -> R64 {
+        match self
+            .service_times
+            .and_then(|service_times| service_times.get(&node))
+        {
+            Some(&service_time) if self.collectible(visited, node, arrival) => service_time,
+            _ => R64::zero(),
+        }
+    }
+
+    /// Returns the most service time a route, which visited the given nodes, spends on path
+    /// back to the goal point. Time windows are ignored, so this is an upper bound.
+    fn return_service_time(
+        &self,
+        visited: &BTreeSet<IndexType>,
+        path: &Solution<IndexType>,
+    ) -> R64 {
+        match self.service_times {
+            Some(service_times) => path
+                .iter_nodes()
+                .skip(1)
+                .filter(|node| !visited.contains(node))
+                .filter_map(|node| service_times.get(node))
+                .fold(R64::zero(), |sum, &service_time| sum + service_time),
+            None => R64::zero(),
+        }
+    }
 
-                    false
+    /// Returns the factor by which the desirability of adding node to the route along edge is
+    /// reduced, if this revisits a node or an edge.
+    fn revisit_discount(
+        &self,
+        visited: &BTreeSet<IndexType>,
+        traversed: &HashSet<Edge<IndexType>>,
+        node: IndexType,
+        edge: Edge<IndexType>,
+    ) -> R64 {
+        self.revisit_penalty.discount(
+            node != self.goal_point && visited.contains(&node),
+            edge_traversed(traversed, edge),
+        )
+    }
+
+    /// Returns the weight of edge, against which the time budget is checked.
+    fn budget_weight(&self, edge: Edge<IndexType>, weight: R64) -> R64 {
+        match self.stochastic_edges {
+            Some(stochastic_edges) => stochastic_edges.budget_weight(edge, weight),
+            None => weight,
+        }
+    }
+
+    /// Returns the length of path with the given length, against which the time budget is
+    /// checked. With stochastic edges this is the sum of the risk adjusted weights of its edges.
+    fn path_budget_length(&self, path: &Solution<IndexType>, length: R64) -> R64 {
+        match self.stochastic_edges {
+            Some(stochastic_edges) if !stochastic_edges.variances.is_empty() => {
+                let graph = self.graph.borrow();
+                path.iter_edges().fold(R64::zero(), |sum, (&from, &to)| {
+                    let &weight = graph.edge_weight((from, to)).unwrap();
+                    sum + stochastic_edges.budget_weight((from, to), weight)
                 })
-                .collect();
+            }
+            _ => length,
+        }
+    }
 
-            // as soon as we have no more candidates to travel to we can just take our calculated shortest path
-            if viable_candidates.is_empty() {
-                // if we added the path even when we have reached the goal point we get it twice at the end of the solution
-                if next_node != self.goal_point {
-                    let (mut path, distance) = self.inv_shortest_paths[&next_node].clone().unwrap();
-                    solution.append(&mut path);
-                    tail_length += distance;
-                    for node in path.iter_nodes() {
-                        if !visited.contains(node) {
-                            visited.insert(*node);
-                            if *self.graph.borrow().node_weight(*node).unwrap() != Nw::zero() {
-                                nodes_with_val += 1;
-                            }
-                        }
+    /// Returns the time it takes to traverse edge.
+    fn traversal_time(&self, edge: Edge<IndexType>, weight: R64, rng: &mut Rng) -> R64 {
+        match self.stochastic_edges {
+            Some(stochastic_edges) => stochastic_edges.sample(edge, weight, rng),
+            None => weight,
+        }
+    }
+
+    /// Returns true if a route, which consumed consumed so far, can still move along edge and
+    /// return to the goal point along the shortest path within the secondary budget.
+    fn fits_secondary(&self, consumed: R64, edge: Edge<IndexType>) -> bool {
+        self.secondary_budget.is_none_or(|budget| {
+            let return_consumption = self.inv_shortest_paths[&edge.1]
+                .as_ref()
+                .map_or_else(R64::zero, |(path, _)| budget.of_route(path));
+            budget.fits(consumed + budget.consumption(edge) + return_consumption)
+        })
+    }
+
+    /// Returns the neighbors of from, which can be visited while still returning to the goal point
+    /// within the time budget and the secondary budget, of which the route consumed consumed.
+    /// The service times of the candidate and of the nodes on its way back count as well.
+    /// The time budget is checked against budget_length, the risk adjusted length of the route
+    /// so far, and the risk adjusted weights of the candidate edge and the way back, while
+    /// time windows are checked against the arrival at tail_length.
+    /// Nodes of earlier routes of the tour and candidates, whose way back passes them, are left
+    /// out, so the routes stay disjoint.
+    fn viable_candidates(
+        &self,
+        tour: &Tour<IndexType, Nw>,
+        from: IndexType,
+        tail_length: R64,
+        budget_length: R64,
+        consumed: R64,
+    ) -> Vec<IndexType> {
+        // the pheromone matrix only contains edges, which can be part of a feasible route
+        self.pheromones.read(|pheromones| {
+            pheromones
+                .iter_neighbor_ids(from)
+                .unwrap()
+                .filter(|node| !tour.earlier_routes.contains(node))
+                .filter(|node| match self.return_costs.get(*node) {
+                    Some(_)
+                        if self.inv_shortest_paths[node]
+                            .as_ref()
+                            .is_some_and(|(path, _)| !self.disjoint(tour, path)) =>
+                    {
+                        false
+                    }
+                    Some(return_cost) => {
+                        let visited = &tour.visited;
+                        let &weight_to = self.graph.borrow().edge_weight((from, *node)).unwrap();
+                        let return_path = self.inv_shortest_paths[node].as_ref();
+                        let return_cost = return_path.map_or(return_cost, |(path, _)| {
+                            self.path_budget_length(path, return_cost)
+                        });
+                        let service_time =
+                            self.service_time(visited, *node, tail_length + weight_to)
+                                + return_path.map_or_else(R64::zero, |(path, _)| {
+                                    self.return_service_time(visited, path)
+                                });
+                        let weight_to = self.budget_weight((from, *node), weight_to);
+                        budget_length + return_cost + weight_to + service_time <= self.max_time
+                            && self.fits_secondary(consumed, (from, *node))
                     }
+                    None => false,
+                })
+                .collect()
+        })
+    }
+
+    /// Returns true if path doesn't enter the earlier routes of tour.
+    fn disjoint(&self, tour: &Tour<IndexType, Nw>, path: &Solution<IndexType>) -> bool {
+        path.iter_nodes()
+            .all(|node| !tour.earlier_routes.contains(node))
+    }
+
+    /// Removes the nodes of the tabu window from the candidates, unless no other candidate is left.
+    fn drop_tabu(&self, tour: &Tour<IndexType, Nw>, candidates: Vec<IndexType>) -> Vec<IndexType> {
+        if tour.recent.is_empty() {
+            return candidates;
+        }
+        let allowed: Vec<IndexType> = candidates
+            .iter()
+            .copied()
+            .filter(|node| !tour.recent.contains(node))
+            .collect();
+        if allowed.is_empty() {
+            candidates
+        } else {
+            allowed
+        }
+    }
+
+    /// Remembers node as left by the current route, forgetting the oldest node of the tabu
+    /// window if it is full.
+    fn remember(&self, tour: &mut Tour<IndexType, Nw>, node: IndexType) {
+        if self.limits.tabu_window == 0 {
+            return;
+        }
+        if tour.recent.len() == self.limits.tabu_window {
+            tour.recent.pop_front();
+        }
+        tour.recent.push_back(node);
+    }
+
+    /// Adds the value of node to the statistics of the tour, if it can be collected at arrival,
+    /// and marks it as visited. Returns the service time spent at node.
+    fn visit(&self, tour: &mut Tour<IndexType, Nw>, node: IndexType, arrival: R64) -> R64 {
+        let service_time = self.service_time(&tour.visited, node, arrival);
+        if self.collectible(&tour.visited, node, arrival) {
+            if let Ok(&value) = self.graph.borrow().node_weight(node) {
+                if value != Nw::zero() {
+                    tour.nodes_with_val += 1;
+                    tour.val_sum += value;
                 }
-                goal_reached = true;
-                break;
             }
+        }
+        tour.visited.insert(node);
+        service_time
+    }
+
+    /// Returns the path from from to the goal point, which the ant takes when returning.
+    /// Without return paths this is the shortest path, otherwise one of the paths fitting into
+    /// the time budget is chosen with a probability proportional to its pheromone level, which
+    /// is the mean level of its edges to the power of alpha. The shortest path always counts as
+    /// fitting and is taken if no path has any pheromone. The service times of not yet
+    /// visited nodes on a path count towards its length, which is risk adjusted like
+    /// budget_length, the length of the route so far. Paths entering earlier routes of tour
+    /// are left out.
+    fn return_path(
+        &self,
+        tour: &Tour<IndexType, Nw>,
+        from: IndexType,
+        budget_length: R64,
+        consumed: R64,
+        rng: &mut Rng,
+    ) -> &'a (Solution<IndexType>, R64) {
+        let shortest = self.inv_shortest_paths[&from].as_ref().unwrap();
+        let paths = match self
+            .return_paths
+            .and_then(|return_paths| return_paths.get(&from))
+        {
+            Some(paths) if paths.len() > 1 => paths,
+            _ => return shortest,
+        };
+        let candidates: Vec<_> = std::iter::once(shortest)
+            .chain(
+                paths
+                    .iter()
+                    .skip(1)
+                    // risk adjusted lengths are never shorter than the sorted distances
+                    .take_while(|(_, distance)| budget_length + *distance <= self.max_time)
+                    .filter(|(path, distance)| {
+                        budget_length
+                            + self.path_budget_length(path, *distance)
+                            + self.return_service_time(&tour.visited, path)
+                            <= self.max_time
+                    })
+                    .filter(|(path, _)| self.disjoint(tour, path))
+                    .filter(|(path, _)| {
+                        self.secondary_budget
+                            .is_none_or(|budget| budget.fits(consumed + budget.of_route(path)))
+                    }),
+            )
+            .collect();
+        let levels: Vec<R64> = self.pheromones.read(|pheromones| {
+            candidates
+                .iter()
+                .map(|(path, _)| {
+                    let edge_levels: Vec<R64> = path
+                        .iter_edges()
+                        .filter_map(|(&from, &to)| pheromones.edge_weight((from, to)).ok())
+                        .copied()
+                        .collect();
+                    if edge_levels.is_empty() {
+                        return R64::one();
+                    }
+                    let mean = edge_levels
+                        .iter()
+                        .fold(R64::zero(), |sum, &level| sum + level)
+                        / R64::from_inner(edge_levels.len() as f64);
+                    R64::powf(mean, R64::from_inner(self.alpha))
+                })
+                .collect()
+        });
+        let level_sum = levels.iter().fold(R64::zero(), |sum, &level| sum + level);
+        if level_sum == R64::zero() {
+            return shortest;
+        }
+
+        let rand = R64::from_inner(gen_float_range(rng, 0.0..level_sum.into_inner()));
+        let mut sum = R64::zero();
+        for (candidate, level) in candidates.iter().zip(levels) {
+            sum += level;
+            if sum >= rand {
+                return candidate;
+            }
+        }
+        shortest
+    }
 
-            let mut best_by_pheromone = self.goal_point;
-            let mut best_pheromone = R64::zero();
-            // weighted pheromone sum will be used in case we visited all neighbors of this node
-            let weighted_pheromone_sum = viable_candidates
+    /// Appends a path from from to the goal point to the tour and returns its length and its
+    /// risk adjusted length, both including the service times spent on it. The traversal times
+    /// of the path are sampled like the ones of the route so far, which has length tail_length
+    /// and risk adjusted length budget_length. consumed is the consumption of the secondary
+    /// budget by the route so far.
+    fn return_to_goal(
+        &self,
+        tour: &mut Tour<IndexType, Nw>,
+        from: IndexType,
+        (tail_length, budget_length): (R64, R64),
+        consumed: R64,
+        rng: &mut Rng,
+    ) -> (R64, R64) {
+        let (path, distance) = self.return_path(tour, from, budget_length, consumed, rng);
+        // the path starts at from, which already is the last node of the solution
+        let mut arrival = tail_length;
+        let mut service_time = R64::zero();
+        for (&previous, &node) in path.iter_edges() {
+            let weight = *self.graph.borrow().edge_weight((previous, node)).unwrap();
+            tour.score += self.move_score(tour, node, (previous, node), weight, arrival);
+            arrival += self.traversal_time((previous, node), weight, rng);
+            let spent = self.visit(tour, node, arrival);
+            arrival += spent;
+            service_time += spent;
+            tour.traversed.insert((previous, node));
+            tour.solution.push_node(node);
+            tour.consumed += self.secondary_consumption((previous, node));
+        }
+
+        (
+            arrival - tail_length,
+            self.path_budget_length(path, *distance) + service_time,
+        )
+    }
+
+    /// Chooses the node to add to the route at from among the viable candidates. direction
+    /// tells at which end the route grows.
+    fn select(
+        &self,
+        tour: &mut Tour<IndexType, Nw>,
+        from: IndexType,
+        tail_length: R64,
+        viable_candidates: &[IndexType],
+        direction: Direction,
+        rng: &mut Rng,
+    ) -> Option<IndexType> {
+        // starting with a candidate, as nothing would be selected if all levels are zero
+        let mut best_by_pheromone = viable_candidates[0];
+        let mut best_pheromone = R64::zero();
+        // weighted pheromone sum will be used in case we visited all neighbors of this node
+        let weighted_pheromone_sum = self.pheromones.read(|pheromones| {
+            viable_candidates
                 .iter()
                 .map(|id| {
                     (
                         id,
-                        self.pheromone_matrix.edge_weight((next_node, *id)).unwrap(),
+                        pheromones.edge_weight(direction.edge(from, *id)).unwrap(),
                     )
                 })
                 .map(|(id, weight)| (id, R64::powf(*weight, R64::from_inner(self.alpha))))
@@ -194,18 +698,25 @@ where
                         best_pheromone = *weight;
                     }
                 })
-                .fold(R64::zero(), |acc, (_, weight_term)| acc + weight_term);
+                .fold(R64::zero(), |acc, (_, weight_term)| acc + weight_term)
+        });
 
-            // the default is using the weighted sum as given by the paper
-            let mut best_node = self.goal_point;
-            let mut best_full = R64::zero();
-            let mut weighted_sum = viable_candidates
+        // the default is using the weighted sum as given by the paper
+        let mut best_node = self.goal_point;
+        let mut best_full = R64::zero();
+        let mut evals = 0;
+        let mut weighted_sum = self.pheromones.read(|pheromones| {
+            viable_candidates
                 .iter()
                 .map(|&id| {
                     (
                         id,
-                        *self.graph.borrow().edge_weight((next_node, id)).unwrap(),
-                        self.pheromone_matrix.edge_weight((next_node, id)).unwrap(),
+                        *self
+                            .graph
+                            .borrow()
+                            .edge_weight(direction.edge(from, id))
+                            .unwrap(),
+                        pheromones.edge_weight(direction.edge(from, id)).unwrap(),
                     )
                 })
                 .inspect(|_| evals += 1) // increment evals for each call to heuristic
@@ -213,10 +724,15 @@ where
                     (
                         to,
                         (self.conditional_weighted_heuristic(
-                            !visited.contains(&to),
+                            self.collectible(&tour.visited, to, tail_length + h_weight),
                             to,
                             h_weight,
                             tail_length,
+                        ) * self.revisit_discount(
+                            &tour.visited,
+                            &tour.traversed,
+                            to,
+                            direction.edge(from, to),
                         ) * R64::powf(*p_weight, R64::from_inner(self.alpha))),
                     )
                 })
@@ -226,97 +742,386 @@ where
                         best_node = *to;
                     }
                 })
-                .fold(R64::zero(), |acc, (_, weighted_term)| acc + weighted_term);
-
-            let mut visited_all_viable = false;
-            if weighted_sum == R64::zero() {
-                weighted_sum = weighted_pheromone_sum;
-                best_node = best_by_pheromone;
-                best_full = best_pheromone;
-                visited_all_viable = true;
+                .fold(R64::zero(), |acc, (_, weighted_term)| acc + weighted_term)
+        });
+        tour.evals += evals;
+
+        let mut visited_all_viable = false;
+        if weighted_sum == R64::zero() {
+            weighted_sum = weighted_pheromone_sum;
+            best_node = best_by_pheromone;
+            visited_all_viable = true;
+        }
+
+        // as soon, as we reach a point where the sum of the weighted pheromones and heuristic
+        // is equal to the random number, we have hit the value with the correct probability
+        // according to the formula at https://en.wikipedia.org/wiki/Ant_colony_optimization_algorithms#Edge_selection
+        let frand = rng.rand_float();
+        let mut use_best = frand <= self.q_0;
+        // mandatory nodes are always visited as soon as they are reachable
+        if let Some(&mandatory) = self.mandatory_nodes.and_then(|mandatory_nodes| {
+            viable_candidates
+                .iter()
+                .find(|id| mandatory_nodes.contains(id) && !tour.visited.contains(id))
+        }) {
+            use_best = true;
+            best_node = mandatory;
+        }
+        let rand = R64::from_inner(frand) * weighted_sum;
+
+        let mut sum = R64::zero();
+        for &id in viable_candidates.iter() {
+            if use_best && id != best_node {
+                continue;
+            }
+            let pheromone_level = self
+                .pheromones
+                .read(|pheromones| *pheromones.edge_weight(direction.edge(from, id)).unwrap());
+            let distance = *self
+                .graph
+                .borrow()
+                .edge_weight(direction.edge(from, id))
+                .unwrap();
+            let weighted_heuristic = if !visited_all_viable {
+                tour.evals += 1;
+                self.conditional_weighted_heuristic(
+                    self.collectible(&tour.visited, id, tail_length + distance),
+                    id,
+                    distance,
+                    tail_length,
+                ) * self.revisit_discount(
+                    &tour.visited,
+                    &tour.traversed,
+                    id,
+                    direction.edge(from, id),
+                )
+            } else {
+                R64::one()
+            };
+            sum += weighted_heuristic * R64::powf(pheromone_level, R64::from_inner(self.alpha));
+
+            // sum is bigger than the random value we generated, so we hit our node
+            // with the correct probability
+            if sum >= rand || use_best {
+                return Some(id);
             }
+        }
+
+        None
+    }
+
+    /// Returns the amount of the secondary budget consumed by traversing edge.
+    fn secondary_consumption(&self, edge: Edge<IndexType>) -> R64 {
+        self.secondary_budget
+            .map_or_else(R64::zero, |budget| budget.consumption(edge))
+    }
 
-            // as soon, as we reach a point where the sum of the weighted pheromones and heuristic
-            // is equal to the random number, we have hit the value with the correct probability
-            // according to the formula at https://en.wikipedia.org/wiki/Ant_colony_optimization_algorithms#Edge_selection
-            let frand = rng.rand_float();
-            let mut use_best = false;
-            if frand <= self.q_0 {
-                use_best = true;
+    /// Constructs a single route from the goal point back to it and returns its length and its
+    /// risk adjusted length. The length consists of the sampled traversal times, while the time
+    /// budget is checked against the risk adjusted length, see StochasticEdges::budget_weight.
+    /// Without stochastic edges both are the same.
+    /// Once the step limit is reached, the route is completed by the shortest path back and all
+    /// remaining routes stay empty.
+    fn walk_route(&self, tour: &mut Tour<IndexType, Nw>, rng: &mut Rng) -> (R64, R64) {
+        let mut tail_length = R64::zero();
+        let mut budget_length = R64::zero();
+        let mut next_node = self.goal_point;
+        let consumed_before = tour.consumed;
+        tour.recent.clear();
+        loop {
+            let consumed = tour.consumed - consumed_before;
+            let reached_limit = self
+                .limits
+                .step_limit
+                .is_some_and(|step_limit| tour.changes >= step_limit);
+            if reached_limit || tour.aborted {
+                tour.aborted = true;
+                if next_node != self.goal_point {
+                    let (length, budget) = self.return_to_goal(
+                        tour,
+                        next_node,
+                        (tail_length, budget_length),
+                        consumed,
+                        rng,
+                    );
+                    return (tail_length + length, budget_length + budget);
+                }
+                return (tail_length, budget_length);
             }
-            let rand = R64::from_inner(frand) * weighted_sum;
 
-            let mut sum = R64::zero();
-            for &id in viable_candidates.iter() {
-                if use_best && id != best_node {
-                    continue;
+            let viable_candidates = self.drop_tabu(
+                tour,
+                self.viable_candidates(tour, next_node, tail_length, budget_length, consumed),
+            );
+
+            // as soon as we have no more candidates to travel to we can just take our calculated shortest path
+            if viable_candidates.is_empty() {
+                // if we added the path even when we have reached the goal point we get it twice at the end of the solution
+                if next_node != self.goal_point {
+                    let (length, budget) = self.return_to_goal(
+                        tour,
+                        next_node,
+                        (tail_length, budget_length),
+                        consumed,
+                        rng,
+                    );
+                    return (tail_length + length, budget_length + budget);
                 }
-                let pheromone_level = self.pheromone_matrix.edge_weight((next_node, id)).unwrap();
+                return (tail_length, budget_length);
+            }
+
+            if let Some(id) = self.select(
+                tour,
+                next_node,
+                tail_length,
+                &viable_candidates,
+                Direction::Forward,
+                rng,
+            ) {
                 let distance = *self.graph.borrow().edge_weight((next_node, id)).unwrap();
-                let weighted_heuristic = if !visited_all_viable {
-                    evals += 1;
-                    self.conditional_weighted_heuristic(
-                        !visited.contains(&id),
-                        id,
-                        distance,
-                        tail_length,
-                    )
-                } else {
-                    R64::one()
+                tour.score += self.move_score(tour, id, (next_node, id), distance, tail_length);
+                let service_time = self.visit(tour, id, tail_length + distance);
+                if tour.traversed.insert((next_node, id)) {
+                    self.pheromones.traversed((next_node, id));
+                }
+
+                tour.solution.push_node(id);
+                tail_length += self.traversal_time((next_node, id), distance, rng) + service_time;
+                budget_length += self.budget_weight((next_node, id), distance) + service_time;
+                tour.consumed += self.secondary_consumption((next_node, id));
+                tour.changes += 1;
+                self.remember(tour, next_node);
+                next_node = id;
+            }
+        }
+    }
+
+    /// Constructs a single route from the goal point back to it by alternately adding nodes to
+    /// its start along outgoing edges and to its end along incoming edges, which lets the route
+    /// spend its time budget away from the goal point more evenly. A node is only added, if the
+    /// shortest path between the two ends still closes the route within the time budget. Once
+    /// neither end can grow or the step limit is reached, the ends are joined by that path and
+    /// the length of the closed route is returned.
+    /// Like in viable_candidates, nodes of earlier routes of the tour and candidates, whose
+    /// shortest path to the opposite end passes them, are left out.
+    /// Every move computes the shortest paths to or from the opposite end, which makes this
+    /// slower than walk_route.
+    fn walk_bidirectional(&self, tour: &mut Tour<IndexType, Nw>, rng: &mut Rng) -> R64 {
+        let mut predecessors: BTreeMap<IndexType, Vec<IndexType>> = BTreeMap::new();
+        self.pheromones.read(|pheromones| {
+            for (from, to) in pheromones.edge_ids() {
+                predecessors.entry(to).or_default().push(from);
+            }
+        });
+        let distance = |paths: &BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
+                        node: IndexType,
+                        end: IndexType| {
+            if node == end {
+                Some(R64::zero())
+            } else {
+                paths
+                    .get(&node)
+                    .and_then(|path| path.as_ref())
+                    .map(|(_, distance)| *distance)
+            }
+        };
+
+        // the end of the route is kept from the goal point backwards
+        let mut backward = vec![self.goal_point];
+        let (mut front, mut back) = (self.goal_point, self.goal_point);
+        let (mut front_length, mut back_length) = (R64::zero(), R64::zero());
+        let mut direction = Direction::Forward;
+        // ends in a row, which could not grow
+        let mut stuck = 0;
+        while stuck < 2 {
+            if self
+                .limits
+                .step_limit
+                .is_some_and(|step_limit| tour.changes >= step_limit)
+            {
+                tour.aborted = true;
+                break;
+            }
+            let (from, length) = match direction {
+                Direction::Forward => (front, front_length),
+                Direction::Backward => (back, back_length),
+            };
+            let candidates: Vec<IndexType> = {
+                let graph = self.graph.borrow();
+                let (neighbors, paths) = match direction {
+                    Direction::Forward => (
+                        self.pheromones.read(|pheromones| {
+                            pheromones.iter_neighbor_ids(front).unwrap().collect()
+                        }),
+                        graph.inv_shortest_paths(back),
+                    ),
+                    Direction::Backward => (
+                        predecessors.get(&back).cloned().unwrap_or_default(),
+                        graph.shortest_paths(front),
+                    ),
                 };
-                sum +=
-                    weighted_heuristic * R64::powf(*pheromone_level, R64::from_inner(self.alpha));
-
-                // sum is bigger than the random value we generated, so we hit our node
-                // with the correct probability
-                if sum >= rand || use_best {
-                    // add to value sum and nodes with val
-                    let borrow = self.graph.borrow();
-                    let nw = borrow.node_weight(id);
-                    if !visited.contains(&id) && nw.is_ok() {
-                        let nw_val = *nw.unwrap();
-                        if nw_val != Nw::zero() {
-                            nodes_with_val += 1;
-                            val_sum += nw_val;
-                        }
-                    }
+                let other_end = match direction {
+                    Direction::Forward => back,
+                    Direction::Backward => front,
+                };
+                neighbors
+                    .into_iter()
+                    .filter(|node| !tour.earlier_routes.contains(node))
+                    .filter(|node| {
+                        paths
+                            .get(node)
+                            .and_then(|path| path.as_ref())
+                            .is_none_or(|(path, _)| self.disjoint(tour, path))
+                    })
+                    .filter(|&node| {
+                        let &weight = graph.edge_weight(direction.edge(from, node)).unwrap();
+                        distance(&paths, node, other_end).is_some_and(|rest| {
+                            front_length + back_length + weight + rest <= self.max_time
+                        })
+                    })
+                    .collect()
+            };
 
-                    solution.push_node(id);
-                    tail_length += distance;
-                    score += weighted_heuristic;
-                    visited.insert(id);
-                    changes += 1;
-                    next_node = id;
-                    break;
+            let selected = if candidates.is_empty() {
+                None
+            } else {
+                self.select(tour, from, length, &candidates, direction, rng)
+            };
+            if let Some(id) = selected {
+                let edge = direction.edge(from, id);
+                let weight = *self.graph.borrow().edge_weight(edge).unwrap();
+                tour.score += self.move_score(tour, id, edge, weight, length);
+                self.visit(tour, id, length + weight);
+                if tour.traversed.insert(edge) {
+                    self.pheromones.traversed(edge);
+                }
+                match direction {
+                    Direction::Forward => {
+                        tour.solution.push_node(id);
+                        front_length += weight;
+                        front = id;
+                    }
+                    Direction::Backward => {
+                        backward.push(id);
+                        back_length += weight;
+                        back = id;
+                    }
                 }
+                tour.changes += 1;
+                stuck = 0;
+            } else {
+                stuck += 1;
+            }
+            direction = match direction {
+                Direction::Forward => Direction::Backward,
+                Direction::Backward => Direction::Forward,
+            };
+        }
+
+        // joins the ends by the shortest path, which was checked to exist by every move
+        let mut join_length = R64::zero();
+        if front != back {
+            let mut paths = self.graph.borrow().shortest_paths(front);
+            let (path, length) = paths.remove(&back).flatten().unwrap();
+            let mut arrival = front_length;
+            for (&previous, &node) in path.iter_edges() {
+                let weight = *self.graph.borrow().edge_weight((previous, node)).unwrap();
+                tour.score += self.move_score(tour, node, (previous, node), weight, arrival);
+                arrival += weight;
+                self.visit(tour, node, arrival);
+                tour.traversed.insert((previous, node));
+                tour.solution.push_node(node);
             }
+            join_length = length;
+        }
+        // back is the last node of the solution now
+        for &node in backward.iter().rev().skip(1) {
+            tour.solution.push_node(node);
         }
 
-        let visited_nodes = visited.len();
+        front_length + join_length + back_length
+    }
+
+    pub fn get_solution(&self) -> AntSolution<IndexType, Nw> {
+        let start_time = Instant::now();
+        let mut rng = rng64(self.rng_seed);
+        let mut tour = Tour::new(self.goal_point, self.limits.tabu_window, self.vehicle_count);
+
+        let mut length = R64::zero();
+        let mut route_lengths = Vec::with_capacity(self.vehicle_count);
+        let mut budget_lengths = Vec::with_capacity(self.vehicle_count);
+        let mut route_scores = Vec::with_capacity(self.vehicle_count);
+        // every vehicle starts a new route at the goal point and avoids the nodes of the earlier
+        // routes, so no reward is collected twice
+        for _ in 0..self.vehicle_count {
+            let consumed_before = tour.consumed;
+            let score_before = tour.score;
+            let route_start = tour.solution.iter_nodes().count();
+            let (route_length, budget_length) = if self.bidirectional {
+                let route_length = self.walk_bidirectional(&mut tour, &mut rng);
+                (route_length, route_length)
+            } else {
+                self.walk_route(&mut tour, &mut rng)
+            };
+            length += route_length;
+            route_lengths.push(route_length);
+            budget_lengths.push(budget_length);
+            route_scores.push(tour.score - score_before);
+            tour.route_consumptions
+                .push(tour.consumed - consumed_before);
+            let goal_point = self.goal_point;
+            tour.earlier_routes.extend(
+                tour.solution
+                    .iter_nodes()
+                    .skip(route_start)
+                    .filter(|&&node| node != goal_point),
+            );
+        }
+
+        let visited_nodes = tour.visited.len();
+        let longest_route = route_lengths
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_else(R64::zero);
 
         // TODO: log errors from sending here
-        let _res = self.sender.send(Message::new(
-            self.id,
-            0,
-            evals,
-            0,
-            changes,
-            0,
-            start_time.elapsed(),
-            tail_length,
-            score,
-            visited_nodes,
-            nodes_with_val,
-            val_sum,
-        ));
+        let _res = self.sender.send(
+            Message::new(
+                self.id,
+                0,
+                tour.evals,
+                0,
+                tour.changes,
+                0,
+                start_time.elapsed(),
+                length,
+                tour.score,
+                visited_nodes,
+                tour.nodes_with_val,
+                tour.val_sum,
+            )
+            .with_routes(route_lengths.len(), longest_route)
+            .with_aborted(tour.aborted)
+            .with_secondary_resource(tour.consumed.into_inner()),
+        );
 
         AntSolution {
-            solution,
-            length: tail_length,
-            score,
+            solution: tour.solution,
+            length,
+            route_lengths,
+            budget_lengths,
+            route_scores,
+            score: tour.score,
             visited_nodes,
-            visited_with_val: nodes_with_val,
-            val_sum,
+            visited_with_val: tour.nodes_with_val,
+            val_sum: tour.val_sum,
+            aborted: tour.aborted,
+            secondary_resource: tour.consumed,
+            route_consumptions: tour.route_consumptions,
+            // without a single move the ant had no feasible first move
+            warning: (tour.changes == 0 && !tour.aborted)
+                .then_some(ConstructionWarning::NoFeasibleMove(self.goal_point)),
         }
     }
 }
@@ -324,8 +1129,702 @@ where
 pub struct AntSolution<IndexType, NwType> {
     pub solution: Solution<IndexType>,
     pub length: R64,
+    pub route_lengths: Vec<R64>,
+    /// Lengths of every route, against which the time budget is checked. These are risk
+    /// adjusted with stochastic edges and equal to route_lengths otherwise.
+    pub budget_lengths: Vec<R64>,
+    /// Score of every route, which add up to score.
+    pub route_scores: Vec<R64>,
     pub score: R64,
     pub visited_nodes: usize,
     pub visited_with_val: usize,
     pub val_sum: NwType,
+    /// True if the construction reached the step limit before returning on its own.
+    pub aborted: bool,
+    /// Consumption of the secondary budget by all routes.
+    pub secondary_resource: R64,
+    /// Consumption of the secondary budget by every route.
+    pub route_consumptions: Vec<R64>,
+    pub warning: Option<ConstructionWarning<IndexType>>,
+}
+
+impl<IndexType, NwType> AntSolution<IndexType, NwType> {
+    /// Returns by how much the longest route exceeds max_time, zero if every route fits. Routes
+    /// are measured by their budget_lengths.
+    pub fn overshoot(&self, max_time: R64) -> R64 {
+        self.budget_lengths
+            .iter()
+            .map(|&length| length - max_time)
+            .fold(R64::zero(), R64::max)
+    }
+
+    /// Returns true if every route stays within secondary_budget, or if there is none.
+    pub fn fits_secondary_budget(
+        &self,
+        secondary_budget: Option<&SecondaryBudget<IndexType, R64>>,
+    ) -> bool
+    where
+        IndexType: Copy + Hash + Eq,
+    {
+        secondary_budget.is_none_or(|budget| {
+            self.route_consumptions
+                .iter()
+                .all(|&consumed| budget.fits(consumed))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use crate::metaheuristic::{return_paths, solution_length_with_service_times, TimeWindow};
+    use std::sync::mpsc;
+
+    fn nw(n: R64, _: R64, _: R64, _: R64) -> R64 {
+        n
+    }
+
+    fn star_graph() -> MatrixGraph<usize, R64, R64> {
+        MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(3.0),
+                R64::from_inner(2.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(5.0)),
+                (0, 2, R64::from_inner(5.0)),
+                (1, 0, R64::from_inner(5.0)),
+                (2, 0, R64::from_inner(5.0)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn pheromones(graph: &MatrixGraph<usize, R64, R64>) -> Pheromones<usize> {
+        Pheromones::new(graph.node_ids(), graph.edge_ids(), R64::from_inner(1.0))
+    }
+
+    #[test]
+    fn multiple_vehicles_share_rewards() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let time_windows = TimeWindows::new();
+        let mandatory_nodes = HashSet::new();
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(10.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_vehicle_count(2)
+        .with_time_windows(&time_windows)
+        .with_mandatory_nodes(&mandatory_nodes);
+        let solution = ant.get_solution();
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 0, 2, 0]));
+        assert_eq!(
+            solution.route_lengths,
+            vec![R64::from_inner(10.0), R64::from_inner(10.0)]
+        );
+        assert_eq!(solution.length, 20.0);
+        assert_eq!(solution.visited_with_val, 2);
+        assert_eq!(solution.val_sum, 5.0);
+    }
+
+    #[test]
+    fn routes_of_multiple_vehicles_are_disjoint() {
+        // 2 can only be reached through 1
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(3.0),
+                R64::from_inner(5.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 0, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 1, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(4.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_vehicle_count(2);
+        let solution = ant.get_solution();
+
+        // the second vehicle can't leave the goal point without entering the first route
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 2, 1, 0]));
+        assert_eq!(
+            solution.route_lengths,
+            vec![R64::from_inner(4.0), R64::zero()]
+        );
+        assert_eq!(solution.route_scores[1], R64::zero());
+        assert_eq!(solution.route_scores[0], solution.score);
+        assert_eq!(solution.val_sum, 8.0);
+    }
+
+    #[test]
+    fn bidirectional_routes_of_multiple_vehicles_are_disjoint() {
+        // 2 can only be reached through 1
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(3.0),
+                R64::from_inner(5.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 0, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 1, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let solution = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(4.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_vehicle_count(2)
+        .with_bidirectional(true)
+        .get_solution();
+
+        // the second vehicle can't leave the goal point without entering the first route
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 2, 1, 0]));
+        assert_eq!(
+            solution.route_lengths,
+            vec![R64::from_inner(4.0), R64::zero()]
+        );
+        assert_eq!(solution.route_scores[1], R64::zero());
+    }
+
+    #[test]
+    fn closed_time_windows_are_skipped() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let mut time_windows = TimeWindows::new();
+        time_windows.insert(1, TimeWindow::new(R64::zero(), R64::from_inner(4.0)));
+        let mandatory_nodes = HashSet::new();
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(10.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_time_windows(&time_windows)
+        .with_mandatory_nodes(&mandatory_nodes);
+        let solution = ant.get_solution();
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 2, 0]));
+        assert_eq!(solution.val_sum, 2.0);
+    }
+
+    #[test]
+    fn service_times_are_only_spent_when_collecting() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let service_times: ServiceTimes<usize, R64> =
+            [(1, R64::from_inner(2.0)), (2, R64::from_inner(1.0))].into();
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        // the route has to pass 0 -> 1 -> 0 again without service to use up the budget exactly
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(33.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_service_times(&service_times);
+        let solution = ant.get_solution();
+
+        assert_eq!(solution.solution.nodes().len(), 7);
+        assert_eq!(solution.val_sum, 5.0);
+        assert_eq!(solution.length, 33.0);
+        assert_eq!(
+            solution_length_with_service_times(
+                &solution.solution,
+                &graph,
+                &service_times,
+                &TimeWindows::new()
+            ),
+            Ok(solution.length)
+        );
+    }
+
+    #[test]
+    fn return_paths_are_risk_adjusted() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        // returning from 1 takes 5 + 1 * 2 with risk aversion
+        let variances = vec![((1, 0), R64::from_inner(4.0))].into_iter().collect();
+        let stochastic_edges = StochasticEdges::new(variances, 1.0);
+        let graph = RefCell::new(graph);
+        let route = |max_time: f64| {
+            let (sender, _receiver) = mpsc::channel();
+            Ant::new(
+                &graph,
+                &pheromones,
+                0,
+                R64::from_inner(max_time),
+                &nw,
+                0,
+                1.0,
+                1.0,
+                1.0,
+                sender,
+                1,
+                &inv_shortest_paths,
+                &return_costs,
+            )
+            .with_stochastic_edges(&stochastic_edges)
+            .get_solution()
+        };
+
+        let solution = route(11.0);
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 2, 0]));
+        assert_eq!(solution.budget_lengths, vec![R64::from_inner(10.0)]);
+        let solution = route(12.0);
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 0]));
+        assert_eq!(solution.budget_lengths, vec![R64::from_inner(12.0)]);
+        assert_eq!(solution.overshoot(R64::from_inner(12.0)), 0.0);
+    }
+
+    #[test]
+    fn routes_stay_within_the_secondary_budget() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let consumption = vec![((0, 1), 3.0), ((1, 0), 3.0), ((0, 2), 1.0), ((2, 0), 1.0)]
+            .into_iter()
+            .map(|(edge, consumed)| (edge, R64::from_inner(consumed)))
+            .collect();
+        let secondary_budget = SecondaryBudget::new(consumption, R64::from_inner(5.0));
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(10.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_secondary_budget(Some(&secondary_budget));
+        let solution = ant.get_solution();
+
+        // node 1 has the higher value, but visiting it consumes 6
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 2, 0]));
+        assert_eq!(solution.secondary_resource, 2.0);
+    }
+
+    #[test]
+    fn reachable_mandatory_nodes_are_visited_first() {
+        let graph = star_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let time_windows = TimeWindows::new();
+        let mandatory_nodes = vec![2].into_iter().collect();
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(10.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_time_windows(&time_windows)
+        .with_mandatory_nodes(&mandatory_nodes);
+        let solution = ant.get_solution();
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 2, 0]));
+    }
+
+    #[test]
+    fn nodes_on_the_way_back_are_counted() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(3.0),
+                R64::from_inner(2.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 0, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        // without pheromones on (1, 2) the ant has to take the shortest path back from 1
+        let pheromones = Pheromones::new(graph.node_ids(), vec![(0, 1)], R64::from_inner(1.0));
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(10.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        );
+        let solution = ant.get_solution();
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 2, 0]));
+        assert_eq!(solution.length, 3.0);
+        assert_eq!(solution.visited_nodes, 3);
+        assert_eq!(solution.visited_with_val, 2);
+        assert_eq!(solution.val_sum, 5.0);
+    }
+
+    // the ant can only oscillate between 0 and 1 without a tabu window
+    #[test]
+    fn bidirectional_routes_are_closed_at_the_goal_point() {
+        // the directed ring 0 -> 1 -> 2 -> 3 -> 0
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![R64::zero(), R64::one(), R64::one(), R64::one()],
+            vec![
+                (0, 1, R64::one()),
+                (1, 2, R64::one()),
+                (2, 3, R64::one()),
+                (3, 0, R64::one()),
+            ],
+        )
+        .unwrap();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let solution = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(4.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_bidirectional(true)
+        .get_solution();
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 2, 3, 0]));
+        assert!(solution.solution.is_closed_tour(0));
+        assert_eq!(solution.length, 4.0);
+        assert_eq!(solution.visited_with_val, 3);
+        assert_eq!(solution.val_sum, 3.0);
+    }
+
+    fn oscillating_graph() -> MatrixGraph<usize, R64, R64> {
+        MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(3.0),
+                R64::from_inner(0.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 0, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 1, R64::from_inner(1.0)),
+                (2, 0, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn limited_solution(
+        max_time: f64,
+        vehicle_count: usize,
+        limits: ConstructionLimits,
+    ) -> AntSolution<usize, R64> {
+        let graph = oscillating_graph();
+        let pheromones = pheromones(&graph);
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(max_time),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_vehicle_count(vehicle_count)
+        .with_construction_limits(limits)
+        .get_solution()
+    }
+
+    #[test]
+    fn tabu_window_prevents_moving_straight_back() {
+        let oscillating = limited_solution(4.0, 1, ConstructionLimits::default());
+        assert_eq!(
+            oscillating.solution,
+            Solution::from_nodes(vec![0, 1, 0, 1, 0])
+        );
+
+        let limits = ConstructionLimits {
+            step_limit: None,
+            tabu_window: 1,
+        };
+        let solution = limited_solution(4.0, 1, limits);
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 2, 0]));
+        assert_eq!(solution.length, 3.0);
+        assert!(!solution.aborted);
+    }
+
+    #[test]
+    fn step_limit_aborts_the_construction() {
+        let limits = ConstructionLimits {
+            step_limit: Some(3),
+            tabu_window: 0,
+        };
+        let solution = limited_solution(10.0, 2, limits);
+
+        assert!(solution.aborted);
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0, 1, 0, 1, 0]));
+        assert_eq!(
+            solution.route_lengths,
+            vec![R64::from_inner(4.0), R64::zero()]
+        );
+    }
+
+    fn star_solution(max_time: f64, level: f64) -> AntSolution<usize, R64> {
+        let graph = star_graph();
+        let pheromones =
+            Pheromones::new(graph.node_ids(), graph.edge_ids(), R64::from_inner(level));
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(max_time),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .get_solution()
+    }
+
+    #[test]
+    fn missing_first_moves_are_reported() {
+        let solution = star_solution(5.0, 1.0);
+
+        assert_eq!(solution.solution, Solution::from_nodes(vec![0]));
+        assert_eq!(solution.length, 0.0);
+        assert_eq!(
+            solution.warning,
+            Some(ConstructionWarning::NoFeasibleMove(0))
+        );
+        assert_eq!(star_solution(10.0, 1.0).warning, None);
+    }
+
+    #[test]
+    fn zero_pheromones_still_select_a_candidate() {
+        let solution = star_solution(10.0, 0.0);
+
+        assert_eq!(solution.solution.nodes().len(), 3);
+        assert_eq!(solution.length, 10.0);
+    }
+
+    /// Returns the lengths of 20 return paths sampled from node 1, which can return directly
+    /// or via node 2, where the edges of the detour have the pheromone level detour_level.
+    fn sampled_return_lengths(detour_level: f64, tail_length: f64) -> HashSet<R64> {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![R64::zero(); 3],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 0, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 0, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        let mut pheromones = pheromones(&graph);
+        for edge in [(1, 2), (2, 0)] {
+            *pheromones.edge_weight_mut(edge).unwrap() = R64::from_inner(detour_level);
+        }
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        let return_paths = return_paths(&graph, 0, 2);
+        let graph = RefCell::new(graph);
+        let (sender, _receiver) = mpsc::channel();
+        let ant = Ant::new(
+            &graph,
+            &pheromones,
+            0,
+            R64::from_inner(4.0),
+            &nw,
+            0,
+            1.0,
+            1.0,
+            1.0,
+            sender,
+            1,
+            &inv_shortest_paths,
+            &return_costs,
+        )
+        .with_return_paths(&return_paths);
+
+        let mut rng = rng64(0);
+        (0..20)
+            .map(|_| {
+                ant.return_path(
+                    &Tour::new(0, 0, 1),
+                    1,
+                    R64::from_inner(tail_length),
+                    R64::zero(),
+                    &mut rng,
+                )
+                .1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn return_paths_are_sampled_within_the_budget() {
+        assert_eq!(
+            sampled_return_lengths(1.0, 1.0).len(),
+            2,
+            "both paths fit into the budget"
+        );
+        assert_eq!(
+            sampled_return_lengths(1.0, 2.5),
+            HashSet::from([R64::from_inner(1.0)])
+        );
+    }
+
+    #[test]
+    fn return_paths_are_weighted_by_pheromone() {
+        assert_eq!(
+            sampled_return_lengths(0.0, 1.0),
+            HashSet::from([R64::from_inner(1.0)]),
+            "the detour has no pheromone"
+        );
+        assert_eq!(sampled_return_lengths(100.0, 1.0).len(), 2);
+    }
 }
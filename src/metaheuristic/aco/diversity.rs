@@ -0,0 +1,181 @@
+use crate::graph::Edge;
+use crate::metaheuristic::aco::Pheromones;
+use crate::metaheuristic::Solution;
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Fraction of the pheromone range, above which an edge counts as a branch in branching_factor.
+const BRANCHING_LAMBDA: f64 = 0.05;
+
+/// Stagnation indicators of one iteration. Low entropy, a branching factor close to 1 and a high
+/// edge overlap all mean, that the ants keep constructing (almost) the same solution.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Diversity {
+    pub pheromone_entropy: f64,
+    pub branching_factor: f64,
+    pub edge_overlap: f64,
+}
+
+impl Diversity {
+    pub fn new<'s, IndexType>(
+        pheromone_matrix: &Pheromones<IndexType>,
+        solutions: impl Iterator<Item = &'s Solution<IndexType>>,
+    ) -> Self
+    where
+        IndexType: 's + Copy + Hash + Eq + Debug + Display + Ord,
+    {
+        Diversity {
+            pheromone_entropy: pheromone_entropy(pheromone_matrix),
+            branching_factor: branching_factor(pheromone_matrix, BRANCHING_LAMBDA),
+            edge_overlap: edge_overlap(solutions),
+        }
+    }
+}
+
+/// Returns the pheromone levels of all outgoing edges for every node with at least one.
+fn outgoing_levels<IndexType>(pheromone_matrix: &Pheromones<IndexType>) -> Vec<Vec<f64>>
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    pheromone_matrix
+        .iter_node_ids()
+        .map(|node| {
+            pheromone_matrix
+                .iter_neighbors(node)
+                .unwrap()
+                .map(|(_, level)| level.into_inner())
+                .collect::<Vec<_>>()
+        })
+        .filter(|levels| !levels.is_empty())
+        .collect()
+}
+
+/// Returns the average entropy of the outgoing pheromone distribution of each node, normalized
+/// by the maximal entropy for its degree. 1 means uniform pheromones, 0 means all pheromone is
+/// on a single edge per node.
+pub fn pheromone_entropy<IndexType>(pheromone_matrix: &Pheromones<IndexType>) -> f64
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    let entropies: Vec<f64> = outgoing_levels(pheromone_matrix)
+        .into_iter()
+        .filter(|levels| levels.len() > 1)
+        .map(|levels| {
+            let sum: f64 = levels.iter().sum();
+            let entropy: f64 = levels
+                .iter()
+                .filter(|&&level| level > 0.0)
+                .map(|level| -(level / sum) * (level / sum).ln())
+                .sum();
+            entropy / (levels.len() as f64).ln()
+        })
+        .collect();
+
+    if entropies.is_empty() {
+        0.0
+    } else {
+        entropies.iter().sum::<f64>() / entropies.len() as f64
+    }
+}
+
+/// Returns the average lambda branching factor, which is the amount of outgoing edges of a node
+/// with a pheromone level of at least min + lambda * (max - min).
+pub fn branching_factor<IndexType>(pheromone_matrix: &Pheromones<IndexType>, lambda: f64) -> f64
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    let levels = outgoing_levels(pheromone_matrix);
+    if levels.is_empty() {
+        return 0.0;
+    }
+
+    let branches: usize = levels
+        .iter()
+        .map(|levels| {
+            let min = levels.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = levels.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let threshold = min + lambda * (max - min);
+            levels.iter().filter(|&&level| level >= threshold).count()
+        })
+        .sum();
+
+    branches as f64 / levels.len() as f64
+}
+
+/// Returns the average jaccard similarity of the edge sets of all pairs of solutions.
+/// 1 means all solutions use exactly the same edges.
+pub fn edge_overlap<'s, IndexType>(solutions: impl Iterator<Item = &'s Solution<IndexType>>) -> f64
+where
+    IndexType: 's + Copy + Hash + Eq,
+{
+    let edge_sets: Vec<HashSet<Edge<IndexType>>> = solutions
+        .map(|solution| {
+            solution
+                .iter_edges()
+                .map(|(from, to)| (*from, *to))
+                .collect()
+        })
+        .collect();
+
+    let mut overlap_sum = 0.0;
+    let mut pairs = 0;
+    for (i, a) in edge_sets.iter().enumerate() {
+        for b in edge_sets.iter().skip(i + 1) {
+            let union = a.union(b).count();
+            if union > 0 {
+                overlap_sum += a.intersection(b).count() as f64 / union as f64;
+            }
+            pairs += 1;
+        }
+    }
+
+    if pairs == 0 {
+        0.0
+    } else {
+        overlap_sum / pairs as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decorum::R64;
+
+    fn pheromones(levels: Vec<f64>) -> Pheromones<usize> {
+        let mut pheromones =
+            Pheromones::new(vec![0, 1, 2], vec![(0, 1), (0, 2)], R64::from_inner(0.0));
+        let _res = pheromones.change_edge((0, 1), R64::from_inner(levels[0]));
+        let _res = pheromones.change_edge((0, 2), R64::from_inner(levels[1]));
+        pheromones
+    }
+
+    #[test]
+    fn uniform_pheromones_have_full_entropy() {
+        let matrix = pheromones(vec![1.0, 1.0]);
+
+        assert!((pheromone_entropy(&matrix) - 1.0).abs() < 1e-12);
+        assert_eq!(branching_factor(&matrix, 0.05), 2.0);
+    }
+
+    #[test]
+    fn converged_pheromones_have_low_entropy() {
+        let matrix = pheromones(vec![1.0, 0.0]);
+
+        assert_eq!(pheromone_entropy(&matrix), 0.0);
+        assert_eq!(branching_factor(&matrix, 0.05), 1.0);
+    }
+
+    #[test]
+    fn edge_overlap_works() {
+        let a = Solution::from_nodes(vec![0, 1, 0]);
+        let b = Solution::from_nodes(vec![0, 2, 0]);
+        let c = Solution::from_nodes(vec![0, 1, 2, 0]);
+
+        assert_eq!(edge_overlap(vec![&a, &a].into_iter()), 1.0);
+        assert_eq!(edge_overlap(vec![&a, &b].into_iter()), 0.0);
+        assert_eq!(edge_overlap(vec![&a, &c].into_iter()), 0.25);
+        assert_eq!(edge_overlap(vec![&a].into_iter()), 0.0);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::graph::export::{Locate, Svg};
+use crate::graph::layout::Layout;
+use crate::metaheuristic::aco::Pheromones;
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::io;
+use std::io::Write;
+
+/// Draws the pheromone levels as heat overlay on the positions of their nodes, so the trails
+/// the ants converge to stand out. Nodes without coordinates are placed by layout.
+pub fn export_heatmap<IndexType, W: Write>(
+    pheromone_matrix: &Pheromones<IndexType>,
+    layout: Option<&Layout>,
+    svg: &Svg,
+    writer: W,
+) -> io::Result<()>
+where
+    IndexType: Locate + Copy + Hash + Eq + Debug + Display + Ord,
+{
+    let ids: Vec<IndexType> = pheromone_matrix.iter_node_ids().collect();
+    let points = IndexType::locate(&ids, layout).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Pheromone heatmaps need nodes with positions.",
+        )
+    })?;
+    let index: HashMap<IndexType, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let levels = ids
+        .iter()
+        .flat_map(|&from| {
+            pheromone_matrix
+                .iter_neighbors(from)
+                .unwrap()
+                .map(move |(to, level)| (from, to, level.into_inner()))
+        })
+        .map(|(from, to, level)| (index[&from], index[&to], level))
+        .collect();
+
+    svg.heatmap(points, levels, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::GeoPoint;
+    use crate::graph::layout;
+    use decorum::R64;
+
+    #[test]
+    fn pheromones_are_drawn_on_the_locations() {
+        let a = GeoPoint::from_degrees(52.0, 13.0);
+        let b = GeoPoint::from_degrees(52.1, 13.1);
+        let pheromones = Pheromones::new(vec![a, b], vec![(a, b), (b, a)], R64::from_inner(1.0));
+        let mut out = Vec::new();
+        export_heatmap(&pheromones, None, &Svg::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().matches("<path").count(), 2);
+
+        // generated graphs are drawn with their layout, but need one
+        let pheromones = Pheromones::new(vec![0, 1], vec![(0, 1)], R64::from_inner(1.0));
+        assert!(export_heatmap(&pheromones, None, &Svg::default(), Vec::new()).is_err());
+        let mut out = Vec::new();
+        let layout = layout::grid((2, 1));
+        export_heatmap(&pheromones, Some(&layout), &Svg::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().matches("<path").count(), 1);
+    }
+}
@@ -1,13 +1,16 @@
+use crate::metaheuristic::aco::{Diversity, PheromoneChange};
 use crate::metaheuristic::supervisor;
 use crate::metaheuristic::supervisor::MessageInfo;
 
 use decorum::R64;
-use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::time::Duration;
 
+/// Sent by every ant and, as ant 0, by the algorithm itself once per iteration. The supervisor
+/// aggregates them into a Record per aggregation window.
 #[derive(Debug)]
 pub struct Message<Nw, Ew> {
     pub ant_id: usize,
+    /// Unused, the supervisor counts iterations by the messages of the algorithm.
     pub iteration: usize,
     pub evaluations: usize,
     pub cpu_time: Duration,
@@ -19,9 +22,27 @@ pub struct Message<Nw, Ew> {
     pub visited_nodes: usize,
     pub visited_nodes_with_val: usize,
     pub collected_val: Nw,
+    pub routes: usize,
+    pub longest_route: Ew,
+    pub diversity: Diversity,
+    /// Change of the pheromone levels during the iteration.
+    pub pheromone_change: PheromoneChange,
+    /// Constructions, which were aborted at the step limit.
+    pub aborted: usize,
+    /// Ant solutions of the iteration, which repeated a route of another ant.
+    pub duplicates: usize,
+    /// Consumption of the secondary budget, 0 for instances without one.
+    pub secondary_resource: f64,
+    /// Ant solutions of the iteration, which were discarded as infeasible.
+    pub infeasible: usize,
+    /// Infeasible ant solutions of the iteration, which exceeded the budget. Solutions can also
+    /// be infeasible for not closing their tour or exceeding the secondary budget.
+    pub over_budget: usize,
+    /// Summed amount, by which the over budget ant solutions exceeded the budget.
+    pub overshoot: f64,
 }
 
-impl<Nw, Ew> Message<Nw, Ew> {
+impl<Nw, Ew: Copy> Message<Nw, Ew> {
     pub fn new(
         ant_id: usize,
         iteration: usize,
@@ -49,9 +70,71 @@ impl<Nw, Ew> Message<Nw, Ew> {
             visited_nodes,
             visited_nodes_with_val,
             collected_val,
+            routes: 1,
+            longest_route: distance,
+            diversity: Diversity::default(),
+            pheromone_change: PheromoneChange::default(),
+            aborted: 0,
+            duplicates: 0,
+            secondary_resource: 0.0,
+            infeasible: 0,
+            over_budget: 0,
+            overshoot: 0.0,
         }
     }
 
+    /// Sets the amount of routes and the length of the longest one, for solutions with multiple routes.
+    pub fn with_routes(mut self, routes: usize, longest_route: Ew) -> Self {
+        self.routes = routes;
+        self.longest_route = longest_route;
+        self
+    }
+
+    /// Attaches the stagnation indicators of the iteration, only sent by the algorithm itself.
+    pub fn with_diversity(mut self, diversity: Diversity) -> Self {
+        self.diversity = diversity;
+        self
+    }
+
+    /// Attaches the change of the pheromone levels during the iteration, only sent by the
+    /// algorithm itself.
+    pub fn with_pheromone_change(mut self, pheromone_change: PheromoneChange) -> Self {
+        self.pheromone_change = pheromone_change;
+        self
+    }
+
+    /// Sets the amount of duplicated ant solutions, only sent by the algorithm itself.
+    pub fn with_duplicates(mut self, duplicates: usize) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Sets the amount of infeasible ant solutions, the amount of those exceeding the budget and
+    /// their summed overshoot of it, only sent by the algorithm itself.
+    pub fn with_infeasible(
+        mut self,
+        infeasible: usize,
+        over_budget: usize,
+        overshoot: f64,
+    ) -> Self {
+        self.infeasible = infeasible;
+        self.over_budget = over_budget;
+        self.overshoot = overshoot;
+        self
+    }
+
+    /// Sets the consumption of the secondary budget of the solution.
+    pub fn with_secondary_resource(mut self, secondary_resource: f64) -> Self {
+        self.secondary_resource = secondary_resource;
+        self
+    }
+
+    /// Marks the construction of the sending ant as aborted at the step limit.
+    pub fn with_aborted(mut self, aborted: bool) -> Self {
+        self.aborted = usize::from(aborted);
+        self
+    }
+
     pub fn from_info(ant_id: usize, iteration: usize, info: MessageInfo<Nw, Ew>) -> Self {
         Self {
             ant_id,
@@ -66,6 +149,16 @@ impl<Nw, Ew> Message<Nw, Ew> {
             visited_nodes: info.visited_nodes,
             visited_nodes_with_val: info.visited_nodes_with_val,
             collected_val: info.collected_val,
+            routes: info.routes,
+            longest_route: info.longest_route,
+            diversity: Diversity::default(),
+            pheromone_change: PheromoneChange::default(),
+            aborted: 0,
+            duplicates: 0,
+            secondary_resource: info.secondary_resource,
+            infeasible: 0,
+            over_budget: 0,
+            overshoot: 0.0,
         }
     }
 
@@ -91,43 +184,18 @@ impl<Nw: Copy, Ew: Copy> supervisor::Message for Message<Nw, Ew> {
             self.visited_nodes_with_val,
             self.collected_val,
         )
+        .with_routes(self.routes, self.longest_route)
+        .with_secondary_resource(self.secondary_resource)
     }
-}
 
-impl<Nw: Serialize, Ew: Serialize> Serialize for Message<Nw, Ew> {
-    default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 12 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 8)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance)?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val)?;
-        state.end()
+    /// Ants don't know the iteration they belong to, so messages always count towards the
+    /// iteration in progress.
+    fn iteration(&self) -> Option<usize> {
+        None
     }
-}
 
-impl Serialize for Message<R64, R64> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 12 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 8)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance.into_inner())?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val.into_inner())?;
-        state.end()
+    /// All ants finish before the algorithm sends its own message as ant 0.
+    fn completes_iteration(&self) -> bool {
+        self.ant_id == 0
     }
 }
@@ -1,5 +1,6 @@
+use crate::metaheuristic::aco::ConstructionLimits;
 use crate::metaheuristic::{Heuristic, Solution};
-use crate::rng::os_random_seed;
+use crate::rng::{os_random_seed, RngKind};
 
 use std::collections::BTreeMap;
 
@@ -9,7 +10,15 @@ pub struct Params<'a, IndexType, Nw, Ew> {
     pub beta: f64,
     pub rho: f64,
     pub q_0: f64,
+    /// Pheromone level of every edge before the first iteration.
+    pub initial_pheromone: f64,
     pub seed: u128,
+    pub rng: RngKind,
+    pub construction_limits: ConstructionLimits,
+    /// Lets the ants grow their routes at both ends instead of walking them from the goal point.
+    pub bidirectional: bool,
+    /// Amount of shortest paths back to the goal point, among which the ants choose.
+    pub return_paths: usize,
     pub ant_count: usize,
     pub inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
 }
@@ -31,9 +40,47 @@ impl<'a, IndexType, Nw, Ew> Params<'a, IndexType, Nw, Ew> {
             beta,
             rho,
             q_0,
+            initial_pheromone: 1.0,
             seed: seed.unwrap_or_else(os_random_seed),
+            rng: RngKind::default(),
+            construction_limits: ConstructionLimits::default(),
+            bidirectional: false,
+            return_paths: 1,
             ant_count,
             inv_shortest_paths,
         }
     }
+
+    /// Draws the random numbers of the algorithm with kind instead of pcg.
+    pub fn with_rng(mut self, kind: RngKind) -> Self {
+        self.rng = kind;
+        self
+    }
+
+    /// Caps the moves of each ant and keeps them from moving straight back to recently left nodes.
+    pub fn with_construction_limits(mut self, limits: ConstructionLimits) -> Self {
+        self.construction_limits = limits;
+        self
+    }
+
+    /// Lets the ants construct their routes from both ends, joining them by the shortest path
+    /// between them once neither end can grow within the time budget.
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    /// Lets the ants return along any of the k shortest paths to the goal point, which still
+    /// fits into the time budget, once they stop collecting. The paths are chosen by their
+    /// pheromone levels, 1 keeps the shortest path only.
+    pub fn with_return_paths(mut self, k: usize) -> Self {
+        self.return_paths = k;
+        self
+    }
+
+    /// Starts every edge with the pheromone level instead of 1.
+    pub fn with_initial_pheromone(mut self, level: f64) -> Self {
+        self.initial_pheromone = level;
+        self
+    }
 }
@@ -0,0 +1,436 @@
+use crate::graph::{Edge, GenericWeightedGraph, GraphError, MatrixGraph};
+
+use decorum::R64;
+use num_traits::Zero;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// Graphs with less than this fraction of all possible edges store their pheromones sparsely.
+const SPARSE_DENSITY: f64 = 0.25;
+
+/// Pheromone levels of the edges of a graph. Dense storage uses an adjacency matrix, which allows
+/// adding edges later on, sparse storage only keeps the edges it was created with.
+/// Both iterate nodes, neighbors and edges in the same order.
+pub enum Pheromones<IndexType: Clone> {
+    Dense(MatrixGraph<IndexType, (), R64>),
+    Sparse(SparsePheromones<IndexType>),
+}
+
+/// Pheromone levels stored in compressed sparse rows, the outgoing edges of the node with
+/// index i are at offsets[i]..offsets[i + 1] of targets and levels.
+pub struct SparsePheromones<IndexType> {
+    node_map: BTreeMap<IndexType, usize>,
+    node_ids: Vec<IndexType>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    levels: Vec<R64>,
+}
+
+impl<IndexType> Pheromones<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    /// Creates pheromones with the given level on all edges, choosing the storage by density.
+    pub fn new(nodes: Vec<IndexType>, edges: Vec<Edge<IndexType>>, level: R64) -> Self {
+        let order = nodes.len() as f64;
+        if (edges.len() as f64) < SPARSE_DENSITY * order * order {
+            Self::sparse(nodes, edges, level)
+        } else {
+            Self::dense(nodes, edges, level)
+        }
+    }
+
+    pub fn dense(nodes: Vec<IndexType>, edges: Vec<Edge<IndexType>>, level: R64) -> Self {
+        Pheromones::Dense(
+            MatrixGraph::new(
+                nodes.into_iter().map(|id| (id, ())).collect(),
+                edges.into_iter().map(|edge| (edge, level)).collect(),
+            )
+            .unwrap(),
+        )
+    }
+
+    pub fn sparse(nodes: Vec<IndexType>, edges: Vec<Edge<IndexType>>, level: R64) -> Self {
+        Pheromones::Sparse(SparsePheromones::new(nodes, edges, level))
+    }
+
+    /// Returns the number of nodes.
+    pub fn order(&self) -> usize {
+        match self {
+            Self::Dense(matrix) => matrix.order(),
+            Self::Sparse(sparse) => sparse.node_ids.len(),
+        }
+    }
+
+    pub fn iter_node_ids(&self) -> Box<dyn Iterator<Item = IndexType> + '_> {
+        match self {
+            Self::Dense(matrix) => matrix.iter_node_ids(),
+            Self::Sparse(sparse) => Box::new(sparse.node_ids.iter().copied()),
+        }
+    }
+
+    pub fn iter_neighbor_ids(
+        &self,
+        id: IndexType,
+    ) -> Result<impl Iterator<Item = IndexType> + '_, GraphError<IndexType>> {
+        Ok(self.iter_neighbors(id)?.map(|(id, _)| id))
+    }
+
+    /// Returns an iterator over the neighbor ids with the pheromone level on the edge to them.
+    /// Ants call this for every step, so neither storage boxes the iterator.
+    pub fn iter_neighbors(
+        &self,
+        id: IndexType,
+    ) -> Result<impl Iterator<Item = (IndexType, &R64)> + '_, GraphError<IndexType>> {
+        Ok(match self {
+            Self::Dense(matrix) => Storage::Dense(matrix.iter_neighbors(id)?),
+            Self::Sparse(sparse) => {
+                let row = sparse.row(id)?;
+                Storage::Sparse(
+                    sparse.targets[row.clone()]
+                        .iter()
+                        .zip(sparse.levels[row].iter())
+                        .map(move |(&to, level)| (sparse.node_ids[to], level)),
+                )
+            }
+        })
+    }
+
+    pub fn edge_ids(&self) -> Vec<Edge<IndexType>> {
+        match self {
+            Self::Dense(matrix) => matrix.edge_ids(),
+            Self::Sparse(sparse) => (0..sparse.node_ids.len())
+                .flat_map(|from| {
+                    sparse.targets[sparse.offsets[from]..sparse.offsets[from + 1]]
+                        .iter()
+                        .map(move |&to| (sparse.node_ids[from], sparse.node_ids[to]))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn edge_weight(&self, edge: Edge<IndexType>) -> Result<&R64, GraphError<IndexType>> {
+        match self {
+            Self::Dense(matrix) => matrix.edge_weight(edge),
+            Self::Sparse(sparse) => sparse.position(edge).map(|i| &sparse.levels[i]),
+        }
+    }
+
+    pub fn edge_weight_mut(
+        &mut self,
+        edge: Edge<IndexType>,
+    ) -> Result<&mut R64, GraphError<IndexType>> {
+        match self {
+            Self::Dense(matrix) => matrix.edge_weight_mut(edge),
+            Self::Sparse(sparse) => sparse.position(edge).map(move |i| &mut sparse.levels[i]),
+        }
+    }
+
+    /// Calls update with every edge and its pheromone level in a single pass over the storage.
+    pub fn update_edges<F>(&mut self, mut update: F)
+    where
+        F: FnMut(Edge<IndexType>, &mut R64),
+    {
+        match self {
+            Self::Dense(matrix) => matrix.update_edges(&mut update),
+            Self::Sparse(sparse) => {
+                for from in 0..sparse.node_ids.len() {
+                    let row = sparse.offsets[from]..sparse.offsets[from + 1];
+                    for (&to, level) in sparse.targets[row.clone()]
+                        .iter()
+                        .zip(sparse.levels[row].iter_mut())
+                    {
+                        update((sparse.node_ids[from], sparse.node_ids[to]), level);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Evaporates the fraction rho of the pheromones on all edges and returns the evaporated
+    /// amount. The levels are multiplied in place, without looking up any edge.
+    pub fn decay(&mut self, rho: f64) -> R64 {
+        let persistence = R64::from_inner(1.0 - rho);
+        let mut evaporated = R64::zero();
+        self.for_each_level(|level| {
+            let after_decay = persistence * *level;
+            evaporated += *level - after_decay;
+            *level = after_decay;
+        });
+        evaporated
+    }
+
+    /// Fits the pheromone levels of all edges into the interval [min, max].
+    pub fn clamp(&mut self, min: R64, max: R64) {
+        self.for_each_level(|level| {
+            if *level < min {
+                *level = min;
+            } else if *level > max {
+                *level = max;
+            }
+        });
+    }
+
+    /// Returns the current levels of all edges, to measure the change of later levels with
+    /// change_since.
+    pub fn snapshot(&self) -> PheromoneSnapshot {
+        PheromoneSnapshot(self.iter_levels().copied().collect())
+    }
+
+    /// Returns the L1 and L∞ distance between the current levels and snapshot, or None if edges
+    /// were added or removed since the snapshot was taken.
+    pub fn change_since(&self, snapshot: &PheromoneSnapshot) -> Option<PheromoneChange> {
+        if self.iter_levels().count() != snapshot.0.len() {
+            return None;
+        }
+        Some(
+            self.iter_levels()
+                .zip(snapshot.0.iter())
+                .map(|(level, before)| (level.into_inner() - before.into_inner()).abs())
+                .fold(PheromoneChange::default(), |change, difference| {
+                    PheromoneChange {
+                        l1: change.l1 + difference,
+                        max: change.max.max(difference),
+                    }
+                }),
+        )
+    }
+
+    fn iter_levels(&self) -> impl Iterator<Item = &R64> + '_ {
+        match self {
+            Self::Dense(matrix) => {
+                Storage::Dense(matrix.adjacency_matrix.iter().flatten().flatten())
+            }
+            Self::Sparse(sparse) => Storage::Sparse(sparse.levels.iter()),
+        }
+    }
+
+    fn for_each_level<F: FnMut(&mut R64)>(&mut self, f: F) {
+        match self {
+            Self::Dense(matrix) => matrix
+                .adjacency_matrix
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .for_each(f),
+            Self::Sparse(sparse) => sparse.levels.iter_mut().for_each(f),
+        }
+    }
+
+    /// Changes the pheromone level of edge. Sparse pheromones can't add new edges and return
+    /// MissingEdge instead.
+    pub fn change_edge(
+        &mut self,
+        edge: Edge<IndexType>,
+        level: R64,
+    ) -> Result<(), GraphError<IndexType>> {
+        match self {
+            Self::Dense(matrix) => matrix.change_edge(edge, level),
+            Self::Sparse(sparse) => {
+                let i = sparse.position(edge)?;
+                sparse.levels[i] = level;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Pheromone levels of all edges at one point of a run, in the order of their storage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PheromoneSnapshot(Vec<R64>);
+
+/// How much the pheromone levels changed between two points of a run. The change approaches
+/// zero while a run converges, so it can be plotted alongside the best scores.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PheromoneChange {
+    /// Sum of the absolute changes of all edges.
+    pub l1: f64,
+    /// Largest absolute change of a single edge.
+    pub max: f64,
+}
+
+/// An iterator over either storage of the pheromones.
+enum Storage<D, S> {
+    Dense(D),
+    Sparse(S),
+}
+
+impl<D, S, T> Iterator for Storage<D, S>
+where
+    D: Iterator<Item = T>,
+    S: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Storage::Dense(iterator) => iterator.next(),
+            Storage::Sparse(iterator) => iterator.next(),
+        }
+    }
+}
+
+impl<IndexType> SparsePheromones<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Debug + Display + Ord,
+{
+    fn new(node_ids: Vec<IndexType>, edges: Vec<Edge<IndexType>>, level: R64) -> Self {
+        let node_map: BTreeMap<IndexType, usize> = node_ids
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, i))
+            .collect();
+        let mut mapped_edges: Vec<(usize, usize)> = edges
+            .iter()
+            .map(|(from, to)| (node_map[from], node_map[to]))
+            .collect();
+        mapped_edges.sort_unstable();
+        mapped_edges.dedup();
+
+        let mut offsets = vec![0; node_ids.len() + 1];
+        for &(from, _) in mapped_edges.iter() {
+            offsets[from + 1] += 1;
+        }
+        for i in 0..node_ids.len() {
+            offsets[i + 1] += offsets[i];
+        }
+
+        SparsePheromones {
+            node_map,
+            node_ids,
+            offsets,
+            levels: vec![level; mapped_edges.len()],
+            targets: mapped_edges.into_iter().map(|(_, to)| to).collect(),
+        }
+    }
+
+    /// Returns the range of the outgoing edges of node.
+    fn row(&self, node: IndexType) -> Result<std::ops::Range<usize>, GraphError<IndexType>> {
+        match self.node_map.get(&node) {
+            Some(&i) => Ok(self.offsets[i]..self.offsets[i + 1]),
+            None => Err(GraphError::MissingNode(node)),
+        }
+    }
+
+    /// Returns the position of edge in targets and levels.
+    fn position(&self, (from, to): Edge<IndexType>) -> Result<usize, GraphError<IndexType>> {
+        let row = self.row(from)?;
+        let to_index = *self.node_map.get(&to).ok_or(GraphError::MissingNode(to))?;
+        self.targets[row.clone()]
+            .binary_search(&to_index)
+            .map(|i| row.start + i)
+            .map_err(|_| GraphError::MissingEdge((from, to)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn both(level: f64) -> Vec<Pheromones<usize>> {
+        let nodes = vec![0, 1, 2, 3];
+        let edges = vec![(0, 2), (0, 1), (1, 0), (2, 3), (3, 0)];
+        vec![
+            Pheromones::dense(nodes.clone(), edges.clone(), R64::from_inner(level)),
+            Pheromones::sparse(nodes, edges, R64::from_inner(level)),
+        ]
+    }
+
+    #[test]
+    fn sparse_and_dense_storage_agree() {
+        let pheromones = both(1.0);
+        let (dense, sparse) = (&pheromones[0], &pheromones[1]);
+
+        assert_eq!(sparse.order(), dense.order());
+        assert_eq!(sparse.edge_ids(), dense.edge_ids());
+        assert_eq!(
+            sparse.iter_node_ids().collect::<Vec<_>>(),
+            dense.iter_node_ids().collect::<Vec<_>>()
+        );
+        for node in 0..4 {
+            assert_eq!(
+                sparse.iter_neighbors(node).unwrap().collect::<Vec<_>>(),
+                dense.iter_neighbors(node).unwrap().collect::<Vec<_>>()
+            );
+        }
+        assert!(sparse.iter_neighbor_ids(4).is_err());
+    }
+
+    #[test]
+    fn levels_can_be_changed() {
+        for mut pheromones in both(1.0) {
+            pheromones
+                .change_edge((2, 3), R64::from_inner(3.0))
+                .unwrap();
+
+            assert_eq!(*pheromones.edge_weight((2, 3)).unwrap(), 3.0);
+            assert_eq!(*pheromones.edge_weight((0, 1)).unwrap(), 1.0);
+            assert!(pheromones.edge_weight((3, 2)).is_err());
+        }
+    }
+
+    #[test]
+    fn levels_are_updated_in_place() {
+        for mut pheromones in both(1.0) {
+            *pheromones.edge_weight_mut((0, 1)).unwrap() += 1.0;
+            assert!(pheromones.edge_weight_mut((3, 2)).is_err());
+
+            let mut visited = Vec::new();
+            pheromones.update_edges(|edge, level| {
+                visited.push(edge);
+                *level *= 0.5;
+            });
+
+            assert_eq!(visited, pheromones.edge_ids());
+            assert_eq!(*pheromones.edge_weight((0, 1)).unwrap(), 1.0);
+            assert_eq!(*pheromones.edge_weight((2, 3)).unwrap(), 0.5);
+        }
+    }
+
+    #[test]
+    fn decay_evaporates_all_levels() {
+        for mut pheromones in both(2.0) {
+            assert_eq!(pheromones.decay(0.25), 2.5);
+            assert_eq!(*pheromones.edge_weight((0, 2)).unwrap(), 1.5);
+
+            pheromones.clamp(R64::from_inner(0.5), R64::from_inner(1.0));
+            assert_eq!(*pheromones.edge_weight((3, 0)).unwrap(), 1.0);
+        }
+    }
+
+    #[test]
+    fn changes_are_measured_against_snapshots() {
+        for mut pheromones in both(1.0) {
+            let snapshot = pheromones.snapshot();
+            assert_eq!(
+                pheromones.change_since(&snapshot),
+                Some(PheromoneChange::default())
+            );
+
+            pheromones.decay(0.5);
+            *pheromones.edge_weight_mut((2, 3)).unwrap() += 2.0;
+            let change = pheromones.change_since(&snapshot).unwrap();
+            assert_eq!(change.l1, 3.5);
+            assert_eq!(change.max, 1.5);
+        }
+
+        let mut dense = Pheromones::dense(vec![0, 1], vec![(0, 1)], R64::from_inner(1.0));
+        let snapshot = dense.snapshot();
+        dense.change_edge((1, 0), R64::from_inner(1.0)).unwrap();
+        assert_eq!(dense.change_since(&snapshot), None);
+    }
+
+    #[test]
+    fn storage_is_chosen_by_density() {
+        let sparse = Pheromones::new(
+            (0..10).collect(),
+            vec![(0, 1), (1, 0)],
+            R64::from_inner(1.0),
+        );
+        let dense = Pheromones::new(vec![0, 1], vec![(0, 1), (1, 0)], R64::from_inner(1.0));
+
+        assert!(matches!(sparse, Pheromones::Sparse(_)));
+        assert!(matches!(dense, Pheromones::Dense(_)));
+    }
+}
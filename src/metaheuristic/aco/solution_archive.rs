@@ -0,0 +1,109 @@
+use crate::metaheuristic::Solution;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Prefix tree over the node sequences of the solutions constructed in one iteration.
+/// Late in convergence many ants construct the same route, which only has to be scored once.
+/// Routes sharing their beginning share their path in the tree, so archiving a route only
+/// allocates nodes for the part, in which it differs from all routes seen so far.
+#[derive(Debug, Clone)]
+pub struct SolutionArchive<IndexType> {
+    // node 0 is the root, which stands for the empty route
+    children: Vec<HashMap<IndexType, usize>>,
+    terminal: Vec<bool>,
+    archived: usize,
+    duplicates: usize,
+}
+
+impl<IndexType> Default for SolutionArchive<IndexType> {
+    fn default() -> Self {
+        SolutionArchive {
+            children: vec![HashMap::new()],
+            terminal: vec![false],
+            archived: 0,
+            duplicates: 0,
+        }
+    }
+}
+
+impl<IndexType: Copy + Hash + Eq> SolutionArchive<IndexType> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Archives solution and returns true, or returns false if the same route already is in
+    /// the archive and counts it as duplicate.
+    pub fn insert(&mut self, solution: &Solution<IndexType>) -> bool {
+        let mut position = 0;
+        for &node in solution.iter_nodes() {
+            position = match self.children[position].get(&node) {
+                Some(&child) => child,
+                None => {
+                    let child = self.children.len();
+                    self.children.push(HashMap::new());
+                    self.terminal.push(false);
+                    self.children[position].insert(node, child);
+                    child
+                }
+            };
+        }
+
+        if self.terminal[position] {
+            self.duplicates += 1;
+            false
+        } else {
+            self.terminal[position] = true;
+            self.archived += 1;
+            true
+        }
+    }
+
+    /// Returns true if the same route as solution is in the archive, or false otherwise.
+    pub fn contains(&self, solution: &Solution<IndexType>) -> bool {
+        let mut position = 0;
+        for node in solution.iter_nodes() {
+            match self.children[position].get(node) {
+                Some(&child) => position = child,
+                None => return false,
+            }
+        }
+        self.terminal[position]
+    }
+
+    /// Returns the amount of distinct routes in the archive.
+    pub fn len(&self) -> usize {
+        self.archived
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archived == 0
+    }
+
+    /// Returns how many inserted solutions were duplicates of archived ones.
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_are_detected() {
+        let mut archive = SolutionArchive::new();
+        assert!(archive.insert(&Solution::from_nodes(vec![0, 1, 2, 0])));
+        assert!(archive.insert(&Solution::from_nodes(vec![0, 1, 0])));
+        assert!(!archive.insert(&Solution::from_nodes(vec![0, 1, 2, 0])));
+        assert!(archive.insert(&Solution::from_nodes(vec![0, 1])));
+
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.duplicates(), 1);
+        assert!(archive.contains(&Solution::from_nodes(vec![0, 1, 0])));
+        assert!(
+            !archive.contains(&Solution::from_nodes(vec![0, 1, 2])),
+            "prefixes of archived routes are not archived themselves"
+        );
+    }
+}
@@ -1,147 +1,329 @@
 use crate::metaheuristic::aco;
+use crate::metaheuristic::aco::{Diversity, PheromoneChange};
 use crate::metaheuristic::supervisor;
-use crate::metaheuristic::supervisor::{Message, MessageInfo};
+use crate::metaheuristic::supervisor::{Aggregate, Distribution, Message, MessageInfo, RunInfo};
 
-use csv::Writer;
-use serde::Serialize;
-use std::collections::HashMap;
-use std::fmt::Debug;
-use std::io::{stderr, Stderr, Write};
+use serde::{Deserialize, Serialize};
 use std::ops::Add;
-use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
 use std::time::Duration;
 
-pub struct Supervisor<W: Write, Nw: Serialize + Sized, Ew: Serialize + Sized> {
-    pub sender: Sender<aco::Message<Nw, Ew>>,
-    receiver: Receiver<aco::Message<Nw, Ew>>,
-    ants: usize,
-    messages: HashMap<usize, Vec<MessageInfo<Nw, Ew>>>,
-    counters: HashMap<usize, usize>,
-    aggregation_rate: usize,
-    writer: Writer<W>,
-}
+pub type Supervisor<W, Nw, Ew> = supervisor::Supervisor<W, aco::Message<Nw, Ew>, AntWindow<Nw, Ew>>;
 
-impl<W, Nw, Ew> Supervisor<W, Nw, Ew>
-where
-    W: Write,
-    Nw: Serialize + Default + Debug + Add<Output = Nw> + Copy,
-    Ew: Serialize + Default + Debug + Add<Output = Ew> + Copy,
-{
-    pub fn new(aggregation_rate: usize, writer: Writer<W>) -> Self {
-        let (tx, rx) = mpsc::channel();
-        Supervisor {
-            sender: tx,
-            receiver: rx,
-            ants: 0,
-            messages: HashMap::default(),
-            counters: HashMap::default(),
-            aggregation_rate,
-            writer,
-        }
-    }
+/// Row of an Aco, MMAco or Acs log. Evaluations and cpu time are summed over all ants of the
+/// aggregation window, improvements and the solution columns are summed over the best
+/// solutions of the algorithm.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Record {
+    pub algorithm: String,
+    pub heuristic: String,
+    pub run_id: String,
+    pub params: String,
+    /// First iteration of the aggregation window.
+    pub iteration: usize,
+    pub evals: usize,
+    pub improvements: usize,
+    pub distance: f64,
+    pub score: f64,
+    pub visited: usize,
+    pub visited_with_val: usize,
+    pub val_sum: f64,
+    pub cpu_time_us: u128,
+    pub routes: usize,
+    pub longest_route: f64,
+    /// Id of the ant with the highest score, 0 if there were no ants.
+    pub best_ant: usize,
+    pub score_min: f64,
+    pub score_median: f64,
+    pub score_max: f64,
+    pub score_std_dev: f64,
+    pub distance_min: f64,
+    pub distance_median: f64,
+    pub distance_max: f64,
+    pub distance_std_dev: f64,
+    pub pheromone_entropy: f64,
+    pub branching_factor: f64,
+    pub edge_overlap: f64,
+    /// Summed absolute change of all pheromone levels over the iterations of the window.
+    pub pheromone_l1_change: f64,
+    /// Largest change of a single pheromone level in an iteration of the window.
+    pub pheromone_max_change: f64,
+    /// Ant constructions, which were aborted at the step limit.
+    pub aborted_constructions: usize,
+    /// Fraction of the ant solutions, which repeated a route of another ant in their iteration.
+    pub duplicate_ratio: f64,
+    /// Consumption of the secondary budget by the best solutions, next to their distance.
+    pub secondary_resource: f64,
+    /// Fraction of the ant solutions, which were discarded as infeasible. High ratios point to
+    /// heuristics or budgets, which don't fit the instance.
+    pub infeasible_ratio: f64,
+    /// Average amount, by which the ant solutions exceeding the budget exceeded it. Solutions,
+    /// which are infeasible for other reasons, are not part of the average.
+    pub mean_overshoot: f64,
+}
 
-    pub fn new_ant(&mut self) -> (Sender<aco::Message<Nw, Ew>>, usize) {
-        self.ants += 1;
-        let id = self.ants;
+impl supervisor::Record for Record {
+    const COLUMNS: &'static [&'static str] = &[
+        "algorithm",
+        "heuristic",
+        "run_id",
+        "params",
+        "iteration",
+        "evals",
+        "improvements",
+        "distance",
+        "score",
+        "visited",
+        "visited_with_val",
+        "val_sum",
+        "cpu_time_us",
+        "routes",
+        "longest_route",
+        "best_ant",
+        "score_min",
+        "score_median",
+        "score_max",
+        "score_std_dev",
+        "distance_min",
+        "distance_median",
+        "distance_max",
+        "distance_std_dev",
+        "pheromone_entropy",
+        "branching_factor",
+        "edge_overlap",
+        "pheromone_l1_change",
+        "pheromone_max_change",
+        "aborted_constructions",
+        "duplicate_ratio",
+        "secondary_resource",
+        "infeasible_ratio",
+        "mean_overshoot",
+    ];
+}
 
-        (self.sender.clone(), id)
-    }
+/// Statistics over the solutions of all ants, which were aggregated into one record.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AntStats {
+    /// Id of the ant with the highest score, 0 if there were no ants.
+    pub best_ant: usize,
+    pub score: Distribution,
+    pub distance: Distribution,
+}
 
-    pub fn aggregate_receive(&mut self) {
-        while let Ok(message) = self.receiver.recv_timeout(Duration::from_millis(1)) {
-            let ant_id = message.id();
-            let mut i = 0;
-            if let Some(count) = self.counters.get_mut(&ant_id) {
-                *count += 1;
-                i = *count;
-            } else {
-                self.counters.insert(ant_id, i);
-            }
+/// Messages of all ants and of the algorithm itself in one aggregation window.
+pub struct AntWindow<Nw, Ew> {
+    // summed up messages of the algorithm, which describe its best solutions
+    best: MessageInfo<Nw, Ew>,
+    evaluations: usize,
+    cpu_time: Duration,
+    // (ant id, score, distance) of every ant solution
+    ant_solutions: Vec<(usize, f64, f64)>,
+    // stagnation indicators sent by the algorithm, the last one is kept
+    diversity: Diversity,
+    // summed up and largest pheromone change over the iterations
+    pheromone_change: PheromoneChange,
+    aborted_constructions: usize,
+    duplicates: usize,
+    infeasible: usize,
+    over_budget: usize,
+    overshoot: f64,
+}
 
-            let idx = i / self.aggregation_rate;
-            if let Some(messages) = self.messages.get_mut(&ant_id) {
-                if idx >= messages.len() {
-                    messages.resize_with(idx + 1, Default::default);
-                }
-                messages[idx] += message.get_info();
-            } else {
-                self.messages.insert(ant_id, vec![message.get_info()]);
-            }
+impl<Nw: Default, Ew: Default> Default for AntWindow<Nw, Ew> {
+    fn default() -> Self {
+        AntWindow {
+            best: MessageInfo::default(),
+            evaluations: 0,
+            cpu_time: Duration::from_micros(0),
+            ant_solutions: Vec::new(),
+            diversity: Diversity::default(),
+            pheromone_change: PheromoneChange::default(),
+            aborted_constructions: 0,
+            duplicates: 0,
+            infeasible: 0,
+            over_budget: 0,
+            overshoot: 0.0,
         }
+    }
+}
 
-        for i in 0..self.messages.get(&0).unwrap().len() {
-            let best_msg = self.messages.get(&0).unwrap().get(i).unwrap();
-            let mut evals = 0;
-            let mut cpu_time = Duration::from_micros(0);
-            for ant_id in 1..self.messages.len() {
-                let messages = self.messages.get(&ant_id).unwrap();
-                let msg_info = messages.get(i).unwrap();
-                evals += msg_info.evaluations;
-                cpu_time += msg_info.cpu_time;
-            }
+impl<Nw, Ew> AntWindow<Nw, Ew> {
+    /// Calculates statistics over all ant solutions of the window.
+    pub fn ant_stats(&self) -> AntStats {
+        let scores: Vec<f64> = self
+            .ant_solutions
+            .iter()
+            .map(|(_, score, _)| *score)
+            .collect();
+        let distances: Vec<f64> = self
+            .ant_solutions
+            .iter()
+            .map(|(_, _, distance)| *distance)
+            .collect();
+        let best_ant = self
+            .ant_solutions
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(id, _, _)| *id);
 
-            let record = aco::Message::new(
-                0,
-                i * self.aggregation_rate,
-                evals,
-                best_msg.n_improvements,
-                best_msg.changes,
-                best_msg.phase,
-                cpu_time,
-                best_msg.distance,
-                best_msg.heuristic_score,
-                best_msg.visited_nodes,
-                best_msg.visited_nodes_with_val,
-                best_msg.collected_val,
-            );
-
-            let res = self.writer.serialize(&record);
-            if let Err(err) = res {
-                eprintln!("{:?}", err);
-            }
+        AntStats {
+            best_ant,
+            score: Distribution::of(&scores),
+            distance: Distribution::of(&distances),
         }
-
-        self.prepare_next();
     }
+}
 
-    pub fn prepare_next(&mut self) {
-        self.ants = 0;
+impl<Nw, Ew> Aggregate<aco::Message<Nw, Ew>> for AntWindow<Nw, Ew>
+where
+    Nw: Default + Copy + Add<Output = Nw> + Into<f64>,
+    Ew: Default + Copy + Add<Output = Ew> + Into<f64> + PartialOrd,
+{
+    type Record = Record;
+
+    fn add(&mut self, message: aco::Message<Nw, Ew>) {
+        if message.id() != 0 {
+            self.evaluations += message.evaluations;
+            self.cpu_time += message.cpu_time;
+            self.aborted_constructions += message.aborted;
+            self.ant_solutions.push((
+                message.id(),
+                message.heuristic_score.into_inner(),
+                message.distance.into(),
+            ));
+        } else {
+            self.diversity = message.diversity;
+            self.pheromone_change.l1 += message.pheromone_change.l1;
+            self.pheromone_change.max = self.pheromone_change.max.max(message.pheromone_change.max);
+            self.duplicates += message.duplicates;
+            self.infeasible += message.infeasible;
+            self.over_budget += message.over_budget;
+            self.overshoot += message.overshoot;
+            self.best += message.get_info();
+        }
     }
 
-    pub fn reset(&mut self) {
-        self.ants = 0;
-        self.messages = HashMap::default();
-        self.counters = HashMap::default();
-        let (tx, rx) = mpsc::channel();
-        self.sender = tx;
-        self.receiver = rx;
+    fn record(&self, iteration: usize, run: &RunInfo) -> Record {
+        let best = &self.best;
+        let stats = self.ant_stats();
+        Record {
+            algorithm: run.algorithm.clone(),
+            heuristic: run.heuristic.clone(),
+            run_id: run.run_id.clone(),
+            params: run.params.clone(),
+            iteration,
+            evals: self.evaluations,
+            improvements: best.n_improvements,
+            distance: best.distance.into(),
+            score: best.heuristic_score.into_inner(),
+            visited: best.visited_nodes,
+            visited_with_val: best.visited_nodes_with_val,
+            val_sum: best.collected_val.into(),
+            cpu_time_us: self.cpu_time.as_micros(),
+            routes: best.routes,
+            longest_route: best.longest_route.into(),
+            best_ant: stats.best_ant,
+            score_min: stats.score.min,
+            score_median: stats.score.median,
+            score_max: stats.score.max,
+            score_std_dev: stats.score.std_dev,
+            distance_min: stats.distance.min,
+            distance_median: stats.distance.median,
+            distance_max: stats.distance.max,
+            distance_std_dev: stats.distance.std_dev,
+            pheromone_entropy: self.diversity.pheromone_entropy,
+            branching_factor: self.diversity.branching_factor,
+            edge_overlap: self.diversity.edge_overlap,
+            pheromone_l1_change: self.pheromone_change.l1,
+            pheromone_max_change: self.pheromone_change.max,
+            aborted_constructions: self.aborted_constructions,
+            duplicate_ratio: if self.ant_solutions.is_empty() {
+                0.0
+            } else {
+                self.duplicates as f64 / self.ant_solutions.len() as f64
+            },
+            secondary_resource: best.secondary_resource,
+            infeasible_ratio: if self.ant_solutions.is_empty() {
+                0.0
+            } else {
+                self.infeasible as f64 / self.ant_solutions.len() as f64
+            },
+            mean_overshoot: if self.over_budget == 0 {
+                0.0
+            } else {
+                self.overshoot / self.over_budget as f64
+            },
+        }
     }
 }
 
-impl<W, Nw: Copy, Ew: Copy> supervisor::Supervisor<aco::Message<Nw, Ew>> for Supervisor<W, Nw, Ew>
-where
-    W: Write,
-    Nw: Serialize + Default + Add<Output = Nw>,
-    Ew: Serialize + Default + Add<Output = Ew>,
-{
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decorum::R64;
 
-impl<Nw, Ew> Default for Supervisor<Stderr, Nw, Ew>
-where
-    Nw: Serialize + Default + Add<Output = Nw>,
-    Ew: Serialize + Default + Add<Output = Ew>,
-{
-    fn default() -> Self {
-        let (tx, rx) = mpsc::channel();
-        Supervisor {
-            sender: tx,
-            receiver: rx,
-            ants: 0,
-            messages: HashMap::default(),
-            counters: HashMap::default(),
-            aggregation_rate: 1,
-            writer: Writer::from_writer(stderr()),
+    fn message(id: usize, score: f64, distance: f64) -> aco::Message<R64, R64> {
+        aco::Message::new(
+            id,
+            0,
+            1,
+            0,
+            0,
+            0,
+            Duration::from_micros(1),
+            R64::from_inner(distance),
+            R64::from_inner(score),
+            0,
+            0,
+            R64::from_inner(0.0),
+        )
+    }
+
+    #[test]
+    fn records_contain_ant_stats_and_diversity() {
+        let mut buffer = Vec::new();
+        {
+            let mut supervisor = Supervisor::new(1, &mut buffer);
+            for (i, (score, distance)) in [(1.0, 4.0), (5.0, 2.0), (3.0, 3.0)].iter().enumerate() {
+                let sender = supervisor.sender();
+                sender
+                    .send(message(i + 1, *score, *distance).with_aborted(i == 0))
+                    .unwrap();
+            }
+            let diversity = Diversity {
+                pheromone_entropy: 0.5,
+                branching_factor: 2.0,
+                edge_overlap: 0.25,
+            };
+            supervisor
+                .sender()
+                .send(
+                    message(0, 5.0, 2.0)
+                        .with_diversity(diversity)
+                        .with_pheromone_change(PheromoneChange { l1: 1.5, max: 0.5 })
+                        .with_duplicates(1)
+                        .with_infeasible(2, 1, 3.0),
+                )
+                .unwrap();
+            supervisor.aggregate_receive();
         }
+        let output = String::from_utf8(buffer).unwrap();
+        let mut lines = output.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let record: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let field = |name: &str| record[header.iter().position(|h| *h == name).unwrap()];
+
+        assert_eq!(field("evals"), "3");
+        assert_eq!(field("best_ant"), "2");
+        assert_eq!(field("score_min"), "1.0");
+        assert_eq!(field("score_median"), "3.0");
+        assert_eq!(field("score_max"), "5.0");
+        assert_eq!(field("distance_median"), "3.0");
+        assert_eq!(field("branching_factor"), "2.0");
+        assert_eq!(field("pheromone_l1_change"), "1.5");
+        assert_eq!(field("pheromone_max_change"), "0.5");
+        assert_eq!(field("aborted_constructions"), "1");
+        assert_eq!(field("duplicate_ratio").parse::<f64>().unwrap(), 1.0 / 3.0);
+        assert_eq!(field("infeasible_ratio").parse::<f64>().unwrap(), 2.0 / 3.0);
+        assert_eq!(field("mean_overshoot"), "3.0");
     }
 }
@@ -1,24 +1,29 @@
 mod ant;
 mod params;
 
-pub use ant::Ant;
+pub use ant::{Ant, LocalUpdate};
 pub use params::Params;
 
-use crate::graph::{GenericWeightedGraph, MatrixGraph};
-use crate::metaheuristic::aco::{Message, Supervisor};
+use crate::graph::{GenericWeightedGraph, StochasticEdges};
+use crate::metaheuristic::aco::{
+	deposit_pheromone, initial_pheromones, reprune_pheromones, ConstructionLimits, Diversity,
+	Message, Pheromones, SolutionArchive, Supervisor,
+};
+use crate::metaheuristic::supervisor::Phase;
 use crate::metaheuristic::{
-	solution_length, solution_score, Heuristic, Metaheuristic, ProblemInstance, Solution,
+	rescore, GoalPaths, Heuristic, IterationCallback, Metaheuristic, Objective, Observer,
+	ProblemInstance, Reoptimize, RevisitPenalty, SecondaryBudget, ServiceTimes, Solution,
+	TimeWindows,
 };
-use crate::rng::rng64;
+use crate::rng::{Rng, RngState};
 use crate::util::{Distance, SmallVal};
 
 use decorum::R64;
 use num_traits::identities::{One, Zero};
-use oorandom::Rand64;
 use serde::Serialize;
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 use std::cmp::{Eq, PartialEq};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::io::Write;
@@ -37,9 +42,9 @@ where
 			IndexType = IndexType,
 			NodeWeightType = Nw,
 			EdgeWeightType = Ew,
-		>,
+		> + 'a,
 	>,
-	pheromone_matrix: RefCell<MatrixGraph<IndexType, (), R64>>,
+	pheromone_matrix: RefCell<Pheromones<IndexType>>,
 	goal_point: IndexType,
 	max_time: Ew,
 	heuristic: &'a Heuristic<Nw, Ew>,
@@ -49,13 +54,27 @@ where
 	ant_count: usize,
 	q_0: f64,
 	t_0: f64,
-	avg_options: usize,
+	// level the pheromones start at, local updates move them towards t_0
+	initial_pheromone: R64,
+	vehicle_count: usize,
+	time_windows: TimeWindows<IndexType, Ew>,
+	mandatory_nodes: HashSet<IndexType>,
+	stochastic_edges: StochasticEdges<IndexType>,
+	revisit_penalty: RevisitPenalty,
+	service_times: ServiceTimes<IndexType, Ew>,
+	secondary_budget: Option<SecondaryBudget<IndexType, Ew>>,
+	objective: Objective,
 	best_solution: Solution<IndexType>,
 	best_score: R64,
 	best_length: Ew,
+	// length and score of every route of the best solution
+	best_routes: Vec<(Ew, R64)>,
+	best_missing: usize,
 	pub supervisor: Supervisor<W, Nw, Ew>,
-	rng: Rand64,
-	inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
+	observer: Observer<'a, IndexType, Ew>,
+	rng: Rng,
+	goal_paths: GoalPaths<IndexType>,
+	construction_limits: ConstructionLimits,
 }
 
 impl<'a, IndexType, Nw, W> Acs<'a, IndexType, Nw, R64, W>
@@ -64,29 +83,64 @@ where
 	Nw: Copy + Zero + PartialOrd + Serialize + SmallVal,
 	W: Write,
 {
-	fn pheromone_update(&mut self, solution: &Solution<IndexType>, solution_score: R64) {
-		let to_add = R64::one() - R64::one() / solution_score;
-		let mut pheromone_borrow = self.pheromone_matrix.borrow_mut();
-
-		// pheromone decay
-		for edge in pheromone_borrow.edge_ids() {
-			let weight = *pheromone_borrow.edge_weight(edge).unwrap();
-			let after_decay = R64::from_inner(1.0 - self.rho) * weight;
-			let _res = pheromone_borrow.change_edge(edge, after_decay);
+	/// Global pheromone update of ACS, which only moves the levels on the edges of the best
+	/// solution found so far towards 1 / length of that solution.
+	fn global_update(&mut self) {
+		if self.best_length == R64::zero() {
+			return;
 		}
-
-		// adding best solution
-		for (from, to) in solution.iter_unique_edges() {
-			let weight = *pheromone_borrow.edge_weight((*from, *to)).unwrap();
-			let _res = pheromone_borrow.change_edge((*from, *to), weight + to_add);
+		let deposit = R64::one() / self.best_length;
+		let rho = R64::from_inner(self.rho);
+		let mut pheromones = self.pheromone_matrix.borrow_mut();
+		for (&from, &to) in self.best_solution.iter_unique_edges() {
+			if let Ok(level) = pheromones.edge_weight_mut((from, to)) {
+				*level = (R64::one() - rho) * *level + rho * deposit;
+			}
 		}
 	}
 
+	/// Seeds the pheromone matrix with an initial solution, adding strength times the initial
+	/// pheromone level to each of its edges. Meant to be called before the first iteration.
+	pub fn warm_start(&mut self, solution: &Solution<IndexType>, strength: f64) {
+		deposit_pheromone(
+			&mut self.pheromone_matrix.borrow_mut(),
+			solution,
+			R64::from_inner(strength) * self.initial_pheromone,
+		);
+	}
+
 	pub fn set_inv_shortest_paths(
 		&mut self,
 		inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
 	) {
-		self.inv_shortest_paths = inv_shortest_paths
+		self.goal_paths.set_inv_shortest_paths(
+			&*self.graph.borrow(),
+			self.goal_point,
+			inv_shortest_paths,
+		);
+	}
+
+	/// Returns the state of the rng, from which the next iteration draws its random numbers.
+	pub fn rng_state(&self) -> RngState {
+		self.rng.state()
+	}
+
+	pub fn pheromones(&self) -> Ref<'_, Pheromones<IndexType>> {
+		self.pheromone_matrix.borrow()
+	}
+
+	pub fn current_solution(&self) -> (&Solution<IndexType>, R64, R64) {
+		(&self.best_solution, self.best_score, self.best_length)
+	}
+
+	/// Returns the length and score of every route of the best solution, a single route has the
+	/// length and score of the solution.
+	pub fn best_routes(&self) -> Vec<(R64, R64)> {
+		if self.vehicle_count == 1 {
+			vec![(self.best_length, self.best_score)]
+		} else {
+			self.best_routes.clone()
+		}
 	}
 }
 
@@ -104,15 +158,16 @@ where
 		supervisor: Self::SupervisorType,
 	) -> Self {
 		let graph = problem.graph.borrow();
-		let pheromones = RefCell::new(
-			MatrixGraph::new(
-				graph.iter_node_ids().map(|id| (id, ())).collect(),
-				graph.iter_edge_ids()
-					.map(|edge| (edge, R64::from_inner(1.0)))
-					.collect(),
-			)
-			.unwrap(),
-		);
+		let initial_pheromone = R64::one();
+		// sampled routes can be shorter than the shortest paths, so stochastic edges prevent pruning
+		let pheromones = RefCell::new(initial_pheromones(
+			&*graph,
+			problem.goal_point,
+			problem.max_time,
+			&params.inv_shortest_paths,
+			problem.stochastic_edges.variances.is_empty(),
+			initial_pheromone,
+		));
 
 		Acs {
 			graph: problem.graph,
@@ -126,42 +181,83 @@ where
 			ant_count: params.ant_count,
 			q_0: params.q_0,
 			t_0: params.t_0,
-			avg_options: graph.order() / 2,
+			initial_pheromone,
+			vehicle_count: problem.vehicle_count,
+			time_windows: problem.time_windows,
+			best_missing: problem.mandatory_nodes.len(),
+			mandatory_nodes: problem.mandatory_nodes,
+			stochastic_edges: problem.stochastic_edges,
+			revisit_penalty: problem.revisit_penalty,
+			service_times: problem.service_times,
+			secondary_budget: problem.secondary_budget,
+			objective: problem.objective,
 			best_solution: Solution::new(),
-			best_score: R64::one(),
+			best_score: R64::zero(),
 			best_length: R64::zero(),
+			best_routes: Vec::new(),
 			supervisor,
-			rng: rng64(params.seed),
-			inv_shortest_paths: params.inv_shortest_paths,
+			observer: Observer::default(),
+			rng: Rng::new(params.rng, params.seed),
+			goal_paths: GoalPaths::new(
+				&*problem.graph.borrow(),
+				problem.goal_point,
+				params.inv_shortest_paths,
+				problem.return_costs.into_inner(),
+				params.return_paths,
+			),
+			construction_limits: params.construction_limits,
 		}
 	}
 
 	fn single_iteration(&mut self) -> Option<&Solution<IndexType>> {
+		let pheromones_before = self.pheromone_matrix.borrow().snapshot();
+		let construction_start = Instant::now();
 		let mut ants = Vec::with_capacity(self.ant_count);
-		for _ in 0..self.ant_count {
-			let (sender, id) = self.supervisor.new_ant();
-			ants.push(Ant::new(
+		for id in 1..=self.ant_count {
+			let sender = self.supervisor.sender();
+			let ant = Ant::new(
 				self.graph,
-				&self.pheromone_matrix,
+				LocalUpdate::new(&self.pheromone_matrix, self.rho, self.t_0),
 				self.goal_point,
 				self.max_time,
 				self.heuristic,
 				self.rng.rand_u64() as u128 + ((self.rng.rand_u64() as u128) << 64),
 				self.alpha,
 				self.beta,
-				self.rho,
 				self.q_0,
-				self.t_0,
 				sender,
 				id,
-				&self.inv_shortest_paths,
-			));
+				&self.goal_paths.inv_shortest_paths,
+				&self.goal_paths.return_costs,
+			)
+			.with_goal_distances(&self.goal_paths.goal_distances)
+			.with_vehicle_count(self.vehicle_count)
+			.with_time_windows(&self.time_windows)
+			.with_mandatory_nodes(&self.mandatory_nodes)
+			.with_stochastic_edges(&self.stochastic_edges)
+			.with_revisit_penalty(self.revisit_penalty)
+			.with_service_times(&self.service_times)
+			.with_construction_limits(self.construction_limits)
+			.with_secondary_budget(self.secondary_budget.as_ref());
+			ants.push(match self.goal_paths.return_paths() {
+				Some(return_paths) => ant.with_return_paths(return_paths),
+				None => ant,
+			});
 		}
 
 		let mut solutions = Vec::new();
 		for ant in ants {
 			solutions.push(ant.get_solution())
 		}
+		self.supervisor
+			.add_time(Phase::Construction, construction_start.elapsed());
+		for warning in solutions.iter().filter_map(|ant_solution| ant_solution.warning) {
+			self.supervisor.warn(warning);
+		}
+		let diversity = Diversity::new(
+			&self.pheromone_matrix.borrow(),
+			solutions.iter().map(|ant_solution| &ant_solution.solution),
+		);
 
 		let start_time = Instant::now();
 		let mut best_length = R64::zero();
@@ -170,9 +266,46 @@ where
 		let mut visited_nodes = 0;
 		let mut visited_with_val = 0;
 		let mut val_sum = R64::zero();
+		let mut route_lengths = Vec::new();
+		let mut route_scores = Vec::new();
+		let mut secondary_resource = R64::zero();
+		let mut best_missing = self.mandatory_nodes.len();
 		let mut improvements = 0;
+		let mut archive = SolutionArchive::new();
+		let mut infeasible = 0;
+		let mut over_budget = 0;
+		let mut overshoot = R64::zero();
 		for ant_solution in solutions.into_iter() {
-			if ant_solution.length <= self.max_time && ant_solution.score > best_score {
+			// every vehicle has the whole time budget and secondary budget for its route
+			let feasible = ant_solution
+				.budget_lengths
+				.iter()
+				.all(|&length| length <= self.max_time)
+				&& ant_solution.fits_secondary_budget(self.secondary_budget.as_ref());
+			if !feasible {
+				infeasible += 1;
+				let exceeded = ant_solution.overshoot(self.max_time);
+				if exceeded > R64::zero() {
+					over_budget += 1;
+					overshoot += exceeded;
+				}
+			}
+			// a repeated route is scored like the first ant constructing it, so it can't improve
+			if !archive.insert(&ant_solution.solution) {
+				continue;
+			}
+			let missing = ant_solution
+				.solution
+				.missing_nodes(&self.mandatory_nodes)
+				.len();
+			if feasible
+				&& self
+					.objective
+					.improves_empty(ant_solution.score, ant_solution.length)
+				&& self.objective.replaces_best(
+					(missing, ant_solution.score, ant_solution.length),
+					(best_missing, best_score, best_length),
+				) {
 				improvements += 1;
 				best_score = ant_solution.score;
 				best_length = ant_solution.length;
@@ -180,11 +313,27 @@ where
 				visited_nodes = ant_solution.visited_nodes;
 				val_sum = ant_solution.val_sum;
 				visited_with_val = ant_solution.visited_with_val;
+				route_lengths = ant_solution.route_lengths;
+				route_scores = ant_solution.route_scores;
+				secondary_resource = ant_solution.secondary_resource;
+				best_missing = missing;
 			}
 		}
 
 		let duration = start_time.elapsed();
-		let _ = self.supervisor.sender.send(Message::new(
+		self.supervisor.add_time(Phase::Scoring, duration);
+		self.supervisor.log_route(
+			&best_solution,
+			best_score.into_inner(),
+			best_length.into_inner(),
+		);
+		let longest_route = route_lengths
+			.iter()
+			.copied()
+			.max()
+			.unwrap_or_else(R64::zero);
+		// sent after the pheromone update, to include the change of the pheromones
+		let message = Message::new(
 			0,
 			0,
 			0,
@@ -197,20 +346,205 @@ where
 			visited_nodes,
 			visited_with_val,
 			val_sum,
-		)); // Ant 0 is always supervisor
-		self.supervisor.prepare_next();
-
-		self.pheromone_update(&best_solution, best_score);
-		if best_score > self.best_score
-			|| best_length < self.best_length && best_score == self.best_score
-		{
-			// println!("solution improved");
+		)
+		.with_routes(route_lengths.len(), longest_route)
+		.with_diversity(diversity)
+		.with_duplicates(archive.duplicates())
+		.with_infeasible(infeasible, over_budget, overshoot.into_inner())
+		.with_secondary_resource(secondary_resource.into_inner());
+
+		let improved = self.objective.replaces_best(
+			(best_missing, best_score, best_length),
+			(self.best_missing, self.best_score, self.best_length),
+		);
+		if improved {
 			self.best_solution = best_solution;
 			self.best_score = best_score;
 			self.best_length = best_length;
+			self.best_routes = route_lengths.iter().copied().zip(route_scores).collect();
+			self.best_missing = best_missing;
+		}
+
+		let update_start = Instant::now();
+		self.global_update();
+		self.supervisor
+			.add_time(Phase::PheromoneUpdate, update_start.elapsed());
+		let pheromone_change = self
+			.pheromone_matrix
+			.borrow()
+			.change_since(&pheromones_before)
+			.unwrap_or_default();
+		let _ = self
+			.supervisor
+			.sender()
+			.send(message.with_pheromone_change(pheromone_change)); // Ant 0 is always supervisor
 
-			return Some(&self.best_solution);
+		self.observer.notify(improved, &self.best_solution, self.best_score, self.best_length);
+		if improved {
+			Some(&self.best_solution)
+		} else {
+			None
 		}
-		None
+	}
+
+	fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, R64>) {
+		self.observer.set(callback);
+	}
+}
+
+impl<'a, IndexType, W> Reoptimize<IndexType> for Acs<'a, IndexType, R64, R64, W>
+where
+	IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
+	W: Write,
+{
+	fn graph_changed(&mut self) {
+		self.goal_paths.recompute(&*self.graph.borrow(), self.goal_point);
+		let repruned = reprune_pheromones(
+			&self.pheromone_matrix.borrow(),
+			&*self.graph.borrow(),
+			self.goal_point,
+			self.max_time,
+			&self.goal_paths.inv_shortest_paths,
+			self.stochastic_edges.variances.is_empty(),
+			self.initial_pheromone,
+		);
+		*self.pheromone_matrix.borrow_mut() = repruned;
+		let (score, length) = rescore(
+			&self.best_solution,
+			self.graph,
+			self.heuristic,
+			self.max_time,
+			&self.time_windows,
+			&self.revisit_penalty,
+			&self.service_times,
+		);
+		self.best_score = score;
+		self.best_length = length;
+	}
+
+	fn best_score(&self) -> R64 {
+		self.best_score
+	}
+
+	fn best_length(&self) -> R64 {
+		self.best_length
+	}
+
+	fn best_solution(&self) -> &Solution<IndexType> {
+		&self.best_solution
+	}
+
+	fn rng_state(&self) -> RngState {
+		Acs::rng_state(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::graph::MatrixGraph;
+	use std::io;
+
+	fn h(nw: R64, _: R64, _: R64, _: R64) -> R64 {
+		nw
+	}
+
+	#[test]
+	fn global_update_only_reinforces_the_best_solution() {
+		let graph = MatrixGraph::new_usize_indexed(
+			vec![R64::zero(), R64::one(), R64::one()],
+			vec![(0, 1), (1, 0), (0, 2), (2, 0)]
+				.into_iter()
+				.map(|(from, to)| (from, to, R64::from_inner(2.0)))
+				.collect(),
+		)
+		.unwrap();
+		let inv_shortest_paths = graph.inv_shortest_paths(0);
+		let graph = RefCell::new(graph);
+		let problem = ProblemInstance::new(&graph, 0, R64::from_inner(10.0));
+		let params = Params::new(&h, 1.0, 1.0, 0.5, 0.9, 0.1, Some(0), 1, inv_shortest_paths);
+		let mut acs = Acs::new(problem, params, Supervisor::new(1, io::sink()));
+		let initial = *acs.pheromones().edge_weight((0, 1)).unwrap();
+
+		acs.best_solution = Solution::from_nodes(vec![0, 1, 0]);
+		acs.best_length = R64::from_inner(4.0);
+		acs.global_update();
+
+		let expected = R64::from_inner(0.5) * initial + R64::from_inner(0.5 / 4.0);
+		assert_eq!(*acs.pheromones().edge_weight((0, 1)).unwrap(), expected);
+		assert_eq!(*acs.pheromones().edge_weight((1, 0)).unwrap(), expected);
+		assert_eq!(*acs.pheromones().edge_weight((0, 2)).unwrap(), initial);
+	}
+
+	#[test]
+	fn warm_starts_deposit_strength_times_the_initial_level() {
+		let graph = MatrixGraph::new_usize_indexed(
+			vec![R64::zero(), R64::one(), R64::one()],
+			vec![(0, 1), (1, 0), (0, 2), (2, 0)]
+				.into_iter()
+				.map(|(from, to)| (from, to, R64::from_inner(2.0)))
+				.collect(),
+		)
+		.unwrap();
+		let inv_shortest_paths = graph.inv_shortest_paths(0);
+		let graph = RefCell::new(graph);
+		let problem = ProblemInstance::new(&graph, 0, R64::from_inner(10.0));
+		let params = Params::new(&h, 1.0, 1.0, 0.5, 0.9, 0.1, Some(0), 1, inv_shortest_paths);
+		let mut acs = Acs::new(problem, params, Supervisor::new(1, io::sink()));
+		let initial = *acs.pheromones().edge_weight((0, 1)).unwrap();
+
+		acs.warm_start(&Solution::from_nodes(vec![0, 1, 0]), 3.0);
+		let expected = initial + R64::from_inner(3.0) * initial;
+		assert_eq!(*acs.pheromones().edge_weight((0, 1)).unwrap(), expected);
+		assert_eq!(*acs.pheromones().edge_weight((1, 0)).unwrap(), expected);
+		assert_eq!(*acs.pheromones().edge_weight((0, 2)).unwrap(), initial);
+	}
+
+	#[test]
+	fn ants_construct_a_route_per_vehicle() {
+		let graph = MatrixGraph::new_usize_indexed(
+			vec![R64::zero(), R64::one(), R64::one()],
+			vec![(0, 1), (1, 0), (0, 2), (2, 0)]
+				.into_iter()
+				.map(|(from, to)| (from, to, R64::from_inner(2.0)))
+				.collect(),
+		)
+		.unwrap();
+		let inv_shortest_paths = graph.inv_shortest_paths(0);
+		let graph = RefCell::new(graph);
+		// the budget only fits a single reward per route
+		let problem = ProblemInstance::new(&graph, 0, R64::from_inner(4.0)).with_vehicle_count(2);
+		let params = Params::new(&h, 1.0, 1.0, 0.5, 0.9, 0.1, Some(0), 1, inv_shortest_paths);
+		let mut acs = Acs::new(problem, params, Supervisor::new(1, io::sink()));
+		acs.single_iteration();
+
+		let (solution, score, length) = acs.current_solution();
+		assert_eq!(solution.iter_nodes().filter(|&&node| node == 0).count(), 3);
+		assert_eq!(score, R64::from_inner(2.0));
+		assert_eq!(length, R64::from_inner(8.0));
+	}
+
+	#[test]
+	fn ants_stay_within_the_secondary_budget() {
+		let graph = MatrixGraph::new_usize_indexed(
+			vec![R64::zero(), R64::one(), R64::one()],
+			vec![(0, 1), (1, 0), (0, 2), (2, 0)]
+				.into_iter()
+				.map(|(from, to)| (from, to, R64::from_inner(2.0)))
+				.collect(),
+		)
+		.unwrap();
+		let inv_shortest_paths = graph.inv_shortest_paths(0);
+		let graph = RefCell::new(graph);
+		let consumption = vec![((0, 2), R64::from_inner(5.0))].into_iter().collect();
+		let problem = ProblemInstance::new(&graph, 0, R64::from_inner(10.0))
+			.with_secondary_budget(SecondaryBudget::new(consumption, R64::from_inner(4.0)));
+		let params = Params::new(&h, 1.0, 1.0, 0.5, 0.9, 0.1, Some(0), 1, inv_shortest_paths);
+		let mut acs = Acs::new(problem, params, Supervisor::new(1, io::sink()));
+		acs.single_iteration();
+
+		let (solution, score, _) = acs.current_solution();
+		assert!(!solution.nodes().contains(&2));
+		assert_eq!(score, R64::one());
 	}
 }
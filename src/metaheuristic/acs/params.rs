@@ -1,5 +1,6 @@
+use crate::metaheuristic::aco::ConstructionLimits;
 use crate::metaheuristic::{Heuristic, Solution};
-use crate::rng::os_random_seed;
+use crate::rng::{os_random_seed, RngKind};
 
 use std::collections::BTreeMap;
 
@@ -11,6 +12,10 @@ pub struct Params<'a, IndexType, Nw, Ew> {
 	pub q_0: f64,
 	pub t_0: f64,
 	pub seed: u128,
+	pub rng: RngKind,
+	pub construction_limits: ConstructionLimits,
+	/// Amount of shortest paths back to the goal point, among which the ants choose.
+	pub return_paths: usize,
 	pub ant_count: usize,
 	pub inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
 }
@@ -35,8 +40,31 @@ impl<'a, IndexType, Nw, Ew> Params<'a, IndexType, Nw, Ew> {
 			q_0,
 			t_0,
 			seed: seed.unwrap_or_else(os_random_seed),
+			rng: RngKind::default(),
+			construction_limits: ConstructionLimits::default(),
+			return_paths: 1,
 			ant_count,
 			inv_shortest_paths,
 		}
 	}
+
+	/// Draws the random numbers of the algorithm with kind instead of pcg.
+	pub fn with_rng(mut self, kind: RngKind) -> Self {
+		self.rng = kind;
+		self
+	}
+
+	/// Caps the moves of each ant and keeps them from moving straight back to recently left nodes.
+	pub fn with_construction_limits(mut self, limits: ConstructionLimits) -> Self {
+		self.construction_limits = limits;
+		self
+	}
+
+	/// Lets the ants return along any of the k shortest paths to the goal point, which still
+	/// fits into the time budget, once they stop collecting. The paths are chosen by their
+	/// pheromone levels, 1 keeps the shortest path only.
+	pub fn with_return_paths(mut self, k: usize) -> Self {
+		self.return_paths = k;
+		self
+	}
 }
@@ -0,0 +1,513 @@
+pub use crate::metaheuristic::random_search::{Message, Record, Supervisor};
+
+use crate::graph::GenericWeightedGraph;
+use crate::metaheuristic::supervisor::Phase;
+use crate::metaheuristic::{
+    solution_score, Heuristic, IterationCallback, Metaheuristic, Objective, Observer,
+    ProblemInstance, Solution,
+};
+use crate::util::Distance;
+
+use decorum::R64;
+use num_traits::identities::Zero;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::io::Write;
+use std::time::Instant;
+
+/// Largest amount of nodes with a reward the search can handle, as visited nodes are stored in
+/// a bit set.
+pub const MAX_NODES: usize = 64;
+
+pub struct Params<'a> {
+    pub heuristic: &'a Heuristic<R64, R64>,
+}
+
+impl<'a> Params<'a> {
+    pub fn new(heuristic: &'a Heuristic<R64, R64>) -> Self {
+        Params { heuristic }
+    }
+}
+
+/// Finds a route collecting the most node weight by branch and bound.
+///
+/// Routes are searched as sequences of nodes with a reward, which are connected by shortest
+/// paths. Branches are pruned if the remaining reachable reward can't beat the best route, and
+/// if another branch already reached the same node with the same nodes visited in less time.
+/// Other objectives than maximizing the score compare routes by their collected reward as
+/// score and prune branches, which can't beat the best route with all remaining reward at
+/// their current length.
+/// The whole search happens in the first iteration, so it is only feasible for small instances,
+/// see reward_nodes. The heuristic is not used by the search, only to score the result.
+/// Time windows, mandatory nodes, multiple vehicles and service times are not modeled, see
+/// unsupported.
+pub struct Exact<'a, IndexType, W: Write> {
+    graph: &'a RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = R64, EdgeWeightType = R64>
+            + 'a,
+    >,
+    goal_point: IndexType,
+    heuristic: &'a Heuristic<R64, R64>,
+    max_time: R64,
+    objective: Objective,
+    pub best_solution: Solution<IndexType>,
+    pub best_score: R64,
+    pub best_length: R64,
+    pub supervisor: Supervisor<W, R64, R64>,
+    observer: Observer<'a, IndexType, R64>,
+    solved: bool,
+}
+
+/// Distances and rewards of the nodes with a reward, index 0 is the goal point.
+struct Search {
+    distances: Vec<Vec<Option<f64>>>,
+    rewards: Vec<f64>,
+    max_time: f64,
+    objective: Objective,
+    // visited nodes and length of the best route
+    best: (Vec<usize>, f64),
+    best_reward: f64,
+    shortest: HashMap<(usize, u64), f64>,
+    evaluations: usize,
+}
+
+impl Search {
+    /// Returns the length of a route, which continues from node to next and then back to the
+    /// goal point, if that is possible within max_time.
+    fn via(&self, length: f64, node: usize, next: usize) -> Option<f64> {
+        let to = self.distances[node][next]?;
+        let back = self.distances[next][0]?;
+        Some(length + to).filter(|&length| length + back <= self.max_time)
+    }
+
+    /// Returns the value of a route with reward and length by the objective.
+    fn value(&self, reward: f64, length: f64) -> Option<R64> {
+        self.objective
+            .value(R64::from_inner(reward), R64::from_inner(length))
+    }
+
+    fn branch(&mut self, route: &mut Vec<usize>, visited: u64, length: f64, reward: f64) {
+        self.evaluations += 1;
+        let node = *route.last().unwrap();
+        let total = length + self.distances[node][0].unwrap_or(0.0);
+        let value = self.value(reward, total);
+        let best_value = self.value(self.best_reward, self.best.1);
+        if value > best_value || value == best_value && total < self.best.1 {
+            self.best_reward = reward;
+            self.best = (route.clone(), total);
+        }
+
+        let next_nodes: Vec<(usize, f64)> = (1..self.rewards.len())
+            .filter(|next| visited & (1 << (next - 1)) == 0)
+            .filter_map(|next| self.via(length, node, next).map(|length| (next, length)))
+            .collect();
+        let bound = reward
+            + next_nodes
+                .iter()
+                .map(|&(next, _)| self.rewards[next])
+                .sum::<f64>();
+        // with shortest paths, no continuation of the route is shorter than returning directly,
+        // only the goal point itself has no length to compare with
+        if route.len() > 1 && self.value(bound, total) <= self.value(self.best_reward, self.best.1)
+        {
+            return;
+        }
+
+        for (next, next_length) in next_nodes {
+            let next_visited = visited | (1 << (next - 1));
+            match self.shortest.get(&(next, next_visited)) {
+                Some(&shortest) if shortest <= next_length => continue,
+                _ => {
+                    self.shortest.insert((next, next_visited), next_length);
+                }
+            }
+            route.push(next);
+            self.branch(
+                route,
+                next_visited,
+                next_length,
+                reward + self.rewards[next],
+            );
+            route.pop();
+        }
+    }
+}
+
+/// Returns the nodes with a reward, which have a round trip from goal_point within max_time.
+/// The exact search grows exponentially with their amount.
+pub fn reward_nodes<IndexType>(
+    graph: &dyn GenericWeightedGraph<
+        IndexType = IndexType,
+        NodeWeightType = R64,
+        EdgeWeightType = R64,
+    >,
+    goal_point: IndexType,
+    max_time: R64,
+) -> Vec<IndexType>
+where
+    IndexType: Copy + Ord + Debug + Display,
+{
+    let paths_from = graph.shortest_paths(goal_point);
+    let paths_to = graph.inv_shortest_paths(goal_point);
+    graph
+        .iter_nodes()
+        .filter(|&(node, weight)| node != goal_point && *weight > R64::zero())
+        .map(|(node, _)| node)
+        .filter(|node| match (paths_from.get(node), paths_to.get(node)) {
+            (Some(Some((_, there))), Some(Some((_, back)))) => *there + *back <= max_time,
+            _ => false,
+        })
+        .collect()
+}
+
+/// Returns the constraints of problem, which the search ignores. Its routes could violate
+/// them, so instances with any of them are refused instead of being searched.
+pub fn unsupported<IndexType>(problem: &ProblemInstance<IndexType, R64, R64>) -> Vec<&'static str> {
+    let mut unsupported = Vec::new();
+    if !problem.time_windows.is_empty() {
+        unsupported.push("time windows");
+    }
+    if !problem.mandatory_nodes.is_empty() {
+        unsupported.push("mandatory nodes");
+    }
+    if problem.vehicle_count > 1 {
+        unsupported.push("multiple vehicles");
+    }
+    if !problem.service_times.is_empty() {
+        unsupported.push("service times");
+    }
+    unsupported
+}
+
+impl<'a, IndexType, W> Exact<'a, IndexType, W>
+where
+    IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
+    W: Write,
+{
+    pub fn current_solution(&self) -> (&Solution<IndexType>, R64, R64) {
+        (&self.best_solution, self.best_score, self.best_length)
+    }
+
+    /// Searches the best route and returns it with the amount of evaluated branches.
+    /// Panics if there are more than MAX_NODES reward nodes.
+    fn search(&self) -> (Solution<IndexType>, usize) {
+        let graph = self.graph.borrow();
+        let mut nodes = vec![self.goal_point];
+        nodes.extend(reward_nodes(&*graph, self.goal_point, self.max_time));
+        assert!(
+            nodes.len() <= MAX_NODES + 1,
+            "Exact supports at most {} nodes with a reward.",
+            MAX_NODES
+        );
+
+        let paths: Vec<_> = nodes
+            .iter()
+            .map(|&node| graph.shortest_paths(node))
+            .collect();
+        let distances = paths
+            .iter()
+            .map(|from| {
+                nodes
+                    .iter()
+                    .map(|to| match from.get(to) {
+                        Some(Some((_, distance))) => Some(distance.into_inner()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        let rewards = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &node)| match graph.node_weight(node) {
+                Ok(weight) if i > 0 => weight.into_inner(),
+                _ => 0.0,
+            })
+            .collect();
+        // the search only works on the copied weights
+        drop(graph);
+
+        let mut search = Search {
+            distances,
+            rewards,
+            max_time: self.max_time.into_inner(),
+            objective: self.objective,
+            best: (vec![0], 0.0),
+            best_reward: 0.0,
+            shortest: HashMap::new(),
+            evaluations: 0,
+        };
+        search.branch(&mut vec![0], 0, 0.0, 0.0);
+
+        let mut route = search.best.0;
+        route.push(0);
+        let mut solution = Solution::from_nodes(vec![self.goal_point]);
+        for step in route.windows(2) {
+            if step[0] == step[1] {
+                continue;
+            }
+            if let Some(Some((path, _))) = paths[step[0]].get(&nodes[step[1]]) {
+                for &node in path.iter_nodes().skip(1) {
+                    solution.push_node(node);
+                }
+            }
+        }
+
+        (solution, search.evaluations)
+    }
+}
+
+impl<'a, IndexType, W> Metaheuristic<'a, IndexType, R64, R64> for Exact<'a, IndexType, W>
+where
+    IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
+    W: Write,
+{
+    type Params = Params<'a>;
+    type SupervisorType = Supervisor<W, R64, R64>;
+
+    fn new(
+        problem: ProblemInstance<'a, IndexType, R64, R64>,
+        params: Self::Params,
+        supervisor: Self::SupervisorType,
+    ) -> Self {
+        Exact {
+            graph: problem.graph,
+            goal_point: problem.goal_point,
+            heuristic: params.heuristic,
+            max_time: problem.max_time,
+            objective: problem.objective,
+            best_solution: Solution::from_nodes(vec![problem.goal_point]),
+            best_score: R64::zero(),
+            best_length: R64::zero(),
+            supervisor,
+            observer: Observer::default(),
+            solved: false,
+        }
+    }
+
+    /// Runs the whole search, later iterations don't do anything.
+    fn single_iteration(&mut self) -> Option<&Solution<IndexType>> {
+        if self.solved {
+            self.observer.notify(
+                false,
+                &self.best_solution,
+                self.best_score,
+                self.best_length,
+            );
+            return None;
+        }
+        self.solved = true;
+
+        let start_time = Instant::now();
+        let (solution, evaluations) = self.search();
+        self.supervisor
+            .add_time(Phase::Construction, start_time.elapsed());
+
+        let graph = self.graph.borrow();
+        let mut length = R64::zero();
+        for (from, to) in solution.iter_edges() {
+            length += *graph.edge_weight((*from, *to)).unwrap();
+        }
+        let mut visited_nodes = 0;
+        let mut visited_with_val = 0;
+        let mut val_sum = R64::zero();
+        for node in solution.iter_unique_nodes() {
+            visited_nodes += 1;
+            let weight = *graph.node_weight(node).unwrap();
+            if weight > R64::zero() {
+                visited_with_val += 1;
+                val_sum += weight;
+            }
+        }
+        drop(graph);
+        let score =
+            solution_score(&solution, self.graph, self.heuristic).unwrap_or_else(|_| R64::zero());
+
+        let _ = self.supervisor.sender().send(Message::new(
+            0,
+            evaluations,
+            1,
+            0,
+            0,
+            start_time.elapsed(),
+            length,
+            score,
+            visited_nodes,
+            visited_with_val,
+            val_sum,
+        ));
+        self.supervisor
+            .log_route(&solution, score.into_inner(), length.into_inner());
+
+        self.best_solution = solution;
+        self.best_score = score;
+        self.best_length = length;
+        self.observer
+            .notify(true, &self.best_solution, self.best_score, self.best_length);
+        Some(&self.best_solution)
+    }
+
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, R64>) {
+        self.observer.set(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphBuilder, MatrixGraph};
+    use std::io;
+
+    fn heuristic(nw: R64, _: R64, _: R64, _: R64) -> R64 {
+        nw
+    }
+
+    #[test]
+    fn unmodeled_constraints_are_unsupported() {
+        let r = R64::from_inner;
+        let graph = RefCell::new(
+            GraphBuilder::new()
+                .nodes((0..).zip(vec![r(0.0), r(3.0)]))
+                .bidirectional_edge(0, 1, r(1.0))
+                .build()
+                .unwrap(),
+        );
+        assert!(unsupported(&ProblemInstance::new(&graph, 0, r(4.0))).is_empty());
+        let problem = ProblemInstance::new(&graph, 0, r(4.0))
+            .with_vehicle_count(2)
+            .with_mandatory_nodes(vec![1].into_iter().collect())
+            .with_service_times(vec![(1, r(1.0))].into_iter().collect());
+        assert_eq!(
+            unsupported(&problem),
+            vec!["mandatory nodes", "multiple vehicles", "service times"]
+        );
+    }
+
+    #[test]
+    fn best_route_is_found() {
+        let r = R64::from_inner;
+        // 2 can only be reached through 1, which is worth more than going to 3 and 4 instead
+        let graph = GraphBuilder::new()
+            .nodes((0..).zip(vec![r(0.0), r(3.0), r(5.0), r(4.0), r(2.0)]))
+            .bidirectional_edge(0, 1, r(1.0))
+            .bidirectional_edge(1, 2, r(3.0))
+            .bidirectional_edge(0, 3, r(2.0))
+            .bidirectional_edge(3, 4, r(2.0))
+            .build()
+            .unwrap();
+        let graph = RefCell::new(graph);
+        let h: &Heuristic<R64, R64> = &heuristic;
+
+        let mut exact = Exact::new(
+            ProblemInstance::new(&graph, 0, r(8.0)),
+            Params::new(h),
+            Supervisor::new(1, io::sink()),
+        );
+        exact.single_iteration();
+        let (solution, score, length) = exact.current_solution();
+        assert_eq!(solution.nodes(), vec![0, 1, 2, 1, 0]);
+        assert_eq!(length, 8.0);
+        assert_eq!(score, 8.0);
+
+        // with more time 3 can be added, but not 4
+        let mut exact = Exact::new(
+            ProblemInstance::new(&graph, 0, r(14.0)),
+            Params::new(h),
+            Supervisor::new(1, io::sink()),
+        );
+        exact.single_iteration();
+        assert_eq!(exact.current_solution().1, 12.0);
+        assert_eq!(exact.current_solution().2, 12.0);
+        assert!(exact.single_iteration().is_none());
+    }
+
+    #[test]
+    fn objectives_change_the_best_route() {
+        let r = R64::from_inner;
+        let graph = GraphBuilder::new()
+            .nodes((0..).zip(vec![r(0.0), r(3.0), r(5.0), r(4.0)]))
+            .bidirectional_edge(0, 1, r(1.0))
+            .bidirectional_edge(1, 2, r(3.0))
+            .bidirectional_edge(0, 3, r(2.0))
+            .build()
+            .unwrap();
+        let graph = RefCell::new(graph);
+        let h: &Heuristic<R64, R64> = &heuristic;
+        let best_route = |objective| {
+            let mut exact = Exact::new(
+                ProblemInstance::new(&graph, 0, r(14.0)).with_objective(objective),
+                Params::new(h),
+                Supervisor::new(1, io::sink()),
+            );
+            exact.single_iteration();
+            exact.current_solution().0.nodes()
+        };
+
+        assert_eq!(
+            best_route(Objective::MaximizeScore),
+            vec![0, 1, 2, 1, 0, 3, 0]
+        );
+        assert_eq!(best_route(Objective::MinimizeLength), vec![0, 1, 0]);
+        // 0 1 0 and 0 1 0 3 0 both have the value 1, the shorter one is kept
+        assert_eq!(
+            best_route(Objective::Weighted {
+                score_weight: r(1.0),
+                length_weight: r(1.0),
+            }),
+            vec![0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn unreachable_rewards_are_ignored() {
+        let r = R64::from_inner;
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(3.0), r(9.0)],
+            vec![(0, 1, r(1.0)), (1, 0, r(1.0)), (0, 2, r(1.0))],
+        )
+        .unwrap();
+
+        assert_eq!(reward_nodes(&graph, 0, r(10.0)), vec![1]);
+        assert!(reward_nodes(&graph, 0, r(1.0)).is_empty());
+    }
+
+    #[test]
+    fn iterations_are_passed_to_the_callback() {
+        let r = R64::from_inner;
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(3.0)],
+            vec![(0, 1, r(1.0)), (1, 0, r(1.0))],
+        )
+        .unwrap();
+        let graph = RefCell::new(graph);
+        let h: &Heuristic<R64, R64> = &heuristic;
+        let events = RefCell::new(Vec::new());
+
+        let mut exact = Exact::new(
+            ProblemInstance::new(&graph, 0, r(4.0)),
+            Params::new(h),
+            Supervisor::new(1, io::sink()),
+        );
+        exact.set_callback(Box::new(|event| {
+            events.borrow_mut().push((
+                event.iteration,
+                event.improved,
+                event.best_score,
+                event.best_solution.nodes(),
+            ))
+        }));
+        exact.single_iteration();
+        exact.single_iteration();
+        drop(exact);
+
+        assert_eq!(
+            events.into_inner(),
+            vec![
+                (0, true, r(3.0), vec![0, 1, 0]),
+                (1, false, r(3.0), vec![0, 1, 0]),
+            ]
+        );
+    }
+}
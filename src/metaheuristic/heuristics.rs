@@ -0,0 +1,74 @@
+mod compose;
+
+pub use compose::{parse, Blend, Constant, Power, Product, Score, WeightedSum};
+
+use decorum::R64;
+use num_traits::real::Real;
+use num_traits::{One, Zero};
+
+use crate::metaheuristic::SyncHeuristic;
+
+/// All heuristics, which experiments can select by name.
+pub const HEURISTICS: &[(&str, &SyncHeuristic<R64, R64>)] = &[
+    ("value", &value),
+    ("value_per_cost", &value_per_cost),
+    ("saturated_value", &saturated_value),
+    ("distance_decay", &distance_decay),
+];
+
+/// Returns the heuristic registered as name together with its static name.
+pub fn by_name(name: &str) -> Option<(&'static str, &'static SyncHeuristic<R64, R64>)> {
+    HEURISTICS
+        .iter()
+        .find(|(registered, _)| *registered == name)
+        .copied()
+}
+
+/// The value of the node.
+pub fn value(nw: R64, _ew: R64, _dist_to_start: R64, _elapsed: R64) -> R64 {
+    nw
+}
+
+/// The value of the node per cost of the edge to it, zero for free edges.
+pub fn value_per_cost(nw: R64, ew: R64, _dist_to_start: R64, _elapsed: R64) -> R64 {
+    if nw != R64::zero() && ew != R64::zero() {
+        nw / ew
+    } else {
+        R64::zero()
+    }
+}
+
+/// Approaches one for high node values, which keeps single nodes from dominating the choice.
+pub fn saturated_value(nw: R64, _ew: R64, _dist_to_start: R64, _elapsed: R64) -> R64 {
+    if nw != R64::zero() {
+        R64::one() - R64::one() / nw
+    } else {
+        R64::zero()
+    }
+}
+
+/// Shifts from the saturated value towards closeness to the goal point, as the route uses up
+/// its budget.
+pub fn distance_decay(nw: R64, _ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+    if nw != R64::zero() && dist_to_start != R64::zero() {
+        R64::powf(R64::one() - R64::one() / nw, R64::one() - elapsed)
+            * R64::powf(R64::one() / dist_to_start, elapsed)
+    } else if nw != R64::zero() {
+        R64::powf(R64::one() - R64::one() / nw, R64::one() - elapsed)
+    } else {
+        R64::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristics_are_found_by_name() {
+        for (name, _) in HEURISTICS {
+            assert_eq!(by_name(name).map(|(found, _)| found), Some(*name));
+        }
+        assert!(by_name("h1").is_none());
+    }
+}
@@ -0,0 +1,357 @@
+use decorum::R64;
+use num_traits::real::Real;
+use num_traits::{One, Zero};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::metaheuristic::heuristics::by_name;
+use crate::metaheuristic::SyncHeuristic;
+
+/// A heuristic or a part of one, which scores a node from the same arguments as heuristics
+/// get. Every heuristic fn is one, the structs of this module combine them into new ones.
+pub trait Score: Send + Sync {
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64;
+
+    /// Returns the heuristic, which scores nodes like self.
+    fn into_heuristic(self) -> Box<SyncHeuristic<R64, R64>>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(move |nw, ew, dist_to_start, elapsed| self.score(nw, ew, dist_to_start, elapsed))
+    }
+}
+
+impl<F> Score for F
+where
+    F: Fn(R64, R64, R64, R64) -> R64 + Send + Sync,
+{
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+        self(nw, ew, dist_to_start, elapsed)
+    }
+}
+
+/// Scores every node the same.
+pub struct Constant(pub R64);
+
+impl Score for Constant {
+    fn score(&self, _nw: R64, _ew: R64, _dist_to_start: R64, _elapsed: R64) -> R64 {
+        self.0
+    }
+}
+
+/// The sum of its terms, each multiplied with its weight.
+#[derive(Default)]
+pub struct WeightedSum {
+    terms: Vec<(R64, Box<dyn Score>)>,
+}
+
+impl WeightedSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn term(mut self, weight: f64, part: impl Score + 'static) -> Self {
+        self.terms.push((R64::from_inner(weight), Box::new(part)));
+        self
+    }
+}
+
+impl Score for WeightedSum {
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+        self.terms.iter().fold(R64::zero(), |sum, (weight, part)| {
+            sum + *weight * part.score(nw, ew, dist_to_start, elapsed)
+        })
+    }
+}
+
+/// The product of its factors.
+#[derive(Default)]
+pub struct Product {
+    factors: Vec<Box<dyn Score>>,
+}
+
+impl Product {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn factor(mut self, part: impl Score + 'static) -> Self {
+        self.factors.push(Box::new(part));
+        self
+    }
+}
+
+impl Score for Product {
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+        self.factors.iter().fold(R64::one(), |product, part| {
+            product * part.score(nw, ew, dist_to_start, elapsed)
+        })
+    }
+}
+
+/// Raises base to exponent. Negative bases have no real fractional powers, so they count as
+/// zero instead of turning the score into NaN.
+fn power(base: R64, exponent: R64) -> R64 {
+    R64::powf(Ord::max(base, R64::zero()), exponent)
+}
+
+/// The score of its base raised to a fixed exponent, negative scores of the base count as zero.
+pub struct Power {
+    base: Box<dyn Score>,
+    exponent: R64,
+}
+
+impl Power {
+    pub fn new(base: impl Score + 'static, exponent: f64) -> Self {
+        Power {
+            base: Box::new(base),
+            exponent: R64::from_inner(exponent),
+        }
+    }
+}
+
+impl Score for Power {
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+        power(
+            self.base.score(nw, ew, dist_to_start, elapsed),
+            self.exponent,
+        )
+    }
+}
+
+/// Shifts from the score of early to the score of late, as the route uses up its budget, by
+/// weighing them geometrically with the elapsed part of the budget like distance_decay.
+pub struct Blend {
+    early: Box<dyn Score>,
+    late: Box<dyn Score>,
+}
+
+impl Blend {
+    pub fn new(early: impl Score + 'static, late: impl Score + 'static) -> Self {
+        Blend {
+            early: Box::new(early),
+            late: Box::new(late),
+        }
+    }
+}
+
+impl Score for Blend {
+    fn score(&self, nw: R64, ew: R64, dist_to_start: R64, elapsed: R64) -> R64 {
+        power(
+            self.early.score(nw, ew, dist_to_start, elapsed),
+            R64::one() - elapsed,
+        ) * power(self.late.score(nw, ew, dist_to_start, elapsed), elapsed)
+    }
+}
+
+/// Parses a composed heuristic given as `name = expression` and returns its name and the
+/// heuristic. Expressions combine the registered heuristics and numbers with `+`, `*` and
+/// `^` for a numeric exponent, with parentheses and with `blend(early, late)`, for example
+/// `mixed = 0.5 * value + blend(saturated_value, value_per_cost) ^ 2`.
+pub fn parse(definition: &str) -> Result<(String, Box<SyncHeuristic<R64, R64>>), String> {
+    let (name, expression) = definition
+        .split_once('=')
+        .ok_or_else(|| format!("{} is not of the form name = expression", definition))?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!(
+            "{} is not a name of letters, digits and underscores",
+            name
+        ));
+    }
+    let mut parser = Parser {
+        chars: expression.chars().peekable(),
+    };
+    let part = parser.sum()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        return Err(format!("unexpected {} in {}", c, expression.trim()));
+    }
+    Ok((
+        name.to_string(),
+        Box::new(move |nw, ew, dist_to_start, elapsed| part.score(nw, ew, dist_to_start, elapsed)),
+    ))
+}
+
+/// Recursive descent parser of expressions, in which ^ binds stronger than * and * binds
+/// stronger than +.
+struct Parser<'s> {
+    chars: Peekable<Chars<'s>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Consumes expected if it is the next character after whitespace.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(format!("expected {}", expected))
+        }
+    }
+
+    /// Returns the longest run of characters matching accept.
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.chars.next_if(|&c| accept(c)) {
+            taken.push(c);
+        }
+        taken
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_whitespace();
+        let number = self.take_while(|c| c.is_ascii_digit() || c == '.' || c == '-');
+        number
+            .parse()
+            .map_err(|_| format!("expected a number instead of {:?}", number))
+    }
+
+    fn sum(&mut self) -> Result<Box<dyn Score>, String> {
+        let first = self.product()?;
+        if !self.eat('+') {
+            return Ok(first);
+        }
+        let mut sum = WeightedSum {
+            terms: vec![(R64::one(), first)],
+        };
+        loop {
+            sum.terms.push((R64::one(), self.product()?));
+            if !self.eat('+') {
+                return Ok(Box::new(sum));
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Box<dyn Score>, String> {
+        let first = self.power()?;
+        if !self.eat('*') {
+            return Ok(first);
+        }
+        let mut product = Product {
+            factors: vec![first],
+        };
+        loop {
+            product.factors.push(self.power()?);
+            if !self.eat('*') {
+                return Ok(Box::new(product));
+            }
+        }
+    }
+
+    fn power(&mut self) -> Result<Box<dyn Score>, String> {
+        let base = self.atom()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        Ok(Box::new(Power {
+            base,
+            exponent: R64::from_inner(self.number()?),
+        }))
+    }
+
+    fn atom(&mut self) -> Result<Box<dyn Score>, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('(') => {
+                self.chars.next();
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || *c == '.' || *c == '-' => {
+                Ok(Box::new(Constant(R64::from_inner(self.number()?))))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                if name == "blend" {
+                    self.expect('(')?;
+                    let early = self.sum()?;
+                    self.expect(',')?;
+                    let late = self.sum()?;
+                    self.expect(')')?;
+                    return Ok(Box::new(Blend { early, late }));
+                }
+                let (_, heuristic) =
+                    by_name(&name).ok_or_else(|| format!("unknown heuristic {}", name))?;
+                Ok(Box::new(heuristic))
+            }
+            Some(c) => Err(format!("unexpected {}", c)),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metaheuristic::heuristics::{
+        distance_decay, saturated_value, value, value_per_cost,
+    };
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn combinators_compose_heuristics() {
+        let sum = WeightedSum::new()
+            .term(0.5, value)
+            .term(2.0, value_per_cost);
+        assert_eq!(sum.score(r(4.0), r(2.0), r(1.0), r(0.0)), r(6.0));
+
+        let product = Product::new().factor(value).factor(Constant(r(3.0)));
+        assert_eq!(product.score(r(4.0), r(2.0), r(1.0), r(0.0)), r(12.0));
+        assert_eq!(
+            Power::new(value, 2.0).score(r(3.0), r(1.0), r(1.0), r(0.0)),
+            r(9.0)
+        );
+        // fractional powers of negative scores would be NaN
+        assert_eq!(
+            Power::new(Constant(r(-4.0)), 0.5).score(r(3.0), r(1.0), r(1.0), r(0.0)),
+            r(0.0)
+        );
+
+        // distance_decay blends the saturated value with the inverse distance to the start
+        let inverse_distance = |_nw, _ew, dist_to_start: R64, _elapsed| R64::one() / dist_to_start;
+        let blend = Blend::new(saturated_value, inverse_distance).into_heuristic();
+        for &elapsed in &[0.0, 0.3, 1.0] {
+            assert_eq!(
+                blend(r(4.0), r(1.0), r(2.0), r(elapsed)),
+                distance_decay(r(4.0), r(1.0), r(2.0), r(elapsed))
+            );
+        }
+    }
+
+    #[test]
+    fn expressions_are_parsed() {
+        let (name, heuristic) = parse("mixed = 0.5 * value + value_per_cost ^ 2").unwrap();
+        assert_eq!(name, "mixed");
+        assert_eq!(heuristic(r(4.0), r(2.0), r(1.0), r(0.0)), r(6.0));
+
+        let (_, heuristic) = parse("h = 2 * (value + 1)").unwrap();
+        assert_eq!(heuristic(r(4.0), r(2.0), r(1.0), r(0.0)), r(10.0));
+        let (_, heuristic) = parse("h = blend(value, 1)").unwrap();
+        assert_eq!(heuristic(r(4.0), r(2.0), r(1.0), r(0.5)), r(2.0));
+
+        for invalid in &[
+            "0.5 * value",
+            "two words = value",
+            "h = value +",
+            "h = unknown",
+            "h = value ^ value",
+            "h = (value",
+            "h = value value",
+        ] {
+            assert!(parse(invalid).is_err(), "{} is invalid", invalid);
+        }
+    }
+}
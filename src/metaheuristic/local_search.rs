@@ -0,0 +1,262 @@
+use crate::graph::GenericWeightedGraph;
+use crate::metaheuristic::Solution;
+
+use num_traits::Zero;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Longest segment moved or removed by a single Or-opt move.
+const MAX_SEGMENT: usize = 3;
+
+/// Shortens solution with 2-opt, Or-opt and detour removing moves, until none of them improves
+/// its length. 2-opt reverses a part of the route, Or-opt moves up to three consecutive nodes
+/// elsewhere and detours are parts of the route, which can be left out. Moves keep the start and
+/// end of the route, only use edges of graph and never stop visiting a node of keep, so the
+/// collected rewards stay the same when keep contains the rewarded nodes of the route.
+/// Returns the shortened solution and its length, or None if no move shortened it or it uses
+/// edges, which are not in graph.
+pub fn shorten_route<G>(
+    solution: &Solution<G::IndexType>,
+    graph: &G,
+    keep: &HashSet<G::IndexType>,
+) -> Option<(Solution<G::IndexType>, G::EdgeWeightType)>
+where
+    G: GenericWeightedGraph + ?Sized,
+    G::IndexType: Copy + Hash + Eq,
+    G::EdgeWeightType: Copy + Zero + PartialOrd,
+{
+    let mut route = Route {
+        nodes: solution.nodes(),
+        graph,
+    };
+    let original = route.length()?;
+    // every move shortens the route, the limit only guards against rounding errors
+    let max_moves = route.nodes.len() * route.nodes.len();
+    let mut moves = 0;
+    while moves < max_moves && (route.two_opt() || route.or_opt() || route.remove_detour(keep)) {
+        moves += 1;
+    }
+    match route.length()? {
+        length if moves > 0 && length < original => {
+            Some((Solution::from_nodes(route.nodes), length))
+        }
+        _ => None,
+    }
+}
+
+struct Route<'g, G: GenericWeightedGraph + ?Sized> {
+    nodes: Vec<G::IndexType>,
+    graph: &'g G,
+}
+
+impl<'g, G> Route<'g, G>
+where
+    G: GenericWeightedGraph + ?Sized,
+    G::IndexType: Copy + Hash + Eq,
+    G::EdgeWeightType: Copy + Zero + PartialOrd,
+{
+    fn weight(&self, from: G::IndexType, to: G::IndexType) -> Option<G::EdgeWeightType> {
+        self.graph.edge_weight((from, to)).ok().copied()
+    }
+
+    fn length(&self) -> Option<G::EdgeWeightType> {
+        self.nodes
+            .windows(2)
+            .try_fold(G::EdgeWeightType::zero(), |length, edge| {
+                Some(length + self.weight(edge[0], edge[1])?)
+            })
+    }
+
+    /// Reverses the first part of the route between two nodes, which makes it shorter.
+    /// Returns true if the route changed.
+    fn two_opt(&mut self) -> bool {
+        let n = self.nodes.len();
+        for i in 0..n.saturating_sub(3) {
+            let before = self.nodes[i];
+            let first = self.nodes[i + 1];
+            // lengths of the segment from first to the node at j, forwards and backwards
+            let mut forward = G::EdgeWeightType::zero();
+            let mut backward = Some(G::EdgeWeightType::zero());
+            for j in i + 2..n - 1 {
+                let (previous, last, after) = (self.nodes[j - 1], self.nodes[j], self.nodes[j + 1]);
+                forward = match self.weight(previous, last) {
+                    Some(weight) => forward + weight,
+                    None => break,
+                };
+                backward = backward.and_then(|length| Some(length + self.weight(last, previous)?));
+                let removed = self.weight(before, first).zip(self.weight(last, after));
+                let added = self.weight(before, last).zip(self.weight(first, after));
+                if let (Some(backward), Some((r_in, r_out)), Some((a_in, a_out))) =
+                    (backward, removed, added)
+                {
+                    if a_in + backward + a_out < r_in + forward + r_out {
+                        self.nodes[i + 1..=j].reverse();
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Moves up to MAX_SEGMENT consecutive nodes between two other nodes of the route, if that
+    /// makes it shorter. Returns true if the route changed.
+    fn or_opt(&mut self) -> bool {
+        let n = self.nodes.len();
+        for len in 1..=MAX_SEGMENT {
+            for start in 1..n.saturating_sub(len) {
+                let end = start + len - 1;
+                let (previous, next) = (self.nodes[start - 1], self.nodes[end + 1]);
+                let (first, last) = (self.nodes[start], self.nodes[end]);
+                let closed = match self.weight(previous, next) {
+                    Some(weight) if previous != next => weight,
+                    _ => continue,
+                };
+                let (removed_in, removed_out) =
+                    match self.weight(previous, first).zip(self.weight(last, next)) {
+                        Some(weights) => weights,
+                        None => continue,
+                    };
+                for k in (0..n - 1).filter(|&k| k + 1 < start || k > end) {
+                    let (a, b) = (self.nodes[k], self.nodes[k + 1]);
+                    let opened = self.weight(a, b);
+                    let added = self.weight(a, first).zip(self.weight(last, b));
+                    if let (Some(opened), Some((added_in, added_out))) = (opened, added) {
+                        if closed + added_in + added_out < removed_in + removed_out + opened {
+                            let segment: Vec<_> = self.nodes.drain(start..=end).collect();
+                            let at = if k < start { k + 1 } else { k + 1 - len };
+                            self.nodes.splice(at..at, segment);
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Leaves out up to MAX_SEGMENT consecutive nodes, if the route stays connected and still
+    /// visits all nodes of keep. A detour returning to the node it started from is left out
+    /// together with one of its ends. Returns true if the route changed.
+    fn remove_detour(&mut self, keep: &HashSet<G::IndexType>) -> bool {
+        let n = self.nodes.len();
+        let mut visits: HashMap<G::IndexType, usize> = HashMap::new();
+        for &node in self.nodes.iter() {
+            *visits.entry(node).or_default() += 1;
+        }
+        for len in 1..=MAX_SEGMENT {
+            for start in 1..n.saturating_sub(len) {
+                let end = start + len - 1;
+                let (previous, next) = (self.nodes[start - 1], self.nodes[end + 1]);
+                // a detour back to previous is removed up to the second visit of previous
+                let (removed_end, added) = if previous == next {
+                    (end + 1, Some(G::EdgeWeightType::zero()))
+                } else {
+                    (end, self.weight(previous, next))
+                };
+                let mut left_out: HashMap<G::IndexType, usize> = HashMap::new();
+                for &node in self.nodes[start..=removed_end].iter() {
+                    *left_out.entry(node).or_default() += 1;
+                }
+                if left_out
+                    .iter()
+                    .any(|(node, count)| keep.contains(node) && visits[node] == *count)
+                {
+                    continue;
+                }
+                let removed = self.nodes[start - 1..=end + 1]
+                    .windows(2)
+                    .try_fold(G::EdgeWeightType::zero(), |length, edge| {
+                        Some(length + self.weight(edge[0], edge[1])?)
+                    });
+                if let (Some(added), Some(removed)) = (added, removed) {
+                    if added < removed {
+                        self.nodes.drain(start..=removed_end);
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    use decorum::R64;
+
+    fn graph(edges: Vec<(usize, usize, f64)>, order: usize) -> MatrixGraph<usize, R64, R64> {
+        MatrixGraph::new_usize_indexed(
+            vec![R64::from_inner(1.0); order],
+            edges
+                .into_iter()
+                .map(|(from, to, weight)| (from, to, R64::from_inner(weight)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn symmetric(edges: Vec<(usize, usize, f64)>, order: usize) -> MatrixGraph<usize, R64, R64> {
+        let both = edges
+            .iter()
+            .flat_map(|&(from, to, weight)| vec![(from, to, weight), (to, from, weight)])
+            .collect();
+        graph(both, order)
+    }
+
+    #[test]
+    fn crossing_routes_are_uncrossed() {
+        // the square 0, 1, 2, 3 with long diagonals
+        let graph = symmetric(
+            vec![
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (2, 3, 1.0),
+                (3, 0, 1.0),
+                (0, 2, 3.0),
+                (1, 3, 3.0),
+            ],
+            4,
+        );
+        let keep = (0..4).collect();
+        let route = Solution::from_nodes(vec![0, 2, 1, 3, 0]);
+        let (shortened, length) = shorten_route(&route, &graph, &keep).unwrap();
+        assert_eq!(length, R64::from_inner(4.0));
+        assert_eq!(shortened.unique_nodes().len(), 4);
+    }
+
+    #[test]
+    fn misplaced_nodes_are_moved() {
+        // a line 0 - 1 - 2 - 3, where node 3 is visited on the way out instead of at the end
+        let graph = symmetric(vec![(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0), (0, 3, 5.0)], 4);
+        let keep = (0..4).collect();
+        let route = Solution::from_nodes(vec![0, 3, 2, 1, 2, 3, 0]);
+        let (shortened, length) = shorten_route(&route, &graph, &keep).unwrap();
+        assert!(length < R64::from_inner(12.0));
+        assert!(shortened.visits_all(&keep));
+    }
+
+    #[test]
+    fn detours_are_removed_unless_they_visit_kept_nodes() {
+        let graph = symmetric(vec![(0, 1, 1.0), (1, 2, 1.0), (1, 3, 1.0)], 4);
+        let route = Solution::from_nodes(vec![0, 1, 2, 1, 3, 1, 0]);
+
+        let keep = vec![3].into_iter().collect();
+        let (shortened, length) = shorten_route(&route, &graph, &keep).unwrap();
+        assert_eq!(shortened.nodes(), vec![0, 1, 3, 1, 0]);
+        assert_eq!(length, R64::from_inner(4.0));
+
+        let keep = vec![2, 3].into_iter().collect();
+        assert_eq!(shorten_route(&route, &graph, &keep), None);
+    }
+
+    #[test]
+    fn routes_on_missing_edges_are_not_changed() {
+        let graph = graph(vec![(0, 1, 1.0)], 2);
+        let route = Solution::from_nodes(vec![0, 1, 0]);
+        assert_eq!(shorten_route(&route, &graph, &HashSet::new()), None);
+    }
+}
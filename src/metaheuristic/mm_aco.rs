@@ -1,22 +1,27 @@
 mod params;
 
-pub use params::Params;
+pub use params::{Params, TauBounds};
 
-use crate::graph::{GenericWeightedGraph, MatrixGraph};
-use crate::metaheuristic::aco::{Ant, Message, Supervisor};
+use crate::graph::{GenericWeightedGraph, StochasticEdges};
+use crate::metaheuristic::aco::{
+    deposit_pheromone, initial_pheromones, reprune_pheromones, Ant, ConstructionLimits, Diversity,
+    Message, Pheromones, SolutionArchive, Supervisor,
+};
+use crate::metaheuristic::supervisor::Phase;
 use crate::metaheuristic::{
-    solution_length, solution_score, Heuristic, Metaheuristic, ProblemInstance, Solution,
+    rescore, GoalPaths, Heuristic, IterationCallback, Metaheuristic, Objective, Observer,
+    ProblemInstance, Reoptimize, RevisitPenalty, SecondaryBudget, ServiceTimes, Solution,
+    TimeWindows,
 };
-use crate::rng::rng64;
+use crate::rng::{Rng, RngState};
 use crate::util::{Distance, SmallVal};
 
 use decorum::R64;
 use num_traits::identities::{One, Zero};
-use oorandom::Rand64;
 use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::io::Write;
@@ -31,9 +36,10 @@ where
     Ew: Serialize + Add<Output = Ew>,
 {
     graph: &'a RefCell<
-        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>,
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + 'a,
     >,
-    pheromone_matrix: MatrixGraph<IndexType, (), R64>,
+    pheromone_matrix: Pheromones<IndexType>,
     goal_point: IndexType,
     max_time: Ew,
     heuristic: &'a Heuristic<Nw, Ew>,
@@ -41,14 +47,35 @@ where
     beta: f64,
     rho: f64,
     ant_count: usize,
+    vehicle_count: usize,
+    time_windows: TimeWindows<IndexType, Ew>,
+    mandatory_nodes: HashSet<IndexType>,
+    stochastic_edges: StochasticEdges<IndexType>,
+    revisit_penalty: RevisitPenalty,
+    service_times: ServiceTimes<IndexType, Ew>,
+    secondary_budget: Option<SecondaryBudget<IndexType, Ew>>,
+    objective: Objective,
+    construction_limits: ConstructionLimits,
+    bidirectional: bool,
     p_best: f64,
-    avg_options: usize,
+    initial_pheromone: R64,
+    tau_bounds: TauBounds,
+    /// Pheromone updates done so far, to end the warm-up phase of tau_bounds.
+    updates: usize,
+    /// Average amount of edges an ant chooses among, which scales tau_min.
+    avg_options: f64,
+    /// Set if avg_options was given in the params instead of taken from the graph.
+    fixed_avg_options: bool,
     best_solution: Solution<IndexType>,
     best_score: R64,
     best_length: Ew,
+    // length and score of every route of the best solution
+    best_routes: Vec<(Ew, R64)>,
+    best_missing: usize,
     pub supervisor: Supervisor<W, Nw, Ew>,
-    rng: Rand64,
-    inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
+    observer: Observer<'a, IndexType, Ew>,
+    rng: Rng,
+    goal_paths: GoalPaths<IndexType>,
 }
 
 impl<'a, IndexType, Nw, W> MMAco<'a, IndexType, Nw, R64, W>
@@ -58,31 +85,51 @@ where
     W: Write,
 {
     fn pheromone_update(&mut self, solution: &Solution<IndexType>, solution_score: R64) {
-        let to_add = R64::one() - R64::one() / solution_score;
-        let tau_max = R64::from_inner(1.0 / (1.0 - self.rho)) * (R64::one() / self.best_score);
+        // routes without score can be the best ones, when the objective isn't the score
+        let to_add = if solution_score > R64::zero() {
+            R64::one() - R64::one() / solution_score
+        } else {
+            R64::zero()
+        };
+        let best_score = if self.best_score > R64::zero() {
+            self.best_score
+        } else {
+            R64::one()
+        };
+        let tau_max = match self.tau_bounds.max {
+            Some(tau_max) => R64::from_inner(tau_max),
+            None => R64::from_inner(1.0 / (1.0 - self.rho)) * (R64::one() / best_score),
+        };
         let root_term = self.p_best.powf(1.0 / self.pheromone_matrix.order() as f64);
-        let mut tau_min = (tau_max * R64::from_inner(1.0 - root_term))
-            / R64::from_inner((self.avg_options - 1) as f64 * root_term);
+        let mut tau_min = match self.tau_bounds.min {
+            Some(tau_min) => R64::from_inner(tau_min),
+            // with at most one option per node, there is no choice for tau_min to keep open
+            None if self.avg_options > 1.0 => {
+                (tau_max * R64::from_inner(1.0 - root_term))
+                    / R64::from_inner((self.avg_options - 1.0) * root_term)
+            }
+            None => tau_max,
+        };
         if tau_min > tau_max {
             tau_min = tau_max;
         }
 
         // pheromone decay
-        for edge in self.pheromone_matrix.edge_ids() {
-            let weight = *self.pheromone_matrix.edge_weight(edge).unwrap();
-            let mut new_weight = R64::from_inner(1.0 - self.rho) * weight;
-            if new_weight < tau_min {
-                new_weight = tau_min;
-            } else if new_weight > tau_max {
-                new_weight = tau_max;
-            }
-            let _res = self.pheromone_matrix.change_edge(edge, new_weight);
+        self.pheromone_matrix.decay(self.rho);
+        self.updates += 1;
+        if self.updates <= self.tau_bounds.warm_up {
+            deposit_pheromone(&mut self.pheromone_matrix, solution, to_add);
+            return;
         }
+        self.pheromone_matrix.clamp(tau_min, tau_max);
 
         // adding best solution
         for (from, to) in solution.iter_unique_edges() {
-            let weight = *self.pheromone_matrix.edge_weight((*from, *to)).unwrap();
-            let mut new_weight = weight + to_add;
+            let weight = match self.pheromone_matrix.edge_weight_mut((*from, *to)) {
+                Ok(weight) => weight,
+                Err(_) => continue,
+            };
+            let mut new_weight = *weight + to_add;
             // fit weight to interval specified by max and min
             if new_weight < tau_min {
                 new_weight = tau_min;
@@ -90,15 +137,53 @@ where
                 new_weight = tau_max;
             }
 
-            let _res = self.pheromone_matrix.change_edge((*from, *to), new_weight);
+            *weight = new_weight;
         }
     }
 
+    /// Seeds the pheromone matrix with an initial solution, adding strength times the initial
+    /// pheromone level to each of its edges. The first pheromone update after the warm-up of the
+    /// tau bounds clamps the levels to the MMAS bounds again.
+    pub fn warm_start(&mut self, solution: &Solution<IndexType>, strength: f64) {
+        deposit_pheromone(
+            &mut self.pheromone_matrix,
+            solution,
+            R64::from_inner(strength) * self.initial_pheromone,
+        );
+    }
+
     pub fn set_inv_shortest_paths(
         &mut self,
         inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, R64)>>,
     ) {
-        self.inv_shortest_paths = inv_shortest_paths
+        self.goal_paths.set_inv_shortest_paths(
+            &*self.graph.borrow(),
+            self.goal_point,
+            inv_shortest_paths,
+        );
+    }
+
+    /// Returns the state of the rng, from which the next iteration draws its random numbers.
+    pub fn rng_state(&self) -> RngState {
+        self.rng.state()
+    }
+
+    pub fn pheromones(&self) -> &Pheromones<IndexType> {
+        &self.pheromone_matrix
+    }
+
+    pub fn current_solution(&self) -> (&Solution<IndexType>, R64, R64) {
+        (&self.best_solution, self.best_score, self.best_length)
+    }
+
+    /// Returns the length and score of every route of the best solution, a single route has the
+    /// length and score of the solution.
+    pub fn best_routes(&self) -> Vec<(R64, R64)> {
+        if self.vehicle_count == 1 {
+            vec![(self.best_length, self.best_score)]
+        } else {
+            self.best_routes.clone()
+        }
     }
 }
 
@@ -116,14 +201,15 @@ where
         supervisor: Self::SupervisorType,
     ) -> Self {
         let graph = problem.graph.borrow();
-        let pheromones = MatrixGraph::new(
-            graph.iter_node_ids().map(|id| (id, ())).collect(),
-            graph
-                .iter_edge_ids()
-                .map(|edge| (edge, R64::from_inner(1.0)))
-                .collect(),
-        )
-        .unwrap();
+        // sampled routes can be shorter than the shortest paths, so stochastic edges prevent pruning
+        let pheromones = initial_pheromones(
+            &*graph,
+            problem.goal_point,
+            problem.max_time,
+            &params.inv_shortest_paths,
+            problem.stochastic_edges.variances.is_empty(),
+            R64::from_inner(params.initial_pheromone),
+        );
 
         MMAco {
             graph: problem.graph,
@@ -135,22 +221,49 @@ where
             beta: params.beta,
             rho: params.rho,
             ant_count: params.ant_count,
+            vehicle_count: problem.vehicle_count,
+            time_windows: problem.time_windows,
+            best_missing: problem.mandatory_nodes.len(),
+            mandatory_nodes: problem.mandatory_nodes,
+            stochastic_edges: problem.stochastic_edges,
+            revisit_penalty: problem.revisit_penalty,
+            service_times: problem.service_times,
+            secondary_budget: problem.secondary_budget,
+            objective: problem.objective,
+            construction_limits: params.construction_limits,
+            bidirectional: params.bidirectional,
             p_best: params.p_best,
-            avg_options: graph.order() / 2,
+            initial_pheromone: R64::from_inner(params.initial_pheromone),
+            tau_bounds: params.tau_bounds,
+            updates: 0,
+            avg_options: params
+                .avg_options
+                .unwrap_or_else(|| average_out_degree(&*graph)),
+            fixed_avg_options: params.avg_options.is_some(),
             best_solution: Solution::new(),
             best_score: R64::one(),
             best_length: R64::zero(),
+            best_routes: Vec::new(),
             supervisor,
-            rng: rng64(params.seed),
-            inv_shortest_paths: params.inv_shortest_paths,
+            observer: Observer::default(),
+            rng: Rng::new(params.rng, params.seed),
+            goal_paths: GoalPaths::new(
+                &*problem.graph.borrow(),
+                problem.goal_point,
+                params.inv_shortest_paths,
+                problem.return_costs.into_inner(),
+                params.return_paths,
+            ),
         }
     }
 
     fn single_iteration(&mut self) -> Option<&Solution<IndexType>> {
+        let pheromones_before = self.pheromone_matrix.snapshot();
+        let construction_start = Instant::now();
         let mut ants = Vec::with_capacity(self.ant_count);
-        for _ in 0..self.ant_count {
-            let (sender, id) = self.supervisor.new_ant();
-            ants.push(Ant::new(
+        for id in 1..=self.ant_count {
+            let sender = self.supervisor.sender();
+            let ant = Ant::new(
                 self.graph,
                 &self.pheromone_matrix,
                 self.goal_point,
@@ -162,14 +275,41 @@ where
                 0.0,
                 sender,
                 id,
-                &self.inv_shortest_paths,
-            ));
+                &self.goal_paths.inv_shortest_paths,
+                &self.goal_paths.return_costs,
+            )
+            .with_goal_distances(&self.goal_paths.goal_distances)
+            .with_vehicle_count(self.vehicle_count)
+            .with_time_windows(&self.time_windows)
+            .with_mandatory_nodes(&self.mandatory_nodes)
+            .with_stochastic_edges(&self.stochastic_edges)
+            .with_revisit_penalty(self.revisit_penalty)
+            .with_service_times(&self.service_times)
+            .with_construction_limits(self.construction_limits)
+            .with_bidirectional(self.bidirectional)
+            .with_secondary_budget(self.secondary_budget.as_ref());
+            ants.push(match self.goal_paths.return_paths() {
+                Some(return_paths) => ant.with_return_paths(return_paths),
+                None => ant,
+            });
         }
 
         let mut solutions = Vec::new();
         for ant in ants {
             solutions.push(ant.get_solution())
         }
+        self.supervisor
+            .add_time(Phase::Construction, construction_start.elapsed());
+        for warning in solutions
+            .iter()
+            .filter_map(|ant_solution| ant_solution.warning)
+        {
+            self.supervisor.warn(warning);
+        }
+        let diversity = Diversity::new(
+            &self.pheromone_matrix,
+            solutions.iter().map(|ant_solution| &ant_solution.solution),
+        );
 
         let start_time = Instant::now();
         let mut best_length = R64::zero();
@@ -178,9 +318,48 @@ where
         let mut visited_nodes = 0;
         let mut visited_with_val = 0;
         let mut val_sum = R64::zero();
+        let mut route_lengths = Vec::new();
+        let mut route_scores = Vec::new();
+        let mut secondary_resource = R64::zero();
+        let mut best_missing = self.mandatory_nodes.len();
         let mut improvements = 0;
+        let mut archive = SolutionArchive::new();
+        let mut infeasible = 0;
+        let mut over_budget = 0;
+        let mut overshoot = R64::zero();
         for ant_solution in solutions.into_iter() {
-            if ant_solution.length <= self.max_time && ant_solution.score > best_score {
+            // bidirectionally constructed routes are only valid, if they were closed at the goal point
+            let feasible = ant_solution.solution.is_closed_tour(self.goal_point)
+                && ant_solution
+                    .budget_lengths
+                    .iter()
+                    .all(|&length| length <= self.max_time)
+                && ant_solution.fits_secondary_budget(self.secondary_budget.as_ref());
+            if !feasible {
+                infeasible += 1;
+                let exceeded = ant_solution.overshoot(self.max_time);
+                if exceeded > R64::zero() {
+                    over_budget += 1;
+                    overshoot += exceeded;
+                }
+            }
+            // a repeated route is scored like the first ant constructing it, so it can't improve
+            if !archive.insert(&ant_solution.solution) {
+                continue;
+            }
+            let missing = ant_solution
+                .solution
+                .missing_nodes(&self.mandatory_nodes)
+                .len();
+            if feasible
+                && self
+                    .objective
+                    .improves_empty(ant_solution.score, ant_solution.length)
+                && self.objective.replaces_best(
+                    (missing, ant_solution.score, ant_solution.length),
+                    (best_missing, best_score, best_length),
+                )
+            {
                 improvements += 1;
                 best_score = ant_solution.score;
                 best_length = ant_solution.length;
@@ -188,11 +367,27 @@ where
                 visited_nodes = ant_solution.visited_nodes;
                 val_sum = ant_solution.val_sum;
                 visited_with_val = ant_solution.visited_with_val;
+                route_lengths = ant_solution.route_lengths;
+                route_scores = ant_solution.route_scores;
+                secondary_resource = ant_solution.secondary_resource;
+                best_missing = missing;
             }
         }
 
         let duration = start_time.elapsed();
-        let _ = self.supervisor.sender.send(Message::new(
+        self.supervisor.add_time(Phase::Scoring, duration);
+        self.supervisor.log_route(
+            &best_solution,
+            best_score.into_inner(),
+            best_length.into_inner(),
+        );
+        let longest_route = route_lengths
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_else(R64::zero);
+        // sent after the pheromone update, to include the change of the pheromones
+        let message = Message::new(
             0,
             0,
             0,
@@ -205,20 +400,137 @@ where
             visited_nodes,
             visited_with_val,
             val_sum,
-        )); // Ant 0 is always supervisor
-        self.supervisor.prepare_next();
+        )
+        .with_routes(route_lengths.len(), longest_route)
+        .with_diversity(diversity)
+        .with_duplicates(archive.duplicates())
+        .with_infeasible(infeasible, over_budget, overshoot.into_inner())
+        .with_secondary_resource(secondary_resource.into_inner());
 
+        let update_start = Instant::now();
         self.pheromone_update(&best_solution, best_score);
-        if best_score > self.best_score
-            || best_length < self.best_length && best_score == self.best_score
-        {
+        self.supervisor
+            .add_time(Phase::PheromoneUpdate, update_start.elapsed());
+        let pheromone_change = self
+            .pheromone_matrix
+            .change_since(&pheromones_before)
+            .unwrap_or_default();
+        let _ = self
+            .supervisor
+            .sender()
+            .send(message.with_pheromone_change(pheromone_change)); // Ant 0 is always supervisor
+        if self.objective.replaces_best(
+            (best_missing, best_score, best_length),
+            (self.best_missing, self.best_score, self.best_length),
+        ) {
             // println!("solution improved");
             self.best_solution = best_solution;
             self.best_score = best_score;
             self.best_length = best_length;
+            self.best_routes = route_lengths.iter().copied().zip(route_scores).collect();
+            self.best_missing = best_missing;
+            self.observer
+                .notify(true, &self.best_solution, self.best_score, self.best_length);
 
             return Some(&self.best_solution);
         }
+        self.observer.notify(
+            false,
+            &self.best_solution,
+            self.best_score,
+            self.best_length,
+        );
         None
     }
+
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, R64>) {
+        self.observer.set(callback);
+    }
+}
+
+impl<'a, IndexType, W> Reoptimize<IndexType> for MMAco<'a, IndexType, R64, R64, W>
+where
+    IndexType: Distance<IndexType> + Copy + PartialEq + Debug + Hash + Eq + Display + Ord,
+    W: Write,
+{
+    fn graph_changed(&mut self) {
+        self.goal_paths
+            .recompute(&*self.graph.borrow(), self.goal_point);
+        self.pheromone_matrix = reprune_pheromones(
+            &self.pheromone_matrix,
+            &*self.graph.borrow(),
+            self.goal_point,
+            self.max_time,
+            &self.goal_paths.inv_shortest_paths,
+            self.stochastic_edges.variances.is_empty(),
+            self.initial_pheromone,
+        );
+        if !self.fixed_avg_options {
+            self.avg_options = average_out_degree(&*self.graph.borrow());
+        }
+        let (score, length) = rescore(
+            &self.best_solution,
+            self.graph,
+            self.heuristic,
+            self.max_time,
+            &self.time_windows,
+            &self.revisit_penalty,
+            &self.service_times,
+        );
+        self.best_score = score;
+        self.best_length = length;
+    }
+
+    fn best_score(&self) -> R64 {
+        self.best_score
+    }
+
+    fn best_length(&self) -> R64 {
+        self.best_length
+    }
+
+    fn best_solution(&self) -> &Solution<IndexType> {
+        &self.best_solution
+    }
+
+    fn rng_state(&self) -> RngState {
+        MMAco::rng_state(self)
+    }
+}
+
+/// Returns the average amount of outgoing edges of the nodes of graph, 0 for empty graphs.
+fn average_out_degree<G>(graph: &G) -> f64
+where
+    G: GenericWeightedGraph + ?Sized,
+{
+    match graph.order() {
+        0 => 0.0,
+        order => graph.size() as f64 / order as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+
+    #[test]
+    fn average_out_degree_counts_directed_edges() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![R64::one(); 4],
+            vec![
+                (0, 1, R64::one()),
+                (1, 0, R64::one()),
+                (1, 2, R64::one()),
+                (2, 3, R64::one()),
+                (3, 0, R64::one()),
+                (3, 2, R64::one()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(average_out_degree(&graph), 1.5);
+        let empty =
+            MatrixGraph::<usize, R64, R64>::new_usize_indexed(Vec::new(), Vec::new()).unwrap();
+        assert_eq!(average_out_degree(&empty), 0.0);
+    }
 }
@@ -1,16 +1,39 @@
+use crate::metaheuristic::aco::ConstructionLimits;
 use crate::metaheuristic::{Heuristic, Solution};
-use crate::rng::os_random_seed;
+use crate::rng::{os_random_seed, RngKind};
 
 use std::collections::BTreeMap;
 
+/// Explicit pheromone bounds, which replace the bounds derived from the best score, and the
+/// amount of iterations before any bounds are applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TauBounds {
+    pub max: Option<f64>,
+    pub min: Option<f64>,
+    /// Iterations, whose pheromone updates are not clamped to the bounds.
+    pub warm_up: usize,
+}
+
 pub struct Params<'a, IndexType, Nw, Ew> {
     pub heuristic: &'a Heuristic<Nw, Ew>,
     pub alpha: f64,
     pub beta: f64,
     pub rho: f64,
     pub seed: u128,
+    pub rng: RngKind,
+    pub construction_limits: ConstructionLimits,
+    /// Lets the ants grow their routes at both ends instead of walking them from the goal point.
+    pub bidirectional: bool,
+    /// Amount of shortest paths back to the goal point, among which the ants choose.
+    pub return_paths: usize,
     pub ant_count: usize,
     pub p_best: f64,
+    /// Pheromone level of every edge before the first iteration.
+    pub initial_pheromone: f64,
+    pub tau_bounds: TauBounds,
+    /// Average amount of options per ant move used for tau_min, the average out-degree of the
+    /// graph if None.
+    pub avg_options: Option<f64>,
     pub inv_shortest_paths: BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
 }
 
@@ -31,9 +54,62 @@ impl<'a, IndexType, Nw, Ew> Params<'a, IndexType, Nw, Ew> {
             beta,
             rho,
             seed: seed.unwrap_or_else(os_random_seed),
+            rng: RngKind::default(),
+            construction_limits: ConstructionLimits::default(),
+            bidirectional: false,
+            return_paths: 1,
             ant_count,
             p_best,
+            initial_pheromone: 1.0,
+            tau_bounds: TauBounds::default(),
+            avg_options: None,
             inv_shortest_paths,
         }
     }
+
+    /// Draws the random numbers of the algorithm with kind instead of pcg.
+    pub fn with_rng(mut self, kind: RngKind) -> Self {
+        self.rng = kind;
+        self
+    }
+
+    /// Caps the moves of each ant and keeps them from moving straight back to recently left nodes.
+    pub fn with_construction_limits(mut self, limits: ConstructionLimits) -> Self {
+        self.construction_limits = limits;
+        self
+    }
+
+    /// Lets the ants construct their routes from both ends, joining them by the shortest path
+    /// between them once neither end can grow within the time budget.
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    /// Lets the ants return along any of the k shortest paths to the goal point, which still
+    /// fits into the time budget, once they stop collecting. The paths are chosen by their
+    /// pheromone levels, 1 keeps the shortest path only.
+    pub fn with_return_paths(mut self, k: usize) -> Self {
+        self.return_paths = k;
+        self
+    }
+
+    /// Computes tau_min with avg_options options per ant move instead of the average out-degree
+    /// of the graph, e.g. to see how sensitive the results are to the lower pheromone bound.
+    pub fn with_avg_options(mut self, avg_options: f64) -> Self {
+        self.avg_options = Some(avg_options);
+        self
+    }
+
+    /// Starts every edge with the pheromone level instead of 1.
+    pub fn with_initial_pheromone(mut self, level: f64) -> Self {
+        self.initial_pheromone = level;
+        self
+    }
+
+    /// Overrides the derived pheromone bounds and delays clamping to them by a warm-up phase.
+    pub fn with_tau_bounds(mut self, bounds: TauBounds) -> Self {
+        self.tau_bounds = bounds;
+        self
+    }
 }
@@ -0,0 +1,241 @@
+use crate::graph::{GenericWeightedGraph, MatrixGraph};
+use crate::metaheuristic::ServiceTimes;
+use crate::util::SmallVal;
+
+use decorum::R64;
+use num_traits::Zero;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, Sub};
+
+/// Values of nodes, which can consist of multiple resources, like the reward of a node and the
+/// time it takes to collect it. Values of visited nodes are summed up with Zero and Add.
+pub trait NodeValue: Copy + Zero {
+    /// Projects the value onto the reward collected by visiting the node, which scores and
+    /// reports use.
+    fn reward(&self) -> R64;
+
+    /// Returns the time spent at the node to collect its reward, which uses up budget like
+    /// traversing an edge, see service_times.
+    fn service_time(&self) -> R64 {
+        R64::zero()
+    }
+}
+
+impl NodeValue for R64 {
+    fn reward(&self) -> R64 {
+        *self
+    }
+}
+
+/// Node value of N resources, the first is the reward and the second the service time.
+/// Further resources are carried along and summed up, so heuristics can use them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeVector<const N: usize>(pub [R64; N]);
+
+impl<const N: usize> NodeValue for NodeVector<N> {
+    fn reward(&self) -> R64 {
+        self.0.first().copied().unwrap_or_else(R64::zero)
+    }
+
+    fn service_time(&self) -> R64 {
+        self.0.get(1).copied().unwrap_or_else(R64::zero)
+    }
+}
+
+impl<const N: usize> Add for NodeVector<N> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<const N: usize> AddAssign for NodeVector<N> {
+    fn add_assign(&mut self, other: Self) {
+        for (value, other) in self.0.iter_mut().zip(other.0) {
+            *value += other;
+        }
+    }
+}
+
+impl<const N: usize> Sub for NodeVector<N> {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        for (value, other) in self.0.iter_mut().zip(other.0) {
+            *value -= other;
+        }
+        self
+    }
+}
+
+impl<const N: usize> Zero for NodeVector<N> {
+    fn zero() -> Self {
+        NodeVector([R64::zero(); N])
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(Zero::is_zero)
+    }
+}
+
+impl<const N: usize> Default for NodeVector<N> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+/// A reward of one without any other resources.
+impl<const N: usize> SmallVal for NodeVector<N> {
+    fn small() -> Self {
+        let mut small = Self::zero();
+        if let Some(reward) = small.0.first_mut() {
+            *reward = R64::small();
+        }
+        small
+    }
+}
+
+/// Reports of the algorithms only contain the reward.
+impl<const N: usize> From<NodeVector<N>> for f64 {
+    fn from(value: NodeVector<N>) -> f64 {
+        value.reward().into_inner()
+    }
+}
+
+/// Returns the service times of the nodes of graph, which have one. TwoSwap takes them from
+/// the node values itself, metaheuristics with scalar node weights like Aco are passed them
+/// with ProblemInstance::with_service_times together with the rewards.
+pub fn service_times<G, IndexType, Nw>(graph: &G) -> ServiceTimes<IndexType, R64>
+where
+    G: GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw> + ?Sized,
+    IndexType: Hash + Copy + Eq,
+    Nw: NodeValue,
+{
+    graph
+        .iter_nodes()
+        .map(|(id, value)| (id, value.service_time()))
+        .filter(|(_, service_time)| !service_time.is_zero())
+        .collect()
+}
+
+/// Returns a copy of graph, whose node weights are the rewards of the node values, for the
+/// metaheuristics with scalar node weights like Aco. The service times are kept by passing
+/// service_times of the original graph to the instance.
+pub fn rewards<G, IndexType, Nw>(graph: &G) -> MatrixGraph<IndexType, R64, R64>
+where
+    G: GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = R64>
+        + ?Sized,
+    IndexType: Hash + Copy + Eq + Display + Debug + Ord,
+    Nw: NodeValue,
+{
+    let mut rewards = MatrixGraph::with_size(graph.order());
+    for (id, value) in graph.iter_nodes() {
+        // Unwrapping is fine, because the ids of graph are unique.
+        rewards.add_node(id, value.reward()).unwrap();
+    }
+    for (edge, weight) in graph.iter_edges() {
+        rewards.add_edge(edge, *weight).unwrap();
+    }
+    rewards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metaheuristic::{aco, two_swap, Aco, Metaheuristic, ProblemInstance, TwoSwap};
+
+    use std::cell::RefCell;
+    use std::io;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    fn v(reward: f64, service_time: f64) -> NodeVector<2> {
+        NodeVector([r(reward), r(service_time)])
+    }
+
+    /// Node 1 has the highest reward, but collecting it takes longer than the budget allows.
+    fn graph() -> MatrixGraph<usize, NodeVector<2>, R64> {
+        let edges = [(0, 1), (1, 0), (0, 2), (2, 0), (1, 2), (2, 1)];
+        MatrixGraph::new_usize_indexed(
+            vec![v(0.0, 0.0), v(5.0, 10.0), v(3.0, 1.0)],
+            edges.iter().map(|&(from, to)| (from, to, r(1.0))).collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn node_vectors_are_summed_per_resource() {
+        let sum = v(1.0, 2.0) + v(3.0, 0.5);
+        assert_eq!(sum, v(4.0, 2.5));
+        assert_eq!(sum - v(4.0, 0.0), v(0.0, 2.5));
+        assert_eq!((sum.reward(), sum.service_time()), (r(4.0), r(2.5)));
+        assert!(NodeVector::<2>::zero().is_zero());
+        assert_eq!(NodeVector::<2>::small(), v(1.0, 0.0));
+        assert_eq!(f64::from(sum), 4.0);
+        assert_eq!(NodeVector([r(2.0)]).service_time(), r(0.0));
+    }
+
+    #[test]
+    fn service_times_are_taken_from_node_values() {
+        let service_times = service_times(&graph());
+        assert_eq!(service_times.len(), 2);
+        assert_eq!(service_times[&1], r(10.0));
+
+        let rewards = rewards(&graph());
+        assert_eq!(*rewards.edge_weight((2, 1)).unwrap(), r(1.0));
+        assert_eq!(*rewards.node_weight(1).unwrap(), r(5.0));
+    }
+
+    #[test]
+    fn two_swap_spends_budget_on_service_times() {
+        let graph = RefCell::new(graph());
+        let heuristic = |value: NodeVector<2>, _: R64, _: R64, _: R64| {
+            value.reward() / (r(1.0) + value.service_time())
+        };
+        let mut two_swap = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, r(5.0)),
+            two_swap::Params::new(&heuristic),
+            two_swap::Supervisor::new(1, io::sink()),
+        );
+        while two_swap.single_iteration().is_some() {}
+
+        let (solution, score, length) = two_swap.current_solution();
+        assert_eq!(solution.nodes(), vec![0, 2, 0]);
+        assert_eq!((score, length), (r(1.5), r(3.0)));
+    }
+
+    #[test]
+    fn aco_spends_budget_on_service_times() {
+        let service_times = service_times(&graph());
+        let graph = rewards(&graph());
+        let inv_shortest_paths = graph.inv_shortest_paths(0);
+        let graph = RefCell::new(graph);
+        let heuristic = |reward: R64, _: R64, _: R64, _: R64| reward;
+        let mut aco = Aco::new(
+            ProblemInstance::new(&graph, 0, r(5.0)).with_service_times(service_times),
+            aco::Params::new(
+                &heuristic,
+                1.0,
+                1.0,
+                0.1,
+                0.5,
+                Some(1),
+                5,
+                inv_shortest_paths,
+            ),
+            aco::Supervisor::new(1, io::sink()),
+        );
+        for _ in 0..10 {
+            aco.single_iteration();
+        }
+
+        let (solution, _, length) = aco.current_solution();
+        assert!(!solution.nodes().contains(&1));
+        assert!(length <= r(5.0));
+    }
+}
@@ -0,0 +1,121 @@
+use decorum::R64;
+use num_traits::Zero;
+
+/// What the metaheuristics optimize, solutions are compared by their value.
+/// Solutions missing fewer mandatory nodes are still preferred regardless of the objective.
+/// The objective only decides, which solutions are kept as best ones, the ants still deposit
+/// pheromone by the rules of their algorithm.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Objective {
+    /// Collects the highest score within the time budget.
+    #[default]
+    MaximizeScore,
+    /// Finds the shortest route, which visits at least one node besides the goal point.
+    /// Together with mandatory nodes, this is a shortest tour problem.
+    MinimizeLength,
+    /// Maximizes score_weight * score - length_weight * length.
+    Weighted {
+        score_weight: R64,
+        length_weight: R64,
+    },
+}
+
+impl Objective {
+    /// Returns the value of a solution with score and length, higher values are better.
+    /// When minimizing the length, solutions of length zero only contain the goal point and
+    /// have no value, so every route is better than them.
+    pub fn value(&self, score: R64, length: R64) -> Option<R64> {
+        match *self {
+            Self::MaximizeScore => Some(score),
+            Self::MinimizeLength if length > R64::zero() => Some(-length),
+            Self::MinimizeLength => None,
+            Self::Weighted {
+                score_weight,
+                length_weight,
+            } => Some(score_weight * score - length_weight * length),
+        }
+    }
+
+    /// Returns true if a solution with score and length is better than one with best_score and
+    /// best_length, or false otherwise.
+    pub fn improves(&self, score: R64, length: R64, best_score: R64, best_length: R64) -> bool {
+        self.value(score, length) > self.value(best_score, best_length)
+    }
+
+    /// Returns true if a solution with score and length is better than the solution only
+    /// containing the goal point, or false otherwise.
+    pub fn improves_empty(&self, score: R64, length: R64) -> bool {
+        self.improves(score, length, R64::zero(), R64::zero())
+    }
+
+    /// Returns true if both solutions have the same value, but the first one is shorter.
+    /// Such solutions replace the best one just like better ones.
+    pub fn shortens(&self, score: R64, length: R64, best_score: R64, best_length: R64) -> bool {
+        self.value(score, length) == self.value(best_score, best_length) && length < best_length
+    }
+
+    /// Returns true if a solution with score and length, which misses missing mandatory nodes,
+    /// should replace the best one, because it misses fewer of them, has a higher value or has
+    /// the same value on a shorter route.
+    pub fn replaces_best(
+        &self,
+        (missing, score, length): (usize, R64, R64),
+        (best_missing, best_score, best_length): (usize, R64, R64),
+    ) -> bool {
+        missing < best_missing
+            || missing == best_missing
+                && (self.improves(score, length, best_score, best_length)
+                    || self.shortens(score, length, best_score, best_length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn maximizing_prefers_higher_scores() {
+        let objective = Objective::MaximizeScore;
+        assert!(objective.improves(r(3.0), r(10.0), r(2.0), r(1.0)));
+        assert!(!objective.improves(r(2.0), r(1.0), r(2.0), r(5.0)));
+        assert!(objective.shortens(r(2.0), r(1.0), r(2.0), r(5.0)));
+        assert!(!objective.improves_empty(r(0.0), r(4.0)));
+    }
+
+    #[test]
+    fn minimizing_prefers_shorter_routes() {
+        let objective = Objective::MinimizeLength;
+        assert!(objective.improves(r(0.0), r(2.0), r(5.0), r(3.0)));
+        assert!(!objective.improves(r(5.0), r(3.0), r(0.0), r(2.0)));
+        // every route beats the solution, which only contains the goal point
+        assert!(objective.improves_empty(r(0.0), r(4.0)));
+        assert!(!objective.improves_empty(r(0.0), r(0.0)));
+    }
+
+    #[test]
+    fn weighted_objectives_trade_score_for_length() {
+        let objective = Objective::Weighted {
+            score_weight: r(1.0),
+            length_weight: r(0.5),
+        };
+        assert_eq!(objective.value(r(4.0), r(2.0)), Some(r(3.0)));
+        assert!(objective.improves(r(4.0), r(2.0), r(5.0), r(6.0)));
+        assert!(!objective.improves_empty(r(1.0), r(2.0)));
+    }
+
+    #[test]
+    fn ties_are_broken_by_missing_nodes_and_length() {
+        let objective = Objective::MaximizeScore;
+        // same score on a shorter route
+        assert!(objective.replaces_best((0, r(2.0), r(3.0)), (0, r(2.0), r(5.0))));
+        assert!(!objective.replaces_best((0, r(2.0), r(5.0)), (0, r(2.0), r(5.0))));
+        assert!(!objective.replaces_best((0, r(2.0), r(6.0)), (0, r(2.0), r(5.0))));
+        // fewer missing mandatory nodes beat any value
+        assert!(objective.replaces_best((0, r(1.0), r(9.0)), (1, r(5.0), r(2.0))));
+        assert!(!objective.replaces_best((1, r(5.0), r(2.0)), (0, r(1.0), r(9.0))));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::metaheuristic::Solution;
+
+use decorum::R64;
+
+/// State of a metaheuristic after one of its iterations, see Metaheuristic::set_callback.
+#[derive(Debug)]
+pub struct IterationEvent<'s, IndexType, Ew> {
+    /// Index of the iteration, counted from 0 for the first call of single_iteration.
+    pub iteration: usize,
+    /// Whether the iteration found a better solution.
+    pub improved: bool,
+    pub best_score: R64,
+    pub best_length: Ew,
+    pub best_solution: &'s Solution<IndexType>,
+}
+
+/// Callback, which is called with the event of every iteration.
+pub type IterationCallback<'a, IndexType, Ew> = Box<dyn FnMut(&IterationEvent<IndexType, Ew>) + 'a>;
+
+/// Counts the iterations of a metaheuristic and passes their events to its callback, if it
+/// has one.
+pub struct Observer<'a, IndexType, Ew> {
+    callback: Option<IterationCallback<'a, IndexType, Ew>>,
+    iteration: usize,
+}
+
+impl<'a, IndexType, Ew> Default for Observer<'a, IndexType, Ew> {
+    fn default() -> Self {
+        Observer {
+            callback: None,
+            iteration: 0,
+        }
+    }
+}
+
+impl<'a, IndexType, Ew> Observer<'a, IndexType, Ew> {
+    /// Replaces the callback, later iterations are passed to the new one.
+    pub fn set(&mut self, callback: IterationCallback<'a, IndexType, Ew>) {
+        self.callback = Some(callback);
+    }
+
+    /// Passes the state after the current iteration to the callback and counts the iteration.
+    pub fn notify(
+        &mut self,
+        improved: bool,
+        best_solution: &Solution<IndexType>,
+        best_score: R64,
+        best_length: Ew,
+    ) {
+        if let Some(callback) = self.callback.as_mut() {
+            callback(&IterationEvent {
+                iteration: self.iteration,
+                improved,
+                best_score,
+                best_length,
+                best_solution,
+            });
+        }
+        self.iteration += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    #[test]
+    fn events_are_passed_to_the_callback() {
+        let events = RefCell::new(Vec::new());
+        let mut observer = Observer::default();
+        let solution = Solution::from_nodes(vec![0, 1, 0]);
+        observer.notify(true, &solution, R64::from_inner(1.0), 2.0);
+
+        observer.set(Box::new(|event: &IterationEvent<usize, f64>| {
+            events
+                .borrow_mut()
+                .push((event.iteration, event.improved, event.best_solution.nodes()))
+        }));
+        observer.notify(false, &solution, R64::from_inner(1.0), 2.0);
+        drop(observer);
+
+        assert_eq!(events.into_inner(), vec![(1, false, vec![0, 1, 0])]);
+    }
+}
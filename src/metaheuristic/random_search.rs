@@ -4,17 +4,19 @@ mod supervisor;
 
 pub use message::Message;
 pub use params::Params;
-pub use supervisor::Supervisor;
+pub use supervisor::{Record, Supervisor};
 
 use crate::graph::GenericWeightedGraph;
-use crate::metaheuristic::{Heuristic, Metaheuristic, ProblemInstance, Solution};
-use crate::rng::rng64;
+use crate::metaheuristic::supervisor::Phase;
+use crate::metaheuristic::{
+    ConstructionWarning, Heuristic, IterationCallback, Metaheuristic, Objective, Observer,
+    ProblemInstance, ReturnCosts, Solution,
+};
+use crate::rng::{Rng, RngState};
 use crate::util::Distance;
 
 use decorum::R64;
 use num_traits::identities::Zero;
-use oorandom::Rand64;
-use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
 use std::collections::BTreeMap;
@@ -26,30 +28,27 @@ use std::iter::Sum;
 use std::ops::{Add, AddAssign, Div, Sub, SubAssign};
 use std::time::{Duration, Instant};
 
-pub struct RandomSearch<
-    'a,
-    IndexType,
-    NodeWeightType: Serialize + Default,
-    EdgeWeightType: Serialize + Default,
-    W: Write,
-> {
+pub struct RandomSearch<'a, IndexType, NodeWeightType: Default, EdgeWeightType: Default, W: Write> {
     graph: &'a RefCell<
         dyn GenericWeightedGraph<
-            IndexType = IndexType,
-            NodeWeightType = NodeWeightType,
-            EdgeWeightType = EdgeWeightType,
-        >,
+                IndexType = IndexType,
+                NodeWeightType = NodeWeightType,
+                EdgeWeightType = EdgeWeightType,
+            > + 'a,
     >,
     goal_point: IndexType,
     heuristic: &'a Heuristic<NodeWeightType, EdgeWeightType>,
     max_time: EdgeWeightType,
+    objective: Objective,
     pub best_solution: Solution<IndexType>,
     pub best_score: R64,
     pub best_length: EdgeWeightType,
     pub supervisor: Supervisor<W, NodeWeightType, EdgeWeightType>,
+    observer: Observer<'a, IndexType, EdgeWeightType>,
     i: usize,
     inv_shortest_paths: &'a BTreeMap<IndexType, Option<(Solution<IndexType>, EdgeWeightType)>>,
-    rng: Rand64,
+    return_costs: ReturnCosts<IndexType, EdgeWeightType>,
+    rng: Rng,
 }
 
 impl<'a, IndexType, NodeWeightType, EdgeWeightType, W>
@@ -60,7 +59,7 @@ where
         + Debug
         + Add<Output = NodeWeightType>
         + Sub<Output = NodeWeightType>
-        + Serialize
+        + Into<f64>
         + Default
         + Zero
         + AddAssign<NodeWeightType>
@@ -75,10 +74,15 @@ where
         + Sum
         + Div<Output = EdgeWeightType>
         + Default
-        + Serialize
+        + Into<f64>
         + Debug,
     W: Write,
 {
+    /// Returns the state of the rng, from which the next iteration draws its random numbers.
+    pub fn rng_state(&self) -> RngState {
+        self.rng.state()
+    }
+
     pub fn current_solution(&self) -> (&Solution<IndexType>, R64, EdgeWeightType) {
         (&self.best_solution, self.best_score, self.best_length)
     }
@@ -88,6 +92,7 @@ where
         self.supervisor.aggregate_receive();
     }
 
+    /// Sends the message of solution to the supervisor and returns its heuristic score.
     fn send_message(
         &self,
         iteration: usize,
@@ -97,7 +102,7 @@ where
         cpu_time: Duration,
         distance: EdgeWeightType,
         solution: &Solution<IndexType>,
-    ) {
+    ) -> R64 {
         let tx = self.supervisor.sender();
 
         let g_borrow = self.graph.borrow();
@@ -152,58 +157,30 @@ where
             val_sum,
         ))
         .unwrap();
+
+        heuristic_score
     }
 
     pub fn generate(&mut self, start_time: Instant) {
-        let mut visited: BTreeMap<IndexType, bool> = BTreeMap::new();
-        let mut length = EdgeWeightType::zero();
-        let mut solution = Solution::from_nodes(vec![self.goal_point]);
-        let mut goal_reached = false;
-        let mut next_node = self.goal_point;
-        while !goal_reached {
-            let viable_candidates: Vec<_> = self
-                .graph
-                .borrow()
-                .iter_neighbor_ids(next_node)
-                .unwrap()
-                .filter(|node| {
-                    if let Some((_, weight)) = &self.inv_shortest_paths[node] {
-                        let &weight_to =
-                            self.graph.borrow().edge_weight((next_node, *node)).unwrap();
-                        if length + *weight + weight_to <= self.max_time {
-                            return true;
-                        }
-                    }
-
-                    false
-                })
-                .collect();
-
-            // as soon as we have no more candidates to travel to we can just take our calculated shortest path
-            if viable_candidates.is_empty() {
-                // if we added the path even when we have reached the goal point we get it twice at the end of the solution
-                if next_node != self.goal_point {
-                    let (mut path, distance) = self.inv_shortest_paths[&next_node].clone().unwrap();
-                    solution.append(&mut path);
-                    length += distance;
-                }
-                goal_reached = true;
-                break;
-            }
-
-            let rand = (viable_candidates.len() as f64 * self.rng.rand_float()) as usize;
-            let new_next_node = viable_candidates[rand];
-            length += *self
-                .graph
-                .borrow()
-                .edge_weight((next_node, new_next_node))
-                .unwrap();
-            solution.push_node(new_next_node);
-            visited.insert(new_next_node, true);
-            next_node = new_next_node;
+        let (solution, length) = Solution::random_feasible_with_paths(
+            &*self.graph.borrow(),
+            self.goal_point,
+            self.max_time,
+            self.inv_shortest_paths,
+            &self.return_costs,
+            &mut self.rng,
+        )
+        .expect("The goal point is not in the graph.");
+        if solution.nodes().len() == 1 {
+            self.supervisor
+                .warn(ConstructionWarning::NoFeasibleMove(self.goal_point));
         }
 
-        self.send_message(
+        self.supervisor
+            .add_time(Phase::Construction, start_time.elapsed());
+
+        let scoring_start = Instant::now();
+        let score = self.send_message(
             self.i,
             solution.nodes().len(),
             0,
@@ -212,6 +189,20 @@ where
             length,
             &solution,
         );
+        self.supervisor
+            .log_route(&solution, score.into_inner(), length.into());
+        if self.objective.improves(
+            score,
+            R64::from_inner(length.into()),
+            self.best_score,
+            R64::from_inner(self.best_length.into()),
+        ) {
+            self.best_solution = solution;
+            self.best_score = score;
+            self.best_length = length;
+        }
+        self.supervisor
+            .add_time(Phase::Scoring, scoring_start.elapsed());
 
         self.i += 1;
     }
@@ -225,7 +216,7 @@ where
         + Debug
         + Add<Output = Nw>
         + Sub<Output = Nw>
-        + Serialize
+        + Into<f64>
         + Default
         + Zero
         + AddAssign<Nw>
@@ -240,7 +231,7 @@ where
         + Sum
         + Div<Output = Ew>
         + Default
-        + Serialize
+        + Into<f64>
         + Debug,
     W: Write,
 {
@@ -257,13 +248,19 @@ where
             goal_point: problem.goal_point,
             max_time: problem.max_time,
             heuristic: params.heuristic,
+            objective: problem.objective,
             best_solution: Solution::new(),
             best_score: R64::zero(),
             best_length: Ew::zero(),
             supervisor,
+            observer: Observer::default(),
             i: 0,
             inv_shortest_paths: params.inv_shortest_paths,
-            rng: rng64(params.seed),
+            return_costs: problem
+                .return_costs
+                .into_inner()
+                .unwrap_or_else(|| ReturnCosts::from_paths(params.inv_shortest_paths)),
+            rng: Rng::new(params.rng, params.seed),
         }
     }
 
@@ -271,7 +268,14 @@ where
         let start_time = Instant::now();
         let prev_best = self.best_solution.clone();
         self.generate(start_time);
-        if self.best_solution != prev_best {
+        let improved = self.best_solution != prev_best;
+        self.observer.notify(
+            improved,
+            &self.best_solution,
+            self.best_score,
+            self.best_length,
+        );
+        if improved {
             Some(&self.best_solution)
         } else {
             self.send_message(
@@ -288,6 +292,10 @@ where
             None
         }
     }
+
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, Ew>) {
+        self.observer.set(callback);
+    }
 }
 
 impl<'a, IndexType, Nw, Ew, W> Iterator for RandomSearch<'a, IndexType, Nw, Ew, W>
@@ -297,7 +305,7 @@ where
         + Debug
         + Add<Output = Nw>
         + Sub<Output = Nw>
-        + Serialize
+        + Into<f64>
         + Default
         + Zero
         + AddAssign<Nw>
@@ -312,7 +320,7 @@ where
         + Sum
         + Div<Output = Ew>
         + Default
-        + Serialize
+        + Into<f64>
         + Debug,
     W: Write,
 {
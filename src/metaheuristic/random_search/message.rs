@@ -2,9 +2,10 @@ use crate::metaheuristic::supervisor;
 use crate::metaheuristic::supervisor::MessageInfo;
 
 use decorum::R64;
-use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::time::Duration;
 
+/// Sent once per iteration to the supervisor, which sums them up into a Record per
+/// aggregation window.
 #[derive(Debug)]
 pub struct Message<Nw, Ew> {
     pub iteration: usize,
@@ -48,27 +49,12 @@ impl<Nw, Ew> Message<Nw, Ew> {
             collected_val,
         }
     }
-
-    pub fn from_info(iteration: usize, info: MessageInfo<Nw, Ew>) -> Self {
-        Self {
-            iteration,
-            evaluations: info.evaluations,
-            n_improvements: info.n_improvements,
-            changes: info.changes,
-            phase: info.phase,
-            cpu_time: info.cpu_time,
-            distance: info.distance,
-            heuristic_score: info.heuristic_score,
-            visited_nodes: info.visited_nodes,
-            visited_nodes_with_val: info.visited_nodes_with_val,
-            collected_val: info.collected_val,
-        }
-    }
 }
 
 impl<Nw: Copy, Ew: Copy> supervisor::Message for Message<Nw, Ew> {
     type EwType = Ew;
     type NwType = Nw;
+
     fn get_info(&self) -> MessageInfo<Nw, Ew> {
         MessageInfo::new(
             self.evaluations,
@@ -83,42 +69,12 @@ impl<Nw: Copy, Ew: Copy> supervisor::Message for Message<Nw, Ew> {
             self.collected_val,
         )
     }
-}
 
-impl<Nw: Serialize, Ew: Serialize> Serialize for Message<Nw, Ew> {
-    default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 11 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 8)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance)?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val)?;
-        state.end()
+    fn iteration(&self) -> Option<usize> {
+        Some(self.iteration)
     }
-}
 
-impl Serialize for Message<R64, R64> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 12 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 8)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance.into_inner())?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val.into_inner())?;
-        state.end()
+    fn completes_iteration(&self) -> bool {
+        true
     }
 }
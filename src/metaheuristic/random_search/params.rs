@@ -1,4 +1,5 @@
 use crate::metaheuristic::{Heuristic, Solution};
+use crate::rng::RngKind;
 
 use std::collections::BTreeMap;
 
@@ -6,6 +7,7 @@ pub struct Params<'a, IndexType, Nw, Ew> {
     pub heuristic: &'a Heuristic<Nw, Ew>,
     pub inv_shortest_paths: &'a BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
     pub seed: u128,
+    pub rng: RngKind,
 }
 
 impl<'a, IndexType, Nw, Ew> Params<'a, IndexType, Nw, Ew> {
@@ -18,6 +20,13 @@ impl<'a, IndexType, Nw, Ew> Params<'a, IndexType, Nw, Ew> {
             heuristic,
             inv_shortest_paths,
             seed,
+            rng: RngKind::default(),
         }
     }
+
+    /// Draws the random numbers of the algorithm with kind instead of pcg.
+    pub fn with_rng(mut self, kind: RngKind) -> Self {
+        self.rng = kind;
+        self
+    }
 }
@@ -0,0 +1,233 @@
+use crate::graph::{GenericWeightedGraph, ShortestPaths};
+use crate::metaheuristic::Solution;
+use crate::util::Distance;
+
+use decorum::R64;
+use num_traits::Zero;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// The minimal cost of returning from each node to the goal point, so checking whether a node
+/// can still be visited within the remaining budget takes a single lookup.
+/// Has to be rebuilt whenever edge weights of the graph change.
+#[derive(Debug, Clone)]
+pub struct ReturnCosts<IndexType, Ew> {
+    costs: HashMap<IndexType, Ew>,
+}
+
+impl<IndexType, Ew> ReturnCosts<IndexType, Ew>
+where
+    IndexType: Copy + Hash + Eq,
+    Ew: Copy,
+{
+    /// Takes the costs from the shortest paths to the goal point, as returned by
+    /// inv_shortest_paths. Nodes without a path are left out.
+    pub fn from_paths(
+        inv_shortest_paths: &BTreeMap<IndexType, Option<(Solution<IndexType>, Ew)>>,
+    ) -> Self {
+        ReturnCosts {
+            costs: inv_shortest_paths
+                .iter()
+                .filter_map(|(node, path)| path.as_ref().map(|(_, cost)| (*node, *cost)))
+                .collect(),
+        }
+    }
+
+    /// Returns the minimal cost of returning from node to the goal point,
+    /// or None if the goal point can't be reached from it.
+    pub fn get(&self, node: IndexType) -> Option<Ew> {
+        self.costs.get(&node).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+}
+
+/// The distance of each node to the goal point, as passed to the heuristic, so constructing a
+/// route doesn't compute it again for every candidate. Geodesic for index types with a
+/// distance, the cost of the shortest path to the goal point otherwise.
+/// Has to be rebuilt together with the return costs.
+#[derive(Debug, Clone)]
+pub struct GoalDistances<IndexType> {
+    distances: HashMap<IndexType, R64>,
+}
+
+impl<IndexType> GoalDistances<IndexType>
+where
+    IndexType: Distance<IndexType> + Copy + Hash + Eq,
+{
+    pub fn new<G>(
+        graph: &G,
+        goal_point: IndexType,
+        return_costs: &ReturnCosts<IndexType, R64>,
+    ) -> Self
+    where
+        G: GenericWeightedGraph<IndexType = IndexType> + ?Sized,
+    {
+        let distances = if IndexType::GEODESIC {
+            graph
+                .iter_node_ids()
+                .map(|node| (node, IndexType::distance(goal_point, node)))
+                .collect()
+        } else {
+            return_costs.costs.clone()
+        };
+        GoalDistances { distances }
+    }
+
+    /// Returns the distance of node to the goal point, zero if it can't reach the goal point.
+    pub fn get(&self, node: IndexType) -> R64 {
+        self.distances.get(&node).copied().unwrap_or_else(R64::zero)
+    }
+}
+
+/// Up to k shortest paths from each node to the goal point, ordered by their length.
+/// Lets ants choose among several ways back, once they run out of time.
+pub type ReturnPaths<IndexType, Ew> = BTreeMap<IndexType, Vec<(Solution<IndexType>, Ew)>>;
+
+/// Computes the k shortest paths from every node of graph to the goal point.
+/// Nodes without a path are left out. Has to be recomputed whenever edge weights change.
+pub fn return_paths<G>(
+    graph: &G,
+    goal_point: G::IndexType,
+    k: usize,
+) -> ReturnPaths<G::IndexType, G::EdgeWeightType>
+where
+    G: GenericWeightedGraph + ?Sized,
+    G::IndexType: Copy + Ord,
+{
+    graph
+        .iter_node_ids()
+        .map(|node| (node, graph.k_shortest_paths(node, goal_point, k)))
+        .filter(|(_, paths)| !paths.is_empty())
+        .collect()
+}
+
+/// The ways back to the goal point, which guide the ants of every colony: the shortest paths,
+/// the return costs and goal distances taken from them and, if ants choose among several, the
+/// return_path_count shortest return paths. Recomputed as a whole whenever the graph changes.
+#[derive(Debug, Clone)]
+pub struct GoalPaths<IndexType> {
+    pub inv_shortest_paths: ShortestPaths<IndexType, R64>,
+    pub return_costs: ReturnCosts<IndexType, R64>,
+    pub goal_distances: GoalDistances<IndexType>,
+    pub return_paths: ReturnPaths<IndexType, R64>,
+    pub return_path_count: usize,
+}
+
+impl<IndexType> GoalPaths<IndexType>
+where
+    IndexType: Distance<IndexType> + Copy + Hash + Eq + Ord,
+{
+    /// Takes the shortest paths and the return costs of the problem instance, which are taken
+    /// from the shortest paths if the instance has none yet. Return paths are only computed for
+    /// a return_path_count larger than 1.
+    pub fn new<G>(
+        graph: &G,
+        goal_point: IndexType,
+        inv_shortest_paths: ShortestPaths<IndexType, R64>,
+        return_costs: Option<ReturnCosts<IndexType, R64>>,
+        return_path_count: usize,
+    ) -> Self
+    where
+        G: GenericWeightedGraph<IndexType = IndexType, EdgeWeightType = R64> + ?Sized,
+    {
+        let return_costs =
+            return_costs.unwrap_or_else(|| ReturnCosts::from_paths(&inv_shortest_paths));
+        let return_paths = if return_path_count > 1 {
+            return_paths(graph, goal_point, return_path_count)
+        } else {
+            ReturnPaths::new()
+        };
+        GoalPaths {
+            goal_distances: GoalDistances::new(graph, goal_point, &return_costs),
+            inv_shortest_paths,
+            return_costs,
+            return_paths,
+            return_path_count,
+        }
+    }
+
+    /// Replaces the shortest paths together with the return costs and goal distances.
+    pub fn set_inv_shortest_paths<G>(
+        &mut self,
+        graph: &G,
+        goal_point: IndexType,
+        inv_shortest_paths: ShortestPaths<IndexType, R64>,
+    ) where
+        G: GenericWeightedGraph<IndexType = IndexType, EdgeWeightType = R64> + ?Sized,
+    {
+        self.return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        self.goal_distances = GoalDistances::new(graph, goal_point, &self.return_costs);
+        self.inv_shortest_paths = inv_shortest_paths;
+    }
+
+    /// Recomputes all paths after edge weights of graph changed.
+    pub fn recompute<G>(&mut self, graph: &G, goal_point: IndexType)
+    where
+        G: GenericWeightedGraph<IndexType = IndexType, EdgeWeightType = R64> + ?Sized,
+    {
+        self.set_inv_shortest_paths(graph, goal_point, graph.inv_shortest_paths(goal_point));
+        if self.return_path_count > 1 {
+            self.return_paths = return_paths(graph, goal_point, self.return_path_count);
+        }
+    }
+
+    /// Returns the paths among which ants choose their way back, None if they always take the
+    /// shortest one.
+    pub fn return_paths(&self) -> Option<&ReturnPaths<IndexType, R64>> {
+        (self.return_path_count > 1).then_some(&self.return_paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GenericWeightedGraph, MatrixGraph};
+
+    use decorum::R64;
+
+    #[test]
+    fn unreachable_nodes_have_no_cost() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![R64::from_inner(0.0); 4],
+            vec![
+                (0, 1, R64::from_inner(2.0)),
+                (1, 0, R64::from_inner(3.0)),
+                (1, 2, R64::from_inner(1.0)),
+                (2, 1, R64::from_inner(1.0)),
+                (0, 3, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        let costs = ReturnCosts::from_paths(&graph.inv_shortest_paths(0));
+
+        assert_eq!(costs.get(1), Some(R64::from_inner(3.0)));
+        assert_eq!(costs.get(2), Some(R64::from_inner(4.0)));
+        assert_eq!(costs.get(3), None);
+    }
+
+    #[test]
+    fn goal_distances_follow_the_graph_without_geodesic_distances() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![R64::from_inner(0.0); 3],
+            vec![
+                (0, 1, R64::from_inner(2.0)),
+                (1, 0, R64::from_inner(3.0)),
+                (0, 2, R64::from_inner(1.0)),
+            ],
+        )
+        .unwrap();
+        let costs = ReturnCosts::from_paths(&graph.inv_shortest_paths(0));
+        let distances = GoalDistances::new(&graph, 0, &costs);
+
+        assert_eq!(distances.get(0), R64::from_inner(0.0));
+        assert_eq!(distances.get(1), R64::from_inner(3.0));
+        assert_eq!(distances.get(2), R64::from_inner(0.0));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::graph::Edge;
+
+use decorum::R64;
+use num_traits::{One, Zero};
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Penalties for visiting a node or traversing an edge again, which are subtracted from the score.
+/// Edges are treated as undirected here, so going back along an edge counts as a revisit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RevisitPenalty {
+    pub node: R64,
+    pub edge: R64,
+}
+
+impl Default for RevisitPenalty {
+    fn default() -> Self {
+        RevisitPenalty {
+            node: R64::zero(),
+            edge: R64::zero(),
+        }
+    }
+}
+
+impl RevisitPenalty {
+    pub fn new(node: R64, edge: R64) -> Self {
+        RevisitPenalty { node, edge }
+    }
+
+    /// Returns the sum of penalties that apply to a move with the given revisits.
+    pub fn of(&self, node_revisited: bool, edge_revisited: bool) -> R64 {
+        let mut penalty = R64::zero();
+        if node_revisited {
+            penalty += self.node;
+        }
+        if edge_revisited {
+            penalty += self.edge;
+        }
+
+        penalty
+    }
+
+    /// Returns the factor 1 / (1 + penalty), by which ants scale the desirability of a move.
+    /// Unlike the score, desirabilities have to stay positive, scores still subtract the
+    /// penalty like every other algorithm does.
+    pub fn discount(&self, node_revisited: bool, edge_revisited: bool) -> R64 {
+        R64::one() / (R64::one() + self.of(node_revisited, edge_revisited))
+    }
+}
+
+/// Returns true if edge has been traversed before in any direction, or false otherwise.
+pub fn edge_traversed<IndexType: Hash + Eq + Copy>(
+    traversed: &HashSet<Edge<IndexType>>,
+    (from, to): Edge<IndexType>,
+) -> bool {
+    traversed.contains(&(from, to)) || traversed.contains(&(to, from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn penalties_add_up() {
+        let penalty = RevisitPenalty::new(R64::from_inner(2.0), R64::from_inner(1.0));
+
+        assert_eq!(penalty.of(false, false), 0.0);
+        assert_eq!(penalty.of(true, false), 2.0);
+        assert_eq!(penalty.of(true, true), 3.0);
+        assert_eq!(penalty.discount(true, true), 0.25);
+        assert_eq!(RevisitPenalty::default().discount(true, true), 1.0);
+    }
+
+    #[test]
+    fn edges_are_undirected() {
+        let mut traversed = HashSet::new();
+        traversed.insert((0, 1));
+
+        assert!(edge_traversed(&traversed, (0, 1)));
+        assert!(edge_traversed(&traversed, (1, 0)));
+        assert!(!edge_traversed(&traversed, (1, 2)));
+    }
+}
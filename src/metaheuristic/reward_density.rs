@@ -0,0 +1,105 @@
+use std::fmt;
+
+/// A rewarded node with the length of the shortest round trip from the goal point visiting it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardNode<IndexType, Nw, Ew> {
+    pub node: IndexType,
+    pub reward: Nw,
+    pub round_trip: Ew,
+}
+
+impl<IndexType, Nw, Ew> RewardNode<IndexType, Nw, Ew>
+where
+    Nw: Copy + Into<f64>,
+    Ew: Copy + Into<f64>,
+{
+    /// Returns the reward per cost of the round trip, which is infinite for free round trips.
+    pub fn density(&self) -> f64 {
+        self.reward.into() / self.round_trip.into()
+    }
+}
+
+/// Distribution of the reward per round trip cost over the rewarded nodes, which shows how
+/// much of the reward is cheap to collect and how selective a route has to be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DensityStats {
+    /// Rewarded nodes with a round trip from the goal point.
+    pub nodes: usize,
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl DensityStats {
+    /// Returns the statistics of the densities of nodes, or None if there are no nodes.
+    pub fn of<IndexType, Nw, Ew>(nodes: &[RewardNode<IndexType, Nw, Ew>]) -> Option<Self>
+    where
+        Nw: Copy + Into<f64>,
+        Ew: Copy + Into<f64>,
+    {
+        let mut densities: Vec<f64> = nodes.iter().map(RewardNode::density).collect();
+        if densities.is_empty() {
+            return None;
+        }
+        densities.sort_by(|a, b| a.total_cmp(b));
+        let count = densities.len();
+        Some(DensityStats {
+            nodes: count,
+            min: densities[0],
+            // both indices are the middle one for odd counts
+            median: (densities[(count - 1) / 2] + densities[count / 2]) / 2.0,
+            mean: densities.iter().sum::<f64>() / count as f64,
+            max: densities[count - 1],
+        })
+    }
+}
+
+/// Densities are shown with two decimals, unless another precision is given.
+impl fmt::Display for DensityStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(2);
+        write!(
+            f,
+            "reward per round trip cost of {} nodes: min {:.*}, median {:.*}, mean {:.*}, max {:.*}",
+            self.nodes,
+            precision,
+            self.min,
+            precision,
+            self.median,
+            precision,
+            self.mean,
+            precision,
+            self.max
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn densities_are_summarized() {
+        let node = |node, reward, round_trip| RewardNode {
+            node,
+            reward,
+            round_trip,
+        };
+        let nodes = vec![node(1, 4.0, 2.0), node(2, 1.0, 4.0), node(3, 6.0, 2.0)];
+        let stats = DensityStats::of(&nodes).unwrap();
+        assert_eq!(stats.nodes, 3);
+        assert_eq!(stats.min, 0.25);
+        assert_eq!(stats.median, 2.0);
+        assert_eq!(stats.mean, 1.75);
+        assert_eq!(stats.max, 3.0);
+        assert_eq!(
+            format!("{:.1}", stats),
+            "reward per round trip cost of 3 nodes: min 0.2, median 2.0, mean 1.8, max 3.0"
+        );
+
+        let stats = DensityStats::of(&nodes[..2]).unwrap();
+        assert_eq!(stats.median, 1.125);
+        assert_eq!(DensityStats::of::<usize, f64, f64>(&[]), None);
+    }
+}
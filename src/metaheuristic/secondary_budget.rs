@@ -0,0 +1,102 @@
+use crate::graph::Edge;
+use crate::metaheuristic::Solution;
+use crate::rng::{gen_float_range, Rng};
+
+use decorum::R64;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Add;
+
+/// A second resource like energy, which traversing edges consumes next to time, together with
+/// its own budget. Edges without a consumption don't use up any of it.
+#[derive(Debug, Clone)]
+pub struct SecondaryBudget<IndexType, T> {
+    consumption: HashMap<Edge<IndexType>, T>,
+    budget: T,
+}
+
+impl<IndexType, T> SecondaryBudget<IndexType, T>
+where
+    IndexType: Copy + Hash + Eq,
+    T: Copy + Zero + Add<Output = T> + PartialOrd,
+{
+    pub fn new(consumption: HashMap<Edge<IndexType>, T>, budget: T) -> Self {
+        SecondaryBudget {
+            consumption,
+            budget,
+        }
+    }
+
+    pub fn budget(&self) -> T {
+        self.budget
+    }
+
+    /// Returns the amount of the resource consumed by traversing edge.
+    pub fn consumption(&self, edge: Edge<IndexType>) -> T {
+        self.consumption.get(&edge).copied().unwrap_or_else(T::zero)
+    }
+
+    /// Returns the amount of the resource consumed along all edges of solution.
+    pub fn of_route(&self, solution: &Solution<IndexType>) -> T {
+        solution.iter_edges().fold(T::zero(), |sum, (&from, &to)| {
+            sum + self.consumption((from, to))
+        })
+    }
+
+    /// Returns true if consumed stays within the budget, or false otherwise.
+    pub fn fits(&self, consumed: T) -> bool {
+        consumed <= self.budget
+    }
+}
+
+/// Draws the consumption of every given edge from range.
+pub fn random_consumption<IndexType: Hash + Eq>(
+    edges: impl Iterator<Item = Edge<IndexType>>,
+    rng: &mut Rng,
+    range: (f64, f64),
+) -> HashMap<Edge<IndexType>, R64> {
+    edges
+        .map(|edge| {
+            (
+                edge,
+                R64::from_inner(gen_float_range(rng, range.0..range.1)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::rng64;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn routes_consume_along_their_edges() {
+        let budget = SecondaryBudget::new([((0, 1), r(2.0)), ((1, 0), r(3.0))].into(), r(5.0));
+        assert_eq!(budget.consumption((1, 2)), r(0.0));
+        assert_eq!(
+            budget.of_route(&Solution::from_nodes(vec![0, 1, 0])),
+            r(5.0)
+        );
+        assert!(budget.fits(r(5.0)));
+        assert!(!budget.fits(r(5.5)));
+    }
+
+    #[test]
+    fn consumption_is_drawn_from_range() {
+        let consumption = random_consumption(
+            vec![(0, 1), (1, 0), (1, 2)].into_iter(),
+            &mut rng64(1),
+            (1.0, 2.0),
+        );
+        assert_eq!(consumption.len(), 3);
+        assert!(consumption
+            .values()
+            .all(|&consumed| consumed >= r(1.0) && consumed < r(2.0)));
+    }
+}
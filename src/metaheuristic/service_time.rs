@@ -0,0 +1,136 @@
+use crate::metaheuristic::time_window::{arrives_in_time, TimeWindows};
+use crate::rng::{gen_float_range, Rng};
+
+use decorum::R64;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Time spent at a node to collect its reward, nodes without one are only passed.
+pub type ServiceTimes<IndexType, T> = HashMap<IndexType, T>;
+
+/// Assigns a service time drawn from range to each of the given nodes with the given
+/// probability.
+pub fn random_service_times<IndexType: Hash + Eq>(
+    nodes: impl Iterator<Item = IndexType>,
+    rng: &mut Rng,
+    probability: f64,
+    range: (f64, f64),
+) -> ServiceTimes<IndexType, R64> {
+    let mut service_times = HashMap::new();
+    for node in nodes {
+        if rng.rand_float() < probability {
+            let duration = gen_float_range(rng, range.0..range.1);
+            service_times.insert(node, R64::from_inner(duration));
+        }
+    }
+
+    service_times
+}
+
+/// Returns the service time spent at node when arriving at arrival, first_arrival tells if the
+/// node wasn't visited before. Service is only spent by the visit collecting the reward of a
+/// node, which is its first arrival within its time window, so later visits only pass it.
+pub fn service_on_arrival<IndexType, T>(
+    service_times: &ServiceTimes<IndexType, T>,
+    time_windows: &TimeWindows<IndexType, T>,
+    first_arrival: bool,
+    node: &IndexType,
+    arrival: T,
+) -> T
+where
+    IndexType: Hash + Eq,
+    T: Copy + Zero + PartialOrd,
+{
+    match service_times.get(node) {
+        Some(&service_time) if first_arrival && arrives_in_time(time_windows, node, arrival) => {
+            service_time
+        }
+        _ => T::zero(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use crate::metaheuristic::{
+        solution_length_with_service_times, solution_score_with_service_times,
+        solution_score_with_time_windows, RevisitPenalty, Solution, TimeWindow,
+    };
+    use crate::rng::rng64;
+
+    use std::cell::RefCell;
+
+    fn r(value: f64) -> R64 {
+        R64::from_inner(value)
+    }
+
+    #[test]
+    fn service_times_are_only_spent_when_collecting() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![r(0.0), r(2.0), r(3.0)],
+            vec![
+                (0, 1, r(1.0)),
+                (1, 2, r(1.0)),
+                (2, 0, r(1.0)),
+                (1, 0, r(1.0)),
+            ],
+        )
+        .unwrap();
+        let graph = RefCell::new(graph);
+        let service_times: ServiceTimes<usize, R64> = [(1, r(2.0)), (2, r(0.5))].into();
+        let length = |nodes: Vec<usize>, time_windows: &TimeWindows<usize, R64>| {
+            solution_length_with_service_times(
+                &Solution::from_nodes(nodes),
+                &graph,
+                &service_times,
+                time_windows,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(length(vec![0, 1, 2, 0], &TimeWindows::new()), r(5.5));
+        // the second visit of 1 only passes it
+        assert_eq!(length(vec![0, 1, 0, 1, 2, 0], &TimeWindows::new()), r(7.5));
+        // 2 is reached after its window closed, so its reward and service are skipped
+        let time_windows: TimeWindows<usize, R64> = [(2, TimeWindow::new(r(0.0), r(2.0)))].into();
+        assert_eq!(length(vec![0, 1, 2, 0], &time_windows), r(5.0));
+
+        let heuristic = |value: R64, _: R64, _: R64, _: R64| value;
+        let score = |nodes: Vec<usize>| {
+            solution_score_with_service_times(
+                &Solution::from_nodes(nodes),
+                &graph,
+                &heuristic,
+                &time_windows,
+                &RevisitPenalty::default(),
+                &service_times,
+            )
+            .unwrap()
+        };
+        // without the service at 1, 2 would be reached in time
+        assert_eq!(score(vec![0, 1, 2, 0]), r(2.0));
+        assert_eq!(
+            solution_score_with_time_windows(
+                &Solution::from_nodes(vec![0, 1, 2, 0]),
+                &graph,
+                &heuristic,
+                &time_windows
+            ),
+            Ok(r(5.0))
+        );
+    }
+
+    #[test]
+    fn service_times_are_drawn_from_range() {
+        let mut rng = rng64(3);
+        let service_times = random_service_times(0..100, &mut rng, 0.5, (1.0, 2.0));
+
+        assert!(service_times.len() > 20 && service_times.len() < 80);
+        assert!(service_times
+            .values()
+            .all(|&duration| duration >= r(1.0) && duration < r(2.0)));
+        assert!(random_service_times(0..10, &mut rng, 0.0, (1.0, 2.0)).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::metaheuristic::Solution;
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// How similar two routes are, to compare the solutions of different algorithms on the same
+/// instance or the best routes of one run before and after a change of the graph.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Similarity {
+    pub edge_overlap: f64,
+    pub jaccard: f64,
+    pub levenshtein: usize,
+}
+
+impl Similarity {
+    pub fn between<IndexType: Copy + Hash + Eq>(
+        first: &Solution<IndexType>,
+        second: &Solution<IndexType>,
+    ) -> Self {
+        Similarity {
+            edge_overlap: edge_overlap(first, second),
+            jaccard: jaccard(first, second),
+            levenshtein: levenshtein(first, second),
+        }
+    }
+}
+
+/// Returns the share of distinct edges, which both routes use, relative to the route with more
+/// distinct edges. Edges are directed, so a reversed route shares no edges with the original.
+/// Two routes without edges are identical.
+pub fn edge_overlap<IndexType: Copy + Hash + Eq>(
+    first: &Solution<IndexType>,
+    second: &Solution<IndexType>,
+) -> f64 {
+    let first: HashSet<_> = first.iter_unique_edges().collect();
+    let second: HashSet<_> = second.iter_unique_edges().collect();
+    let larger = first.len().max(second.len());
+    if larger == 0 {
+        return 1.0;
+    }
+    first.intersection(&second).count() as f64 / larger as f64
+}
+
+/// Returns the Jaccard index of the sets of nodes both routes visit, the amount of shared nodes
+/// relative to the amount of nodes visited by either. Two empty routes are identical.
+pub fn jaccard<IndexType: Copy + Hash + Eq>(
+    first: &Solution<IndexType>,
+    second: &Solution<IndexType>,
+) -> f64 {
+    let first: HashSet<_> = first.iter_unique_nodes().collect();
+    let second: HashSet<_> = second.iter_unique_nodes().collect();
+    let union = first.union(&second).count();
+    if union == 0 {
+        return 1.0;
+    }
+    first.intersection(&second).count() as f64 / union as f64
+}
+
+/// Returns the least amount of node insertions, removals and replacements, which turn the node
+/// sequence of first into the one of second.
+pub fn levenshtein<IndexType: Copy + Hash + Eq>(
+    first: &Solution<IndexType>,
+    second: &Solution<IndexType>,
+) -> usize {
+    let second: Vec<IndexType> = second.iter_nodes().copied().collect();
+    // distances from the prefix of first seen so far to all prefixes of second
+    let mut distances: Vec<usize> = (0..=second.len()).collect();
+    for (i, node) in first.iter_nodes().enumerate() {
+        let mut diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, other) in second.iter().enumerate() {
+            let replaced = diagonal + usize::from(node != other);
+            diagonal = distances[j + 1];
+            distances[j + 1] = replaced.min(distances[j] + 1).min(diagonal + 1);
+        }
+    }
+    distances[second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(nodes: &[usize]) -> Solution<usize> {
+        Solution::from_nodes(nodes.to_vec())
+    }
+
+    #[test]
+    fn identical_routes_are_similar() {
+        let similarity = Similarity::between(&route(&[0, 1, 2, 0]), &route(&[0, 1, 2, 0]));
+        assert_eq!(
+            similarity,
+            Similarity {
+                edge_overlap: 1.0,
+                jaccard: 1.0,
+                levenshtein: 0,
+            }
+        );
+        assert_eq!(Similarity::between(&route(&[]), &route(&[])).jaccard, 1.0);
+    }
+
+    #[test]
+    fn measures_differ_for_reversed_routes() {
+        let first = route(&[0, 1, 2, 0]);
+        let reversed = route(&[0, 2, 1, 0]);
+        assert_eq!(edge_overlap(&first, &reversed), 0.0);
+        assert_eq!(jaccard(&first, &reversed), 1.0);
+        assert_eq!(levenshtein(&first, &reversed), 2);
+    }
+
+    #[test]
+    fn partial_overlaps_are_measured() {
+        let first = route(&[0, 1, 2, 3, 0]);
+        let second = route(&[0, 1, 2, 0]);
+        // 0 -> 1 and 1 -> 2 are shared out of the 4 edges of the first route
+        assert_eq!(edge_overlap(&first, &second), 0.5);
+        assert_eq!(jaccard(&first, &second), 0.75);
+        assert_eq!(levenshtein(&first, &second), 1);
+        assert_eq!(levenshtein(&route(&[]), &second), 4);
+    }
+}
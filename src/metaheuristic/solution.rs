@@ -1,26 +1,30 @@
 use crate::graph::{Edge, GenericWeightedGraph, GraphError};
-use crate::metaheuristic::Heuristic;
+use crate::metaheuristic::revisit_penalty::{edge_traversed, RevisitPenalty};
+use crate::metaheuristic::service_time::{service_on_arrival, ServiceTimes};
+use crate::metaheuristic::time_window::{arrives_in_time, TimeWindows};
+use crate::metaheuristic::{Heuristic, ReturnCosts};
+use crate::rng::{gen_range, Rng};
 use crate::util::Distance;
 
 use decorum::R64;
 use num_traits::identities::Zero;
 use std::cell::RefCell;
 use std::cmp::Eq;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::iter::Sum;
-use std::ops::Add;
+use std::ops::{Add, AddAssign};
 
 pub fn solution_length<IndexType, NodeWeightType, EdgeWeightType>(
     solution: &Solution<IndexType>,
     graph: &RefCell<
         dyn GenericWeightedGraph<
-            IndexType = IndexType,
-            NodeWeightType = NodeWeightType,
-            EdgeWeightType = EdgeWeightType,
-        >,
+                IndexType = IndexType,
+                NodeWeightType = NodeWeightType,
+                EdgeWeightType = EdgeWeightType,
+            > + '_,
     >,
 ) -> Result<EdgeWeightType, GraphError<IndexType>>
 where
@@ -39,34 +43,155 @@ where
         .sum())
 }
 
+/// Like solution_length, but with the time spent serving nodes, which is spent by the visit
+/// collecting the reward of a node, see service_on_arrival.
+pub fn solution_length_with_service_times<IndexType, NodeWeightType, EdgeWeightType>(
+    solution: &Solution<IndexType>,
+    graph: &RefCell<
+        dyn GenericWeightedGraph<
+                IndexType = IndexType,
+                NodeWeightType = NodeWeightType,
+                EdgeWeightType = EdgeWeightType,
+            > + '_,
+    >,
+    service_times: &ServiceTimes<IndexType, EdgeWeightType>,
+    time_windows: &TimeWindows<IndexType, EdgeWeightType>,
+) -> Result<EdgeWeightType, GraphError<IndexType>>
+where
+    IndexType: Distance<IndexType> + PartialEq + Copy + Debug + Display + Hash + Eq,
+    EdgeWeightType: Sum + Copy + Zero + PartialOrd,
+{
+    if service_times.is_empty() {
+        return solution_length(solution, graph);
+    }
+    let mut visited: HashSet<IndexType> = HashSet::new();
+    let mut length = EdgeWeightType::zero();
+    let g_borrow = graph.borrow();
+    for (from, to) in solution.iter_edges() {
+        length = length + *g_borrow.edge_weight((*from, *to))?;
+        length = length
+            + service_on_arrival(service_times, time_windows, visited.insert(*to), to, length);
+    }
+
+    Ok(length)
+}
+
 pub fn solution_score<IndexType, Nw, Ew>(
     solution: &Solution<IndexType>,
     graph: &RefCell<
-        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>,
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + '_,
+    >,
+    heuristic: &Heuristic<Nw, Ew>,
+) -> Result<R64, GraphError<IndexType>>
+where
+    IndexType: Distance<IndexType> + PartialEq + Copy + Debug + Display + Hash + Eq,
+    Nw: Sum + Copy + Debug + Zero + Add<Output = Nw>,
+    Ew: Copy + Debug + Zero + Add<Output = Ew> + PartialOrd,
+{
+    solution_score_with_time_windows(solution, graph, heuristic, &TimeWindows::new())
+}
+
+/// Like solution_score, but the value of a node only counts if it is reached within its time window.
+/// Only the first arrival at a node is considered, so the value is lost when arriving too early.
+pub fn solution_score_with_time_windows<IndexType, Nw, Ew>(
+    solution: &Solution<IndexType>,
+    graph: &RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + '_,
     >,
     heuristic: &Heuristic<Nw, Ew>,
+    time_windows: &TimeWindows<IndexType, Ew>,
 ) -> Result<R64, GraphError<IndexType>>
 where
     IndexType: Distance<IndexType> + PartialEq + Copy + Debug + Display + Hash + Eq,
     Nw: Sum + Copy + Debug + Zero + Add<Output = Nw>,
-    Ew: Copy + Debug + Zero + Add<Output = Ew>,
+    Ew: Copy + Debug + Zero + Add<Output = Ew> + PartialOrd,
+{
+    solution_score_with_penalties(
+        solution,
+        graph,
+        heuristic,
+        time_windows,
+        &RevisitPenalty::default(),
+    )
+}
+
+/// Like solution_score_with_time_windows, but every revisit of a node other than the start and
+/// every repeated traversal of an edge reduces the score by the given penalties.
+pub fn solution_score_with_penalties<IndexType, Nw, Ew>(
+    solution: &Solution<IndexType>,
+    graph: &RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + '_,
+    >,
+    heuristic: &Heuristic<Nw, Ew>,
+    time_windows: &TimeWindows<IndexType, Ew>,
+    penalty: &RevisitPenalty,
+) -> Result<R64, GraphError<IndexType>>
+where
+    IndexType: Distance<IndexType> + PartialEq + Copy + Debug + Display + Hash + Eq,
+    Nw: Sum + Copy + Debug + Zero + Add<Output = Nw>,
+    Ew: Copy + Debug + Zero + Add<Output = Ew> + PartialOrd,
+{
+    solution_score_with_service_times(
+        solution,
+        graph,
+        heuristic,
+        time_windows,
+        penalty,
+        &ServiceTimes::new(),
+    )
+}
+
+/// Like solution_score_with_penalties, but arrivals are delayed by the service times spent
+/// before, which decides whether later nodes are reached within their time windows.
+pub fn solution_score_with_service_times<IndexType, Nw, Ew>(
+    solution: &Solution<IndexType>,
+    graph: &RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = Nw, EdgeWeightType = Ew>
+            + '_,
+    >,
+    heuristic: &Heuristic<Nw, Ew>,
+    time_windows: &TimeWindows<IndexType, Ew>,
+    penalty: &RevisitPenalty,
+    service_times: &ServiceTimes<IndexType, Ew>,
+) -> Result<R64, GraphError<IndexType>>
+where
+    IndexType: Distance<IndexType> + PartialEq + Copy + Debug + Display + Hash + Eq,
+    Nw: Sum + Copy + Debug + Zero + Add<Output = Nw>,
+    Ew: Copy + Debug + Zero + Add<Output = Ew> + PartialOrd,
 {
     let start = solution.node_list[0];
     let mut visited: HashSet<IndexType> = HashSet::new();
+    let mut traversed: HashSet<Edge<IndexType>> = HashSet::new();
     let mut distance_traveled = Ew::zero();
     let mut sum = R64::zero();
     let g_borrow = graph.borrow();
     for (from, to) in solution.iter_edges() {
         let ew = *g_borrow.edge_weight((*from, *to))?;
-        let nw = if !visited.contains(to) {
+        distance_traveled = ew + distance_traveled;
+        let nw = if !visited.contains(to) && arrives_in_time(time_windows, to, distance_traveled) {
             *g_borrow.node_weight(*to)?
         } else {
             Nw::zero()
         };
 
-        distance_traveled = ew + distance_traveled;
         sum += heuristic(nw, ew, IndexType::distance(start, *to), distance_traveled);
+        sum -= penalty.of(
+            *to != start && visited.contains(to),
+            edge_traversed(&traversed, (*from, *to)),
+        );
+        distance_traveled = distance_traveled
+            + service_on_arrival(
+                service_times,
+                time_windows,
+                !visited.contains(to),
+                to,
+                distance_traveled,
+            );
         visited.insert(*to);
+        traversed.insert((*from, *to));
     }
 
     Ok(sum)
@@ -166,6 +291,25 @@ where
         self.iter_unique_nodes().collect()
     }
 
+    /// Returns all nodes of required, which are not visited by this solution.
+    pub fn missing_nodes(&self, required: &HashSet<IndexType>) -> Vec<IndexType> {
+        required
+            .iter()
+            .filter(|node| !self.node_list.contains(node))
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if every node of required is visited by this solution, or false otherwise.
+    pub fn visits_all(&self, required: &HashSet<IndexType>) -> bool {
+        required.iter().all(|node| self.node_list.contains(node))
+    }
+
+    /// Returns true if the solution starts and ends at goal_point, as every closed tour does.
+    pub fn is_closed_tour(&self, goal_point: IndexType) -> bool {
+        self.node_list.first() == Some(&goal_point) && self.node_list.last() == Some(&goal_point)
+    }
+
     pub fn iter_unique_edges(&self) -> Box<dyn Iterator<Item = (&IndexType, &IndexType)> + '_> {
         let mut visited = HashSet::new();
         for edge in self.iter_edges() {
@@ -194,6 +338,88 @@ where
     }
 }
 
+impl<IndexType> Solution<IndexType>
+where
+    IndexType: Copy + Hash + Eq + Ord + Debug + Display,
+{
+    /// Returns a random closed tour from goal_point, with a length of at most budget, and its
+    /// length. The tour walks to random neighbors, from which goal_point can still be reached
+    /// within budget, until there are none left and then returns along the shortest path.
+    /// If no neighbor of goal_point can be visited, the tour only consists of goal_point.
+    /// Returns None if goal_point is not in graph.
+    pub fn random_feasible<G>(
+        graph: &G,
+        goal_point: IndexType,
+        budget: G::EdgeWeightType,
+        rng: &mut Rng,
+    ) -> Option<(Self, G::EdgeWeightType)>
+    where
+        G: GenericWeightedGraph<IndexType = IndexType> + ?Sized,
+        G::EdgeWeightType: Copy + Zero + AddAssign + PartialOrd,
+    {
+        if !graph.has_node(goal_point) {
+            return None;
+        }
+        let inv_shortest_paths = graph.inv_shortest_paths(goal_point);
+        let return_costs = ReturnCosts::from_paths(&inv_shortest_paths);
+        Self::random_feasible_with_paths(
+            graph,
+            goal_point,
+            budget,
+            &inv_shortest_paths,
+            &return_costs,
+            rng,
+        )
+    }
+
+    /// Like random_feasible, but takes the shortest paths to goal_point and the return costs
+    /// computed from them, so drawing many tours on the same graph doesn't search them again.
+    pub fn random_feasible_with_paths<G>(
+        graph: &G,
+        goal_point: IndexType,
+        budget: G::EdgeWeightType,
+        inv_shortest_paths: &BTreeMap<IndexType, Option<(Self, G::EdgeWeightType)>>,
+        return_costs: &ReturnCosts<IndexType, G::EdgeWeightType>,
+        rng: &mut Rng,
+    ) -> Option<(Self, G::EdgeWeightType)>
+    where
+        G: GenericWeightedGraph<IndexType = IndexType> + ?Sized,
+        G::EdgeWeightType: Copy + Zero + AddAssign + PartialOrd,
+    {
+        let mut length = G::EdgeWeightType::zero();
+        let mut solution = Solution::from_nodes(vec![goal_point]);
+        let mut current = goal_point;
+        loop {
+            let candidates: Vec<(IndexType, G::EdgeWeightType)> = graph
+                .iter_neighbors(current)
+                .ok()?
+                .filter(|&(node, &weight)| match return_costs.get(node) {
+                    Some(return_cost) => length + return_cost + weight <= budget,
+                    None => false,
+                })
+                .map(|(node, &weight)| (node, weight))
+                .collect();
+            if candidates.is_empty() {
+                break;
+            }
+            let (next, weight) = candidates[gen_range(rng, 0..candidates.len())];
+            length += weight;
+            solution.push_node(next);
+            current = next;
+        }
+
+        // the walk only reaches nodes with a path back, which fits into the rest of budget
+        if current != goal_point {
+            let (path, distance) = inv_shortest_paths.get(&current)?.as_ref()?;
+            for &node in path.iter_nodes().skip(1) {
+                solution.push_node(node);
+            }
+            length += *distance;
+        }
+        Some((solution, length))
+    }
+}
+
 impl<IndexType: Display> Display for Solution<IndexType> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -212,6 +438,8 @@ impl<IndexType: Display> Display for Solution<IndexType> {
 mod tests {
     use super::*;
     use crate::graph::MatrixGraph;
+    use crate::metaheuristic::time_window::TimeWindow;
+    use crate::rng::RngKind;
     use decorum::R64;
 
     fn node_list() -> Vec<usize> {
@@ -380,4 +608,87 @@ mod tests {
             R64::from_inner(8.0)
         );
     }
+
+    #[test]
+    fn solution_score_respects_time_windows() {
+        let s1 = valid_solution_with_duplicates();
+        let g = weighted_graph();
+        let rc = RefCell::new(g);
+        let mut time_windows = TimeWindows::new();
+        // node 2 is first reached after 1.0, so its value is lost
+        time_windows.insert(
+            2,
+            TimeWindow::new(R64::from_inner(2.0), R64::from_inner(5.0)),
+        );
+        time_windows.insert(
+            3,
+            TimeWindow::new(R64::from_inner(2.0), R64::from_inner(5.0)),
+        );
+
+        assert_eq!(
+            solution_score_with_time_windows(&s1, &rc, &nw_heuristic, &time_windows).unwrap(),
+            R64::from_inner(3.0)
+        );
+    }
+
+    #[test]
+    fn solution_score_subtracts_revisit_penalties() {
+        let s1 = valid_solution_with_duplicates();
+        let g = weighted_graph();
+        let rc = RefCell::new(g);
+        // nodes 2 and 3 and the edge between them are visited twice, returning to 1 is free
+        let penalty = RevisitPenalty::new(R64::from_inner(1.0), R64::from_inner(0.5));
+
+        assert_eq!(
+            solution_score_with_penalties(&s1, &rc, &nw_heuristic, &TimeWindows::new(), &penalty)
+                .unwrap(),
+            R64::from_inner(5.0)
+        );
+    }
+
+    #[test]
+    fn missing_nodes_works() {
+        let s1 = valid_solution_with_duplicates();
+        let required: HashSet<usize> = vec![2, 4, 6].into_iter().collect();
+
+        assert_eq!(s1.missing_nodes(&required), vec![6]);
+        assert!(!s1.visits_all(&required));
+        assert!(s1.visits_all(&vec![1, 3].into_iter().collect()));
+    }
+
+    #[test]
+    fn is_closed_tour_works() {
+        assert!(Solution::from_nodes(vec![1, 2, 3, 1]).is_closed_tour(1));
+        assert!(Solution::from_nodes(vec![1]).is_closed_tour(1));
+        assert!(!Solution::from_nodes(vec![1, 2, 3]).is_closed_tour(1));
+        assert!(!Solution::from_nodes(vec![2, 3, 2]).is_closed_tour(1));
+        assert!(!Solution::<usize>::new().is_closed_tour(1));
+    }
+
+    #[test]
+    fn random_feasible_tours_are_closed_and_within_budget() {
+        let graph = weighted_graph();
+        let budget = R64::from_inner(5.0);
+        for seed in 0..20 {
+            let mut rng = Rng::new(RngKind::default(), seed);
+            let (tour, length) = Solution::random_feasible(&graph, 1, budget, &mut rng).unwrap();
+            assert!(tour.is_closed_tour(1));
+            assert!(length <= budget);
+            let edge_lengths: Option<Vec<_>> = tour
+                .iter_edges()
+                .map(|(&from, &to)| graph.edge_weight((from, to)).ok().copied())
+                .collect();
+            assert_eq!(
+                edge_lengths.map(|lengths| lengths.into_iter().sum()),
+                Some(length)
+            );
+        }
+
+        let mut rng = Rng::new(RngKind::default(), 0);
+        let (tour, length) =
+            Solution::random_feasible(&graph, 1, R64::from_inner(1.0), &mut rng).unwrap();
+        assert_eq!(tour.nodes(), vec![1]);
+        assert_eq!(length, R64::zero());
+        assert_eq!(Solution::random_feasible(&graph, 9, budget, &mut rng), None);
+    }
 }
@@ -1,17 +1,479 @@
+use csv::{Writer, WriterBuilder};
 use decorum::R64;
 use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::io::{self, stderr, Stderr, Write};
+use std::marker::PhantomData;
 use std::ops::{Add, AddAssign};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
 use std::time::Duration;
 
-pub trait Supervisor<MessageType: Message> {}
+use crate::metaheuristic::Solution;
+
+/// Collects the messages sent during a run and writes them as csv records.
+///
+/// Messages are aggregated by iteration: with an aggregation rate of n, the messages of
+/// iterations k·n to (k + 1)·n - 1 are combined into a single record with iteration k·n.
+/// Records are only written once their iterations are complete, except for the last one,
+/// which is always written by aggregate_receive at the end of a run. The header is written
+/// before the first record, or by aggregate_receive for runs without any records.
+pub struct Supervisor<W: Write, M, A> {
+    sender: Sender<M>,
+    receiver: Receiver<M>,
+    windows: Vec<A>,
+    writer: Writer<W>,
+    header_written: bool,
+    run: RunInfo,
+    aggregation_rate: usize,
+    flush_every: Option<usize>,
+    // amount of iterations received so far and of records already written
+    iterations: usize,
+    written: usize,
+    times: PhaseTimes,
+    evaluations: usize,
+    cpu_time: Duration,
+    routes: Option<Box<dyn Write>>,
+    // amount of iterations, whose best route was passed to log_route
+    routed: usize,
+    // distinct warnings reported so far, each is only printed once
+    warnings: BTreeSet<String>,
+}
+
+/// Identifies the run a log belongs to, written into every record.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RunInfo {
+    pub algorithm: String,
+    pub heuristic: String,
+    pub run_id: String,
+    /// Parameters of the algorithm, to tell the logs of parameter sweeps apart.
+    pub params: String,
+}
+
+/// Phases of an iteration, which are timed separately over a whole run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Building new solutions. Local searches count their whole iteration as construction.
+    Construction,
+    PheromoneUpdate,
+    /// Comparing the constructed solutions against each other and the best one so far.
+    Scoring,
+}
+
+/// Total time spent in each phase during a run.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PhaseTimes {
+    pub construction: Duration,
+    pub pheromone_update: Duration,
+    pub scoring: Duration,
+}
+
+impl PhaseTimes {
+    pub fn add(&mut self, phase: Phase, duration: Duration) {
+        match phase {
+            Phase::Construction => self.construction += duration,
+            Phase::PheromoneUpdate => self.pheromone_update += duration,
+            Phase::Scoring => self.scoring += duration,
+        }
+    }
+}
+
+/// Final record of a run, which allows comparing algorithms by the time they took rather than
+/// by their iterations. Times are in microseconds, wall time also includes logging and setup,
+/// cpu time sums up the time of all threads, so it exceeds the wall time of parallel runs.
+/// The node weight collected by the best solution, an upper bound on it and the fingerprint of
+/// the graph are only known to the caller and left empty by Supervisor::summary.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Summary {
+    pub algorithm: String,
+    pub heuristic: String,
+    pub run_id: String,
+    pub iterations: usize,
+    pub evaluations: usize,
+    pub score: f64,
+    pub length: f64,
+    /// Lengths of the routes of the best solution, separated by spaces.
+    #[serde(default)]
+    pub route_lengths: String,
+    /// Scores of the routes of the best solution, separated by spaces.
+    #[serde(default)]
+    pub route_scores: String,
+    pub collected: f64,
+    pub reward_bound: f64,
+    /// Fingerprint of the graph the run started on, to group runs on the same instance.
+    pub graph_fingerprint: String,
+    pub wall_time_us: u128,
+    #[serde(default)]
+    pub cpu_time_us: u128,
+    pub construction_us: u128,
+    pub pheromone_update_us: u128,
+    pub scoring_us: u128,
+}
+
+impl Summary {
+    /// Sets the length and score of every route of the best solution. Without routes the
+    /// solution counts as a single route.
+    pub fn with_routes(mut self, routes: &[(R64, R64)]) -> Self {
+        let join = |values: Vec<f64>| {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let routes = if routes.is_empty() {
+            vec![(self.length, self.score)]
+        } else {
+            routes
+                .iter()
+                .map(|(length, score)| (length.into_inner(), score.into_inner()))
+                .collect()
+        };
+        self.route_lengths = join(routes.iter().map(|&(length, _)| length).collect());
+        self.route_scores = join(routes.iter().map(|&(_, score)| score).collect());
+        self
+    }
+}
+
+/// A row of a supervisor log.
+pub trait Record: Serialize {
+    /// Names of the serialized fields in order.
+    const COLUMNS: &'static [&'static str];
+}
+
+/// Combines the messages of one aggregation window into a csv record.
+pub trait Aggregate<M>: Default {
+    type Record: Record;
+
+    fn add(&mut self, message: M);
+
+    /// Builds the record of the window, which starts at iteration.
+    fn record(&self, iteration: usize, run: &RunInfo) -> Self::Record;
+}
 
 pub trait Message {
     type EwType;
     type NwType;
     fn get_info(&self) -> MessageInfo<Self::NwType, Self::EwType>;
+
+    /// Returns the iteration the message belongs to, None for the iteration in progress.
+    fn iteration(&self) -> Option<usize>;
+
+    /// Returns true if no further messages of its iteration follow this one.
+    fn completes_iteration(&self) -> bool;
+}
+
+/// Records, which can be built from the summed up infos of an aggregation window.
+pub trait FromInfo<Nw, Ew>: Record {
+    fn from_info(iteration: usize, info: &MessageInfo<Nw, Ew>, run: &RunInfo) -> Self;
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+impl<W: Write, M, A> Supervisor<W, M, A> {
+    pub fn new(aggregation_rate: usize, output: W) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Supervisor {
+            sender: tx,
+            receiver: rx,
+            windows: Vec::new(),
+            writer: WriterBuilder::new().has_headers(false).from_writer(output),
+            header_written: false,
+            run: RunInfo::default(),
+            aggregation_rate,
+            flush_every: None,
+            iterations: 0,
+            written: 0,
+            times: PhaseTimes::default(),
+            evaluations: 0,
+            cpu_time: Duration::default(),
+            routes: None,
+            routed: 0,
+            warnings: BTreeSet::new(),
+        }
+    }
+
+    /// Writes completed records every flush_every iterations, instead of only at the end.
+    pub fn with_flush_every(mut self, flush_every: Option<usize>) -> Self {
+        self.flush_every = flush_every;
+        self
+    }
+
+    /// Appends the best route of every aggregation_rate-th iteration to routes as a json line.
+    pub fn with_route_log(mut self, routes: Option<Box<dyn Write>>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Sets the run, which is written into every record.
+    pub fn with_run(mut self, run: RunInfo) -> Self {
+        self.run = run;
+        self
+    }
+
+    pub fn run(&self) -> &RunInfo {
+        &self.run
+    }
+
+    pub fn sender(&self) -> Sender<M> {
+        self.sender.clone()
+    }
+
+    /// Returns the amount of iterations combined into one record.
+    pub fn aggregation_rate(&self) -> usize {
+        self.aggregation_rate
+    }
+
+    /// Returns the index of the record, which the messages of iteration are aggregated into.
+    pub fn record_index(&self, iteration: usize) -> usize {
+        iteration / self.aggregation_rate.max(1)
+    }
+
+    /// Adds duration to the time spent in phase.
+    pub fn add_time(&mut self, phase: Phase, duration: Duration) {
+        self.times.add(phase, duration);
+    }
+
+    pub fn phase_times(&self) -> PhaseTimes {
+        self.times
+    }
+
+    /// Builds the summary of a run, which took iterations and wall_time in total. Evaluations
+    /// and cpu time are counted from the messages received so far.
+    pub fn summary(
+        &self,
+        iterations: usize,
+        score: f64,
+        length: f64,
+        wall_time: Duration,
+    ) -> Summary {
+        Summary {
+            algorithm: self.run.algorithm.clone(),
+            heuristic: self.run.heuristic.clone(),
+            run_id: self.run.run_id.clone(),
+            iterations,
+            evaluations: self.evaluations,
+            score,
+            length,
+            route_lengths: String::new(),
+            route_scores: String::new(),
+            collected: 0.0,
+            reward_bound: 0.0,
+            graph_fingerprint: String::new(),
+            wall_time_us: wall_time.as_micros(),
+            cpu_time_us: self.cpu_time.as_micros(),
+            construction_us: self.times.construction.as_micros(),
+            pheromone_update_us: self.times.pheromone_update.as_micros(),
+            scoring_us: self.times.scoring.as_micros(),
+        }
+    }
+
+    /// Passes the best route of the current iteration to the route log, if there is one.
+    /// Algorithms call this once per iteration, so only every aggregation_rate-th route is
+    /// written.
+    pub fn log_route<IndexType: Display + Copy + Hash + Eq>(
+        &mut self,
+        route: &Solution<IndexType>,
+        score: f64,
+        length: f64,
+    ) {
+        let iteration = self.routed;
+        self.routed += 1;
+        if let Some(routes) = &mut self.routes {
+            if iteration.is_multiple_of(self.aggregation_rate.max(1)) {
+                let entry = RouteEntry {
+                    iteration,
+                    score,
+                    length,
+                    route: encode_route(route),
+                };
+                let res = serde_json::to_writer(&mut *routes, &entry)
+                    .map_err(io::Error::from)
+                    .and_then(|_| writeln!(routes));
+                if let Err(err) = res {
+                    eprintln!("{:?}", err);
+                }
+            }
+        }
+    }
+
+    /// Reports a problem the algorithm recovered from on stderr. Algorithms may run into the
+    /// same problem every iteration, so each distinct warning is only printed once.
+    pub fn warn(&mut self, warning: impl Display) {
+        let warning = warning.to_string();
+        if !self.warnings.contains(&warning) {
+            eprintln!("Warning ({}): {}", self.run.algorithm, warning);
+            self.warnings.insert(warning);
+        }
+    }
+
+    /// Returns the distinct warnings reported so far.
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// Drops all received messages, timings and warnings and starts over with a new channel.
+    pub fn reset(&mut self) {
+        self.windows = Vec::new();
+        self.iterations = 0;
+        self.written = 0;
+        self.times = PhaseTimes::default();
+        self.evaluations = 0;
+        self.cpu_time = Duration::default();
+        self.routed = 0;
+        self.warnings = BTreeSet::new();
+        let (tx, rx) = mpsc::channel();
+        self.sender = tx;
+        self.receiver = rx;
+    }
+}
+
+impl<W: Write, M: Message, A: Aggregate<M>> Supervisor<W, M, A> {
+    fn receive(&mut self) {
+        while let Ok(message) = self.receiver.recv_timeout(Duration::from_millis(1)) {
+            self.add(message);
+        }
+    }
+
+    fn add(&mut self, message: M) {
+        let iteration = message.iteration().unwrap_or(self.iterations);
+        let idx = self.record_index(iteration);
+        if idx >= self.windows.len() {
+            self.windows.resize_with(idx + 1, Default::default);
+        }
+        let completes = message.completes_iteration();
+        let info = message.get_info();
+        self.evaluations += info.evaluations;
+        self.cpu_time += info.cpu_time;
+        self.windows[idx].add(message);
+        if completes {
+            self.iterations = self.iterations.max(iteration + 1);
+        }
+    }
+
+    /// Returns the amount of heuristic evaluations of all messages sent so far. Unlike the
+    /// other receiving methods it doesn't wait for further messages, so it can be called
+    /// after every iteration.
+    pub fn evaluations(&mut self) -> usize {
+        while let Ok(message) = self.receiver.try_recv() {
+            self.add(message);
+        }
+        self.evaluations
+    }
+
+    /// Writes all records before end, which were not written yet, and flushes the writer.
+    fn write_records(&mut self, end: usize) {
+        if !self.header_written {
+            let res = self.writer.write_record(A::Record::COLUMNS);
+            if let Err(err) = res {
+                eprintln!("{:?}", err);
+            }
+            self.header_written = true;
+        }
+        let end = end.min(self.windows.len());
+        for i in self.written..end {
+            let record = self.windows[i].record(i * self.aggregation_rate, &self.run);
+            let res = self.writer.serialize(record);
+            if let Err(err) = res {
+                eprintln!("{:?}", err);
+            }
+        }
+        self.written = self.written.max(end);
+        let _res = self.writer.flush();
+    }
+
+    /// Receives all pending messages and writes the records of completed iterations.
+    pub fn write_completed(&mut self) {
+        self.receive();
+        self.write_records(self.record_index(self.iterations));
+    }
+
+    /// Receives all pending messages and writes all remaining records.
+    pub fn aggregate_receive(&mut self) {
+        self.receive();
+        self.write_records(self.windows.len());
+        if let Some(routes) = &mut self.routes {
+            let _res = routes.flush();
+        }
+    }
+
+    /// Notifies the supervisor that iteration is over, which writes completed records every
+    /// flush_every iterations.
+    pub fn end_iteration(&mut self, iteration: usize) {
+        if let Some(flush_every) = self.flush_every {
+            if (iteration + 1).is_multiple_of(flush_every) {
+                self.write_completed();
+            }
+        }
+    }
+}
+
+/// Line of the route log.
+#[derive(Serialize)]
+struct RouteEntry {
+    iteration: usize,
+    score: f64,
+    length: f64,
+    route: Vec<Value>,
+}
+
+/// Encodes the nodes of route as json values, numeric ids are written as numbers and other ids
+/// as strings.
+fn encode_route<IndexType: Display + Copy + Hash + Eq>(route: &Solution<IndexType>) -> Vec<Value> {
+    route
+        .iter_nodes()
+        .map(|node| {
+            let id = node.to_string();
+            match id.parse::<Number>() {
+                Ok(number) => Value::Number(number),
+                Err(_) => Value::String(id),
+            }
+        })
+        .collect()
+}
+
+impl<M, A> Default for Supervisor<Stderr, M, A> {
+    fn default() -> Self {
+        Supervisor::new(1, stderr())
+    }
+}
+
+/// Sums up the infos of all messages of a window into a record of type R, for algorithms
+/// sending a single message per iteration.
+pub struct Totals<Nw, Ew, R> {
+    info: MessageInfo<Nw, Ew>,
+    record: PhantomData<R>,
+}
+
+impl<Nw: Default, Ew: Default, R> Default for Totals<Nw, Ew, R> {
+    fn default() -> Self {
+        Totals {
+            info: MessageInfo::default(),
+            record: PhantomData,
+        }
+    }
+}
+
+impl<M, R> Aggregate<M> for Totals<M::NwType, M::EwType, R>
+where
+    M: Message,
+    M::NwType: Default + Copy + Add<Output = M::NwType>,
+    M::EwType: Default + Copy + Add<Output = M::EwType> + PartialOrd,
+    R: FromInfo<M::NwType, M::EwType>,
+{
+    type Record = R;
+
+    fn add(&mut self, message: M) {
+        self.info += message.get_info();
+    }
+
+    fn record(&self, iteration: usize, run: &RunInfo) -> R {
+        R::from_info(iteration, &self.info, run)
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct MessageInfo<Nw, Ew> {
     pub evaluations: usize,
     pub cpu_time: Duration,
@@ -23,9 +485,14 @@ pub struct MessageInfo<Nw, Ew> {
     pub visited_nodes: usize,
     pub visited_nodes_with_val: usize,
     pub collected_val: Nw,
+    pub routes: usize,
+    pub longest_route: Ew,
+    /// Consumption of the secondary budget, 0 for instances without one.
+    #[serde(default)]
+    pub secondary_resource: f64,
 }
 
-impl<Nw, Ew> MessageInfo<Nw, Ew> {
+impl<Nw, Ew: Copy> MessageInfo<Nw, Ew> {
     pub fn new(
         evaluations: usize,
         n_improvements: usize,
@@ -49,11 +516,27 @@ impl<Nw, Ew> MessageInfo<Nw, Ew> {
             visited_nodes,
             visited_nodes_with_val,
             collected_val,
+            routes: 1,
+            longest_route: distance,
+            secondary_resource: 0.0,
         }
     }
+
+    /// Sets the amount of routes and the length of the longest one, for solutions with multiple routes.
+    pub fn with_routes(mut self, routes: usize, longest_route: Ew) -> Self {
+        self.routes = routes;
+        self.longest_route = longest_route;
+        self
+    }
+
+    /// Sets the consumption of the secondary budget of the solution.
+    pub fn with_secondary_resource(mut self, secondary_resource: f64) -> Self {
+        self.secondary_resource = secondary_resource;
+        self
+    }
 }
 
-impl<Nw: Add<Output = Nw>, Ew: Add<Output = Ew>> Add for MessageInfo<Nw, Ew> {
+impl<Nw: Add<Output = Nw>, Ew: Add<Output = Ew> + PartialOrd> Add for MessageInfo<Nw, Ew> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
@@ -68,11 +551,17 @@ impl<Nw: Add<Output = Nw>, Ew: Add<Output = Ew>> Add for MessageInfo<Nw, Ew> {
             visited_nodes: self.visited_nodes + other.visited_nodes,
             visited_nodes_with_val: self.visited_nodes_with_val + other.visited_nodes_with_val,
             collected_val: self.collected_val + other.collected_val,
+            routes: other.routes,
+            // routes are driven in parallel, so the longest route is not summed up
+            longest_route: longer(self.longest_route, other.longest_route),
+            secondary_resource: self.secondary_resource + other.secondary_resource,
         }
     }
 }
 
-impl<Nw: Copy + Add<Output = Nw>, Ew: Copy + Add<Output = Ew>> AddAssign for MessageInfo<Nw, Ew> {
+impl<Nw: Copy + Add<Output = Nw>, Ew: Copy + Add<Output = Ew> + PartialOrd> AddAssign
+    for MessageInfo<Nw, Ew>
+{
     fn add_assign(&mut self, other: Self) {
         *self = Self {
             evaluations: self.evaluations + other.evaluations,
@@ -85,6 +574,375 @@ impl<Nw: Copy + Add<Output = Nw>, Ew: Copy + Add<Output = Ew>> AddAssign for Mes
             visited_nodes: self.visited_nodes + other.visited_nodes,
             visited_nodes_with_val: self.visited_nodes_with_val + other.visited_nodes_with_val,
             collected_val: self.collected_val + other.collected_val,
+            routes: other.routes,
+            // routes are driven in parallel, so the longest route is not summed up
+            longest_route: longer(self.longest_route, other.longest_route),
+            secondary_resource: self.secondary_resource + other.secondary_resource,
+        };
+    }
+}
+
+fn longer<Ew: PartialOrd>(a: Ew, b: Ew) -> Ew {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Summary statistics of a set of values, e.g. the scores of all ants in one iteration.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    pub std_dev: f64,
+}
+
+impl Distribution {
+    /// Calculates the distribution of values. An empty slice results in all statistics being 0.
+    pub fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+
+        Distribution {
+            min: sorted[0],
+            median,
+            max: sorted[n - 1],
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metaheuristic::{aco, random_search, two_swap, ConstructionWarning};
+
+    /// Returns the iteration and evals columns of all records written so far.
+    fn records<M, A>(supervisor: &Supervisor<Vec<u8>, M, A>) -> Vec<String> {
+        let output = String::from_utf8(supervisor.writer.get_ref().clone()).unwrap();
+        let mut lines = output.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let column = |name| header.iter().position(|h| *h == name).unwrap();
+        let (iteration, evals) = (column("iteration"), column("evals"));
+        lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                format!("{},{}", fields[iteration], fields[evals])
+            })
+            .collect()
+    }
+
+    /// Returns the header csv writes for a record of type R.
+    fn serialized_header<R: Record + Default>() -> String {
+        let mut writer = Writer::from_writer(Vec::new());
+        writer.serialize(R::default()).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        output.lines().next().unwrap().to_string()
+    }
+
+    #[test]
+    fn longest_routes_are_not_summed_up() {
+        let info = |longest_route: f64| {
+            MessageInfo::new(
+                1,
+                0,
+                0,
+                0,
+                Duration::default(),
+                8.0,
+                R64::from_inner(0.0),
+                0,
+                0,
+                0.0,
+            )
+            .with_routes(2, longest_route)
+        };
+        let mut sum = info(5.0) + info(3.0);
+        assert_eq!(sum.longest_route, 5.0);
+        assert_eq!(sum.distance, 16.0);
+        sum += info(6.0);
+        assert_eq!(sum.longest_route, 6.0);
+    }
+
+    #[test]
+    fn summaries_list_every_route() {
+        let summary = Summary {
+            score: 7.0,
+            length: 9.0,
+            ..Summary::default()
         };
+        let r = R64::from_inner;
+        let routes = summary
+            .clone()
+            .with_routes(&[(r(4.0), r(5.0)), (r(5.0), r(2.0))]);
+        assert_eq!(routes.route_lengths, "4 5");
+        assert_eq!(routes.route_scores, "5 2");
+        let single = summary.with_routes(&[]);
+        assert_eq!(single.route_lengths, "9");
+        assert_eq!(single.route_scores, "7");
+    }
+
+    #[test]
+    fn columns_match_records() {
+        assert_eq!(
+            serialized_header::<aco::Record>(),
+            aco::Record::COLUMNS.join(",")
+        );
+        assert_eq!(
+            serialized_header::<two_swap::Record>(),
+            two_swap::Record::COLUMNS.join(",")
+        );
+        assert_eq!(
+            serialized_header::<random_search::Record>(),
+            random_search::Record::COLUMNS.join(",")
+        );
+    }
+
+    #[test]
+    fn header_is_written_without_records() {
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> = Supervisor::new(1, Vec::new());
+        supervisor.aggregate_receive();
+        let output = String::from_utf8(supervisor.writer.get_ref().clone()).unwrap();
+
+        assert_eq!(output.trim_end(), two_swap::Record::COLUMNS.join(","));
+    }
+
+    #[test]
+    fn records_identify_the_run() {
+        let run = RunInfo {
+            algorithm: "two_swap".to_string(),
+            heuristic: "h1".to_string(),
+            run_id: "h1_s0".to_string(),
+            params: "alpha=1 beta=2".to_string(),
+        };
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> =
+            Supervisor::new(1, Vec::new()).with_run(run);
+        let r = R64::from_inner;
+        let message = two_swap::Message::new(
+            0,
+            2,
+            1,
+            0,
+            0,
+            Duration::from_micros(5),
+            r(3.0),
+            r(4.0),
+            2,
+            1,
+            r(4.0),
+        );
+        supervisor.sender().send(message).unwrap();
+        supervisor.aggregate_receive();
+
+        let output = supervisor.writer.get_ref().clone();
+        let record: two_swap::Record = csv::Reader::from_reader(output.as_slice())
+            .deserialize()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.algorithm, "two_swap");
+        assert_eq!(record.heuristic, "h1");
+        assert_eq!(record.run_id, "h1_s0");
+        assert_eq!(record.params, "alpha=1 beta=2");
+        assert_eq!(record.evals, 2);
+        assert_eq!(record.score, 4.0);
+        assert_eq!(record.cpu_time_us, 5);
+    }
+
+    #[test]
+    fn iterations_are_aggregated_and_flushed() {
+        let mut supervisor: aco::Supervisor<_, R64, R64> =
+            Supervisor::new(2, Vec::new()).with_flush_every(Some(2));
+        let r = R64::from_inner;
+        let message = |id| {
+            aco::Message::new(
+                id,
+                0,
+                1,
+                0,
+                0,
+                0,
+                Duration::default(),
+                r(1.0),
+                r(1.0),
+                0,
+                0,
+                r(0.0),
+            )
+        };
+
+        for i in 0..3 {
+            for id in 1..=2 {
+                supervisor.sender().send(message(id)).unwrap();
+            }
+            supervisor.sender().send(message(0)).unwrap();
+            supervisor.end_iteration(i);
+            if i == 1 {
+                assert_eq!(records(&supervisor), vec!["0,4"]);
+            }
+        }
+        supervisor.aggregate_receive();
+
+        assert_eq!(records(&supervisor), vec!["0,4", "2,2"]);
+    }
+
+    #[test]
+    fn messages_are_aggregated_by_their_iteration() {
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> = Supervisor::new(3, Vec::new());
+        let r = R64::from_inner;
+        for i in [0, 1, 2, 3, 4].iter() {
+            let message = two_swap::Message::new(
+                *i,
+                *i + 1,
+                0,
+                0,
+                0,
+                Duration::default(),
+                r(1.0),
+                r(1.0),
+                0,
+                0,
+                r(0.0),
+            );
+            supervisor.sender().send(message).unwrap();
+        }
+        supervisor.write_completed();
+        assert_eq!(records(&supervisor), vec!["0,6"]);
+
+        supervisor.aggregate_receive();
+        // the last window is written even though it is incomplete, but only once
+        supervisor.aggregate_receive();
+        assert_eq!(records(&supervisor), vec!["0,6", "3,9"]);
+    }
+
+    #[test]
+    fn evaluations_are_counted_across_writes() {
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> = Supervisor::new(1, Vec::new());
+        let r = R64::from_inner;
+        let message = |i, evals: usize| {
+            two_swap::Message::new(
+                i,
+                evals,
+                0,
+                0,
+                0,
+                Duration::from_micros(evals as u64),
+                r(1.0),
+                r(1.0),
+                0,
+                0,
+                r(0.0),
+            )
+        };
+
+        supervisor.sender().send(message(0, 4)).unwrap();
+        assert_eq!(supervisor.evaluations(), 4);
+        supervisor.sender().send(message(1, 3)).unwrap();
+        supervisor.aggregate_receive();
+        assert_eq!(supervisor.evaluations(), 7);
+        // messages received early are still written
+        assert_eq!(records(&supervisor), vec!["0,4", "1,3"]);
+        let summary = supervisor.summary(2, 1.0, 1.0, Duration::default());
+        assert_eq!(summary.evaluations, 7);
+        assert_eq!(summary.cpu_time_us, 7);
+    }
+
+    #[test]
+    fn phase_times_are_summed_into_the_summary() {
+        let mut supervisor: aco::Supervisor<_, R64, R64> = Supervisor::new(1, Vec::new());
+        supervisor.add_time(Phase::Construction, Duration::from_micros(30));
+        supervisor.add_time(Phase::Scoring, Duration::from_micros(5));
+        supervisor.add_time(Phase::Construction, Duration::from_micros(20));
+        supervisor.add_time(Phase::PheromoneUpdate, Duration::from_micros(7));
+
+        let summary = supervisor.summary(2, 3.0, 4.0, Duration::from_micros(100));
+        assert_eq!(summary.iterations, 2);
+        assert_eq!(summary.wall_time_us, 100);
+        assert_eq!(summary.construction_us, 50);
+        assert_eq!(summary.pheromone_update_us, 7);
+        assert_eq!(summary.scoring_us, 5);
+
+        supervisor.reset();
+        assert_eq!(supervisor.phase_times(), PhaseTimes::default());
+    }
+
+    #[test]
+    fn distribution_works() {
+        let dist = Distribution::of(&[4.0, 1.0, 3.0, 2.0]);
+
+        assert_eq!(dist.min, 1.0);
+        assert_eq!(dist.median, 2.5);
+        assert_eq!(dist.max, 4.0);
+        assert!((dist.std_dev - 1.25f64.sqrt()).abs() < 1e-12);
+        assert_eq!(Distribution::of(&[2.0, 7.0, 5.0]).median, 5.0);
+    }
+
+    #[test]
+    fn distribution_of_nothing_is_zero() {
+        assert_eq!(Distribution::of(&[]), Distribution::default());
+    }
+
+    /// Writer, whose output can still be read after it was moved into a supervisor.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn routes_are_logged_at_the_aggregation_rate() {
+        let buffer = SharedBuffer::default();
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> =
+            Supervisor::new(2, Vec::new()).with_route_log(Some(Box::new(buffer.clone())));
+        for i in 0..3 {
+            supervisor.log_route(&Solution::from_nodes(vec![0, i, 0]), i as f64, 2.5);
+        }
+        supervisor.aggregate_receive();
+
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                r#"{"iteration":0,"score":0.0,"length":2.5,"route":[0,0,0]}"#,
+                r#"{"iteration":2,"score":2.0,"length":2.5,"route":[0,2,0]}"#,
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&encode_route(&Solution::from_nodes(vec!["a\"b"]))).unwrap(),
+            r#"["a\"b"]"#
+        );
+    }
+
+    #[test]
+    fn warnings_are_reported_once() {
+        let mut supervisor: two_swap::Supervisor<_, R64, R64> = Supervisor::new(1, Vec::new());
+        supervisor.warn(ConstructionWarning::NoFeasibleMove(0));
+        supervisor.warn(ConstructionWarning::NoFeasibleMove(0));
+        supervisor.warn(ConstructionWarning::NoFeasibleMove(1));
+
+        assert_eq!(supervisor.warnings().count(), 2);
+        supervisor.reset();
+        assert_eq!(supervisor.warnings().count(), 0);
     }
 }
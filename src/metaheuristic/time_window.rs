@@ -0,0 +1,98 @@
+use crate::rng::{gen_float_range, Rng};
+
+use decorum::R64;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub type TimeWindows<IndexType, T> = HashMap<IndexType, TimeWindow<T>>;
+
+/// The interval [open, close] in which the reward of a node can be collected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow<T> {
+    pub open: T,
+    pub close: T,
+}
+
+impl<T: PartialOrd> TimeWindow<T> {
+    pub fn new(open: T, close: T) -> Self {
+        TimeWindow { open, close }
+    }
+
+    /// Returns true if arrival lies within the window, or false otherwise.
+    pub fn contains(&self, arrival: T) -> bool {
+        arrival >= self.open && arrival <= self.close
+    }
+}
+
+/// Returns true if there is no time window for node in windows, or arrival lies within it.
+pub fn arrives_in_time<IndexType: Hash + Eq, T: PartialOrd + Copy>(
+    windows: &TimeWindows<IndexType, T>,
+    node: &IndexType,
+    arrival: T,
+) -> bool {
+    windows
+        .get(node)
+        .is_none_or(|window| window.contains(arrival))
+}
+
+/// Assigns a time window to each of the given nodes with the given probability.
+/// The width of each window is drawn from width_range, its opening time is chosen so that
+/// the window ends before horizon.
+pub fn random_time_windows<IndexType: Hash + Eq>(
+    nodes: impl Iterator<Item = IndexType>,
+    rng: &mut Rng,
+    probability: f64,
+    width_range: (f64, f64),
+    horizon: f64,
+) -> TimeWindows<IndexType, R64> {
+    let mut windows = HashMap::new();
+    for node in nodes {
+        if rng.rand_float() < probability {
+            let width = gen_float_range(rng, width_range.0..width_range.1);
+            let open = gen_float_range(rng, 0.0..(horizon - width).max(0.0));
+            windows.insert(
+                node,
+                TimeWindow::new(R64::from_inner(open), R64::from_inner(open + width)),
+            );
+        }
+    }
+
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::rng64;
+
+    #[test]
+    fn contains_works() {
+        let window = TimeWindow::new(2.0, 5.0);
+
+        assert!(window.contains(2.0));
+        assert!(window.contains(5.0));
+        assert!(!window.contains(1.9));
+        assert!(!window.contains(5.1));
+    }
+
+    #[test]
+    fn nodes_without_window_are_always_in_time() {
+        let mut windows = TimeWindows::new();
+        windows.insert(1, TimeWindow::new(2.0, 5.0));
+
+        assert!(arrives_in_time(&windows, &0, 100.0));
+        assert!(!arrives_in_time(&windows, &1, 100.0));
+    }
+
+    #[test]
+    fn random_time_windows_stay_within_horizon() {
+        let mut rng = rng64(12345);
+        let windows = random_time_windows(0..100, &mut rng, 0.5, (1.0, 3.0), 10.0);
+
+        assert!(!windows.is_empty() && windows.len() < 100);
+        for window in windows.values() {
+            assert!(window.open >= 0.0 && window.close <= 10.0);
+            assert!(window.close - window.open >= 1.0 && window.close - window.open <= 3.0);
+        }
+    }
+}
@@ -4,47 +4,178 @@ mod supervisor;
 
 pub use message::Message;
 pub use params::Params;
-pub use supervisor::Supervisor;
+pub use supervisor::{Record, Supervisor};
 
-use crate::graph::GenericWeightedGraph;
-use crate::metaheuristic::{solution_length, Heuristic, Metaheuristic, ProblemInstance, Solution};
+use crate::graph::{Edge, GenericWeightedGraph};
+use crate::metaheuristic::node_value::service_times;
+use crate::metaheuristic::revisit_penalty::edge_traversed;
+use crate::metaheuristic::supervisor::Phase;
+use crate::metaheuristic::{
+    solution_length_with_service_times, Heuristic, IterationCallback, Metaheuristic, NodeValue,
+    Objective, Observer, ProblemInstance, RevisitPenalty, SecondaryBudget, ServiceTimes, Solution,
+    SyncHeuristic, TimeWindows,
+};
 use crate::util::{Distance, SmallVal};
 
 use decorum::R64;
 use num_traits::identities::Zero;
-use serde::Serialize;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::io::Write;
 use std::iter::Sum;
+use std::marker::PhantomData;
 use std::ops::{Add, AddAssign, Div, Sub, SubAssign};
+use std::thread;
 use std::time::{Duration, Instant};
 
-pub struct TwoSwap<
-    'a,
-    IndexType,
-    NodeWeightType: Serialize + Default,
-    EdgeWeightType: Serialize + Default,
-    W: Write,
-> {
+/// A neighbor of the start of a solution edge, which could be inserted into that edge.
+/// Everything depending on the new solution is looked up before the candidates are scored, so
+/// scoring them only reads the graph weights and can run on multiple threads.
+struct Candidate<IndexType, Nw, Ew> {
+    node: IndexType,
+    /// None if the node is part of the new solution already and has no value to it.
+    node_weight: Option<Nw>,
+    weight: Ew,
+    /// Weight of the edge from node to the end of the solution edge, None if there is none.
+    return_weight: Option<Ew>,
+    penalty: R64,
+}
+
+/// Candidates scored on each thread at least, as starting a thread costs more than scoring a
+/// few candidates.
+const MIN_CANDIDATES_PER_THREAD: usize = 32;
+
+/// Scores the candidates of a solution edge on scoped threads, see TwoSwap::with_threads.
+type ThreadedScoring<'a, IndexType, Nw, Ew> = Box<
+    dyn Fn(&[Candidate<IndexType, Nw, Ew>], IndexType, Option<Nw>, Ew) -> Vec<(R64, usize)> + 'a,
+>;
+
+/// The part of a TwoSwap needed to score moves, which can be shared between threads if its
+/// heuristic is a SyncHeuristic.
+struct Scorer<'a, IndexType, Nw, Ew, H: ?Sized = Heuristic<Nw, Ew>> {
+    heuristic: &'a H,
+    goal_point: IndexType,
+    max_time: Ew,
+    node_weight_type: PhantomData<Nw>,
+}
+
+impl<'a, IndexType, Nw, Ew, H> Scorer<'a, IndexType, Nw, Ew, H>
+where
+    IndexType: Distance<IndexType> + Copy,
+    Nw: Copy,
+    Ew: Copy + Add<Output = Ew> + Div<Output = Ew>,
+    H: Fn(Nw, Ew, R64, Ew) -> R64 + ?Sized,
+{
+    fn score(&self, node_weight: Nw, edge_weight: Ew, point: IndexType, distance_up_to: Ew) -> R64 {
+        (self.heuristic)(
+            node_weight,
+            edge_weight,
+            IndexType::distance(self.goal_point, point),
+            distance_up_to / self.max_time,
+        )
+    }
+
+    /// Scores inserting candidate before to, whose weight is None if it has no value to the
+    /// new solution. Returns the score together with the amount of heuristic evaluations.
+    fn score_candidate(
+        &self,
+        candidate: &Candidate<IndexType, Nw, Ew>,
+        to: IndexType,
+        to_weight: Option<Nw>,
+        tail_length: Ew,
+    ) -> (R64, usize) {
+        let mut evals = 0;
+        let mut score = R64::zero();
+        if let Some(node_weight) = candidate.node_weight {
+            evals += 1;
+            score += self.score(node_weight, candidate.weight, candidate.node, tail_length);
+        }
+        if let (Some(return_weight), Some(to_weight)) = (candidate.return_weight, to_weight) {
+            evals += 1;
+            score += self.score(to_weight, return_weight, to, tail_length + candidate.weight);
+        }
+        (score - candidate.penalty, evals)
+    }
+
+    /// Scores all candidates in place.
+    fn score_candidates(
+        &self,
+        candidates: &[Candidate<IndexType, Nw, Ew>],
+        to: IndexType,
+        to_weight: Option<Nw>,
+        tail_length: Ew,
+    ) -> Vec<(R64, usize)> {
+        candidates
+            .iter()
+            .map(|candidate| self.score_candidate(candidate, to, to_weight, tail_length))
+            .collect()
+    }
+
+    /// Scores all candidates, split onto up to threads scoped threads, which each get at least
+    /// MIN_CANDIDATES_PER_THREAD of them.
+    fn score_candidates_threaded(
+        &self,
+        candidates: &[Candidate<IndexType, Nw, Ew>],
+        to: IndexType,
+        to_weight: Option<Nw>,
+        tail_length: Ew,
+        threads: usize,
+    ) -> Vec<(R64, usize)>
+    where
+        IndexType: Sync,
+        Nw: Sync,
+        Ew: Sync,
+        H: Sync,
+    {
+        let threads = threads.min(candidates.len() / MIN_CANDIDATES_PER_THREAD);
+        if threads <= 1 {
+            return self.score_candidates(candidates, to, to_weight, tail_length);
+        }
+        let chunk_size = candidates.len().div_ceil(threads);
+        let score_chunk = |chunk: &[Candidate<IndexType, Nw, Ew>]| -> Vec<(R64, usize)> {
+            self.score_candidates(chunk, to, to_weight, tail_length)
+        };
+        let score_chunk = &score_chunk;
+        thread::scope(|scope| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || score_chunk(chunk)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+pub struct TwoSwap<'a, IndexType, NodeWeightType: Default, EdgeWeightType: Default, W: Write> {
     graph: &'a RefCell<
         dyn GenericWeightedGraph<
-            IndexType = IndexType,
-            NodeWeightType = NodeWeightType,
-            EdgeWeightType = EdgeWeightType,
-        >,
+                IndexType = IndexType,
+                NodeWeightType = NodeWeightType,
+                EdgeWeightType = EdgeWeightType,
+            > + 'a,
     >,
     goal_point: IndexType,
     heuristic: &'a Heuristic<NodeWeightType, EdgeWeightType>,
     max_time: EdgeWeightType,
+    mandatory_nodes: HashSet<IndexType>,
+    revisit_penalty: RevisitPenalty,
+    objective: Objective,
+    secondary_budget: Option<SecondaryBudget<IndexType, EdgeWeightType>>,
+    service_times: ServiceTimes<IndexType, EdgeWeightType>,
+    // scores the candidates on threads, if set by with_threads
+    threaded_scoring: Option<ThreadedScoring<'a, IndexType, NodeWeightType, EdgeWeightType>>,
     pub best_solution: Solution<IndexType>,
     pub best_score: R64,
     pub best_length: EdgeWeightType,
     pub supervisor: Supervisor<W, NodeWeightType, EdgeWeightType>,
+    observer: Observer<'a, IndexType, EdgeWeightType>,
     i: usize,
 }
 
@@ -56,9 +187,9 @@ where
         + Debug
         + Add<Output = NodeWeightType>
         + Sub<Output = NodeWeightType>
-        + Serialize
+        + Into<f64>
         + Default
-        + Zero
+        + NodeValue
         + AddAssign<NodeWeightType>
         + PartialEq
         + SmallVal,
@@ -72,7 +203,8 @@ where
         + Sum
         + Div<Output = EdgeWeightType>
         + Default
-        + Serialize
+        + From<R64>
+        + Into<f64>
         + Debug,
     W: Write,
 {
@@ -83,12 +215,17 @@ where
         point: IndexType,
         distance_up_to: EdgeWeightType,
     ) -> R64 {
-        (self.heuristic)(
-            node_weight,
-            edge_weight,
-            IndexType::distance(self.goal_point, point),
-            distance_up_to / self.max_time,
-        )
+        self.scorer()
+            .score(node_weight, edge_weight, point, distance_up_to)
+    }
+
+    fn scorer(&self) -> Scorer<'a, IndexType, NodeWeightType, EdgeWeightType> {
+        Scorer {
+            heuristic: self.heuristic,
+            goal_point: self.goal_point,
+            max_time: self.max_time,
+            node_weight_type: PhantomData,
+        }
     }
 
     fn score_edge(&self, from: IndexType, to: IndexType, distance_up_to: EdgeWeightType) -> R64 {
@@ -114,6 +251,84 @@ where
         )
     }
 
+    /// Returns true if a solution with score and length is better than one with best_score and
+    /// best_length by the objective of the instance, or false otherwise.
+    fn improves(
+        &self,
+        score: R64,
+        length: EdgeWeightType,
+        best_score: R64,
+        best_length: EdgeWeightType,
+    ) -> bool {
+        self.objective.improves(
+            score,
+            R64::from_inner(length.into()),
+            best_score,
+            R64::from_inner(best_length.into()),
+        )
+    }
+
+    /// Returns the penalty for moving from from to to, given the nodes and edges of the new solution.
+    fn revisit_cost(
+        &self,
+        visited: &HashMap<IndexType, bool>,
+        traversed: &HashSet<Edge<IndexType>>,
+        from: IndexType,
+        to: IndexType,
+    ) -> R64 {
+        self.revisit_penalty.of(
+            to != self.goal_point && visited.contains_key(&to),
+            edge_traversed(traversed, (from, to)),
+        )
+    }
+
+    /// Returns the service time of node, which is spent at the first arrival at it.
+    fn service_time(&self, node: &IndexType) -> EdgeWeightType {
+        self.service_times
+            .get(node)
+            .copied()
+            .unwrap_or_else(EdgeWeightType::zero)
+    }
+
+    /// Returns the service time spent along solution, once for every node it arrives at.
+    fn route_service_time(&self, solution: &Solution<IndexType>) -> EdgeWeightType {
+        let mut arrived = HashSet::new();
+        solution
+            .iter_nodes()
+            .skip(1)
+            .filter(|node| arrived.insert(**node))
+            .map(|node| self.service_time(node))
+            .fold(EdgeWeightType::zero(), |sum, service_time| {
+                sum + service_time
+            })
+    }
+
+    /// Returns the length of solution including the service times spent along it, or None if
+    /// it uses edges, which are not in the graph.
+    fn route_length(&self, solution: &Solution<IndexType>) -> Option<EdgeWeightType> {
+        solution_length_with_service_times(
+            solution,
+            self.graph,
+            &self.service_times,
+            &TimeWindows::new(),
+        )
+        .ok()
+    }
+
+    /// Returns the amount of the secondary budget consumed by traversing edge.
+    fn secondary_consumption(&self, edge: Edge<IndexType>) -> EdgeWeightType {
+        self.secondary_budget
+            .as_ref()
+            .map_or_else(EdgeWeightType::zero, |budget| budget.consumption(edge))
+    }
+
+    /// Returns true if consumed stays within the secondary budget or there is none.
+    fn fits_secondary(&self, consumed: EdgeWeightType) -> bool {
+        self.secondary_budget
+            .as_ref()
+            .is_none_or(|budget| budget.fits(consumed))
+    }
+
     fn send_message(
         &self,
         iteration: usize,
@@ -142,41 +357,75 @@ where
             }
         }
 
-        tx.send(Message::new(
-            iteration,
-            evaluations,
-            n_improvements,
-            changes,
-            phase,
-            cpu_time,
-            distance,
-            heuristic_score,
-            visited_nodes,
-            visited_with_val,
-            val_sum,
-        ))
+        let secondary_resource = self
+            .secondary_budget
+            .as_ref()
+            .map_or(0.0, |budget| budget.of_route(solution).into());
+        tx.send(
+            Message::new(
+                iteration,
+                evaluations,
+                n_improvements,
+                changes,
+                phase,
+                cpu_time,
+                distance,
+                heuristic_score,
+                visited_nodes,
+                visited_with_val,
+                val_sum,
+            )
+            .with_secondary_resource(secondary_resource),
+        )
         .unwrap();
     }
 
     pub fn initialize(&mut self) {
         let start_time = Instant::now();
         let mut evals = 0;
-        // we take the node with best score we can also get back from
+        // we take the best round trip to a neighbor we can also get back from, which fits the
+        // secondary budget
         let max = self
             .graph
             .borrow()
             .iter_neighbors(self.goal_point)
             .unwrap()
-            .filter(|(id, _)| self.graph.borrow().has_edge((*id, self.goal_point)))
-            .map(|(id, weight)| -> (IndexType, R64) {
+            .filter_map(|(id, weight)| {
+                let back = *self
+                    .graph
+                    .borrow()
+                    .edge_weight((id, self.goal_point))
+                    .ok()?;
+                // the round trip arrives at id and back at the goal point for the first time
+                let service_time = self.service_time(&id) + self.service_time(&self.goal_point);
+                Some((id, *weight, back + service_time))
+            })
+            .filter(|&(id, _, _)| {
+                self.fits_secondary(
+                    self.secondary_consumption((self.goal_point, id))
+                        + self.secondary_consumption((id, self.goal_point)),
+                )
+            })
+            .map(|(id, weight, back)| -> (IndexType, R64, EdgeWeightType) {
                 (
                     id,
-                    self.score_with_known_edge(id, *weight, EdgeWeightType::zero())
-                        + self.score_edge(id, self.goal_point, EdgeWeightType::zero()),
+                    // going back to the goal point always traverses the first edge again
+                    self.score_with_known_edge(id, weight, EdgeWeightType::zero())
+                        + self.score_edge(id, self.goal_point, EdgeWeightType::zero())
+                        - self.revisit_penalty.edge,
+                    weight + back,
                 )
             })
             .inspect(|_| evals += 1)
-            .max_by(|(_, ev_a), (_, ev_b)| ev_a.partial_cmp(ev_b).unwrap());
+            .max_by(|(_, score_a, length_a), (_, score_b, length_b)| {
+                self.objective
+                    .value(*score_a, R64::from_inner((*length_a).into()))
+                    .cmp(
+                        &self
+                            .objective
+                            .value(*score_b, R64::from_inner((*length_b).into())),
+                    )
+            });
 
         // if there is no path back max will have no solution
         if let Some(solution) = max {
@@ -184,7 +433,7 @@ where
             self.best_solution.push_node(solution.0);
             self.best_solution.push_node(self.goal_point);
             self.best_score = solution.1;
-            self.best_length = solution_length(&self.best_solution, self.graph).unwrap();
+            self.best_length = self.route_length(&self.best_solution).unwrap();
         }
 
         self.send_message(
@@ -201,6 +450,33 @@ where
         self.i += 1;
     }
 
+    /// Replaces the current solution by solution, which starts and ends at the goal point, so
+    /// the following iterations refine it. It is scored like the solutions found by expand.
+    /// Meant to be called before the first iteration, solutions exceeding the time budget or
+    /// using missing edges are ignored.
+    pub fn warm_start(&mut self, solution: &Solution<IndexType>) {
+        let length = match self.route_length(solution) {
+            Some(length) if length <= self.max_time => length,
+            _ => return,
+        };
+        let mut visited = HashMap::new();
+        let mut traversed = HashSet::new();
+        let mut tail_length = EdgeWeightType::zero();
+        let mut score = R64::zero();
+        for (&from, &to) in solution.iter_edges() {
+            if !visited.contains_key(&to) {
+                score += self.score_edge(from, to, tail_length);
+            }
+            score -= self.revisit_cost(&visited, &traversed, from, to);
+            visited.insert(to, true);
+            traversed.insert((from, to));
+            tail_length += *self.graph.borrow().edge_weight((from, to)).unwrap();
+        }
+        self.best_solution = solution.clone();
+        self.best_score = score;
+        self.best_length = length;
+    }
+
     pub fn current_solution(&self) -> (&Solution<IndexType>, R64, EdgeWeightType) {
         (&self.best_solution, self.best_score, self.best_length)
     }
@@ -210,80 +486,171 @@ where
         self.supervisor.aggregate_receive();
     }
 
+    /// Returns the weight of the solution edge from from to to, the weight of to and the
+    /// neighbors of from, which could be inserted into the edge.
+    /// The graph is only borrowed while they are looked up, scoring them doesn't need it.
+    fn candidates(
+        &self,
+        from: IndexType,
+        to: IndexType,
+        visited: &HashMap<IndexType, bool>,
+        traversed: &HashSet<Edge<IndexType>>,
+    ) -> (
+        EdgeWeightType,
+        NodeWeightType,
+        Vec<Candidate<IndexType, NodeWeightType, EdgeWeightType>>,
+    ) {
+        let graph = self.graph.borrow();
+        let candidates = graph
+            .iter_neighbors(from)
+            .unwrap()
+            .map(|(nid, weight)| {
+                let return_weight = graph.edge_weight((nid, to)).ok().copied();
+                let mut penalty = self.revisit_cost(visited, traversed, from, nid);
+                if return_weight.is_some() {
+                    penalty += self.revisit_cost(visited, traversed, nid, to);
+                }
+                Candidate {
+                    node: nid,
+                    // nodes that have been visited before don't have a value to us
+                    node_weight: if visited.contains_key(&nid) {
+                        None
+                    } else {
+                        Some(*graph.node_weight(nid).unwrap())
+                    },
+                    weight: *weight,
+                    return_weight,
+                    penalty,
+                }
+            })
+            .collect();
+        (
+            *graph.edge_weight((from, to)).unwrap(),
+            *graph.node_weight(to).unwrap(),
+            candidates,
+        )
+    }
+
     pub fn expand(&mut self, start_time: Instant) -> bool {
         let mut evals = 0;
         let mut new_best = Solution::from_nodes(vec![self.goal_point]);
         let mut head_length = self.best_length; // initialized to the 0 of Ew
         let mut tail_length = EdgeWeightType::zero();
         let mut temp_visited = HashMap::new();
+        let mut temp_traversed = HashSet::new();
         let mut max: R64;
         let mut score = R64::zero();
         let mut prev_best_score = self.best_score;
-        let mut temp_score: R64;
+        let mut prev_best_length = self.best_length;
         let mut temp_new_distance = tail_length;
+        // consumption of the secondary budget, split like the lengths
+        let mut head_consumed = self
+            .secondary_budget
+            .as_ref()
+            .map_or_else(EdgeWeightType::zero, |budget| {
+                budget.of_route(&self.best_solution)
+            });
+        let mut tail_consumed = EdgeWeightType::zero();
+        let mut temp_new_consumed = tail_consumed;
+        // the service times of the old solution stay part of the head, only the ones of newly
+        // visited nodes are added to the tail
+        let old_nodes: HashSet<IndexType> =
+            self.best_solution.iter_nodes().skip(1).copied().collect();
+        let old_service_time = self.route_service_time(&self.best_solution);
         let mut improvements = 0;
         let mut changes = 0;
-        let g_borrowed = self.graph.borrow();
+        let scorer = self.scorer();
         for (from, to) in self.best_solution.iter_edges() {
-            let original_distance = *g_borrowed.edge_weight((*from, *to)).unwrap();
-            // temp_visited.insert(*from, true);
-            let t_weight = g_borrowed.node_weight(*to).unwrap();
+            let (original_distance, t_weight, candidates) =
+                self.candidates(*from, *to, &temp_visited, &temp_traversed);
             // if we already visited the node we can ignore it
             max = if temp_visited.contains_key(to) {
                 R64::zero()
             } else {
                 evals += 1;
-                self.score(*t_weight, original_distance, *to, tail_length)
-            };
+                self.score(t_weight, original_distance, *to, tail_length)
+            } - self.revisit_cost(&temp_visited, &temp_traversed, *from, *to);
             let mut best_follow = *to;
+            let original_consumption = self.secondary_consumption((*from, *to));
 
-            for (nid, weight) in g_borrowed.iter_neighbors(*from).unwrap() {
-                // nodes that have been visited before don't have a value to us
-                temp_score = if temp_visited.contains_key(&nid) {
-                    R64::zero()
-                } else {
-                    evals += 1;
-                    self.score_with_known_edge(nid, *weight, tail_length)
-                };
-                if let Ok(return_weight) = g_borrowed.edge_weight((nid, *to)) {
-                    // only score this edge if the to node has not yet been visited
-                    temp_score += if temp_visited.contains_key(to) {
-                        R64::zero()
+            // only score the edge to the to node if it has not yet been visited
+            let to_weight = if temp_visited.contains_key(to) {
+                None
+            } else {
+                Some(t_weight)
+            };
+            let scores = match &self.threaded_scoring {
+                Some(score_candidates) => {
+                    score_candidates(&candidates, *to, to_weight, tail_length)
+                }
+                None => scorer.score_candidates(&candidates, *to, to_weight, tail_length),
+            };
+
+            for (candidate, (temp_score, candidate_evals)) in candidates.iter().zip(scores) {
+                evals += candidate_evals;
+                if let Some(return_weight) = candidate.return_weight {
+                    let service_time = if candidate.node_weight.is_some()
+                        && !old_nodes.contains(&candidate.node)
+                    {
+                        self.service_time(&candidate.node)
                     } else {
-                        evals += 1;
-                        self.score(*t_weight, *return_weight, *to, tail_length + *weight)
+                        EdgeWeightType::zero()
                     };
-                    let new_distance =
-                        tail_length + head_length - original_distance + *weight + *return_weight;
-                    if temp_score > max && new_distance <= self.max_time {
+                    let new_distance = tail_length + head_length - original_distance
+                        + candidate.weight
+                        + return_weight
+                        + service_time;
+                    let consumption = self.secondary_consumption((*from, candidate.node))
+                        + self.secondary_consumption((candidate.node, *to));
+                    if temp_score > max
+                        && new_distance <= self.max_time
+                        && self.fits_secondary(
+                            tail_consumed + head_consumed - original_consumption + consumption,
+                        )
+                    {
                         max = temp_score;
-                        best_follow = nid;
-                        temp_new_distance = *weight + *return_weight;
+                        best_follow = candidate.node;
+                        temp_new_distance = candidate.weight + return_weight + service_time;
+                        temp_new_consumed = consumption;
                     }
                 }
             }
 
             head_length -= original_distance;
+            head_consumed -= original_consumption;
             if best_follow != *to {
                 changes += 1;
                 temp_visited.insert(best_follow, true);
                 temp_visited.insert(*to, true);
+                temp_traversed.insert((*from, best_follow));
+                temp_traversed.insert((best_follow, *to));
                 new_best.push_node(best_follow);
                 new_best.push_node(*to);
                 tail_length += temp_new_distance;
+                tail_consumed += temp_new_consumed;
             } else {
                 temp_visited.insert(*to, true);
+                temp_traversed.insert((*from, *to));
                 new_best.push_node(*to);
                 tail_length += original_distance;
+                tail_consumed += original_consumption;
             }
             score += max;
-            if score > prev_best_score {
+            // the rest of the old solution is still part of the new one
+            if self.improves(
+                score,
+                tail_length + head_length,
+                prev_best_score,
+                prev_best_length,
+            ) {
                 improvements += 1;
                 prev_best_score = score;
+                prev_best_length = tail_length + head_length;
             }
         }
 
-        if score > self.best_score {
+        let length = tail_length + old_service_time;
+        if self.improves(score, length, self.best_score, self.best_length) {
             self.send_message(
                 self.i,
                 evals,
@@ -291,7 +658,7 @@ where
                 changes,
                 0,
                 start_time.elapsed(),
-                tail_length,
+                length,
                 score,
                 &new_best,
             );
@@ -299,7 +666,7 @@ where
             self.i += 1;
             self.best_solution = new_best;
             self.best_score = score;
-            self.best_length = tail_length;
+            self.best_length = length;
 
             true
         } else {
@@ -346,6 +713,10 @@ where
                             .borrow()
                             .edge_weight((nodes[i + 1], nodes[i + 2]))
                             .unwrap()
+                    // without consuming more of the secondary budget
+                    && self.secondary_consumption((nodes[i], nodes[i + 2]))
+                        <= self.secondary_consumption((nodes[i], nodes[i + 1]))
+                            + self.secondary_consumption((nodes[i + 1], nodes[i + 2]))
                 {
                     length += n_dist;
                     improvements += 1;
@@ -369,7 +740,12 @@ where
             }
         }
 
-        if improvements != 0 {
+        // only revisits are skipped, so the same service times are spent
+        let length = length + self.route_service_time(&new_solution);
+        // a contraction must never drop a mandatory node, which was visited before
+        let keeps_mandatory = new_solution.visits_all(&self.mandatory_nodes)
+            || !self.best_solution.visits_all(&self.mandatory_nodes);
+        if improvements != 0 && keeps_mandatory {
             self.send_message(
                 self.i,
                 0,
@@ -393,6 +769,33 @@ where
     }
 }
 
+impl<'a, IndexType, Nw, Ew, W> TwoSwap<'a, IndexType, Nw, Ew, W>
+where
+    IndexType: Distance<IndexType> + Copy + Sync + 'a,
+    Nw: Copy + Default + Sync + 'a,
+    Ew: Copy + Default + Add<Output = Ew> + Div<Output = Ew> + Sync + 'a,
+    W: Write,
+{
+    /// Scores the candidates of each solution edge on up to threads scoped threads, which share
+    /// heuristic, so it replaces the heuristic of the params. Each thread gets at least
+    /// MIN_CANDIDATES_PER_THREAD candidates, so only nodes with many neighbors are split up.
+    pub fn with_threads(mut self, heuristic: &'a SyncHeuristic<Nw, Ew>, threads: usize) -> Self {
+        self.heuristic = heuristic;
+        let scorer = Scorer {
+            heuristic,
+            goal_point: self.goal_point,
+            max_time: self.max_time,
+            node_weight_type: PhantomData,
+        };
+        self.threaded_scoring = Some(Box::new(
+            move |candidates: &[Candidate<IndexType, Nw, Ew>], to, to_weight, tail_length| {
+                scorer.score_candidates_threaded(candidates, to, to_weight, tail_length, threads)
+            },
+        ));
+        self
+    }
+}
+
 impl<'a, IndexType, Nw, Ew, W> Metaheuristic<'a, IndexType, Nw, Ew>
     for TwoSwap<'a, IndexType, Nw, Ew, W>
 where
@@ -401,9 +804,9 @@ where
         + Debug
         + Add<Output = Nw>
         + Sub<Output = Nw>
-        + Serialize
+        + Into<f64>
         + Default
-        + Zero
+        + NodeValue
         + AddAssign<Nw>
         + PartialEq
         + SmallVal,
@@ -417,7 +820,8 @@ where
         + Sum
         + Div<Output = Ew>
         + Default
-        + Serialize
+        + From<R64>
+        + Into<f64>
         + Debug,
     W: Write,
 {
@@ -429,15 +833,28 @@ where
         params: Self::Params,
         supervisor: Self::SupervisorType,
     ) -> Self {
+        // the service times of the node values are spent like the ones of the instance
+        let mut node_service_times = problem.service_times;
+        for (node, service_time) in service_times(&*problem.graph.borrow()) {
+            let node_service_time = node_service_times.entry(node).or_insert_with(Ew::zero);
+            *node_service_time += Ew::from(service_time);
+        }
         let mut swap = TwoSwap {
             graph: problem.graph,
             goal_point: problem.goal_point,
             max_time: problem.max_time,
+            mandatory_nodes: problem.mandatory_nodes,
+            revisit_penalty: problem.revisit_penalty,
+            objective: problem.objective,
+            secondary_budget: problem.secondary_budget,
+            service_times: node_service_times,
             heuristic: params.heuristic,
+            threaded_scoring: None,
             best_solution: Solution::new(),
             best_score: R64::zero(),
             best_length: Ew::zero(),
             supervisor,
+            observer: Observer::default(),
             i: 0,
         };
 
@@ -452,7 +869,21 @@ where
         //     println!("{:?} with weight {:?}", edge, weight);
         // }
         let start_time = Instant::now();
-        if self.expand(start_time) || self.contract(start_time) {
+        let improved = self.expand(start_time) || self.contract(start_time);
+        self.supervisor
+            .add_time(Phase::Construction, start_time.elapsed());
+        self.supervisor.log_route(
+            &self.best_solution,
+            self.best_score.into_inner(),
+            self.best_length.into(),
+        );
+        self.observer.notify(
+            improved,
+            &self.best_solution,
+            self.best_score,
+            self.best_length,
+        );
+        if improved {
             Some(&self.best_solution)
         } else {
             self.send_message(
@@ -471,6 +902,10 @@ where
             None
         }
     }
+
+    fn set_callback(&mut self, callback: IterationCallback<'a, IndexType, Ew>) {
+        self.observer.set(callback);
+    }
 }
 
 impl<'a, IndexType, Nw, Ew, W> Iterator for TwoSwap<'a, IndexType, Nw, Ew, W>
@@ -480,9 +915,9 @@ where
         + Debug
         + Add<Output = Nw>
         + Sub<Output = Nw>
-        + Serialize
+        + Into<f64>
         + Default
-        + Zero
+        + NodeValue
         + AddAssign<Nw>
         + PartialEq
         + SmallVal,
@@ -496,7 +931,8 @@ where
         + Sum
         + Div<Output = Ew>
         + Default
-        + Serialize
+        + From<R64>
+        + Into<f64>
         + Debug,
     W: Write,
 {
@@ -511,9 +947,12 @@ where
 mod tests {
     use super::*;
     use crate::graph::MatrixGraph;
-    use crate::metaheuristic::Metaheuristic;
+    use crate::metaheuristic::aco::{self, Aco};
+    use crate::metaheuristic::{solution_score_with_penalties, Metaheuristic, TimeWindows};
     use csv::Writer;
+    use std::cell::Cell;
     use std::io::{Error, Write};
+    use std::rc::Rc;
     use std::result::Result;
 
     struct Blind {}
@@ -560,7 +999,7 @@ mod tests {
     }
 
     fn blind_supervisor() -> Supervisor<Blind, R64, R64> {
-        Supervisor::new(1, Writer::from_writer(Blind {}))
+        Supervisor::new(1, Blind {})
     }
 
     #[test]
@@ -577,6 +1016,70 @@ mod tests {
         assert_eq!(solution.1, 7.0);
     }
 
+    #[test]
+    fn warm_start_scores_the_given_route() {
+        let graph = RefCell::new(weighted_graph());
+        let route = Solution::from_nodes(vec![0, 3, 4, 3, 0]);
+        let mut optimizer = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(100.0)),
+            Params::new(&nw),
+            blind_supervisor(),
+        );
+        optimizer.warm_start(&route);
+        let solution = optimizer.current_solution();
+        assert_eq!(solution.0, &route);
+        assert_eq!(solution.1, 9.5);
+        assert_eq!(solution.2, 25.1);
+
+        // routes exceeding the budget keep the initial solution
+        let mut optimizer = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(20.0)),
+            Params::new(&nw),
+            blind_supervisor(),
+        );
+        optimizer.warm_start(&route);
+        assert_eq!(optimizer.current_solution().1, 7.0);
+    }
+
+    #[test]
+    fn ants_score_routes_like_two_swap() {
+        let r = R64::from_inner;
+        // the only route collecting both rewards goes back along the edges it came
+        let graph = RefCell::new(
+            MatrixGraph::new_usize_indexed(
+                vec![r(0.0), r(2.0), r(3.0)],
+                vec![
+                    (0, 1, r(1.0)),
+                    (1, 0, r(1.0)),
+                    (1, 2, r(1.0)),
+                    (2, 1, r(1.0)),
+                ],
+            )
+            .unwrap(),
+        );
+        let penalty = RevisitPenalty::new(r(1.0), r(0.5));
+        let problem = || ProblemInstance::new(&graph, 0, r(4.0)).with_revisit_penalty(penalty);
+        let inv_shortest_paths = graph.borrow().inv_shortest_paths(0);
+        let mut ants = Aco::new(
+            problem(),
+            aco::Params::new(&nw, 1.0, 1.0, 0.1, 1.0, Some(3), 1, inv_shortest_paths),
+            aco::Supervisor::new(1, Blind {}),
+        );
+        let _ = ants.single_iteration();
+        let (route, score, _) = ants.current_solution();
+        assert_eq!(route, &Solution::from_nodes(vec![0, 1, 2, 1, 0]));
+        assert_eq!(score, 3.0);
+
+        let mut optimizer = TwoSwap::new(problem(), Params::new(&nw), blind_supervisor());
+        optimizer.warm_start(route);
+        assert_eq!(optimizer.current_solution().1, score);
+        let heuristic: &Heuristic<R64, R64> = &nw;
+        assert_eq!(
+            solution_score_with_penalties(route, &graph, heuristic, &TimeWindows::new(), &penalty),
+            Ok(score)
+        );
+    }
+
     #[test]
     fn single_iteration_works() {
         let graph = RefCell::new(weighted_graph());
@@ -608,4 +1111,198 @@ mod tests {
         assert_eq!(solution.0, &correct);
         assert_eq!(solution.1, 7.8);
     }
+
+    #[test]
+    fn expansions_have_to_improve_the_objective() {
+        let graph = RefCell::new(weighted_graph());
+        let mut optimizer = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(100.0)).with_objective(
+                Objective::Weighted {
+                    score_weight: R64::from_inner(1.0),
+                    length_weight: R64::from_inner(1.0),
+                },
+            ),
+            Params::new(&nw),
+            blind_supervisor(),
+        );
+        optimizer.solve();
+        let solution = optimizer.current_solution();
+        // inserting 1 gains 0.8, but makes the route 11.5 longer
+        let correct = Solution::<usize>::from_edges(vec![(0, 3), (3, 0)]).unwrap();
+
+        assert_eq!(solution.0, &correct);
+        assert_eq!(solution.1, 7.0);
+    }
+
+    #[test]
+    fn expansions_stay_within_the_secondary_budget() {
+        let graph = RefCell::new(weighted_graph());
+        let consumption = vec![(0, 1, 5.0), (2, 1, 5.0), (3, 1, 5.0), (4, 1, 5.0)]
+            .into_iter()
+            .chain(vec![(0, 3, 1.0), (3, 0, 1.0)])
+            .map(|(from, to, consumed)| ((from, to), R64::from_inner(consumed)))
+            .collect();
+        let mut buffer = Vec::new();
+        {
+            let mut optimizer = TwoSwap::new(
+                ProblemInstance::new(&graph, 0, R64::from_inner(100.0))
+                    .with_secondary_budget(SecondaryBudget::new(consumption, R64::from_inner(4.0))),
+                Params::new(&nw),
+                Supervisor::new(1, &mut buffer),
+            );
+            optimizer.solve();
+            // without the secondary budget 1 would be inserted, see solve_works
+            let correct = Solution::<usize>::from_edges(vec![(0, 3), (3, 0)]).unwrap();
+            assert_eq!(optimizer.current_solution().0, &correct);
+        }
+        let output = String::from_utf8(buffer).unwrap();
+        let mut lines = output.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let column = header
+            .iter()
+            .position(|h| *h == "secondary_resource")
+            .unwrap();
+        assert!(lines.all(|line| line.split(',').nth(column) == Some("2.0")));
+    }
+
+    /// A complete graph on order nodes, so every solution edge has many candidates.
+    fn complete_graph(order: usize) -> MatrixGraph<usize, R64, R64> {
+        let nodes = (0..order)
+            .map(|node| R64::from_inner(node as f64))
+            .collect();
+        let edges = (0..order)
+            .flat_map(|from| {
+                (0..order)
+                    .filter(move |to| *to != from)
+                    .map(move |to| (from, to, R64::from_inner(1.0 + ((from * to) % 7) as f64)))
+            })
+            .collect();
+        MatrixGraph::new_usize_indexed(nodes, edges).unwrap()
+    }
+
+    #[test]
+    fn graph_can_change_between_iterations() {
+        let graph = RefCell::new(weighted_graph());
+        let mut optimizer = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(100.0)),
+            Params::new(&nw),
+            blind_supervisor(),
+        );
+        let _ = optimizer.single_iteration();
+        *graph.borrow_mut().node_weight_mut(2).unwrap() = R64::from_inner(50.0);
+        *graph.borrow_mut().edge_weight_mut((1, 3)).unwrap() = R64::from_inner(3.0);
+        optimizer.solve();
+
+        assert!(optimizer.current_solution().0.iter_nodes().next().is_some());
+    }
+
+    #[test]
+    fn heuristics_without_threads_need_not_be_sync() {
+        let calls = Rc::new(Cell::new(0));
+        let counted = Rc::clone(&calls);
+        let counting = move |n: R64, _: R64, _: R64, _: R64| {
+            counted.set(counted.get() + 1);
+            n
+        };
+        let graph = RefCell::new(complete_graph(5));
+        let mut optimizer = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(40.0)),
+            Params::new(&counting),
+            blind_supervisor(),
+        );
+        optimizer.solve();
+
+        assert!(calls.get() > 0);
+    }
+
+    #[test]
+    fn threads_find_the_same_solutions() {
+        // large enough for the candidates of each edge to be split onto two threads
+        let graph = RefCell::new(complete_graph(2 * MIN_CANDIDATES_PER_THREAD + 1));
+        let mut sequential = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(40.0)),
+            Params::new(&nw),
+            blind_supervisor(),
+        );
+        let mut parallel = TwoSwap::new(
+            ProblemInstance::new(&graph, 0, R64::from_inner(40.0)),
+            Params::new(&nw),
+            blind_supervisor(),
+        )
+        .with_threads(&nw, 4);
+        sequential.solve();
+        parallel.solve();
+
+        assert_eq!(sequential.current_solution(), parallel.current_solution());
+    }
+}
+
+#[cfg(all(test, feature = "nightly"))]
+mod benches {
+    use super::*;
+    use crate::graph::MatrixGraph;
+    use std::io;
+    use test::Bencher;
+    extern crate test;
+
+    fn nw(n: R64, _: R64, _: R64, _: R64) -> R64 {
+        n
+    }
+
+    fn complete_graph(order: usize) -> MatrixGraph<usize, R64, R64> {
+        let nodes = (0..order)
+            .map(|node| R64::from_inner(node as f64))
+            .collect();
+        let edges = (0..order)
+            .flat_map(|from| {
+                (0..order)
+                    .filter(move |to| *to != from)
+                    .map(move |to| (from, to, R64::from_inner(1.0 + ((from * to) % 7) as f64)))
+            })
+            .collect();
+        MatrixGraph::new_usize_indexed(nodes, edges).unwrap()
+    }
+
+    /// Stands in for heuristics, which are expensive to evaluate.
+    fn expensive(n: R64, e: R64, _: R64, _: R64) -> R64 {
+        (0..200).fold(n, |acc, i| acc + (e.into_inner() * i as f64).sin() * 1e-9)
+    }
+
+    /// Expands a solution through a complete graph of 300 nodes, the ratio of the sequential
+    /// and the threaded times is the speedup of scoring the candidates on threads.
+    fn bench_expand(b: &mut Bencher, heuristic: &SyncHeuristic<R64, R64>, threads: usize) {
+        let graph = RefCell::new(complete_graph(300));
+        b.iter(|| {
+            let mut optimizer = TwoSwap::new(
+                ProblemInstance::new(&graph, 0, R64::from_inner(60.0)),
+                Params::new(heuristic),
+                Supervisor::new(1, io::sink()),
+            )
+            .with_threads(heuristic, threads);
+            for _ in 0..5 {
+                optimizer.expand(Instant::now());
+            }
+            test::black_box(optimizer.current_solution().1)
+        })
+    }
+
+    #[bench]
+    fn bench_expand_sequential(b: &mut Bencher) {
+        bench_expand(b, &nw, 1);
+    }
+
+    #[bench]
+    fn bench_expand_4_threads(b: &mut Bencher) {
+        bench_expand(b, &nw, 4);
+    }
+
+    #[bench]
+    fn bench_expand_expensive_sequential(b: &mut Bencher) {
+        bench_expand(b, &expensive, 1);
+    }
+
+    #[bench]
+    fn bench_expand_expensive_4_threads(b: &mut Bencher) {
+        bench_expand(b, &expensive, 4);
+    }
 }
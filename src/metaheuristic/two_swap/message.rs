@@ -2,9 +2,10 @@ use crate::metaheuristic::supervisor;
 use crate::metaheuristic::supervisor::MessageInfo;
 
 use decorum::R64;
-use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::time::Duration;
 
+/// Sent once per iteration to the supervisor, which sums them up into a Record per
+/// aggregation window.
 #[derive(Debug)]
 pub struct Message<Nw, Ew> {
     pub iteration: usize,
@@ -18,6 +19,8 @@ pub struct Message<Nw, Ew> {
     pub visited_nodes: usize,
     pub visited_nodes_with_val: usize,
     pub collected_val: Nw,
+    /// Consumption of the secondary budget, 0 for instances without one.
+    pub secondary_resource: f64,
 }
 
 impl<Nw, Ew> Message<Nw, Ew> {
@@ -46,29 +49,21 @@ impl<Nw, Ew> Message<Nw, Ew> {
             visited_nodes,
             visited_nodes_with_val,
             collected_val,
+            secondary_resource: 0.0,
         }
     }
 
-    pub fn from_info(iteration: usize, info: MessageInfo<Nw, Ew>) -> Self {
-        Self {
-            iteration,
-            evaluations: info.evaluations,
-            n_improvements: info.n_improvements,
-            changes: info.changes,
-            phase: info.phase,
-            cpu_time: info.cpu_time,
-            distance: info.distance,
-            heuristic_score: info.heuristic_score,
-            visited_nodes: info.visited_nodes,
-            visited_nodes_with_val: info.visited_nodes_with_val,
-            collected_val: info.collected_val,
-        }
+    /// Sets the consumption of the secondary budget of the solution.
+    pub fn with_secondary_resource(mut self, secondary_resource: f64) -> Self {
+        self.secondary_resource = secondary_resource;
+        self
     }
 }
 
 impl<Nw: Copy, Ew: Copy> supervisor::Message for Message<Nw, Ew> {
     type EwType = Ew;
     type NwType = Nw;
+
     fn get_info(&self) -> MessageInfo<Nw, Ew> {
         MessageInfo::new(
             self.evaluations,
@@ -82,49 +77,14 @@ impl<Nw: Copy, Ew: Copy> supervisor::Message for Message<Nw, Ew> {
             self.visited_nodes_with_val,
             self.collected_val,
         )
+        .with_secondary_resource(self.secondary_resource)
     }
-}
 
-impl<Nw: Serialize, Ew: Serialize> Serialize for Message<Nw, Ew> {
-    default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 11 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 11)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("n_improvements", &self.n_improvements)?;
-        state.serialize_field("changes", &self.changes)?;
-        state.serialize_field("phase", &self.phase)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance)?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val)?;
-        state.end()
+    fn iteration(&self) -> Option<usize> {
+        Some(self.iteration)
     }
-}
 
-impl Serialize for Message<R64, R64> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 12 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("Message", 11)?;
-        state.serialize_field("iteration", &self.iteration)?;
-        state.serialize_field("evaluations", &self.evaluations)?;
-        state.serialize_field("n_improvements", &self.n_improvements)?;
-        state.serialize_field("changes", &self.changes)?;
-        state.serialize_field("phase", &self.phase)?;
-        state.serialize_field("cpu_time_mus", &self.cpu_time.as_micros())?;
-        state.serialize_field("distance", &self.distance.into_inner())?;
-        state.serialize_field("heuristic_score", &self.heuristic_score.into_inner())?;
-        state.serialize_field("visited_nodes", &self.visited_nodes)?;
-        state.serialize_field("visited_nodes_with_val", &self.visited_nodes_with_val)?;
-        state.serialize_field("collected_val", &self.collected_val.into_inner())?;
-        state.end()
+    fn completes_iteration(&self) -> bool {
+        true
     }
 }
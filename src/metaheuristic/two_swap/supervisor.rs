@@ -1,99 +1,75 @@
 use crate::metaheuristic::supervisor;
-use crate::metaheuristic::supervisor::{Message, MessageInfo};
+use crate::metaheuristic::supervisor::{FromInfo, MessageInfo, RunInfo, Totals};
 use crate::metaheuristic::two_swap;
 
-use csv::Writer;
-use serde::Serialize;
-use std::default::Default;
-use std::io::{stderr, Stderr, Write};
-use std::ops::Add;
-use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
 
-pub struct Supervisor<W: Write, Nw: Serialize + Sized, Ew: Serialize + Sized> {
-    sender: Sender<two_swap::Message<Nw, Ew>>,
-    receiver: Receiver<two_swap::Message<Nw, Ew>>,
-    messages: Vec<MessageInfo<Nw, Ew>>,
-    writer: Writer<W>,
-    aggregation_rate: usize,
-}
-
-impl<W, Nw, Ew> Supervisor<W, Nw, Ew>
-where
-    W: Write,
-    Nw: Serialize + Default + Add<Output = Nw> + Copy,
-    Ew: Serialize + Default + Add<Output = Ew> + Copy,
-{
-    pub fn new(aggregation_rate: usize, writer: Writer<W>) -> Self {
-        let (tx, rx) = mpsc::channel();
-        Supervisor {
-            sender: tx,
-            receiver: rx,
-            messages: Vec::default(),
-            writer,
-            aggregation_rate,
-        }
-    }
-
-    pub fn sender(&self) -> Sender<two_swap::Message<Nw, Ew>> {
-        self.sender.clone()
-    }
-
-    pub fn aggregate_receive(&mut self) {
-        while let Ok(message) = self.receiver.recv_timeout(Duration::from_millis(1)) {
-            let idx = message.iteration / self.aggregation_rate;
-            if idx >= self.messages.len() {
-                self.messages.resize_with(idx + 1, Default::default);
-            }
-            self.messages[idx] += message.get_info();
-        }
+pub type Supervisor<W, Nw, Ew> =
+    supervisor::Supervisor<W, two_swap::Message<Nw, Ew>, Totals<Nw, Ew, Record>>;
 
-        for i in 0..self.messages.len() {
-            let msg_info = self.messages.get(i).unwrap();
-            let record = two_swap::Message::new(
-                i * self.aggregation_rate,
-                msg_info.evaluations,
-                msg_info.n_improvements,
-                msg_info.changes,
-                msg_info.phase,
-                msg_info.cpu_time,
-                msg_info.distance,
-                msg_info.heuristic_score,
-                msg_info.visited_nodes,
-                msg_info.visited_nodes_with_val,
-                msg_info.collected_val,
-            );
-            let res = self.writer.serialize(record);
-            if let Err(err) = res {
-                eprintln!("{:?}", err);
-            }
-        }
-    }
+/// Row of a TwoSwap log, summed up over one aggregation window.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Record {
+    pub algorithm: String,
+    pub heuristic: String,
+    pub run_id: String,
+    pub params: String,
+    /// First iteration of the aggregation window.
+    pub iteration: usize,
+    pub evals: usize,
+    pub improvements: usize,
+    pub distance: f64,
+    pub score: f64,
+    pub visited: usize,
+    pub visited_with_val: usize,
+    pub val_sum: f64,
+    pub cpu_time_us: u128,
+    pub changes: usize,
+    pub phase: usize,
+    /// Consumption of the secondary budget, next to the distance.
+    pub secondary_resource: f64,
 }
 
-impl<W, Nw: Copy, Ew: Copy> supervisor::Supervisor<two_swap::Message<Nw, Ew>>
-    for Supervisor<W, Nw, Ew>
-where
-    W: Write,
-    Nw: Serialize + Default + Add<Output = Nw>,
-    Ew: Serialize + Default + Add<Output = Ew>,
-{
+impl supervisor::Record for Record {
+    const COLUMNS: &'static [&'static str] = &[
+        "algorithm",
+        "heuristic",
+        "run_id",
+        "params",
+        "iteration",
+        "evals",
+        "improvements",
+        "distance",
+        "score",
+        "visited",
+        "visited_with_val",
+        "val_sum",
+        "cpu_time_us",
+        "changes",
+        "phase",
+        "secondary_resource",
+    ];
 }
 
-impl<Nw, Ew> Default for Supervisor<Stderr, Nw, Ew>
-where
-    Nw: Serialize + Default + Add<Output = Nw>,
-    Ew: Serialize + Default + Add<Output = Ew>,
-{
-    fn default() -> Self {
-        let (tx, rx) = mpsc::channel();
-        Supervisor {
-            sender: tx,
-            receiver: rx,
-            messages: Vec::default(),
-            writer: Writer::from_writer(stderr()),
-            aggregation_rate: 1,
+impl<Nw: Copy + Into<f64>, Ew: Copy + Into<f64>> FromInfo<Nw, Ew> for Record {
+    fn from_info(iteration: usize, info: &MessageInfo<Nw, Ew>, run: &RunInfo) -> Self {
+        Record {
+            algorithm: run.algorithm.clone(),
+            heuristic: run.heuristic.clone(),
+            run_id: run.run_id.clone(),
+            params: run.params.clone(),
+            iteration,
+            evals: info.evaluations,
+            improvements: info.n_improvements,
+            distance: info.distance.into(),
+            score: info.heuristic_score.into_inner(),
+            visited: info.visited_nodes,
+            visited_with_val: info.visited_nodes_with_val,
+            val_sum: info.collected_val.into(),
+            cpu_time_us: info.cpu_time.as_micros(),
+            changes: info.changes,
+            phase: info.phase,
+            secondary_resource: info.secondary_resource,
         }
     }
 }
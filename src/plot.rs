@@ -0,0 +1,312 @@
+use crate::graph::export::svg::{color, range};
+
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+use tera::{Context, Tera};
+
+/// Amount of labeled ticks on each axis.
+const TICKS: usize = 5;
+
+/// Columns of a supervisor log, which all algorithms write. Other columns are ignored.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub algorithm: String,
+    pub heuristic: String,
+    pub run_id: String,
+    pub iteration: usize,
+    pub score: f64,
+    pub cpu_time_us: u128,
+}
+
+/// Reads all records of a supervisor log.
+pub fn read_log<R: Read>(reader: R) -> Result<Vec<LogRecord>, csv::Error> {
+    Reader::from_reader(reader).deserialize().collect()
+}
+
+/// Best score found so far over the iterations and over the cpu time of a run.
+///
+/// Records of logs with an aggregation rate above 1 hold the summed score of their window, so
+/// their mean score per iteration is used instead, which is exact for an aggregation rate of 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Convergence {
+    pub label: String,
+    pub by_iteration: Vec<(f64, f64)>,
+    /// Cpu time in milliseconds at the end of each window.
+    pub by_cpu_time: Vec<(f64, f64)>,
+}
+
+impl Convergence {
+    pub fn of(label: &str, records: &[LogRecord]) -> Self {
+        let mut best = f64::NEG_INFINITY;
+        let mut cpu_time_us = 0;
+        let mut by_iteration = Vec::with_capacity(records.len());
+        let mut by_cpu_time = Vec::with_capacity(records.len());
+        for (i, record) in records.iter().enumerate() {
+            // the last window is assumed to be as long as the one before
+            let window = match (records.get(i + 1), i.checked_sub(1)) {
+                (Some(next), _) => next.iteration - record.iteration,
+                (None, Some(previous)) => record.iteration - records[previous].iteration,
+                (None, None) => 1,
+            }
+            .max(1);
+            best = best.max(record.score / window as f64);
+            cpu_time_us += record.cpu_time_us;
+            by_iteration.push(((record.iteration + window) as f64, best));
+            by_cpu_time.push((cpu_time_us as f64 / 1000.0, best));
+        }
+
+        Convergence {
+            label: label.to_string(),
+            by_iteration,
+            by_cpu_time,
+        }
+    }
+}
+
+/// A series of points drawn as line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Serialize)]
+struct Tick {
+    position: f64,
+    label: String,
+}
+
+#[derive(Serialize)]
+struct Line {
+    points: String,
+    color: String,
+    label: String,
+    legend_y: f64,
+}
+
+/// Evenly spaced ticks over range, mapped onto the canvas with position.
+fn ticks((min, max): (f64, f64), position: impl Fn(f64) -> f64) -> Vec<Tick> {
+    let precision = if max - min >= 10.0 { 0 } else { 2 };
+    (0..TICKS)
+        .map(|i| {
+            let value = min + (max - min) * i as f64 / (TICKS - 1) as f64;
+            Tick {
+                position: position(value),
+                label: format!("{:.*}", precision, value),
+            }
+        })
+        .collect()
+}
+
+/// Draws series as line chart in svg, with one color and legend entry per series.
+pub struct LineChart {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub width: usize,
+    pub height: usize,
+    pub padding: usize,
+}
+
+impl LineChart {
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> Self {
+        LineChart {
+            title: title.to_string(),
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+            width: 800,
+            height: 500,
+            padding: 60,
+        }
+    }
+
+    pub fn render<W: Write>(&self, series: &[Series], writer: W) -> io::Result<()> {
+        let points = || series.iter().flat_map(|series| series.points.iter());
+        let x_range = range(points().map(|&(x, _)| x));
+        let y_range = range(points().map(|&(_, y)| y));
+        let padding = self.padding as f64;
+        let scale = |value: f64, (min, max): (f64, f64), length: usize| {
+            if max > min {
+                (value - min) / (max - min) * length as f64
+            } else {
+                0.0
+            }
+        };
+        let x = |value| padding + scale(value, x_range, self.width);
+        let y = |value| padding + self.height as f64 - scale(value, y_range, self.height);
+
+        let count = series.len();
+        let lines: Vec<Line> = series
+            .iter()
+            .enumerate()
+            .map(|(i, series)| Line {
+                points: series
+                    .points
+                    .iter()
+                    .map(|&(px, py)| format!("{},{}", x(px), y(py)))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                color: color(i as f64 / count.saturating_sub(1).max(1) as f64),
+                label: series.label.clone(),
+                legend_y: padding + self.height as f64 - 15.0 * (count - i) as f64,
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("title", &self.title);
+        context.insert("x_label", &self.x_label);
+        context.insert("y_label", &self.y_label);
+        context.insert("width", &self.width);
+        context.insert("height", &self.height);
+        context.insert("padding", &self.padding);
+        context.insert("x_ticks", &ticks(x_range, x));
+        context.insert("y_ticks", &ticks(y_range, y));
+        context.insert("lines", &lines);
+
+        let mut template = String::new();
+        File::open("src/templates/line_chart.svg")?.read_to_string(&mut template)?;
+        let mut tera = Tera::default();
+        tera.add_raw_template("line_chart.svg", &template)
+            .map_err(io::Error::other)?;
+        tera.autoescape_on(vec!["line_chart.svg"]);
+        tera.render_to("line_chart.svg", &context, writer)
+            .map_err(io::Error::other)
+    }
+}
+
+/// Draws the convergence of all runs over iterations to `convergence_iterations.svg` and over
+/// cpu time to `convergence_cpu_time.svg` in folder.
+pub fn plot_convergence(runs: &[Convergence], folder: &Path) -> io::Result<()> {
+    let series = |points: fn(&Convergence) -> &Vec<(f64, f64)>| -> Vec<Series> {
+        runs.iter()
+            .map(|run| Series {
+                label: run.label.clone(),
+                points: points(run).clone(),
+            })
+            .collect()
+    };
+    LineChart::new("Best score", "Iteration", "Score").render(
+        &series(|run| &run.by_iteration),
+        BufWriter::new(File::create(folder.join("convergence_iterations.svg"))?),
+    )?;
+    LineChart::new("Best score", "CPU time [ms]", "Score").render(
+        &series(|run| &run.by_cpu_time),
+        BufWriter::new(File::create(folder.join("convergence_cpu_time.svg"))?),
+    )
+}
+
+/// Collects the supervisor logs of all config folders in experiment_dir, which are the folders
+/// with a config.yaml, including experiment_dir itself. Other files are skipped, as they fail
+/// to parse as supervisor log. Runs are labeled with their config folder and run id.
+pub fn collect_runs(experiment_dir: &Path) -> io::Result<Vec<Convergence>> {
+    let mut folders = vec![experiment_dir.to_path_buf()];
+    for entry in fs::read_dir(experiment_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            folders.push(path);
+        }
+    }
+    folders.retain(|folder| folder.join("config.yaml").is_file());
+    folders.sort();
+
+    let mut runs = Vec::new();
+    for folder in folders {
+        let config = folder
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let mut logs: Vec<_> = fs::read_dir(&folder)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        logs.sort();
+        for log in logs {
+            let records = match File::open(&log).map(read_log) {
+                Ok(Ok(records)) if !records.is_empty() => records,
+                _ => continue,
+            };
+            let label = format!("{} {} {}", config, records[0].algorithm, records[0].run_id);
+            runs.push(Convergence::of(&label, &records));
+        }
+    }
+
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(iteration: usize, score: f64, cpu_time_us: u128) -> LogRecord {
+        LogRecord {
+            algorithm: "aco".to_string(),
+            heuristic: "h1".to_string(),
+            run_id: "h1".to_string(),
+            iteration,
+            score,
+            cpu_time_us,
+        }
+    }
+
+    #[test]
+    fn best_scores_never_decrease() {
+        let records = vec![
+            record(0, 2.0, 1000),
+            record(1, 1.0, 500),
+            record(2, 3.0, 1500),
+        ];
+        let convergence = Convergence::of("run", &records);
+        assert_eq!(
+            convergence.by_iteration,
+            vec![(1.0, 2.0), (2.0, 2.0), (3.0, 3.0)]
+        );
+        assert_eq!(
+            convergence.by_cpu_time,
+            vec![(1.0, 2.0), (1.5, 2.0), (3.0, 3.0)]
+        );
+
+        // summed scores of aggregated windows are averaged
+        let records = vec![record(0, 4.0, 0), record(2, 8.0, 0)];
+        let convergence = Convergence::of("run", &records);
+        assert_eq!(convergence.by_iteration, vec![(2.0, 2.0), (4.0, 4.0)]);
+    }
+
+    #[test]
+    fn logs_are_read_by_their_common_columns() {
+        let log =
+            "algorithm,heuristic,run_id,iteration,evals,score,cpu_time_us\naco,h1,h1,0,10,1.5,20\n";
+        assert_eq!(read_log(log.as_bytes()).unwrap(), vec![record(0, 1.5, 20)]);
+        assert!(read_log("period,iteration,best_score\n0,0,1.5\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn every_series_is_drawn_with_legend() {
+        let series = vec![
+            Series {
+                label: "aco h1".to_string(),
+                points: vec![(0.0, 1.0), (10.0, 2.0)],
+            },
+            Series {
+                label: "acs h1".to_string(),
+                points: vec![(0.0, 0.5), (5.0, 3.0)],
+            },
+        ];
+        let mut out = Vec::new();
+        LineChart::new("Best score", "Iteration", "Score")
+            .render(&series, &mut out)
+            .unwrap();
+
+        let svg = String::from_utf8(out).unwrap();
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("points=\"60,460 860,260\""));
+        assert!(svg.contains("stroke=\"#440154\""));
+        assert!(svg.contains("stroke=\"#fde725\""));
+        assert!(svg.contains("acs h1"));
+        assert!(svg.contains(">10<"));
+    }
+}
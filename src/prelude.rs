@@ -0,0 +1,25 @@
+//! The types most tools need to build graphs and run the metaheuristics on them.
+//!
+//! ```ignore
+//! use dop_with_aco::prelude::*;
+//! ```
+//!
+//! Everything re-exported here is part of the stable public api of the crate. The parameters
+//! of each algorithm share the name Params, so they are reached through their modules, for
+//! example `aco::Params`.
+
+pub use crate::geo::GeoPoint;
+pub use crate::graph::export::{Dot, DotOptions, Export, GeoJson, Svg};
+pub use crate::graph::generate::{ErdosRenyi, Generate, Grid, StochasticBlock};
+pub use crate::graph::layout::Layout;
+pub use crate::graph::{
+    Fingerprint, GenericWeightedGraph, GeoGraph, GraphBuilder, GraphCell, GraphError, GraphSummary,
+    MatrixGraph, StochasticWeight, SyncGraph, WeightedGraph,
+};
+pub use crate::metaheuristic::{
+    aco, acs, exact, mm_aco, random_search, two_swap, Aco, Acs, Exact, Heuristic, MMAco,
+    Metaheuristic, NodeValue, NodeVector, ProblemInstance, RandomSearch, Reoptimize, Solution,
+    SolutionError, SyncHeuristic, TwoSwap,
+};
+pub use crate::rng::{Rng, RngKind};
+pub use crate::util::Point;
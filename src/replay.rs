@@ -0,0 +1,421 @@
+use crate::dynamics::{DynamicsEngine, Neighborhood};
+use crate::graph::{GenericWeightedGraph, GraphCell, MatrixGraph};
+use crate::metaheuristic::{
+    solution_length_with_service_times, ServiceTimes, Solution, TimeWindows,
+};
+use crate::rng::RngState;
+use crate::util::Distance;
+
+use csv::{Reader, Writer};
+use decorum::R64;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::io::{Read, Write};
+
+/// State of a run after one iteration, as written to its replay log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayRecord {
+    pub iteration: usize,
+    /// State of the algorithms rng before the iteration, see RngState for its format.
+    /// Empty for deterministic algorithms.
+    pub rng_state: String,
+    pub improved: bool,
+    pub score: f64,
+    pub length: f64,
+    /// Node ids of the best solution found so far, separated by spaces.
+    pub solution: String,
+    /// Seed of the graph dynamics of the run, empty without graph dynamics.
+    #[serde(default)]
+    pub dynamics_seed: Option<u64>,
+    /// Index of the last change of the graph dynamics before the iteration, empty before the
+    /// first change.
+    #[serde(default)]
+    pub period: Option<usize>,
+    /// Steps of the graph dynamics since that change, see DynamicsEngine::step.
+    #[serde(default)]
+    pub step: usize,
+}
+
+/// Writes one ReplayRecord per iteration, which is enough to reconstruct the best solutions of
+/// a run over time and to restart any iteration from its rng state.
+pub struct ReplayLog<W: Write> {
+    writer: Writer<W>,
+    iteration: usize,
+    dynamics_seed: Option<u64>,
+    period: Option<usize>,
+    step: usize,
+}
+
+impl<W: Write> ReplayLog<W> {
+    pub fn new(writer: Writer<W>) -> Self {
+        ReplayLog {
+            writer,
+            iteration: 0,
+            dynamics_seed: None,
+            period: None,
+            step: 0,
+        }
+    }
+
+    /// Records the seed of the graph dynamics, so their graph states can be restored.
+    pub fn with_dynamics_seed(mut self, seed: Option<u64>) -> Self {
+        self.dynamics_seed = seed;
+        self
+    }
+
+    /// Sets the position of the graph dynamics of the following records, the index of the last
+    /// applied change and the steps taken since.
+    pub fn set_dynamics_position(&mut self, period: usize, step: usize) {
+        self.period = Some(period);
+        self.step = step;
+    }
+
+    /// Records the best solution after the current iteration, rng_state is the state before it.
+    pub fn record<IndexType: Copy + Hash + Eq + Display>(
+        &mut self,
+        rng_state: Option<RngState>,
+        improved: bool,
+        solution: &Solution<IndexType>,
+        score: R64,
+        length: R64,
+    ) -> csv::Result<()> {
+        self.writer.serialize(ReplayRecord {
+            iteration: self.iteration,
+            rng_state: rng_state.map(|state| state.to_string()).unwrap_or_default(),
+            improved,
+            score: score.into_inner(),
+            length: length.into_inner(),
+            solution: solution
+                .iter_nodes()
+                .map(|node| node.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            dynamics_seed: self.dynamics_seed,
+            period: self.period,
+            step: self.step,
+        })?;
+        self.iteration += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Restores the graph states of a run with graph dynamics, by repeating the changes and steps
+/// of its dynamics up to the position recorded for each iteration.
+pub struct DynamicsReplay<'g, IndexType> {
+    engine: DynamicsEngine<'g, IndexType>,
+    period: Option<usize>,
+    step: usize,
+}
+
+impl<'g, IndexType> DynamicsReplay<'g, IndexType>
+where
+    IndexType: 'static + Neighborhood + Clone + Hash + Copy + Eq + Debug + Display + Ord,
+{
+    /// The engine has to be created like the one of the run, on the same graph and with the
+    /// seed of the records.
+    pub fn new(engine: DynamicsEngine<'g, IndexType>) -> Self {
+        DynamicsReplay {
+            engine,
+            period: None,
+            step: 0,
+        }
+    }
+
+    /// Brings graph into its state during the iteration of record. Records have to be passed in
+    /// the order they were logged.
+    pub fn advance<C>(&mut self, graph: &C, record: &ReplayRecord)
+    where
+        C: GraphCell<Graph = MatrixGraph<IndexType, R64, R64>> + ?Sized,
+    {
+        while self.period < record.period {
+            self.engine.apply(graph);
+            self.period = Some(self.period.map_or(0, |period| period + 1));
+            self.step = 0;
+        }
+        while self.step < record.step {
+            self.engine.step(graph);
+            self.step += 1;
+        }
+    }
+
+    /// Applies the changes left of all changes of the run, so the generators of the engine are
+    /// left in the same state as after the run.
+    pub fn finish<C>(&mut self, graph: &C, changes: usize)
+    where
+        C: GraphCell<Graph = MatrixGraph<IndexType, R64, R64>> + ?Sized,
+    {
+        while self.period.map_or(0, |period| period + 1) < changes {
+            self.engine.apply(graph);
+            self.period = Some(self.period.map_or(0, |period| period + 1));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    InvalidLog(csv::Error),
+    UnknownNode(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLog(e) => write!(f, "Invalid replay log: {}", e),
+            Self::UnknownNode(node) => write!(f, "Node {} of the replay log not in graph.", node),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Best solution after one iteration, reconstructed from a replay log.
+pub struct ReplayStep<IndexType> {
+    pub record: ReplayRecord,
+    pub solution: Solution<IndexType>,
+    /// Length of the solution in the graph, None if it uses edges missing in the graph.
+    pub length: Option<f64>,
+    /// Sum of the node weights of all distinct nodes of the solution.
+    pub collected: f64,
+}
+
+impl<IndexType> ReplayStep<IndexType> {
+    /// Returns true if the logged length matches the length of the solution in the graph.
+    pub fn consistent(&self) -> bool {
+        self.length
+            .is_some_and(|length| (length - self.record.length).abs() <= 1e-6 * length.max(1.0))
+    }
+}
+
+/// Reads all records of a replay log.
+pub fn read_records<R: Read>(reader: R) -> Result<Vec<ReplayRecord>, ReplayError> {
+    Reader::from_reader(reader)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(ReplayError::InvalidLog)
+}
+
+/// Reconstructs the solutions of records with the nodes of graph. Their lengths include the
+/// service times spent with the given time windows.
+pub fn reconstruct<IndexType>(
+    graph: &RefCell<
+        dyn GenericWeightedGraph<IndexType = IndexType, NodeWeightType = R64, EdgeWeightType = R64>
+            + '_,
+    >,
+    records: Vec<ReplayRecord>,
+    service_times: &ServiceTimes<IndexType, R64>,
+    time_windows: &TimeWindows<IndexType, R64>,
+) -> Result<Vec<ReplayStep<IndexType>>, ReplayError>
+where
+    IndexType: Distance<IndexType> + Copy + Debug + Display + Hash + Eq,
+{
+    let ids: HashMap<String, IndexType> = graph
+        .borrow()
+        .iter_node_ids()
+        .map(|id| (id.to_string(), id))
+        .collect();
+
+    records
+        .into_iter()
+        .map(|record| {
+            let nodes = record
+                .solution
+                .split_whitespace()
+                .map(|node| {
+                    ids.get(node)
+                        .copied()
+                        .ok_or_else(|| ReplayError::UnknownNode(node.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let solution = Solution::from_nodes(nodes);
+            let length =
+                solution_length_with_service_times(&solution, graph, service_times, time_windows)
+                    .ok()
+                    .map(|length| length.into_inner());
+            let collected = solution
+                .iter_unique_nodes()
+                .filter_map(|node| graph.borrow().node_weight(node).ok().copied())
+                .map(|weight| weight.into_inner())
+                .sum();
+
+            Ok(ReplayStep {
+                record,
+                solution,
+                length,
+                collected,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::experiment_config::graph_dynamics_config::FullConfig;
+
+    #[test]
+    fn logged_solutions_are_reconstructed() {
+        let graph = MatrixGraph::new_usize_indexed(
+            vec![
+                R64::from_inner(0.0),
+                R64::from_inner(2.0),
+                R64::from_inner(3.0),
+            ],
+            vec![
+                (0, 1, R64::from_inner(1.0)),
+                (1, 0, R64::from_inner(1.0)),
+                (1, 2, R64::from_inner(2.0)),
+                (2, 0, R64::from_inner(4.0)),
+            ],
+        )
+        .unwrap();
+        let graph = RefCell::new(graph);
+
+        let mut log = ReplayLog::new(Writer::from_writer(Vec::new()));
+        let r = R64::from_inner;
+        log.record(
+            Some(RngState::Pcg { state: 1, inc: 3 }),
+            true,
+            &Solution::from_nodes(vec![0, 1, 0]),
+            r(2.0),
+            r(2.0),
+        )
+        .unwrap();
+        log.record(
+            Some(RngState::Counter { key: 5, counter: 3 }),
+            true,
+            &Solution::from_nodes(vec![0, 1, 2, 0]),
+            r(5.0),
+            r(7.0),
+        )
+        .unwrap();
+        log.record(
+            None,
+            false,
+            &Solution::from_nodes(vec![0, 2, 0]),
+            r(3.0),
+            r(5.0),
+        )
+        .unwrap();
+        let data = log.writer.into_inner().unwrap();
+
+        let records = read_records(data.as_slice()).unwrap();
+        assert_eq!(records[0].rng_state, "1:3");
+        assert_eq!(
+            records[1].rng_state.parse(),
+            Ok(RngState::Counter { key: 5, counter: 3 })
+        );
+        assert_eq!(records[2].rng_state, "");
+
+        let steps =
+            reconstruct(&graph, records, &ServiceTimes::new(), &TimeWindows::new()).unwrap();
+        assert_eq!(steps[1].solution.nodes(), vec![0, 1, 2, 0]);
+        assert_eq!(steps[1].collected, 5.0);
+        assert!(steps[0].consistent());
+        assert!(steps[1].consistent());
+        // 0 -> 2 is not an edge of the graph
+        assert_eq!(steps[2].length, None);
+        assert!(!steps[2].consistent());
+    }
+
+    #[test]
+    fn unknown_nodes_are_rejected() {
+        let graph = RefCell::new(
+            MatrixGraph::new_usize_indexed(
+                vec![R64::from_inner(0.0)],
+                vec![(0, 0, R64::from_inner(1.0))],
+            )
+            .unwrap(),
+        );
+        let record = ReplayRecord {
+            iteration: 0,
+            rng_state: String::new(),
+            improved: false,
+            score: 0.0,
+            length: 0.0,
+            solution: "0 7 0".to_string(),
+            dynamics_seed: None,
+            period: None,
+            step: 0,
+        };
+
+        assert!(reconstruct(
+            &graph,
+            vec![record],
+            &ServiceTimes::new(),
+            &TimeWindows::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn dynamics_positions_restore_the_graph_states() {
+        let cfg = FullConfig {
+            seed: 11,
+            changes: 3,
+            change_after_i: 2,
+            recovery_time_ms: None,
+            edge_change_probability: 0.5,
+            node_change_probability: 0.5,
+            edge_change_intensity: 1.0,
+            node_change_intensity: 1.0,
+            regeneration: None,
+            congestion: None,
+        };
+        let graph = || {
+            RefCell::new(
+                MatrixGraph::new_usize_indexed(
+                    vec![R64::from_inner(1.0); 4],
+                    (0..4)
+                        .flat_map(|i| (0..4).filter(move |&j| j != i).map(move |j| (i, j)))
+                        .map(|(i, j)| (i, j, R64::from_inner(1.0 + i as f64 + j as f64)))
+                        .collect(),
+                )
+                .unwrap(),
+            )
+        };
+        let mut generator = || R64::from_inner(1.0);
+        let mut replay_generator = || R64::from_inner(1.0);
+        let weights = |graph: &RefCell<MatrixGraph<usize, R64, R64>>| {
+            let graph = graph.borrow();
+            let nodes = graph.iter_nodes().map(|(_, &w)| w).collect::<Vec<_>>();
+            let edges = graph.iter_edges().map(|(_, &w)| w).collect::<Vec<_>>();
+            (nodes, edges)
+        };
+
+        // log one iteration before the changes and after each of them, like a run
+        let original = graph();
+        let mut engine = DynamicsEngine::new(&original, cfg, &mut generator, None);
+        let mut log = ReplayLog::new(Writer::from_writer(Vec::new())).with_dynamics_seed(Some(11));
+        let solution = Solution::from_nodes(vec![0, 1, 0]);
+        let r = R64::from_inner;
+        let mut states = vec![weights(&original)];
+        log.record(None, true, &solution, r(1.0), r(3.0)).unwrap();
+        for change in 0..cfg.changes {
+            engine.apply(&original);
+            log.set_dynamics_position(change, 0);
+            states.push(weights(&original));
+            log.record(None, false, &solution, r(1.0), r(3.0)).unwrap();
+        }
+        let records = read_records(log.writer.into_inner().unwrap().as_slice()).unwrap();
+        assert_eq!(records[0].period, None);
+        assert_eq!(records[2].period, Some(1));
+        assert_eq!(records[2].dynamics_seed, Some(11));
+
+        let replayed = graph();
+        let engine = DynamicsEngine::new(&replayed, cfg, &mut replay_generator, None);
+        let mut dynamics = DynamicsReplay::new(engine);
+        for (record, state) in records.iter().zip(states) {
+            dynamics.advance(&replayed, record);
+            assert_eq!(weights(&replayed), state);
+        }
+    }
+}
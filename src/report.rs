@@ -0,0 +1,410 @@
+use crate::metaheuristic::similarity::Similarity;
+use crate::metaheuristic::Solution;
+use crate::replay::read_records;
+
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/// Best score found so far in a change period of a run, as written after every iteration of
+/// the recovery from a change. Iteration 0 is the state right after the change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeriodRecord {
+    pub period: usize,
+    pub iteration: usize,
+    pub best_score: f64,
+}
+
+/// Dynamic optimization metrics of a run, or their mean over several runs.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub run_id: String,
+    pub runs: usize,
+    /// Mean difference between the best-known value of a period and the best score found so far
+    /// in it, over all iterations of all periods.
+    pub offline_error: f64,
+    pub offline_error_std: f64,
+    /// Mean ratio between the best score of a period and its best-known value.
+    pub accuracy: f64,
+    pub accuracy_std: f64,
+}
+
+/// Period log of a run, together with the instance it ran on. Only runs on the same graph
+/// from the same start node solve the same instance and share best-known values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodRun {
+    pub run_id: String,
+    pub fingerprint: String,
+    pub start_node: String,
+    pub records: Vec<PeriodRecord>,
+}
+
+/// Graph fingerprint, start node and period, which identify a best-known value.
+pub type PeriodKey = (String, String, usize);
+
+impl PeriodRun {
+    fn key(&self, period: usize) -> PeriodKey {
+        (self.fingerprint.clone(), self.start_node.clone(), period)
+    }
+}
+
+/// Reads all records of a period log.
+pub fn read_periods<R: Read>(reader: R) -> Result<Vec<PeriodRecord>, csv::Error> {
+    Reader::from_reader(reader).deserialize().collect()
+}
+
+/// Returns the best score of every period of every instance over all runs on it, which is used
+/// as the best-known value of the period, as the actual optimum of a changed instance is unknown.
+pub fn best_known(runs: &[PeriodRun]) -> BTreeMap<PeriodKey, f64> {
+    let mut best = BTreeMap::new();
+    for run in runs {
+        for record in run.records.iter() {
+            let score = best
+                .entry(run.key(record.period))
+                .or_insert(record.best_score);
+            *score = score.max(record.best_score);
+        }
+    }
+    best
+}
+
+fn accuracy(score: f64, best_known: f64) -> f64 {
+    if best_known > 0.0 {
+        score / best_known
+    } else {
+        1.0
+    }
+}
+
+/// Computes the metrics of a single run against the best-known values of its periods.
+pub fn run_metrics(run: &PeriodRun, best_known: &BTreeMap<PeriodKey, f64>) -> Metrics {
+    let errors: Vec<f64> = run
+        .records
+        .iter()
+        .map(|record| best_known[&run.key(record.period)] - record.best_score)
+        .collect();
+
+    let mut period_best = BTreeMap::new();
+    for record in run.records.iter() {
+        let score = period_best
+            .entry(record.period)
+            .or_insert(record.best_score);
+        *score = score.max(record.best_score);
+    }
+    let accuracies: Vec<f64> = period_best
+        .iter()
+        .map(|(&period, &score)| accuracy(score, best_known[&run.key(period)]))
+        .collect();
+
+    Metrics {
+        run_id: run.run_id.clone(),
+        runs: 1,
+        offline_error: mean(&errors),
+        offline_error_std: 0.0,
+        accuracy: mean(&accuracies),
+        accuracy_std: 0.0,
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn std(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    let squares: Vec<f64> = values.iter().map(|value| (value - mean).powi(2)).collect();
+    self::mean(&squares).sqrt()
+}
+
+/// Returns the metrics of all runs, followed by their mean and standard deviation over all
+/// runs with the run id "all". Every run is compared against the best score of each period
+/// over all runs on the same instance.
+pub fn report(runs: &[PeriodRun]) -> Vec<Metrics> {
+    let best_known = best_known(runs);
+    let mut metrics: Vec<Metrics> = runs
+        .iter()
+        .map(|run| run_metrics(run, &best_known))
+        .collect();
+
+    let errors: Vec<f64> = metrics.iter().map(|m| m.offline_error).collect();
+    let accuracies: Vec<f64> = metrics.iter().map(|m| m.accuracy).collect();
+    metrics.push(Metrics {
+        run_id: "all".to_string(),
+        runs: runs.len(),
+        offline_error: mean(&errors),
+        offline_error_std: std(&errors),
+        accuracy: mean(&accuracies),
+        accuracy_std: std(&accuracies),
+    });
+
+    metrics
+}
+
+/// Final best route of a run, together with the fingerprint of the graph the run started on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteRun {
+    pub label: String,
+    pub fingerprint: Option<String>,
+    pub route: Vec<String>,
+}
+
+/// Similarity of the final routes of two runs. Routes are only comparable on the same graph,
+/// which is the case if both runs have the same graph fingerprint.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RouteComparison {
+    pub first: String,
+    pub second: String,
+    pub same_graph: bool,
+    pub edge_overlap: f64,
+    pub jaccard: f64,
+    pub levenshtein: usize,
+}
+
+/// Compares the routes of every pair of runs, e.g. to see how differently algorithms solve
+/// the same instance.
+pub fn compare_routes(runs: &[RouteRun]) -> Vec<RouteComparison> {
+    // node ids are numbered, as solutions need copyable nodes
+    let mut ids = HashMap::new();
+    let routes: Vec<Solution<usize>> = runs
+        .iter()
+        .map(|run| {
+            Solution::from_nodes(
+                run.route
+                    .iter()
+                    .map(|node| {
+                        let next = ids.len();
+                        *ids.entry(node.as_str()).or_insert(next)
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let mut comparisons = Vec::new();
+    for (i, first) in runs.iter().enumerate() {
+        for (j, second) in runs.iter().enumerate().skip(i + 1) {
+            let similarity = Similarity::between(&routes[i], &routes[j]);
+            comparisons.push(RouteComparison {
+                first: first.label.clone(),
+                second: second.label.clone(),
+                same_graph: first.fingerprint.is_some() && first.fingerprint == second.fingerprint,
+                edge_overlap: similarity.edge_overlap,
+                jaccard: similarity.jaccard,
+                levenshtein: similarity.levenshtein,
+            });
+        }
+    }
+    comparisons
+}
+
+#[derive(Deserialize)]
+struct SummaryFingerprint {
+    graph_fingerprint: String,
+}
+
+/// Collects the final routes of all runs with a `_replay` log in the config folders of
+/// experiment_dir, which are the folders with a config.yaml, including experiment_dir itself.
+/// The graph fingerprint is read from the `_summary` file next to the log, if there is one.
+/// Runs are labeled with their config folder and log name.
+pub fn collect_routes(experiment_dir: &Path) -> io::Result<Vec<RouteRun>> {
+    let mut folders = vec![experiment_dir.to_path_buf()];
+    for entry in fs::read_dir(experiment_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            folders.push(path);
+        }
+    }
+    folders.retain(|folder| folder.join("config.yaml").is_file());
+    folders.sort();
+
+    let mut runs = Vec::new();
+    for folder in folders {
+        let config = folder
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let mut logs: Vec<(String, _)> = fs::read_dir(&folder)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?.strip_suffix("_replay")?;
+                Some((name.to_string(), path.clone()))
+            })
+            .collect();
+        logs.sort();
+        for (name, log) in logs {
+            let route = match File::open(&log).map(read_records) {
+                Ok(Ok(records)) => match records.last() {
+                    Some(last) => last.solution.split_whitespace().map(String::from).collect(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let fingerprint = File::open(folder.join(format!("{}_summary", name)))
+                .ok()
+                .and_then(|file| {
+                    Reader::from_reader(file)
+                        .deserialize::<SummaryFingerprint>()
+                        .next()?
+                        .ok()
+                })
+                .map(|summary| summary.graph_fingerprint);
+            runs.push(RouteRun {
+                label: format!("{} {}", config, name),
+                fingerprint,
+                route,
+            });
+        }
+    }
+
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(run_id: &str, start_node: &str, scores: &[(usize, f64)]) -> PeriodRun {
+        PeriodRun {
+            run_id: run_id.to_string(),
+            fingerprint: "ff".to_string(),
+            start_node: start_node.to_string(),
+            records: scores
+                .iter()
+                .enumerate()
+                .map(|(iteration, &(period, best_score))| PeriodRecord {
+                    period,
+                    iteration,
+                    best_score,
+                })
+                .collect(),
+        }
+    }
+
+    fn key(start_node: &str, period: usize) -> PeriodKey {
+        ("ff".to_string(), start_node.to_string(), period)
+    }
+
+    #[test]
+    fn runs_are_compared_to_the_best_known_values() {
+        let runs = vec![
+            run("a", "0", &[(0, 2.0), (0, 4.0), (1, 5.0)]),
+            run("b", "0", &[(0, 1.0), (0, 2.0), (1, 10.0)]),
+        ];
+        assert_eq!(
+            best_known(&runs).into_iter().collect::<Vec<_>>(),
+            vec![(key("0", 0), 4.0), (key("0", 1), 10.0)]
+        );
+
+        let metrics = report(&runs);
+        assert_eq!(metrics.len(), 3);
+        // errors 2, 0 and 5
+        assert_eq!(metrics[0].offline_error, 7.0 / 3.0);
+        assert_eq!(metrics[0].accuracy, 0.75);
+        // errors 3, 2 and 0
+        assert_eq!(metrics[1].offline_error, 5.0 / 3.0);
+        assert_eq!(metrics[1].accuracy, 0.75);
+
+        assert_eq!(metrics[2].run_id, "all");
+        assert_eq!(metrics[2].runs, 2);
+        assert_eq!(metrics[2].offline_error, 2.0);
+        assert!((metrics[2].offline_error_std - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(metrics[2].accuracy_std, 0.0);
+    }
+
+    #[test]
+    fn runs_from_other_start_nodes_are_not_compared() {
+        let runs = vec![
+            run("s0", "0", &[(0, 2.0), (1, 5.0)]),
+            run("s1", "1", &[(0, 4.0), (1, 10.0)]),
+        ];
+        assert_eq!(best_known(&runs).len(), 4);
+
+        let metrics = report(&runs);
+        assert_eq!(metrics[0].offline_error, 0.0);
+        assert_eq!(metrics[0].accuracy, 1.0);
+        assert_eq!(metrics[1].offline_error, 0.0);
+    }
+
+    #[test]
+    fn periods_are_read_from_logs() {
+        let log = "period,iteration,best_score\n0,0,1.5\n1,0,0.0\n";
+        let records = read_periods(log.as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].best_score, 1.5);
+
+        // nothing to gain in a period without reward
+        let metrics = report(&[PeriodRun {
+            run_id: "a".to_string(),
+            fingerprint: "ff".to_string(),
+            start_node: "0".to_string(),
+            records,
+        }]);
+        assert_eq!(metrics[0].offline_error, 0.0);
+        assert_eq!(metrics[0].accuracy, 1.0);
+    }
+
+    fn route_run(label: &str, fingerprint: Option<&str>, route: &str) -> RouteRun {
+        RouteRun {
+            label: label.to_string(),
+            fingerprint: fingerprint.map(String::from),
+            route: route.split(' ').map(String::from).collect(),
+        }
+    }
+
+    #[test]
+    fn routes_of_all_runs_are_compared() {
+        let runs = vec![
+            route_run("aco", Some("ab"), "a b c a"),
+            route_run("two_swap", Some("ab"), "a c b a"),
+            route_run("other", None, "a b c a"),
+        ];
+        let comparisons = compare_routes(&runs);
+        assert_eq!(comparisons.len(), 3);
+
+        assert_eq!(comparisons[0].first, "aco");
+        assert_eq!(comparisons[0].second, "two_swap");
+        assert!(comparisons[0].same_graph);
+        assert_eq!(comparisons[0].edge_overlap, 0.0);
+        assert_eq!(comparisons[0].jaccard, 1.0);
+        assert_eq!(comparisons[0].levenshtein, 2);
+
+        assert!(
+            !comparisons[1].same_graph,
+            "graphs without fingerprint differ"
+        );
+        assert_eq!(comparisons[1].edge_overlap, 1.0);
+    }
+
+    #[test]
+    fn routes_are_collected_from_replay_logs() {
+        let dir = std::env::temp_dir().join(format!("report_routes_{}", std::process::id()));
+        let config = dir.join("config");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(config.join("config.yaml"), "").unwrap();
+        fs::write(
+            config.join("h1_replay"),
+            "iteration,rng_state,improved,score,length,solution\n\
+             0,,true,1.0,2.0,0 1 0\n\
+             1,,true,2.0,3.0,0 1 2 0\n",
+        )
+        .unwrap();
+        fs::write(
+            config.join("h1_summary"),
+            "score,graph_fingerprint\n2.0,ff\n",
+        )
+        .unwrap();
+        fs::write(config.join("h1"), "not a replay log").unwrap();
+
+        let runs = collect_routes(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(runs, vec![route_run("config h1", Some("ff"), "0 1 2 0")]);
+    }
+}
@@ -1,12 +1,193 @@
 use getrandom::getrandom;
 use oorandom::Rand64;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
 
-pub fn preseeded_rng64() -> Rand64 {
-    Rand64::new(os_random_seed())
+/// Algorithms an Rng can draw its numbers with.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RngKind {
+    /// Permuted congruential generator of oorandom.
+    #[default]
+    Pcg,
+    /// SplitMix64 used as counter-based generator, the n-th number is a hash of the key and n.
+    /// Streams with different keys are independent, which makes splitting them cheap.
+    Counter,
 }
 
-pub fn rng64(seed: u128) -> Rand64 {
-    Rand64::new(seed)
+/// State of an Rng, from which it continues to draw the exact same numbers.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngState {
+    Pcg { state: u128, inc: u128 },
+    Counter { key: u64, counter: u64 },
+}
+
+/// Written as "state:inc" for pcg, like replay logs always did, and as "counter:key:counter"
+/// for counter-based rngs.
+impl fmt::Display for RngState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pcg { state, inc } => write!(f, "{}:{}", state, inc),
+            Self::Counter { key, counter } => write!(f, "counter:{}:{}", key, counter),
+        }
+    }
+}
+
+impl FromStr for RngState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rng state {}.", s);
+        let parts: Vec<&str> = s.split(':').collect();
+        match parts.as_slice() {
+            [state, inc] => Ok(Self::Pcg {
+                state: state.parse().map_err(|_| invalid())?,
+                inc: inc.parse().map_err(|_| invalid())?,
+            }),
+            ["counter", key, counter] => Ok(Self::Counter {
+                key: key.parse().map_err(|_| invalid())?,
+                counter: counter.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Generator {
+    Pcg(Rand64),
+    Counter { key: u64, counter: u64 },
+}
+
+/// Random number generator used throughout the crate, which hides the algorithm behind it.
+#[derive(Debug, Clone, Copy)]
+pub struct Rng {
+    generator: Generator,
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(kind: RngKind, seed: u128) -> Self {
+        let generator = match kind {
+            RngKind::Pcg => Generator::Pcg(Rand64::new(seed)),
+            RngKind::Counter => Generator::Counter {
+                key: mix64(seed as u64 ^ mix64((seed >> 64) as u64)),
+                counter: 0,
+            },
+        };
+        Rng { generator }
+    }
+
+    pub fn from_state(state: RngState) -> Self {
+        let generator = match state {
+            RngState::Pcg { state, inc } => Generator::Pcg(Rand64::from_state((state, inc))),
+            RngState::Counter { key, counter } => Generator::Counter { key, counter },
+        };
+        Rng { generator }
+    }
+
+    pub fn state(&self) -> RngState {
+        match &self.generator {
+            Generator::Pcg(rng) => {
+                let (state, inc) = rng.state();
+                RngState::Pcg { state, inc }
+            }
+            &Generator::Counter { key, counter } => RngState::Counter { key, counter },
+        }
+    }
+
+    pub fn kind(&self) -> RngKind {
+        match self.generator {
+            Generator::Pcg(_) => RngKind::Pcg,
+            Generator::Counter { .. } => RngKind::Counter,
+        }
+    }
+
+    /// Returns the independent stream with the given id, without advancing this rng.
+    /// Pcg rngs have no cheap independent streams, their streams are seeded from state and id.
+    pub fn stream(&self, id: u64) -> Self {
+        let generator = match &self.generator {
+            Generator::Pcg(rng) => {
+                let id = ((mix64(id) as u128) << 64) | mix64(id ^ GOLDEN_GAMMA) as u128;
+                Generator::Pcg(Rand64::new(rng.state().0 ^ id))
+            }
+            &Generator::Counter { key, .. } => Generator::Counter {
+                key: mix64(key ^ mix64(id.wrapping_add(1))),
+                counter: 0,
+            },
+        };
+        Rng { generator }
+    }
+
+    pub fn rand_u64(&mut self) -> u64 {
+        match &mut self.generator {
+            Generator::Pcg(rng) => rng.rand_u64(),
+            Generator::Counter { key, counter } => {
+                *counter = counter.wrapping_add(1);
+                mix64(key.wrapping_add(counter.wrapping_mul(GOLDEN_GAMMA)))
+            }
+        }
+    }
+
+    /// Returns a number in [0, 1).
+    pub fn rand_float(&mut self) -> f64 {
+        match &mut self.generator {
+            Generator::Pcg(rng) => rng.rand_float(),
+            Generator::Counter { .. } => (self.rand_u64() >> 11) as f64 / (1u64 << 53) as f64,
+        }
+    }
+}
+
+/// Returns an integer drawn uniformly from range, which contains its start but not its end,
+/// like an index into a slice of len elements for 0..len.
+/// Panics if range is empty.
+pub fn gen_range(rng: &mut Rng, range: Range<usize>) -> usize {
+    assert!(
+        !range.is_empty(),
+        "Can't draw from the empty range {:?}.",
+        range
+    );
+    let len = range.end - range.start;
+    // rounding can scale numbers just below 1 up to len
+    range.start + ((rng.rand_float() * len as f64) as usize).min(len - 1)
+}
+
+/// Returns a number drawn uniformly from range, which contains its start but not its end.
+/// Ranges with equal bounds always return their start, so config ranges can fix a value.
+pub fn gen_float_range(rng: &mut Rng, range: Range<f64>) -> f64 {
+    let value = range.start + rng.rand_float() * (range.end - range.start);
+    // like for integers, rounding can reach the end
+    if value >= range.end && range.end > range.start {
+        range.end.next_down()
+    } else {
+        value
+    }
+}
+
+pub fn preseeded_rng64() -> Rng {
+    rng64(os_random_seed())
+}
+
+pub fn rng64(seed: u128) -> Rng {
+    Rng::new(RngKind::Pcg, seed)
+}
+
+/// Derives the seed of a component from a master seed and the name of the component, so all
+/// components draw different numbers and the master seed alone reproduces them.
+pub fn derive_seed(master_seed: u64, component: &str) -> u64 {
+    component.bytes().fold(
+        mix64(master_seed.wrapping_add(GOLDEN_GAMMA)),
+        |hash, byte| mix64(hash ^ byte as u64),
+    )
 }
 
 pub fn os_random_seed() -> u128 {
@@ -14,3 +195,73 @@ pub fn os_random_seed() -> u128 {
     let _res = getrandom(&mut buf);
     u128::from_le_bytes(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg_draws_like_oorandom() {
+        let mut rng = rng64(42);
+        let mut expected = Rand64::new(42);
+        for _ in 0..10 {
+            assert_eq!(rng.rand_u64(), expected.rand_u64());
+        }
+        assert_eq!(rng.rand_float(), expected.rand_float());
+    }
+
+    #[test]
+    fn rngs_continue_from_their_state() {
+        for &kind in &[RngKind::Pcg, RngKind::Counter] {
+            let mut rng = Rng::new(kind, 7);
+            rng.rand_u64();
+            let state = rng.state();
+            let restored: RngState = state.to_string().parse().unwrap();
+            assert_eq!(restored, state);
+
+            let mut restored = Rng::from_state(restored);
+            assert_eq!(restored.kind(), kind);
+            for _ in 0..10 {
+                assert_eq!(rng.rand_u64(), restored.rand_u64());
+            }
+        }
+        assert!("1:2:3".parse::<RngState>().is_err());
+    }
+
+    #[test]
+    fn seeds_are_derived_per_component() {
+        assert_eq!(derive_seed(1, "algorithm"), derive_seed(1, "algorithm"));
+        assert_ne!(derive_seed(1, "algorithm"), derive_seed(2, "algorithm"));
+        assert_ne!(derive_seed(1, "algorithm"), derive_seed(1, "experiment"));
+    }
+
+    #[test]
+    fn ranges_exclude_their_end() {
+        let mut rng = rng64(3);
+        let mut drawn = [false; 3];
+        for _ in 0..100 {
+            drawn[gen_range(&mut rng, 2..5) - 2] = true;
+            assert!((1.5..2.5).contains(&gen_float_range(&mut rng, 1.5..2.5)));
+        }
+        assert!(drawn.iter().all(|&drawn| drawn));
+        assert_eq!(gen_float_range(&mut rng, 2.0..2.0), 2.0);
+        assert_eq!(gen_range(&mut rng, 7..8), 7);
+    }
+
+    #[test]
+    fn streams_differ() {
+        for &kind in &[RngKind::Pcg, RngKind::Counter] {
+            let rng = Rng::new(kind, 7);
+            let mut first = rng.stream(0);
+            let mut second = rng.stream(1);
+            assert_ne!(first.rand_u64(), second.rand_u64());
+            assert_eq!(rng.stream(1).rand_u64(), rng.stream(1).rand_u64());
+        }
+
+        let mut rng = Rng::new(RngKind::Counter, 7);
+        for _ in 0..1000 {
+            let x = rng.rand_float();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::experiment_config::ExperimentConfig;
+use crate::graph::{stable_hash, Fingerprint, Scaling};
+use crate::metaheuristic::supervisor::Summary;
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Describes where, when and with what a run was made, written as a `_run_meta.json` file next
+/// to its log, so the results of many runs stay traceable to their machine and config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunMeta {
+    pub hostname: String,
+    /// Seconds since the unix epoch, at which the experiment started.
+    pub started: u64,
+    pub crate_version: String,
+    pub run_id: String,
+    pub algorithm: String,
+    pub heuristic: String,
+    /// Stable hash of the resolved config, to group runs of the same config.
+    pub config_hash: String,
+    pub config: serde_json::Value,
+    /// Fingerprint of the graph the run started on.
+    pub graph_fingerprint: String,
+    pub seeds: Seeds,
+    /// Wall time of the run in microseconds, including logging and setup.
+    pub runtime_us: u128,
+    /// Scalings of the edge weights and rewards, if the graph was normalized, which convert
+    /// the lengths and scores of the run back to the weights of the original graph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_scaling: Option<Scaling>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_scaling: Option<Scaling>,
+}
+
+/// Seeds a run was made with. With os_seeded the algorithm draws its seed from the operating
+/// system instead, which is recorded as drawn.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Seeds {
+    /// Seed of the experiment, from which the start nodes and service times are drawn.
+    pub experiment: u64,
+    /// Seed of the algorithm, None for algorithms without randomness.
+    pub algorithm: Option<u64>,
+    pub os_seeded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drawn: Option<u128>,
+}
+
+/// Everything the runs of an experiment share, which is written into the meta file of each of
+/// them together with what is particular to the run.
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    hostname: String,
+    started: u64,
+    config_hash: String,
+    config: serde_json::Value,
+    seeds: Seeds,
+    edge_scaling: Option<Scaling>,
+    node_scaling: Option<Scaling>,
+}
+
+impl RunContext {
+    /// Takes the config after its seeds were resolved, so the meta files allow to repeat runs.
+    pub fn new(config: &ExperimentConfig) -> Self {
+        let json = serde_json::to_string(config).unwrap_or_default();
+        RunContext {
+            hostname: hostname(),
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            config_hash: format!("{:016x}", stable_hash(json.as_bytes())),
+            config: serde_json::from_str(&json).unwrap_or_default(),
+            seeds: Seeds {
+                experiment: config.experiment.cfg().seed,
+                algorithm: config.algorithm.seed(),
+                os_seeded: config.rng.is_some_and(|rng| rng.os_seeded),
+                drawn: None,
+            },
+            edge_scaling: None,
+            node_scaling: None,
+        }
+    }
+
+    /// Records the seed an os seeded run drew, which replaces the seed of its algorithm.
+    pub fn with_drawn_seed(mut self, drawn: Option<u128>) -> Self {
+        self.seeds.drawn = drawn;
+        self
+    }
+
+    /// Records the scalings, with which the edge weights and rewards were normalized.
+    pub fn with_scalings(mut self, edges: Option<Scaling>, nodes: Option<Scaling>) -> Self {
+        self.edge_scaling = edges;
+        self.node_scaling = nodes;
+        self
+    }
+
+    /// Returns the meta data of the run summarized by summary.
+    pub fn meta(&self, fingerprint: Fingerprint, summary: &Summary) -> RunMeta {
+        RunMeta {
+            hostname: self.hostname.clone(),
+            started: self.started,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            run_id: summary.run_id.clone(),
+            algorithm: summary.algorithm.clone(),
+            heuristic: summary.heuristic.clone(),
+            config_hash: self.config_hash.clone(),
+            config: self.config.clone(),
+            graph_fingerprint: fingerprint.to_string(),
+            seeds: self.seeds,
+            runtime_us: summary.wall_time_us,
+            edge_scaling: self.edge_scaling,
+            node_scaling: self.node_scaling,
+        }
+    }
+
+    /// Writes the meta data of the run logged to filename to a `_run_meta.json` file next to it.
+    pub fn write_meta(
+        &self,
+        filename: &str,
+        fingerprint: Fingerprint,
+        summary: &Summary,
+    ) -> io::Result<()> {
+        let file = File::create(format!("{}_run_meta.json", filename))?;
+        serde_json::to_writer_pretty(file, &self.meta(fingerprint, summary))?;
+        Ok(())
+    }
+}
+
+/// Returns the name of the machine, empty if it is unknown.
+fn hostname() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(seed: u64) -> ExperimentConfig {
+        let yaml = format!(
+            "
+experiment:
+  finished: false
+  seed: {}
+  aggregation_rate: 1
+  max_time: 10.0
+algorithm:
+  ant_count: 2
+  iterations: 1
+  alpha: 1.0
+  beta: 1.0
+  rho: 0.1
+  q_0: 0.0
+  seed: 4
+graph_creation:
+  seed: 2
+  size: [2, 2]
+  nw_range: [1.0, 2.0]
+  ew_range: [1.0, 2.0]
+  node_weight_probability: 0.5
+",
+            seed
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn meta_data_identifies_the_config() {
+        let summary = Summary {
+            algorithm: "aco".to_string(),
+            run_id: "h1".to_string(),
+            wall_time_us: 5,
+            ..Summary::default()
+        };
+        let meta = RunContext::new(&config(1)).meta(Fingerprint(1), &summary);
+        assert_eq!(meta.run_id, "h1");
+        assert_eq!(meta.graph_fingerprint, "0000000000000001");
+        assert_eq!(meta.runtime_us, 5);
+        assert_eq!(meta.config["experiment"]["seed"], 1);
+        assert_eq!(
+            meta.seeds,
+            Seeds {
+                experiment: 1,
+                algorithm: Some(4),
+                os_seeded: false,
+                drawn: None,
+            }
+        );
+        let drawn = RunContext::new(&config(1))
+            .with_drawn_seed(Some(7))
+            .meta(Fingerprint(1), &summary);
+        assert_eq!(drawn.seeds.drawn, Some(7));
+
+        let same = RunContext::new(&config(1)).meta(Fingerprint(1), &summary);
+        let other = RunContext::new(&config(3)).meta(Fingerprint(1), &summary);
+        assert_eq!(meta.config_hash, same.config_hash);
+        assert_ne!(meta.config_hash, other.config_hash);
+    }
+}
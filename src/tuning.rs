@@ -0,0 +1,209 @@
+use crate::experiment_config::sweep_config::SweepValues;
+use crate::experiment_config::tuning_config::TuningConfig;
+use crate::rng::{rng64, Rng};
+
+use std::collections::BTreeMap;
+
+/// A parameter configuration together with its scores on the instances evaluated so far.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub id: usize,
+    pub round: usize,
+    pub params: BTreeMap<String, f64>,
+    pub scores: Vec<f64>,
+}
+
+impl Candidate {
+    /// Returns the mean score over the first n instances.
+    pub fn mean_score(&self, n: usize) -> f64 {
+        let n = n.min(self.scores.len());
+        if n == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        self.scores[..n].iter().sum::<f64>() / n as f64
+    }
+}
+
+/// Tunes parameters similar to irace: every round new configurations are sampled around the
+/// elites of the previous round and raced against them. During a race all configurations are
+/// evaluated instance by instance, after each instance the worst ones are pruned.
+/// Elites keep their scores, as all races use the same instances.
+pub struct Tuner<'a> {
+    cfg: &'a TuningConfig,
+    rng: Rng,
+    next_id: usize,
+}
+
+impl<'a> Tuner<'a> {
+    pub fn new(cfg: &'a TuningConfig) -> Self {
+        Tuner {
+            cfg,
+            rng: rng64(cfg.seed as u128),
+            next_id: 0,
+        }
+    }
+
+    /// Returns a standard normally distributed value, using the Box-Muller transform.
+    fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.rng.rand_float();
+        let u2 = self.rng.rand_float();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Samples a new configuration. In the first round values are drawn uniformly, afterwards
+    /// they are drawn around a parent elite, which is chosen with a probability decreasing with
+    /// its rank. The spread around the parent shrinks with every round.
+    fn propose(&mut self, elites: &[Candidate], round: usize) -> Option<Candidate> {
+        let n = elites.len();
+        let parent = if n == 0 {
+            None
+        } else {
+            // rank weights n, n - 1, ..., 1
+            let mut pick = self.rng.rand_float() * (n * (n + 1) / 2) as f64;
+            let mut parent = &elites[n - 1];
+            for (rank, elite) in elites.iter().enumerate() {
+                pick -= (n - rank) as f64;
+                if pick < 0.0 {
+                    parent = elite;
+                    break;
+                }
+            }
+            Some(parent)
+        };
+        let spread = 0.5f64.powi(round as i32);
+
+        let mut params = BTreeMap::new();
+        for (name, values) in self.cfg.params.iter() {
+            let parent_value = parent.and_then(|parent| parent.params.get(name));
+            let value = match (values, parent_value) {
+                (SweepValues::Range { min, max, .. }, Some(&value)) => {
+                    let std_dev = spread * (max - min) / 2.0;
+                    (value + std_dev * self.normal()).max(*min).min(*max)
+                }
+                (SweepValues::List(_), Some(&value)) if self.rng.rand_float() > spread => value,
+                _ => values.sample(self.rng.rand_float())?,
+            };
+            params.insert(name.clone(), value);
+        }
+
+        self.next_id += 1;
+        Some(Candidate {
+            id: self.next_id - 1,
+            round,
+            params,
+            scores: Vec::new(),
+        })
+    }
+
+    /// Evaluates all candidates instance by instance and prunes the worst ones after each.
+    /// Returns the survivors sorted by their mean score, best first.
+    fn race(
+        &self,
+        mut candidates: Vec<Candidate>,
+        evaluate: &mut dyn FnMut(&Candidate, usize) -> Option<f64>,
+    ) -> Vec<Candidate> {
+        for instance in 0..self.cfg.instances {
+            for candidate in candidates.iter_mut() {
+                if candidate.scores.len() <= instance {
+                    let score = evaluate(candidate, instance).unwrap_or(f64::NEG_INFINITY);
+                    candidate.scores.push(score);
+                }
+            }
+
+            let evaluated = instance + 1;
+            candidates.sort_by(|a, b| b.mean_score(evaluated).total_cmp(&a.mean_score(evaluated)));
+            if evaluated >= self.cfg.first_test && evaluated < self.cfg.instances {
+                let survivors =
+                    ((candidates.len() as f64 * self.cfg.survival_rate).ceil() as usize).max(1);
+                candidates.truncate(survivors);
+            }
+        }
+        candidates.retain(|candidate| candidate.mean_score(self.cfg.instances).is_finite());
+
+        candidates
+    }
+
+    /// Runs all rounds and returns the final elites, best first.
+    /// evaluate is called with a candidate and the index of the instance to run it on,
+    /// None marks a failed run.
+    pub fn run(
+        &mut self,
+        evaluate: &mut dyn FnMut(&Candidate, usize) -> Option<f64>,
+    ) -> Vec<Candidate> {
+        let mut elites: Vec<Candidate> = Vec::new();
+        for round in 0..self.cfg.rounds {
+            let mut candidates = elites.clone();
+            while candidates.len() < self.cfg.candidates.max(elites.len() + 1) {
+                match self.propose(&elites, round) {
+                    Some(candidate) => candidates.push(candidate),
+                    None => return elites,
+                }
+            }
+            elites = self.race(candidates, evaluate);
+        }
+
+        elites
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TuningConfig {
+        let mut params = BTreeMap::new();
+        params.insert(
+            "alpha".to_string(),
+            SweepValues::Range {
+                min: 0.0,
+                max: 10.0,
+                steps: 0,
+            },
+        );
+        params.insert("beta".to_string(), SweepValues::List(vec![1.0, 2.0, 3.0]));
+        TuningConfig {
+            params,
+            heuristic: "h1".to_string(),
+            candidates: 8,
+            rounds: 5,
+            instances: 4,
+            first_test: 2,
+            survival_rate: 0.5,
+            seed: 12345,
+        }
+    }
+
+    #[test]
+    fn tuning_finds_good_parameters() {
+        let cfg = config();
+        let mut evaluations = 0;
+        // best at alpha = 7 and beta = 2, instances add some noise
+        let elites = Tuner::new(&cfg).run(&mut |candidate, instance| {
+            evaluations += 1;
+            let alpha = candidate.params["alpha"];
+            let beta = candidate.params["beta"];
+            Some(-(alpha - 7.0).powi(2) - (beta - 2.0).abs() * 10.0 + instance as f64 * 0.1)
+        });
+
+        assert!(!elites.is_empty());
+        assert!((elites[0].params["alpha"] - 7.0).abs() < 1.5);
+        assert_eq!(elites[0].params["beta"], 2.0);
+        // racing prunes candidates, so not every candidate sees every instance
+        assert!(evaluations < cfg.rounds * cfg.candidates * cfg.instances);
+    }
+
+    #[test]
+    fn failed_runs_are_pruned() {
+        let cfg = config();
+        let elites = Tuner::new(&cfg).run(&mut |candidate, _| {
+            if candidate.params["beta"] == 3.0 {
+                None
+            } else {
+                Some(1.0)
+            }
+        });
+
+        assert!(elites.iter().all(|elite| elite.params["beta"] != 3.0));
+    }
+}
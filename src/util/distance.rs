@@ -2,6 +2,10 @@ use crate::geo::{geodistance_haversine, GeoPoint};
 use decorum::R64;
 
 pub trait Distance<T> {
+    /// Whether distance measures the distance between two points. Otherwise the distance to
+    /// the goal point is taken from the shortest paths of the graph, see GoalDistances.
+    const GEODESIC: bool = true;
+
     fn distance(p1: T, p2: T) -> R64;
 }
 
@@ -12,6 +16,8 @@ impl Distance<GeoPoint> for GeoPoint {
 }
 
 impl Distance<usize> for usize {
+    const GEODESIC: bool = false;
+
     fn distance(p1: usize, p2: usize) -> R64 {
         R64::from_inner(0.0)
     }
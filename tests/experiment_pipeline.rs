@@ -0,0 +1,574 @@
+//! Runs tiny experiments of every algorithm end to end, from the yaml config to the logs.
+
+use dop_with_aco::dynamic_graph_experiment::{DynamicGraphExperiment, RunSummary};
+use dop_with_aco::experiment_config::general_experiment_config::MultilevelConfig;
+use dop_with_aco::experiment_config::{
+    ExperimentConfig, ExperimentConfigError, GeneralExperimentConfig, GraphCreationConfig,
+    GraphDynamicsConfig,
+};
+use dop_with_aco::metaheuristic::supervisor::Record;
+use dop_with_aco::metaheuristic::{aco, heuristics, random_search, two_swap, SyncHeuristic};
+use dop_with_aco::run_context::RunMeta;
+
+use decorum::R64;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAX_TIME: f64 = 30.0;
+
+/// Removes its directory when dropped, so failing tests don't leave logs behind.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("dop_with_aco_{}_{}", name, std::process::id()));
+        let _res = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _res = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns the config of a 5x5 grid experiment with the given algorithm section.
+fn config(algorithm: &str) -> ExperimentConfig {
+    let yaml = format!(
+        "
+experiment:
+  finished: false
+  seed: 1
+  aggregation_rate: 1
+  max_time: {}
+  route_log: true
+algorithm: {}
+graph_creation:
+  seed: 2
+  size: [5, 5]
+  nw_range: [10.0, 20.0]
+  ew_range: [2.0, 5.0]
+  node_weight_probability: 0.5
+",
+        MAX_TIME, algorithm
+    );
+    serde_yaml::from_str(&yaml).unwrap()
+}
+
+/// Runs the experiment into dir and returns its summary.
+fn run(config: &ExperimentConfig, dir: &Path) -> RunSummary {
+    DynamicGraphExperiment::run_config(config, &heuristics::value_per_cost, "h2", &dir.join("h2"))
+        .unwrap()
+        .unwrap()
+}
+
+/// Returns the rows of the csv file with their column names.
+fn rows(path: &Path) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut reader = csv::Reader::from_path(path).unwrap();
+    let header = reader.headers().unwrap().iter().map(String::from).collect();
+    let rows = reader
+        .records()
+        .map(|record| record.unwrap().iter().map(String::from).collect())
+        .collect();
+    (header, rows)
+}
+
+/// Returns the graph fingerprint from the summary of the run in dir.
+fn fingerprint(dir: &Path) -> String {
+    let (header, records) = rows(&dir.join("h2_summary"));
+    let column = header
+        .iter()
+        .position(|h| h == "graph_fingerprint")
+        .unwrap();
+    records[0][column].clone()
+}
+
+/// Returns the meta data of the run in dir.
+fn meta(dir: &Path) -> RunMeta {
+    let file = fs::File::open(dir.join("h2_run_meta.json")).unwrap();
+    serde_json::from_reader(file).unwrap()
+}
+
+/// Runs algorithm twice and checks the logs, the final route and that both runs agree.
+fn check_pipeline(name: &str, algorithm: &str, columns: &[&str]) {
+    let config = config(algorithm);
+    let first = TempDir::new(&format!("{}_first", name));
+    let second = TempDir::new(&format!("{}_second", name));
+    let summary = run(&config, first.path());
+    let file = first.path().join("h2");
+
+    let (header, records) = rows(&file);
+    assert_eq!(header, columns);
+    assert!(!records.is_empty());
+    assert!(first.path().join("h2_summary").exists());
+    assert!(first.path().join("h2_routes.jsonl").exists());
+
+    // replaying checks the logged solutions against the graph
+    DynamicGraphExperiment::replay_config(&config, &heuristics::value_per_cost, "h2", &file)
+        .unwrap();
+    let (header, replayed) = rows(&first.path().join("h2_replayed"));
+    let column = |name: &str| header.iter().position(|h| h == name).unwrap();
+    let last = replayed.last().unwrap();
+    let route: Vec<&str> = last[column("solution")].split(' ').collect();
+    assert_eq!(last[column("consistent")], "true");
+    assert_eq!(route.first(), route.last());
+    assert!(last[column("length")].parse::<f64>().unwrap() <= MAX_TIME);
+    assert!(summary.length <= MAX_TIME);
+    assert!(route.len() > 1);
+
+    let repeated = run(&config, second.path());
+    assert_eq!(fingerprint(first.path()).len(), 16);
+    assert_eq!(
+        fingerprint(first.path()),
+        fingerprint(second.path()),
+        "both runs start on the same graph"
+    );
+    assert_eq!(summary.score, repeated.score);
+    assert_eq!(summary.length, repeated.length);
+    let (meta, repeated_meta) = (meta(first.path()), meta(second.path()));
+    assert_eq!(meta.graph_fingerprint, fingerprint(first.path()));
+    assert_eq!(meta.config_hash, repeated_meta.config_hash);
+    assert_eq!(meta.crate_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(meta.seeds.experiment, 1);
+    assert_eq!(
+        fs::read_to_string(first.path().join("h2_replay")).unwrap(),
+        fs::read_to_string(second.path().join("h2_replay")).unwrap()
+    );
+}
+
+#[test]
+fn aco_pipeline() {
+    check_pipeline(
+        "aco",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn aco_pipeline_with_return_paths() {
+    check_pipeline(
+        "aco_return_paths",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5, \
+         step_limit: 4, return_paths: 3}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn mm_aco_pipeline() {
+    check_pipeline(
+        "mm_aco",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, seed: 3, ant_count: 5, p_best: 0.05, iterations: 5}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn mm_aco_pipeline_with_tau_bounds() {
+    check_pipeline(
+        "mm_aco_tau_bounds",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, seed: 3, ant_count: 5, p_best: 0.05, iterations: 5, \
+         initial_pheromone: 2.0, tau_max: 3.0, tau_min: 0.5, warm_up: 2}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn aco_pipeline_with_bidirectional_construction() {
+    check_pipeline(
+        "aco_bidirectional",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5, \
+         bidirectional: true}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn acs_pipeline() {
+    check_pipeline(
+        "acs",
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, t_0: 0.1, seed: 3, ant_count: 5, \
+         iterations: 5}",
+        aco::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn random_search_pipeline() {
+    check_pipeline(
+        "random",
+        "{seed: 3, iterations: 5}",
+        random_search::Record::COLUMNS,
+    );
+}
+
+#[test]
+fn two_swap_pipeline() {
+    check_pipeline("two_swap", "{}", two_swap::Record::COLUMNS);
+}
+
+#[test]
+fn exact_pipeline() {
+    check_pipeline("exact", "{node_limit: 25}", random_search::Record::COLUMNS);
+}
+
+#[test]
+fn replays_restore_the_graph_states_of_the_dynamics() {
+    let mut config =
+        config("{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    config.graph_dynamics = Some(
+        serde_yaml::from_str::<GraphDynamicsConfig>(
+            "{seed: 4, changes: 2, change_after_i: 2, edge_change_probability: 0.5, \
+             node_change_probability: 0.5, edge_change_intensity: 1.0, \
+             node_change_intensity: 1.0}",
+        )
+        .unwrap(),
+    );
+    let dir = TempDir::new("dynamics_replay");
+    run(&config, dir.path());
+    let file = dir.path().join("h2");
+    DynamicGraphExperiment::replay_config(&config, &heuristics::value_per_cost, "h2", &file)
+        .unwrap();
+
+    // the recovery iterations are logged and replayed in the graph they ran on
+    let (header, replayed) = rows(&dir.path().join("h2_replayed"));
+    let consistent = header.iter().position(|h| h == "consistent").unwrap();
+    assert_eq!(replayed.len(), 5 + 2 * 2);
+    assert!(replayed.iter().all(|row| row[consistent] == "true"));
+}
+
+#[test]
+fn heuristics_share_the_graph_of_their_config() {
+    let mut config =
+        config("{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    config.graph_dynamics = Some(
+        serde_yaml::from_str::<GraphDynamicsConfig>(
+            "{seed: 4, changes: 2, change_after_i: 2, edge_change_probability: 0.5, \
+             node_change_probability: 0.5, edge_change_intensity: 1.0, \
+             node_change_intensity: 1.0}",
+        )
+        .unwrap(),
+    );
+    let heuristics: [(&SyncHeuristic<R64, R64>, &str); 2] = [
+        (&heuristics::saturated_value, "h1"),
+        (&heuristics::value_per_cost, "h2"),
+    ];
+    let shared = TempDir::new("shared_graph");
+    let separate = TempDir::new("separate_graphs");
+    let summaries =
+        DynamicGraphExperiment::run_heuristics(&config, &heuristics, shared.path()).unwrap();
+    assert_eq!(summaries.len(), 2);
+
+    // the dynamics of every heuristic have to draw the same weights as in a run of its own
+    for (&(heuristic, name), summary) in heuristics.iter().zip(summaries) {
+        let summary = summary.unwrap();
+        let own = DynamicGraphExperiment::run_config(
+            &config,
+            heuristic,
+            name,
+            &separate.path().join(name),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(summary.score, own.score);
+        assert_eq!(summary.length, own.length);
+        assert_eq!(
+            fs::read_to_string(shared.path().join(format!("{}_replay", name))).unwrap(),
+            fs::read_to_string(separate.path().join(format!("{}_replay", name))).unwrap()
+        );
+    }
+}
+
+#[test]
+fn grids_weight_directions_separately() {
+    let algorithm =
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}";
+    let plain = config(algorithm);
+    let grid = plain.graph_creation.grid().unwrap();
+    let with_grid = |horizontal, vertical, asymmetry| {
+        let mut config = config(algorithm);
+        let mut grid = grid;
+        grid.size = (6, 4);
+        grid.horizontal_ew_range = horizontal;
+        grid.vertical_ew_range = vertical;
+        grid.asymmetry = asymmetry;
+        config.graph_creation = GraphCreationConfig::Grid(grid);
+        config
+    };
+    let fingerprint_of = |config: &ExperimentConfig, name: &str| {
+        let dir = TempDir::new(name);
+        run(config, dir.path());
+        fingerprint(dir.path())
+    };
+
+    let rectangular = fingerprint_of(&with_grid(None, None, None), "rectangular_grid");
+    assert_eq!(
+        rectangular,
+        fingerprint_of(
+            &with_grid(Some(grid.ew_range), Some(grid.ew_range), None),
+            "explicit_ranges_grid"
+        ),
+        "ranges equal to ew_range draw the same weights"
+    );
+    assert_ne!(
+        rectangular,
+        fingerprint_of(&with_grid(None, Some((20.0, 30.0)), None), "vertical_grid")
+    );
+    assert_ne!(
+        rectangular,
+        fingerprint_of(&with_grid(None, None, Some(2.0)), "asymmetric_grid")
+    );
+}
+
+#[test]
+fn sparse_erdos_renyi_graphs_are_repaired() {
+    let mut config =
+        config("{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    config.graph_creation = serde_yaml::from_str(
+        "{seed: 2, size: 12, nw_range: [10.0, 20.0], ew_range: [2.0, 5.0], \
+         node_weight_probability: 0.5, connection_probability: 0.02, min_out_degree: 1, \
+         repair_connectivity: true}",
+    )
+    .unwrap();
+    assert!(config.graph_creation.erdos_renyi().is_ok());
+    let dir = TempDir::new("repaired_erdos_renyi");
+    let summary = run(&config, dir.path());
+    assert!(summary.length <= MAX_TIME);
+    assert!(
+        summary.score > 0.0,
+        "the goal point has edges to other nodes"
+    );
+}
+
+#[test]
+fn planted_routes_bound_the_found_score() {
+    let mut config = config(
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 10}",
+    );
+    config.graph_creation = serde_yaml::from_str(
+        "{seed: 2, size: 12, route_size: 5, nw_range: [10.0, 20.0], ew_range: [1.0, 3.0], \
+         noise_probability: 0.3}",
+    )
+    .unwrap();
+    assert!(config.graph_creation.planted_route().is_ok());
+    let dir = TempDir::new("planted_route");
+    let summary = run(&config, dir.path());
+
+    let (header, records) = rows(&dir.path().join("h2_planted"));
+    let column = |name: &str| header.iter().position(|h| h == name).unwrap();
+    let record = &records[0];
+    let planted_score: f64 = record[column("planted_score")].parse().unwrap();
+    assert!(record[column("planted_length")].parse::<f64>().unwrap() <= MAX_TIME);
+    assert_eq!(record[column("planted_route")].split(' ').count(), 6);
+    assert!(
+        summary.collected <= planted_score + 1e-9,
+        "the planted route is optimal"
+    );
+    assert_eq!(record[column("recovered")], "true");
+}
+
+/// Returns config with service times of 5 to 10 time units at every rewarded node.
+fn with_service_times(mut config: ExperimentConfig) -> ExperimentConfig {
+    let mut experiment = config.experiment.cfg();
+    experiment.service_times = Some(serde_yaml::from_str("{range: [5.0, 10.0]}").unwrap());
+    config.experiment = GeneralExperimentConfig::Full(experiment);
+    config
+}
+
+#[test]
+fn service_times_use_up_the_budget() {
+    for algorithm in [
+        "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}",
+        "{threads: 1}",
+    ] {
+        let plain_dir = TempDir::new("without_service_times");
+        let serviced_dir = TempDir::new("with_service_times");
+        let without = run(&config(algorithm), plain_dir.path());
+        let with = run(&with_service_times(config(algorithm)), serviced_dir.path());
+        assert!(with.length <= MAX_TIME);
+        assert!(
+            with.score < without.score,
+            "every collected reward costs at least 5 more time units"
+        );
+    }
+
+    let dir = TempDir::new("exact_service_times");
+    let result = DynamicGraphExperiment::run_config(
+        &with_service_times(config("{node_limit: 25}")),
+        &heuristics::value_per_cost,
+        "h2",
+        &dir.path().join("h2"),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn failing_graph_validation_rejects_disconnected_graphs() {
+    let failing = |graph_creation: Option<&str>| {
+        let mut config = config("{node_limit: 25}");
+        let mut experiment = config.experiment.cfg();
+        experiment.graph_validation = Some(serde_yaml::from_str("fail").unwrap());
+        config.experiment = GeneralExperimentConfig::Full(experiment);
+        if let Some(graph_creation) = graph_creation {
+            config.graph_creation = serde_yaml::from_str(graph_creation).unwrap();
+        }
+        config
+    };
+
+    let dir = TempDir::new("failing_graph_validation");
+    let sparse = failing(Some(
+        "{seed: 2, size: 12, nw_range: [10.0, 20.0], ew_range: [2.0, 5.0], \
+         node_weight_probability: 0.5, connection_probability: 0.02}",
+    ));
+    let rejected = DynamicGraphExperiment::run_config(
+        &sparse,
+        &heuristics::value_per_cost,
+        "h2",
+        &dir.path().join("h2"),
+    );
+    assert!(
+        matches!(rejected, Err(ExperimentConfigError::InvalidGraphConfig(_))),
+        "most nodes have no edges"
+    );
+    assert!(run(&failing(None), dir.path()).length <= MAX_TIME);
+}
+
+#[test]
+fn post_processing_shortens_final_routes() {
+    let algorithm = "{seed: 3, iterations: 5}";
+    let plain = config(algorithm);
+    let mut processed = config(algorithm);
+    let mut experiment = processed.experiment.cfg();
+    experiment.post_process = true;
+    processed.experiment = GeneralExperimentConfig::Full(experiment);
+
+    let plain_dir = TempDir::new("without_post_processing");
+    let processed_dir = TempDir::new("with_post_processing");
+    let without = run(&plain, plain_dir.path());
+    let with = run(&processed, processed_dir.path());
+    assert!(with.length <= without.length);
+    assert!(with.score >= without.score);
+    assert_eq!(
+        with.collected, without.collected,
+        "rewarded nodes stay on the route"
+    );
+}
+
+#[test]
+fn multilevel_runs_refine_the_coarse_route() {
+    let algorithm = "{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, \
+                     iterations: 5}";
+    let mut multilevel = config(algorithm);
+    let mut experiment = multilevel.experiment.cfg();
+    experiment.multilevel = Some(MultilevelConfig { max_reward: 0.0 });
+    multilevel.experiment = GeneralExperimentConfig::Full(experiment);
+
+    let dir = TempDir::new("multilevel");
+    let summary = run(&multilevel, dir.path());
+    assert!(summary.score > 0.0);
+    assert!(summary.length <= MAX_TIME);
+    assert!(dir.path().join("h2").exists());
+}
+
+#[test]
+fn normalized_runs_record_their_scalings() {
+    let mut normalized = config("{node_limit: 25}");
+    let mut experiment = normalized.experiment.cfg();
+    experiment.normalization =
+        Some(serde_yaml::from_str("{edges: min_max, nodes: min_max}").unwrap());
+    normalized.experiment = GeneralExperimentConfig::Full(experiment);
+
+    let dir = TempDir::new("normalization");
+    let summary = run(&normalized, dir.path());
+    let meta = meta(dir.path());
+    let edges = meta.edge_scaling.unwrap();
+    assert_eq!(edges.offset, 0.0);
+    assert!(edges.factor >= 2.0 && edges.factor <= 5.0);
+    // the budget is given in the original units and scaled along with the edges
+    assert!(summary.length > 0.0 && summary.length * edges.factor <= MAX_TIME);
+    assert_eq!(meta.node_scaling.unwrap().offset, 0.0);
+}
+
+#[test]
+fn z_score_normalized_rewards_stay_non_negative() {
+    // an odd beta can't be applied to negative heuristic values
+    let mut normalized =
+        config("{alpha: 1.0, beta: 2.5, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    let mut experiment = normalized.experiment.cfg();
+    experiment.normalization = Some(serde_yaml::from_str("{nodes: z_score}").unwrap());
+    normalized.experiment = GeneralExperimentConfig::Full(experiment);
+
+    let dir = TempDir::new("z_score_nodes");
+    let summary = run(&normalized, dir.path());
+    assert_eq!(meta(dir.path()).node_scaling.unwrap().offset, 0.0);
+    assert!(summary.score > 0.0);
+}
+
+#[test]
+fn z_score_normalization_of_edges_is_rejected() {
+    let mut normalized = config("{node_limit: 25}");
+    let mut experiment = normalized.experiment.cfg();
+    experiment.normalization = Some(serde_yaml::from_str("{edges: z_score}").unwrap());
+    normalized.experiment = GeneralExperimentConfig::Full(experiment);
+
+    let dir = TempDir::new("z_score");
+    let result = DynamicGraphExperiment::run_config(
+        &normalized,
+        &heuristics::value_per_cost,
+        "h2",
+        &dir.path().join("h2"),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn only_generated_graphs_are_exported() {
+    let config = config("{node_limit: 25}");
+    let dir = TempDir::new("export");
+    let out = dir.path().join("instance");
+    let out = out.to_str().unwrap();
+    assert!(DynamicGraphExperiment::export_geopoint_config(&config, out).is_err());
+    assert!(DynamicGraphExperiment::export_usize_config(&config, out).is_ok());
+}
+
+#[test]
+fn unwritable_logs_are_reported_as_errors() {
+    let dir = TempDir::new("unwritable");
+    let result = DynamicGraphExperiment::run_config(
+        &config("{node_limit: 25}"),
+        &heuristics::value_per_cost,
+        "h2",
+        &dir.path().join("missing").join("h2"),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn os_seeded_runs_record_their_drawn_seed() {
+    let mut os_seeded =
+        config("{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    os_seeded.rng = Some(serde_yaml::from_str("{os_seeded: true}").unwrap());
+    let dir = TempDir::new("os_seeded");
+    run(&os_seeded, dir.path());
+    let seeds = meta(dir.path()).seeds;
+    assert!(seeds.os_seeded && seeds.drawn.is_some());
+}
+
+#[test]
+fn generated_graphs_get_pheromone_heatmaps() {
+    let mut heatmaps =
+        config("{alpha: 1.0, beta: 2.0, rho: 0.2, q_0: 0.5, seed: 3, ant_count: 5, iterations: 5}");
+    heatmaps.pheromone_heatmap = Some(serde_yaml::from_str("{every: 5}").unwrap());
+    let dir = TempDir::new("heatmaps");
+    run(&heatmaps, dir.path());
+    let svg = fs::read_to_string(dir.path().join("h2_heatmap_5.svg")).unwrap();
+    assert!(svg.contains("<path"));
+}